@@ -2,9 +2,12 @@
 
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
+use tracing::instrument;
 
 use crate::error::{Error, Result};
-use crate::types::{Chain, Transaction, Wallet};
+use crate::http::HttpConfig;
+use crate::traits::ChainClient;
+use crate::types::{Chain, Transaction};
 
 /// EVM client configuration
 #[derive(Clone, Debug)]
@@ -13,7 +16,10 @@ pub struct EvmClientConfig {
     pub chain: Chain,
     pub api_key: Option<String>,
     pub max_retries: u32,
+    /// Request timeout, superseded by `http.timeout`; only used while that
+    /// is left at its default
     pub timeout_secs: u64,
+    pub http: HttpConfig,
 }
 
 impl EvmClientConfig {
@@ -24,6 +30,7 @@ impl EvmClientConfig {
             api_key: None,
             max_retries: 3,
             timeout_secs: 30,
+            http: HttpConfig::default(),
         }
     }
     
@@ -31,18 +38,52 @@ impl EvmClientConfig {
         self.api_key = Some(api_key.into());
         self
     }
+    
+    pub fn with_http(mut self, http: HttpConfig) -> Self {
+        self.http = http;
+        self
+    }
+    
+    /// Transport settings for the client, with `timeout_secs` applied
+    /// unless `http.timeout` was changed from its default
+    pub fn http_config(&self) -> HttpConfig {
+        if self.http.timeout == HttpConfig::default().timeout {
+            self.http.clone().with_timeout(std::time::Duration::from_secs(self.timeout_secs))
+        } else {
+            self.http.clone()
+        }
+    }
+    
+    /// Set the RPC API key from the `evm_rpc_api_key` secret, when present
+    #[cfg(feature = "secrets")]
+    pub fn with_secrets(mut self, secrets: &crate::secrets::SecretsLoader) -> Result<Self> {
+        if let Some(api_key) = secrets.get("evm_rpc_api_key")? {
+            self.api_key = Some(api_key.expose_secret().to_string());
+        }
+        Ok(self)
+    }
 }
 
 /// EVM chain client
 #[derive(Clone, Debug)]
 pub struct EvmClient {
     config: EvmClientConfig,
+    http: reqwest::Client,
 }
 
 impl EvmClient {
     /// Create a new EVM client
+    ///
+    /// Panics if the HTTP settings are invalid; use [`EvmClient::try_new`] to
+    /// handle that case.
     pub fn new(config: EvmClientConfig) -> Self {
-        Self { config }
+        Self::try_new(config).expect("Failed to create HTTP client")
+    }
+    
+    /// Create a new EVM client, validating the HTTP settings
+    pub fn try_new(config: EvmClientConfig) -> Result<Self> {
+        let http = config.http_config().build_client()?;
+        Ok(Self { config, http })
     }
     
     /// Get client configuration
@@ -50,12 +91,18 @@ impl EvmClient {
         &self.config
     }
     
+    /// HTTP client used for RPC calls, built from the configured transport
+    pub fn http(&self) -> &reqwest::Client {
+        &self.http
+    }
+    
     /// Get chain
     pub fn chain(&self) -> Chain {
         self.config.chain
     }
     
     /// Get native balance for address
+    #[instrument(skip(self), fields(venue = "evm", chain = %self.config.chain, endpoint = "eth_getBalance"))]
     pub async fn get_balance(&self, address: &str) -> Result<f64> {
         // Placeholder implementation
         // In real implementation, use ethers-rs or similar
@@ -63,35 +110,40 @@ impl EvmClient {
     }
     
     /// Get token balance
+    #[instrument(skip(self), fields(venue = "evm", chain = %self.config.chain, endpoint = "eth_call"))]
     pub async fn get_token_balance(&self, address: &str, token_address: &str) -> Result<f64> {
         // Placeholder implementation
         Ok(0.0)
     }
     
     /// Get transaction by hash
+    #[instrument(skip(self), fields(venue = "evm", chain = %self.config.chain, endpoint = "eth_getTransactionByHash"))]
     pub async fn get_transaction(&self, tx_hash: &str) -> Result<Transaction> {
         // Placeholder implementation
         Err(Error::msg("Not implemented"))
     }
     
     /// Get latest block number
+    #[instrument(skip(self), fields(venue = "evm", chain = %self.config.chain, endpoint = "eth_blockNumber"))]
     pub async fn get_block_number(&self) -> Result<u64> {
         // Placeholder implementation
         Ok(0)
     }
     
     /// Send raw transaction
-    pub async fn send_transaction(&self, signed_tx: &str) -> Result<String> {
+    #[instrument(skip(self, _signed_tx), fields(venue = "evm", chain = %self.config.chain, endpoint = "eth_sendRawTransaction"))]
+    pub async fn send_transaction(&self, _signed_tx: &str) -> Result<String> {
         // Placeholder implementation
         Err(Error::msg("Not implemented"))
     }
     
     /// Estimate gas for transaction
+    #[instrument(skip(self, _data), fields(venue = "evm", chain = %self.config.chain, endpoint = "eth_estimateGas"))]
     pub async fn estimate_gas(
         &self,
         from: &str,
         to: &str,
-        data: Option<&str>,
+        _data: Option<&str>,
         value: Option<&str>,
     ) -> Result<u64> {
         // Placeholder implementation
@@ -99,6 +151,43 @@ impl EvmClient {
     }
 }
 
+#[async_trait]
+impl ChainClient for EvmClient {
+    fn chain(&self) -> Chain {
+        self.config.chain
+    }
+    
+    async fn get_balance(&self, address: &str) -> Result<f64> {
+        EvmClient::get_balance(self, address).await
+    }
+    
+    async fn get_token_balance(&self, address: &str, token_address: &str) -> Result<f64> {
+        EvmClient::get_token_balance(self, address, token_address).await
+    }
+    
+    async fn get_transaction(&self, tx_hash: &str) -> Result<Transaction> {
+        EvmClient::get_transaction(self, tx_hash).await
+    }
+    
+    async fn get_block_number(&self) -> Result<u64> {
+        EvmClient::get_block_number(self).await
+    }
+    
+    async fn send_transaction(&self, signed_tx: &str) -> Result<String> {
+        EvmClient::send_transaction(self, signed_tx).await
+    }
+    
+    async fn estimate_gas(
+        &self,
+        from: &str,
+        to: &str,
+        data: Option<&str>,
+        value: Option<&str>,
+    ) -> Result<u64> {
+        EvmClient::estimate_gas(self, from, to, data, value).await
+    }
+}
+
 /// ERC20 token interface
 pub struct Erc20Token {
     client: EvmClient,
@@ -114,30 +203,35 @@ impl Erc20Token {
     }
     
     /// Get token name
+    #[instrument(skip(self), fields(venue = "evm", chain = %self.client.chain(), token = %self.address, endpoint = "name"))]
     pub async fn name(&self) -> Result<String> {
         // Placeholder
         Ok("Unknown".to_string())
     }
     
     /// Get token symbol
+    #[instrument(skip(self), fields(venue = "evm", chain = %self.client.chain(), token = %self.address, endpoint = "symbol"))]
     pub async fn symbol(&self) -> Result<String> {
         // Placeholder
         Ok("UNK".to_string())
     }
     
     /// Get token decimals
+    #[instrument(skip(self), fields(venue = "evm", chain = %self.client.chain(), token = %self.address, endpoint = "decimals"))]
     pub async fn decimals(&self) -> Result<u8> {
         // Placeholder
         Ok(18)
     }
     
     /// Get total supply
+    #[instrument(skip(self), fields(venue = "evm", chain = %self.client.chain(), token = %self.address, endpoint = "totalSupply"))]
     pub async fn total_supply(&self) -> Result<f64> {
         // Placeholder
         Ok(0.0)
     }
     
     /// Get balance of address
+    #[instrument(skip(self), fields(venue = "evm", chain = %self.client.chain(), token = %self.address, endpoint = "balanceOf"))]
     pub async fn balance_of(&self, address: &str) -> Result<f64> {
         self.client.get_token_balance(address, &self.address).await
     }
@@ -172,3 +266,20 @@ pub struct RpcError {
     pub code: i64,
     pub message: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_timeout_precedence() {
+        let mut config = EvmClientConfig::new("http://localhost:8545", Chain::Ethereum);
+        config.timeout_secs = 10;
+        assert_eq!(config.http_config().timeout, Duration::from_secs(10));
+
+        let config = config.with_http(HttpConfig::default().with_timeout(Duration::from_secs(5)));
+        assert_eq!(config.http_config().timeout, Duration::from_secs(5));
+        assert!(EvmClient::try_new(config).is_ok());
+    }
+}
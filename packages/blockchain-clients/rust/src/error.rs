@@ -16,6 +16,9 @@ pub enum Error {
     #[error("Rate limit exceeded")]
     RateLimit,
     
+    #[error("Timed out: {0}")]
+    Timeout(String),
+    
     #[error("Invalid address: {0}")]
     InvalidAddress(String),
     
@@ -28,6 +31,9 @@ pub enum Error {
     #[error("Market not found: {0}")]
     MarketNotFound(String),
     
+    #[error("Price unavailable: {0}")]
+    PriceUnavailable(String),
+    
     #[error("Order rejected: {0}")]
     OrderRejected(String),
     
@@ -40,6 +46,18 @@ pub enum Error {
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
     
+    #[cfg(feature = "export")]
+    #[error("CSV error: {0}")]
+    Csv(#[from] csv::Error),
+    
+    #[cfg(feature = "keystore")]
+    #[error("Keystore error: {0}")]
+    Keystore(#[from] eth_keystore::KeystoreError),
+    
+    #[cfg(feature = "sqlite")]
+    #[error("Storage error: {0}")]
+    Storage(#[from] rusqlite::Error),
+    
     #[error("Other error: {0}")]
     Other(String),
 }
@@ -55,7 +73,8 @@ impl Error {
         matches!(self,
             Error::Network(_) |
             Error::Rpc(_) |
-            Error::RateLimit
+            Error::RateLimit |
+            Error::Timeout(_)
         )
     }
 }
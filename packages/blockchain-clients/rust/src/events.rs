@@ -0,0 +1,247 @@
+//! Normalized market event streams
+//!
+//! Every streaming source yields [`MarketEvent`]s through the [`EventStream`]
+//! trait, so consumers can multiplex feeds from many venues into one loop with
+//! [`EventMux`].
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::error::Result;
+use crate::traits::Exchange;
+use crate::types::{Chain, Fill, Order, OrderBook, OrderSide};
+
+/// Normalized event from any venue or chain
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MarketEvent {
+    /// Full order book snapshot for a token
+    BookUpdate(OrderBook),
+    /// Public trade print
+    Trade {
+        token_id: String,
+        side: OrderSide,
+        price: f64,
+        size: f64,
+        timestamp: DateTime<Utc>,
+    },
+    /// Change to one of our orders
+    OrderUpdate(Order),
+    /// Execution against one of our orders
+    Fill(Fill),
+    /// New block on a chain
+    Block {
+        chain: Chain,
+        number: u64,
+        timestamp: DateTime<Utc>,
+    },
+    /// Reference or index price
+    Price {
+        symbol: String,
+        price: f64,
+        timestamp: DateTime<Utc>,
+    },
+}
+
+impl MarketEvent {
+    /// Token or symbol the event refers to, if any
+    pub fn instrument(&self) -> Option<&str> {
+        match self {
+            MarketEvent::BookUpdate(book) => Some(&book.asset_id),
+            MarketEvent::Trade { token_id, .. } => Some(token_id),
+            MarketEvent::OrderUpdate(order) => Some(&order.token_id),
+            MarketEvent::Fill(_) | MarketEvent::Block { .. } => None,
+            MarketEvent::Price { symbol, .. } => Some(symbol),
+        }
+    }
+}
+
+/// Source of market events
+#[async_trait]
+pub trait EventStream: Send {
+    /// Name of the venue or feed, used to tag multiplexed events
+    fn source(&self) -> &str;
+
+    /// Wait for the next event; `None` once the stream has ended
+    async fn next_event(&mut self) -> Option<Result<MarketEvent>>;
+}
+
+/// Event stream fed from a channel, for bridging push-based feeds
+pub struct ChannelStream {
+    source: String,
+    rx: mpsc::Receiver<Result<MarketEvent>>,
+}
+
+impl ChannelStream {
+    /// Create a stream and the sender that feeds it
+    pub fn new(source: impl Into<String>, buffer: usize) -> (mpsc::Sender<Result<MarketEvent>>, Self) {
+        let (tx, rx) = mpsc::channel(buffer);
+        (tx, Self { source: source.into(), rx })
+    }
+}
+
+#[async_trait]
+impl EventStream for ChannelStream {
+    fn source(&self) -> &str {
+        &self.source
+    }
+
+    async fn next_event(&mut self) -> Option<Result<MarketEvent>> {
+        self.rx.recv().await
+    }
+}
+
+/// Event stream that polls order books from any [`Exchange`]
+///
+/// Emits a `BookUpdate` per token on every tick. Venues without a push feed
+/// can be consumed this way alongside streaming sources.
+pub struct PollingBookStream {
+    exchange: Arc<dyn Exchange>,
+    token_ids: Vec<String>,
+    interval: tokio::time::Interval,
+    pending: Vec<String>,
+}
+
+impl PollingBookStream {
+    pub fn new(exchange: Arc<dyn Exchange>, token_ids: Vec<String>, every: Duration) -> Self {
+        let mut interval = tokio::time::interval(every);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        Self {
+            exchange,
+            token_ids,
+            interval,
+            pending: Vec::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl EventStream for PollingBookStream {
+    fn source(&self) -> &str {
+        self.exchange.venue()
+    }
+
+    async fn next_event(&mut self) -> Option<Result<MarketEvent>> {
+        if self.token_ids.is_empty() {
+            return None;
+        }
+        if self.pending.is_empty() {
+            self.interval.tick().await;
+            self.pending = self.token_ids.iter().rev().cloned().collect();
+        }
+        let token_id = self.pending.pop()?;
+        Some(self.exchange.get_order_book(&token_id).await.map(MarketEvent::BookUpdate))
+    }
+}
+
+/// Event tagged with the stream it came from
+#[derive(Debug)]
+pub struct SourcedEvent {
+    pub source: String,
+    pub event: Result<MarketEvent>,
+}
+
+/// Multiplexes many event streams into one receiver
+///
+/// Each stream is driven on its own task; dropping the mux aborts them.
+pub struct EventMux {
+    tx: mpsc::Sender<SourcedEvent>,
+    rx: mpsc::Receiver<SourcedEvent>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl EventMux {
+    pub fn new(buffer: usize) -> Self {
+        let (tx, rx) = mpsc::channel(buffer);
+        Self {
+            tx,
+            rx,
+            tasks: Vec::new(),
+        }
+    }
+
+    /// Start driving a stream
+    pub fn add<S: EventStream + 'static>(&mut self, mut stream: S) {
+        let tx = self.tx.clone();
+        self.tasks.push(tokio::spawn(async move {
+            while let Some(event) = stream.next_event().await {
+                let sourced = SourcedEvent {
+                    source: stream.source().to_string(),
+                    event,
+                };
+                if tx.send(sourced).await.is_err() {
+                    break;
+                }
+            }
+        }));
+    }
+
+    /// Wait for the next event from any stream
+    ///
+    /// Returns `None` once every stream has ended.
+    pub async fn next(&mut self) -> Option<SourcedEvent> {
+        if self.tasks.iter().all(|t| t.is_finished()) && self.rx.is_empty() {
+            return None;
+        }
+        tokio::select! {
+            event = self.rx.recv() => event,
+            _ = wait_all(&mut self.tasks) => self.rx.try_recv().ok(),
+        }
+    }
+}
+
+async fn wait_all(tasks: &mut [JoinHandle<()>]) {
+    for task in tasks.iter_mut() {
+        if !task.is_finished() {
+            let _ = task.await;
+        }
+    }
+}
+
+impl Drop for EventMux {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn price(symbol: &str, price: f64) -> MarketEvent {
+        MarketEvent::Price {
+            symbol: symbol.to_string(),
+            price,
+            timestamp: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mux_merges_sources() {
+        let (tx_a, a) = ChannelStream::new("a", 8);
+        let (tx_b, b) = ChannelStream::new("b", 8);
+
+        let mut mux = EventMux::new(8);
+        mux.add(a);
+        mux.add(b);
+
+        tx_a.send(Ok(price("ETH", 3000.0))).await.unwrap();
+        tx_b.send(Ok(price("BTC", 60000.0))).await.unwrap();
+        drop(tx_a);
+        drop(tx_b);
+
+        let mut sources = Vec::new();
+        while let Some(sourced) = mux.next().await {
+            sources.push(sourced.source);
+        }
+        sources.sort();
+        assert_eq!(sources, vec!["a", "b"]);
+    }
+}
@@ -2,12 +2,19 @@
 
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
-use reqwest::{Client, header};
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use reqwest::Client;
+use serde::Serialize;
+use std::future::Future;
+use tokio::time::Instant;
+use tracing::instrument;
 
 use crate::error::{Error, Result};
-use crate::types::{Order, OrderSide, OrderType, OrderStatus, Wallet, Chain, Token};
+use crate::fees::FeeSchedule;
+use crate::http::{self, HttpConfig};
+use crate::traits::{Exchange, PredictionMarket};
+use crate::types::{Order, OrderSide, OrderType, Wallet, Chain, TimeInForce};
+
+pub use crate::types::{Fill, Market, MarketToken, OrderBook, OrderBookEntry, OrderRequest, TradeResult};
 
 /// Polymarket API configuration
 #[derive(Clone, Debug)]
@@ -17,6 +24,8 @@ pub struct PolymarketConfig {
     pub api_secret: Option<String>,
     pub rpc_url: String,
     pub chain: Chain,
+    pub fees: FeeSchedule,
+    pub http: HttpConfig,
 }
 
 impl Default for PolymarketConfig {
@@ -27,8 +36,40 @@ impl Default for PolymarketConfig {
             api_secret: None,
             rpc_url: "https://polygon-rpc.com".to_string(),
             chain: Chain::Polygon,
+            fees: FeeSchedule::zero(),
+            http: HttpConfig::default(),
+        }
+    }
+}
+
+impl PolymarketConfig {
+    /// Staging CLOB settled on the Amoy testnet
+    pub fn testnet() -> Self {
+        Self {
+            api_url: "https://clob-staging.polymarket.com".to_string(),
+            rpc_url: "https://rpc-amoy.polygon.technology".to_string(),
+            chain: Chain::Amoy,
+            ..Self::default()
         }
     }
+    
+    /// Check if the config points at the testnet
+    pub fn is_testnet(&self) -> bool {
+        self.chain.is_testnet()
+    }
+}
+
+#[cfg(feature = "secrets")]
+impl PolymarketConfig {
+    /// Default config with credentials resolved from the `polymarket_api_key`
+    /// and `polymarket_api_secret` secrets, when present
+    pub fn from_secrets(secrets: &crate::secrets::SecretsLoader) -> Result<Self> {
+        Ok(Self {
+            api_key: secrets.get("polymarket_api_key")?.map(|s| s.expose_secret().to_string()),
+            api_secret: secrets.get("polymarket_api_secret")?.map(|s| s.expose_secret().to_string()),
+            ..Self::default()
+        })
+    }
 }
 
 /// Polymarket CLOB client
@@ -37,84 +78,68 @@ pub struct PolymarketClient {
     config: PolymarketConfig,
     http: Client,
     credentials: Option<Credentials>,
+    /// Deadline for every call, overriding `http.call_deadline`
+    deadline: Option<Instant>,
 }
 
 #[derive(Clone, Debug)]
+// Read once authenticated requests are signed
+#[allow(dead_code)]
 struct Credentials {
     api_key: String,
     secret: String,
     expires_at: DateTime<Utc>,
 }
 
-/// Market data
-#[derive(Clone, Debug, Deserialize)]
-pub struct Market {
-    pub id: String,
-    pub condition_id: String,
-    pub question: String,
-    pub slug: String,
-    #[serde(rename = "marketMakerAddress")]
-    pub market_maker_address: String,
-    pub tokens: Vec<MarketToken>,
-    #[serde(rename = "active")]
-    pub is_active: bool,
-    #[serde(rename = "closed")]
-    pub is_closed: bool,
-    #[serde(rename = "closedTime")]
-    pub closed_time: Option<DateTime<Utc>>,
-}
-
-/// Market token (outcome)
-#[derive(Clone, Debug, Deserialize)]
-pub struct MarketToken {
-    pub token_id: String,
-    pub outcome: String,
-    pub price: f64,
-}
-
-/// Order book
-#[derive(Clone, Debug, Deserialize)]
-pub struct OrderBook {
-    pub market: String,
-    pub asset_id: String,
-    pub bids: Vec<OrderBookEntry>,
-    pub asks: Vec<OrderBookEntry>,
-    pub timestamp: DateTime<Utc>,
-}
-
-/// Order book entry
-#[derive(Clone, Debug, Deserialize)]
-pub struct OrderBookEntry {
-    pub price: f64,
-    pub size: f64,
-}
-
-/// Trade execution result
-#[derive(Clone, Debug, Deserialize)]
-pub struct TradeResult {
-    pub order_id: String,
-    pub status: String,
-    pub filled_size: f64,
-    pub avg_price: f64,
-    pub transaction_hash: Option<String>,
-}
-
 impl PolymarketClient {
     /// Create a new Polymarket client
+    ///
+    /// Panics if the HTTP settings are invalid; use [`PolymarketClient::try_new`]
+    /// to handle that case.
     pub fn new(config: PolymarketConfig) -> Self {
-        let http = Client::builder()
-            .timeout(std::time::Duration::from_secs(30))
-            .build()
-            .expect("Failed to create HTTP client");
+        Self::try_new(config).expect("Failed to create HTTP client")
+    }
+    
+    /// Create a new Polymarket client, validating the HTTP settings
+    pub fn try_new(config: PolymarketConfig) -> Result<Self> {
+        let http = config.http.build_client()?;
         
-        Self {
+        Ok(Self {
             config,
             http,
             credentials: None,
+            deadline: None,
+        })
+    }
+    
+    /// Client whose calls fail with [`Error::Timeout`] once `deadline`
+    /// passes, body reads included
+    ///
+    /// ```rust,ignore
+    /// let deadline = Instant::now() + Duration::from_secs(3);
+    /// let book = client.with_deadline(deadline).get_order_book(token_id).await?;
+    /// ```
+    pub fn with_deadline(&self, deadline: Instant) -> Self {
+        Self {
+            deadline: Some(deadline),
+            ..self.clone()
+        }
+    }
+    
+    /// Run `call` under the client's deadline, or the configured per-call
+    /// deadline
+    async fn within_deadline<T>(&self, call: impl Future<Output = Result<T>>) -> Result<T> {
+        let deadline = self
+            .deadline
+            .or_else(|| self.config.http.call_deadline.map(|limit| Instant::now() + limit));
+        match deadline {
+            Some(deadline) => http::with_deadline(deadline, call).await,
+            None => call.await,
         }
     }
     
     /// Authenticate with API credentials
+    #[instrument(skip_all, fields(venue = "polymarket", endpoint = "auth"))]
     pub async fn authenticate(&mut self, api_key: &str, api_secret: &str) -> Result<()> {
         // In real implementation, would exchange for a JWT or session token
         self.credentials = Some(Credentials {
@@ -133,183 +158,316 @@ impl PolymarketClient {
     }
     
     /// Get active markets
+    #[instrument(skip(self), fields(venue = "polymarket", endpoint = "/markets"))]
     pub async fn get_active_markets(&self) -> Result<Vec<Market>> {
-        let url = format!("{}/markets", self.config.api_url);
-        
-        let response = self.http
-            .get(&url)
-            .send()
-            .await
-            .map_err(Error::Network)?;
-        
-        if !response.status().is_success() {
-            return Err(Error::Rpc(format!(
-                "Failed to fetch markets: {}",
-                response.status()
-            )));
-        }
-        
-        let markets: Vec<Market> = response.json().await.map_err(Error::Network)?;
-        Ok(markets.into_iter().filter(|m| m.is_active && !m.is_closed).collect())
+        self.within_deadline(async {
+            let url = format!("{}/markets", self.config.api_url);
+            
+            let response = self.http
+                .get(&url)
+                .timeout(self.config.http.timeouts.market_read)
+                .send()
+                .await
+                .map_err(http::classify)?;
+            
+            if !response.status().is_success() {
+                return Err(Error::Rpc(format!(
+                    "Failed to fetch markets: {}",
+                    response.status()
+                )));
+            }
+            
+            let markets: Vec<Market> = response.json().await.map_err(http::classify)?;
+            Ok(markets.into_iter().filter(|m| m.is_active && !m.is_closed).collect())
+        })
+        .await
     }
     
     /// Get market by ID
+    #[instrument(skip(self), fields(venue = "polymarket", endpoint = "/markets/{id}"))]
     pub async fn get_market(&self, market_id: &str) -> Result<Market> {
-        let url = format!("{}/markets/{}", self.config.api_url, market_id);
-        
-        let response = self.http
-            .get(&url)
-            .send()
-            .await
-            .map_err(Error::Network)?;
-        
-        if response.status().as_u16() == 404 {
-            return Err(Error::MarketNotFound(market_id.to_string()));
-        }
-        
-        response.json().await.map_err(Error::Network)
+        self.within_deadline(async {
+            let url = format!("{}/markets/{}", self.config.api_url, market_id);
+            
+            let response = self.http
+                .get(&url)
+                .timeout(self.config.http.timeouts.market_read)
+                .send()
+                .await
+                .map_err(http::classify)?;
+            
+            if response.status().as_u16() == 404 {
+                return Err(Error::MarketNotFound(market_id.to_string()));
+            }
+            
+            response.json().await.map_err(http::classify)
+        })
+        .await
     }
     
     /// Get order book for market
+    #[instrument(skip(self), fields(venue = "polymarket", endpoint = "/book/{id}"))]
     pub async fn get_order_book(&self, token_id: &str) -> Result<OrderBook> {
-        let url = format!("{}/book/{}?side=buy&side=sell", self.config.api_url, token_id);
-        
-        let response = self.http
-            .get(&url)
-            .send()
-            .await
-            .map_err(Error::Network)?;
-        
-        response.json().await.map_err(Error::Network)
+        self.within_deadline(async {
+            let url = format!("{}/book/{}?side=buy&side=sell", self.config.api_url, token_id);
+            
+            let response = self.http
+                .get(&url)
+                .timeout(self.config.http.timeouts.book_read)
+                .send()
+                .await
+                .map_err(http::classify)?;
+            
+            response.json().await.map_err(http::classify)
+        })
+        .await
     }
     
     /// Place an order
+    #[instrument(
+        skip(self, order),
+        fields(
+            venue = "polymarket",
+            endpoint = "/order",
+            token_id = %order.token_id,
+            side = %order.side,
+            order_id = tracing::field::Empty,
+        )
+    )]
     pub async fn place_order(&self, order: &OrderRequest) -> Result<TradeResult> {
-        if !self.is_authenticated() {
-            return Err(Error::Authentication("Not authenticated".to_string()));
-        }
-        
-        let url = format!("{}/order", self.config.api_url);
-        
-        let response = self.http
-            .post(&url)
-            .json(order)
-            .send()
-            .await
-            .map_err(Error::Network)?;
-        
-        if !response.status().is_success() {
-            let error_text = response.text().await.unwrap_or_default();
-            return Err(Error::OrderRejected(error_text));
-        }
-        
-        response.json().await.map_err(Error::Network)
+        self.within_deadline(async {
+            if !self.is_authenticated() {
+                return Err(Error::Authentication("Not authenticated".to_string()));
+            }
+            
+            let body = PolymarketOrder::from_request(order)?;
+            let url = format!("{}/order", self.config.api_url);
+            
+            let response = self.http
+                .post(&url)
+                .timeout(self.config.http.timeouts.order_place)
+                .json(&body)
+                .send()
+                .await
+                .map_err(http::classify)?;
+            
+            if !response.status().is_success() {
+                let error_text = response.text().await.unwrap_or_default();
+                return Err(Error::OrderRejected(error_text));
+            }
+            
+            let result: TradeResult = response.json().await.map_err(http::classify)?;
+            tracing::Span::current().record("order_id", result.order_id.as_str());
+            Ok(result)
+        })
+        .await
     }
     
     /// Cancel an order
+    #[instrument(skip(self), fields(venue = "polymarket", endpoint = "/order/{id}"))]
     pub async fn cancel_order(&self, order_id: &str) -> Result<bool> {
-        if !self.is_authenticated() {
-            return Err(Error::Authentication("Not authenticated".to_string()));
-        }
-        
-        let url = format!("{}/order/{}", self.config.api_url, order_id);
-        
-        let response = self.http
-            .delete(&url)
-            .send()
-            .await
-            .map_err(Error::Network)?;
-        
-        Ok(response.status().is_success())
+        self.within_deadline(async {
+            if !self.is_authenticated() {
+                return Err(Error::Authentication("Not authenticated".to_string()));
+            }
+            
+            let url = format!("{}/order/{}", self.config.api_url, order_id);
+            
+            let response = self.http
+                .delete(&url)
+                .timeout(self.config.http.timeouts.order_cancel)
+                .send()
+                .await
+                .map_err(http::classify)?;
+            
+            Ok(response.status().is_success())
+        })
+        .await
     }
     
     /// Get open orders
+    #[instrument(skip(self, wallet), fields(venue = "polymarket", endpoint = "/orders", address = %wallet.address))]
     pub async fn get_open_orders(&self, wallet: &Wallet) -> Result<Vec<Order>> {
-        let url = format!(
-            "{}/orders?address={}&status=OPEN",
-            self.config.api_url, wallet.address
-        );
-        
-        let response = self.http
-            .get(&url)
-            .send()
-            .await
-            .map_err(Error::Network)?;
-        
-        response.json().await.map_err(Error::Network)
+        self.within_deadline(async {
+            let url = format!(
+                "{}/orders?address={}&status=OPEN",
+                self.config.api_url, wallet.address
+            );
+            
+            let response = self.http
+                .get(&url)
+                .timeout(self.config.http.timeouts.account_read)
+                .send()
+                .await
+                .map_err(http::classify)?;
+            
+            response.json().await.map_err(http::classify)
+        })
+        .await
     }
     
     /// Get fills/trades for wallet
+    #[instrument(skip(self, wallet), fields(venue = "polymarket", endpoint = "/fills", address = %wallet.address))]
     pub async fn get_fills(&self, wallet: &Wallet, limit: usize) -> Result<Vec<Fill>> {
-        let url = format!(
-            "{}/fills?address={}&limit={}",
-            self.config.api_url, wallet.address, limit
-        );
-        
-        let response = self.http
-            .get(&url)
-            .send()
-            .await
-            .map_err(Error::Network)?;
-        
-        response.json().await.map_err(Error::Network)
+        self.within_deadline(async {
+            let url = format!(
+                "{}/fills?address={}&limit={}",
+                self.config.api_url, wallet.address, limit
+            );
+            
+            let response = self.http
+                .get(&url)
+                .timeout(self.config.http.timeouts.account_read)
+                .send()
+                .await
+                .map_err(http::classify)?;
+            
+            response.json().await.map_err(http::classify)
+        })
+        .await
     }
 }
 
-/// Order request
-#[derive(Clone, Debug, Serialize)]
-pub struct OrderRequest {
+/// Order body in the shape the CLOB expects
+///
+/// The CLOB has no stop or reduce-only orders; those are rejected here rather
+/// than silently sent as plain limits. Market orders become FOK (or FAK when
+/// IOC is requested) with `price` as the worst acceptable price.
+#[derive(Debug, Serialize)]
+struct PolymarketOrder<'a> {
     #[serde(rename = "tokenID")]
-    pub token_id: String,
-    pub side: OrderSide,
-    #[serde(rename = "type")]
-    pub order_type: OrderType,
-    pub size: f64,
-    pub price: f64,
+    token_id: &'a str,
+    side: OrderSide,
+    size: f64,
+    price: f64,
+    #[serde(rename = "orderType")]
+    order_type: &'static str,
+    #[serde(rename = "postOnly", skip_serializing_if = "std::ops::Not::not")]
+    post_only: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub nonce: Option<u64>,
+    nonce: Option<u64>,
 }
 
-impl OrderRequest {
-    pub fn buy(token_id: impl Into<String>, size: f64, price: f64) -> Self {
-        Self {
-            token_id: token_id.into(),
-            side: OrderSide::Buy,
-            order_type: OrderType::Limit,
-            size,
-            price,
-            nonce: None,
-        }
+/// Reject orders the CLOB cannot take: inconsistent flags, stops and
+/// reduce-only orders
+pub(crate) fn check_order(order: &OrderRequest) -> Result<()> {
+    order.validate().map_err(Error::OrderRejected)?;
+    
+    if order.order_type.is_stop() {
+        return Err(Error::OrderRejected(
+            "Polymarket does not support stop orders".to_string(),
+        ));
+    }
+    if order.reduce_only {
+        return Err(Error::OrderRejected(
+            "Polymarket does not support reduce-only orders".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+impl<'a> PolymarketOrder<'a> {
+    fn from_request(order: &'a OrderRequest) -> Result<Self> {
+        check_order(order)?;
+        
+        let order_type = match (order.order_type, order.time_in_force) {
+            (OrderType::Market, TimeInForce::Ioc) => "FAK",
+            (OrderType::Market, _) => "FOK",
+            (_, TimeInForce::Gtc) => "GTC",
+            (_, TimeInForce::Ioc) => "FAK",
+            (_, TimeInForce::Fok) => "FOK",
+        };
+        
+        Ok(Self {
+            token_id: &order.token_id,
+            side: order.side,
+            size: order.size,
+            price: order.price,
+            order_type,
+            post_only: order.post_only,
+            nonce: order.nonce,
+        })
+    }
+}
+
+#[async_trait]
+impl Exchange for PolymarketClient {
+    fn venue(&self) -> &'static str {
+        "polymarket"
     }
     
-    pub fn sell(token_id: impl Into<String>, size: f64, price: f64) -> Self {
-        Self {
-            token_id: token_id.into(),
-            side: OrderSide::Sell,
-            order_type: OrderType::Limit,
-            size,
-            price,
-            nonce: None,
-        }
+    fn fee_schedule(&self) -> FeeSchedule {
+        self.config.fees.clone()
     }
     
-    pub fn with_nonce(mut self, nonce: u64) -> Self {
-        self.nonce = Some(nonce);
-        self
+    async fn get_order_book(&self, token_id: &str) -> Result<OrderBook> {
+        PolymarketClient::get_order_book(self, token_id).await
+    }
+    
+    async fn place_order(&self, order: &OrderRequest) -> Result<TradeResult> {
+        PolymarketClient::place_order(self, order).await
+    }
+    
+    async fn cancel_order(&self, order_id: &str) -> Result<bool> {
+        PolymarketClient::cancel_order(self, order_id).await
+    }
+    
+    async fn get_open_orders(&self, wallet: &Wallet) -> Result<Vec<Order>> {
+        PolymarketClient::get_open_orders(self, wallet).await
+    }
+    
+    async fn get_fills(&self, wallet: &Wallet, limit: usize) -> Result<Vec<Fill>> {
+        PolymarketClient::get_fills(self, wallet, limit).await
+    }
+}
+
+#[async_trait]
+impl PredictionMarket for PolymarketClient {
+    async fn get_active_markets(&self) -> Result<Vec<Market>> {
+        PolymarketClient::get_active_markets(self).await
+    }
+    
+    async fn get_market(&self, market_id: &str) -> Result<Market> {
+        PolymarketClient::get_market(self, market_id).await
     }
 }
 
-/// Fill/Trade record
-#[derive(Clone, Debug, Deserialize)]
-pub struct Fill {
-    pub id: String,
-    #[serde(rename = "orderId")]
-    pub order_id: String,
-    pub side: OrderSide,
-    pub size: f64,
-    pub price: f64,
-    pub fee: f64,
-    pub timestamp: DateTime<Utc>,
-    #[serde(rename = "transactionHash")]
-    pub transaction_hash: String,
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    async fn slow_server() -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/markets/slow"))
+            .respond_with(ResponseTemplate::new(404).set_delay(Duration::from_millis(500)))
+            .mount(&server)
+            .await;
+        server
+    }
+
+    fn client(server: &MockServer, http: HttpConfig) -> PolymarketClient {
+        PolymarketClient::new(PolymarketConfig {
+            api_url: server.uri(),
+            http,
+            ..PolymarketConfig::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn test_call_deadline() {
+        let server = slow_server().await;
+        let unlimited = client(&server, HttpConfig::default());
+
+        let deadline = Instant::now() + Duration::from_millis(50);
+        let result = unlimited.with_deadline(deadline).get_market("slow").await;
+        assert!(matches!(result, Err(Error::Timeout(_))));
+        assert!(matches!(unlimited.get_market("slow").await, Err(Error::MarketNotFound(_))));
+
+        let limited = client(&server, HttpConfig::default().with_call_deadline(Duration::from_millis(50)));
+        assert!(matches!(limited.get_market("slow").await, Err(Error::Timeout(_))));
+    }
 }
@@ -0,0 +1,301 @@
+//! CSV import/export
+//!
+//! Writes orders, fills and positions as flat CSV rows (and reads them back)
+//! so trade history can go straight into accounting tools and spreadsheets.
+//! Nested fields such as the wallet and token metadata are flattened into
+//! prefixed columns.
+
+use chrono::{DateTime, Utc};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::io::{Read, Write};
+
+use crate::error::Result;
+use crate::types::{
+    Chain, Fill, Order, OrderSide, OrderStatus, OrderType, Position, TimeInForce, Token, Wallet,
+};
+
+/// Flat CSV row for an [`Order`]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OrderRecord {
+    pub id: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub token_id: String,
+    pub side: OrderSide,
+    pub order_type: OrderType,
+    pub time_in_force: TimeInForce,
+    pub post_only: bool,
+    pub reduce_only: bool,
+    pub size: f64,
+    pub price: f64,
+    pub stop_price: Option<f64>,
+    pub status: OrderStatus,
+    pub wallet_address: String,
+    pub wallet_chain: Chain,
+    pub wallet_label: Option<String>,
+}
+
+impl From<&Order> for OrderRecord {
+    fn from(order: &Order) -> Self {
+        Self {
+            id: order.id.clone(),
+            created_at: order.created_at,
+            token_id: order.token_id.clone(),
+            side: order.side,
+            order_type: order.order_type,
+            time_in_force: order.time_in_force,
+            post_only: order.post_only,
+            reduce_only: order.reduce_only,
+            size: order.size,
+            price: order.price,
+            stop_price: order.stop_price,
+            status: order.status,
+            wallet_address: order.wallet.address.clone(),
+            wallet_chain: order.wallet.chain,
+            wallet_label: order.wallet.label.clone(),
+        }
+    }
+}
+
+impl From<OrderRecord> for Order {
+    fn from(record: OrderRecord) -> Self {
+        Self {
+            id: record.id,
+            side: record.side,
+            order_type: record.order_type,
+            token_id: record.token_id,
+            size: record.size,
+            price: record.price,
+            time_in_force: record.time_in_force,
+            post_only: record.post_only,
+            reduce_only: record.reduce_only,
+            stop_price: record.stop_price,
+            wallet: Wallet {
+                address: record.wallet_address,
+                chain: record.wallet_chain,
+                label: record.wallet_label,
+            },
+            created_at: record.created_at,
+            status: record.status,
+        }
+    }
+}
+
+/// Flat CSV row for a [`Fill`]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FillRecord {
+    pub id: String,
+    pub order_id: String,
+    pub timestamp: DateTime<Utc>,
+    pub side: OrderSide,
+    pub size: f64,
+    pub price: f64,
+    pub fee: f64,
+    pub transaction_hash: String,
+}
+
+impl From<&Fill> for FillRecord {
+    fn from(fill: &Fill) -> Self {
+        Self {
+            id: fill.id.clone(),
+            order_id: fill.order_id.clone(),
+            timestamp: fill.timestamp,
+            side: fill.side,
+            size: fill.size,
+            price: fill.price,
+            fee: fill.fee,
+            transaction_hash: fill.transaction_hash.clone(),
+        }
+    }
+}
+
+impl From<FillRecord> for Fill {
+    fn from(record: FillRecord) -> Self {
+        Self {
+            id: record.id,
+            order_id: record.order_id,
+            side: record.side,
+            size: record.size,
+            price: record.price,
+            fee: record.fee,
+            timestamp: record.timestamp,
+            transaction_hash: record.transaction_hash,
+        }
+    }
+}
+
+/// Flat CSV row for a [`Position`]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PositionRecord {
+    pub token_id: String,
+    pub opened_at: DateTime<Utc>,
+    pub size: f64,
+    pub entry_price: f64,
+    pub current_price: f64,
+    pub unrealized_pnl: f64,
+    pub token_address: String,
+    pub token_symbol: String,
+    pub token_name: String,
+    pub token_decimals: u8,
+    pub token_chain: Chain,
+    pub token_logo_url: Option<String>,
+    pub wallet_address: String,
+    pub wallet_chain: Chain,
+    pub wallet_label: Option<String>,
+}
+
+impl From<&Position> for PositionRecord {
+    fn from(position: &Position) -> Self {
+        Self {
+            token_id: position.token_id.clone(),
+            opened_at: position.opened_at,
+            size: position.size,
+            entry_price: position.entry_price,
+            current_price: position.current_price,
+            unrealized_pnl: position.unrealized_pnl(),
+            token_address: position.token.address.clone(),
+            token_symbol: position.token.symbol.clone(),
+            token_name: position.token.name.clone(),
+            token_decimals: position.token.decimals,
+            token_chain: position.token.chain,
+            token_logo_url: position.token.logo_url.clone(),
+            wallet_address: position.wallet.address.clone(),
+            wallet_chain: position.wallet.chain,
+            wallet_label: position.wallet.label.clone(),
+        }
+    }
+}
+
+impl From<PositionRecord> for Position {
+    fn from(record: PositionRecord) -> Self {
+        Self {
+            token_id: record.token_id,
+            token: Token {
+                address: record.token_address,
+                symbol: record.token_symbol,
+                name: record.token_name,
+                decimals: record.token_decimals,
+                chain: record.token_chain,
+                logo_url: record.token_logo_url,
+            },
+            size: record.size,
+            entry_price: record.entry_price,
+            current_price: record.current_price,
+            wallet: Wallet {
+                address: record.wallet_address,
+                chain: record.wallet_chain,
+                label: record.wallet_label,
+            },
+            opened_at: record.opened_at,
+        }
+    }
+}
+
+fn write_records<W: Write, T: Serialize>(writer: W, records: impl IntoIterator<Item = T>) -> Result<()> {
+    let mut csv = csv::Writer::from_writer(writer);
+    for record in records {
+        csv.serialize(record)?;
+    }
+    csv.flush()?;
+    Ok(())
+}
+
+fn read_records<R: Read, T: DeserializeOwned>(reader: R) -> Result<Vec<T>> {
+    let mut csv = csv::Reader::from_reader(reader);
+    let mut records = Vec::new();
+    for record in csv.deserialize() {
+        records.push(record?);
+    }
+    Ok(records)
+}
+
+/// Write orders as CSV with a header row
+pub fn write_orders<W: Write>(writer: W, orders: &[Order]) -> Result<()> {
+    write_records(writer, orders.iter().map(OrderRecord::from))
+}
+
+/// Read orders from CSV written by [`write_orders`]
+pub fn read_orders<R: Read>(reader: R) -> Result<Vec<Order>> {
+    let records: Vec<OrderRecord> = read_records(reader)?;
+    Ok(records.into_iter().map(Order::from).collect())
+}
+
+/// Write fills as CSV with a header row
+pub fn write_fills<W: Write>(writer: W, fills: &[Fill]) -> Result<()> {
+    write_records(writer, fills.iter().map(FillRecord::from))
+}
+
+/// Read fills from CSV written by [`write_fills`]
+pub fn read_fills<R: Read>(reader: R) -> Result<Vec<Fill>> {
+    let records: Vec<FillRecord> = read_records(reader)?;
+    Ok(records.into_iter().map(Fill::from).collect())
+}
+
+/// Write positions as CSV with a header row
+///
+/// Includes a derived `unrealized_pnl` column, which is ignored on import.
+pub fn write_positions<W: Write>(writer: W, positions: &[Position]) -> Result<()> {
+    write_records(writer, positions.iter().map(PositionRecord::from))
+}
+
+/// Read positions from CSV written by [`write_positions`]
+pub fn read_positions<R: Read>(reader: R) -> Result<Vec<Position>> {
+    let records: Vec<PositionRecord> = read_records(reader)?;
+    Ok(records.into_iter().map(Position::from).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fill_round_trip() {
+        let fill = Fill {
+            id: "f1".to_string(),
+            order_id: "o1".to_string(),
+            side: OrderSide::Buy,
+            size: 10.0,
+            price: 0.55,
+            fee: 0.01,
+            timestamp: Utc::now(),
+            transaction_hash: "0xabc".to_string(),
+        };
+
+        let mut buf = Vec::new();
+        write_fills(&mut buf, std::slice::from_ref(&fill)).unwrap();
+        let text = String::from_utf8(buf.clone()).unwrap();
+        assert!(text.starts_with("id,order_id,timestamp,side"));
+
+        let fills = read_fills(buf.as_slice()).unwrap();
+        assert_eq!(fills.len(), 1);
+        assert_eq!(fills[0].order_id, "o1");
+        assert_eq!(fills[0].side, OrderSide::Buy);
+        assert_eq!(fills[0].timestamp, fill.timestamp);
+    }
+
+    #[test]
+    fn test_order_round_trip() {
+        let order = Order {
+            id: Some("o1".to_string()),
+            side: OrderSide::Sell,
+            order_type: OrderType::StopLimit,
+            token_id: "t1".to_string(),
+            size: 5.0,
+            price: 0.4,
+            time_in_force: TimeInForce::Gtc,
+            post_only: false,
+            reduce_only: true,
+            stop_price: Some(0.42),
+            wallet: Wallet::new("0xabc", Chain::Polygon).with_label("main"),
+            created_at: Utc::now(),
+            status: OrderStatus::Open,
+        };
+
+        let mut buf = Vec::new();
+        write_orders(&mut buf, &[order]).unwrap();
+        let orders = read_orders(buf.as_slice()).unwrap();
+
+        assert_eq!(orders[0].order_type, OrderType::StopLimit);
+        assert_eq!(orders[0].stop_price, Some(0.42));
+        assert_eq!(orders[0].wallet.label.as_deref(), Some("main"));
+    }
+}
@@ -0,0 +1,133 @@
+//! Venue fee models
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::types::{OrderSide, Position};
+
+/// Whether a fill added or removed liquidity
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Liquidity {
+    #[serde(rename = "maker")]
+    Maker,
+    #[serde(rename = "taker")]
+    Taker,
+}
+
+/// Fee schedule for a venue
+///
+/// Trading fees are expressed in basis points of notional; gas and withdrawal
+/// fees are flat amounts in the quote currency.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct FeeSchedule {
+    pub maker_bps: f64,
+    pub taker_bps: f64,
+    /// Expected gas cost of settling one fill on-chain
+    #[serde(default)]
+    pub gas_per_fill: f64,
+    /// Withdrawal fee by asset symbol
+    #[serde(default)]
+    pub withdrawal_fees: HashMap<String, f64>,
+}
+
+/// Expected cost breakdown of a fill
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FillCost {
+    pub notional: f64,
+    pub fee: f64,
+    pub gas: f64,
+}
+
+impl FillCost {
+    /// Fees and gas combined
+    pub fn total_fees(&self) -> f64 {
+        self.fee + self.gas
+    }
+
+    /// Cash moved by the fill: paid for a buy (positive), received for a sell
+    /// (negative), fees included
+    pub fn cash_flow(&self, side: OrderSide) -> f64 {
+        match side {
+            OrderSide::Buy => self.notional + self.total_fees(),
+            OrderSide::Sell => -(self.notional - self.total_fees()),
+        }
+    }
+}
+
+impl FeeSchedule {
+    /// Schedule with no fees at all
+    pub fn zero() -> Self {
+        Self::default()
+    }
+
+    pub fn new(maker_bps: f64, taker_bps: f64) -> Self {
+        Self {
+            maker_bps,
+            taker_bps,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_gas_per_fill(mut self, gas: f64) -> Self {
+        self.gas_per_fill = gas;
+        self
+    }
+
+    pub fn with_withdrawal_fee(mut self, symbol: impl Into<String>, fee: f64) -> Self {
+        self.withdrawal_fees.insert(symbol.into(), fee);
+        self
+    }
+
+    /// Fee rate in basis points for the given liquidity
+    pub fn rate_bps(&self, liquidity: Liquidity) -> f64 {
+        match liquidity {
+            Liquidity::Maker => self.maker_bps,
+            Liquidity::Taker => self.taker_bps,
+        }
+    }
+
+    /// Trading fee on a notional amount
+    pub fn fee(&self, liquidity: Liquidity, notional: f64) -> f64 {
+        notional.abs() * self.rate_bps(liquidity) / 10_000.0
+    }
+
+    /// Expected cost of a fill before placing it
+    pub fn expected_cost(&self, size: f64, price: f64, liquidity: Liquidity) -> FillCost {
+        let notional = size * price;
+        FillCost {
+            notional,
+            fee: self.fee(liquidity, notional),
+            gas: self.gas_per_fill,
+        }
+    }
+
+    /// Withdrawal fee for an asset, zero if unknown
+    pub fn withdrawal_fee(&self, symbol: &str) -> f64 {
+        self.withdrawal_fees.get(symbol).copied().unwrap_or(0.0)
+    }
+}
+
+impl Position {
+    /// Unrealized PnL net of the taker fee and gas to close at the current price
+    pub fn unrealized_pnl_after_fees(&self, fees: &FeeSchedule) -> f64 {
+        let exit = fees.expected_cost(self.size, self.current_price, Liquidity::Taker);
+        self.unrealized_pnl() - exit.total_fees()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expected_cost() {
+        let fees = FeeSchedule::new(0.0, 20.0).with_gas_per_fill(0.05);
+        let cost = fees.expected_cost(100.0, 0.5, Liquidity::Taker);
+
+        assert_eq!(cost.notional, 50.0);
+        assert!((cost.fee - 0.1).abs() < 1e-12);
+        assert!((cost.cash_flow(OrderSide::Buy) - 50.15).abs() < 1e-12);
+        assert!((cost.cash_flow(OrderSide::Sell) + 49.85).abs() < 1e-12);
+        assert_eq!(fees.expected_cost(100.0, 0.5, Liquidity::Maker).fee, 0.0);
+    }
+}
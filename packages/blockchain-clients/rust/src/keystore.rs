@@ -0,0 +1,221 @@
+//! Encrypted keystore for wallet private keys
+//!
+//! Keys are stored as Web3 Secret Storage (keystore v3, scrypt) JSON files
+//! and only ever held in memory in buffers that are zeroized on drop.
+
+use k256::ecdsa::SigningKey;
+use sha3::{Digest, Keccak256};
+use std::path::{Component, Path, PathBuf};
+use zeroize::Zeroizing;
+
+use crate::error::{Error, Result};
+use crate::traits::Signer;
+
+/// Raw private key bytes, zeroized on drop
+#[derive(Clone)]
+pub struct SecretKey(Zeroizing<Vec<u8>>);
+
+impl SecretKey {
+    pub fn from_bytes(bytes: Vec<u8>) -> Self {
+        Self(Zeroizing::new(bytes))
+    }
+
+    /// Parse a hex key, with or without a `0x` prefix
+    pub fn from_hex(hex_key: &str) -> Result<Self> {
+        let bytes = hex::decode(hex_key.trim().trim_start_matches("0x"))
+            .map_err(|_| Error::Config("Invalid private key hex".to_string()))?;
+        Ok(Self::from_bytes(bytes))
+    }
+
+    /// Borrow the key material
+    pub fn expose_secret(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl std::fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SecretKey(<redacted>)")
+    }
+}
+
+/// secp256k1 signer backed by an in-memory key
+pub struct LocalSigner {
+    key: SigningKey,
+    address: String,
+}
+
+impl LocalSigner {
+    pub fn from_secret(secret: &SecretKey) -> Result<Self> {
+        let key = SigningKey::from_slice(secret.expose_secret())
+            .map_err(|_| Error::Config("Invalid secp256k1 private key".to_string()))?;
+        let address = address_of(&key);
+        Ok(Self { key, address })
+    }
+
+    /// Decrypt a keystore file and build a signer from it
+    pub fn from_keystore(path: impl AsRef<Path>, password: &str) -> Result<Self> {
+        let secret = decrypt_file(path, password)?;
+        Self::from_secret(&secret)
+    }
+}
+
+impl std::fmt::Debug for LocalSigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LocalSigner").field("address", &self.address).finish()
+    }
+}
+
+impl Signer for LocalSigner {
+    fn address(&self) -> &str {
+        &self.address
+    }
+
+    fn sign_hash(&self, hash: &[u8; 32]) -> Result<[u8; 65]> {
+        let (signature, recovery_id) = self
+            .key
+            .sign_prehash_recoverable(hash)
+            .map_err(|e| Error::msg(format!("Signing failed: {}", e)))?;
+        let mut out = [0u8; 65];
+        out[..64].copy_from_slice(&signature.to_bytes());
+        out[64] = 27 + recovery_id.to_byte();
+        Ok(out)
+    }
+}
+
+/// Checksum-free `0x` address for a signing key
+fn address_of(key: &SigningKey) -> String {
+    let point = key.verifying_key().to_encoded_point(false);
+    let hash = Keccak256::digest(&point.as_bytes()[1..]);
+    format!("0x{}", hex::encode(&hash[12..]))
+}
+
+/// Decrypt a single keystore file
+pub fn decrypt_file(path: impl AsRef<Path>, password: &str) -> Result<SecretKey> {
+    let bytes = eth_keystore::decrypt_key(path, password)?;
+    Ok(SecretKey::from_bytes(bytes))
+}
+
+/// Directory of encrypted keystore files
+#[derive(Clone, Debug)]
+pub struct Keystore {
+    dir: PathBuf,
+}
+
+impl Keystore {
+    /// Open a keystore directory, creating it if needed
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    /// Keystore directory
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Path of the key stored under `name`, which must be a plain file name
+    /// so keys cannot be read or written outside the directory
+    fn key_path(&self, name: &str) -> Result<PathBuf> {
+        let mut components = Path::new(name).components();
+        match (components.next(), components.next()) {
+            (Some(Component::Normal(file)), None) if file == name && !name.contains('\\') => {
+                Ok(self.dir.join(name))
+            }
+            _ => Err(Error::Config(format!("Invalid key name: {:?}", name))),
+        }
+    }
+
+    /// Generate a new key, store it encrypted under `name`, and return a signer
+    pub fn create(&self, name: &str, password: &str) -> Result<LocalSigner> {
+        self.key_path(name)?;
+        let (bytes, _) = eth_keystore::new(&self.dir, &mut rand::thread_rng(), password, Some(name))?;
+        LocalSigner::from_secret(&SecretKey::from_bytes(bytes))
+    }
+
+    /// Encrypt an existing key and store it under `name`
+    pub fn import(&self, name: &str, secret: &SecretKey, password: &str) -> Result<PathBuf> {
+        // Validate before writing anything to disk
+        let path = self.key_path(name)?;
+        LocalSigner::from_secret(secret)?;
+        eth_keystore::encrypt_key(
+            &self.dir,
+            &mut rand::thread_rng(),
+            secret.expose_secret(),
+            password,
+            Some(name),
+        )?;
+        Ok(path)
+    }
+
+    /// Decrypt the key stored under `name`
+    pub fn decrypt(&self, name: &str, password: &str) -> Result<SecretKey> {
+        decrypt_file(self.key_path(name)?, password)
+    }
+
+    /// Decrypt the key stored under `name` into a signer
+    pub fn signer(&self, name: &str, password: &str) -> Result<LocalSigner> {
+        LocalSigner::from_secret(&self.decrypt(name, password)?)
+    }
+
+    /// Names of stored keys
+    pub fn list(&self) -> Result<Vec<String>> {
+        let mut names = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            if entry.file_type()?.is_file() {
+                names.push(entry.file_name().to_string_lossy().into_owned());
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Well-known test key (Hardhat account #0)
+    const KEY: &str = "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+    #[test]
+    fn test_address_derivation() {
+        let signer = LocalSigner::from_secret(&SecretKey::from_hex(KEY).unwrap()).unwrap();
+        assert_eq!(signer.address(), "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266");
+    }
+
+    #[test]
+    fn test_import_and_decrypt() {
+        let dir = std::env::temp_dir().join(format!("keystore-test-{}", std::process::id()));
+        let keystore = Keystore::open(&dir).unwrap();
+
+        let secret = SecretKey::from_hex(KEY).unwrap();
+        keystore.import("main", &secret, "hunter2").unwrap();
+
+        assert_eq!(keystore.list().unwrap(), vec!["main"]);
+        assert!(keystore.decrypt("main", "wrong").is_err());
+        let signer = keystore.signer("main", "hunter2").unwrap();
+        assert_eq!(signer.address(), "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266");
+        assert!(format!("{:?}", secret).contains("redacted"));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_rejects_unsafe_names() {
+        let dir = std::env::temp_dir().join(format!("keystore-names-{}", std::process::id()));
+        let keystore = Keystore::open(&dir).unwrap();
+        let secret = SecretKey::from_hex(KEY).unwrap();
+
+        for name in ["", ".", "..", "../escape", "sub/key", "/tmp/key", "./key", "key/", "..\\key"] {
+            assert!(matches!(keystore.import(name, &secret, "pw"), Err(Error::Config(_))), "{:?}", name);
+            assert!(matches!(keystore.decrypt(name, "pw"), Err(Error::Config(_))), "{:?}", name);
+            assert!(matches!(keystore.create(name, "pw"), Err(Error::Config(_))), "{:?}", name);
+        }
+        assert!(keystore.list().unwrap().is_empty());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -7,6 +7,13 @@
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
 pub mod error;
+pub mod events;
+pub mod fees;
+pub mod http;
+pub mod orderbook;
+pub mod paper;
+pub mod pricing;
+pub mod traits;
 pub mod types;
 
 #[cfg(feature = "evm")]
@@ -17,13 +24,45 @@ pub mod chains;
 #[cfg_attr(docsrs, doc(cfg(feature = "polymarket")))]
 pub mod exchanges;
 
+#[cfg(feature = "export")]
+#[cfg_attr(docsrs, doc(cfg(feature = "export")))]
+pub mod export;
+
+#[cfg(feature = "keystore")]
+#[cfg_attr(docsrs, doc(cfg(feature = "keystore")))]
+pub mod keystore;
+
+#[cfg(feature = "secrets")]
+#[cfg_attr(docsrs, doc(cfg(feature = "secrets")))]
+pub mod secrets;
+
+#[cfg(feature = "sqlite")]
+#[cfg_attr(docsrs, doc(cfg(feature = "sqlite")))]
+pub mod store;
+
+#[cfg(feature = "mock")]
+#[cfg_attr(docsrs, doc(cfg(feature = "mock")))]
+pub mod mock;
+
 pub use error::{Error, Result};
+pub use events::{EventMux, EventStream, MarketEvent};
+pub use fees::{FeeSchedule, Liquidity};
+pub use http::{with_deadline, HttpConfig, OperationTimeouts};
+pub use orderbook::{estimate_slippage, SlippageEstimate};
+pub use paper::{PaperConfig, PaperExchange};
+pub use pricing::{Pricer, StaticPricer};
+pub use traits::{ChainClient, Exchange, PredictionMarket, Signer};
 pub use types::{Chain, Token, Wallet, Order, OrderSide, OrderType, Position};
 
 /// Re-export commonly used types
 pub mod prelude {
     pub use crate::{
         error::{Error, Result},
+        events::*,
+        fees::*,
+        orderbook::*,
+        pricing::*,
+        traits::*,
         types::*,
     };
     
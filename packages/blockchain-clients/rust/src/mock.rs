@@ -0,0 +1,440 @@
+//! In-memory mock clients
+//!
+//! Deterministic stand-ins for the network clients, for unit-testing strategy
+//! and risk code. Clones share state, so a test can hand one clone to the code
+//! under test and inspect the recorded calls through another.
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::error::{Error, Result};
+use crate::fees::FeeSchedule;
+use crate::traits::{ChainClient, Exchange, PredictionMarket};
+use crate::types::{
+    Chain, Fill, Market, Order, OrderBook, OrderRequest, OrderStatus, OrderType,
+    TimeInForce, TradeResult, Transaction, Wallet,
+};
+
+#[derive(Debug, Default)]
+struct EvmState {
+    balances: HashMap<String, f64>,
+    token_balances: HashMap<(String, String), f64>,
+    transactions: HashMap<String, Transaction>,
+    block_number: u64,
+    gas_estimate: u64,
+    sent: Vec<String>,
+    failure: Option<String>,
+}
+
+/// Mock EVM chain client
+#[derive(Clone, Debug)]
+pub struct MockEvmClient {
+    chain: Chain,
+    state: Arc<Mutex<EvmState>>,
+}
+
+impl MockEvmClient {
+    pub fn new(chain: Chain) -> Self {
+        Self {
+            chain,
+            state: Arc::new(Mutex::new(EvmState {
+                gas_estimate: 21000,
+                ..Default::default()
+            })),
+        }
+    }
+
+    /// Set native balance returned for an address
+    pub fn set_balance(&self, address: impl Into<String>, balance: f64) {
+        self.state.lock().unwrap().balances.insert(address.into(), balance);
+    }
+
+    /// Set token balance returned for an address
+    pub fn set_token_balance(
+        &self,
+        address: impl Into<String>,
+        token_address: impl Into<String>,
+        balance: f64,
+    ) {
+        self.state
+            .lock()
+            .unwrap()
+            .token_balances
+            .insert((address.into(), token_address.into()), balance);
+    }
+
+    /// Register a transaction returned by `get_transaction`
+    pub fn add_transaction(&self, tx: Transaction) {
+        self.state.lock().unwrap().transactions.insert(tx.hash.clone(), tx);
+    }
+
+    /// Set the block number returned by `get_block_number`
+    pub fn set_block_number(&self, block_number: u64) {
+        self.state.lock().unwrap().block_number = block_number;
+    }
+
+    /// Set the value returned by `estimate_gas`
+    pub fn set_gas_estimate(&self, gas: u64) {
+        self.state.lock().unwrap().gas_estimate = gas;
+    }
+
+    /// Make the next call fail with an RPC error
+    pub fn fail_next(&self, message: impl Into<String>) {
+        self.state.lock().unwrap().failure = Some(message.into());
+    }
+
+    /// Raw transactions submitted through `send_transaction`
+    pub fn sent_transactions(&self) -> Vec<String> {
+        self.state.lock().unwrap().sent.clone()
+    }
+
+    fn take_failure(&self) -> Result<()> {
+        match self.state.lock().unwrap().failure.take() {
+            Some(message) => Err(Error::Rpc(message)),
+            None => Ok(()),
+        }
+    }
+}
+
+#[async_trait]
+impl ChainClient for MockEvmClient {
+    fn chain(&self) -> Chain {
+        self.chain
+    }
+
+    async fn get_balance(&self, address: &str) -> Result<f64> {
+        self.take_failure()?;
+        Ok(self.state.lock().unwrap().balances.get(address).copied().unwrap_or(0.0))
+    }
+
+    async fn get_token_balance(&self, address: &str, token_address: &str) -> Result<f64> {
+        self.take_failure()?;
+        let key = (address.to_string(), token_address.to_string());
+        Ok(self.state.lock().unwrap().token_balances.get(&key).copied().unwrap_or(0.0))
+    }
+
+    async fn get_transaction(&self, tx_hash: &str) -> Result<Transaction> {
+        self.take_failure()?;
+        self.state
+            .lock()
+            .unwrap()
+            .transactions
+            .get(tx_hash)
+            .cloned()
+            .ok_or_else(|| Error::InvalidTransaction(tx_hash.to_string()))
+    }
+
+    async fn get_block_number(&self) -> Result<u64> {
+        self.take_failure()?;
+        Ok(self.state.lock().unwrap().block_number)
+    }
+
+    async fn send_transaction(&self, signed_tx: &str) -> Result<String> {
+        self.take_failure()?;
+        let mut state = self.state.lock().unwrap();
+        state.sent.push(signed_tx.to_string());
+        Ok(format!("0x{:064x}", state.sent.len()))
+    }
+
+    async fn estimate_gas(
+        &self,
+        _from: &str,
+        _to: &str,
+        _data: Option<&str>,
+        _value: Option<&str>,
+    ) -> Result<u64> {
+        self.take_failure()?;
+        Ok(self.state.lock().unwrap().gas_estimate)
+    }
+}
+
+#[derive(Debug, Default)]
+struct ExchangeState {
+    markets: Vec<Market>,
+    books: HashMap<String, OrderBook>,
+    open_orders: Vec<Order>,
+    fills: Vec<Fill>,
+    placed: Vec<OrderRequest>,
+    cancelled: Vec<String>,
+    fees: FeeSchedule,
+    next_id: u64,
+    failure: Option<String>,
+}
+
+/// Mock Polymarket client
+///
+/// Orders are checked like the real client's and fill immediately at their
+/// limit price, unless a failure has been queued with
+/// [`MockPolymarketClient::fail_next`]. Without an order book for the token
+/// they fill in full; with one, only the liquidity at or better than the
+/// limit fills. FOK orders that cannot fill in full are rejected, and the
+/// unfilled rest of a GTC limit is reported open but not kept.
+#[derive(Clone, Debug, Default)]
+pub struct MockPolymarketClient {
+    state: Arc<Mutex<ExchangeState>>,
+}
+
+impl MockPolymarketClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a market returned by market queries
+    pub fn add_market(&self, market: Market) {
+        self.state.lock().unwrap().markets.push(market);
+    }
+
+    /// Set the order book returned for a token
+    pub fn set_order_book(&self, book: OrderBook) {
+        self.state.lock().unwrap().books.insert(book.asset_id.clone(), book);
+    }
+
+    /// Add an order returned by `get_open_orders`
+    pub fn add_open_order(&self, order: Order) {
+        self.state.lock().unwrap().open_orders.push(order);
+    }
+
+    /// Add a fill returned by `get_fills`
+    pub fn add_fill(&self, fill: Fill) {
+        self.state.lock().unwrap().fills.push(fill);
+    }
+
+    /// Set the fee schedule reported by the mock
+    pub fn set_fee_schedule(&self, fees: FeeSchedule) {
+        self.state.lock().unwrap().fees = fees;
+    }
+
+    /// Make the next call fail with an RPC error
+    pub fn fail_next(&self, message: impl Into<String>) {
+        self.state.lock().unwrap().failure = Some(message.into());
+    }
+
+    /// Orders submitted through `place_order`
+    pub fn placed_orders(&self) -> Vec<OrderRequest> {
+        self.state.lock().unwrap().placed.clone()
+    }
+
+    /// Order IDs submitted through `cancel_order`
+    pub fn cancelled_orders(&self) -> Vec<String> {
+        self.state.lock().unwrap().cancelled.clone()
+    }
+
+    fn take_failure(&self) -> Result<()> {
+        match self.state.lock().unwrap().failure.take() {
+            Some(message) => Err(Error::Rpc(message)),
+            None => Ok(()),
+        }
+    }
+}
+
+#[async_trait]
+impl Exchange for MockPolymarketClient {
+    fn venue(&self) -> &'static str {
+        "mock"
+    }
+
+    fn fee_schedule(&self) -> FeeSchedule {
+        self.state.lock().unwrap().fees.clone()
+    }
+
+    async fn get_order_book(&self, token_id: &str) -> Result<OrderBook> {
+        self.take_failure()?;
+        self.state
+            .lock()
+            .unwrap()
+            .books
+            .get(token_id)
+            .cloned()
+            .ok_or_else(|| Error::MarketNotFound(token_id.to_string()))
+    }
+
+    async fn place_order(&self, order: &OrderRequest) -> Result<TradeResult> {
+        self.take_failure()?;
+        crate::exchanges::polymarket::check_order(order)?;
+        let mut state = self.state.lock().unwrap();
+        let fillable = match state.books.get(&order.token_id) {
+            Some(book) => fillable_size(book, order),
+            None => order.size,
+        };
+        let filled = fillable.min(order.size);
+        if order.time_in_force == TimeInForce::Fok && order.size - filled > f64::EPSILON {
+            return Err(Error::OrderRejected(format!(
+                "FOK order for {} could only fill {}",
+                order.size, filled
+            )));
+        }
+
+        let status = if order.size - filled <= f64::EPSILON {
+            OrderStatus::Filled
+        } else if order.order_type != OrderType::Market && order.time_in_force == TimeInForce::Gtc {
+            if filled > 0.0 {
+                OrderStatus::PartiallyFilled
+            } else {
+                OrderStatus::Open
+            }
+        } else if filled > 0.0 {
+            OrderStatus::PartiallyFilled
+        } else {
+            OrderStatus::Cancelled
+        };
+        state.next_id += 1;
+        state.placed.push(order.clone());
+        Ok(TradeResult {
+            order_id: format!("mock-{}", state.next_id),
+            status: format!("{:?}", status).to_lowercase(),
+            filled_size: filled,
+            avg_price: if filled > 0.0 { order.price } else { 0.0 },
+            transaction_hash: None,
+        })
+    }
+
+    async fn cancel_order(&self, order_id: &str) -> Result<bool> {
+        self.take_failure()?;
+        let mut state = self.state.lock().unwrap();
+        state.cancelled.push(order_id.to_string());
+        let before = state.open_orders.len();
+        state.open_orders.retain(|o| o.id.as_deref() != Some(order_id));
+        Ok(state.open_orders.len() < before)
+    }
+
+    async fn get_open_orders(&self, wallet: &Wallet) -> Result<Vec<Order>> {
+        self.take_failure()?;
+        Ok(self
+            .state
+            .lock()
+            .unwrap()
+            .open_orders
+            .iter()
+            .filter(|o| o.wallet.address == wallet.address && o.status == OrderStatus::Open)
+            .cloned()
+            .collect())
+    }
+
+    async fn get_fills(&self, _wallet: &Wallet, limit: usize) -> Result<Vec<Fill>> {
+        self.take_failure()?;
+        Ok(self.state.lock().unwrap().fills.iter().rev().take(limit).cloned().collect())
+    }
+}
+
+/// Size on the opposite side of `book` that `order` can take
+fn fillable_size(book: &OrderBook, order: &OrderRequest) -> f64 {
+    match order.order_type {
+        OrderType::Market => book.levels_for(order.side).iter().map(|l| l.size).sum(),
+        _ => book.cumulative_size(order.side, order.price),
+    }
+}
+
+#[async_trait]
+impl PredictionMarket for MockPolymarketClient {
+    async fn get_active_markets(&self) -> Result<Vec<Market>> {
+        self.take_failure()?;
+        Ok(self
+            .state
+            .lock()
+            .unwrap()
+            .markets
+            .iter()
+            .filter(|m| m.is_active && !m.is_closed)
+            .cloned()
+            .collect())
+    }
+
+    async fn get_market(&self, market_id: &str) -> Result<Market> {
+        self.take_failure()?;
+        self.state
+            .lock()
+            .unwrap()
+            .markets
+            .iter()
+            .find(|m| m.id == market_id)
+            .cloned()
+            .ok_or_else(|| Error::MarketNotFound(market_id.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::OrderBookEntry;
+    use chrono::Utc;
+
+    fn book(token_id: &str) -> OrderBook {
+        OrderBook {
+            market: "m1".to_string(),
+            asset_id: token_id.to_string(),
+            bids: vec![OrderBookEntry { price: 0.45, size: 100.0 }],
+            asks: vec![OrderBookEntry { price: 0.55, size: 100.0 }],
+            timestamp: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mock_exchange_records_orders() {
+        let client = MockPolymarketClient::new();
+        let exchange: &dyn Exchange = &client;
+
+        let result = exchange.place_order(&OrderRequest::buy("t1", 10.0, 0.5)).await.unwrap();
+        assert_eq!(result.filled_size, 10.0);
+        assert_eq!(client.placed_orders().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_mock_exchange_rejects_and_partial_fills() {
+        let client = MockPolymarketClient::new();
+        client.set_order_book(book("t1"));
+
+        let stop = OrderRequest {
+            order_type: OrderType::Stop,
+            stop_price: Some(0.4),
+            ..OrderRequest::sell("t1", 10.0, 0.4)
+        };
+        let reduce_only = OrderRequest {
+            reduce_only: true,
+            ..OrderRequest::sell("t1", 10.0, 0.4)
+        };
+        let no_stop_price = OrderRequest {
+            order_type: OrderType::StopLimit,
+            ..OrderRequest::sell("t1", 10.0, 0.4)
+        };
+        for order in [stop, reduce_only, no_stop_price] {
+            assert!(matches!(client.place_order(&order).await, Err(Error::OrderRejected(_))));
+        }
+
+        let fok = OrderRequest::buy("t1", 150.0, 0.55).with_time_in_force(TimeInForce::Fok);
+        assert!(matches!(client.place_order(&fok).await, Err(Error::OrderRejected(_))));
+        assert!(client.placed_orders().is_empty());
+
+        let below_ask = client.place_order(&OrderRequest::buy("t1", 10.0, 0.50)).await.unwrap();
+        assert_eq!(below_ask.status, "open");
+        assert_eq!(below_ask.filled_size, 0.0);
+
+        let partial = client.place_order(&OrderRequest::buy("t1", 150.0, 0.55)).await.unwrap();
+        assert_eq!(partial.status, "partiallyfilled");
+        assert_eq!(partial.filled_size, 100.0);
+
+        let ioc = OrderRequest::sell("t1", 10.0, 0.50).with_time_in_force(TimeInForce::Ioc);
+        let ioc = client.place_order(&ioc).await.unwrap();
+        assert_eq!(ioc.status, "cancelled");
+        assert_eq!(client.placed_orders().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_mock_exchange_fail_next() {
+        let client = MockPolymarketClient::new();
+        client.set_order_book(book("t1"));
+        client.fail_next("boom");
+
+        assert!(client.get_order_book("t1").await.is_err());
+        assert!(client.get_order_book("t1").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_mock_evm_balances() {
+        let client = MockEvmClient::new(Chain::Polygon);
+        client.set_balance("0xabc", 2.5);
+
+        assert_eq!(client.get_balance("0xabc").await.unwrap(), 2.5);
+        assert_eq!(client.get_balance("0xdef").await.unwrap(), 0.0);
+    }
+}
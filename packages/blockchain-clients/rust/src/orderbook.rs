@@ -0,0 +1,280 @@
+//! Order book analytics
+//!
+//! Book levels are not assumed to arrive sorted; every method orders them
+//! best-first before walking the book. Methods taking an [`OrderSide`] read
+//! the levels an order on that side would trade against: asks for a buy,
+//! bids for a sell.
+
+use crate::types::{OrderBook, OrderBookEntry, OrderSide};
+
+impl OrderBook {
+    /// Bids sorted best (highest) first
+    pub fn sorted_bids(&self) -> Vec<&OrderBookEntry> {
+        let mut bids: Vec<_> = self.bids.iter().collect();
+        bids.sort_by(|a, b| b.price.total_cmp(&a.price));
+        bids
+    }
+
+    /// Asks sorted best (lowest) first
+    pub fn sorted_asks(&self) -> Vec<&OrderBookEntry> {
+        let mut asks: Vec<_> = self.asks.iter().collect();
+        asks.sort_by(|a, b| a.price.total_cmp(&b.price));
+        asks
+    }
+
+    /// Levels a taker on `side` would trade against, best first
+    pub fn levels_for(&self, side: OrderSide) -> Vec<&OrderBookEntry> {
+        match side {
+            OrderSide::Buy => self.sorted_asks(),
+            OrderSide::Sell => self.sorted_bids(),
+        }
+    }
+
+    /// Highest bid
+    pub fn best_bid(&self) -> Option<&OrderBookEntry> {
+        self.bids.iter().max_by(|a, b| a.price.total_cmp(&b.price))
+    }
+
+    /// Lowest ask
+    pub fn best_ask(&self) -> Option<&OrderBookEntry> {
+        self.asks.iter().min_by(|a, b| a.price.total_cmp(&b.price))
+    }
+
+    /// Midpoint of the best bid and ask
+    pub fn mid_price(&self) -> Option<f64> {
+        Some((self.best_bid()?.price + self.best_ask()?.price) / 2.0)
+    }
+
+    /// Best ask minus best bid
+    pub fn spread(&self) -> Option<f64> {
+        Some(self.best_ask()?.price - self.best_bid()?.price)
+    }
+
+    /// Spread relative to the mid, in basis points
+    pub fn spread_bps(&self) -> Option<f64> {
+        let mid = self.mid_price()?;
+        if mid <= 0.0 {
+            return None;
+        }
+        Some(self.spread()? / mid * 10_000.0)
+    }
+
+    /// Size-weighted mid using top-of-book sizes
+    ///
+    /// Leans toward the side with less size, where the next trade is more
+    /// likely to move the price.
+    pub fn microprice(&self) -> Option<f64> {
+        let bid = self.best_bid()?;
+        let ask = self.best_ask()?;
+        let total = bid.size + ask.size;
+        if total <= 0.0 {
+            return self.mid_price();
+        }
+        Some((bid.price * ask.size + ask.price * bid.size) / total)
+    }
+
+    /// Size a `side` order could take at exactly `price`
+    pub fn depth_at_price(&self, side: OrderSide, price: f64) -> f64 {
+        self.levels_for(side)
+            .into_iter()
+            .filter(|l| (l.price - price).abs() < 1e-12)
+            .map(|l| l.size)
+            .sum()
+    }
+
+    /// Size a `side` order limited at `price` could take: asks at or below
+    /// it for a buy, bids at or above it for a sell
+    pub fn cumulative_size(&self, side: OrderSide, price: f64) -> f64 {
+        match side {
+            OrderSide::Buy => self.asks.iter().filter(|l| l.price <= price).map(|l| l.size).sum(),
+            OrderSide::Sell => self.bids.iter().filter(|l| l.price >= price).map(|l| l.size).sum(),
+        }
+    }
+
+    /// Bid/ask size imbalance over the top `levels` levels, in `[-1, 1]`
+    ///
+    /// Positive values mean more resting bid size than ask size.
+    pub fn imbalance(&self, levels: usize) -> Option<f64> {
+        let bid_size: f64 = self.sorted_bids().iter().take(levels).map(|l| l.size).sum();
+        let ask_size: f64 = self.sorted_asks().iter().take(levels).map(|l| l.size).sum();
+        let total = bid_size + ask_size;
+        if total <= 0.0 {
+            return None;
+        }
+        Some((bid_size - ask_size) / total)
+    }
+
+    /// Average price a taker on `side` would pay or receive for `size`
+    ///
+    /// Returns `None` if the book is not deep enough to fill the whole size.
+    pub fn average_fill_price(&self, side: OrderSide, size: f64) -> Option<f64> {
+        if size <= 0.0 {
+            return None;
+        }
+        let mut remaining = size;
+        let mut notional = 0.0;
+        for level in self.levels_for(side) {
+            let take = remaining.min(level.size);
+            notional += take * level.price;
+            remaining -= take;
+            if remaining <= f64::EPSILON {
+                return Some(notional / size);
+            }
+        }
+        None
+    }
+}
+
+/// Expected execution of a taker order against a book
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SlippageEstimate {
+    pub side: OrderSide,
+    pub requested_size: f64,
+    /// Size the book can absorb; less than requested when the book is thin
+    pub fillable_size: f64,
+    /// Size-weighted average price over the fillable size
+    pub expected_price: f64,
+    /// Best opposite price before the order
+    pub reference_price: f64,
+    /// Adverse move from the reference price, in basis points
+    pub slippage_bps: f64,
+}
+
+impl SlippageEstimate {
+    /// Check if the book can fill the whole order
+    pub fn is_complete(&self) -> bool {
+        self.requested_size - self.fillable_size <= f64::EPSILON
+    }
+
+    /// Check if the order should be vetoed for exceeding `max_bps` of
+    /// slippage or for being larger than the book
+    pub fn exceeds(&self, max_bps: f64) -> bool {
+        !self.is_complete() || self.slippage_bps > max_bps
+    }
+}
+
+/// Estimate the fill price and slippage of a taker order of `size`
+///
+/// Returns `None` if the opposite side of the book is empty or `size` is not
+/// positive.
+pub fn estimate_slippage(book: &OrderBook, side: OrderSide, size: f64) -> Option<SlippageEstimate> {
+    if size <= 0.0 {
+        return None;
+    }
+    let levels = book.levels_for(side);
+    let reference_price = levels.first()?.price;
+
+    let mut remaining = size;
+    let mut notional = 0.0;
+    for level in levels {
+        if remaining <= f64::EPSILON {
+            break;
+        }
+        let take = remaining.min(level.size);
+        notional += take * level.price;
+        remaining -= take;
+    }
+
+    let fillable_size = size - remaining.max(0.0);
+    if fillable_size <= 0.0 {
+        return None;
+    }
+    let expected_price = notional / fillable_size;
+    let adverse = match side {
+        OrderSide::Buy => expected_price - reference_price,
+        OrderSide::Sell => reference_price - expected_price,
+    };
+
+    Some(SlippageEstimate {
+        side,
+        requested_size: size,
+        fillable_size,
+        expected_price,
+        reference_price,
+        slippage_bps: if reference_price > 0.0 {
+            adverse / reference_price * 10_000.0
+        } else {
+            0.0
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn book() -> OrderBook {
+        OrderBook {
+            market: "m".to_string(),
+            asset_id: "t".to_string(),
+            bids: vec![
+                OrderBookEntry { price: 0.47, size: 200.0 },
+                OrderBookEntry { price: 0.48, size: 100.0 },
+            ],
+            asks: vec![
+                OrderBookEntry { price: 0.52, size: 300.0 },
+                OrderBookEntry { price: 0.50, size: 100.0 },
+            ],
+            timestamp: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_top_of_book() {
+        let book = book();
+        assert_eq!(book.best_bid().unwrap().price, 0.48);
+        assert_eq!(book.best_ask().unwrap().price, 0.50);
+        assert!((book.mid_price().unwrap() - 0.49).abs() < 1e-12);
+        assert!((book.microprice().unwrap() - 0.49).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_depth_and_imbalance() {
+        let book = book();
+        assert_eq!(book.depth_at_price(OrderSide::Buy, 0.52), 300.0);
+        assert_eq!(book.depth_at_price(OrderSide::Sell, 0.52), 0.0);
+        assert_eq!(book.depth_at_price(OrderSide::Sell, 0.47), 200.0);
+        assert_eq!(book.cumulative_size(OrderSide::Sell, 0.47), 300.0);
+        assert_eq!(book.cumulative_size(OrderSide::Buy, 0.50), 100.0);
+        assert_eq!(book.cumulative_size(OrderSide::Buy, 0.49), 0.0);
+        assert_eq!(book.imbalance(1).unwrap(), 0.0);
+        assert!((book.imbalance(2).unwrap() - (-0.1428571)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_sides_agree() {
+        // Every method reads the levels an order on the given side takes
+        let book = book();
+        for side in [OrderSide::Buy, OrderSide::Sell] {
+            let levels = book.levels_for(side);
+            let (best, worst) = (levels[0].price, levels[levels.len() - 1].price);
+            let total: f64 = levels.iter().map(|l| l.size).sum();
+            assert_eq!(book.cumulative_size(side, worst), total);
+            assert_eq!(book.depth_at_price(side, best), levels[0].size);
+            assert_eq!(book.average_fill_price(side, levels[0].size), Some(best));
+            assert_eq!(estimate_slippage(&book, side, total).unwrap().reference_price, best);
+        }
+    }
+
+    #[test]
+    fn test_average_fill_price() {
+        let book = book();
+        assert!((book.average_fill_price(OrderSide::Buy, 200.0).unwrap() - 0.51).abs() < 1e-12);
+        assert_eq!(book.average_fill_price(OrderSide::Sell, 1000.0), None);
+    }
+
+    #[test]
+    fn test_estimate_slippage() {
+        let book = book();
+        let estimate = estimate_slippage(&book, OrderSide::Buy, 200.0).unwrap();
+        assert!(estimate.is_complete());
+        assert!((estimate.slippage_bps - 200.0).abs() < 1e-9);
+        assert!(estimate.exceeds(100.0));
+        assert!(!estimate.exceeds(250.0));
+
+        let thin = estimate_slippage(&book, OrderSide::Sell, 1000.0).unwrap();
+        assert_eq!(thin.fillable_size, 300.0);
+        assert!(thin.exceeds(f64::MAX));
+    }
+}
@@ -0,0 +1,664 @@
+//! Paper-trading exchange
+//!
+//! Simulates order execution against live or recorded order books while
+//! tracking a virtual cash balance and positions. Produces the same
+//! `Order`/`Fill`/`Position` types as the real clients, so strategies can be
+//! validated end to end before risking funds.
+
+use async_trait::async_trait;
+use chrono::Utc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::error::{Error, Result};
+use crate::fees::{FeeSchedule, Liquidity};
+use crate::traits::{Exchange, PredictionMarket};
+use crate::types::{
+    Fill, Market, Order, OrderBook, OrderBookEntry, OrderRequest, OrderSide, OrderStatus,
+    OrderType, Position, TimeInForce, Token, TradeResult, Wallet,
+};
+
+/// Paper exchange configuration
+#[derive(Clone, Debug)]
+pub struct PaperConfig {
+    /// Starting virtual cash balance
+    pub initial_balance: f64,
+    /// Fees charged on simulated fills; resting fills pay maker rates
+    pub fees: FeeSchedule,
+    /// Wallet that simulated orders and positions are attributed to
+    pub wallet: Wallet,
+}
+
+impl PaperConfig {
+    pub fn new(wallet: Wallet, initial_balance: f64) -> Self {
+        Self {
+            initial_balance,
+            fees: FeeSchedule::zero(),
+            wallet,
+        }
+    }
+
+    pub fn with_fees(mut self, fees: FeeSchedule) -> Self {
+        self.fees = fees;
+        self
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Holding {
+    size: f64,
+    entry_price: f64,
+    opened_at: chrono::DateTime<Utc>,
+}
+
+#[derive(Clone, Debug)]
+struct RestingOrder {
+    order: Order,
+    remaining: f64,
+}
+
+#[derive(Debug, Default)]
+struct PaperState {
+    balance: f64,
+    holdings: HashMap<String, Holding>,
+    marks: HashMap<String, f64>,
+    books: HashMap<String, OrderBook>,
+    resting: Vec<RestingOrder>,
+    fills: Vec<Fill>,
+    next_id: u64,
+}
+
+impl PaperState {
+    fn next_id(&mut self, prefix: &str) -> String {
+        self.next_id += 1;
+        format!("paper-{}-{}", prefix, self.next_id)
+    }
+
+    /// Cash committed to resting buy orders
+    fn reserved(&self) -> f64 {
+        self.resting
+            .iter()
+            .filter(|r| r.order.side == OrderSide::Buy)
+            .map(|r| r.remaining * r.order.price)
+            .sum()
+    }
+
+    /// Position size not already committed to resting sell orders
+    fn available_size(&self, token_id: &str) -> f64 {
+        let held = self.holdings.get(token_id).map(|h| h.size).unwrap_or(0.0);
+        let committed: f64 = self
+            .resting
+            .iter()
+            .filter(|r| r.order.side == OrderSide::Sell && r.order.token_id == token_id)
+            .map(|r| r.remaining)
+            .sum();
+        held - committed
+    }
+
+    fn apply_fill(
+        &mut self,
+        order_id: &str,
+        token_id: &str,
+        side: OrderSide,
+        size: f64,
+        price: f64,
+        fee: f64,
+    ) {
+        let notional = size * price;
+        let now = Utc::now();
+
+        match side {
+            OrderSide::Buy => {
+                self.balance -= notional + fee;
+                let holding = self.holdings.entry(token_id.to_string()).or_insert(Holding {
+                    size: 0.0,
+                    entry_price: 0.0,
+                    opened_at: now,
+                });
+                let new_size = holding.size + size;
+                holding.entry_price = (holding.entry_price * holding.size + notional) / new_size;
+                holding.size = new_size;
+            }
+            OrderSide::Sell => {
+                self.balance += notional - fee;
+                if let Some(holding) = self.holdings.get_mut(token_id) {
+                    holding.size -= size;
+                    if holding.size <= f64::EPSILON {
+                        self.holdings.remove(token_id);
+                    }
+                }
+            }
+        }
+
+        self.marks.insert(token_id.to_string(), price);
+        let id = self.next_id("fill");
+        self.fills.push(Fill {
+            id,
+            order_id: order_id.to_string(),
+            side,
+            size,
+            price,
+            fee,
+            timestamp: now,
+            transaction_hash: String::new(),
+        });
+    }
+}
+
+/// Simulated exchange with a virtual balance
+///
+/// Books come from an optional live source (any [`PredictionMarket`]) or from
+/// snapshots supplied with [`PaperExchange::set_order_book`]. Orders take
+/// liquidity level by level; the unfilled remainder of a GTC limit order rests
+/// until a later book crosses it, while IOC and market remainders are dropped
+/// and FOK orders that cannot fill entirely are rejected. Selling is limited to
+/// held size, so positions never go short. Stop orders are not simulated.
+#[derive(Clone)]
+pub struct PaperExchange {
+    config: PaperConfig,
+    source: Option<Arc<dyn PredictionMarket>>,
+    state: Arc<Mutex<PaperState>>,
+}
+
+impl std::fmt::Debug for PaperExchange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PaperExchange")
+            .field("config", &self.config)
+            .field("live", &self.source.is_some())
+            .finish()
+    }
+}
+
+impl PaperExchange {
+    /// Create a paper exchange driven by recorded order books
+    pub fn new(config: PaperConfig) -> Self {
+        let state = PaperState {
+            balance: config.initial_balance,
+            ..Default::default()
+        };
+        Self {
+            config,
+            source: None,
+            state: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    /// Create a paper exchange that reads books and markets from a live venue
+    pub fn with_source(config: PaperConfig, source: Arc<dyn PredictionMarket>) -> Self {
+        let mut exchange = Self::new(config);
+        exchange.source = Some(source);
+        exchange
+    }
+
+    /// Get configuration
+    pub fn config(&self) -> &PaperConfig {
+        &self.config
+    }
+
+    /// Current virtual cash balance, including cash reserved for resting orders
+    pub fn balance(&self) -> f64 {
+        self.state.lock().unwrap().balance
+    }
+
+    /// Cash not reserved by resting buy orders
+    pub fn available_balance(&self) -> f64 {
+        let state = self.state.lock().unwrap();
+        state.balance - state.reserved()
+    }
+
+    /// Cash plus positions marked at their last known price
+    pub fn equity(&self) -> f64 {
+        let state = self.state.lock().unwrap();
+        let marked: f64 = state
+            .holdings
+            .iter()
+            .map(|(token_id, h)| h.size * state.marks.get(token_id).copied().unwrap_or(h.entry_price))
+            .sum();
+        state.balance + marked
+    }
+
+    /// Open positions, marked at the last known price
+    pub fn positions(&self) -> Vec<Position> {
+        let state = self.state.lock().unwrap();
+        state
+            .holdings
+            .iter()
+            .map(|(token_id, h)| Position {
+                token_id: token_id.clone(),
+                token: Token {
+                    address: token_id.clone(),
+                    symbol: token_id.clone(),
+                    name: token_id.clone(),
+                    decimals: 6,
+                    chain: self.config.wallet.chain,
+                    logo_url: None,
+                },
+                size: h.size,
+                entry_price: h.entry_price,
+                current_price: state.marks.get(token_id).copied().unwrap_or(h.entry_price),
+                wallet: self.config.wallet.clone(),
+                opened_at: h.opened_at,
+            })
+            .collect()
+    }
+
+    /// Supply a recorded book snapshot and match resting orders against it
+    ///
+    /// Fills use up the snapshot's liquidity until the next one arrives.
+    pub fn set_order_book(&self, mut book: OrderBook) {
+        let mut state = self.state.lock().unwrap();
+        self.match_resting(&mut state, &mut book);
+        state.books.insert(book.asset_id.clone(), book);
+    }
+
+    /// Fetch the latest book from the live source and match resting orders
+    pub async fn refresh(&self, token_id: &str) -> Result<()> {
+        let source = self
+            .source
+            .as_ref()
+            .ok_or_else(|| Error::Config("Paper exchange has no live source".to_string()))?;
+        let book = source.get_order_book(token_id).await?;
+        self.set_order_book(book);
+        Ok(())
+    }
+
+    fn match_resting(&self, state: &mut PaperState, book: &mut OrderBook) {
+        let resting = std::mem::take(&mut state.resting);
+
+        for mut r in resting {
+            if r.order.token_id != book.asset_id {
+                state.resting.push(r);
+                continue;
+            }
+            let levels = match r.order.side {
+                OrderSide::Buy => &mut book.asks,
+                OrderSide::Sell => &mut book.bids,
+            };
+            let fills = take_liquidity(levels, r.order.side, r.remaining, Some(r.order.price));
+            let order_id = r.order.id.clone().unwrap_or_default();
+            for (price, size) in fills {
+                r.remaining -= size;
+                let fee = self.config.fees.expected_cost(size, price, Liquidity::Maker).total_fees();
+                state.apply_fill(&order_id, &r.order.token_id, r.order.side, size, price, fee);
+            }
+            if r.remaining > f64::EPSILON {
+                r.order.status = OrderStatus::PartiallyFilled;
+                state.resting.push(r);
+            }
+        }
+    }
+
+    async fn current_book(&self, token_id: &str) -> Result<OrderBook> {
+        if let Some(source) = &self.source {
+            let book = source.get_order_book(token_id).await?;
+            self.state.lock().unwrap().books.insert(token_id.to_string(), book.clone());
+            return Ok(book);
+        }
+        self.state
+            .lock()
+            .unwrap()
+            .books
+            .get(token_id)
+            .cloned()
+            .ok_or_else(|| Error::MarketNotFound(token_id.to_string()))
+    }
+}
+
+/// Consume book levels for an order, returning `(price, size)` fills
+fn take_liquidity(
+    levels: &mut Vec<OrderBookEntry>,
+    side: OrderSide,
+    mut size: f64,
+    limit: Option<f64>,
+) -> Vec<(f64, f64)> {
+    match side {
+        OrderSide::Buy => levels.sort_by(|a, b| a.price.total_cmp(&b.price)),
+        OrderSide::Sell => levels.sort_by(|a, b| b.price.total_cmp(&a.price)),
+    }
+
+    let mut fills = Vec::new();
+    for level in levels.iter_mut() {
+        if size <= f64::EPSILON {
+            break;
+        }
+        let crosses = match (side, limit) {
+            (_, None) => true,
+            (OrderSide::Buy, Some(limit)) => level.price <= limit,
+            (OrderSide::Sell, Some(limit)) => level.price >= limit,
+        };
+        if !crosses {
+            break;
+        }
+        let take = size.min(level.size);
+        if take > 0.0 {
+            fills.push((level.price, take));
+            level.size -= take;
+            size -= take;
+        }
+    }
+    levels.retain(|l| l.size > f64::EPSILON);
+    fills
+}
+
+/// Remove `(price, size)` fills from the levels they were taken from
+fn consume_liquidity(levels: &mut Vec<OrderBookEntry>, fills: &[(f64, f64)]) {
+    for &(price, size) in fills {
+        if let Some(level) = levels.iter_mut().find(|l| l.price == price) {
+            level.size -= size;
+        }
+    }
+    levels.retain(|l| l.size > f64::EPSILON);
+}
+
+#[async_trait]
+impl Exchange for PaperExchange {
+    fn venue(&self) -> &'static str {
+        "paper"
+    }
+
+    fn fee_schedule(&self) -> FeeSchedule {
+        self.config.fees.clone()
+    }
+
+    async fn get_order_book(&self, token_id: &str) -> Result<OrderBook> {
+        self.current_book(token_id).await
+    }
+
+    async fn place_order(&self, order: &OrderRequest) -> Result<TradeResult> {
+        if order.size <= 0.0 {
+            return Err(Error::OrderRejected("Order size must be positive".to_string()));
+        }
+        order.validate().map_err(Error::OrderRejected)?;
+        if order.order_type.is_stop() {
+            return Err(Error::OrderRejected(
+                "Paper exchange does not simulate stop orders".to_string(),
+            ));
+        }
+        if order.reduce_only && order.side == OrderSide::Buy {
+            return Err(Error::OrderRejected(
+                "Reduce-only buy would open a position".to_string(),
+            ));
+        }
+
+        let book = self.current_book(&order.token_id).await?;
+        let mut state = self.state.lock().unwrap();
+
+        let limit = match order.order_type {
+            OrderType::Market => None,
+            _ => Some(order.price),
+        };
+
+        match order.side {
+            OrderSide::Buy => {
+                let required = self
+                    .config
+                    .fees
+                    .expected_cost(order.size, order.price, Liquidity::Taker)
+                    .cash_flow(OrderSide::Buy);
+                let available = state.balance - state.reserved();
+                if limit.is_some() && required > available {
+                    return Err(Error::InsufficientBalance {
+                        required: format!("{:.2}", required),
+                        available: format!("{:.2}", available),
+                    });
+                }
+            }
+            OrderSide::Sell => {
+                let available = state.available_size(&order.token_id);
+                if order.size > available + f64::EPSILON {
+                    return Err(Error::InsufficientBalance {
+                        required: format!("{}", order.size),
+                        available: format!("{}", available),
+                    });
+                }
+            }
+        }
+
+        let mut levels = match order.side {
+            OrderSide::Buy => book.asks.clone(),
+            OrderSide::Sell => book.bids.clone(),
+        };
+        
+        if order.post_only {
+            let crosses = match order.side {
+                OrderSide::Buy => levels.iter().any(|l| l.price <= order.price),
+                OrderSide::Sell => levels.iter().any(|l| l.price >= order.price),
+            };
+            if crosses {
+                return Err(Error::OrderRejected("Post-only order would cross".to_string()));
+            }
+        }
+        
+        let mut fills = take_liquidity(&mut levels, order.side, order.size, limit);
+
+        // Market buys are bounded by cash rather than a limit price
+        if order.side == OrderSide::Buy && limit.is_none() {
+            let mut budget = state.balance - state.reserved();
+            let fee_mult = 1.0 + self.config.fees.taker_bps / 10_000.0;
+            let gas = self.config.fees.gas_per_fill;
+            fills.retain_mut(|(price, size)| {
+                let affordable = ((budget - gas) / (*price * fee_mult)).max(0.0);
+                *size = size.min(affordable);
+                budget -= *size * *price * fee_mult + gas;
+                *size > f64::EPSILON
+            });
+        }
+
+        let fillable: f64 = fills.iter().map(|(_, size)| size).sum();
+        if order.time_in_force == TimeInForce::Fok && order.size - fillable > f64::EPSILON {
+            return Err(Error::OrderRejected(format!(
+                "FOK order for {} could only fill {}",
+                order.size, fillable
+            )));
+        }
+        
+        // Later orders see the liquidity this one took
+        if let Some(book) = state.books.get_mut(&order.token_id) {
+            let levels = match order.side {
+                OrderSide::Buy => &mut book.asks,
+                OrderSide::Sell => &mut book.bids,
+            };
+            consume_liquidity(levels, &fills);
+        }
+
+        let order_id = state.next_id("order");
+        let mut filled = 0.0;
+        let mut notional = 0.0;
+        for &(price, size) in &fills {
+            let fee = self.config.fees.expected_cost(size, price, Liquidity::Taker).total_fees();
+            state.apply_fill(&order_id, &order.token_id, order.side, size, price, fee);
+            filled += size;
+            notional += price * size;
+        }
+
+        let remaining = order.size - filled;
+        let status = if remaining <= f64::EPSILON {
+            OrderStatus::Filled
+        } else if limit.is_some() && order.time_in_force == TimeInForce::Gtc {
+            let status = if filled > 0.0 {
+                OrderStatus::PartiallyFilled
+            } else {
+                OrderStatus::Open
+            };
+            state.resting.push(RestingOrder {
+                order: Order {
+                    id: Some(order_id.clone()),
+                    side: order.side,
+                    order_type: order.order_type,
+                    token_id: order.token_id.clone(),
+                    size: order.size,
+                    price: order.price,
+                    time_in_force: order.time_in_force,
+                    post_only: order.post_only,
+                    reduce_only: order.reduce_only,
+                    stop_price: None,
+                    wallet: self.config.wallet.clone(),
+                    created_at: Utc::now(),
+                    status,
+                },
+                remaining,
+            });
+            status
+        } else if filled > 0.0 {
+            OrderStatus::PartiallyFilled
+        } else {
+            OrderStatus::Cancelled
+        };
+
+        Ok(TradeResult {
+            order_id,
+            status: format!("{:?}", status).to_lowercase(),
+            filled_size: filled,
+            avg_price: if filled > 0.0 { notional / filled } else { 0.0 },
+            transaction_hash: None,
+        })
+    }
+
+    async fn cancel_order(&self, order_id: &str) -> Result<bool> {
+        let mut state = self.state.lock().unwrap();
+        let before = state.resting.len();
+        state.resting.retain(|r| r.order.id.as_deref() != Some(order_id));
+        Ok(state.resting.len() < before)
+    }
+
+    async fn get_open_orders(&self, wallet: &Wallet) -> Result<Vec<Order>> {
+        if wallet.address != self.config.wallet.address {
+            return Ok(Vec::new());
+        }
+        Ok(self
+            .state
+            .lock()
+            .unwrap()
+            .resting
+            .iter()
+            .map(|r| r.order.clone())
+            .collect())
+    }
+
+    async fn get_fills(&self, wallet: &Wallet, limit: usize) -> Result<Vec<Fill>> {
+        if wallet.address != self.config.wallet.address {
+            return Ok(Vec::new());
+        }
+        Ok(self.state.lock().unwrap().fills.iter().rev().take(limit).cloned().collect())
+    }
+}
+
+#[async_trait]
+impl PredictionMarket for PaperExchange {
+    async fn get_active_markets(&self) -> Result<Vec<Market>> {
+        match &self.source {
+            Some(source) => source.get_active_markets().await,
+            None => Ok(Vec::new()),
+        }
+    }
+
+    async fn get_market(&self, market_id: &str) -> Result<Market> {
+        match &self.source {
+            Some(source) => source.get_market(market_id).await,
+            None => Err(Error::MarketNotFound(market_id.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Chain;
+
+    fn exchange() -> PaperExchange {
+        let wallet = Wallet::new("0xpaper", Chain::Polygon);
+        let paper = PaperExchange::new(PaperConfig::new(wallet, 1000.0));
+        paper.set_order_book(OrderBook {
+            market: "m1".to_string(),
+            asset_id: "yes".to_string(),
+            bids: vec![
+                OrderBookEntry { price: 0.48, size: 100.0 },
+                OrderBookEntry { price: 0.47, size: 100.0 },
+            ],
+            asks: vec![
+                OrderBookEntry { price: 0.50, size: 100.0 },
+                OrderBookEntry { price: 0.52, size: 100.0 },
+            ],
+            timestamp: Utc::now(),
+        });
+        paper
+    }
+
+    #[tokio::test]
+    async fn test_limit_buy_walks_book() {
+        let paper = exchange();
+        let result = paper.place_order(&OrderRequest::buy("yes", 150.0, 0.52)).await.unwrap();
+
+        assert_eq!(result.filled_size, 150.0);
+        assert!((result.avg_price - 0.50666).abs() < 1e-4);
+        assert!((paper.balance() - (1000.0 - 76.0)).abs() < 1e-9);
+        assert_eq!(paper.positions()[0].size, 150.0);
+    }
+
+    #[tokio::test]
+    async fn test_limit_remainder_rests_until_crossed() {
+        let paper = exchange();
+        let result = paper.place_order(&OrderRequest::buy("yes", 50.0, 0.45)).await.unwrap();
+        assert_eq!(result.filled_size, 0.0);
+
+        let wallet = paper.config().wallet.clone();
+        assert_eq!(paper.get_open_orders(&wallet).await.unwrap().len(), 1);
+
+        paper.set_order_book(OrderBook {
+            market: "m1".to_string(),
+            asset_id: "yes".to_string(),
+            bids: vec![],
+            asks: vec![OrderBookEntry { price: 0.44, size: 100.0 }],
+            timestamp: Utc::now(),
+        });
+
+        assert!(paper.get_open_orders(&wallet).await.unwrap().is_empty());
+        assert_eq!(paper.get_fills(&wallet, 10).await.unwrap()[0].price, 0.44);
+    }
+
+    #[tokio::test]
+    async fn test_fills_deplete_book() {
+        let paper = exchange();
+        let ioc = OrderRequest::buy("yes", 100.0, 0.50).with_time_in_force(TimeInForce::Ioc);
+        assert_eq!(paper.place_order(&ioc).await.unwrap().filled_size, 100.0);
+        assert_eq!(paper.place_order(&ioc).await.unwrap().filled_size, 0.0);
+        assert_eq!(paper.get_order_book("yes").await.unwrap().asks[0].price, 0.52);
+
+        // Resting orders filled by a new snapshot use up its liquidity too
+        paper.place_order(&OrderRequest::sell("yes", 60.0, 0.55)).await.unwrap();
+        paper.set_order_book(OrderBook {
+            market: "m1".to_string(),
+            asset_id: "yes".to_string(),
+            bids: vec![OrderBookEntry { price: 0.56, size: 100.0 }],
+            asks: vec![],
+            timestamp: Utc::now(),
+        });
+        let book = paper.get_order_book("yes").await.unwrap();
+        assert!((book.bids[0].size - 40.0).abs() < 1e-9);
+        let market_sell = OrderRequest::sell("yes", 40.0, 0.5).market();
+        assert_eq!(paper.place_order(&market_sell).await.unwrap().filled_size, 40.0);
+        assert!(paper.get_order_book("yes").await.unwrap().bids.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_time_in_force() {
+        let paper = exchange();
+        let fok = OrderRequest::buy("yes", 150.0, 0.50).with_time_in_force(TimeInForce::Fok);
+        assert!(paper.place_order(&fok).await.is_err());
+
+        let ioc = OrderRequest::buy("yes", 150.0, 0.50).with_time_in_force(TimeInForce::Ioc);
+        let result = paper.place_order(&ioc).await.unwrap();
+        assert_eq!(result.filled_size, 100.0);
+
+        let wallet = paper.config().wallet.clone();
+        assert!(paper.get_open_orders(&wallet).await.unwrap().is_empty());
+        assert!(paper.place_order(&OrderRequest::buy("yes", 10.0, 0.52).post_only()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rejects_oversized_orders() {
+        let paper = exchange();
+        assert!(paper.place_order(&OrderRequest::buy("yes", 5000.0, 0.5)).await.is_err());
+        assert!(paper.place_order(&OrderRequest::sell("yes", 10.0, 0.4)).await.is_err());
+    }
+}
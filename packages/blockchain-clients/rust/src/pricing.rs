@@ -0,0 +1,154 @@
+//! USD valuation
+//!
+//! Portfolio code marks tokens and positions to USD through the [`Pricer`]
+//! trait, so the price source (an API provider, on-chain feeds, or a fixed
+//! table in tests) can be swapped without touching reporting code.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::RwLock;
+
+use crate::error::{Error, Result};
+use crate::types::{Chain, Position, Token};
+
+/// Source of USD prices for tokens
+#[async_trait]
+pub trait Pricer: Send + Sync {
+    /// Current USD price of one whole token
+    async fn price_usd(&self, token: &Token) -> Result<f64>;
+
+    /// USD price of one whole token at a past time
+    async fn price_usd_at(&self, token: &Token, at: DateTime<Utc>) -> Result<f64>;
+}
+
+type PriceHistory = BTreeMap<DateTime<Utc>, f64>;
+
+/// In-memory price table with history
+///
+/// Lookups return the latest mark at or before the requested time. Useful in
+/// tests and for replaying recorded prices.
+#[derive(Debug, Default)]
+pub struct StaticPricer {
+    marks: RwLock<HashMap<(Chain, String), PriceHistory>>,
+}
+
+impl StaticPricer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a USD mark for a token at a time
+    pub fn set_price_at(&self, token: &Token, at: DateTime<Utc>, price: f64) {
+        self.marks
+            .write()
+            .unwrap()
+            .entry(key(token))
+            .or_default()
+            .insert(at, price);
+    }
+
+    /// Record a USD mark for a token now
+    pub fn set_price(&self, token: &Token, price: f64) {
+        self.set_price_at(token, Utc::now(), price);
+    }
+}
+
+fn key(token: &Token) -> (Chain, String) {
+    (token.chain, token.address.to_lowercase())
+}
+
+#[async_trait]
+impl Pricer for StaticPricer {
+    async fn price_usd(&self, token: &Token) -> Result<f64> {
+        self.marks
+            .read()
+            .unwrap()
+            .get(&key(token))
+            .and_then(|history| history.values().next_back().copied())
+            .ok_or_else(|| Error::PriceUnavailable(token.symbol.clone()))
+    }
+
+    async fn price_usd_at(&self, token: &Token, at: DateTime<Utc>) -> Result<f64> {
+        self.marks
+            .read()
+            .unwrap()
+            .get(&key(token))
+            .and_then(|history| history.range(..=at).next_back().map(|(_, p)| *p))
+            .ok_or_else(|| Error::PriceUnavailable(format!("{} at {}", token.symbol, at)))
+    }
+}
+
+impl Token {
+    /// USD value of an amount of this token
+    pub async fn value_usd(&self, amount: f64, pricer: &dyn Pricer) -> Result<f64> {
+        Ok(amount * pricer.price_usd(self).await?)
+    }
+
+    /// USD value of an amount of this token at a past time
+    pub async fn value_usd_at(&self, amount: f64, pricer: &dyn Pricer, at: DateTime<Utc>) -> Result<f64> {
+        Ok(amount * pricer.price_usd_at(self, at).await?)
+    }
+}
+
+impl Position {
+    /// Current USD value of the position
+    pub async fn value_usd(&self, pricer: &dyn Pricer) -> Result<f64> {
+        self.token.value_usd(self.size, pricer).await
+    }
+
+    /// USD value of the position at a past time, at its current size
+    pub async fn value_usd_at(&self, pricer: &dyn Pricer, at: DateTime<Utc>) -> Result<f64> {
+        self.token.value_usd_at(self.size, pricer, at).await
+    }
+
+    /// Unrealized PnL in USD, against the token's USD mark when the position
+    /// was opened
+    pub async fn unrealized_pnl_usd(&self, pricer: &dyn Pricer) -> Result<f64> {
+        let now = self.value_usd(pricer).await?;
+        let opened = self.value_usd_at(pricer, self.opened_at).await?;
+        Ok(now - opened)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Wallet;
+
+    fn weth() -> Token {
+        Token {
+            address: "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2".to_string(),
+            symbol: "WETH".to_string(),
+            name: "Wrapped Ether".to_string(),
+            decimals: 18,
+            chain: Chain::Ethereum,
+            logo_url: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_position_usd_marks() {
+        let pricer = StaticPricer::new();
+        let opened_at = Utc::now() - chrono::Duration::days(1);
+        pricer.set_price_at(&weth(), opened_at, 3000.0);
+        pricer.set_price(&weth(), 3300.0);
+
+        let position = Position {
+            token_id: weth().address,
+            token: weth(),
+            size: 2.0,
+            entry_price: 1.0,
+            current_price: 1.1,
+            wallet: Wallet::new("0xabc", Chain::Ethereum),
+            opened_at,
+        };
+
+        assert_eq!(position.value_usd(&pricer).await.unwrap(), 6600.0);
+        assert_eq!(position.unrealized_pnl_usd(&pricer).await.unwrap(), 600.0);
+        assert!(pricer
+            .price_usd_at(&weth(), opened_at - chrono::Duration::hours(1))
+            .await
+            .is_err());
+    }
+}
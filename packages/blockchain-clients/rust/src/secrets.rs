@@ -0,0 +1,261 @@
+//! Secrets loading
+//!
+//! Resolves API keys, secrets and mnemonics from a list of sources tried in
+//! priority order: the OS keyring, environment variables, and encrypted
+//! keystore files. Application code asks for a secret by name instead of
+//! calling `std::env::var` directly.
+//!
+//! The `secrets` feature enables the `keyring` and `keystore` features, so
+//! every source is always available. [`SecretsLoader::standard`] sets all
+//! three up in that order. A keyring that cannot be reached is skipped with a
+//! warning rather than failing the lookup.
+
+use std::path::PathBuf;
+use zeroize::Zeroizing;
+
+use crate::error::{Error, Result};
+
+/// Secret value, zeroized on drop
+#[derive(Clone)]
+pub struct Secret(Zeroizing<String>);
+
+impl Secret {
+    pub fn new(value: impl Into<String>) -> Self {
+        Self(Zeroizing::new(value.into()))
+    }
+
+    /// Borrow the secret value
+    pub fn expose_secret(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Debug for Secret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Secret(<redacted>)")
+    }
+}
+
+/// Where a secret can be loaded from
+#[derive(Clone)]
+pub enum SecretSource {
+    /// OS keyring entry with the secret name as the user
+    Keyring { service: String },
+    /// Environment variable named `PREFIX` + the upper-cased secret name
+    Env { prefix: String },
+    /// Keystore file named after the secret in a directory, encrypted with a
+    /// shared password
+    EncryptedFile { dir: PathBuf, password: Secret },
+}
+
+impl std::fmt::Debug for SecretSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SecretSource::Keyring { service } => write!(f, "Keyring({})", service),
+            SecretSource::Env { prefix } => write!(f, "Env({}*)", prefix),
+            SecretSource::EncryptedFile { dir, .. } => write!(f, "EncryptedFile({})", dir.display()),
+        }
+    }
+}
+
+/// Resolves named secrets from sources in priority order
+#[derive(Clone, Debug, Default)]
+pub struct SecretsLoader {
+    sources: Vec<SecretSource>,
+}
+
+impl SecretsLoader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loader that only reads unprefixed environment variables
+    pub fn from_env() -> Self {
+        Self::new().with_env("")
+    }
+
+    /// Loader trying the OS keyring under `service`, then environment
+    /// variables starting with `env_prefix`, then keystore files in `dir`
+    pub fn standard(
+        service: impl Into<String>,
+        env_prefix: impl Into<String>,
+        dir: impl Into<PathBuf>,
+        password: Secret,
+    ) -> Self {
+        Self::new()
+            .with_keyring(service)
+            .with_env(env_prefix)
+            .with_encrypted_dir(dir, password)
+    }
+
+    /// Look secrets up in the OS keyring under `service`
+    pub fn with_keyring(mut self, service: impl Into<String>) -> Self {
+        self.sources.push(SecretSource::Keyring { service: service.into() });
+        self
+    }
+
+    /// Look secrets up in environment variables, e.g. prefix `BOT_` and name
+    /// `polymarket_api_key` reads `BOT_POLYMARKET_API_KEY`
+    pub fn with_env(mut self, prefix: impl Into<String>) -> Self {
+        self.sources.push(SecretSource::Env { prefix: prefix.into() });
+        self
+    }
+
+    /// Look secrets up in keystore files under `dir`
+    pub fn with_encrypted_dir(mut self, dir: impl Into<PathBuf>, password: Secret) -> Self {
+        self.sources.push(SecretSource::EncryptedFile {
+            dir: dir.into(),
+            password,
+        });
+        self
+    }
+
+    /// Configured sources, highest priority first
+    pub fn sources(&self) -> &[SecretSource] {
+        &self.sources
+    }
+
+    /// Resolve a secret from the first source that has it
+    pub fn get(&self, name: &str) -> Result<Option<Secret>> {
+        for source in &self.sources {
+            if let Some(secret) = load(source, name)? {
+                tracing::debug!(name, ?source, "Resolved secret");
+                return Ok(Some(secret));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Resolve a secret, failing if no source has it
+    pub fn require(&self, name: &str) -> Result<Secret> {
+        self.get(name)?
+            .ok_or_else(|| Error::Config(format!("Secret not found: {}", name)))
+    }
+}
+
+fn env_var_name(prefix: &str, name: &str) -> String {
+    format!("{}{}", prefix, name).to_uppercase().replace(['-', '.'], "_")
+}
+
+fn load(source: &SecretSource, name: &str) -> Result<Option<Secret>> {
+    match source {
+        // An unavailable keyring, e.g. on a headless server, must not hide
+        // the sources after it
+        SecretSource::Keyring { service } => {
+            match keyring::Entry::new(service, name).and_then(|entry| entry.get_password()) {
+                Ok(value) => Ok(Some(Secret::new(value))),
+                Err(keyring::Error::NoEntry) => Ok(None),
+                Err(e) => {
+                    tracing::warn!(name, service, "Keyring lookup failed, trying the next source: {}", e);
+                    Ok(None)
+                }
+            }
+        }
+        SecretSource::Env { prefix } => match std::env::var(env_var_name(prefix, name)) {
+            Ok(value) if !value.is_empty() => Ok(Some(Secret::new(value))),
+            _ => Ok(None),
+        },
+        SecretSource::EncryptedFile { dir, password } => {
+            let path = dir.join(name);
+            if !path.exists() {
+                return Ok(None);
+            }
+            let bytes = crate::keystore::decrypt_file(&path, password.expose_secret())?;
+            let value = String::from_utf8(bytes.expose_secret().to_vec())
+                .map_err(|_| Error::Config(format!("Secret {} is not valid UTF-8", name)))?;
+            Ok(Some(Secret::new(value)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_env_priority() {
+        std::env::set_var("SECRETS_TEST_A_API_KEY", "from-a");
+        std::env::set_var("SECRETS_TEST_B_API_KEY", "from-b");
+        std::env::set_var("SECRETS_TEST_B_OTHER", "only-b");
+
+        let loader = SecretsLoader::new()
+            .with_env("SECRETS_TEST_A_")
+            .with_env("SECRETS_TEST_B_");
+
+        assert_eq!(loader.require("api_key").unwrap().expose_secret(), "from-a");
+        assert_eq!(loader.require("other").unwrap().expose_secret(), "only-b");
+        assert!(loader.get("missing").unwrap().is_none());
+        assert!(loader.require("missing").is_err());
+    }
+
+    #[test]
+    fn test_standard_order() {
+        let loader = SecretsLoader::standard("trading-bot", "BOT_", "/etc/trading/secrets", Secret::new("pw"));
+        let sources: Vec<String> = loader.sources().iter().map(|s| format!("{:?}", s)).collect();
+        assert_eq!(
+            sources,
+            ["Keyring(trading-bot)", "Env(BOT_*)", "EncryptedFile(/etc/trading/secrets)"]
+        );
+    }
+
+    /// Keyring backend where every lookup fails
+    struct BrokenKeyring;
+
+    impl keyring::credential::CredentialApi for BrokenKeyring {
+        fn set_secret(&self, _: &[u8]) -> keyring::Result<()> {
+            Err(keyring::Error::NoStorageAccess("locked".into()))
+        }
+
+        fn get_secret(&self) -> keyring::Result<Vec<u8>> {
+            Err(keyring::Error::NoStorageAccess("locked".into()))
+        }
+
+        fn delete_credential(&self) -> keyring::Result<()> {
+            Err(keyring::Error::NoStorageAccess("locked".into()))
+        }
+
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+    }
+
+    impl keyring::credential::CredentialBuilderApi for BrokenKeyring {
+        fn build(&self, _: Option<&str>, _: &str, _: &str) -> keyring::Result<Box<keyring::credential::Credential>> {
+            Ok(Box::new(BrokenKeyring))
+        }
+
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+    }
+
+    #[test]
+    fn test_keyring_failure_falls_through() {
+        keyring::set_default_credential_builder(Box::new(BrokenKeyring));
+        std::env::set_var("SECRETS_TEST_KEYRING_API_KEY", "from-env");
+
+        let loader = SecretsLoader::new()
+            .with_keyring("trading-bot")
+            .with_env("SECRETS_TEST_KEYRING_");
+        assert_eq!(loader.require("api_key").unwrap().expose_secret(), "from-env");
+        assert!(loader.get("missing").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_encrypted_file() {
+        let dir = std::env::temp_dir().join(format!("secrets-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        eth_keystore::encrypt_key(&dir, &mut rand::thread_rng(), b"from-file", "hunter2", Some("api_key")).unwrap();
+
+        let loader = SecretsLoader::new().with_encrypted_dir(&dir, Secret::new("hunter2"));
+        let wrong_password = SecretsLoader::new().with_encrypted_dir(&dir, Secret::new("wrong"));
+        let found = loader.get("api_key");
+        let denied = wrong_password.get("api_key");
+        let missing = loader.get("other");
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(found.unwrap().unwrap().expose_secret(), "from-file");
+        assert!(denied.is_err());
+        assert!(missing.unwrap().is_none());
+    }
+}
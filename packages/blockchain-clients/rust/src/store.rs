@@ -0,0 +1,408 @@
+//! Persistent order and fill store
+//!
+//! Records every order, amendment, fill and cancellation in sqlite so that
+//! long-running bots keep a durable history for reconciliation and restarts.
+//! Orders and fills are stored as JSON alongside indexed columns for the
+//! common queries.
+
+use chrono::{NaiveDate, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::error::{Error, Result};
+use crate::types::{Fill, Order, OrderStatus};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS orders (
+    id          TEXT PRIMARY KEY,
+    token_id    TEXT NOT NULL,
+    status      TEXT NOT NULL,
+    created_at  TEXT NOT NULL,
+    updated_at  TEXT NOT NULL,
+    data        TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS orders_token_id ON orders (token_id);
+CREATE INDEX IF NOT EXISTS orders_status ON orders (status);
+
+CREATE TABLE IF NOT EXISTS order_events (
+    seq         INTEGER PRIMARY KEY AUTOINCREMENT,
+    order_id    TEXT NOT NULL,
+    kind        TEXT NOT NULL,
+    recorded_at TEXT NOT NULL,
+    data        TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS order_events_order_id ON order_events (order_id);
+
+CREATE TABLE IF NOT EXISTS fills (
+    id          TEXT PRIMARY KEY,
+    order_id    TEXT NOT NULL,
+    day         TEXT NOT NULL,
+    timestamp   TEXT NOT NULL,
+    data        TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS fills_order_id ON fills (order_id);
+CREATE INDEX IF NOT EXISTS fills_day ON fills (day);
+";
+
+/// Kind of entry in an order's event history
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OrderEventKind {
+    Placed,
+    Amended,
+    Filled,
+    Cancelled,
+}
+
+impl OrderEventKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            OrderEventKind::Placed => "placed",
+            OrderEventKind::Amended => "amended",
+            OrderEventKind::Filled => "filled",
+            OrderEventKind::Cancelled => "cancelled",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "placed" => Some(OrderEventKind::Placed),
+            "amended" => Some(OrderEventKind::Amended),
+            "filled" => Some(OrderEventKind::Filled),
+            "cancelled" => Some(OrderEventKind::Cancelled),
+            _ => None,
+        }
+    }
+}
+
+/// Entry in an order's event history
+#[derive(Clone, Debug)]
+pub struct OrderEvent {
+    pub order_id: String,
+    pub kind: OrderEventKind,
+    pub recorded_at: chrono::DateTime<Utc>,
+    /// JSON snapshot of the order (or fill) at the time of the event
+    pub data: String,
+}
+
+/// sqlite-backed order and fill history
+#[derive(Debug)]
+pub struct OrderStore {
+    conn: Mutex<Connection>,
+}
+
+impl OrderStore {
+    /// Open (or create) a store at the given path
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::init(Connection::open(path)?)
+    }
+
+    /// Open a throwaway in-memory store
+    pub fn open_in_memory() -> Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Record a newly placed order
+    ///
+    /// The order must have an ID; placing the same ID twice replaces the
+    /// stored snapshot and is logged as a second `Placed` event.
+    pub fn record_order(&self, order: &Order) -> Result<()> {
+        let id = order_id(order)?;
+        let data = serde_json::to_string(order)?;
+        let now = Utc::now().to_rfc3339();
+
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT OR REPLACE INTO orders (id, token_id, status, created_at, updated_at, data)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![id, order.token_id, status_str(order.status), order.created_at.to_rfc3339(), now, data],
+        )?;
+        insert_event(&tx, id, OrderEventKind::Placed, &now, &data)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Record an amendment to an existing order's size and/or price
+    pub fn record_amendment(&self, order_id: &str, size: Option<f64>, price: Option<f64>) -> Result<Order> {
+        self.update_order(order_id, OrderEventKind::Amended, |order| {
+            if let Some(size) = size {
+                order.size = size;
+            }
+            if let Some(price) = price {
+                order.price = price;
+            }
+        })
+    }
+
+    /// Record a cancellation
+    pub fn record_cancel(&self, order_id: &str) -> Result<Order> {
+        self.update_order(order_id, OrderEventKind::Cancelled, |order| {
+            order.status = OrderStatus::Cancelled;
+        })
+    }
+
+    /// Record a fill, moving its order to filled or partially filled
+    ///
+    /// Fills for orders the store has not seen are still recorded, as are
+    /// late fills for orders already filled, cancelled or failed; those
+    /// keep their status.
+    pub fn record_fill(&self, fill: &Fill) -> Result<()> {
+        let data = serde_json::to_string(fill)?;
+        let now = Utc::now().to_rfc3339();
+
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT OR REPLACE INTO fills (id, order_id, day, timestamp, data) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                fill.id,
+                fill.order_id,
+                fill.timestamp.date_naive().to_string(),
+                fill.timestamp.to_rfc3339(),
+                data
+            ],
+        )?;
+
+        if let Some(mut order) = load_order(&tx, &fill.order_id)?.filter(|o| !o.status.is_terminal()) {
+            let filled: f64 = tx
+                .prepare("SELECT data FROM fills WHERE order_id = ?1")?
+                .query_map(params![fill.order_id], |row| row.get::<_, String>(0))?
+                .filter_map(|d| d.ok())
+                .filter_map(|d| serde_json::from_str::<Fill>(&d).ok())
+                .map(|f| f.size)
+                .sum();
+            order.status = if filled + f64::EPSILON >= order.size {
+                OrderStatus::Filled
+            } else {
+                OrderStatus::PartiallyFilled
+            };
+            save_order(&tx, &order, &now)?;
+        }
+        insert_event(&tx, &fill.order_id, OrderEventKind::Filled, &now, &data)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Get an order by ID
+    pub fn order(&self, order_id: &str) -> Result<Option<Order>> {
+        let conn = self.conn.lock().unwrap();
+        load_order(&conn, order_id)
+    }
+
+    /// Orders for a market (token), oldest first
+    pub fn orders_by_market(&self, token_id: &str) -> Result<Vec<Order>> {
+        self.query_orders("SELECT data FROM orders WHERE token_id = ?1 ORDER BY created_at", params![token_id])
+    }
+
+    /// Orders still working on the venue
+    pub fn open_orders(&self) -> Result<Vec<Order>> {
+        self.query_orders(
+            "SELECT data FROM orders WHERE status IN ('pending', 'open', 'partially_filled') ORDER BY created_at",
+            params![],
+        )
+    }
+
+    /// Fills executed on a given UTC day
+    pub fn fills_by_day(&self, day: NaiveDate) -> Result<Vec<Fill>> {
+        self.query_fills("SELECT data FROM fills WHERE day = ?1 ORDER BY timestamp", params![day.to_string()])
+    }
+
+    /// Fills for an order
+    pub fn fills_for_order(&self, order_id: &str) -> Result<Vec<Fill>> {
+        self.query_fills("SELECT data FROM fills WHERE order_id = ?1 ORDER BY timestamp", params![order_id])
+    }
+
+    /// Full event history of an order, oldest first
+    pub fn history(&self, order_id: &str) -> Result<Vec<OrderEvent>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT order_id, kind, recorded_at, data FROM order_events WHERE order_id = ?1 ORDER BY seq",
+        )?;
+        let rows = stmt.query_map(params![order_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?, row.get::<_, String>(3)?))
+        })?;
+
+        let mut events = Vec::new();
+        for row in rows {
+            let (order_id, kind, recorded_at, data) = row?;
+            events.push(OrderEvent {
+                order_id,
+                kind: OrderEventKind::parse(&kind)
+                    .ok_or_else(|| Error::msg(format!("Unknown order event kind: {}", kind)))?,
+                recorded_at: parse_time(&recorded_at)?,
+                data,
+            });
+        }
+        Ok(events)
+    }
+
+    fn update_order(&self, order_id: &str, kind: OrderEventKind, f: impl FnOnce(&mut Order)) -> Result<Order> {
+        let now = Utc::now().to_rfc3339();
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let mut order = load_order(&tx, order_id)?
+            .ok_or_else(|| Error::msg(format!("Unknown order: {}", order_id)))?;
+        f(&mut order);
+        let data = save_order(&tx, &order, &now)?;
+        insert_event(&tx, order_id, kind, &now, &data)?;
+        tx.commit()?;
+        Ok(order)
+    }
+
+    fn query_orders(&self, sql: &str, params: impl rusqlite::Params) -> Result<Vec<Order>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(sql)?;
+        let rows = stmt.query_map(params, |row| row.get::<_, String>(0))?;
+        let mut orders = Vec::new();
+        for data in rows {
+            orders.push(serde_json::from_str(&data?)?);
+        }
+        Ok(orders)
+    }
+
+    fn query_fills(&self, sql: &str, params: impl rusqlite::Params) -> Result<Vec<Fill>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(sql)?;
+        let rows = stmt.query_map(params, |row| row.get::<_, String>(0))?;
+        let mut fills = Vec::new();
+        for data in rows {
+            fills.push(serde_json::from_str(&data?)?);
+        }
+        Ok(fills)
+    }
+}
+
+fn order_id(order: &Order) -> Result<&str> {
+    order
+        .id
+        .as_deref()
+        .ok_or_else(|| Error::InvalidTransaction("Order has no ID".to_string()))
+}
+
+fn status_str(status: OrderStatus) -> &'static str {
+    match status {
+        OrderStatus::Pending => "pending",
+        OrderStatus::Open => "open",
+        OrderStatus::Filled => "filled",
+        OrderStatus::PartiallyFilled => "partially_filled",
+        OrderStatus::Cancelled => "cancelled",
+        OrderStatus::Failed => "failed",
+    }
+}
+
+fn parse_time(s: &str) -> Result<chrono::DateTime<Utc>> {
+    chrono::DateTime::parse_from_rfc3339(s)
+        .map(|t| t.with_timezone(&Utc))
+        .map_err(|e| Error::msg(format!("Invalid timestamp {}: {}", s, e)))
+}
+
+fn load_order(conn: &Connection, order_id: &str) -> Result<Option<Order>> {
+    let data: Option<String> = conn
+        .query_row("SELECT data FROM orders WHERE id = ?1", params![order_id], |row| row.get(0))
+        .optional()?;
+    data.map(|d| serde_json::from_str(&d).map_err(Error::from)).transpose()
+}
+
+fn save_order(conn: &Connection, order: &Order, now: &str) -> Result<String> {
+    let data = serde_json::to_string(order)?;
+    conn.execute(
+        "UPDATE orders SET status = ?2, updated_at = ?3, data = ?4 WHERE id = ?1",
+        params![order_id(order)?, status_str(order.status), now, data],
+    )?;
+    Ok(data)
+}
+
+fn insert_event(conn: &Connection, order_id: &str, kind: OrderEventKind, now: &str, data: &str) -> Result<()> {
+    conn.execute(
+        "INSERT INTO order_events (order_id, kind, recorded_at, data) VALUES (?1, ?2, ?3, ?4)",
+        params![order_id, kind.as_str(), now, data],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Chain, OrderSide, OrderType, TimeInForce, Wallet};
+
+    fn order(id: &str, token_id: &str) -> Order {
+        Order {
+            id: Some(id.to_string()),
+            side: OrderSide::Buy,
+            order_type: OrderType::Limit,
+            token_id: token_id.to_string(),
+            size: 10.0,
+            price: 0.5,
+            time_in_force: TimeInForce::Gtc,
+            post_only: false,
+            reduce_only: false,
+            stop_price: None,
+            wallet: Wallet::new("0xabc", Chain::Polygon),
+            created_at: Utc::now(),
+            status: OrderStatus::Open,
+        }
+    }
+
+    fn fill(id: &str, order_id: &str, size: f64) -> Fill {
+        Fill {
+            id: id.to_string(),
+            order_id: order_id.to_string(),
+            side: OrderSide::Buy,
+            size,
+            price: 0.5,
+            fee: 0.0,
+            timestamp: Utc::now(),
+            transaction_hash: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_order_lifecycle() {
+        let store = OrderStore::open_in_memory().unwrap();
+        store.record_order(&order("o1", "yes")).unwrap();
+        store.record_order(&order("o2", "no")).unwrap();
+
+        store.record_amendment("o1", None, Some(0.52)).unwrap();
+        store.record_fill(&fill("f1", "o1", 4.0)).unwrap();
+        assert_eq!(store.order("o1").unwrap().unwrap().status, OrderStatus::PartiallyFilled);
+
+        store.record_fill(&fill("f2", "o1", 6.0)).unwrap();
+        store.record_cancel("o2").unwrap();
+
+        assert!(store.open_orders().unwrap().is_empty());
+        assert_eq!(store.orders_by_market("yes").unwrap()[0].price, 0.52);
+        assert_eq!(store.fills_by_day(Utc::now().date_naive()).unwrap().len(), 2);
+
+        let kinds: Vec<_> = store.history("o1").unwrap().iter().map(|e| e.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                OrderEventKind::Placed,
+                OrderEventKind::Amended,
+                OrderEventKind::Filled,
+                OrderEventKind::Filled
+            ]
+        );
+    }
+
+    #[test]
+    fn test_late_fill_after_cancel() {
+        let store = OrderStore::open_in_memory().unwrap();
+        store.record_order(&order("o1", "yes")).unwrap();
+        store.record_fill(&fill("f1", "o1", 4.0)).unwrap();
+        store.record_cancel("o1").unwrap();
+
+        store.record_fill(&fill("f2", "o1", 6.0)).unwrap();
+        assert_eq!(store.order("o1").unwrap().unwrap().status, OrderStatus::Cancelled);
+        assert_eq!(store.fills_for_order("o1").unwrap().len(), 2);
+        assert_eq!(store.history("o1").unwrap().last().unwrap().kind, OrderEventKind::Filled);
+    }
+}
@@ -0,0 +1,87 @@
+//! Client traits
+//!
+//! Strategy and risk code should depend on these traits rather than on the
+//! concrete clients, so that mock or paper implementations can be swapped in.
+
+use async_trait::async_trait;
+
+use crate::error::Result;
+use crate::fees::FeeSchedule;
+use crate::types::{
+    Chain, Fill, Market, Order, OrderBook, OrderRequest, TradeResult, Transaction, Wallet,
+};
+
+/// Read/write access to a blockchain network
+#[async_trait]
+pub trait ChainClient: Send + Sync {
+    /// Chain this client is connected to
+    fn chain(&self) -> Chain;
+
+    /// Get native balance for address
+    async fn get_balance(&self, address: &str) -> Result<f64>;
+
+    /// Get token balance
+    async fn get_token_balance(&self, address: &str, token_address: &str) -> Result<f64>;
+
+    /// Get transaction by hash
+    async fn get_transaction(&self, tx_hash: &str) -> Result<Transaction>;
+
+    /// Get latest block number
+    async fn get_block_number(&self) -> Result<u64>;
+
+    /// Send raw transaction, returning its hash
+    async fn send_transaction(&self, signed_tx: &str) -> Result<String>;
+
+    /// Estimate gas for transaction
+    async fn estimate_gas(
+        &self,
+        from: &str,
+        to: &str,
+        data: Option<&str>,
+        value: Option<&str>,
+    ) -> Result<u64>;
+}
+
+/// Signs on behalf of a wallet without exposing the key
+pub trait Signer: Send + Sync {
+    /// Address of the signing wallet
+    fn address(&self) -> &str;
+
+    /// Sign a 32-byte hash, returning a 65-byte `r || s || v` signature
+    fn sign_hash(&self, hash: &[u8; 32]) -> Result<[u8; 65]>;
+}
+
+/// Order entry and account queries on a trading venue
+#[async_trait]
+pub trait Exchange: Send + Sync {
+    /// Short venue name, used in logs and traces
+    fn venue(&self) -> &'static str;
+
+    /// Fees charged by the venue, for costing fills before placement
+    fn fee_schedule(&self) -> FeeSchedule;
+
+    /// Get order book for a token
+    async fn get_order_book(&self, token_id: &str) -> Result<OrderBook>;
+
+    /// Place an order
+    async fn place_order(&self, order: &OrderRequest) -> Result<TradeResult>;
+
+    /// Cancel an order, returning whether the venue accepted the cancel
+    async fn cancel_order(&self, order_id: &str) -> Result<bool>;
+
+    /// Get open orders for wallet
+    async fn get_open_orders(&self, wallet: &Wallet) -> Result<Vec<Order>>;
+
+    /// Get fills/trades for wallet
+    async fn get_fills(&self, wallet: &Wallet, limit: usize) -> Result<Vec<Fill>>;
+}
+
+/// Market discovery on a binary-outcome prediction market
+#[async_trait]
+pub trait PredictionMarket: Exchange {
+    /// Get active markets
+    async fn get_active_markets(&self) -> Result<Vec<Market>>;
+
+    /// Get market by ID
+    async fn get_market(&self, market_id: &str) -> Result<Market>;
+}
@@ -18,6 +18,14 @@ pub enum Chain {
     Base,
     #[serde(rename = "solana")]
     Solana,
+    #[serde(rename = "sepolia")]
+    Sepolia,
+    #[serde(rename = "base_sepolia")]
+    BaseSepolia,
+    #[serde(rename = "amoy")]
+    Amoy,
+    #[serde(rename = "solana_devnet")]
+    SolanaDevnet,
 }
 
 impl Chain {
@@ -30,23 +38,74 @@ impl Chain {
             Chain::Arbitrum => 42161,
             Chain::Optimism => 10,
             Chain::Base => 8453,
-            Chain::Solana => 0, // Solana doesn't use EVM chain IDs
+            Chain::Solana | Chain::SolanaDevnet => 0, // Solana doesn't use EVM chain IDs
+            Chain::Sepolia => 11155111,
+            Chain::BaseSepolia => 84532,
+            Chain::Amoy => 80002,
         }
     }
     
+    /// Look up an EVM chain by chain ID
+    pub fn from_chain_id(chain_id: u64) -> Option<Self> {
+        [
+            Chain::Ethereum,
+            Chain::Polygon,
+            Chain::Bsc,
+            Chain::Arbitrum,
+            Chain::Optimism,
+            Chain::Base,
+            Chain::Sepolia,
+            Chain::BaseSepolia,
+            Chain::Amoy,
+        ]
+        .into_iter()
+        .find(|c| c.chain_id() == chain_id)
+    }
+    
     /// Check if chain is EVM-compatible
     pub fn is_evm(&self) -> bool {
-        !matches!(self, Chain::Solana)
+        !matches!(self, Chain::Solana | Chain::SolanaDevnet)
+    }
+    
+    /// Check if chain is a test network
+    pub fn is_testnet(&self) -> bool {
+        matches!(
+            self,
+            Chain::Sepolia | Chain::BaseSepolia | Chain::Amoy | Chain::SolanaDevnet
+        )
+    }
+    
+    /// Get the mainnet a testnet mirrors (mainnets return themselves)
+    pub fn mainnet(&self) -> Chain {
+        match self {
+            Chain::Sepolia => Chain::Ethereum,
+            Chain::BaseSepolia => Chain::Base,
+            Chain::Amoy => Chain::Polygon,
+            Chain::SolanaDevnet => Chain::Solana,
+            other => *other,
+        }
+    }
+    
+    /// Get faucet URL for test networks
+    pub fn faucet_url(&self) -> Option<&'static str> {
+        match self {
+            Chain::Sepolia => Some("https://sepoliafaucet.com"),
+            Chain::BaseSepolia => Some("https://www.alchemy.com/faucets/base-sepolia"),
+            Chain::Amoy => Some("https://faucet.polygon.technology"),
+            Chain::SolanaDevnet => Some("https://faucet.solana.com"),
+            _ => None,
+        }
     }
     
     /// Get native token symbol
     pub fn native_token(&self) -> &'static str {
         match self {
             Chain::Ethereum => "ETH",
-            Chain::Polygon => "MATIC",
+            Chain::Polygon | Chain::Amoy => "MATIC",
             Chain::Bsc => "BNB",
             Chain::Arbitrum | Chain::Optimism | Chain::Base => "ETH",
-            Chain::Solana => "SOL",
+            Chain::Sepolia | Chain::BaseSepolia => "ETH",
+            Chain::Solana | Chain::SolanaDevnet => "SOL",
         }
     }
     
@@ -60,6 +119,18 @@ impl Chain {
             Chain::Optimism => "https://optimistic.etherscan.io",
             Chain::Base => "https://basescan.org",
             Chain::Solana => "https://solscan.io",
+            Chain::Sepolia => "https://sepolia.etherscan.io",
+            Chain::BaseSepolia => "https://sepolia.basescan.org",
+            Chain::Amoy => "https://amoy.polygonscan.com",
+            Chain::SolanaDevnet => "https://solscan.io",
+        }
+    }
+    
+    /// Build a block explorer link for a path such as `tx/<hash>`
+    pub fn explorer_link(&self, path: &str) -> String {
+        match self {
+            Chain::SolanaDevnet => format!("{}/{}?cluster=devnet", self.explorer_url(), path),
+            _ => format!("{}/{}", self.explorer_url(), path),
         }
     }
 }
@@ -94,7 +165,7 @@ impl Wallet {
     
     /// Get explorer URL for this wallet
     pub fn explorer_url(&self) -> String {
-        format!("{}/address/{}", self.chain.explorer_url(), self.address)
+        self.chain.explorer_link(&format!("address/{}", self.address))
     }
 }
 
@@ -110,6 +181,9 @@ pub struct Token {
 }
 
 /// Order side
+///
+/// Always the side of the order, never of the book: a buy trades against
+/// asks and a sell against bids, in order book methods too.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum OrderSide {
     #[serde(rename = "buy")]
@@ -145,6 +219,40 @@ pub enum OrderType {
     Limit,
     #[serde(rename = "stop")]
     Stop,
+    #[serde(rename = "stop_limit")]
+    StopLimit,
+}
+
+impl OrderType {
+    /// Check if the order only triggers once a stop price is reached
+    pub fn is_stop(&self) -> bool {
+        matches!(self, OrderType::Stop | OrderType::StopLimit)
+    }
+}
+
+/// Time in force
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimeInForce {
+    /// Good till cancelled
+    #[default]
+    #[serde(rename = "GTC")]
+    Gtc,
+    /// Immediate or cancel: fill what is available, cancel the rest
+    #[serde(rename = "IOC")]
+    Ioc,
+    /// Fill or kill: fill entirely and immediately, or not at all
+    #[serde(rename = "FOK")]
+    Fok,
+}
+
+impl std::fmt::Display for TimeInForce {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TimeInForce::Gtc => write!(f, "GTC"),
+            TimeInForce::Ioc => write!(f, "IOC"),
+            TimeInForce::Fok => write!(f, "FOK"),
+        }
+    }
 }
 
 /// Order representation
@@ -156,6 +264,14 @@ pub struct Order {
     pub token_id: String,
     pub size: f64,
     pub price: f64,
+    #[serde(default)]
+    pub time_in_force: TimeInForce,
+    #[serde(default)]
+    pub post_only: bool,
+    #[serde(default)]
+    pub reduce_only: bool,
+    #[serde(default)]
+    pub stop_price: Option<f64>,
     pub wallet: Wallet,
     pub created_at: DateTime<Utc>,
     pub status: OrderStatus,
@@ -178,6 +294,13 @@ pub enum OrderStatus {
     Failed,
 }
 
+impl OrderStatus {
+    /// Whether the order is done and its status can no longer change
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, OrderStatus::Filled | OrderStatus::Cancelled | OrderStatus::Failed)
+    }
+}
+
 /// Position tracking
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Position {
@@ -210,6 +333,177 @@ impl Position {
     }
 }
 
+/// Market data
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Market {
+    pub id: String,
+    pub condition_id: String,
+    pub question: String,
+    pub slug: String,
+    #[serde(rename = "marketMakerAddress")]
+    pub market_maker_address: String,
+    pub tokens: Vec<MarketToken>,
+    #[serde(rename = "active")]
+    pub is_active: bool,
+    #[serde(rename = "closed")]
+    pub is_closed: bool,
+    #[serde(rename = "closedTime")]
+    pub closed_time: Option<DateTime<Utc>>,
+}
+
+/// Market token (outcome)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MarketToken {
+    pub token_id: String,
+    pub outcome: String,
+    pub price: f64,
+}
+
+/// Order book
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OrderBook {
+    pub market: String,
+    pub asset_id: String,
+    pub bids: Vec<OrderBookEntry>,
+    pub asks: Vec<OrderBookEntry>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Order book entry
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OrderBookEntry {
+    pub price: f64,
+    pub size: f64,
+}
+
+/// Trade execution result
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TradeResult {
+    pub order_id: String,
+    pub status: String,
+    pub filled_size: f64,
+    pub avg_price: f64,
+    pub transaction_hash: Option<String>,
+}
+
+/// Order request
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OrderRequest {
+    #[serde(rename = "tokenID")]
+    pub token_id: String,
+    pub side: OrderSide,
+    #[serde(rename = "type")]
+    pub order_type: OrderType,
+    pub size: f64,
+    pub price: f64,
+    #[serde(rename = "timeInForce", default)]
+    pub time_in_force: TimeInForce,
+    #[serde(rename = "postOnly", default)]
+    pub post_only: bool,
+    #[serde(rename = "reduceOnly", default)]
+    pub reduce_only: bool,
+    #[serde(rename = "stopPrice", default, skip_serializing_if = "Option::is_none")]
+    pub stop_price: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<u64>,
+}
+
+impl OrderRequest {
+    pub fn buy(token_id: impl Into<String>, size: f64, price: f64) -> Self {
+        Self {
+            token_id: token_id.into(),
+            side: OrderSide::Buy,
+            order_type: OrderType::Limit,
+            size,
+            price,
+            time_in_force: TimeInForce::Gtc,
+            post_only: false,
+            reduce_only: false,
+            stop_price: None,
+            nonce: None,
+        }
+    }
+    
+    pub fn sell(token_id: impl Into<String>, size: f64, price: f64) -> Self {
+        Self {
+            token_id: token_id.into(),
+            side: OrderSide::Sell,
+            order_type: OrderType::Limit,
+            size,
+            price,
+            time_in_force: TimeInForce::Gtc,
+            post_only: false,
+            reduce_only: false,
+            stop_price: None,
+            nonce: None,
+        }
+    }
+    
+    pub fn with_nonce(mut self, nonce: u64) -> Self {
+        self.nonce = Some(nonce);
+        self
+    }
+    
+    /// Turn into a market order; `price` is kept as a worst-price bound
+    pub fn market(mut self) -> Self {
+        self.order_type = OrderType::Market;
+        self
+    }
+    
+    /// Turn into a stop-limit order triggered at `stop_price`
+    pub fn stop_limit(mut self, stop_price: f64) -> Self {
+        self.order_type = OrderType::StopLimit;
+        self.stop_price = Some(stop_price);
+        self
+    }
+    
+    pub fn with_time_in_force(mut self, time_in_force: TimeInForce) -> Self {
+        self.time_in_force = time_in_force;
+        self
+    }
+    
+    /// Only add liquidity; reject if the order would cross
+    pub fn post_only(mut self) -> Self {
+        self.post_only = true;
+        self
+    }
+    
+    /// Only reduce an existing position
+    pub fn reduce_only(mut self) -> Self {
+        self.reduce_only = true;
+        self
+    }
+    
+    /// Check the flag combination is internally consistent
+    pub fn validate(&self) -> Result<(), String> {
+        if self.post_only && self.time_in_force != TimeInForce::Gtc {
+            return Err(format!("post-only orders cannot be {}", self.time_in_force));
+        }
+        if self.post_only && self.order_type == OrderType::Market {
+            return Err("post-only orders must be limit orders".to_string());
+        }
+        if self.order_type.is_stop() && self.stop_price.is_none() {
+            return Err("stop orders require a stop price".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Fill/Trade record
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Fill {
+    pub id: String,
+    #[serde(rename = "orderId")]
+    pub order_id: String,
+    pub side: OrderSide,
+    pub size: f64,
+    pub price: f64,
+    pub fee: f64,
+    pub timestamp: DateTime<Utc>,
+    #[serde(rename = "transactionHash")]
+    pub transaction_hash: String,
+}
+
 /// Transaction receipt
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Transaction {
@@ -227,7 +521,7 @@ pub struct Transaction {
 impl Transaction {
     /// Get explorer URL for this transaction
     pub fn explorer_url(&self) -> String {
-        format!("{}/tx/{}", self.chain.explorer_url(), self.hash)
+        self.chain.explorer_link(&format!("tx/{}", self.hash))
     }
 }
 
@@ -239,6 +533,28 @@ mod tests {
     fn test_chain_id() {
         assert_eq!(Chain::Ethereum.chain_id(), 1);
         assert_eq!(Chain::Polygon.chain_id(), 137);
+        assert_eq!(Chain::from_chain_id(80002), Some(Chain::Amoy));
+        assert_eq!(Chain::from_chain_id(0), None);
+    }
+    
+    #[test]
+    fn test_testnets() {
+        assert!(Chain::Sepolia.is_testnet());
+        assert!(!Chain::Ethereum.is_testnet());
+        assert_eq!(Chain::BaseSepolia.mainnet(), Chain::Base);
+        assert!(Chain::Amoy.faucet_url().is_some());
+        assert!(!Chain::SolanaDevnet.is_evm());
+    }
+    
+    #[test]
+    fn test_order_request_validate() {
+        assert!(OrderRequest::buy("t", 1.0, 0.5).post_only().validate().is_ok());
+        assert!(OrderRequest::buy("t", 1.0, 0.5)
+            .post_only()
+            .with_time_in_force(TimeInForce::Ioc)
+            .validate()
+            .is_err());
+        assert!(OrderRequest::sell("t", 1.0, 0.5).stop_limit(0.45).validate().is_ok());
     }
     
     #[test]
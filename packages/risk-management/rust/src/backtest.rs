@@ -0,0 +1,373 @@
+//! Replaying recorded history through the risk rules
+//!
+//! [`Backtest`] feeds a recorded sequence of trades, position marks and API
+//! errors through a circuit breaker, kill switch, drawdown stop and per-trade
+//! stop loss, each optional and configured as in production. The
+//! [`BacktestReport`] lists when each rule fired and compares the P&L with
+//! the rules against the P&L of the recording, so limits can be tuned on
+//! evidence.
+//!
+//! ```rust,ignore
+//! let report = Backtest::new(10_000.0)
+//!     .circuit_breaker(CircuitBreakerConfig { max_consecutive_losses: 4, ..Default::default() })
+//!     .max_drawdown_pct(15.0)
+//!     .stop_loss(150.0)
+//!     .run(&events);
+//!
+//! for firing in &report.firings {
+//!     println!("{} {}: {}", firing.at, firing.rule, firing.reason);
+//! }
+//! println!("P&L impact: {:+.2}", report.pnl_impact());
+//! ```
+//!
+//! Rules are replayed with the history's timestamps, so cooldowns and daily
+//! limits behave as they would have. A circuit breaker resumes trading when
+//! its cooldown ends; the kill switch and drawdown stop halt for the rest of
+//! the run. Trades that would have been opened while halted are skipped in
+//! full; marks are applied as recorded.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::circuit_breaker::{CircuitBreaker, CircuitBreakerConfig};
+use crate::drawdown::DrawdownTracker;
+use crate::kill_switch::{KillSwitch, KillSwitchConfig};
+
+/// One recorded event, in time order
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ReplayEvent {
+    /// Closed trade
+    Trade {
+        at: DateTime<Utc>,
+        pnl: f64,
+        /// Worst unrealized loss while open, as a positive amount; without
+        /// it a stop is assumed hit only if the trade lost more than the stop
+        max_adverse: Option<f64>,
+    },
+    /// Open positions marked to market
+    Mark {
+        at: DateTime<Utc>,
+        unrealized_pnl: f64,
+        open_positions: usize,
+    },
+    /// Failed API call
+    ApiError { at: DateTime<Utc> },
+    /// Successful API call, clearing the error count
+    ApiOk { at: DateTime<Utc> },
+}
+
+impl ReplayEvent {
+    pub fn at(&self) -> DateTime<Utc> {
+        match self {
+            ReplayEvent::Trade { at, .. }
+            | ReplayEvent::Mark { at, .. }
+            | ReplayEvent::ApiError { at }
+            | ReplayEvent::ApiOk { at } => *at,
+        }
+    }
+}
+
+/// Risk rule exercised by a backtest
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rule {
+    CircuitBreaker,
+    KillSwitch,
+    Drawdown,
+    StopLoss,
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rule::CircuitBreaker => write!(f, "Circuit breaker"),
+            Rule::KillSwitch => write!(f, "Kill switch"),
+            Rule::Drawdown => write!(f, "Drawdown stop"),
+            Rule::StopLoss => write!(f, "Stop loss"),
+        }
+    }
+}
+
+/// A rule firing during the replay
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RuleFiring {
+    pub rule: Rule,
+    pub at: DateTime<Utc>,
+    pub reason: String,
+}
+
+/// Risk rules to replay history against
+#[derive(Clone, Debug)]
+pub struct Backtest {
+    starting_balance: f64,
+    circuit_breaker: Option<CircuitBreakerConfig>,
+    kill_switch: Option<KillSwitchConfig>,
+    max_drawdown_pct: Option<f64>,
+    stop_loss: Option<f64>,
+}
+
+impl Backtest {
+    /// No rules until configured
+    pub fn new(starting_balance: f64) -> Self {
+        Self {
+            starting_balance,
+            circuit_breaker: None,
+            kill_switch: None,
+            max_drawdown_pct: None,
+            stop_loss: None,
+        }
+    }
+
+    pub fn circuit_breaker(mut self, config: CircuitBreakerConfig) -> Self {
+        self.circuit_breaker = Some(config);
+        self
+    }
+
+    pub fn kill_switch(mut self, config: KillSwitchConfig) -> Self {
+        self.kill_switch = Some(config);
+        self
+    }
+
+    /// Halt once equity falls `pct` below its peak
+    pub fn max_drawdown_pct(mut self, pct: f64) -> Self {
+        self.max_drawdown_pct = Some(pct);
+        self
+    }
+
+    /// Cap each trade's loss at `amount`
+    pub fn stop_loss(mut self, amount: f64) -> Self {
+        self.stop_loss = Some(amount.abs());
+        self
+    }
+
+    /// Replay `events`, which must be in time order
+    pub fn run(&self, events: &[ReplayEvent]) -> BacktestReport {
+        let mut breaker = self.circuit_breaker.clone().map(CircuitBreaker::with_config);
+        let mut kill_switch = self.kill_switch.clone().map(KillSwitch::with_config);
+        let mut drawdown = DrawdownTracker::new(self.max_drawdown_pct.unwrap_or(f64::INFINITY)).with_capacity(0);
+        let mut drawdown_halted = false;
+
+        let mut report = BacktestReport {
+            starting_balance: self.starting_balance,
+            final_equity: self.starting_balance,
+            ..BacktestReport::default()
+        };
+        let mut realized = self.starting_balance;
+        let mut unrealized = 0.0;
+        let mut open_positions = 0;
+
+        for event in events {
+            let at = event.at();
+            if let Some(cb) = breaker.as_mut().filter(|cb| cb.cooldown_elapsed_at(at)) {
+                cb.reset();
+            }
+
+            match *event {
+                ReplayEvent::Trade { pnl, max_adverse, .. } => {
+                    report.trades += 1;
+                    report.baseline_pnl += pnl;
+
+                    let halted = drawdown_halted
+                        || kill_switch.as_ref().is_some_and(KillSwitch::is_triggered)
+                        || breaker.as_ref().is_some_and(|cb| !cb.check_at(at).passed);
+                    if halted {
+                        report.trades_skipped += 1;
+                        continue;
+                    }
+
+                    let mut pnl = pnl;
+                    if let Some(stop) = self.stop_loss {
+                        if max_adverse.map_or(pnl < -stop, |adverse| adverse >= stop) {
+                            report.trades_stopped += 1;
+                            report.firings.push(RuleFiring {
+                                rule: Rule::StopLoss,
+                                at,
+                                reason: format!("Closed at -{:.2} instead of {:+.2}", stop, pnl),
+                            });
+                            pnl = -stop;
+                        }
+                    }
+                    report.trades_taken += 1;
+                    report.pnl += pnl;
+                    realized += pnl;
+                    if let Some(cb) = breaker.as_mut() {
+                        cb.record_trade_at(pnl, at);
+                    }
+                }
+                ReplayEvent::Mark { unrealized_pnl, open_positions: open, .. } => {
+                    unrealized = unrealized_pnl;
+                    open_positions = open;
+                }
+                ReplayEvent::ApiError { .. } => {
+                    if let Some(ks) = kill_switch.as_mut() {
+                        ks.record_error();
+                    }
+                }
+                ReplayEvent::ApiOk { .. } => {
+                    if let Some(ks) = kill_switch.as_mut() {
+                        ks.clear_errors();
+                    }
+                }
+            }
+
+            let equity = realized + unrealized;
+            drawdown.record_at(equity, at);
+
+            if let Some(cb) = breaker.as_mut() {
+                cb.update_balance_at(equity, at);
+                let was_open = cb.status().triggered_at.is_some();
+                let check = cb.check_and_trigger_at(at);
+                if !check.passed && !was_open {
+                    report.firings.push(RuleFiring {
+                        rule: Rule::CircuitBreaker,
+                        at,
+                        reason: check.message,
+                    });
+                }
+            }
+            if let Some(ks) = kill_switch.as_mut() {
+                ks.update_state(equity, open_positions);
+                let was_triggered = ks.is_triggered();
+                let check = ks.check_and_trigger();
+                if !check.passed && !was_triggered {
+                    report.firings.push(RuleFiring {
+                        rule: Rule::KillSwitch,
+                        at,
+                        reason: check.message,
+                    });
+                }
+            }
+            let check = drawdown.check();
+            if !check.passed && !drawdown_halted {
+                drawdown_halted = true;
+                report.firings.push(RuleFiring {
+                    rule: Rule::Drawdown,
+                    at,
+                    reason: check.message,
+                });
+            }
+        }
+
+        report.final_equity = realized + unrealized;
+        report.max_drawdown_pct = drawdown.max_drawdown_pct();
+        report
+    }
+}
+
+/// Outcome of [`Backtest::run`]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct BacktestReport {
+    /// Every rule firing, in time order
+    pub firings: Vec<RuleFiring>,
+    pub trades: usize,
+    pub trades_taken: usize,
+    /// Trades not taken because a rule had halted trading
+    pub trades_skipped: usize,
+    /// Trades closed early by the stop loss
+    pub trades_stopped: usize,
+    /// Realized P&L of the recording
+    pub baseline_pnl: f64,
+    /// Realized P&L with the rules applied
+    pub pnl: f64,
+    pub starting_balance: f64,
+    pub final_equity: f64,
+    /// Largest peak-to-trough decline with the rules applied, in percent
+    pub max_drawdown_pct: f64,
+}
+
+impl BacktestReport {
+    /// P&L with the rules minus P&L of the recording; positive if the
+    /// rules helped
+    pub fn pnl_impact(&self) -> f64 {
+        self.pnl - self.baseline_pnl
+    }
+
+    /// Firings of one rule
+    pub fn firings_of(&self, rule: Rule) -> impl Iterator<Item = &RuleFiring> {
+        self.firings.iter().filter(move |firing| firing.rule == rule)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    fn trade(minutes: i64, pnl: f64) -> ReplayEvent {
+        ReplayEvent::Trade {
+            at: Utc.with_ymd_and_hms(2025, 3, 3, 9, 0, 0).unwrap() + Duration::minutes(minutes),
+            pnl,
+            max_adverse: None,
+        }
+    }
+
+    #[test]
+    fn test_circuit_breaker_skips_until_cooldown() {
+        let events = vec![
+            trade(0, -100.0),
+            trade(10, -100.0),
+            trade(20, -50.0),
+            trade(30, 200.0),
+            trade(90, 80.0),
+        ];
+        let config = CircuitBreakerConfig {
+            max_consecutive_losses: 2,
+            max_daily_drawdown_pct: 50.0,
+            ..CircuitBreakerConfig::default()
+        };
+        let report = Backtest::new(10_000.0).circuit_breaker(config).run(&events);
+
+        assert_eq!(report.firings.len(), 1);
+        assert_eq!(report.firings[0].rule, Rule::CircuitBreaker);
+        assert_eq!(report.firings[0].at, events[1].at());
+        // Both trades inside the 30 minute cooldown are skipped
+        assert_eq!(report.trades_skipped, 2);
+        assert_eq!(report.pnl, -120.0);
+        assert_eq!(report.baseline_pnl, 30.0);
+        assert_eq!(report.pnl_impact(), -150.0);
+    }
+
+    #[test]
+    fn test_stops_and_halts() {
+        let events = vec![
+            trade(0, 50.0),
+            ReplayEvent::Trade {
+                at: trade(5, 0.0).at(),
+                pnl: 30.0,
+                max_adverse: Some(120.0),
+            },
+            trade(10, -400.0),
+            ReplayEvent::Mark {
+                at: trade(15, 0.0).at(),
+                unrealized_pnl: -900.0,
+                open_positions: 2,
+            },
+            trade(20, 500.0),
+        ];
+        let report = Backtest::new(1_000.0).stop_loss(100.0).max_drawdown_pct(50.0).run(&events);
+
+        assert_eq!(report.trades_stopped, 2);
+        assert_eq!(report.firings_of(Rule::StopLoss).count(), 2);
+        assert_eq!(report.firings_of(Rule::Drawdown).count(), 1);
+        assert_eq!(report.trades_skipped, 1);
+        assert_eq!(report.pnl, 50.0 - 100.0 - 100.0);
+        assert!(report.max_drawdown_pct > 50.0);
+
+        let events = vec![
+            trade(0, 50.0),
+            ReplayEvent::ApiError { at: trade(5, 0.0).at() },
+            trade(10, 20.0),
+        ];
+        let config = KillSwitchConfig {
+            max_api_errors: 1,
+            ..KillSwitchConfig::default()
+        };
+        let report = Backtest::new(1_000.0).kill_switch(config).run(&events);
+        let fired: Vec<_> = report.firings_of(Rule::KillSwitch).collect();
+        assert_eq!(fired.len(), 1);
+        assert!(fired[0].reason.contains("API errors"));
+        assert_eq!(report.trades_skipped, 1);
+    }
+}
@@ -1,19 +1,43 @@
 use chrono::{DateTime, Duration, Utc};
-use std::collections::VecDeque;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use tokio::sync::broadcast;
 
+use crate::config::ensure;
+use crate::error::Result;
+use crate::events::{EventEmitter, RiskEvent, RiskSource};
 use crate::types::{RiskCheck, RiskLevel};
 
 /// Circuit breaker configuration
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct CircuitBreakerConfig {
     /// Maximum consecutive losses before halting
     pub max_consecutive_losses: usize,
     /// Maximum daily drawdown percentage
     pub max_daily_drawdown_pct: f64,
+    /// Maximum decline from the day's highest balance, in percent; trips on
+    /// a giveback even while the day is still up
+    pub max_intraday_drawdown_pct: Option<f64>,
     /// Cooldown duration after trigger
+    #[serde(rename = "cooldown_secs", with = "crate::config::duration_secs")]
     pub cooldown_duration: Duration,
     /// Minimum trades before evaluating
     pub min_trades_for_evaluation: usize,
+    /// Median order-placement latency, in milliseconds, over the execution
+    /// window that trips the breaker
+    pub max_median_latency_ms: Option<u64>,
+    /// Mean adverse slippage, in basis points, over the execution window
+    /// that trips the breaker
+    pub max_avg_slippage_bps: Option<f64>,
+    /// Number of recent executions the latency and slippage limits look at
+    pub execution_window: usize,
+    /// Minimum executions in the window before the latency and slippage
+    /// limits are evaluated
+    pub min_executions_for_evaluation: usize,
+    /// Consecutive losses that halt one market or strategy key; defaults to
+    /// `max_consecutive_losses`
+    pub max_consecutive_losses_per_key: Option<usize>,
 }
 
 impl Default for CircuitBreakerConfig {
@@ -21,28 +45,94 @@ impl Default for CircuitBreakerConfig {
         Self {
             max_consecutive_losses: 3,
             max_daily_drawdown_pct: 5.0,
+            max_intraday_drawdown_pct: None,
             cooldown_duration: Duration::minutes(30),
             min_trades_for_evaluation: 5,
+            max_median_latency_ms: None,
+            max_avg_slippage_bps: None,
+            execution_window: 20,
+            min_executions_for_evaluation: 5,
+            max_consecutive_losses_per_key: None,
         }
     }
 }
 
+impl CircuitBreakerConfig {
+    /// Reject values that would make the breaker trip always or never
+    pub fn validate(&self) -> Result<()> {
+        ensure(self.max_consecutive_losses > 0, "circuit_breaker.max_consecutive_losses must be at least 1")?;
+        ensure(self.execution_window > 0, "circuit_breaker.execution_window must be at least 1")?;
+        ensure(
+            self.min_executions_for_evaluation > 0 && self.min_executions_for_evaluation <= self.execution_window,
+            "circuit_breaker.min_executions_for_evaluation must be between 1 and execution_window",
+        )?;
+        ensure(
+            self.max_consecutive_losses_per_key != Some(0),
+            "circuit_breaker.max_consecutive_losses_per_key must be at least 1",
+        )?;
+        ensure(
+            self.max_daily_drawdown_pct > 0.0 && self.max_daily_drawdown_pct <= 100.0,
+            "circuit_breaker.max_daily_drawdown_pct must be in (0, 100]",
+        )?;
+        ensure(
+            self.max_intraday_drawdown_pct.map_or(true, |pct| pct > 0.0 && pct <= 100.0),
+            "circuit_breaker.max_intraday_drawdown_pct must be in (0, 100]",
+        )?;
+        ensure(self.cooldown_duration >= Duration::zero(), "circuit_breaker.cooldown_secs must not be negative")
+    }
+}
+
 /// Circuit breaker for trading halts
 #[derive(Clone, Debug)]
 pub struct CircuitBreaker {
     config: CircuitBreakerConfig,
     trade_history: VecDeque<TradeRecord>,
+    executions: VecDeque<ExecutionRecord>,
     consecutive_losses: usize,
+    /// Consecutive losses per market or strategy key
+    key_losses: BTreeMap<String, usize>,
     daily_pnl: f64,
     last_reset: DateTime<Utc>,
+    /// First balance reported this UTC day
+    day_start_balance: Option<f64>,
+    /// Highest balance reported this UTC day
+    day_peak_balance: Option<f64>,
+    /// Latest balance reported
+    current_balance: Option<f64>,
     triggered_at: Option<DateTime<Utc>>,
+    events: EventEmitter,
+    /// Whether `CooldownExpired` was sent for the current trigger
+    cooldown_reported: bool,
 }
 
-#[derive(Clone, Debug)]
-struct TradeRecord {
-    timestamp: DateTime<Utc>,
-    pnl: f64,
-    was_profitable: bool,
+/// A closed trade's result
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TradeRecord {
+    pub timestamp: DateTime<Utc>,
+    pub pnl: f64,
+    pub was_profitable: bool,
+}
+
+impl TradeRecord {
+    pub fn new(pnl: f64, timestamp: DateTime<Utc>) -> Self {
+        Self {
+            timestamp,
+            pnl,
+            was_profitable: pnl >= 0.0,
+        }
+    }
+}
+
+/// Execution telemetry of one order
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ExecutionRecord {
+    pub timestamp: DateTime<Utc>,
+    /// Time from sending the order to the exchange acknowledging it
+    #[serde(rename = "latency_ms", with = "crate::config::duration_millis")]
+    pub latency: Duration,
+    /// Fill price worse than expected, in basis points; negative for price
+    /// improvement
+    pub slippage_bps: f64,
 }
 
 impl CircuitBreaker {
@@ -56,10 +146,17 @@ impl CircuitBreaker {
         Self {
             config,
             trade_history: VecDeque::new(),
+            executions: VecDeque::new(),
             consecutive_losses: 0,
+            key_losses: BTreeMap::new(),
             daily_pnl: 0.0,
             last_reset: Utc::now(),
+            day_start_balance: None,
+            day_peak_balance: None,
+            current_balance: None,
             triggered_at: None,
+            events: EventEmitter::new(RiskSource::CircuitBreaker),
+            cooldown_reported: false,
         }
     }
     
@@ -75,21 +172,83 @@ impl CircuitBreaker {
         self
     }
     
+    /// Configure max decline from the day's peak balance
+    pub fn max_intraday_drawdown_pct(mut self, max: f64) -> Self {
+        self.config.max_intraday_drawdown_pct = Some(max);
+        self
+    }
+    
+    /// Configure the consecutive losses that halt a single key
+    pub fn max_consecutive_losses_per_key(mut self, max: usize) -> Self {
+        self.config.max_consecutive_losses_per_key = Some(max);
+        self
+    }
+    
+    /// Trip when median order latency over the execution window exceeds
+    /// `max`
+    pub fn max_median_latency(mut self, max: Duration) -> Self {
+        self.config.max_median_latency_ms = Some(max.num_milliseconds().max(0) as u64);
+        self
+    }
+    
+    /// Trip when mean slippage over the execution window exceeds `max` bps
+    pub fn max_avg_slippage_bps(mut self, max: f64) -> Self {
+        self.config.max_avg_slippage_bps = Some(max);
+        self
+    }
+    
+    /// Configure how many recent executions the latency and slippage limits
+    /// cover
+    pub fn execution_window(mut self, executions: usize) -> Self {
+        self.config.execution_window = executions;
+        self
+    }
+    
+    /// Evaluate the latency and slippage limits only once the window holds
+    /// `executions`
+    pub fn min_executions_for_evaluation(mut self, executions: usize) -> Self {
+        self.config.min_executions_for_evaluation = executions;
+        self
+    }
+    
     /// Configure cooldown duration
     pub fn cooldown_duration(mut self, duration: Duration) -> Self {
         self.config.cooldown_duration = duration;
         self
     }
     
+    pub fn config(&self) -> &CircuitBreakerConfig {
+        &self.config
+    }
+    
+    /// Replace the config, keeping trade history, streaks and any trigger
+    pub fn set_config(&mut self, config: CircuitBreakerConfig) {
+        self.config = config;
+        while self.executions.len() > self.config.execution_window {
+            self.executions.pop_front();
+        }
+        self.publish(Utc::now());
+    }
+    
+    /// Publish events on `sender`, e.g. one shared with a kill switch
+    pub fn with_events(mut self, sender: broadcast::Sender<RiskEvent>) -> Self {
+        self.events.attach(sender);
+        self
+    }
+    
+    /// Receive this breaker's future events
+    pub fn subscribe(&mut self) -> broadcast::Receiver<RiskEvent> {
+        self.events.subscribe()
+    }
+    
     /// Record a trade outcome
     pub fn record_trade(&mut self, pnl: f64) {
-        let now = Utc::now();
-        
-        // Reset daily stats if needed
-        if now.date_naive() != self.last_reset.date_naive() {
-            self.daily_pnl = 0.0;
-            self.last_reset = now;
-        }
+        self.record_trade_at(pnl, Utc::now());
+    }
+    
+    /// Record a trade that closed at `now`, e.g. when replaying history
+    pub fn record_trade_at(&mut self, pnl: f64, now: DateTime<Utc>) {
+        self.roll_day(now);
         
         self.daily_pnl += pnl;
         
@@ -99,23 +258,157 @@ impl CircuitBreaker {
             self.consecutive_losses = 0;
         }
         
-        self.trade_history.push_back(TradeRecord {
-            timestamp: now,
-            pnl,
-            was_profitable: pnl >= 0.0,
-        });
+        self.trade_history.push_back(TradeRecord::new(pnl, now));
         
         // Trim old history (keep last 100)
         while self.trade_history.len() > 100 {
             self.trade_history.pop_front();
         }
+        
+        self.publish(now);
+    }
+    
+    /// Record an order's placement latency and slippage
+    pub fn record_execution(&mut self, latency: Duration, slippage_bps: f64) {
+        self.record_execution_at(latency, slippage_bps, Utc::now());
+    }
+    
+    /// Record an order executed at `now`
+    pub fn record_execution_at(&mut self, latency: Duration, slippage_bps: f64, now: DateTime<Utc>) {
+        self.executions.push_back(ExecutionRecord {
+            timestamp: now,
+            latency,
+            slippage_bps,
+        });
+        while self.executions.len() > self.config.execution_window {
+            self.executions.pop_front();
+        }
+        self.publish(now);
+    }
+    
+    /// Median latency over the execution window; `None` without executions
+    pub fn median_latency(&self) -> Option<Duration> {
+        let mut latencies: Vec<Duration> = self.executions.iter().map(|e| e.latency).collect();
+        latencies.sort();
+        let mid = latencies.len() / 2;
+        match latencies.len() {
+            0 => None,
+            n if n % 2 == 1 => Some(latencies[mid]),
+            _ => Some((latencies[mid - 1] + latencies[mid]) / 2),
+        }
+    }
+    
+    /// Mean slippage over the execution window; `None` without executions
+    pub fn avg_slippage_bps(&self) -> Option<f64> {
+        if self.executions.is_empty() {
+            return None;
+        }
+        Some(self.executions.iter().map(|e| e.slippage_bps).sum::<f64>() / self.executions.len() as f64)
+    }
+    
+    /// Record a trade outcome for a market or strategy `key`
+    ///
+    /// The trade counts toward the global streak as well as the key's own.
+    pub fn record_trade_for(&mut self, key: &str, pnl: f64) {
+        self.record_trade_for_at(key, pnl, Utc::now());
+    }
+    
+    /// Record a trade for `key` that closed at `now`
+    pub fn record_trade_for_at(&mut self, key: &str, pnl: f64, now: DateTime<Utc>) {
+        let losses = self.key_losses.entry(key.to_string()).or_default();
+        if pnl < 0.0 {
+            *losses += 1;
+        } else {
+            *losses = 0;
+        }
+        self.record_trade_at(pnl, now);
+    }
+    
+    /// Current losing streak of `key`
+    pub fn key_consecutive_losses(&self, key: &str) -> usize {
+        self.key_losses.get(key).copied().unwrap_or(0)
+    }
+    
+    fn max_key_losses(&self) -> usize {
+        self.config
+            .max_consecutive_losses_per_key
+            .unwrap_or(self.config.max_consecutive_losses)
+    }
+    
+    /// Keys halted by their own losing streak, sorted
+    pub fn halted_keys(&self) -> Vec<&str> {
+        let max = self.max_key_losses();
+        self.key_losses
+            .iter()
+            .filter(|(_, losses)| **losses >= max)
+            .map(|(key, _)| key.as_str())
+            .collect()
+    }
+    
+    /// Clear the losing streak of `key`
+    pub fn reset_key(&mut self, key: &str) {
+        self.key_losses.remove(key);
+    }
+    
+    /// The last 100 trades, oldest first
+    pub fn trade_history(&self) -> impl Iterator<Item = &TradeRecord> {
+        self.trade_history.iter()
+    }
+    
+    /// Report the account balance
+    ///
+    /// The first balance of each UTC day is the baseline for the daily
+    /// drawdown check, which is skipped until a balance has been reported.
+    pub fn update_balance(&mut self, balance: f64) {
+        self.update_balance_at(balance, Utc::now());
+    }
+    
+    /// Report the balance observed at `now`
+    pub fn update_balance_at(&mut self, balance: f64, now: DateTime<Utc>) {
+        self.roll_day(now);
+        self.day_start_balance.get_or_insert(balance);
+        self.day_peak_balance = Some(self.day_peak_balance.map_or(balance, |peak| peak.max(balance)));
+        self.current_balance = Some(balance);
+        self.publish(now);
+    }
+    
+    /// Reset daily stats when the UTC day changes
+    fn roll_day(&mut self, now: DateTime<Utc>) {
+        if now.date_naive() != self.last_reset.date_naive() {
+            self.daily_pnl = 0.0;
+            self.day_start_balance = None;
+            self.day_peak_balance = None;
+            self.current_balance = None;
+            self.last_reset = now;
+        }
+    }
+    
+    /// Loss since the start of the day as a percentage of the day's first
+    /// balance; `None` until a balance has been reported
+    pub fn daily_drawdown_pct(&self) -> Option<f64> {
+        let start = self.day_start_balance.filter(|b| *b > 0.0)?;
+        let current = self.current_balance?;
+        Some(((start - current) / start * 100.0).max(0.0))
+    }
+    
+    /// Decline from the day's highest balance as a percentage of it;
+    /// `None` until a balance has been reported
+    pub fn intraday_drawdown_pct(&self) -> Option<f64> {
+        let peak = self.day_peak_balance.filter(|b| *b > 0.0)?;
+        let current = self.current_balance?;
+        Some(((peak - current) / peak * 100.0).max(0.0))
     }
     
     /// Check if trading is allowed
     pub fn check(&self) -> RiskCheck {
+        self.check_at(Utc::now())
+    }
+    
+    /// Check if trading is allowed at `now`
+    pub fn check_at(&self, now: DateTime<Utc>) -> RiskCheck {
         // Check if in cooldown
         if let Some(triggered) = self.triggered_at {
-            let elapsed = Utc::now() - triggered;
+            let elapsed = now - triggered;
             if elapsed < self.config.cooldown_duration {
                 let remaining = self.config.cooldown_duration - elapsed;
                 return RiskCheck::fail(
@@ -136,39 +429,149 @@ impl CircuitBreaker {
             );
         }
         
-        // Check daily drawdown (would need starting balance for accurate %)
-        // This is a simplified check
+        // Check daily drawdown
+        if let Some(drawdown) = self.daily_drawdown_pct() {
+            if drawdown >= self.config.max_daily_drawdown_pct {
+                return RiskCheck::fail(
+                    RiskLevel::Critical,
+                    format!(
+                        "Max daily drawdown reached: {:.2}% >= {:.2}%",
+                        drawdown,
+                        self.config.max_daily_drawdown_pct
+                    )
+                );
+            }
+        }
+        
+        // Check giveback from the intraday peak
+        if let (Some(max), Some(drawdown)) = (self.config.max_intraday_drawdown_pct, self.intraday_drawdown_pct()) {
+            if drawdown >= max {
+                return RiskCheck::fail(
+                    RiskLevel::Critical,
+                    format!(
+                        "Max intraday drawdown from peak reached: {:.2}% >= {:.2}%",
+                        drawdown,
+                        max
+                    )
+                );
+            }
+        }
+        
+        // Check execution quality, once there are enough executions
+        if self.executions.len() >= self.config.min_executions_for_evaluation {
+            if let (Some(max), Some(median)) = (self.config.max_median_latency_ms, self.median_latency()) {
+                if median.num_milliseconds() > max as i64 {
+                    return RiskCheck::fail(
+                        RiskLevel::Critical,
+                        format!(
+                            "Median order latency {}ms exceeds {}ms",
+                            median.num_milliseconds(),
+                            max
+                        )
+                    );
+                }
+            }
+            if let (Some(max), Some(slippage)) = (self.config.max_avg_slippage_bps, self.avg_slippage_bps()) {
+                if slippage > max {
+                    return RiskCheck::fail(
+                        RiskLevel::Critical,
+                        format!("Average slippage {:.1}bps exceeds {:.1}bps", slippage, max)
+                    );
+                }
+            }
+        }
         
         RiskCheck::pass("Circuit breaker OK")
     }
     
+    /// Check if trading `key` is allowed: the global check, then the key's
+    /// losing streak
+    ///
+    /// A halted key stays halted until it is reset or the breaker is reset.
+    pub fn check_key(&self, key: &str) -> RiskCheck {
+        self.check_key_at(key, Utc::now())
+    }
+    
+    /// Check if trading `key` is allowed at `now`
+    pub fn check_key_at(&self, key: &str, now: DateTime<Utc>) -> RiskCheck {
+        let check = self.check_at(now);
+        if !check.passed {
+            return check;
+        }
+        let losses = self.key_consecutive_losses(key);
+        if losses >= self.max_key_losses() {
+            return RiskCheck::fail(
+                RiskLevel::Critical,
+                format!("Max consecutive losses reached for {}: {}", key, losses)
+            );
+        }
+        check
+    }
+    
     /// Check and trigger if needed
     pub fn check_and_trigger(&mut self) -> RiskCheck {
-        let check = self.check();
+        self.check_and_trigger_at(Utc::now())
+    }
+    
+    /// Check at `now` and trigger if needed
+    pub fn check_and_trigger_at(&mut self, now: DateTime<Utc>) -> RiskCheck {
+        let check = self.check_at(now);
         if !check.passed && self.triggered_at.is_none() {
-            self.triggered_at = Some(Utc::now());
+            self.triggered_at = Some(now);
+            self.cooldown_reported = false;
+            self.events.triggered(&check.message, now);
         }
+        self.publish(now);
         check
     }
     
     /// Manually trigger the circuit breaker
     pub fn trigger(&mut self, reason: impl Into<String>) -> RiskCheck {
-        self.triggered_at = Some(Utc::now());
-        RiskCheck::fail(RiskLevel::Critical, reason)
+        let check = RiskCheck::fail(RiskLevel::Critical, reason);
+        let now = Utc::now();
+        self.triggered_at = Some(now);
+        self.cooldown_reported = false;
+        self.events.triggered(&check.message, now);
+        self.publish(now);
+        check
+    }
+    
+    /// Whether the breaker was triggered and its cooldown is over at `now`
+    pub fn cooldown_elapsed_at(&self, now: DateTime<Utc>) -> bool {
+        self.triggered_at
+            .is_some_and(|triggered| now - triggered >= self.config.cooldown_duration)
     }
     
     /// Reset the circuit breaker
     pub fn reset(&mut self) {
-        self.triggered_at = None;
+        let was_triggered = self.triggered_at.take().is_some();
         self.consecutive_losses = 0;
+        self.key_losses.clear();
+        if was_triggered {
+            self.events.reset();
+        }
+        self.publish(Utc::now());
+    }
+    
+    /// Send `CooldownExpired` once per trigger, and `LevelChanged`, as of
+    /// `now` so replays report the recorded times
+    fn publish(&mut self, now: DateTime<Utc>) {
+        if self.cooldown_elapsed_at(now) && !self.cooldown_reported {
+            self.cooldown_reported = true;
+            self.events.cooldown_expired(now);
+        }
+        let level = self.check_at(now).level;
+        self.events.level(level, now);
     }
     
     /// Get current status
     pub fn status(&self) -> CircuitBreakerStatus {
         CircuitBreakerStatus {
-            is_open: self.triggered_at.is_some() && self.check().passed == false,
+            is_open: self.triggered_at.is_some() && !self.check().passed,
             consecutive_losses: self.consecutive_losses,
             daily_pnl: self.daily_pnl,
+            daily_drawdown_pct: self.daily_drawdown_pct(),
+            intraday_drawdown_pct: self.intraday_drawdown_pct(),
             triggered_at: self.triggered_at,
         }
     }
@@ -186,6 +589,10 @@ pub struct CircuitBreakerStatus {
     pub is_open: bool,
     pub consecutive_losses: usize,
     pub daily_pnl: f64,
+    /// See [`CircuitBreaker::daily_drawdown_pct`]
+    pub daily_drawdown_pct: Option<f64>,
+    /// See [`CircuitBreaker::intraday_drawdown_pct`]
+    pub intraday_drawdown_pct: Option<f64>,
     pub triggered_at: Option<DateTime<Utc>>,
 }
 
@@ -221,17 +628,194 @@ mod tests {
         assert!(cb.check().passed);
     }
     
+    #[test]
+    fn test_per_key_streaks() {
+        let mut cb = CircuitBreaker::new()
+            .max_consecutive_losses(4)
+            .max_consecutive_losses_per_key(2);
+        
+        cb.record_trade_for("ETH-USD", -10.0);
+        cb.record_trade_for("BTC-USD", 5.0);
+        cb.record_trade_for("ETH-USD", -10.0);
+        assert!(cb.check().passed);
+        assert!(cb.check_key("BTC-USD").passed);
+        let check = cb.check_key("ETH-USD");
+        assert!(!check.passed);
+        assert!(check.message.contains("ETH-USD"));
+        assert_eq!(cb.halted_keys(), vec!["ETH-USD"]);
+        
+        // Losses across keys still add up to the global streak
+        cb.record_trade_for("SOL-USD", -10.0);
+        cb.record_trade_for("BTC-USD", -10.0);
+        cb.record_trade_for("XRP-USD", -10.0);
+        assert_eq!(cb.key_consecutive_losses("BTC-USD"), 1);
+        assert!(!cb.check_key("BTC-USD").passed);
+        
+        cb.reset();
+        assert!(cb.check_key("ETH-USD").passed);
+    }
+    
+    #[test]
+    fn test_daily_drawdown() {
+        let mut cb = CircuitBreaker::new()
+            .max_daily_drawdown_pct(5.0);
+        let today = Utc::now();
+        
+        cb.update_balance_at(10_000.0, today);
+        cb.update_balance_at(9_600.0, today);
+        assert_eq!(cb.daily_drawdown_pct(), Some(4.0));
+        assert!(cb.check().passed);
+        
+        cb.update_balance_at(9_500.0, today);
+        let check = cb.check();
+        assert!(!check.passed);
+        assert!(check.message.contains("daily drawdown"));
+        
+        // The next day starts from its first balance
+        cb.update_balance_at(9_500.0, today + Duration::days(1));
+        assert_eq!(cb.daily_drawdown_pct(), Some(0.0));
+        assert!(cb.check().passed);
+    }
+    
+    #[test]
+    fn test_intraday_giveback() {
+        let mut cb = CircuitBreaker::new()
+            .max_intraday_drawdown_pct(4.0);
+        let today = Utc::now();
+        
+        cb.update_balance_at(10_000.0, today);
+        cb.update_balance_at(11_000.0, today);
+        cb.update_balance_at(10_600.0, today);
+        assert!(cb.check().passed);
+        
+        // Still up on the day, but 5% off the peak
+        cb.update_balance_at(10_450.0, today);
+        assert_eq!(cb.daily_drawdown_pct(), Some(0.0));
+        assert!((cb.intraday_drawdown_pct().unwrap() - 5.0).abs() < 1e-9);
+        let check = cb.check();
+        assert!(!check.passed);
+        assert!(check.message.contains("intraday"));
+        
+        cb.update_balance_at(10_450.0, today + Duration::days(1));
+        assert!(cb.check().passed);
+    }
+    
+    #[test]
+    fn test_execution_quality() {
+        let mut cb = CircuitBreaker::new()
+            .max_median_latency(Duration::milliseconds(250))
+            .max_avg_slippage_bps(15.0)
+            .execution_window(5);
+        
+        for ms in [100, 120, 900, 110] {
+            cb.record_execution(Duration::milliseconds(ms), 5.0);
+        }
+        // Not evaluated below min_executions_for_evaluation
+        assert!(cb.check().passed);
+        cb.record_execution(Duration::milliseconds(130), 5.0);
+        assert_eq!(cb.median_latency(), Some(Duration::milliseconds(120)));
+        assert!(cb.check().passed);
+        
+        for _ in 0..3 {
+            cb.record_execution(Duration::milliseconds(400), 5.0);
+        }
+        let check = cb.check();
+        assert!(!check.passed);
+        assert!(check.message.contains("latency 400ms"));
+        
+        for _ in 0..5 {
+            cb.record_execution(Duration::milliseconds(100), 20.0);
+        }
+        assert_eq!(cb.avg_slippage_bps(), Some(20.0));
+        assert!(cb.check().message.contains("slippage"));
+    }
+    
+    #[test]
+    fn test_min_executions_for_evaluation() {
+        let mut cb = CircuitBreaker::new()
+            .max_avg_slippage_bps(15.0)
+            .min_executions_for_evaluation(2);
+        cb.config.min_trades_for_evaluation = 50;
+        
+        cb.record_execution(Duration::milliseconds(100), 40.0);
+        assert!(cb.check().passed);
+        cb.record_execution(Duration::milliseconds(100), 40.0);
+        assert!(cb.check().message.contains("slippage"));
+        
+        for min in [0, 21] {
+            let config = CircuitBreakerConfig {
+                min_executions_for_evaluation: min,
+                ..CircuitBreakerConfig::default()
+            };
+            assert!(config.validate().is_err());
+        }
+    }
+    
     #[test]
     fn test_cooldown() {
         let mut cb = CircuitBreaker::new()
             .max_consecutive_losses(1)
-            .cooldown_duration(Duration::seconds(0)); // Instant cooldown
+            .cooldown_duration(Duration::minutes(30));
+        let start = Utc::now();
         
-        cb.record_trade(-10.0);
-        assert!(!cb.check().passed);
+        cb.record_trade_at(-10.0, start);
+        assert!(!cb.check_and_trigger_at(start).passed);
+        
+        // A win during the cooldown breaks the streak but not the cooldown
+        cb.record_trade_at(5.0, start + Duration::minutes(10));
+        assert!(cb.check_at(start + Duration::minutes(10)).message.contains("remaining"));
         
         // Should pass after cooldown
-        std::thread::sleep(std::time::Duration::from_millis(10));
-        assert!(cb.check().passed);
+        assert!(cb.check_at(start + Duration::minutes(30)).passed);
+    }
+    
+    #[test]
+    fn test_events() {
+        let mut cb = CircuitBreaker::new()
+            .max_consecutive_losses(2)
+            .cooldown_duration(Duration::zero());
+        let mut events = cb.subscribe();
+        
+        cb.record_trade(-10.0);
+        cb.record_trade(-10.0);
+        cb.check_and_trigger();
+        cb.reset();
+        
+        let received: Vec<RiskEvent> = std::iter::from_fn(|| events.try_recv().ok()).collect();
+        assert!(matches!(received[0], RiskEvent::LevelChanged { to: RiskLevel::Critical, .. }));
+        assert!(matches!(&received[1], RiskEvent::Triggered { reason, .. } if reason.contains("consecutive losses")));
+        assert!(matches!(received[2], RiskEvent::CooldownExpired { source: RiskSource::CircuitBreaker, .. }));
+        assert!(matches!(received[3], RiskEvent::Reset { .. }));
+        assert!(matches!(received[4], RiskEvent::LevelChanged { to: RiskLevel::Normal, .. }));
+        assert_eq!(received.len(), 5);
+    }
+    
+    #[test]
+    fn test_replay_events_use_replayed_time() {
+        use chrono::TimeZone;
+        
+        let mut cb = CircuitBreaker::new()
+            .max_consecutive_losses(2)
+            .cooldown_duration(Duration::minutes(30));
+        let mut events = cb.subscribe();
+        let start = Utc.with_ymd_and_hms(2025, 3, 3, 12, 0, 0).unwrap();
+        
+        cb.record_trade_at(-10.0, start);
+        cb.record_trade_at(-10.0, start + Duration::minutes(1));
+        cb.check_and_trigger_at(start + Duration::minutes(1));
+        cb.record_trade_at(5.0, start + Duration::minutes(10));
+        cb.record_trade_at(5.0, start + Duration::minutes(40));
+        
+        let received: Vec<RiskEvent> = std::iter::from_fn(|| events.try_recv().ok()).collect();
+        assert!(matches!(received[0], RiskEvent::LevelChanged { at, .. } if at == start + Duration::minutes(1)));
+        assert!(matches!(received[1], RiskEvent::Triggered { at, .. } if at == start + Duration::minutes(1)));
+        let expired: Vec<_> = received
+            .iter()
+            .filter_map(|e| match e {
+                RiskEvent::CooldownExpired { at, .. } => Some(*at),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(expired, vec![start + Duration::minutes(40)]);
     }
 }
@@ -0,0 +1,315 @@
+//! Clock drift check
+//!
+//! Signed orders, nonces and order expirations assume the host clock agrees
+//! with the exchange; when it skews they break without an obvious error.
+//! [`ClockCheck`] measures the local clock's offset against a
+//! [`TimeSource`] (an exchange's server time, or NTP via [`NtpSource`]) and
+//! fails when the drift exceeds its limit or the last measurement is too old.
+//!
+//! ```rust,ignore
+//! let mut clock = ClockCheck::new(Duration::milliseconds(500));
+//! clock.sync(&NtpSource::new("pool.ntp.org:123")).await?;
+//!
+//! let clock = Arc::new(Mutex::new(clock));
+//! let pipeline = PreTradePipeline::new().with_check(clock.clone());
+//! ```
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use tokio::net::UdpSocket;
+
+use crate::error::Result;
+use crate::pretrade::{OrderIntent, PreTradeCheck, PreTradeContext};
+use crate::types::{RiskCheck, RiskLevel};
+
+/// Future returned by [`TimeSource::server_time`]
+pub type ServerTime<'a> = Pin<Box<dyn Future<Output = Result<DateTime<Utc>>> + Send + 'a>>;
+
+/// A reference clock
+pub trait TimeSource: Send + Sync {
+    /// The reference's current time
+    fn server_time(&self) -> ServerTime<'_>;
+}
+
+/// Local clock offset from a reference
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ClockOffset {
+    /// Reference time minus local time; positive when the local clock is
+    /// behind
+    #[serde(rename = "offset_ms", with = "crate::config::duration_millis")]
+    pub offset: Duration,
+    /// Time the request took, which bounds the measurement's accuracy
+    #[serde(rename = "round_trip_ms", with = "crate::config::duration_millis")]
+    pub round_trip: Duration,
+    pub measured_at: DateTime<Utc>,
+}
+
+impl ClockOffset {
+    /// Offset from a server time read between local times `sent` and
+    /// `received`, assuming it was stamped halfway through the round trip
+    pub fn from_samples(sent: DateTime<Utc>, server_time: DateTime<Utc>, received: DateTime<Utc>) -> Self {
+        let round_trip = received - sent;
+        Self {
+            offset: server_time - (sent + round_trip / 2),
+            round_trip,
+            measured_at: received,
+        }
+    }
+
+    /// Query `source` and measure the offset
+    pub async fn measure(source: &dyn TimeSource) -> Result<Self> {
+        let sent = Utc::now();
+        let server_time = source.server_time().await?;
+        Ok(Self::from_samples(sent, server_time, Utc::now()))
+    }
+}
+
+/// SNTP time source
+#[derive(Clone, Debug)]
+pub struct NtpSource {
+    server: String,
+    timeout: std::time::Duration,
+}
+
+/// Seconds from the NTP epoch (1900) to the Unix epoch
+const NTP_UNIX_OFFSET: i64 = 2_208_988_800;
+
+impl NtpSource {
+    /// Query `server`, e.g. `"pool.ntp.org:123"` or `"[2001:db8::123]:123"`,
+    /// with a 2 second timeout
+    pub fn new(server: impl Into<String>) -> Self {
+        Self {
+            server: server.into(),
+            timeout: std::time::Duration::from_secs(2),
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Send one SNTP request and read the server's transmit time
+    async fn query(&self) -> Result<DateTime<Utc>> {
+        let server = tokio::net::lookup_host(&self.server).await?.next().ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::NotFound, format!("No address for {}", self.server))
+        })?;
+        // Bind in the server's address family so IPv6-only servers work
+        let local: SocketAddr = if server.is_ipv6() {
+            (std::net::Ipv6Addr::UNSPECIFIED, 0).into()
+        } else {
+            (std::net::Ipv4Addr::UNSPECIFIED, 0).into()
+        };
+        let socket = UdpSocket::bind(local).await?;
+        socket.connect(server).await?;
+
+        // Client request: leap indicator 0, version 3, mode 3
+        let mut packet = [0u8; 48];
+        packet[0] = 0x1b;
+        socket.send(&packet).await?;
+        let received = socket.recv(&mut packet).await?;
+        let invalid = |message: &str| {
+            std::io::Error::new(std::io::ErrorKind::InvalidData, format!("{} from {}", message, self.server))
+        };
+        if received < 48 {
+            return Err(invalid("Short NTP response").into());
+        }
+        ntp_time(&packet[40..48]).ok_or_else(|| invalid("Invalid NTP transmit time").into())
+    }
+}
+
+impl TimeSource for NtpSource {
+    fn server_time(&self) -> ServerTime<'_> {
+        Box::pin(async move {
+            match tokio::time::timeout(self.timeout, self.query()).await {
+                Ok(time) => time,
+                Err(_) => Err(std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    format!("No NTP response from {}", self.server),
+                )
+                .into()),
+            }
+        })
+    }
+}
+
+/// Decode a 64-bit NTP timestamp: seconds since 1900 and a 32-bit fraction
+fn ntp_time(bytes: &[u8]) -> Option<DateTime<Utc>> {
+    let seconds = u32::from_be_bytes(bytes[0..4].try_into().ok()?) as i64;
+    let fraction = u32::from_be_bytes(bytes[4..8].try_into().ok()?) as u64;
+    let nanos = (fraction * 1_000_000_000) >> 32;
+    Utc.timestamp_opt(seconds - NTP_UNIX_OFFSET, nanos as u32).single()
+}
+
+/// Fails when the local clock drifts from the reference
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClockCheck {
+    /// Largest allowed offset, either direction, in milliseconds
+    pub max_drift_ms: u64,
+    /// How long a measurement stays valid, in seconds
+    pub max_sync_age_secs: u64,
+    #[serde(skip)]
+    last: Option<ClockOffset>,
+}
+
+impl Default for ClockCheck {
+    fn default() -> Self {
+        Self {
+            max_drift_ms: 1_000,
+            max_sync_age_secs: 3_600,
+            last: None,
+        }
+    }
+}
+
+impl ClockCheck {
+    pub fn new(max_drift: Duration) -> Self {
+        Self {
+            max_drift_ms: max_drift.num_milliseconds().unsigned_abs(),
+            ..Self::default()
+        }
+    }
+
+    /// Require a measurement at least every `age`
+    pub fn max_sync_age(mut self, age: Duration) -> Self {
+        self.max_sync_age_secs = age.num_seconds().max(0) as u64;
+        self
+    }
+
+    /// Keep a measurement taken elsewhere, e.g. from an exchange response
+    pub fn record(&mut self, offset: ClockOffset) {
+        self.last = Some(offset);
+    }
+
+    /// Measure against `source`, keep the result and check it
+    pub async fn sync(&mut self, source: &dyn TimeSource) -> Result<RiskCheck> {
+        let offset = ClockOffset::measure(source).await?;
+        self.record(offset);
+        Ok(self.check_at(offset.measured_at))
+    }
+
+    /// Latest measurement
+    pub fn offset(&self) -> Option<ClockOffset> {
+        self.last
+    }
+
+    pub fn check(&self) -> RiskCheck {
+        self.check_at(Utc::now())
+    }
+
+    /// Check the latest measurement as of `now`
+    pub fn check_at(&self, now: DateTime<Utc>) -> RiskCheck {
+        let Some(last) = self.last else {
+            return RiskCheck::fail(RiskLevel::High, "Clock offset not measured");
+        };
+        let age = now - last.measured_at;
+        if age > Duration::seconds(self.max_sync_age_secs as i64) {
+            return RiskCheck::fail(
+                RiskLevel::High,
+                format!("Clock offset last measured {}s ago", age.num_seconds()),
+            );
+        }
+        let drift = last.offset.num_milliseconds();
+        if drift.unsigned_abs() > self.max_drift_ms {
+            RiskCheck::fail(
+                RiskLevel::Critical,
+                format!("Clock drift {}ms exceeds {}ms", drift, self.max_drift_ms),
+            )
+        } else {
+            RiskCheck::pass(format!("Clock drift {}ms", drift))
+        }
+    }
+}
+
+impl PreTradeCheck for ClockCheck {
+    fn check(&self, _intent: &OrderIntent, _ctx: &PreTradeContext<'_>) -> RiskCheck {
+        ClockCheck::check(self)
+    }
+
+    fn name(&self) -> &'static str {
+        "Clock"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reference clock running `ahead` of the local one
+    struct Skewed {
+        ahead: Duration,
+    }
+
+    impl TimeSource for Skewed {
+        fn server_time(&self) -> ServerTime<'_> {
+            Box::pin(async move { Ok(Utc::now() + self.ahead) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_drift_limits() {
+        let mut clock = ClockCheck::new(Duration::milliseconds(500)).max_sync_age(Duration::minutes(10));
+        assert!(!clock.check().passed);
+
+        let check = clock.sync(&Skewed { ahead: Duration::milliseconds(100) }).await.unwrap();
+        assert!(check.passed);
+        let offset = clock.offset().unwrap().offset.num_milliseconds();
+        assert!((90..=110).contains(&offset));
+        assert!(!clock.check_at(Utc::now() + Duration::minutes(11)).passed);
+
+        let check = clock.sync(&Skewed { ahead: Duration::seconds(-2) }).await.unwrap();
+        assert_eq!((check.passed, check.level), (false, RiskLevel::Critical));
+    }
+
+    #[test]
+    fn test_offset_from_samples() {
+        let sent = Utc.with_ymd_and_hms(2025, 3, 3, 12, 0, 0).unwrap();
+        let received = sent + Duration::milliseconds(200);
+        // Server stamped its reply at local 12:00:00.100 plus 1.5s of skew
+        let offset = ClockOffset::from_samples(sent, sent + Duration::milliseconds(1_600), received);
+        assert_eq!(offset.offset, Duration::milliseconds(1_500));
+        assert_eq!(offset.round_trip, Duration::milliseconds(200));
+
+        // 2025-03-03T12:00:00.5Z as an NTP timestamp
+        let seconds = (sent.timestamp() + NTP_UNIX_OFFSET) as u32;
+        let mut bytes = seconds.to_be_bytes().to_vec();
+        bytes.extend(0x8000_0000u32.to_be_bytes());
+        assert_eq!(ntp_time(&bytes), Some(sent + Duration::milliseconds(500)));
+    }
+
+    /// One-shot SNTP server on `addr` replying with `time`
+    async fn ntp_server(addr: &str, time: DateTime<Utc>) -> Option<String> {
+        let socket = UdpSocket::bind(addr).await.ok()?;
+        let local = socket.local_addr().ok()?;
+        tokio::spawn(async move {
+            let mut packet = [0u8; 48];
+            let (_, peer) = socket.recv_from(&mut packet).await.unwrap();
+            let seconds = (time.timestamp() + NTP_UNIX_OFFSET) as u32;
+            packet[40..44].copy_from_slice(&seconds.to_be_bytes());
+            packet[44..48].copy_from_slice(&[0; 4]);
+            socket.send_to(&packet, peer).await.unwrap();
+        });
+        Some(local.to_string())
+    }
+
+    #[tokio::test]
+    async fn test_ntp_source() {
+        let time = Utc.with_ymd_and_hms(2025, 3, 3, 12, 0, 0).unwrap();
+        let v4 = ntp_server("127.0.0.1:0", time).await.unwrap();
+        assert_eq!(NtpSource::new(v4).server_time().await.unwrap(), time);
+
+        // Hosts without IPv6 loopback skip the IPv6 case
+        if let Some(v6) = ntp_server("[::1]:0", time).await {
+            assert_eq!(NtpSource::new(v6).server_time().await.unwrap(), time);
+        }
+
+        let silent = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        let source = NtpSource::new(silent.local_addr().unwrap().to_string())
+            .with_timeout(std::time::Duration::from_millis(50));
+        assert!(source.server_time().await.is_err());
+    }
+}
@@ -0,0 +1,292 @@
+//! Risk configuration files
+//!
+//! [`RiskConfig`] gathers the trading limits, circuit breaker, kill switch,
+//! exposure, margin, velocity and sizing settings so they can live in a TOML
+//! file instead of code. Every section is optional and falls back to the
+//! defaults; loading validates the values and rejects the file if any is out
+//! of range.
+//!
+//! ```toml
+//! [limits]
+//! max_daily_loss = 250.0
+//!
+//! [circuit_breaker]
+//! max_consecutive_losses = 4
+//! cooldown_secs = 3600
+//!
+//! [kill_switch]
+//! balance_floor = 1000.0
+//! max_feed_age_ms = { "ETH-USD" = 5000 }
+//! kill_file = "/var/run/trading/KILL"
+//!
+//! [soft_limits]
+//! consecutive_losses = 2
+//! size_factor = 0.5
+//!
+//! [exposure]
+//! max_position_pct = 20.0
+//! market.default = 2000.0
+//! category.overrides = { memecoins = 500.0 }
+//!
+//! [margin]
+//! max_leverage = 5.0
+//!
+//! [velocity]
+//! max_orders_per_minute = 20
+//! max_notional_per_hour = 50000.0
+//!
+//! [sizing]
+//! strategy = "kelly"
+//! win_rate = 0.55
+//! avg_win = 120.0
+//! avg_loss = 80.0
+//! max_size = 500.0
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::circuit_breaker::{CircuitBreaker, CircuitBreakerConfig};
+use crate::error::{Error, Result};
+use crate::exposure::ExposureLimits;
+use crate::kill_switch::{KillSwitch, KillSwitchConfig};
+use crate::manager::{RiskManager, SoftLimits};
+use crate::margin::MarginLimits;
+use crate::position_sizing::{
+    AntiMartingaleSizing, DrawdownKellySizing, FixedFractionalSizing, FixedRatioSizing, KellySizing,
+    OptimalFSizing, PositionSizer, VolatilityBasedSizing,
+};
+use crate::types::TradingLimits;
+use crate::velocity::VelocityLimits;
+
+/// `Err(Error::Config(message))` unless `condition` holds
+pub(crate) fn ensure(condition: bool, message: impl Into<String>) -> Result<()> {
+    if condition {
+        Ok(())
+    } else {
+        Err(Error::Config(message.into()))
+    }
+}
+
+/// Serde for `chrono::Duration` as whole seconds
+pub(crate) mod duration_secs {
+    use chrono::Duration;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(duration.num_seconds())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        i64::deserialize(deserializer).map(Duration::seconds)
+    }
+}
+
+/// Serde for `chrono::Duration` as whole milliseconds
+pub(crate) mod duration_millis {
+    use chrono::Duration;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(duration.num_milliseconds())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        i64::deserialize(deserializer).map(Duration::milliseconds)
+    }
+}
+
+/// Sizing strategy, selected by the `strategy` key
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "strategy", rename_all = "snake_case")]
+pub enum SizingParams {
+    Kelly(KellySizing),
+    DrawdownKelly(DrawdownKellySizing),
+    FixedFractional(FixedFractionalSizing),
+    OptimalF(OptimalFSizing),
+    FixedRatio(FixedRatioSizing),
+    VolatilityBased(VolatilityBasedSizing),
+    AntiMartingale(AntiMartingaleSizing),
+}
+
+/// Position sizing strategy and bounds
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SizingConfig {
+    #[serde(flatten)]
+    pub strategy: SizingParams,
+    #[serde(default)]
+    pub min_size: f64,
+    /// No upper bound if `None`
+    #[serde(default)]
+    pub max_size: Option<f64>,
+}
+
+impl Default for SizingConfig {
+    fn default() -> Self {
+        Self {
+            strategy: SizingParams::FixedFractional(FixedFractionalSizing::moderate()),
+            min_size: 0.0,
+            max_size: None,
+        }
+    }
+}
+
+impl SizingConfig {
+    /// Reject bad strategy parameters or bounds
+    pub fn validate(&self) -> Result<()> {
+        match &self.strategy {
+            SizingParams::Kelly(s) => s.validate()?,
+            SizingParams::DrawdownKelly(s) => s.validate()?,
+            SizingParams::FixedFractional(s) => s.validate()?,
+            SizingParams::OptimalF(s) => s.validate()?,
+            SizingParams::FixedRatio(s) => s.validate()?,
+            SizingParams::VolatilityBased(s) => s.validate()?,
+            SizingParams::AntiMartingale(s) => s.validate()?,
+        }
+        ensure(self.min_size >= 0.0, "sizing.min_size must not be negative")?;
+        ensure(
+            self.max_size.map_or(true, |max| max >= self.min_size),
+            "sizing.max_size must not be below sizing.min_size",
+        )
+    }
+
+    /// Position sizer using the configured strategy and bounds
+    pub fn build(&self) -> PositionSizer {
+        let sizer = match self.strategy.clone() {
+            SizingParams::Kelly(s) => PositionSizer::new(s),
+            SizingParams::DrawdownKelly(s) => PositionSizer::new(s),
+            SizingParams::FixedFractional(s) => PositionSizer::new(s),
+            SizingParams::OptimalF(s) => PositionSizer::new(s),
+            SizingParams::FixedRatio(s) => PositionSizer::new(s),
+            SizingParams::VolatilityBased(s) => PositionSizer::new(s),
+            SizingParams::AntiMartingale(s) => PositionSizer::new(s),
+        };
+        let sizer = sizer.with_min_size(self.min_size);
+        match self.max_size {
+            Some(max) => sizer.with_max_size(max),
+            None => sizer,
+        }
+    }
+}
+
+/// All risk settings, as loaded from a config file
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RiskConfig {
+    pub limits: TradingLimits,
+    pub circuit_breaker: CircuitBreakerConfig,
+    pub kill_switch: KillSwitchConfig,
+    pub exposure: ExposureLimits,
+    pub soft_limits: SoftLimits,
+    pub margin: MarginLimits,
+    pub velocity: VelocityLimits,
+    pub sizing: SizingConfig,
+}
+
+impl RiskConfig {
+    /// Load and validate a TOML config file
+    pub fn from_toml(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)?;
+        Self::from_toml_str(&text).map_err(|e| Error::Config(format!("{}: {}", path.display(), e)))
+    }
+
+    /// Parse and validate TOML config text
+    pub fn from_toml_str(text: &str) -> Result<Self> {
+        let config: Self = toml::from_str(text)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Check every section
+    pub fn validate(&self) -> Result<()> {
+        self.limits.validate()?;
+        self.circuit_breaker.validate()?;
+        self.kill_switch.validate()?;
+        self.exposure.validate()?;
+        self.soft_limits.validate()?;
+        self.margin.validate()?;
+        self.velocity.validate()?;
+        self.sizing.validate()
+    }
+
+    /// Risk manager built from the circuit breaker, kill switch, soft limits
+    /// and sizing sections
+    pub fn risk_manager(&self) -> RiskManager {
+        RiskManager::with_soft_limits(
+            CircuitBreaker::with_config(self.circuit_breaker.clone()),
+            KillSwitch::with_config(self.kill_switch.clone()),
+            self.sizing.build(),
+            self.soft_limits.clone(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_parse_config() {
+        let config = RiskConfig::from_toml_str(
+            r#"
+            [limits]
+            max_daily_loss = 250
+
+            [circuit_breaker]
+            max_consecutive_losses = 4
+            cooldown_secs = 3600
+
+            [sizing]
+            strategy = "fixed_fractional"
+            risk_per_trade_pct = 1.5
+            max_size = 100.0
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.limits.max_daily_loss, 250.0);
+        assert_eq!(config.limits.max_open_positions, 5);
+        assert_eq!(config.circuit_breaker.cooldown_duration, Duration::hours(1));
+        assert_eq!(config.kill_switch.max_api_errors, 5);
+        let sizer = config.sizing.build();
+        assert_eq!(sizer.calculate(1_000.0), 15.0);
+        assert_eq!(sizer.calculate(100_000.0), 100.0);
+    }
+
+    #[test]
+    fn test_defaults_round_trip() {
+        let config = RiskConfig::from_toml_str("").unwrap();
+        let text = toml::to_string(&config).unwrap();
+        let parsed = RiskConfig::from_toml_str(&text).unwrap();
+        assert_eq!(parsed.circuit_breaker.cooldown_duration, Duration::minutes(30));
+        assert!(matches!(parsed.sizing.strategy, SizingParams::FixedFractional(_)));
+    }
+
+    #[test]
+    fn test_rejects_invalid_values() {
+        let err = RiskConfig::from_toml_str("[circuit_breaker]\nmax_daily_drawdown_pct = 150.0").unwrap_err();
+        assert!(err.to_string().contains("max_daily_drawdown_pct"));
+
+        let err = RiskConfig::from_toml_str("[sizing]\nstrategy = \"kelly\"\nwin_rate = 1.5").unwrap_err();
+        assert!(err.to_string().contains("kelly.win_rate"));
+
+        let err = RiskConfig::from_toml_str("[sizing]\nstrategy = \"drawdown_kelly\"\nmax_probability = 0.0").unwrap_err();
+        assert!(err.to_string().contains("drawdown_kelly.max_probability"));
+
+        assert!(RiskConfig::from_toml_str("[exposure]\nmarket.overrides = { BTC-USD = -1.0 }").is_err());
+        assert!(RiskConfig::from_toml_str("[sizing]\nstrategy = \"martingale\"").is_err());
+        assert!(RiskConfig::from_toml_str("[margin]\nmax_leverage = 0.5").is_err());
+        assert!(RiskConfig::from_toml_str("[limits]\nmax_daily_loss = \"lots\"").is_err());
+    }
+
+    #[test]
+    fn test_from_toml_names_file() {
+        let path = std::env::temp_dir().join(format!("risk-config-test-{}.toml", std::process::id()));
+        std::fs::write(&path, "[kill_switch]\nmax_api_errors = 0\n").unwrap();
+        let err = RiskConfig::from_toml(&path).unwrap_err();
+        assert!(err.to_string().contains("risk-config-test"));
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,265 @@
+//! Drawdown tracking
+//!
+//! [`DrawdownTracker`] follows account equity over time: the running peak,
+//! the lowest point since that peak, how far below the peak equity is now,
+//! the worst drawdown seen, and how long equity has been under water. Its
+//! [`check`](DrawdownTracker::check) fails once the current drawdown reaches
+//! the configured limit.
+//!
+//! ```rust,ignore
+//! let mut drawdown = DrawdownTracker::new(10.0);
+//! drawdown.record(10_000.0);
+//! drawdown.record(9_200.0);
+//! assert_eq!(drawdown.current_drawdown_pct(), 8.0);
+//! assert!(drawdown.check().passed);
+//! ```
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+use crate::types::{RiskCheck, RiskLevel, TradingLimits};
+
+/// Equity at a point in time
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EquityMark {
+    pub timestamp: DateTime<Utc>,
+    pub equity: f64,
+}
+
+/// Decline from a peak to a trough
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Drawdown {
+    pub peak: EquityMark,
+    pub trough: EquityMark,
+}
+
+impl Drawdown {
+    /// Peak minus trough
+    pub fn amount(&self) -> f64 {
+        self.peak.equity - self.trough.equity
+    }
+
+    /// Decline as a percentage of the peak
+    pub fn pct(&self) -> f64 {
+        if self.peak.equity <= 0.0 {
+            return 0.0;
+        }
+        self.amount() / self.peak.equity * 100.0
+    }
+}
+
+/// Running peak, trough and drawdown of an equity curve
+#[derive(Clone, Debug)]
+pub struct DrawdownTracker {
+    /// Drawdown percentage at which the check fails
+    max_drawdown_pct: f64,
+    /// Marks kept for [`equity_curve`](Self::equity_curve)
+    capacity: usize,
+    curve: VecDeque<EquityMark>,
+    peak: Option<EquityMark>,
+    /// Lowest mark since `peak`
+    trough: Option<EquityMark>,
+    last: Option<EquityMark>,
+    worst: Option<Drawdown>,
+}
+
+impl DrawdownTracker {
+    /// Tracker failing its check at `max_drawdown_pct` below the peak
+    pub fn new(max_drawdown_pct: f64) -> Self {
+        Self {
+            max_drawdown_pct,
+            capacity: 1000,
+            curve: VecDeque::new(),
+            peak: None,
+            trough: None,
+            last: None,
+            worst: None,
+        }
+    }
+
+    /// Tracker using the limits' `max_drawdown_pct`
+    pub fn from_limits(limits: &TradingLimits) -> Self {
+        Self::new(limits.max_drawdown_pct)
+    }
+
+    /// Keep the last `capacity` marks of the equity curve (default 1000);
+    /// peak and drawdown statistics cover every mark regardless
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Record the current equity
+    pub fn record(&mut self, equity: f64) {
+        self.record_at(equity, Utc::now());
+    }
+
+    /// Record equity observed at `timestamp`
+    pub fn record_at(&mut self, equity: f64, timestamp: DateTime<Utc>) {
+        let mark = EquityMark { timestamp, equity };
+        match self.peak {
+            Some(peak) if equity < peak.equity => {
+                if self.trough.map_or(true, |t| equity < t.equity) {
+                    self.trough = Some(mark);
+                }
+                let current = Drawdown { peak, trough: mark };
+                if self.worst.map_or(true, |w| current.pct() > w.pct()) {
+                    self.worst = Some(current);
+                }
+            }
+            _ => {
+                self.peak = Some(mark);
+                self.trough = None;
+            }
+        }
+        self.last = Some(mark);
+
+        if self.capacity > 0 {
+            if self.curve.len() == self.capacity {
+                self.curve.pop_front();
+            }
+            self.curve.push_back(mark);
+        }
+    }
+
+    /// Highest equity seen
+    pub fn peak(&self) -> Option<EquityMark> {
+        self.peak
+    }
+
+    /// Lowest equity since the peak; `None` at a new high
+    pub fn trough(&self) -> Option<EquityMark> {
+        self.trough
+    }
+
+    /// Latest mark
+    pub fn last(&self) -> Option<EquityMark> {
+        self.last
+    }
+
+    /// How far the latest mark is below the peak
+    pub fn current_drawdown(&self) -> f64 {
+        match (self.peak, self.last) {
+            (Some(peak), Some(last)) => peak.equity - last.equity,
+            _ => 0.0,
+        }
+    }
+
+    /// [`current_drawdown`](Self::current_drawdown) as a percentage of the peak
+    pub fn current_drawdown_pct(&self) -> f64 {
+        match (self.peak, self.last) {
+            (Some(peak), Some(last)) => Drawdown { peak, trough: last }.pct(),
+            _ => 0.0,
+        }
+    }
+
+    /// Largest peak-to-trough decline seen
+    pub fn max_drawdown(&self) -> Option<Drawdown> {
+        self.worst
+    }
+
+    /// Percentage of the largest decline seen, 0 if none
+    pub fn max_drawdown_pct(&self) -> f64 {
+        self.worst.map_or(0.0, |w| w.pct())
+    }
+
+    /// Whether equity is below its peak
+    pub fn is_underwater(&self) -> bool {
+        self.current_drawdown() > 0.0
+    }
+
+    /// Time since the peak while equity is below it, zero at a high
+    pub fn underwater_duration(&self) -> Duration {
+        self.underwater_duration_at(Utc::now())
+    }
+
+    fn underwater_duration_at(&self, now: DateTime<Utc>) -> Duration {
+        match self.peak {
+            Some(peak) if self.is_underwater() => now - peak.timestamp,
+            _ => Duration::zero(),
+        }
+    }
+
+    /// Recorded marks, oldest first
+    pub fn equity_curve(&self) -> impl Iterator<Item = &EquityMark> {
+        self.curve.iter()
+    }
+
+    /// Fail once the current drawdown reaches the limit
+    pub fn check(&self) -> RiskCheck {
+        let pct = self.current_drawdown_pct();
+        if pct >= self.max_drawdown_pct {
+            RiskCheck::fail(
+                RiskLevel::Critical,
+                format!("Max drawdown reached: {:.2}% >= {:.2}%", pct, self.max_drawdown_pct),
+            )
+        } else {
+            RiskCheck::pass(format!("Drawdown {:.2}% (limit {:.2}%)", pct, self.max_drawdown_pct))
+        }
+    }
+
+    /// Forget all marks, e.g. after a deposit or withdrawal
+    pub fn reset(&mut self) {
+        self.curve.clear();
+        self.peak = None;
+        self.trough = None;
+        self.last = None;
+        self.worst = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_peak_trough_and_max() {
+        let start = Utc::now();
+        let mut tracker = DrawdownTracker::new(20.0);
+        for (i, equity) in [100.0, 120.0, 90.0, 105.0, 130.0, 117.0].into_iter().enumerate() {
+            tracker.record_at(equity, start + Duration::hours(i as i64));
+        }
+
+        assert_eq!(tracker.peak().unwrap().equity, 130.0);
+        assert_eq!(tracker.trough().unwrap().equity, 117.0);
+        assert!((tracker.current_drawdown_pct() - 10.0).abs() < 1e-9);
+
+        let worst = tracker.max_drawdown().unwrap();
+        assert_eq!(worst.peak.equity, 120.0);
+        assert_eq!(worst.trough.equity, 90.0);
+        assert!((tracker.max_drawdown_pct() - 25.0).abs() < 1e-9);
+
+        assert_eq!(tracker.underwater_duration_at(start + Duration::hours(7)), Duration::hours(3));
+        assert_eq!(tracker.equity_curve().count(), 6);
+    }
+
+    #[test]
+    fn test_check_against_limit() {
+        let mut tracker = DrawdownTracker::from_limits(&TradingLimits::default());
+        tracker.record(1_000.0);
+        tracker.record(950.0);
+        assert!(tracker.check().passed);
+
+        tracker.record(890.0);
+        let check = tracker.check();
+        assert!(!check.passed);
+        assert_eq!(check.level, RiskLevel::Critical);
+
+        tracker.record(1_010.0);
+        assert!(tracker.check().passed);
+        assert!(!tracker.is_underwater());
+        assert_eq!(tracker.underwater_duration(), Duration::zero());
+    }
+
+    #[test]
+    fn test_curve_capacity() {
+        let mut tracker = DrawdownTracker::new(10.0).with_capacity(2);
+        for equity in [100.0, 50.0, 75.0] {
+            tracker.record(equity);
+        }
+        let curve: Vec<f64> = tracker.equity_curve().map(|m| m.equity).collect();
+        assert_eq!(curve, vec![50.0, 75.0]);
+        assert_eq!(tracker.max_drawdown_pct(), 50.0);
+    }
+}
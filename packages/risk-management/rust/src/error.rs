@@ -0,0 +1,23 @@
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Configuration error: {0}")]
+    Config(String),
+    
+    #[error("TOML error: {0}")]
+    Toml(#[from] toml::de::Error),
+    
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    
+    #[cfg(feature = "csv")]
+    #[error("CSV error: {0}")]
+    Csv(#[from] csv::Error),
+    
+    #[cfg(feature = "sqlite")]
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+}
@@ -0,0 +1,189 @@
+//! Risk events
+//!
+//! The circuit breaker and kill switch publish a [`RiskEvent`] on a tokio
+//! broadcast channel whenever they trip, reset, finish a cooldown or change
+//! risk level, and the config watcher when it reloads or rejects the config
+//! file, so a Telegram bot or order manager can react instead of polling
+//! `status()`.
+//!
+//! ```rust,ignore
+//! let mut events = risk_manager.subscribe();
+//! tokio::spawn(async move {
+//!     while let Ok(event) = events.recv().await {
+//!         if let RiskEvent::Triggered { source, reason, .. } = &event {
+//!             alerts.send(Alert::critical(format!("{} tripped: {}", source, reason))).await;
+//!         }
+//!     }
+//! });
+//! ```
+//!
+//! Events are raised by the state changes that cause them, e.g. a cooldown
+//! expiring is reported on the next trade, balance update or check.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use tokio::sync::broadcast;
+
+use crate::types::RiskLevel;
+
+/// Events buffered per subscriber before the slowest one starts lagging
+pub const EVENT_CAPACITY: usize = 64;
+
+/// Which component raised an event
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RiskSource {
+    CircuitBreaker,
+    KillSwitch,
+    Config,
+}
+
+impl fmt::Display for RiskSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RiskSource::CircuitBreaker => write!(f, "Circuit breaker"),
+            RiskSource::KillSwitch => write!(f, "Kill switch"),
+            RiskSource::Config => write!(f, "Config"),
+        }
+    }
+}
+
+/// A change in a risk component's state
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum RiskEvent {
+    /// Trading halted
+    Triggered {
+        source: RiskSource,
+        reason: String,
+        at: DateTime<Utc>,
+    },
+    /// Halt cleared by a reset
+    Reset { source: RiskSource, at: DateTime<Utc> },
+    /// Circuit breaker cooldown elapsed
+    CooldownExpired { source: RiskSource, at: DateTime<Utc> },
+    /// Risk level of the component's check changed
+    LevelChanged {
+        source: RiskSource,
+        from: RiskLevel,
+        to: RiskLevel,
+        at: DateTime<Utc>,
+    },
+    /// New limits applied from the config file
+    ConfigReloaded {
+        source: RiskSource,
+        /// Top-level sections that changed, e.g. `circuit_breaker`
+        sections: Vec<String>,
+        at: DateTime<Utc>,
+    },
+    /// Changed config file failed to parse or validate; the previous
+    /// limits stay in force
+    ConfigRejected {
+        source: RiskSource,
+        error: String,
+        at: DateTime<Utc>,
+    },
+}
+
+impl RiskEvent {
+    pub fn source(&self) -> RiskSource {
+        match self {
+            RiskEvent::Triggered { source, .. }
+            | RiskEvent::Reset { source, .. }
+            | RiskEvent::CooldownExpired { source, .. }
+            | RiskEvent::LevelChanged { source, .. }
+            | RiskEvent::ConfigReloaded { source, .. }
+            | RiskEvent::ConfigRejected { source, .. } => *source,
+        }
+    }
+}
+
+/// Sender half held by a risk component; publishes nothing until attached
+#[derive(Clone, Debug)]
+pub(crate) struct EventEmitter {
+    source: RiskSource,
+    sender: Option<broadcast::Sender<RiskEvent>>,
+    /// Level of the last check, for `LevelChanged`
+    level: RiskLevel,
+}
+
+impl EventEmitter {
+    pub(crate) fn new(source: RiskSource) -> Self {
+        Self {
+            source,
+            sender: None,
+            level: RiskLevel::Normal,
+        }
+    }
+
+    pub(crate) fn attach(&mut self, sender: broadcast::Sender<RiskEvent>) {
+        self.sender = Some(sender);
+    }
+
+    /// Receiver for future events, creating a channel if none is attached
+    pub(crate) fn subscribe(&mut self) -> broadcast::Receiver<RiskEvent> {
+        self.sender
+            .get_or_insert_with(|| broadcast::channel(EVENT_CAPACITY).0)
+            .subscribe()
+    }
+
+    fn send(&self, event: RiskEvent) {
+        if let Some(sender) = &self.sender {
+            // No subscribers is not an error
+            let _ = sender.send(event);
+        }
+    }
+
+    pub(crate) fn triggered(&self, reason: &str, at: DateTime<Utc>) {
+        self.send(RiskEvent::Triggered {
+            source: self.source,
+            reason: reason.to_string(),
+            at,
+        });
+    }
+
+    pub(crate) fn reset(&self) {
+        self.send(RiskEvent::Reset {
+            source: self.source,
+            at: Utc::now(),
+        });
+    }
+
+    pub(crate) fn cooldown_expired(&self, at: DateTime<Utc>) {
+        self.send(RiskEvent::CooldownExpired {
+            source: self.source,
+            at,
+        });
+    }
+
+    pub(crate) fn config_reloaded(&self, sections: Vec<String>) {
+        self.send(RiskEvent::ConfigReloaded {
+            source: self.source,
+            sections,
+            at: Utc::now(),
+        });
+    }
+
+    pub(crate) fn config_rejected(&self, error: &str) {
+        self.send(RiskEvent::ConfigRejected {
+            source: self.source,
+            error: error.to_string(),
+            at: Utc::now(),
+        });
+    }
+
+    /// Publish `LevelChanged` if `level`, as of `at`, differs from the last
+    /// one seen
+    pub(crate) fn level(&mut self, level: RiskLevel, at: DateTime<Utc>) {
+        if level != self.level {
+            let from = std::mem::replace(&mut self.level, level);
+            self.send(RiskEvent::LevelChanged {
+                source: self.source,
+                from,
+                to: level,
+                at,
+            });
+        }
+    }
+}
@@ -0,0 +1,278 @@
+//! Maximum adverse and favorable excursion
+//!
+//! [`ExcursionTracker`] follows each open trade's price between entry and
+//! exit and records its maximum adverse excursion (MAE, the worst open loss)
+//! and maximum favorable excursion (MFE, the best open profit) as a
+//! percentage of the entry price. [`ExcursionStats`] summarizes the closed
+//! trades so stop and take-profit distances can be set from how trades
+//! actually move: a stop just beyond the MAE of most winners rarely cuts a
+//! winner short.
+//!
+//! ```rust,ignore
+//! let mut excursions = ExcursionTracker::new(500);
+//! excursions.open("ETH-USD", Side::Buy, 3_000.0);
+//! excursions.update_price("ETH-USD", 2_950.0);
+//! excursions.close("ETH-USD", 3_090.0);
+//!
+//! let stats = excursions.stats();
+//! println!("stop at {:.2}%", stats.winners_mae.percentile(90.0));
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+use crate::types::Side;
+
+/// A trade being tracked
+#[derive(Clone, Debug)]
+struct OpenTrade {
+    side: Side,
+    entry_price: f64,
+    /// Lowest and highest price seen since entry
+    low: f64,
+    high: f64,
+}
+
+impl OpenTrade {
+    fn excursion(&self, exit_price: f64) -> TradeExcursion {
+        let pct = |from: f64, to: f64| (to - from) / self.entry_price * 100.0;
+        let (mae_pct, mfe_pct, return_pct) = match self.side {
+            Side::Buy => (
+                pct(self.low, self.entry_price).max(0.0),
+                pct(self.entry_price, self.high).max(0.0),
+                pct(self.entry_price, exit_price),
+            ),
+            Side::Sell => (
+                pct(self.entry_price, self.high).max(0.0),
+                pct(self.low, self.entry_price).max(0.0),
+                pct(exit_price, self.entry_price),
+            ),
+        };
+        TradeExcursion {
+            side: self.side,
+            entry_price: self.entry_price,
+            exit_price,
+            mae_pct,
+            mfe_pct,
+            return_pct,
+        }
+    }
+}
+
+/// Excursions of a closed trade, in percent of the entry price
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TradeExcursion {
+    pub side: Side,
+    pub entry_price: f64,
+    pub exit_price: f64,
+    /// Worst open loss, as a positive percentage
+    pub mae_pct: f64,
+    /// Best open profit
+    pub mfe_pct: f64,
+    /// Return at exit, negative for a loss
+    pub return_pct: f64,
+}
+
+impl TradeExcursion {
+    pub fn is_winner(&self) -> bool {
+        self.return_pct > 0.0
+    }
+}
+
+/// Tracks MAE and MFE of open trades, keyed by market
+#[derive(Clone, Debug)]
+pub struct ExcursionTracker {
+    open: HashMap<String, OpenTrade>,
+    closed: VecDeque<TradeExcursion>,
+    capacity: usize,
+}
+
+impl ExcursionTracker {
+    /// Keep the last `capacity` closed trades
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            open: HashMap::new(),
+            closed: VecDeque::new(),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Start tracking a trade in `market`, replacing any open one
+    pub fn open(&mut self, market: impl Into<String>, side: Side, entry_price: f64) {
+        self.open.insert(
+            market.into(),
+            OpenTrade {
+                side,
+                entry_price,
+                low: entry_price,
+                high: entry_price,
+            },
+        );
+    }
+
+    /// Feed a price for `market`; ignored if no trade is open there
+    pub fn update_price(&mut self, market: &str, price: f64) {
+        if let Some(trade) = self.open.get_mut(market) {
+            trade.low = trade.low.min(price);
+            trade.high = trade.high.max(price);
+        }
+    }
+
+    /// Close the trade in `market` at `exit_price`, which also counts as a
+    /// price update
+    pub fn close(&mut self, market: &str, exit_price: f64) -> Option<TradeExcursion> {
+        self.update_price(market, exit_price);
+        let excursion = self.open.remove(market)?.excursion(exit_price);
+        if self.closed.len() == self.capacity {
+            self.closed.pop_front();
+        }
+        self.closed.push_back(excursion.clone());
+        Some(excursion)
+    }
+
+    /// Excursions so far of the trade open in `market`, as if closed at
+    /// `price`
+    pub fn open_excursion(&self, market: &str, price: f64) -> Option<TradeExcursion> {
+        let mut trade = self.open.get(market)?.clone();
+        trade.low = trade.low.min(price);
+        trade.high = trade.high.max(price);
+        Some(trade.excursion(price))
+    }
+
+    /// Closed trades, oldest first
+    pub fn closed(&self) -> impl Iterator<Item = &TradeExcursion> {
+        self.closed.iter()
+    }
+
+    pub fn stats(&self) -> ExcursionStats {
+        ExcursionStats::from_trades(self.closed.iter())
+    }
+}
+
+impl Default for ExcursionTracker {
+    fn default() -> Self {
+        Self::new(1_000)
+    }
+}
+
+/// Sorted sample of excursion percentages
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Distribution {
+    sorted: Vec<f64>,
+}
+
+impl Distribution {
+    pub fn new(mut values: Vec<f64>) -> Self {
+        values.sort_by(f64::total_cmp);
+        Self { sorted: values }
+    }
+
+    pub fn len(&self) -> usize {
+        self.sorted.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sorted.is_empty()
+    }
+
+    /// 0 if empty
+    pub fn mean(&self) -> f64 {
+        if self.sorted.is_empty() {
+            return 0.0;
+        }
+        self.sorted.iter().sum::<f64>() / self.sorted.len() as f64
+    }
+
+    pub fn median(&self) -> f64 {
+        self.percentile(50.0)
+    }
+
+    pub fn max(&self) -> f64 {
+        self.sorted.last().copied().unwrap_or(0.0)
+    }
+
+    /// Linearly interpolated percentile `p` (0 to 100), 0 if empty
+    pub fn percentile(&self, p: f64) -> f64 {
+        let n = self.sorted.len();
+        if n == 0 {
+            return 0.0;
+        }
+        let rank = p.clamp(0.0, 100.0) / 100.0 * (n - 1) as f64;
+        let (low, high) = (rank.floor() as usize, rank.ceil() as usize);
+        self.sorted[low] + (self.sorted[high] - self.sorted[low]) * (rank - low as f64)
+    }
+}
+
+/// MAE and MFE distributions of closed trades
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExcursionStats {
+    pub trades: usize,
+    pub mae: Distribution,
+    pub mfe: Distribution,
+    /// MAE of winning trades, the basis for stop distances
+    pub winners_mae: Distribution,
+    /// MFE of losing trades: profit given back before the loss
+    pub losers_mfe: Distribution,
+}
+
+impl ExcursionStats {
+    pub fn from_trades<'a>(trades: impl IntoIterator<Item = &'a TradeExcursion>) -> Self {
+        let trades: Vec<&TradeExcursion> = trades.into_iter().collect();
+        let collect = |filter: fn(&TradeExcursion) -> bool, value: fn(&TradeExcursion) -> f64| {
+            Distribution::new(trades.iter().copied().filter(|t| filter(t)).map(value).collect())
+        };
+        Self {
+            trades: trades.len(),
+            mae: collect(|_| true, |t| t.mae_pct),
+            mfe: collect(|_| true, |t| t.mfe_pct),
+            winners_mae: collect(TradeExcursion::is_winner, |t| t.mae_pct),
+            losers_mfe: collect(|t| !t.is_winner(), |t| t.mfe_pct),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tracks_long_and_short() {
+        let mut tracker = ExcursionTracker::new(10);
+        tracker.open("ETH-USD", Side::Buy, 100.0);
+        tracker.open("BTC-USD", Side::Sell, 200.0);
+        for (eth, btc) in [(97.0, 204.0), (108.0, 190.0), (103.0, 196.0)] {
+            tracker.update_price("ETH-USD", eth);
+            tracker.update_price("BTC-USD", btc);
+        }
+        assert_eq!(tracker.open_excursion("ETH-USD", 103.0).unwrap().mfe_pct, 8.0);
+
+        let long = tracker.close("ETH-USD", 104.0).unwrap();
+        assert_eq!((long.mae_pct, long.mfe_pct, long.return_pct), (3.0, 8.0, 4.0));
+
+        let short = tracker.close("BTC-USD", 202.0).unwrap();
+        assert_eq!((short.mae_pct, short.mfe_pct, short.return_pct), (2.0, 5.0, -1.0));
+
+        assert!(tracker.close("ETH-USD", 1.0).is_none());
+        let stats = tracker.stats();
+        assert_eq!(stats.trades, 2);
+        assert_eq!(stats.winners_mae.max(), 3.0);
+        assert_eq!(stats.losers_mfe.median(), 5.0);
+    }
+
+    #[test]
+    fn test_distribution() {
+        let dist = Distribution::new(vec![4.0, 1.0, 3.0, 2.0, 5.0]);
+        assert_eq!(dist.median(), 3.0);
+        assert_eq!(dist.mean(), 3.0);
+        assert_eq!(dist.percentile(90.0), 4.6);
+        assert_eq!(Distribution::default().percentile(50.0), 0.0);
+
+        let mut tracker = ExcursionTracker::new(2);
+        for exit in [101.0, 102.0, 103.0] {
+            tracker.open("X", Side::Buy, 100.0);
+            tracker.close("X", exit);
+        }
+        assert_eq!(tracker.closed().count(), 2);
+        assert_eq!(tracker.stats().mfe.percentile(0.0), 2.0);
+    }
+}
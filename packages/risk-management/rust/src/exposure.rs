@@ -0,0 +1,332 @@
+//! Exposure limits
+//!
+//! [`ExposureLimits`] caps gross notional per market, per token and per
+//! category, and the share of capital any one position may take, across a
+//! list of open [`Position`]s. Each scope has an optional default limit and
+//! per-key overrides.
+//!
+//! ```rust,ignore
+//! let limits = ExposureLimits::new()
+//!     .max_market_notional(2_000.0)
+//!     .market_limit("BTC-USD", 5_000.0)
+//!     .category_limit("memecoins", 500.0)
+//!     .max_position_pct(20.0);
+//!
+//! let positions = vec![Position::new("BTC-USD", 0.05, 65_000.0).with_token("BTC")];
+//! let check = limits.check(&positions, 25_000.0);
+//! ```
+//!
+//! With the `blockchain-clients` feature, `blockchain_clients::Position`
+//! converts into [`Position`] (market = token id, token = symbol).
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::config::ensure;
+use crate::error::Result;
+use crate::types::{RiskCheck, RiskLevel};
+
+/// An open position, as the exposure checks see it
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Position {
+    pub market: String,
+    /// Underlying asset, e.g. "BTC" for both BTC-USD and BTC-PERP
+    pub token: Option<String>,
+    /// User-defined group, e.g. "politics" or "layer-1"
+    pub category: Option<String>,
+    /// Signed size; negative for shorts
+    pub size: f64,
+    /// Current price
+    pub price: f64,
+}
+
+impl Position {
+    pub fn new(market: impl Into<String>, size: f64, price: f64) -> Self {
+        Self {
+            market: market.into(),
+            token: None,
+            category: None,
+            size,
+            price,
+        }
+    }
+
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    pub fn with_category(mut self, category: impl Into<String>) -> Self {
+        self.category = Some(category.into());
+        self
+    }
+
+    /// Gross value, `|size| * price`
+    pub fn notional(&self) -> f64 {
+        self.size.abs() * self.price
+    }
+}
+
+#[cfg(feature = "blockchain-clients")]
+impl From<&blockchain_clients::Position> for Position {
+    fn from(position: &blockchain_clients::Position) -> Self {
+        Self::new(position.token_id.clone(), position.size, position.current_price)
+            .with_token(position.token.symbol.clone())
+    }
+}
+
+#[cfg(feature = "blockchain-clients")]
+impl From<blockchain_clients::Position> for Position {
+    fn from(position: blockchain_clients::Position) -> Self {
+        Self::from(&position)
+    }
+}
+
+/// What an exposure limit groups positions by
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExposureScope {
+    Market,
+    Token,
+    Category,
+    /// A single position against capital
+    Position,
+}
+
+impl fmt::Display for ExposureScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExposureScope::Market => write!(f, "market"),
+            ExposureScope::Token => write!(f, "token"),
+            ExposureScope::Category => write!(f, "category"),
+            ExposureScope::Position => write!(f, "position"),
+        }
+    }
+}
+
+/// Notional cap applying to every key of a scope, with per-key overrides
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotionalLimit {
+    /// Cap for keys without an override; none if `None`
+    pub default: Option<f64>,
+    pub overrides: HashMap<String, f64>,
+}
+
+impl NotionalLimit {
+    /// Cap for `key`
+    pub fn limit_for(&self, key: &str) -> Option<f64> {
+        self.overrides.get(key).copied().or(self.default)
+    }
+
+    fn validate(&self, scope: ExposureScope) -> Result<()> {
+        let non_negative = self.default.map_or(true, |v| v >= 0.0) && self.overrides.values().all(|v| *v >= 0.0);
+        ensure(non_negative, format!("exposure.{} limits must not be negative", scope))
+    }
+}
+
+/// A limit exceeded by the current positions
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExposureBreach {
+    pub scope: ExposureScope,
+    /// Market, token or category name
+    pub key: String,
+    pub notional: f64,
+    pub limit: f64,
+}
+
+impl fmt::Display for ExposureBreach {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} exposure {:.2} exceeds {:.2}",
+            self.scope, self.key, self.notional, self.limit
+        )
+    }
+}
+
+/// Exposure caps per market, token and category, and per position
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExposureLimits {
+    pub market: NotionalLimit,
+    pub token: NotionalLimit,
+    pub category: NotionalLimit,
+    /// Largest single position as a percentage of capital
+    pub max_position_pct: Option<f64>,
+}
+
+impl ExposureLimits {
+    /// No limits
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cap every market at `max` notional
+    pub fn max_market_notional(mut self, max: f64) -> Self {
+        self.market.default = Some(max);
+        self
+    }
+
+    /// Cap `market` at `max` notional, overriding the default
+    pub fn market_limit(mut self, market: impl Into<String>, max: f64) -> Self {
+        self.market.overrides.insert(market.into(), max);
+        self
+    }
+
+    /// Cap every token at `max` notional
+    pub fn max_token_notional(mut self, max: f64) -> Self {
+        self.token.default = Some(max);
+        self
+    }
+
+    /// Cap `token` at `max` notional, overriding the default
+    pub fn token_limit(mut self, token: impl Into<String>, max: f64) -> Self {
+        self.token.overrides.insert(token.into(), max);
+        self
+    }
+
+    /// Cap every category at `max` notional
+    pub fn max_category_notional(mut self, max: f64) -> Self {
+        self.category.default = Some(max);
+        self
+    }
+
+    /// Cap `category` at `max` notional, overriding the default
+    pub fn category_limit(mut self, category: impl Into<String>, max: f64) -> Self {
+        self.category.overrides.insert(category.into(), max);
+        self
+    }
+
+    /// Cap any single position at `pct` percent of capital
+    pub fn max_position_pct(mut self, pct: f64) -> Self {
+        self.max_position_pct = Some(pct);
+        self
+    }
+
+    /// Reject negative limits
+    pub fn validate(&self) -> Result<()> {
+        self.market.validate(ExposureScope::Market)?;
+        self.token.validate(ExposureScope::Token)?;
+        self.category.validate(ExposureScope::Category)?;
+        ensure(
+            self.max_position_pct.map_or(true, |pct| pct > 0.0 && pct <= 100.0),
+            "exposure.max_position_pct must be in (0, 100]",
+        )
+    }
+
+    /// Every limit `positions` exceed, markets first, then tokens,
+    /// categories and single positions
+    pub fn breaches(&self, positions: &[Position], capital: f64) -> Vec<ExposureBreach> {
+        let mut breaches = Vec::new();
+        let scopes: [(ExposureScope, &NotionalLimit, PositionKey); 3] = [
+            (ExposureScope::Market, &self.market, |p| Some(p.market.as_str())),
+            (ExposureScope::Token, &self.token, |p| p.token.as_deref()),
+            (ExposureScope::Category, &self.category, |p| p.category.as_deref()),
+        ];
+        for (scope, limits, key) in scopes {
+            for (key, notional) in totals(positions, key) {
+                if let Some(limit) = limits.limit_for(&key).filter(|limit| notional > *limit) {
+                    breaches.push(ExposureBreach { scope, key, notional, limit });
+                }
+            }
+        }
+
+        if let Some(pct) = self.max_position_pct {
+            let limit = capital.max(0.0) * pct / 100.0;
+            for position in positions.iter().filter(|p| p.notional() > limit) {
+                breaches.push(ExposureBreach {
+                    scope: ExposureScope::Position,
+                    key: position.market.clone(),
+                    notional: position.notional(),
+                    limit,
+                });
+            }
+        }
+        breaches
+    }
+
+    /// Fail if `positions` exceed any limit
+    pub fn check(&self, positions: &[Position], capital: f64) -> RiskCheck {
+        let breaches = self.breaches(positions, capital);
+        if breaches.is_empty() {
+            return RiskCheck::pass("Exposure within limits");
+        }
+        let details: Vec<String> = breaches.iter().map(ToString::to_string).collect();
+        RiskCheck::fail(RiskLevel::High, format!("Exposure limits exceeded: {}", details.join("; ")))
+    }
+}
+
+/// Market, token or category of a position
+type PositionKey = fn(&Position) -> Option<&str>;
+
+/// Gross notional per key, sorted by key
+fn totals(positions: &[Position], key: PositionKey) -> Vec<(String, f64)> {
+    let mut totals: HashMap<&str, f64> = HashMap::new();
+    for position in positions {
+        if let Some(key) = key(position) {
+            *totals.entry(key).or_default() += position.notional();
+        }
+    }
+    let mut totals: Vec<(String, f64)> = totals.into_iter().map(|(k, v)| (k.to_string(), v)).collect();
+    totals.sort_by(|a, b| a.0.cmp(&b.0));
+    totals
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn positions() -> Vec<Position> {
+        vec![
+            Position::new("BTC-USD", 0.02, 50_000.0).with_token("BTC").with_category("majors"),
+            Position::new("BTC-PERP", -0.03, 50_000.0).with_token("BTC").with_category("majors"),
+            Position::new("PEPE-USD", 1_000_000.0, 0.0005).with_token("PEPE").with_category("memes"),
+        ]
+    }
+
+    #[test]
+    fn test_within_limits() {
+        let limits = ExposureLimits::new()
+            .max_market_notional(2_000.0)
+            .max_token_notional(3_000.0)
+            .max_position_pct(50.0);
+        assert!(limits.breaches(&positions(), 10_000.0).is_empty());
+        assert!(limits.check(&positions(), 10_000.0).passed);
+    }
+
+    #[test]
+    fn test_scopes_and_overrides() {
+        let limits = ExposureLimits::new()
+            .max_market_notional(2_000.0)
+            .market_limit("BTC-PERP", 1_000.0)
+            .max_token_notional(2_000.0)
+            .category_limit("memes", 400.0)
+            .max_position_pct(10.0);
+        let breaches = limits.breaches(&positions(), 10_000.0);
+
+        let summary: Vec<(ExposureScope, &str)> = breaches.iter().map(|b| (b.scope, b.key.as_str())).collect();
+        assert_eq!(
+            summary,
+            vec![
+                (ExposureScope::Market, "BTC-PERP"),
+                (ExposureScope::Token, "BTC"),
+                (ExposureScope::Category, "memes"),
+                (ExposureScope::Position, "BTC-PERP"),
+            ]
+        );
+        assert_eq!(breaches[1].notional, 2_500.0);
+
+        let check = limits.check(&positions(), 10_000.0);
+        assert_eq!(check.level, RiskLevel::High);
+        assert!(check.message.contains("token BTC exposure 2500.00 exceeds 2000.00"));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(ExposureLimits::new().market_limit("X", -1.0).validate().is_err());
+        assert!(ExposureLimits::new().max_position_pct(150.0).validate().is_err());
+        assert!(ExposureLimits::new().max_token_notional(100.0).validate().is_ok());
+    }
+}
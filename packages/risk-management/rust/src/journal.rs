@@ -0,0 +1,467 @@
+//! Persistent trade journal
+//!
+//! [`TradeJournal`] records every closed trade with its strategy tag,
+//! market, entry and exit, fees and notes, and answers queries and summaries
+//! over them. Unlike the circuit breaker's recent-trade buffer it keeps the
+//! full history, and with a [`JournalStore`] it survives restarts: every
+//! entry is appended to the store as it is recorded and the store is loaded
+//! back on open. Stores are behind features: `csv` for [`CsvStore`] and
+//! `sqlite` for [`SqliteStore`].
+//!
+//! ```rust,ignore
+//! let mut journal = TradeJournal::open(CsvStore::new("trades.csv"))?;
+//! journal.record(
+//!     JournalEntry::new("momentum", "ETH-USD", Side::Buy, 2.0, 3_000.0, 3_150.0)
+//!         .with_fees(4.5)
+//!         .with_notes("breakout"),
+//! )?;
+//!
+//! let summary = journal.summary(&JournalQuery::new().strategy("momentum"));
+//! println!("{} trades, net {:.2}", summary.trades, summary.net_pnl);
+//! ```
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::error::Result;
+use crate::metrics::PerformanceMetrics;
+use crate::types::Side;
+
+/// One closed trade
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub strategy: String,
+    pub market: String,
+    pub side: Side,
+    pub size: f64,
+    pub entry_price: f64,
+    pub exit_price: f64,
+    pub opened_at: DateTime<Utc>,
+    pub closed_at: DateTime<Utc>,
+    /// Fees and gas paid over the round trip
+    pub fees: f64,
+    pub notes: Option<String>,
+}
+
+impl JournalEntry {
+    /// Trade opened and closed now, without fees
+    pub fn new(
+        strategy: impl Into<String>,
+        market: impl Into<String>,
+        side: Side,
+        size: f64,
+        entry_price: f64,
+        exit_price: f64,
+    ) -> Self {
+        let now = Utc::now();
+        Self {
+            strategy: strategy.into(),
+            market: market.into(),
+            side,
+            size,
+            entry_price,
+            exit_price,
+            opened_at: now,
+            closed_at: now,
+            fees: 0.0,
+            notes: None,
+        }
+    }
+
+    pub fn with_times(mut self, opened_at: DateTime<Utc>, closed_at: DateTime<Utc>) -> Self {
+        self.opened_at = opened_at;
+        self.closed_at = closed_at;
+        self
+    }
+
+    pub fn with_fees(mut self, fees: f64) -> Self {
+        self.fees = fees;
+        self
+    }
+
+    pub fn with_notes(mut self, notes: impl Into<String>) -> Self {
+        self.notes = Some(notes.into());
+        self
+    }
+
+    /// P&L before fees
+    pub fn gross_pnl(&self) -> f64 {
+        let move_ = self.exit_price - self.entry_price;
+        match self.side {
+            Side::Buy => move_ * self.size,
+            Side::Sell => -move_ * self.size,
+        }
+    }
+
+    /// P&L after fees
+    pub fn net_pnl(&self) -> f64 {
+        self.gross_pnl() - self.fees
+    }
+}
+
+/// Filter over journal entries; unset fields match everything
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct JournalQuery {
+    pub strategy: Option<String>,
+    pub market: Option<String>,
+    /// Closed at or after
+    pub since: Option<DateTime<Utc>>,
+    /// Closed before
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl JournalQuery {
+    /// Query matching every entry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn strategy(mut self, strategy: impl Into<String>) -> Self {
+        self.strategy = Some(strategy.into());
+        self
+    }
+
+    pub fn market(mut self, market: impl Into<String>) -> Self {
+        self.market = Some(market.into());
+        self
+    }
+
+    pub fn since(mut self, since: DateTime<Utc>) -> Self {
+        self.since = Some(since);
+        self
+    }
+
+    pub fn until(mut self, until: DateTime<Utc>) -> Self {
+        self.until = Some(until);
+        self
+    }
+
+    pub fn matches(&self, entry: &JournalEntry) -> bool {
+        self.strategy.as_ref().map_or(true, |s| *s == entry.strategy)
+            && self.market.as_ref().map_or(true, |m| *m == entry.market)
+            && self.since.map_or(true, |t| entry.closed_at >= t)
+            && self.until.map_or(true, |t| entry.closed_at < t)
+    }
+}
+
+/// Totals over a set of journal entries
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct JournalSummary {
+    pub trades: usize,
+    /// Trades with a positive net P&L
+    pub wins: usize,
+    pub win_rate: f64,
+    pub gross_pnl: f64,
+    pub fees: f64,
+    pub net_pnl: f64,
+    /// Net P&L per trade
+    pub avg_net_pnl: f64,
+}
+
+impl JournalSummary {
+    pub fn from_entries<'a>(entries: impl IntoIterator<Item = &'a JournalEntry>) -> Self {
+        let mut summary = entries.into_iter().fold(Self::default(), |mut summary, entry| {
+            summary.trades += 1;
+            if entry.net_pnl() > 0.0 {
+                summary.wins += 1;
+            }
+            summary.gross_pnl += entry.gross_pnl();
+            summary.fees += entry.fees;
+            summary
+        });
+        summary.net_pnl = summary.gross_pnl - summary.fees;
+        if summary.trades > 0 {
+            summary.win_rate = summary.wins as f64 / summary.trades as f64;
+            summary.avg_net_pnl = summary.net_pnl / summary.trades as f64;
+        }
+        summary
+    }
+}
+
+/// Durable storage behind a [`TradeJournal`]
+pub trait JournalStore: Send + fmt::Debug {
+    /// Persist one new entry
+    fn append(&mut self, entry: &JournalEntry) -> Result<()>;
+
+    /// Every stored entry, in the order appended
+    fn load(&self) -> Result<Vec<JournalEntry>>;
+}
+
+/// Full trade history, optionally persisted
+#[derive(Debug, Default)]
+pub struct TradeJournal {
+    entries: Vec<JournalEntry>,
+    store: Option<Box<dyn JournalStore>>,
+}
+
+impl TradeJournal {
+    /// In-memory journal
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Journal persisted to `store`, starting from the entries already in it
+    pub fn open(store: impl JournalStore + 'static) -> Result<Self> {
+        Ok(Self {
+            entries: store.load()?,
+            store: Some(Box::new(store)),
+        })
+    }
+
+    /// Record a closed trade, writing it to the store first
+    pub fn record(&mut self, entry: JournalEntry) -> Result<()> {
+        if let Some(store) = self.store.as_mut() {
+            store.append(&entry)?;
+        }
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    /// Every entry, in the order recorded
+    pub fn entries(&self) -> &[JournalEntry] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Entries matching `query`, in the order recorded
+    pub fn query<'a>(&'a self, query: &'a JournalQuery) -> impl Iterator<Item = &'a JournalEntry> {
+        self.entries.iter().filter(move |e| query.matches(e))
+    }
+
+    pub fn summary(&self, query: &JournalQuery) -> JournalSummary {
+        JournalSummary::from_entries(self.query(query))
+    }
+
+    /// Summary of each strategy's entries matching `query`
+    pub fn summary_by_strategy(&self, query: &JournalQuery) -> BTreeMap<String, JournalSummary> {
+        let mut by_strategy: BTreeMap<&str, Vec<&JournalEntry>> = BTreeMap::new();
+        for entry in self.query(query) {
+            by_strategy.entry(&entry.strategy).or_default().push(entry);
+        }
+        by_strategy
+            .into_iter()
+            .map(|(strategy, entries)| (strategy.to_string(), JournalSummary::from_entries(entries)))
+            .collect()
+    }
+
+    /// Performance metrics of the net P&L of entries matching `query`
+    pub fn metrics(&self, query: &JournalQuery, starting_equity: f64) -> PerformanceMetrics {
+        let pnls: Vec<f64> = self.query(query).map(JournalEntry::net_pnl).collect();
+        PerformanceMetrics::from_pnls(&pnls, starting_equity)
+    }
+}
+
+/// Journal store appending to a CSV file with a header row
+#[cfg(feature = "csv")]
+#[derive(Clone, Debug)]
+pub struct CsvStore {
+    path: std::path::PathBuf,
+}
+
+#[cfg(feature = "csv")]
+impl CsvStore {
+    /// Store at `path`, created on the first append
+    pub fn new(path: impl Into<std::path::PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+#[cfg(feature = "csv")]
+impl JournalStore for CsvStore {
+    fn append(&mut self, entry: &JournalEntry) -> Result<()> {
+        let file = std::fs::OpenOptions::new().create(true).append(true).open(&self.path)?;
+        let is_new = file.metadata()?.len() == 0;
+        let mut writer = csv::WriterBuilder::new().has_headers(is_new).from_writer(file);
+        writer.serialize(entry)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    fn load(&self) -> Result<Vec<JournalEntry>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let mut reader = csv::Reader::from_path(&self.path)?;
+        let entries = reader.deserialize().collect::<std::result::Result<_, _>>()?;
+        Ok(entries)
+    }
+}
+
+/// Journal store in a SQLite `trades` table
+#[cfg(feature = "sqlite")]
+#[derive(Debug)]
+pub struct SqliteStore {
+    conn: rusqlite::Connection,
+}
+
+#[cfg(feature = "sqlite")]
+impl SqliteStore {
+    /// Open or create the database at `path`
+    pub fn open(path: impl AsRef<std::path::Path>) -> Result<Self> {
+        Self::with_connection(rusqlite::Connection::open(path)?)
+    }
+
+    /// Database that lives only as long as the store
+    pub fn in_memory() -> Result<Self> {
+        Self::with_connection(rusqlite::Connection::open_in_memory()?)
+    }
+
+    fn with_connection(conn: rusqlite::Connection) -> Result<Self> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS trades (
+                id INTEGER PRIMARY KEY,
+                strategy TEXT NOT NULL,
+                market TEXT NOT NULL,
+                side TEXT NOT NULL,
+                size REAL NOT NULL,
+                entry_price REAL NOT NULL,
+                exit_price REAL NOT NULL,
+                opened_at TEXT NOT NULL,
+                closed_at TEXT NOT NULL,
+                fees REAL NOT NULL,
+                notes TEXT
+            )",
+            [],
+        )?;
+        Ok(Self { conn })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl JournalStore for SqliteStore {
+    fn append(&mut self, entry: &JournalEntry) -> Result<()> {
+        let side = match entry.side {
+            Side::Buy => "Buy",
+            Side::Sell => "Sell",
+        };
+        self.conn.execute(
+            "INSERT INTO trades
+                (strategy, market, side, size, entry_price, exit_price, opened_at, closed_at, fees, notes)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            rusqlite::params![
+                entry.strategy,
+                entry.market,
+                side,
+                entry.size,
+                entry.entry_price,
+                entry.exit_price,
+                entry.opened_at.to_rfc3339(),
+                entry.closed_at.to_rfc3339(),
+                entry.fees,
+                entry.notes,
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn load(&self) -> Result<Vec<JournalEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT strategy, market, side, size, entry_price, exit_price, opened_at, closed_at, fees, notes
+             FROM trades ORDER BY id",
+        )?;
+        let time = |value: String, column: usize| {
+            DateTime::parse_from_rfc3339(&value)
+                .map(|t| t.with_timezone(&Utc))
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(column, rusqlite::types::Type::Text, e.into()))
+        };
+        let rows = stmt.query_map([], |row| {
+            Ok(JournalEntry {
+                strategy: row.get(0)?,
+                market: row.get(1)?,
+                side: if row.get::<_, String>(2)? == "Sell" { Side::Sell } else { Side::Buy },
+                size: row.get(3)?,
+                entry_price: row.get(4)?,
+                exit_price: row.get(5)?,
+                opened_at: time(row.get(6)?, 6)?,
+                closed_at: time(row.get(7)?, 7)?,
+                fees: row.get(8)?,
+                notes: row.get(9)?,
+            })
+        })?;
+        Ok(rows.collect::<std::result::Result<_, _>>()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    fn trades() -> Vec<JournalEntry> {
+        let start = Utc.with_ymd_and_hms(2025, 3, 3, 9, 0, 0).unwrap();
+        let at = |hours: i64| (start + Duration::hours(hours), start + Duration::hours(hours + 1));
+        let (o1, c1) = at(0);
+        let (o2, c2) = at(24);
+        let (o3, c3) = at(48);
+        vec![
+            JournalEntry::new("momentum", "ETH-USD", Side::Buy, 2.0, 3_000.0, 3_100.0)
+                .with_times(o1, c1)
+                .with_fees(5.0),
+            JournalEntry::new("momentum", "BTC-USD", Side::Sell, 0.1, 60_000.0, 61_000.0)
+                .with_times(o2, c2)
+                .with_fees(3.0)
+                .with_notes("stopped out"),
+            JournalEntry::new("mean-revert", "ETH-USD", Side::Sell, 1.0, 3_200.0, 3_150.0)
+                .with_times(o3, c3)
+                .with_fees(1.0),
+        ]
+    }
+
+    #[test]
+    fn test_query_and_summary() {
+        let mut journal = TradeJournal::new();
+        for entry in trades() {
+            journal.record(entry).unwrap();
+        }
+
+        let momentum = journal.summary(&JournalQuery::new().strategy("momentum"));
+        assert_eq!((momentum.trades, momentum.wins), (2, 1));
+        assert_eq!(momentum.gross_pnl, 100.0);
+        assert_eq!(momentum.net_pnl, 92.0);
+
+        let eth = JournalQuery::new().market("ETH-USD");
+        assert_eq!(journal.query(&eth).count(), 2);
+        let since = JournalQuery::new().since(journal.entries()[1].closed_at);
+        assert_eq!(journal.summary(&since).net_pnl, -103.0 + 49.0);
+
+        let by_strategy = journal.summary_by_strategy(&JournalQuery::new());
+        assert_eq!(by_strategy["mean-revert"].avg_net_pnl, 49.0);
+        assert_eq!(journal.metrics(&eth, 10_000.0).win_rate, 1.0);
+    }
+
+    #[cfg(feature = "csv")]
+    #[test]
+    fn test_csv_store_round_trip() {
+        let path = std::env::temp_dir().join(format!("journal-{}.csv", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let mut journal = TradeJournal::open(CsvStore::new(&path)).unwrap();
+        for entry in trades() {
+            journal.record(entry).unwrap();
+        }
+        let reopened = TradeJournal::open(CsvStore::new(&path)).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(reopened.entries(), &trades()[..]);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_sqlite_store_round_trip() {
+        let mut store = SqliteStore::in_memory().unwrap();
+        for entry in trades() {
+            store.append(&entry).unwrap();
+        }
+        let journal = TradeJournal::open(store).unwrap();
+        assert_eq!(journal.entries(), &trades()[..]);
+        assert_eq!(journal.entries()[1].notes.as_deref(), Some("stopped out"));
+    }
+}
@@ -1,9 +1,21 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::sync::broadcast;
 
+use crate::config::ensure;
+use crate::error::Result;
+use crate::events::{EventEmitter, RiskEvent, RiskSource};
 use crate::types::{RiskCheck, RiskLevel};
 
 /// Kill switch configuration
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct KillSwitchConfig {
     /// Minimum balance before kill
     pub balance_floor: f64,
@@ -13,6 +25,28 @@ pub struct KillSwitchConfig {
     pub max_api_errors: usize,
     /// Enable manual override
     pub manual_override: bool,
+    /// What firing the switch does
+    pub trigger_mode: KillSwitchMode,
+    /// Required data feeds and the longest gap, in milliseconds, allowed
+    /// between their heartbeats
+    pub max_feed_age_ms: BTreeMap<String, u64>,
+    /// Sentinel file whose presence trips the switch, so operators can halt
+    /// trading from outside the process
+    pub kill_file: Option<PathBuf>,
+}
+
+/// Trading allowed by the kill switch
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KillSwitchMode {
+    /// Not triggered
+    #[default]
+    Normal,
+    /// Triggered; only orders that reduce or close a position pass
+    /// pre-trade checks
+    CloseOnly,
+    /// Triggered; no orders
+    Halted,
 }
 
 impl Default for KillSwitchConfig {
@@ -22,10 +56,26 @@ impl Default for KillSwitchConfig {
             max_open_positions: 10,
             max_api_errors: 5,
             manual_override: true,
+            trigger_mode: KillSwitchMode::Halted,
+            max_feed_age_ms: BTreeMap::new(),
+            kill_file: None,
         }
     }
 }
 
+impl KillSwitchConfig {
+    /// Reject values that would make the switch trip always or never
+    pub fn validate(&self) -> Result<()> {
+        ensure(self.balance_floor >= 0.0, "kill_switch.balance_floor must not be negative")?;
+        ensure(self.max_open_positions > 0, "kill_switch.max_open_positions must be at least 1")?;
+        ensure(self.max_api_errors > 0, "kill_switch.max_api_errors must be at least 1")?;
+        ensure(
+            self.max_feed_age_ms.values().all(|ms| *ms > 0),
+            "kill_switch.max_feed_age_ms must be at least 1 for every feed",
+        )
+    }
+}
+
 /// Kill switch for emergency stops
 #[derive(Clone, Debug)]
 pub struct KillSwitch {
@@ -33,9 +83,47 @@ pub struct KillSwitch {
     current_balance: f64,
     open_positions: usize,
     consecutive_errors: usize,
+    /// Latest heartbeat of each data feed
+    feed_heartbeats: BTreeMap<String, DateTime<Utc>>,
+    /// Baseline for feeds that have not sent a heartbeat yet
+    created_at: DateTime<Utc>,
     manually_triggered: bool,
     triggered_at: Option<DateTime<Utc>>,
     trigger_reason: Option<String>,
+    /// Mode while triggered
+    mode: KillSwitchMode,
+    /// Contents of the kill file when it was last polled, if it existed
+    kill_file_contents: Option<String>,
+    events: EventEmitter,
+    hooks: TriggerHooks,
+}
+
+type TriggerHook = Arc<dyn Fn(String) -> Pin<Box<dyn Future<Output = ()> + Send>> + Send + Sync>;
+
+/// Callbacks spawned when the switch fires
+#[derive(Clone, Default)]
+struct TriggerHooks(Vec<TriggerHook>);
+
+impl fmt::Debug for TriggerHooks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "TriggerHooks({})", self.0.len())
+    }
+}
+
+impl TriggerHooks {
+    /// Spawn every hook on the current tokio runtime
+    fn fire(&self, reason: &str) {
+        if self.0.is_empty() {
+            return;
+        }
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            tracing::error!("Kill switch fired outside a tokio runtime; {} trigger hooks not run", self.0.len());
+            return;
+        };
+        for hook in &self.0 {
+            runtime.spawn(hook(reason.to_string()));
+        }
+    }
 }
 
 /// Conditions that can trigger kill switch
@@ -45,6 +133,10 @@ pub enum KillSwitchCondition {
     MaxPositions(usize),
     ApiErrors(usize),
     Manual(String),
+    /// `feed` has not sent a heartbeat within `max_age`
+    StaleData { feed: String, max_age: Duration },
+    /// A sentinel file exists at this path
+    KillFile(PathBuf),
 }
 
 impl KillSwitch {
@@ -60,9 +152,15 @@ impl KillSwitch {
             current_balance: 0.0,
             open_positions: 0,
             consecutive_errors: 0,
+            feed_heartbeats: BTreeMap::new(),
+            created_at: Utc::now(),
             manually_triggered: false,
             triggered_at: None,
             trigger_reason: None,
+            mode: KillSwitchMode::Normal,
+            kill_file_contents: None,
+            events: EventEmitter::new(RiskSource::KillSwitch),
+            hooks: TriggerHooks::default(),
         }
     }
     
@@ -78,20 +176,152 @@ impl KillSwitch {
         self
     }
     
+    /// Watch for `condition`
+    pub fn condition(mut self, condition: KillSwitchCondition) -> Self {
+        match condition {
+            KillSwitchCondition::BalanceFloor(floor) => self.config.balance_floor = floor,
+            KillSwitchCondition::MaxPositions(max) => self.config.max_open_positions = max,
+            KillSwitchCondition::ApiErrors(max) => self.config.max_api_errors = max,
+            KillSwitchCondition::Manual(reason) => self.manual_trigger(reason),
+            KillSwitchCondition::StaleData { feed, max_age } => {
+                self.config.max_feed_age_ms.insert(feed, max_age.num_milliseconds().max(0) as u64);
+            }
+            KillSwitchCondition::KillFile(path) => self.config.kill_file = Some(path),
+        }
+        self
+    }
+    
+    /// Require a heartbeat from `feed` at least every `max_age`
+    pub fn require_feed(self, feed: impl Into<String>, max_age: Duration) -> Self {
+        self.condition(KillSwitchCondition::StaleData { feed: feed.into(), max_age })
+    }
+    
+    /// Trip whenever a file exists at `path`; its contents, if any, are
+    /// the reason
+    ///
+    /// The file is only looked at by [`poll_kill_file`](Self::poll_kill_file)
+    /// or [`RiskManager::watch_kill_file`](crate::RiskManager::watch_kill_file),
+    /// so checks never touch the filesystem.
+    pub fn kill_file(self, path: impl Into<PathBuf>) -> Self {
+        self.condition(KillSwitchCondition::KillFile(path.into()))
+    }
+    
+    /// Configure what firing the switch does
+    pub fn trigger_mode(mut self, mode: KillSwitchMode) -> Self {
+        self.config.trigger_mode = mode;
+        self
+    }
+    
+    pub fn config(&self) -> &KillSwitchConfig {
+        &self.config
+    }
+    
+    /// Replace the config, keeping the current state and any trigger
+    pub fn set_config(&mut self, config: KillSwitchConfig) {
+        if config.kill_file != self.config.kill_file {
+            self.kill_file_contents = None;
+        }
+        self.config = config;
+        self.publish_level();
+    }
+    
+    /// Publish events on `sender`, e.g. one shared with a circuit breaker
+    pub fn with_events(mut self, sender: broadcast::Sender<RiskEvent>) -> Self {
+        self.events.attach(sender);
+        self
+    }
+    
+    /// Run `hook` with the trigger reason each time the switch fires, e.g.
+    /// to cancel open orders and flatten positions
+    ///
+    /// Hooks are spawned on the current tokio runtime, so triggering never
+    /// waits for them.
+    pub fn on_trigger<F, Fut>(mut self, hook: F) -> Self
+    where
+        F: Fn(String) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.hooks.0.push(Arc::new(move |reason| Box::pin(hook(reason))));
+        self
+    }
+    
+    /// Receive this switch's future events
+    pub fn subscribe(&mut self) -> broadcast::Receiver<RiskEvent> {
+        self.events.subscribe()
+    }
+    
     /// Update current state
     pub fn update_state(&mut self, balance: f64, open_positions: usize) {
         self.current_balance = balance;
         self.open_positions = open_positions;
+        self.publish_level();
     }
     
     /// Record API error
     pub fn record_error(&mut self) {
         self.consecutive_errors += 1;
+        self.publish_level();
     }
     
     /// Clear errors (on successful operation)
     pub fn clear_errors(&mut self) {
         self.consecutive_errors = 0;
+        self.publish_level();
+    }
+    
+    /// Path of the kill file, if one is configured
+    pub fn kill_file_path(&self) -> Option<&Path> {
+        self.config.kill_file.as_deref()
+    }
+    
+    /// Record the kill file's contents, or `None` if it does not exist, as
+    /// read by [`read_kill_file`]
+    pub fn record_kill_file(&mut self, contents: Option<String>) {
+        self.kill_file_contents = contents;
+        self.publish_level();
+    }
+    
+    /// Read the kill file with blocking I/O and record what was found; async
+    /// code should use [`RiskManager::poll_kill_file`](crate::RiskManager::poll_kill_file)
+    pub fn poll_kill_file(&mut self) {
+        let Some(path) = self.kill_file_path() else { return };
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => Some(contents),
+            Err(e) => kill_file_error(e),
+        };
+        self.record_kill_file(contents);
+    }
+    
+    /// Record that `feed` delivered fresh data now
+    pub fn heartbeat(&mut self, feed: &str) {
+        self.heartbeat_at(feed, Utc::now());
+    }
+    
+    /// Record that `feed` delivered data stamped `at`
+    pub fn heartbeat_at(&mut self, feed: &str, at: DateTime<Utc>) {
+        let last = self.feed_heartbeats.entry(feed.to_string()).or_insert(at);
+        *last = (*last).max(at);
+        self.publish_level();
+    }
+    
+    /// Required feeds without a heartbeat within their deadline at `now`,
+    /// with the time since their last heartbeat (or since the switch was
+    /// created, if none)
+    pub fn stale_feeds_at(&self, now: DateTime<Utc>) -> Vec<(&str, Duration)> {
+        self.config
+            .max_feed_age_ms
+            .iter()
+            .filter_map(|(feed, max_ms)| {
+                let last = self.feed_heartbeats.get(feed).copied().unwrap_or(self.created_at);
+                let age = now - last;
+                (age.num_milliseconds() > *max_ms as i64).then_some((feed.as_str(), age))
+            })
+            .collect()
+    }
+    
+    fn publish_level(&mut self) {
+        let level = self.check().level;
+        self.events.level(level, Utc::now());
     }
     
     /// Check all conditions
@@ -142,6 +372,32 @@ impl KillSwitch {
             );
         }
         
+        // Check data feeds
+        if let Some((feed, age)) = self.stale_feeds_at(Utc::now()).first() {
+            return RiskCheck::fail(
+                RiskLevel::Critical,
+                format!(
+                    "Stale data: {} not updated for {}ms (max {}ms)",
+                    feed,
+                    age.num_milliseconds(),
+                    self.config.max_feed_age_ms[*feed]
+                )
+            );
+        }
+        
+        // Check sentinel file, as last polled
+        if let (Some(path), Some(reason)) = (&self.config.kill_file, &self.kill_file_contents) {
+            let reason = reason.trim();
+            return RiskCheck::fail(
+                RiskLevel::Critical,
+                if reason.is_empty() {
+                    format!("Kill file present: {}", path.display())
+                } else {
+                    format!("Kill file present: {}: {}", path.display(), reason)
+                }
+            );
+        }
+        
         // Check manual trigger
         if self.manually_triggered {
             return RiskCheck::fail(
@@ -164,8 +420,15 @@ impl KillSwitch {
     
     /// Trigger kill switch
     pub fn trigger(&mut self, reason: impl Into<String>) {
+        let reason = reason.into();
+        if self.triggered_at.is_none() {
+            self.mode = self.config.trigger_mode;
+            self.events.triggered(&reason, Utc::now());
+            self.hooks.fire(&reason);
+        }
         self.triggered_at = Some(Utc::now());
-        self.trigger_reason = Some(reason.into());
+        self.trigger_reason = Some(reason);
+        self.publish_level();
     }
     
     /// Manually trigger
@@ -176,12 +439,49 @@ impl KillSwitch {
         }
     }
     
+    /// Trigger into close-only mode, or relax a halt to close-only so
+    /// positions can be unwound
+    pub fn close_only(&mut self, reason: impl Into<String>) {
+        self.trigger(format!("Close-only: {}", reason.into()));
+        self.mode = KillSwitchMode::CloseOnly;
+    }
+    
+    /// Apply `check` to one order in `market`: in close-only mode, a failed
+    /// check passes if the order reduces a position
+    pub fn check_order(&self, check: RiskCheck, market: &str, reduces: bool) -> RiskCheck {
+        if check.passed || self.mode() != KillSwitchMode::CloseOnly {
+            return check;
+        }
+        if reduces {
+            RiskCheck::pass(format!("Close-only: {} order reduces the position", market))
+        } else {
+            RiskCheck::fail(
+                RiskLevel::Critical,
+                format!("Close-only: {} order would open or increase a position", market),
+            )
+        }
+    }
+    
+    /// Current mode
+    pub fn mode(&self) -> KillSwitchMode {
+        if self.is_triggered() {
+            self.mode
+        } else {
+            KillSwitchMode::Normal
+        }
+    }
+    
     /// Reset kill switch
     pub fn reset(&mut self) {
-        self.triggered_at = None;
+        let was_triggered = self.triggered_at.take().is_some();
+        self.mode = KillSwitchMode::Normal;
         self.trigger_reason = None;
         self.manually_triggered = false;
         self.consecutive_errors = 0;
+        if was_triggered {
+            self.events.reset();
+        }
+        self.publish_level();
     }
     
     /// Check if triggered
@@ -200,9 +500,15 @@ impl KillSwitch {
             is_triggered: self.is_triggered(),
             triggered_at: self.triggered_at,
             reason: self.trigger_reason.clone(),
+            mode: self.mode(),
             current_balance: self.current_balance,
             open_positions: self.open_positions,
             consecutive_errors: self.consecutive_errors,
+            stale_feeds: self
+                .stale_feeds_at(Utc::now())
+                .into_iter()
+                .map(|(feed, _)| feed.to_string())
+                .collect(),
         }
     }
 }
@@ -213,15 +519,31 @@ impl Default for KillSwitch {
     }
 }
 
+/// Contents of the kill file at `path`, or `None` if it does not exist
+pub async fn read_kill_file(path: &Path) -> Option<String> {
+    match tokio::fs::read_to_string(path).await {
+        Ok(contents) => Some(contents),
+        Err(e) => kill_file_error(e),
+    }
+}
+
+/// A kill file that exists but cannot be read still trips the switch
+fn kill_file_error(error: std::io::Error) -> Option<String> {
+    (error.kind() != std::io::ErrorKind::NotFound).then(String::new)
+}
+
 /// Kill switch status
 #[derive(Clone, Debug)]
 pub struct KillSwitchStatus {
     pub is_triggered: bool,
     pub triggered_at: Option<DateTime<Utc>>,
     pub reason: Option<String>,
+    pub mode: KillSwitchMode,
     pub current_balance: f64,
     pub open_positions: usize,
     pub consecutive_errors: usize,
+    /// Required data feeds past their heartbeat deadline
+    pub stale_feeds: Vec<String>,
 }
 
 /// Composite risk guard combining multiple safety mechanisms
@@ -334,9 +656,57 @@ mod tests {
         assert!(!ks.is_triggered());
     }
     
+    #[test]
+    fn test_stale_data() {
+        let mut ks = KillSwitch::new()
+            .condition(KillSwitchCondition::StaleData { feed: "ETH-USD".into(), max_age: Duration::seconds(5) })
+            .require_feed("BTC-USD", Duration::seconds(30));
+        ks.update_state(1000.0, 0);
+        
+        // A feed that never reported is stale once its deadline passes
+        let stale = ks.stale_feeds_at(Utc::now() + Duration::seconds(31));
+        assert_eq!(stale.iter().map(|(feed, _)| *feed).collect::<Vec<_>>(), vec!["BTC-USD", "ETH-USD"]);
+        
+        // The ETH feed's last data is ten seconds old
+        ks.heartbeat_at("ETH-USD", Utc::now() - Duration::seconds(10));
+        let check = ks.check_and_trigger();
+        assert!(!check.passed);
+        assert!(check.message.starts_with("Stale data: ETH-USD"));
+        assert!(ks.is_triggered());
+        
+        ks.heartbeat("ETH-USD");
+        ks.reset();
+        assert!(ks.check().passed);
+    }
+    
+    #[test]
+    fn test_kill_file() {
+        let path = std::env::temp_dir().join(format!("kill-switch-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut ks = KillSwitch::new().kill_file(&path);
+        ks.update_state(1000.0, 0);
+        assert!(ks.check().passed);
+        
+        std::fs::write(&path, "exchange incident\n").unwrap();
+        assert!(ks.check().passed);
+        ks.poll_kill_file();
+        std::fs::remove_file(&path).unwrap();
+        let check = ks.check_and_trigger();
+        assert!(!check.passed);
+        assert!(check.message.ends_with(": exchange incident"));
+        
+        // Removing the file does not undo the trigger
+        ks.poll_kill_file();
+        assert!(ks.is_triggered());
+        ks.reset();
+        assert!(ks.check().passed);
+    }
+    
     #[test]
     fn test_risk_guard() {
-        let ks = KillSwitch::new();
+        // A fresh switch has no balance yet, so it sits below the floor
+        let mut ks = KillSwitch::new();
+        ks.update_state(1000.0, 0);
         let mut guard = RiskGuard::new(ks);
         
         guard.add_check(|| RiskCheck::pass("Custom OK"));
@@ -348,4 +718,26 @@ mod tests {
         assert!(!guard.all_passed());
         assert!(guard.first_failure().is_some());
     }
+    
+    #[tokio::test]
+    async fn test_on_trigger_hooks() {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let mut ks = KillSwitch::new()
+            .balance_floor(100.0)
+            .on_trigger(move |reason| {
+                let tx = tx.clone();
+                async move {
+                    // e.g. cancel open orders and flatten positions
+                    tx.send(reason).unwrap();
+                }
+            });
+        
+        ks.update_state(50.0, 0);
+        ks.check_and_trigger();
+        ks.check_and_trigger();
+        
+        assert_eq!(rx.recv().await.unwrap(), "Balance below floor: 50 < 100");
+        tokio::task::yield_now().await;
+        assert!(rx.try_recv().is_err()); // fires once per trigger
+    }
 }
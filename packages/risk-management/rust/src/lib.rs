@@ -2,25 +2,100 @@
 //!
 //! A modular risk management library for algorithmic trading.
 
+pub mod backtest;
 pub mod circuit_breaker;
+pub mod clock;
+pub mod config;
+pub mod drawdown;
+pub mod error;
+pub mod events;
+pub mod excursion;
+pub mod exposure;
+pub mod journal;
 pub mod kill_switch;
+pub mod manager;
+pub mod margin;
+pub mod metrics;
+pub mod monte_carlo;
+pub mod portfolio;
 pub mod position_sizing;
+pub mod pretrade;
+pub mod registry;
+pub mod reload;
+pub mod rolling;
+pub mod schedule;
 pub mod types;
+pub mod var;
+pub mod velocity;
+pub mod volatility;
+pub mod webhook;
 
-pub use circuit_breaker::{CircuitBreaker, CircuitBreakerConfig};
-pub use kill_switch::{KillSwitch, KillSwitchCondition, KillSwitchConfig};
+pub use backtest::{Backtest, BacktestReport, ReplayEvent, Rule, RuleFiring};
+pub use circuit_breaker::{CircuitBreaker, CircuitBreakerConfig, ExecutionRecord, TradeRecord};
+pub use clock::{ClockCheck, ClockOffset, NtpSource, ServerTime, TimeSource};
+pub use config::{RiskConfig, SizingConfig, SizingParams};
+pub use drawdown::{Drawdown, DrawdownTracker, EquityMark};
+pub use error::{Error, Result};
+pub use events::{RiskEvent, RiskSource};
+pub use excursion::{Distribution, ExcursionStats, ExcursionTracker, TradeExcursion};
+pub use exposure::{ExposureLimits, Position};
+pub use journal::{JournalEntry, JournalQuery, JournalStore, JournalSummary, TradeJournal};
+#[cfg(feature = "csv")]
+pub use journal::CsvStore;
+#[cfg(feature = "sqlite")]
+pub use journal::SqliteStore;
+pub use kill_switch::{KillSwitch, KillSwitchCondition, KillSwitchConfig, KillSwitchMode};
+pub use manager::{RiskManager, RiskStatus, SoftLimits};
+pub use margin::{MarginLimits, MarginPosition};
+pub use metrics::{PerformanceLimits, PerformanceMetrics, TradeLog};
+pub use monte_carlo::{MonteCarlo, SimulationReport};
+pub use portfolio::{Portfolio, PortfolioLimits};
 pub use position_sizing::{
-    PositionSizer, KellySizing, FixedFractionalSizing, 
-    VolatilityBasedSizing, SizingStrategy
+    PositionSizer, KellySizing, DrawdownKellySizing, FixedFractionalSizing,
+    FixedRatioSizing, OptimalFSizing, VolatilityBasedSizing, RiskParitySizing,
+    SizingContext, SizingStrategy
 };
-pub use types::{RiskCheck, RiskLevel, RiskReport};
+pub use pretrade::{
+    ExpectedValueCheck, OrderIntent, PreTradeCheck, PreTradeContext, PreTradeDecision, PreTradePipeline,
+};
+pub use registry::{CircuitBreakerRegistry, RegistryStatus};
+pub use reload::ConfigWatcher;
+pub use rolling::{RollingStats, RollingWindow};
+pub use schedule::{Blackout, ScheduleCheck, TradingWindow};
+pub use types::{Fill, Order, RiskCheck, RiskLevel, RiskReport, Side, TradingLimits};
+pub use var::{PositionReturns, VarConfig, VarEstimate, VarMethod, VarModel};
+pub use velocity::VelocityLimits;
+pub use volatility::{annualize, Atr, Candle, EwmaVolatility, RealizedVolatility};
+pub use webhook::KillWebhook;
 
 /// Re-export commonly used types
 pub mod prelude {
     pub use crate::{
+        backtest::*,
         circuit_breaker::*,
+        clock::*,
+        config::*,
+        drawdown::*,
+        events::*,
+        excursion::*,
+        exposure::*,
+        journal::*,
         kill_switch::*,
+        manager::*,
+        margin::*,
+        metrics::*,
+        monte_carlo::*,
+        portfolio::*,
         position_sizing::*,
+        pretrade::*,
+        registry::*,
+        reload::*,
+        rolling::*,
+        schedule::*,
         types::*,
+        var::*,
+        velocity::*,
+        volatility::*,
+        webhook::*,
     };
 }
@@ -0,0 +1,524 @@
+//! Shared risk state
+//!
+//! [`RiskManager`] owns a circuit breaker, kill switch and position sizer
+//! behind an async lock. It is cheap to clone, so each strategy task can hold
+//! its own handle while all of them check and update one risk state.
+//!
+//! Between all clear and halted there is a reduced-size mode: once losses
+//! reach the [`SoftLimits`], position sizes are scaled down instead of
+//! stopping trading.
+//!
+//! ```rust,ignore
+//! let risk = RiskManager::new(
+//!     CircuitBreaker::new().max_consecutive_losses(3),
+//!     KillSwitch::new().balance_floor(500.0),
+//!     PositionSizer::new(FixedFractionalSizing::moderate()),
+//! );
+//! risk.update_account(10_000.0, 2).await;
+//!
+//! let report = risk.pre_trade_check(&Order::new("BTC-USD", Side::Buy, 0.01, 65_000.0)).await;
+//! if report.all_passed() {
+//!     // place the order, then
+//!     risk.record_fill(&fill).await;
+//! }
+//! ```
+
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::{broadcast, RwLock};
+
+use crate::circuit_breaker::{CircuitBreaker, CircuitBreakerStatus};
+use crate::config::{ensure, RiskConfig};
+use crate::error::Result;
+use crate::events::{RiskEvent, EVENT_CAPACITY};
+use crate::kill_switch::{read_kill_file, KillSwitch, KillSwitchMode, KillSwitchStatus};
+use crate::position_sizing::PositionSizer;
+use crate::types::{Fill, Order, RiskCheck, RiskLevel, RiskReport};
+
+/// Thresholds below the circuit breaker's at which position sizes shrink
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SoftLimits {
+    /// Losing streak that reduces sizes; off if `None`
+    pub consecutive_losses: Option<usize>,
+    /// Daily drawdown percentage that reduces sizes; off if `None`
+    pub daily_drawdown_pct: Option<f64>,
+    /// Multiplier on position sizes while reduced
+    pub size_factor: f64,
+}
+
+impl Default for SoftLimits {
+    fn default() -> Self {
+        Self {
+            consecutive_losses: None,
+            daily_drawdown_pct: None,
+            size_factor: 0.5,
+        }
+    }
+}
+
+impl SoftLimits {
+    /// Reject factors outside (0, 1] and empty thresholds
+    pub fn validate(&self) -> Result<()> {
+        ensure(
+            self.size_factor > 0.0 && self.size_factor <= 1.0,
+            "soft_limits.size_factor must be in (0, 1]",
+        )?;
+        ensure(
+            self.consecutive_losses.map_or(true, |n| n > 0),
+            "soft_limits.consecutive_losses must be at least 1",
+        )?;
+        ensure(
+            self.daily_drawdown_pct.map_or(true, |pct| pct > 0.0),
+            "soft_limits.daily_drawdown_pct must be positive",
+        )
+    }
+
+    /// Why sizes are reduced, if any soft limit is reached
+    fn reason(&self, circuit_breaker: &CircuitBreakerStatus) -> Option<String> {
+        if let Some(max) = self.consecutive_losses.filter(|max| circuit_breaker.consecutive_losses >= *max) {
+            return Some(format!("{} consecutive losses (soft limit {})", circuit_breaker.consecutive_losses, max));
+        }
+        let drawdown = circuit_breaker.daily_drawdown_pct?;
+        let max = self.daily_drawdown_pct.filter(|max| drawdown >= *max)?;
+        Some(format!("daily drawdown {:.2}% (soft limit {:.2}%)", drawdown, max))
+    }
+}
+
+struct RiskState {
+    circuit_breaker: CircuitBreaker,
+    kill_switch: KillSwitch,
+    sizer: PositionSizer,
+    soft_limits: SoftLimits,
+}
+
+impl RiskState {
+    fn balance(&self) -> f64 {
+        self.kill_switch.status().current_balance
+    }
+
+    /// Size multiplier and, when reduced, the reason
+    fn size_factor(&self) -> (f64, Option<String>) {
+        match self.soft_limits.reason(&self.circuit_breaker.status()) {
+            Some(reason) => (self.soft_limits.size_factor, Some(reason)),
+            None => (1.0, None),
+        }
+    }
+
+    fn max_position_size(&self) -> f64 {
+        self.sizer.calculate(self.balance()) * self.size_factor().0
+    }
+
+    fn size_check(&self, order: &Order) -> RiskCheck {
+        let (factor, reason) = self.size_factor();
+        let limit = self.sizer.calculate(self.balance()) * factor;
+        let reduced = reason
+            .map(|reason| format!(" (reduced to {:.0}%: {})", factor * 100.0, reason))
+            .unwrap_or_default();
+        let notional = order.notional();
+        if notional > limit {
+            RiskCheck::fail(
+                RiskLevel::High,
+                format!("Order notional {:.2} exceeds position size limit {:.2}{}", notional, limit, reduced),
+            )
+        } else {
+            RiskCheck::pass(format!("Position size OK{}", reduced))
+        }
+    }
+}
+
+/// Risk state shared across tasks
+#[derive(Clone)]
+pub struct RiskManager {
+    state: Arc<RwLock<RiskState>>,
+    events: broadcast::Sender<RiskEvent>,
+}
+
+impl RiskManager {
+    /// Manager over the given components
+    ///
+    /// Their events are published on the manager's channel; see
+    /// [`subscribe`](Self::subscribe).
+    pub fn new(circuit_breaker: CircuitBreaker, kill_switch: KillSwitch, sizer: PositionSizer) -> Self {
+        Self::with_soft_limits(circuit_breaker, kill_switch, sizer, SoftLimits::default())
+    }
+
+    /// Manager that reduces position sizes at `soft_limits`
+    pub fn with_soft_limits(
+        circuit_breaker: CircuitBreaker,
+        kill_switch: KillSwitch,
+        sizer: PositionSizer,
+        soft_limits: SoftLimits,
+    ) -> Self {
+        let (events, _) = broadcast::channel(EVENT_CAPACITY);
+        Self {
+            state: Arc::new(RwLock::new(RiskState {
+                circuit_breaker: circuit_breaker.with_events(events.clone()),
+                kill_switch: kill_switch.with_events(events.clone()),
+                sizer,
+                soft_limits,
+            })),
+            events,
+        }
+    }
+
+    /// Replace the soft limits
+    pub async fn set_soft_limits(&self, soft_limits: SoftLimits) {
+        self.state.write().await.soft_limits = soft_limits;
+    }
+
+    /// Apply the circuit breaker, kill switch and soft limit sections of
+    /// `config`, keeping the current risk state
+    ///
+    /// The sizer is left alone, since rebuilding it would reset strategies
+    /// that track their own results.
+    pub async fn apply_config(&self, config: &RiskConfig) {
+        let mut state = self.state.write().await;
+        state.circuit_breaker.set_config(config.circuit_breaker.clone());
+        state.kill_switch.set_config(config.kill_switch.clone());
+        state.soft_limits = config.soft_limits.clone();
+    }
+
+    pub(crate) fn event_sender(&self) -> broadcast::Sender<RiskEvent> {
+        self.events.clone()
+    }
+
+    /// Receive future circuit breaker and kill switch events
+    pub fn subscribe(&self) -> broadcast::Receiver<RiskEvent> {
+        self.events.subscribe()
+    }
+
+    /// Run the kill switch, circuit breaker and position size checks for
+    /// `order`
+    ///
+    /// A failing kill switch or circuit breaker is triggered, so it keeps
+    /// failing until reset or cooled down. The circuit breaker also fails
+    /// while the order's market is on its own losing streak. A close-only
+    /// kill switch passes orders marked as [reducing](Order::reducing).
+    pub async fn pre_trade_check(&self, order: &Order) -> RiskReport {
+        let mut state = self.state.write().await;
+        let kill_switch = state.kill_switch.check_and_trigger();
+        let kill_switch = state.kill_switch.check_order(kill_switch, &order.market, order.reduces_position);
+        let mut circuit_breaker = state.circuit_breaker.check_and_trigger();
+        if circuit_breaker.passed {
+            circuit_breaker = state.circuit_breaker.check_key(&order.market);
+        }
+        RiskReport::new()
+            .add_check("Kill switch", kill_switch)
+            .add_check("Circuit breaker", circuit_breaker)
+            .add_check("Position size", state.size_check(order))
+    }
+
+    /// Record an executed trade
+    ///
+    /// Fills show the exchange is reachable, so API errors are cleared.
+    pub async fn record_fill(&self, fill: &Fill) {
+        let mut state = self.state.write().await;
+        state.circuit_breaker.record_trade_for(&fill.market, fill.pnl);
+        state.kill_switch.clear_errors();
+    }
+
+    /// Record an order's placement latency and slippage in bps for the
+    /// circuit breaker's execution-quality limits
+    pub async fn record_execution(&self, latency: Duration, slippage_bps: f64) {
+        self.state.write().await.circuit_breaker.record_execution(latency, slippage_bps);
+    }
+
+    /// Update the balance and open position count the checks use
+    ///
+    /// The first balance of each UTC day is the circuit breaker's baseline
+    /// for daily drawdown.
+    pub async fn update_account(&self, balance: f64, open_positions: usize) {
+        let mut state = self.state.write().await;
+        state.kill_switch.update_state(balance, open_positions);
+        state.circuit_breaker.update_balance(balance);
+    }
+
+    /// Record fresh data from `feed` for the kill switch's stale-data check
+    pub async fn heartbeat(&self, feed: &str) {
+        self.state.write().await.kill_switch.heartbeat(feed);
+    }
+
+    /// Read the kill switch's kill file, if one is configured, and record
+    /// whether it exists
+    ///
+    /// The file is read without holding the risk state's lock.
+    pub async fn poll_kill_file(&self) {
+        let Some(path) = self.state.read().await.kill_switch.kill_file_path().map(Path::to_path_buf) else {
+            return;
+        };
+        let contents = read_kill_file(&path).await;
+        let mut state = self.state.write().await;
+        // The config may have been reloaded with another path meanwhile
+        if state.kill_switch.kill_file_path() == Some(path.as_path()) {
+            state.kill_switch.record_kill_file(contents);
+        }
+    }
+
+    /// [`poll_kill_file`](Self::poll_kill_file) every `interval`; runs until
+    /// the task is dropped
+    pub async fn watch_kill_file(self, interval: std::time::Duration) {
+        loop {
+            self.poll_kill_file().await;
+            tokio::time::sleep(interval).await;
+        }
+    }
+
+    /// Record a failed exchange call
+    pub async fn record_error(&self) {
+        self.state.write().await.kill_switch.record_error();
+    }
+
+    /// Largest order notional the sizer allows at the current balance,
+    /// after any soft-limit reduction
+    pub async fn max_position_size(&self) -> f64 {
+        self.state.read().await.max_position_size()
+    }
+
+    /// Halt trading until [`reset`](Self::reset)
+    pub async fn trigger_kill_switch(&self, reason: impl Into<String>) {
+        self.state.write().await.kill_switch.manual_trigger(reason);
+    }
+
+    /// Reset the kill switch and circuit breaker
+    pub async fn reset(&self) {
+        let mut state = self.state.write().await;
+        state.kill_switch.reset();
+        state.circuit_breaker.reset();
+    }
+
+    /// Snapshot of the risk state
+    pub async fn status(&self) -> RiskStatus {
+        let state = self.state.read().await;
+        let kill_switch = state.kill_switch.status();
+        let circuit_breaker = state.circuit_breaker.status();
+        let (size_factor, reduced_because) = state.size_factor();
+        let level = if kill_switch.is_triggered || circuit_breaker.is_open {
+            RiskLevel::Critical
+        } else {
+            let reduced = if reduced_because.is_some() { RiskLevel::Elevated } else { RiskLevel::Normal };
+            state.kill_switch.check().level.max(state.circuit_breaker.check().level).max(reduced)
+        };
+        RiskStatus {
+            level,
+            max_position_size: state.max_position_size(),
+            size_factor,
+            reduced_because,
+            kill_switch,
+            circuit_breaker,
+        }
+    }
+}
+
+/// Snapshot returned by [`RiskManager::status`]
+#[derive(Clone, Debug)]
+pub struct RiskStatus {
+    /// Worst level across the kill switch and circuit breaker; at least
+    /// Elevated while sizes are reduced
+    pub level: RiskLevel,
+    pub max_position_size: f64,
+    /// 1.0, or the soft limits' factor while sizes are reduced
+    pub size_factor: f64,
+    /// Soft limit reached, if sizes are reduced
+    pub reduced_because: Option<String>,
+    pub kill_switch: KillSwitchStatus,
+    pub circuit_breaker: CircuitBreakerStatus,
+}
+
+impl RiskStatus {
+    /// Whether new orders would be allowed
+    pub fn allows_trading(&self) -> bool {
+        self.level.allows_trading()
+    }
+
+    /// Whether orders that reduce a position would be allowed, which a
+    /// close-only kill switch still permits
+    pub fn allows_reducing(&self) -> bool {
+        self.allows_trading() || (self.kill_switch.mode == KillSwitchMode::CloseOnly && !self.circuit_breaker.is_open)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::position_sizing::FixedFractionalSizing;
+    use crate::types::Side;
+
+    fn manager() -> RiskManager {
+        RiskManager::new(
+            CircuitBreaker::new().max_consecutive_losses(2),
+            KillSwitch::new().balance_floor(100.0),
+            PositionSizer::new(FixedFractionalSizing::moderate()),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_pre_trade_check() {
+        let risk = manager();
+        risk.update_account(10_000.0, 1).await;
+
+        let small = Order::new("BTC-USD", Side::Buy, 0.002, 50_000.0);
+        assert!(risk.pre_trade_check(&small).await.all_passed());
+
+        let large = Order::new("BTC-USD", Side::Buy, 0.01, 50_000.0);
+        let report = risk.pre_trade_check(&large).await;
+        assert_eq!(report.failed_checks().len(), 1);
+        assert_eq!(report.failed_checks()[0].0, "Position size");
+    }
+
+    #[tokio::test]
+    async fn test_shared_across_tasks() {
+        let risk = manager();
+        risk.update_account(10_000.0, 0).await;
+
+        let tasks: Vec<_> = (0..2)
+            .map(|_| {
+                let risk = risk.clone();
+                tokio::spawn(async move { risk.record_fill(&Fill::new("ETH-USD", Side::Sell, 1.0, 3_000.0, -25.0)).await })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        let order = Order::new("ETH-USD", Side::Buy, 0.01, 3_000.0);
+        assert!(!risk.pre_trade_check(&order).await.all_passed());
+        let status = risk.status().await;
+        assert_eq!(status.circuit_breaker.consecutive_losses, 2);
+        assert!(!status.allows_trading());
+
+        risk.reset().await;
+        assert!(risk.pre_trade_check(&order).await.all_passed());
+    }
+
+    #[tokio::test]
+    async fn test_apply_config() {
+        let risk = manager();
+        risk.update_account(10_000.0, 0).await;
+        risk.record_fill(&Fill::new("ETH-USD", Side::Sell, 1.0, 3_000.0, -25.0)).await;
+        let order = Order::new("BTC-USD", Side::Buy, 0.002, 50_000.0);
+        assert!(risk.pre_trade_check(&order).await.all_passed());
+
+        let config = RiskConfig::from_toml_str("[circuit_breaker]\nmax_consecutive_losses = 1\n").unwrap();
+        risk.apply_config(&config).await;
+        assert!(!risk.pre_trade_check(&order).await.all_passed());
+        assert_eq!(risk.status().await.circuit_breaker.consecutive_losses, 1);
+    }
+
+    #[tokio::test]
+    async fn test_events() {
+        use crate::events::RiskSource;
+
+        let risk = manager();
+        let mut events = risk.subscribe();
+        risk.update_account(10_000.0, 0).await;
+        risk.trigger_kill_switch("maintenance").await;
+        risk.reset().await;
+
+        let mut received = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            received.push(event);
+        }
+        assert!(matches!(
+            &received[0],
+            RiskEvent::Triggered { source: RiskSource::KillSwitch, reason, .. } if reason == "Manual: maintenance"
+        ));
+        assert!(matches!(
+            received[1],
+            RiskEvent::LevelChanged { source: RiskSource::KillSwitch, to: RiskLevel::Critical, .. }
+        ));
+        assert!(matches!(received[2], RiskEvent::Reset { source: RiskSource::KillSwitch, .. }));
+        assert!(matches!(
+            received[3],
+            RiskEvent::LevelChanged { from: RiskLevel::Critical, to: RiskLevel::Normal, .. }
+        ));
+        assert_eq!(received.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_reduced_size_mode() {
+        let risk = RiskManager::with_soft_limits(
+            CircuitBreaker::new().max_consecutive_losses(4),
+            KillSwitch::new().balance_floor(100.0),
+            PositionSizer::new(FixedFractionalSizing::moderate()),
+            SoftLimits {
+                consecutive_losses: Some(2),
+                ..SoftLimits::default()
+            },
+        );
+        risk.update_account(10_000.0, 0).await;
+        let full = risk.max_position_size().await;
+        let order = Order::new("BTC-USD", Side::Buy, 0.003, 50_000.0);
+        assert!(risk.pre_trade_check(&order).await.all_passed());
+
+        for _ in 0..2 {
+            risk.record_fill(&Fill::new("BTC-USD", Side::Sell, 0.001, 50_000.0, -10.0)).await;
+        }
+        let status = risk.status().await;
+        assert_eq!(status.size_factor, 0.5);
+        assert_eq!(status.level, RiskLevel::Elevated);
+        assert!(status.allows_trading());
+        assert_eq!(status.max_position_size, full * 0.5);
+
+        let report = risk.pre_trade_check(&order).await;
+        assert_eq!(report.failed_checks()[0].0, "Position size");
+        assert!(report.failed_checks()[0].1.message.contains("reduced to 50%: 2 consecutive losses"));
+
+        risk.record_fill(&Fill::new("BTC-USD", Side::Sell, 0.001, 50_000.0, 30.0)).await;
+        assert_eq!(risk.status().await.size_factor, 1.0);
+        assert!(risk.pre_trade_check(&order).await.all_passed());
+    }
+
+    #[tokio::test]
+    async fn test_close_only_kill_switch() {
+        let risk = RiskManager::new(
+            CircuitBreaker::new(),
+            KillSwitch::new().max_positions(2).trigger_mode(KillSwitchMode::CloseOnly),
+            PositionSizer::new(FixedFractionalSizing::moderate()),
+        );
+        risk.update_account(10_000.0, 3).await;
+
+        let open = Order::new("BTC-USD", Side::Buy, 0.002, 50_000.0);
+        let report = risk.pre_trade_check(&open).await;
+        assert_eq!(report.failed_checks().len(), 1);
+        assert!(report.failed_checks()[0].1.message.starts_with("Close-only: "));
+
+        let close = Order::new("BTC-USD", Side::Sell, 0.002, 50_000.0).reducing();
+        assert!(risk.pre_trade_check(&close).await.all_passed());
+
+        let status = risk.status().await;
+        assert_eq!(status.kill_switch.mode, KillSwitchMode::CloseOnly);
+        assert!(!status.allows_trading());
+        assert!(status.allows_reducing());
+
+        risk.update_account(10_000.0, 1).await;
+        risk.reset().await;
+        assert!(risk.pre_trade_check(&open).await.all_passed());
+        assert_eq!(KillSwitchMode::default(), KillSwitchMode::Normal);
+    }
+
+    #[tokio::test]
+    async fn test_kill_file() {
+        let path = std::env::temp_dir().join(format!("risk-manager-kill-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let risk = RiskManager::new(
+            CircuitBreaker::new(),
+            KillSwitch::new().kill_file(&path),
+            PositionSizer::new(FixedFractionalSizing::moderate()),
+        );
+        risk.update_account(10_000.0, 0).await;
+        let order = Order::new("BTC-USD", Side::Buy, 0.002, 50_000.0);
+        risk.poll_kill_file().await;
+        assert!(risk.pre_trade_check(&order).await.all_passed());
+
+        std::fs::write(&path, "exchange incident\n").unwrap();
+        // Checks only see the file once it has been polled
+        assert!(risk.pre_trade_check(&order).await.all_passed());
+        risk.poll_kill_file().await;
+        std::fs::remove_file(&path).unwrap();
+        let report = risk.pre_trade_check(&order).await;
+        assert!(report.failed_checks()[0].1.message.ends_with(": exchange incident"));
+    }
+}
@@ -0,0 +1,256 @@
+//! Leverage and liquidation risk
+//!
+//! For perpetual futures venues: [`MarginPosition`] computes an isolated
+//! position's liquidation price from its entry price, leverage and the
+//! maintenance margin rate, and [`MarginLimits`] checks leverage per position
+//! and across the account, account equity against maintenance margin, and
+//! how close each mark price is to liquidation. Checks turn Critical as the
+//! price nears liquidation.
+//!
+//! ```rust,ignore
+//! let limits = MarginLimits::default().max_leverage(5.0);
+//! let position = MarginPosition::new("BTC-PERP", 0.5, 60_000.0, 5.0).with_mark_price(50_500.0);
+//!
+//! println!("liquidation at {:.0}", position.liquidation_price(limits.maintenance_margin_rate));
+//! let report = limits.report(equity, &[position]);
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::ensure;
+use crate::error::Result;
+use crate::types::{RiskCheck, RiskLevel, RiskReport};
+
+/// An isolated-margin perpetual position
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MarginPosition {
+    pub market: String,
+    /// Contracts, negative for shorts
+    pub size: f64,
+    pub entry_price: f64,
+    pub mark_price: f64,
+    pub leverage: f64,
+}
+
+impl MarginPosition {
+    /// Position marked at its entry price
+    pub fn new(market: impl Into<String>, size: f64, entry_price: f64, leverage: f64) -> Self {
+        Self {
+            market: market.into(),
+            size,
+            entry_price,
+            mark_price: entry_price,
+            leverage,
+        }
+    }
+
+    pub fn with_mark_price(mut self, price: f64) -> Self {
+        self.mark_price = price;
+        self
+    }
+
+    /// Gross value at the mark price
+    pub fn notional(&self) -> f64 {
+        self.size.abs() * self.mark_price
+    }
+
+    /// Collateral posted at entry
+    pub fn initial_margin(&self) -> f64 {
+        self.size.abs() * self.entry_price / self.leverage
+    }
+
+    pub fn unrealized_pnl(&self) -> f64 {
+        self.size * (self.mark_price - self.entry_price)
+    }
+
+    /// Price at which the margin left equals maintenance margin, ignoring
+    /// fees and funding
+    pub fn liquidation_price(&self, maintenance_margin_rate: f64) -> f64 {
+        let buffer = 1.0 / self.leverage - maintenance_margin_rate;
+        if self.size >= 0.0 {
+            self.entry_price * (1.0 - buffer)
+        } else {
+            self.entry_price * (1.0 + buffer)
+        }
+    }
+
+    /// Adverse move from the mark price to liquidation, in percent; 0 or
+    /// less once past it
+    pub fn distance_to_liquidation_pct(&self, maintenance_margin_rate: f64) -> f64 {
+        let liquidation = self.liquidation_price(maintenance_margin_rate);
+        let distance = if self.size >= 0.0 {
+            self.mark_price - liquidation
+        } else {
+            liquidation - self.mark_price
+        };
+        distance / self.mark_price * 100.0
+    }
+}
+
+/// Leverage, maintenance margin and liquidation distance limits
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MarginLimits {
+    /// Highest leverage for any one position
+    pub max_leverage: f64,
+    /// Highest total notional over account equity
+    pub max_account_leverage: f64,
+    /// Maintenance margin as a fraction of notional, e.g. 0.005
+    pub maintenance_margin_rate: f64,
+    /// Distance to liquidation, in percent, below which the check fails High
+    pub warn_distance_pct: f64,
+    /// Distance to liquidation, in percent, below which it fails Critical
+    pub critical_distance_pct: f64,
+}
+
+impl Default for MarginLimits {
+    fn default() -> Self {
+        Self {
+            max_leverage: 10.0,
+            max_account_leverage: 3.0,
+            maintenance_margin_rate: 0.005,
+            warn_distance_pct: 10.0,
+            critical_distance_pct: 3.0,
+        }
+    }
+}
+
+impl MarginLimits {
+    pub fn max_leverage(mut self, max: f64) -> Self {
+        self.max_leverage = max;
+        self
+    }
+
+    pub fn max_account_leverage(mut self, max: f64) -> Self {
+        self.max_account_leverage = max;
+        self
+    }
+
+    pub fn maintenance_margin_rate(mut self, rate: f64) -> Self {
+        self.maintenance_margin_rate = rate;
+        self
+    }
+
+    /// Distances to liquidation, in percent, that fail High and Critical
+    pub fn liquidation_distance_pct(mut self, warn: f64, critical: f64) -> Self {
+        self.warn_distance_pct = warn;
+        self.critical_distance_pct = critical;
+        self
+    }
+
+    /// Reject limits that can never pass or never fail
+    pub fn validate(&self) -> Result<()> {
+        ensure(self.max_leverage >= 1.0, "margin.max_leverage must be at least 1")?;
+        ensure(self.max_account_leverage > 0.0, "margin.max_account_leverage must be positive")?;
+        ensure(
+            self.maintenance_margin_rate >= 0.0 && self.maintenance_margin_rate < 1.0 / self.max_leverage,
+            "margin.maintenance_margin_rate must be in [0, 1 / max_leverage)",
+        )?;
+        ensure(
+            self.critical_distance_pct >= 0.0 && self.critical_distance_pct <= self.warn_distance_pct,
+            "margin.critical_distance_pct must be between 0 and warn_distance_pct",
+        )
+    }
+
+    /// Leverage and liquidation distance of one position
+    pub fn check_position(&self, position: &MarginPosition) -> RiskCheck {
+        if position.leverage > self.max_leverage {
+            return RiskCheck::fail(
+                RiskLevel::High,
+                format!(
+                    "{} leverage {:.1}x exceeds {:.1}x",
+                    position.market, position.leverage, self.max_leverage
+                ),
+            );
+        }
+
+        let liquidation = position.liquidation_price(self.maintenance_margin_rate);
+        let distance = position.distance_to_liquidation_pct(self.maintenance_margin_rate);
+        let message = format!(
+            "{} mark {} is {:.2}% from liquidation at {:.2}",
+            position.market, position.mark_price, distance, liquidation
+        );
+        if distance <= self.critical_distance_pct {
+            RiskCheck::fail(RiskLevel::Critical, message)
+        } else if distance <= self.warn_distance_pct {
+            RiskCheck::fail(RiskLevel::High, message)
+        } else {
+            RiskCheck::pass(message)
+        }
+    }
+
+    /// Account leverage and equity against maintenance margin
+    pub fn check_account(&self, equity: f64, positions: &[MarginPosition]) -> RiskCheck {
+        let notional: f64 = positions.iter().map(MarginPosition::notional).sum();
+        let maintenance = notional * self.maintenance_margin_rate;
+        if equity <= maintenance {
+            return RiskCheck::fail(
+                RiskLevel::Critical,
+                format!("Equity {:.2} at or below maintenance margin {:.2}", equity, maintenance),
+            );
+        }
+        let leverage = notional / equity;
+        if leverage > self.max_account_leverage {
+            RiskCheck::fail(
+                RiskLevel::High,
+                format!(
+                    "Account leverage {:.2}x exceeds {:.2}x",
+                    leverage, self.max_account_leverage
+                ),
+            )
+        } else {
+            RiskCheck::pass(format!("Account leverage {:.2}x", leverage))
+        }
+    }
+
+    /// Account check, then one check per position named by its market
+    pub fn report(&self, equity: f64, positions: &[MarginPosition]) -> RiskReport {
+        positions.iter().fold(
+            RiskReport::new().add_check("Margin", self.check_account(equity, positions)),
+            |report, position| report.add_check(format!("Liquidation {}", position.market), self.check_position(position)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_liquidation_price() {
+        let long = MarginPosition::new("BTC-PERP", 1.0, 60_000.0, 10.0);
+        // 10x leaves a 10% buffer, less 0.5% maintenance
+        assert!((long.liquidation_price(0.005) - 54_300.0).abs() < 1e-6);
+        assert!((long.distance_to_liquidation_pct(0.005) - 9.5).abs() < 1e-9);
+
+        let short = MarginPosition::new("BTC-PERP", -1.0, 60_000.0, 10.0).with_mark_price(61_000.0);
+        assert!((short.liquidation_price(0.005) - 65_700.0).abs() < 1e-6);
+        assert_eq!(short.unrealized_pnl(), -1_000.0);
+        assert_eq!(short.initial_margin(), 6_000.0);
+    }
+
+    #[test]
+    fn test_checks_escalate_near_liquidation() {
+        let limits = MarginLimits::default().max_leverage(5.0);
+        let position = MarginPosition::new("ETH-PERP", 10.0, 3_000.0, 5.0);
+        // Liquidation at 2_415
+        assert!(limits.check_position(&position).passed);
+
+        let near = limits.check_position(&position.clone().with_mark_price(2_600.0));
+        assert_eq!((near.passed, near.level), (false, RiskLevel::High));
+        let nearer = limits.check_position(&position.clone().with_mark_price(2_450.0));
+        assert_eq!(nearer.level, RiskLevel::Critical);
+
+        let levered = limits.check_position(&MarginPosition::new("SOL-PERP", 1.0, 150.0, 20.0));
+        assert!(levered.message.contains("leverage 20.0x"));
+
+        let positions = [position];
+        assert!(limits.check_account(15_000.0, &positions).passed);
+        assert_eq!(limits.check_account(5_000.0, &positions).level, RiskLevel::High);
+        assert_eq!(limits.check_account(100.0, &positions).level, RiskLevel::Critical);
+
+        let report = limits.report(5_000.0, &positions);
+        assert_eq!(report.checks.len(), 2);
+        assert_eq!(report.failed_checks()[0].0, "Margin");
+    }
+}
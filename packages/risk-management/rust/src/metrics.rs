@@ -0,0 +1,358 @@
+//! Performance metrics
+//!
+//! [`PerformanceMetrics`] summarizes a run of closed trades: win rate,
+//! expectancy, profit factor and per-trade Sharpe, Sortino and Calmar ratios.
+//! It can be computed from the circuit breaker's recent trades or from a
+//! longer [`TradeLog`], over everything or a rolling window of the latest
+//! trades. [`PerformanceLimits`] turns the metrics into a [`RiskCheck`], e.g.
+//! for a [`RiskGuard`](crate::kill_switch::RiskGuard).
+//!
+//! ```rust,ignore
+//! let mut log = TradeLog::new(1_000);
+//! log.record(120.0);
+//! log.record(-80.0);
+//!
+//! let metrics = log.rolling(50, 10_000.0);
+//! println!("win rate {:.0}%, profit factor {:?}", metrics.win_rate * 100.0, metrics.profit_factor);
+//! ```
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+use crate::circuit_breaker::{CircuitBreaker, TradeRecord};
+use crate::types::{RiskCheck, RiskLevel};
+
+/// Summary statistics of a run of trades
+///
+/// Ratios are per trade, not annualized, with each trade's return taken
+/// against the equity before it. They are `None` when undefined, e.g. a
+/// profit factor with no losing trades.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PerformanceMetrics {
+    pub trades: usize,
+    /// Fraction of profitable trades, 0 to 1
+    pub win_rate: f64,
+    /// Mean P&L of profitable trades
+    pub avg_win: f64,
+    /// Mean loss of losing trades, as a positive number
+    pub avg_loss: f64,
+    /// Mean P&L per trade
+    pub expectancy: f64,
+    pub total_pnl: f64,
+    /// Gross profit over gross loss
+    pub profit_factor: Option<f64>,
+    /// Mean return over its standard deviation
+    pub sharpe: Option<f64>,
+    /// Mean return over downside deviation
+    pub sortino: Option<f64>,
+    /// Total return over the largest peak-to-trough decline
+    pub calmar: Option<f64>,
+    /// Largest decline of the equity curve, as a fraction of the peak
+    pub max_drawdown: f64,
+}
+
+impl PerformanceMetrics {
+    /// Metrics of `pnls`, in order, starting from `starting_equity`
+    pub fn from_pnls(pnls: &[f64], starting_equity: f64) -> Self {
+        let n = pnls.len();
+        if n == 0 {
+            return Self::default();
+        }
+
+        let wins: Vec<f64> = pnls.iter().copied().filter(|p| *p >= 0.0).collect();
+        let losses: Vec<f64> = pnls.iter().filter(|p| **p < 0.0).map(|p| -p).collect();
+        let gross_profit: f64 = wins.iter().sum();
+        let gross_loss: f64 = losses.iter().sum();
+
+        let mut equity = starting_equity;
+        let mut peak = starting_equity;
+        let mut max_drawdown: f64 = 0.0;
+        let mut returns = Vec::with_capacity(n);
+        for pnl in pnls {
+            if equity > 0.0 {
+                returns.push(pnl / equity);
+            }
+            equity += pnl;
+            peak = peak.max(equity);
+            if peak > 0.0 {
+                max_drawdown = max_drawdown.max((peak - equity) / peak);
+            }
+        }
+
+        let mean_return = mean(&returns);
+        let std_dev = std_dev(&returns, mean_return);
+        let downside = if returns.is_empty() {
+            0.0
+        } else {
+            (returns.iter().map(|r| r.min(0.0).powi(2)).sum::<f64>() / returns.len() as f64).sqrt()
+        };
+        let total_return = if starting_equity > 0.0 {
+            (equity - starting_equity) / starting_equity
+        } else {
+            0.0
+        };
+
+        Self {
+            trades: n,
+            win_rate: wins.len() as f64 / n as f64,
+            avg_win: mean(&wins),
+            avg_loss: mean(&losses),
+            expectancy: (gross_profit - gross_loss) / n as f64,
+            total_pnl: gross_profit - gross_loss,
+            profit_factor: ratio(gross_profit, gross_loss),
+            sharpe: std_dev.and_then(|sd| ratio(mean_return, sd)),
+            sortino: ratio(mean_return, downside),
+            calmar: ratio(total_return, max_drawdown),
+            max_drawdown,
+        }
+    }
+
+    /// Metrics of `trades`, in order, starting from `starting_equity`
+    pub fn from_trades<'a>(trades: impl IntoIterator<Item = &'a TradeRecord>, starting_equity: f64) -> Self {
+        let pnls: Vec<f64> = trades.into_iter().map(|t| t.pnl).collect();
+        Self::from_pnls(&pnls, starting_equity)
+    }
+
+    /// Metrics of the circuit breaker's recent trades
+    pub fn from_circuit_breaker(circuit_breaker: &CircuitBreaker, starting_equity: f64) -> Self {
+        Self::from_trades(circuit_breaker.trade_history(), starting_equity)
+    }
+}
+
+fn mean(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        0.0
+    } else {
+        values.iter().sum::<f64>() / values.len() as f64
+    }
+}
+
+/// Sample standard deviation; `None` with fewer than two values
+fn std_dev(values: &[f64], mean: f64) -> Option<f64> {
+    if values.len() < 2 {
+        return None;
+    }
+    Some((values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (values.len() - 1) as f64).sqrt())
+}
+
+/// `numerator / denominator`, `None` for a zero denominator
+fn ratio(numerator: f64, denominator: f64) -> Option<f64> {
+    (denominator > 0.0).then(|| numerator / denominator)
+}
+
+/// Trade history longer than the circuit breaker's
+#[derive(Clone, Debug)]
+pub struct TradeLog {
+    capacity: usize,
+    trades: VecDeque<TradeRecord>,
+}
+
+impl TradeLog {
+    /// Log keeping the last `capacity` trades
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            trades: VecDeque::new(),
+        }
+    }
+
+    /// Record a trade closed now
+    pub fn record(&mut self, pnl: f64) {
+        self.record_at(pnl, Utc::now());
+    }
+
+    /// Record a trade closed at `timestamp`
+    pub fn record_at(&mut self, pnl: f64, timestamp: DateTime<Utc>) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.trades.len() == self.capacity {
+            self.trades.pop_front();
+        }
+        self.trades.push_back(TradeRecord::new(pnl, timestamp));
+    }
+
+    /// Logged trades, oldest first
+    pub fn trades(&self) -> impl Iterator<Item = &TradeRecord> {
+        self.trades.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.trades.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.trades.is_empty()
+    }
+
+    /// Metrics of every logged trade
+    pub fn metrics(&self, starting_equity: f64) -> PerformanceMetrics {
+        PerformanceMetrics::from_trades(&self.trades, starting_equity)
+    }
+
+    /// Metrics of the latest `window` trades, starting from `equity` before
+    /// the first of them
+    pub fn rolling(&self, window: usize, equity: f64) -> PerformanceMetrics {
+        let skip = self.trades.len().saturating_sub(window);
+        PerformanceMetrics::from_trades(self.trades.iter().skip(skip), equity)
+    }
+}
+
+impl Default for TradeLog {
+    fn default() -> Self {
+        Self::new(10_000)
+    }
+}
+
+/// Minimum acceptable performance
+///
+/// Unset limits are not checked, and nothing is checked until there are
+/// `min_trades` trades.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PerformanceLimits {
+    pub min_trades: usize,
+    pub min_win_rate: Option<f64>,
+    pub min_expectancy: Option<f64>,
+    pub min_profit_factor: Option<f64>,
+    pub min_sharpe: Option<f64>,
+    pub min_sortino: Option<f64>,
+}
+
+impl Default for PerformanceLimits {
+    fn default() -> Self {
+        Self {
+            min_trades: 20,
+            min_win_rate: None,
+            min_expectancy: None,
+            min_profit_factor: None,
+            min_sharpe: None,
+            min_sortino: None,
+        }
+    }
+}
+
+impl PerformanceLimits {
+    /// Fail Critical at the first metric below its limit
+    ///
+    /// An undefined ratio fails only when it is undefined for lack of
+    /// profit, e.g. no Sharpe because every trade lost the same amount.
+    pub fn check(&self, metrics: &PerformanceMetrics) -> RiskCheck {
+        if metrics.trades < self.min_trades {
+            return RiskCheck::pass(format!(
+                "Performance: {} of {} trades needed",
+                metrics.trades, self.min_trades
+            ));
+        }
+
+        let profitable = metrics.expectancy > 0.0;
+        let limits = [
+            ("Win rate", Some(metrics.win_rate), self.min_win_rate),
+            ("Expectancy", Some(metrics.expectancy), self.min_expectancy),
+            ("Profit factor", metrics.profit_factor, self.min_profit_factor),
+            ("Sharpe", metrics.sharpe, self.min_sharpe),
+            ("Sortino", metrics.sortino, self.min_sortino),
+        ];
+        for (name, value, min) in limits {
+            let Some(min) = min else { continue };
+            match value {
+                Some(value) if value < min => {
+                    return RiskCheck::fail(
+                        RiskLevel::Critical,
+                        format!("{} {:.2} below minimum {:.2}", name, value, min),
+                    );
+                }
+                None if !profitable => {
+                    return RiskCheck::fail(
+                        RiskLevel::Critical,
+                        format!("{} undefined over {} unprofitable trades", name, metrics.trades),
+                    );
+                }
+                _ => {}
+            }
+        }
+        RiskCheck::pass("Performance OK")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trade_stats() {
+        let metrics = PerformanceMetrics::from_pnls(&[100.0, -50.0, 200.0, -50.0], 1_000.0);
+        assert_eq!(metrics.trades, 4);
+        assert_eq!(metrics.win_rate, 0.5);
+        assert_eq!(metrics.avg_win, 150.0);
+        assert_eq!(metrics.avg_loss, 50.0);
+        assert_eq!(metrics.expectancy, 50.0);
+        assert_eq!(metrics.profit_factor, Some(3.0));
+        // Equity 1000 -> 1100 -> 1050: the worst decline is 50 / 1100
+        assert!((metrics.max_drawdown - 50.0 / 1_100.0).abs() < 1e-12);
+        assert!((metrics.calmar.unwrap() - 0.2 / (50.0 / 1_100.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_ratios() {
+        let pnls = [10.0, -10.0, 10.0, -10.0];
+        let metrics = PerformanceMetrics::from_pnls(&pnls, 100.0);
+        let returns = [0.1, -10.0 / 110.0, 0.1, -10.0 / 110.0];
+        let mean = returns.iter().sum::<f64>() / 4.0;
+        let sd = (returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / 3.0).sqrt();
+        let downside = (2.0 * (10.0f64 / 110.0).powi(2) / 4.0).sqrt();
+        assert!((metrics.sharpe.unwrap() - mean / sd).abs() < 1e-12);
+        assert!((metrics.sortino.unwrap() - mean / downside).abs() < 1e-12);
+
+        let all_wins = PerformanceMetrics::from_pnls(&[5.0, 5.0], 100.0);
+        assert_eq!(all_wins.profit_factor, None);
+        assert_eq!(all_wins.sortino, None);
+        assert_eq!(all_wins.calmar, None);
+        assert_eq!(PerformanceMetrics::from_pnls(&[], 100.0), PerformanceMetrics::default());
+    }
+
+    #[test]
+    fn test_rolling_window() {
+        let mut log = TradeLog::new(3);
+        for pnl in [-100.0, 10.0, 20.0, 30.0] {
+            log.record(pnl);
+        }
+        assert_eq!(log.len(), 3);
+        assert_eq!(log.metrics(1_000.0).win_rate, 1.0);
+        let last_two = log.rolling(2, 1_000.0);
+        assert_eq!(last_two.trades, 2);
+        assert_eq!(last_two.total_pnl, 50.0);
+
+        let mut cb = CircuitBreaker::new();
+        cb.record_trade(25.0);
+        cb.record_trade(-5.0);
+        assert_eq!(PerformanceMetrics::from_circuit_breaker(&cb, 1_000.0).profit_factor, Some(5.0));
+    }
+
+    #[test]
+    fn test_limits_as_kill_switch_input() {
+        use crate::kill_switch::{KillSwitch, RiskGuard};
+        use std::sync::{Arc, Mutex};
+
+        let limits = PerformanceLimits {
+            min_trades: 4,
+            min_profit_factor: Some(1.2),
+            ..PerformanceLimits::default()
+        };
+        let log = Arc::new(Mutex::new(TradeLog::default()));
+        let mut guard = RiskGuard::new(KillSwitch::new().balance_floor(0.0));
+        let shared = log.clone();
+        guard.add_check(move || limits.check(&shared.lock().unwrap().metrics(1_000.0)));
+
+        for pnl in [10.0, -20.0, 10.0] {
+            log.lock().unwrap().record(pnl);
+        }
+        assert!(guard.all_passed());
+
+        log.lock().unwrap().record(-20.0);
+        let failure = guard.first_failure().unwrap();
+        assert_eq!(failure.level, RiskLevel::Critical);
+        assert!(failure.message.contains("Profit factor"));
+    }
+}
@@ -0,0 +1,232 @@
+//! Monte Carlo simulation of sizing strategies
+//!
+//! [`MonteCarlo`] resamples a history of trade returns (P&L per unit of
+//! position size) with replacement and replays them as many equity curves,
+//! sizing each trade with a [`SizingStrategy`] from the equity at the time.
+//! The [`SimulationReport`] gives the distribution of final equity and
+//! maximum drawdown and the risk of ruin, to compare sizing choices before
+//! deploying them.
+//!
+//! ```rust,ignore
+//! let sim = MonteCarlo::from_trades(&closed_pnls, 100.0)
+//!     .paths(5_000)
+//!     .trades_per_path(250)
+//!     .seed(7);
+//!
+//! let full = sim.run(10_000.0, &kelly);
+//! let half = sim.run(10_000.0, &DrawdownKellySizing::new(kelly.clone(), 30.0, 0.05));
+//! println!("ruin {:.1}% vs {:.1}%", full.risk_of_ruin * 100.0, half.risk_of_ruin * 100.0);
+//! ```
+//!
+//! Strategies are sized through `calculate`, so state updated by trade
+//! results (e.g. anti-martingale streaks) is not simulated.
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::position_sizing::{PositionSizer, SizingStrategy};
+
+/// Resampling simulator over a trade return distribution
+#[derive(Clone, Debug)]
+pub struct MonteCarlo {
+    returns: Vec<f64>,
+    paths: usize,
+    trades_per_path: usize,
+    /// Loss from the starting equity, in percent, counted as ruin
+    ruin_pct: f64,
+    seed: Option<u64>,
+}
+
+impl MonteCarlo {
+    /// Simulate from trade returns per unit of position size, e.g. -1.0 for
+    /// a bet lost in full
+    pub fn new(returns: Vec<f64>) -> Self {
+        let trades_per_path = returns.len().max(1);
+        Self {
+            returns,
+            paths: 1_000,
+            trades_per_path,
+            ruin_pct: 50.0,
+            seed: None,
+        }
+    }
+
+    /// Simulate from closed trade P&L, each taken at position size `stake`
+    pub fn from_trades(pnls: &[f64], stake: f64) -> Self {
+        Self::new(pnls.iter().map(|pnl| pnl / stake).collect())
+    }
+
+    /// Number of equity curves, 1000 by default
+    pub fn paths(mut self, paths: usize) -> Self {
+        self.paths = paths.max(1);
+        self
+    }
+
+    /// Trades per curve, the history length by default
+    pub fn trades_per_path(mut self, trades: usize) -> Self {
+        self.trades_per_path = trades.max(1);
+        self
+    }
+
+    /// Loss from the starting equity, in percent, that counts as ruin;
+    /// 50 by default
+    pub fn ruin_pct(mut self, pct: f64) -> Self {
+        self.ruin_pct = pct.clamp(0.0, 100.0);
+        self
+    }
+
+    /// Fix the random seed for reproducible runs
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Simulate `strategy` from `starting_equity`
+    pub fn run(&self, starting_equity: f64, strategy: &dyn SizingStrategy) -> SimulationReport {
+        self.simulate(starting_equity, |capital| strategy.calculate(capital))
+    }
+
+    /// Simulate a sizer, including its size bounds
+    pub fn run_sizer(&self, starting_equity: f64, sizer: &PositionSizer) -> SimulationReport {
+        self.simulate(starting_equity, |capital| sizer.calculate(capital))
+    }
+
+    fn simulate(&self, starting_equity: f64, size: impl Fn(f64) -> f64) -> SimulationReport {
+        let mut rng = match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        let ruin_level = starting_equity * (1.0 - self.ruin_pct / 100.0);
+
+        let mut final_equities = Vec::with_capacity(self.paths);
+        let mut max_drawdowns = Vec::with_capacity(self.paths);
+        let mut ruined = 0;
+
+        for _ in 0..self.paths {
+            let mut equity = starting_equity;
+            let mut peak = starting_equity;
+            let mut max_drawdown: f64 = 0.0;
+
+            if !self.returns.is_empty() {
+                for _ in 0..self.trades_per_path {
+                    let r = self.returns[rng.gen_range(0..self.returns.len())];
+                    // Never stake more than the account holds
+                    let stake = size(equity).clamp(0.0, equity.max(0.0));
+                    equity += stake * r;
+                    peak = peak.max(equity);
+                    if peak > 0.0 {
+                        max_drawdown = max_drawdown.max((peak - equity) / peak);
+                    }
+                    if equity <= ruin_level {
+                        break;
+                    }
+                }
+            }
+
+            if equity <= ruin_level {
+                ruined += 1;
+            }
+            final_equities.push(equity);
+            max_drawdowns.push(max_drawdown);
+        }
+
+        final_equities.sort_by(f64::total_cmp);
+        max_drawdowns.sort_by(f64::total_cmp);
+        let median_final_equity = percentile(&final_equities, 50.0);
+        SimulationReport {
+            paths: self.paths,
+            starting_equity,
+            median_final_equity,
+            median_growth: median_final_equity / starting_equity - 1.0,
+            risk_of_ruin: ruined as f64 / self.paths as f64,
+            final_equities,
+            max_drawdowns,
+        }
+    }
+}
+
+/// Outcome of a simulation run
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SimulationReport {
+    pub paths: usize,
+    pub starting_equity: f64,
+    pub median_final_equity: f64,
+    /// Median final equity over the starting equity, minus 1
+    pub median_growth: f64,
+    /// Fraction of paths that hit the ruin level
+    pub risk_of_ruin: f64,
+    /// Final equity of every path, ascending
+    pub final_equities: Vec<f64>,
+    /// Largest decline of every path as a fraction of its peak, ascending
+    pub max_drawdowns: Vec<f64>,
+}
+
+impl SimulationReport {
+    /// Maximum drawdown at percentile `p` (0 to 100) across paths
+    pub fn drawdown_percentile(&self, p: f64) -> f64 {
+        percentile(&self.max_drawdowns, p)
+    }
+
+    /// Final equity at percentile `p` (0 to 100) across paths
+    pub fn equity_percentile(&self, p: f64) -> f64 {
+        percentile(&self.final_equities, p)
+    }
+
+    /// Fraction of paths whose maximum drawdown exceeded `pct` percent
+    pub fn probability_of_drawdown(&self, pct: f64) -> f64 {
+        let threshold = pct / 100.0;
+        let exceeded = self.max_drawdowns.iter().filter(|&&dd| dd > threshold).count();
+        exceeded as f64 / self.max_drawdowns.len().max(1) as f64
+    }
+}
+
+/// Nearest-rank percentile of ascending `sorted`
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = (p.clamp(0.0, 100.0) / 100.0 * (sorted.len() - 1) as f64).round() as usize;
+    sorted[rank]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::position_sizing::{FixedFractionalSizing, KellySizing};
+
+    fn coin_flips() -> MonteCarlo {
+        // Even-money bet won 60% of the time
+        MonteCarlo::new(vec![1.0, 1.0, 1.0, -1.0, -1.0])
+            .paths(2_000)
+            .trades_per_path(100)
+            .seed(42)
+    }
+
+    #[test]
+    fn test_overbetting_is_ruinous() {
+        let sim = coin_flips();
+        let kelly = KellySizing::new().win_rate(0.6);
+        let conservative = sim.run(1_000.0, &FixedFractionalSizing::conservative());
+        let full_kelly = sim.run(1_000.0, &kelly);
+        let reckless = sim.run(1_000.0, &FixedFractionalSizing::new(60.0));
+
+        assert!(full_kelly.median_growth > conservative.median_growth);
+        assert!(full_kelly.median_growth > reckless.median_growth);
+        assert!(reckless.risk_of_ruin > 0.9);
+        assert_eq!(conservative.risk_of_ruin, 0.0);
+        assert!(conservative.drawdown_percentile(95.0) < full_kelly.drawdown_percentile(95.0));
+        assert_eq!(conservative.probability_of_drawdown(50.0), 0.0);
+    }
+
+    #[test]
+    fn test_seeded_runs_repeat() {
+        let sizer = PositionSizer::new(FixedFractionalSizing::aggressive()).with_max_size(40.0);
+        let a = coin_flips().run_sizer(1_000.0, &sizer);
+        let b = coin_flips().run_sizer(1_000.0, &sizer);
+        assert_eq!(a.final_equities, b.final_equities);
+        assert_eq!(a.final_equities.len(), 2_000);
+        assert!(a.equity_percentile(0.0) <= a.median_final_equity);
+        assert!(a.median_final_equity <= a.equity_percentile(100.0));
+    }
+}
@@ -0,0 +1,195 @@
+//! Portfolio risk report
+//!
+//! [`RiskReport::from_portfolio`] runs the exposure, concentration, drawdown
+//! and VaR checks over a [`Portfolio`] in one call. The report renders as
+//! text with `Display`, e.g. for a Telegram message or a log line.
+//!
+//! ```rust,ignore
+//! let portfolio = Portfolio::new(25_000.0, positions)
+//!     .with_returns("BTC-USD", btc_daily_returns)
+//!     .with_drawdown(drawdown_tracker);
+//! let limits = PortfolioLimits {
+//!     exposure: ExposureLimits::new().max_position_pct(25.0),
+//!     max_concentration_pct: Some(60.0),
+//!     var: VarConfig { max_var: Some(1_000.0), ..VarConfig::default() },
+//! };
+//! println!("{}", RiskReport::from_portfolio(&portfolio, &limits));
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::config::ensure;
+use crate::drawdown::DrawdownTracker;
+use crate::error::Result;
+use crate::exposure::{ExposureLimits, Position};
+use crate::types::{RiskCheck, RiskLevel, RiskReport};
+use crate::var::{PositionReturns, VarConfig, VarModel};
+
+/// Open positions with the context the portfolio checks need
+#[derive(Clone, Debug)]
+pub struct Portfolio {
+    /// Account capital the percentage limits apply to
+    pub capital: f64,
+    pub positions: Vec<Position>,
+    /// Per-period returns by market, oldest first, for VaR
+    pub returns: HashMap<String, Vec<f64>>,
+    /// Equity history for the drawdown check
+    pub drawdown: Option<DrawdownTracker>,
+}
+
+impl Portfolio {
+    pub fn new(capital: f64, positions: Vec<Position>) -> Self {
+        Self {
+            capital,
+            positions,
+            returns: HashMap::new(),
+            drawdown: None,
+        }
+    }
+
+    /// Return history of `market`
+    pub fn with_returns(mut self, market: impl Into<String>, returns: Vec<f64>) -> Self {
+        self.returns.insert(market.into(), returns);
+        self
+    }
+
+    /// Equity history, checked against the tracker's own drawdown limit
+    pub fn with_drawdown(mut self, tracker: DrawdownTracker) -> Self {
+        self.drawdown = Some(tracker);
+        self
+    }
+
+    /// Gross notional of all positions
+    pub fn gross_exposure(&self) -> f64 {
+        self.positions.iter().map(Position::notional).sum()
+    }
+
+    /// Positions with return history, valued long-positive
+    fn position_returns(&self) -> Vec<PositionReturns> {
+        self.positions
+            .iter()
+            .filter_map(|p| {
+                let returns = self.returns.get(&p.market)?;
+                Some(PositionReturns::new(p.market.clone(), p.size * p.price, returns.clone()))
+            })
+            .collect()
+    }
+}
+
+/// Limits for [`RiskReport::from_portfolio`]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PortfolioLimits {
+    pub exposure: ExposureLimits,
+    /// Largest share of gross exposure one position may hold, in percent
+    pub max_concentration_pct: Option<f64>,
+    pub var: VarConfig,
+}
+
+impl PortfolioLimits {
+    /// Check every section
+    pub fn validate(&self) -> Result<()> {
+        self.exposure.validate()?;
+        ensure(
+            self.max_concentration_pct.map_or(true, |pct| pct > 0.0 && pct <= 100.0),
+            "portfolio.max_concentration_pct must be in (0, 100]",
+        )?;
+        self.var.validate()
+    }
+
+    fn concentration_check(&self, portfolio: &Portfolio) -> RiskCheck {
+        let gross = portfolio.gross_exposure();
+        let Some(largest) = portfolio.positions.iter().max_by(|a, b| a.notional().total_cmp(&b.notional())) else {
+            return RiskCheck::pass("No open positions");
+        };
+        if gross <= 0.0 {
+            return RiskCheck::pass("No open positions");
+        }
+        let pct = largest.notional() / gross * 100.0;
+        match self.max_concentration_pct {
+            Some(max) if pct > max => RiskCheck::fail(
+                RiskLevel::High,
+                format!("{} is {:.1}% of exposure (limit {:.1}%)", largest.market, pct, max),
+            ),
+            _ => RiskCheck::pass(format!("Largest position {} at {:.1}% of exposure", largest.market, pct)),
+        }
+    }
+}
+
+impl RiskReport {
+    /// Exposure, concentration, drawdown, VaR and CVaR checks for
+    /// `portfolio`
+    ///
+    /// Drawdown passes without equity history, and VaR covers only positions
+    /// with return history.
+    pub fn from_portfolio(portfolio: &Portfolio, limits: &PortfolioLimits) -> Self {
+        let drawdown = match &portfolio.drawdown {
+            Some(tracker) => tracker.check(),
+            None => RiskCheck::pass("Drawdown: no equity history"),
+        };
+        let report = RiskReport::new()
+            .add_check("Exposure", limits.exposure.check(&portfolio.positions, portfolio.capital))
+            .add_check("Concentration", limits.concentration_check(portfolio))
+            .add_check("Drawdown", drawdown);
+        VarModel::new(limits.var.clone()).add_to_report(report, &portfolio.position_returns())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn portfolio() -> Portfolio {
+        let returns: Vec<f64> = (0..20).map(|i| (i as f64 - 10.0) / 100.0).collect();
+        Portfolio::new(
+            10_000.0,
+            vec![
+                Position::new("BTC-USD", 0.06, 50_000.0),
+                Position::new("ETH-USD", 0.5, 2_000.0),
+            ],
+        )
+        .with_returns("BTC-USD", returns.clone())
+        .with_returns("ETH-USD", returns)
+    }
+
+    #[test]
+    fn test_all_checks_pass() {
+        let report = RiskReport::from_portfolio(&portfolio(), &PortfolioLimits::default());
+        let names: Vec<&str> = report.checks.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["Exposure", "Concentration", "Drawdown", "VaR", "CVaR"]);
+        assert!(report.all_passed());
+        assert!(report.checks[1].1.message.contains("BTC-USD at 75.0%"));
+    }
+
+    #[test]
+    fn test_failures_raise_level() {
+        let mut tracker = DrawdownTracker::new(10.0);
+        tracker.record(12_000.0);
+        tracker.record(10_000.0);
+        let portfolio = portfolio().with_drawdown(tracker);
+        let limits = PortfolioLimits {
+            exposure: ExposureLimits::new().max_position_pct(25.0),
+            max_concentration_pct: Some(60.0),
+            var: VarConfig { max_var: Some(300.0), ..VarConfig::default() },
+        };
+
+        let report = RiskReport::from_portfolio(&portfolio, &limits);
+        let failed: Vec<&str> = report.failed_checks().iter().map(|(name, _)| name.as_str()).collect();
+        // VaR is 10% of 4,000 gross
+        assert_eq!(failed, vec!["Exposure", "Concentration", "Drawdown", "VaR"]);
+        assert_eq!(report.overall_level, RiskLevel::Critical);
+
+        let text = report.to_string();
+        assert!(text.starts_with("🔴 Risk report: Critical"));
+        assert!(text.contains("❌ Drawdown: Max drawdown reached"));
+        assert!(text.contains("✅ CVaR"));
+    }
+
+    #[test]
+    fn test_validate() {
+        let limits = PortfolioLimits { max_concentration_pct: Some(0.0), ..PortfolioLimits::default() };
+        assert!(limits.validate().is_err());
+        assert!(PortfolioLimits::default().validate().is_ok());
+    }
+}
@@ -1,10 +1,66 @@
-/// Position sizing strategies for trading
+//! Position sizing strategies for trading
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::config::ensure;
+use crate::error::{Error, Result};
+use crate::volatility::{Atr, Candle, EwmaVolatility};
+
+/// What is known about one opportunity when sizing it
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SizingContext {
+    pub capital: f64,
+    /// Entry price
+    pub price: Option<f64>,
+    /// Estimated expected profit per unit staked
+    pub edge: Option<f64>,
+    /// Current volatility (or ATR) of the instrument
+    pub volatility: Option<f64>,
+    /// Capital already at risk in open positions
+    pub open_risk: f64,
+}
+
+impl SizingContext {
+    pub fn new(capital: f64) -> Self {
+        Self {
+            capital,
+            ..Self::default()
+        }
+    }
+    
+    pub fn with_price(mut self, price: f64) -> Self {
+        self.price = Some(price);
+        self
+    }
+    
+    pub fn with_edge(mut self, edge: f64) -> Self {
+        self.edge = Some(edge);
+        self
+    }
+    
+    pub fn with_volatility(mut self, volatility: f64) -> Self {
+        self.volatility = Some(volatility);
+        self
+    }
+    
+    pub fn with_open_risk(mut self, open_risk: f64) -> Self {
+        self.open_risk = open_risk;
+        self
+    }
+}
 
 /// Sizing strategy trait
 pub trait SizingStrategy: Send + Sync {
     /// Calculate position size given available capital
     fn calculate(&self, capital: f64) -> f64;
     
+    /// Calculate position size for one opportunity; strategies that only
+    /// need capital use `calculate`
+    fn calculate_with(&self, ctx: &SizingContext) -> f64 {
+        self.calculate(ctx.capital)
+    }
+    
     /// Get strategy name
     fn name(&self) -> &'static str;
 }
@@ -43,12 +99,19 @@ impl PositionSizer {
         let size = self.strategy.calculate(capital);
         size.clamp(self.min_size, self.max_size)
     }
+    
+    /// Calculate position size for one opportunity with bounds
+    pub fn calculate_with(&self, ctx: &SizingContext) -> f64 {
+        let size = self.strategy.calculate_with(ctx);
+        size.clamp(self.min_size, self.max_size)
+    }
 }
 
 /// Kelly Criterion sizing
 /// Optimal growth: f* = (p*b - q) / b
 /// where p = win rate, q = loss rate, b = avg win / avg loss
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct KellySizing {
     win_rate: f64,
     avg_win: f64,
@@ -79,6 +142,13 @@ impl KellySizing {
         self
     }
     
+    /// Reject parameters outside their valid ranges
+    pub fn validate(&self) -> Result<()> {
+        ensure((0.0..=1.0).contains(&self.win_rate), "kelly.win_rate must be between 0 and 1")?;
+        ensure(self.avg_win > 0.0, "kelly.avg_win must be positive")?;
+        ensure(self.avg_loss > 0.0, "kelly.avg_loss must be positive")
+    }
+    
     /// Calculate full Kelly fraction
     pub fn kelly_fraction(&self) -> f64 {
         let loss_rate = 1.0 - self.win_rate;
@@ -88,12 +158,135 @@ impl KellySizing {
         f.max(0.0)
     }
     
+    /// Kelly fraction for one opportunity: `edge / b` when the context
+    /// has an edge estimate, the configured win rate otherwise
+    pub fn fraction_for(&self, ctx: &SizingContext) -> f64 {
+        match ctx.edge {
+            Some(edge) => (edge * self.avg_loss / self.avg_win).max(0.0),
+            None => self.kelly_fraction(),
+        }
+    }
+    
     /// Calculate position size
     pub fn calculate_size(&self, capital: f64, half_kelly: bool) -> f64 {
         let kelly = self.kelly_fraction();
         let fraction = if half_kelly { kelly * 0.5 } else { kelly };
         capital * fraction
     }
+    
+    /// Jointly optimal fractions for simultaneous bets whose outcomes are
+    /// correlated, `correlation[i][j]` between `bets[i]` and `bets[j]`
+    ///
+    /// Mean-variance approximation f = Σ⁻¹μ, with each bet's variance scaled
+    /// so that a lone or uncorrelated bet gets exactly its `kelly_fraction`.
+    /// Positively correlated bets share their allocation; bets without edge,
+    /// or whose weight would go negative, get 0. The total is capped at 1.
+    pub fn joint_fractions(bets: &[KellySizing], correlation: &[Vec<f64>]) -> Result<Vec<f64>> {
+        let n = bets.len();
+        ensure(
+            correlation.len() == n && correlation.iter().all(|row| row.len() == n),
+            "kelly.correlation must be a square matrix with one row per bet",
+        )?;
+        for (i, row) in correlation.iter().enumerate() {
+            ensure((row[i] - 1.0).abs() < 1e-9, "kelly.correlation diagonal must be 1")?;
+            for (j, &rho) in row.iter().enumerate() {
+                ensure(
+                    (-1.0..=1.0).contains(&rho) && (rho - correlation[j][i]).abs() < 1e-9,
+                    "kelly.correlation must be symmetric with entries in [-1, 1]",
+                )?;
+            }
+        }
+        
+        let mut active: Vec<usize> = (0..n).filter(|&i| bets[i].kelly_fraction() > 0.0).collect();
+        let mut fractions = vec![0.0; n];
+        while !active.is_empty() {
+            // Per unit stake a win pays b and a loss costs 1; variance b
+            // makes μ/σ² equal the single-bet Kelly fraction
+            let odds: Vec<f64> = active.iter().map(|&i| bets[i].avg_win / bets[i].avg_loss).collect();
+            let mean: Vec<f64> = active
+                .iter()
+                .zip(&odds)
+                .map(|(&i, b)| bets[i].win_rate * b - (1.0 - bets[i].win_rate))
+                .collect();
+            let covariance: Vec<Vec<f64>> = active
+                .iter()
+                .zip(&odds)
+                .map(|(&i, bi)| {
+                    active
+                        .iter()
+                        .zip(&odds)
+                        .map(|(&j, bj)| correlation[i][j] * (bi * bj).sqrt())
+                        .collect()
+                })
+                .collect();
+            let solution = solve(covariance, mean).ok_or_else(|| {
+                Error::Config("kelly.correlation must be positive definite".to_string())
+            })?;
+            
+            if solution.iter().all(|&f| f > 0.0) {
+                for (&i, f) in active.iter().zip(solution) {
+                    fractions[i] = f;
+                }
+                break;
+            }
+            // Drop the most negative weight and solve again
+            let worst = solution
+                .iter()
+                .enumerate()
+                .min_by(|a, b| a.1.total_cmp(b.1))
+                .map(|(k, _)| k)
+                .unwrap_or(0);
+            active.remove(worst);
+        }
+        
+        let total: f64 = fractions.iter().sum();
+        if total > 1.0 {
+            fractions.iter_mut().for_each(|f| *f /= total);
+        }
+        Ok(fractions)
+    }
+    
+    /// Stakes for simultaneous bets, see [`KellySizing::joint_fractions`]
+    pub fn joint_sizes(
+        capital: f64,
+        bets: &[KellySizing],
+        correlation: &[Vec<f64>],
+        half_kelly: bool,
+    ) -> Result<Vec<f64>> {
+        let scale = if half_kelly { 0.5 } else { 1.0 };
+        Ok(Self::joint_fractions(bets, correlation)?
+            .into_iter()
+            .map(|f| capital * f * scale)
+            .collect())
+    }
+}
+
+/// Solve `a x = b` by Gaussian elimination; `None` if `a` is singular
+fn solve(mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Option<Vec<f64>> {
+    let n = b.len();
+    for col in 0..n {
+        let pivot = (col..n).max_by(|&x, &y| a[x][col].abs().total_cmp(&a[y][col].abs()))?;
+        if a[pivot][col].abs() < 1e-12 {
+            return None;
+        }
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+        let (done, rest) = a.split_at_mut(col + 1);
+        let pivot_row = &done[col];
+        for (offset, row) in rest.iter_mut().enumerate() {
+            let factor = row[col] / pivot_row[col];
+            for (x, p) in row[col..].iter_mut().zip(&pivot_row[col..]) {
+                *x -= factor * p;
+            }
+            b[col + 1 + offset] -= factor * b[col];
+        }
+    }
+    let mut x = vec![0.0; n];
+    for row in (0..n).rev() {
+        let tail: f64 = (row + 1..n).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (b[row] - tail) / a[row][row];
+    }
+    Some(x)
 }
 
 impl SizingStrategy for KellySizing {
@@ -101,6 +294,10 @@ impl SizingStrategy for KellySizing {
         self.calculate_size(capital, false)
     }
     
+    fn calculate_with(&self, ctx: &SizingContext) -> f64 {
+        ctx.capital * self.fraction_for(ctx)
+    }
+    
     fn name(&self) -> &'static str {
         "Kelly Criterion"
     }
@@ -112,8 +309,95 @@ impl Default for KellySizing {
     }
 }
 
+/// Fractional Kelly chosen to bound the chance of a deep drawdown
+///
+/// Betting `c` times the Kelly fraction, the probability that equity ever
+/// falls `X` below its peak is `(1 - X)^(2/c - 1)` (continuous-time
+/// approximation). This picks the largest `c`, at most 1, keeping that
+/// probability at or below `max_probability` for `X = max_drawdown_pct`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DrawdownKellySizing {
+    #[serde(flatten)]
+    kelly: KellySizing,
+    #[serde(default = "default_kelly_drawdown_pct")]
+    max_drawdown_pct: f64,
+    #[serde(default = "default_kelly_drawdown_probability")]
+    max_probability: f64,
+}
+
+fn default_kelly_drawdown_pct() -> f64 {
+    50.0
+}
+
+fn default_kelly_drawdown_probability() -> f64 {
+    0.1
+}
+
+impl DrawdownKellySizing {
+    /// Keep P(drawdown > `max_drawdown_pct`) at or below `max_probability`
+    pub fn new(kelly: KellySizing, max_drawdown_pct: f64, max_probability: f64) -> Self {
+        Self {
+            kelly,
+            max_drawdown_pct: max_drawdown_pct.clamp(0.01, 99.99),
+            max_probability: max_probability.clamp(1e-6, 1.0),
+        }
+    }
+    
+    /// Reject parameters outside their valid ranges
+    pub fn validate(&self) -> Result<()> {
+        self.kelly.validate()?;
+        ensure(
+            self.max_drawdown_pct > 0.0 && self.max_drawdown_pct < 100.0,
+            "drawdown_kelly.max_drawdown_pct must be in (0, 100)",
+        )?;
+        ensure(
+            self.max_probability > 0.0 && self.max_probability <= 1.0,
+            "drawdown_kelly.max_probability must be in (0, 1]",
+        )
+    }
+    
+    /// Probability of ever falling `drawdown_pct` below peak when betting
+    /// `kelly_multiple` times full Kelly
+    pub fn drawdown_probability(kelly_multiple: f64, drawdown_pct: f64) -> f64 {
+        if kelly_multiple <= 0.0 {
+            return 0.0;
+        }
+        let exponent = 2.0 / kelly_multiple - 1.0;
+        if exponent <= 0.0 {
+            return 1.0;
+        }
+        (1.0 - drawdown_pct / 100.0).powf(exponent)
+    }
+    
+    /// Multiple of full Kelly meeting the bound
+    pub fn kelly_multiple(&self) -> f64 {
+        let keep = (1.0 - self.max_drawdown_pct / 100.0).ln();
+        let multiple = 2.0 / (1.0 + self.max_probability.ln() / keep);
+        multiple.min(1.0)
+    }
+    
+    /// Fraction of capital to bet
+    pub fn fraction(&self) -> f64 {
+        self.kelly.kelly_fraction() * self.kelly_multiple()
+    }
+}
+
+impl SizingStrategy for DrawdownKellySizing {
+    fn calculate(&self, capital: f64) -> f64 {
+        capital * self.fraction()
+    }
+    
+    fn calculate_with(&self, ctx: &SizingContext) -> f64 {
+        ctx.capital * self.kelly.fraction_for(ctx) * self.kelly_multiple()
+    }
+    
+    fn name(&self) -> &'static str {
+        "Drawdown-Constrained Kelly"
+    }
+}
+
 /// Fixed fractional sizing (risk fixed % per trade)
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct FixedFractionalSizing {
     risk_per_trade_pct: f64,
 }
@@ -139,6 +423,11 @@ impl FixedFractionalSizing {
     pub fn aggressive() -> Self {
         Self::new(5.0)
     }
+    
+    /// Reject parameters outside their valid ranges
+    pub fn validate(&self) -> Result<()> {
+        validate_risk_pct("fixed_fractional", self.risk_per_trade_pct)
+    }
 }
 
 impl SizingStrategy for FixedFractionalSizing {
@@ -151,23 +440,210 @@ impl SizingStrategy for FixedFractionalSizing {
     }
 }
 
+/// Ralph Vince's optimal f, from a history of trade P&L
+///
+/// f maximizes the terminal wealth relative TWR(f) = Π(1 + f · pnl / |largest
+/// loss|), i.e. the fraction of capital to risk against a repeat of the
+/// worst trade. Full optimal f is very aggressive, so the size is scaled by
+/// `safety_fraction`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OptimalFSizing {
+    trades: Vec<f64>,
+    #[serde(default = "default_safety_fraction")]
+    safety_fraction: f64,
+}
+
+fn default_safety_fraction() -> f64 {
+    0.5
+}
+
+impl OptimalFSizing {
+    pub fn new(trades: Vec<f64>) -> Self {
+        Self {
+            trades,
+            safety_fraction: 0.5,
+        }
+    }
+    
+    /// Share of optimal f actually used, in (0, 1]
+    pub fn with_safety_fraction(mut self, fraction: f64) -> Self {
+        self.safety_fraction = fraction.clamp(0.01, 1.0);
+        self
+    }
+    
+    /// Add a closed trade to the history
+    pub fn record_trade(&mut self, pnl: f64) {
+        self.trades.push(pnl);
+    }
+    
+    /// Reject parameters outside their valid ranges
+    pub fn validate(&self) -> Result<()> {
+        ensure(
+            self.safety_fraction > 0.0 && self.safety_fraction <= 1.0,
+            "optimal_f.safety_fraction must be in (0, 1]",
+        )?;
+        ensure(self.trades.iter().all(|t| t.is_finite()), "optimal_f.trades must be finite")
+    }
+    
+    /// Magnitude of the worst trade, `None` without a losing trade
+    pub fn largest_loss(&self) -> Option<f64> {
+        self.trades
+            .iter()
+            .copied()
+            .filter(|&t| t < 0.0)
+            .min_by(f64::total_cmp)
+            .map(f64::abs)
+    }
+    
+    /// Terminal wealth relative of the history when risking `f`
+    pub fn twr(&self, f: f64) -> f64 {
+        self.log_twr(f).exp()
+    }
+    
+    fn log_twr(&self, f: f64) -> f64 {
+        let Some(worst) = self.largest_loss() else {
+            return 0.0;
+        };
+        self.trades.iter().map(|t| (1.0 + f * t / worst).max(0.0).ln()).sum()
+    }
+    
+    /// Optimal f in [0, 1); 0 without a losing trade or without edge
+    pub fn optimal_f(&self) -> f64 {
+        if self.largest_loss().is_none() || self.trades.iter().sum::<f64>() <= 0.0 {
+            return 0.0;
+        }
+        // log TWR is concave in f, so a ternary search finds the peak
+        let (mut low, mut high) = (0.0, 1.0);
+        for _ in 0..100 {
+            let a = low + (high - low) / 3.0;
+            let b = high - (high - low) / 3.0;
+            if self.log_twr(a) < self.log_twr(b) {
+                low = a;
+            } else {
+                high = b;
+            }
+        }
+        (low + high) / 2.0
+    }
+}
+
+impl SizingStrategy for OptimalFSizing {
+    fn calculate(&self, capital: f64) -> f64 {
+        capital * self.optimal_f() * self.safety_fraction
+    }
+    
+    fn name(&self) -> &'static str {
+        "Optimal f"
+    }
+}
+
+/// Ryan Jones' fixed ratio sizing
+///
+/// Going from N to N + 1 contracts takes N · `delta` more profit, so size
+/// grows with the square root of accumulated profit rather than in
+/// proportion to it. Profit is capital above `starting_capital`; sizing
+/// steps back down as it is lost, never below one contract.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FixedRatioSizing {
+    starting_capital: f64,
+    delta: f64,
+    /// Size of one contract; 1 to size in contracts
+    #[serde(default = "default_unit_size")]
+    unit_size: f64,
+}
+
+fn default_unit_size() -> f64 {
+    1.0
+}
+
+impl FixedRatioSizing {
+    pub fn new(starting_capital: f64, delta: f64) -> Self {
+        Self {
+            starting_capital,
+            delta: delta.max(0.01),
+            unit_size: 1.0,
+        }
+    }
+    
+    pub fn with_unit_size(mut self, size: f64) -> Self {
+        self.unit_size = size.max(0.0);
+        self
+    }
+    
+    /// Reject parameters outside their valid ranges
+    pub fn validate(&self) -> Result<()> {
+        ensure(self.starting_capital > 0.0, "fixed_ratio.starting_capital must be positive")?;
+        ensure(self.delta > 0.0, "fixed_ratio.delta must be positive")?;
+        ensure(self.unit_size > 0.0, "fixed_ratio.unit_size must be positive")
+    }
+    
+    /// Contracts to trade at `capital`
+    pub fn contracts(&self, capital: f64) -> u64 {
+        let profit = (capital - self.starting_capital).max(0.0);
+        // Reaching N contracts takes delta * N(N - 1) / 2
+        let n = 0.5 * (1.0 + (1.0 + 8.0 * profit / self.delta).sqrt());
+        // Guard against the root landing just below a whole number
+        (n + 1e-9).floor().max(1.0) as u64
+    }
+}
+
+impl SizingStrategy for FixedRatioSizing {
+    fn calculate(&self, capital: f64) -> f64 {
+        self.contracts(capital) as f64 * self.unit_size
+    }
+    
+    fn name(&self) -> &'static str {
+        "Fixed Ratio"
+    }
+}
+
 /// Volatility-based sizing (ATR-based)
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct VolatilityBasedSizing {
+    #[serde(default = "default_atr_period")]
     atr_period: usize,
+    #[serde(default = "default_risk_pct")]
     risk_per_trade_pct: f64,
+    #[serde(rename = "atr")]
     current_atr: f64,
+    /// Fed by `update_candle`, created on first use
+    #[serde(skip)]
+    atr: Option<Atr>,
+}
+
+fn default_atr_period() -> usize {
+    14
+}
+
+fn default_risk_pct() -> f64 {
+    2.0
+}
+
+fn validate_risk_pct(strategy: &str, pct: f64) -> Result<()> {
+    ensure(
+        pct > 0.0 && pct <= 100.0,
+        format!("{}.risk_per_trade_pct must be in (0, 100]", strategy),
+    )
 }
 
 impl VolatilityBasedSizing {
+    /// Start from `atr` as a fraction of price, e.g. 0.02 for a 2% range
     pub fn new(atr: f64) -> Self {
         Self {
             atr_period: 14,
             risk_per_trade_pct: 2.0,
             current_atr: atr.max(0.0001),
+            atr: None,
         }
     }
     
+    /// Candles averaged by `update_candle`
+    pub fn with_atr_period(mut self, period: usize) -> Self {
+        self.atr_period = period.max(1);
+        self.atr = None;
+        self
+    }
+    
     pub fn with_risk_pct(mut self, pct: f64) -> Self {
         self.risk_per_trade_pct = pct.clamp(0.1, 10.0);
         self
@@ -177,6 +653,32 @@ impl VolatilityBasedSizing {
         self.current_atr = atr.max(0.0001);
     }
     
+    /// Update the ATR from a candle; the last value set is kept until
+    /// `atr_period` candles have been seen
+    ///
+    /// The ATR is taken as a fraction of the close, so sizing depends on how
+    /// volatile the market is rather than on its price level.
+    pub fn update_candle(&mut self, candle: &Candle) {
+        let period = self.atr_period;
+        if let Some(atr) = self.atr.get_or_insert_with(|| Atr::new(period)).update(candle) {
+            if candle.close > 0.0 {
+                self.update_atr(atr / candle.close);
+            }
+        }
+    }
+    
+    /// ATR currently used for sizing
+    pub fn atr(&self) -> f64 {
+        self.current_atr
+    }
+    
+    /// Reject parameters outside their valid ranges
+    pub fn validate(&self) -> Result<()> {
+        validate_risk_pct("volatility_based", self.risk_per_trade_pct)?;
+        ensure(self.atr_period > 0, "volatility_based.atr_period must be at least 1")?;
+        ensure(self.current_atr > 0.0, "volatility_based.atr must be positive")
+    }
+    
     /// Calculate position size based on ATR stop
     pub fn calculate_with_stop(&self, capital: f64, entry_price: f64, stop_price: f64) -> f64 {
         let risk_amount = capital * (self.risk_per_trade_pct / 100.0);
@@ -197,22 +699,160 @@ impl SizingStrategy for VolatilityBasedSizing {
         capital * (self.risk_per_trade_pct / 100.0) * volatility_factor
     }
     
+    /// Uses the context's volatility in place of the tracked ATR
+    fn calculate_with(&self, ctx: &SizingContext) -> f64 {
+        let atr = ctx.volatility.map_or(self.current_atr, |v| v.max(0.0001));
+        ctx.capital * (self.risk_per_trade_pct / 100.0) / (1.0 + atr)
+    }
+    
     fn name(&self) -> &'static str {
         "Volatility-Based"
     }
 }
 
+/// Risk parity across concurrent positions
+///
+/// Allocates `gross_pct` of capital across markets in inverse proportion to
+/// their volatility, so each contributes roughly the same risk. Volatilities
+/// are set directly or estimated from prices with [`EwmaVolatility`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RiskParitySizing {
+    #[serde(default = "default_gross_pct")]
+    gross_pct: f64,
+    #[serde(default = "default_ewma_lambda")]
+    lambda: f64,
+    /// Latest volatility per market
+    #[serde(default)]
+    volatilities: BTreeMap<String, f64>,
+    #[serde(skip)]
+    estimators: HashMap<String, EwmaVolatility>,
+}
+
+fn default_gross_pct() -> f64 {
+    100.0
+}
+
+fn default_ewma_lambda() -> f64 {
+    0.94
+}
+
+impl RiskParitySizing {
+    pub fn new() -> Self {
+        Self {
+            gross_pct: 100.0,
+            lambda: 0.94,
+            volatilities: BTreeMap::new(),
+            estimators: HashMap::new(),
+        }
+    }
+    
+    /// Share of capital allocated in total
+    pub fn with_gross_pct(mut self, pct: f64) -> Self {
+        self.gross_pct = pct.clamp(0.0, 100.0);
+        self
+    }
+    
+    /// Decay of the EWMA estimators used by `update_price`
+    pub fn with_lambda(mut self, lambda: f64) -> Self {
+        self.lambda = lambda;
+        self
+    }
+    
+    /// Use `vol` for `market`
+    pub fn set_volatility(&mut self, market: impl Into<String>, vol: f64) {
+        self.volatilities.insert(market.into(), vol);
+    }
+    
+    /// Feed a price for `market`; its volatility is updated from the second
+    /// price on
+    pub fn update_price(&mut self, market: &str, price: f64) {
+        let lambda = self.lambda;
+        let estimator = self
+            .estimators
+            .entry(market.to_string())
+            .or_insert_with(|| EwmaVolatility::new(lambda));
+        if let Some(vol) = estimator.update(price) {
+            self.volatilities.insert(market.to_string(), vol);
+        }
+    }
+    
+    /// Stop allocating to `market`
+    pub fn remove(&mut self, market: &str) {
+        self.volatilities.remove(market);
+        self.estimators.remove(market);
+    }
+    
+    /// Reject parameters outside their valid ranges
+    pub fn validate(&self) -> Result<()> {
+        ensure(
+            self.gross_pct > 0.0 && self.gross_pct <= 100.0,
+            "risk_parity.gross_pct must be in (0, 100]",
+        )?;
+        ensure(self.lambda > 0.0 && self.lambda < 1.0, "risk_parity.lambda must be in (0, 1)")?;
+        ensure(
+            self.volatilities.values().all(|&v| v >= 0.0),
+            "risk_parity.volatilities must not be negative",
+        )
+    }
+    
+    /// Capital per market; markets with zero volatility get nothing
+    pub fn allocations(&self, capital: f64) -> BTreeMap<String, f64> {
+        let inverse: BTreeMap<&str, f64> = self
+            .volatilities
+            .iter()
+            .map(|(market, &vol)| (market.as_str(), if vol > 0.0 { 1.0 / vol } else { 0.0 }))
+            .collect();
+        let total: f64 = inverse.values().sum();
+        let budget = capital * self.gross_pct / 100.0;
+        inverse
+            .into_iter()
+            .map(|(market, weight)| {
+                let share = if total > 0.0 { weight / total } else { 0.0 };
+                (market.to_string(), budget * share)
+            })
+            .collect()
+    }
+    
+    /// Capital for one market, 0 if it has no volatility estimate
+    pub fn allocation(&self, market: &str, capital: f64) -> f64 {
+        self.allocations(capital).get(market).copied().unwrap_or(0.0)
+    }
+}
+
+impl Default for RiskParitySizing {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Anti-martingale (increase size on wins, decrease on losses)
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct AntiMartingaleSizing {
     base_size: f64,
+    #[serde(skip)]
     consecutive_wins: usize,
+    #[serde(skip)]
     consecutive_losses: usize,
+    #[serde(default = "default_win_multiplier")]
     win_multiplier: f64,
+    #[serde(default = "default_loss_divisor")]
     loss_divisor: f64,
+    #[serde(default = "default_max_multiplier")]
     max_multiplier: f64,
 }
 
+fn default_win_multiplier() -> f64 {
+    1.5
+}
+
+fn default_loss_divisor() -> f64 {
+    2.0
+}
+
+fn default_max_multiplier() -> f64 {
+    4.0
+}
+
 impl AntiMartingaleSizing {
     pub fn new(base_size: f64) -> Self {
         Self {
@@ -235,6 +875,14 @@ impl AntiMartingaleSizing {
         }
     }
     
+    /// Reject parameters outside their valid ranges
+    pub fn validate(&self) -> Result<()> {
+        ensure(self.base_size > 0.0, "anti_martingale.base_size must be positive")?;
+        ensure(self.win_multiplier >= 1.0, "anti_martingale.win_multiplier must be at least 1")?;
+        ensure(self.loss_divisor >= 1.0, "anti_martingale.loss_divisor must be at least 1")?;
+        ensure(self.max_multiplier >= 1.0, "anti_martingale.max_multiplier must be at least 1")
+    }
+    
     fn current_multiplier(&self) -> f64 {
         let multiplier = (self.win_multiplier.powi(self.consecutive_wins as i32))
             / (self.loss_divisor.powi(self.consecutive_losses as i32));
@@ -273,6 +921,109 @@ mod tests {
         assert!((size - 2000.0).abs() < 0.001);
     }
     
+    #[test]
+    fn test_sizing_context() {
+        let kelly = KellySizing::new().win_rate(0.6).avg_win(100.0).avg_loss(50.0);
+        let ctx = SizingContext::new(10_000.0).with_price(0.45);
+        assert!((kelly.calculate_with(&ctx) - 4_000.0).abs() < 1e-6);
+        
+        // An opportunity expected to return 0.3 per unit at 2:1 odds
+        assert!((kelly.calculate_with(&ctx.with_edge(0.3)) - 1_500.0).abs() < 1e-6);
+        assert_eq!(kelly.calculate_with(&ctx.with_edge(-0.1)), 0.0);
+        
+        let sizer = PositionSizer::new(VolatilityBasedSizing::new(0.1)).with_max_size(150.0);
+        assert!((sizer.calculate_with(&ctx.with_volatility(1.0)) - 100.0).abs() < 1e-9);
+        assert_eq!(sizer.calculate_with(&ctx), 150.0);
+        
+        // Strategies without an override fall back to capital only
+        let fixed = FixedFractionalSizing::moderate();
+        assert_eq!(fixed.calculate_with(&ctx.with_edge(0.3)), fixed.calculate(10_000.0));
+    }
+    
+    #[test]
+    fn test_joint_kelly() {
+        let bet = KellySizing::new().win_rate(0.6).avg_win(100.0).avg_loss(50.0);
+        let no_edge = KellySizing::new().win_rate(0.4);
+        let bets = [bet.clone(), bet.clone(), no_edge];
+        let correlated = |rho: f64| vec![
+            vec![1.0, rho, 0.0],
+            vec![rho, 1.0, 0.0],
+            vec![0.0, 0.0, 1.0],
+        ];
+        
+        // Independent bets each get their own Kelly fraction
+        let independent = KellySizing::joint_fractions(&bets, &correlated(0.0)).unwrap();
+        assert!((independent[0] - 0.4).abs() < 1e-9);
+        assert!((independent[1] - 0.4).abs() < 1e-9);
+        assert_eq!(independent[2], 0.0);
+        
+        // Correlated bets split roughly one bet's worth between them
+        let joint = KellySizing::joint_fractions(&bets, &correlated(0.9)).unwrap();
+        assert!((joint[0] - 0.8 / (2.0 * 1.9)).abs() < 1e-9);
+        assert!(joint[0] + joint[1] < 0.45);
+        
+        // Hedged bets are capped at the full bankroll
+        let hedged = KellySizing::joint_sizes(1000.0, &bets, &correlated(-0.5), false).unwrap();
+        assert!((hedged[0] - 500.0).abs() < 1e-6);
+        
+        assert!(KellySizing::joint_fractions(&bets, &correlated(1.0)).is_err());
+        assert!(KellySizing::joint_fractions(&bets, &[vec![1.0]]).is_err());
+    }
+    
+    #[test]
+    fn test_drawdown_kelly() {
+        let kelly = KellySizing::new().win_rate(0.6).avg_win(100.0).avg_loss(50.0);
+        
+        // 10% chance of halving: ln(0.1) / ln(0.5) = 3.32, c = 2 / 4.32
+        let sizer = DrawdownKellySizing::new(kelly.clone(), 50.0, 0.1);
+        assert!((sizer.kelly_multiple() - 0.463).abs() < 0.001);
+        let p = DrawdownKellySizing::drawdown_probability(sizer.kelly_multiple(), 50.0);
+        assert!((p - 0.1).abs() < 1e-9);
+        assert!((sizer.calculate(10_000.0) - 10_000.0 * 0.4 * sizer.kelly_multiple()).abs() < 1e-9);
+        
+        // Half Kelly halves with probability 1/8
+        assert!((DrawdownKellySizing::drawdown_probability(0.5, 50.0) - 0.125).abs() < 1e-9);
+        
+        // A loose bound never goes past full Kelly
+        assert_eq!(DrawdownKellySizing::new(kelly, 10.0, 0.95).kelly_multiple(), 1.0);
+    }
+    
+    #[test]
+    fn test_optimal_f() {
+        // Vince's example series: f = 0.24, TWR = 1.0957
+        let sizer = OptimalFSizing::new(vec![9.0, 18.0, 7.0, 1.0, 10.0, -5.0, -3.0, -17.0, -7.0]);
+        assert_eq!(sizer.largest_loss(), Some(17.0));
+        assert!((sizer.optimal_f() - 0.24).abs() < 0.005);
+        assert!((sizer.twr(sizer.optimal_f()) - 1.0957).abs() < 0.001);
+        assert!((sizer.calculate(10_000.0) - 10_000.0 * sizer.optimal_f() * 0.5).abs() < 1e-9);
+        
+        assert_eq!(OptimalFSizing::new(vec![5.0, 3.0]).optimal_f(), 0.0);
+        assert_eq!(OptimalFSizing::new(vec![5.0, -8.0]).optimal_f(), 0.0);
+    }
+    
+    #[test]
+    fn test_risk_parity() {
+        let mut sizer = RiskParitySizing::new().with_gross_pct(50.0);
+        sizer.set_volatility("BTC", 0.02);
+        sizer.set_volatility("ETH", 0.04);
+        
+        let allocations = sizer.allocations(30_000.0);
+        assert!((allocations["BTC"] - 10_000.0).abs() < 1e-6);
+        assert!((allocations["ETH"] - 5_000.0).abs() < 1e-6);
+        assert_eq!(sizer.allocation("SOL", 30_000.0), 0.0);
+        
+        // Estimated from prices: the choppier market gets less
+        let mut sizer = RiskParitySizing::new();
+        for (calm, wild) in [(100.0, 100.0), (101.0, 110.0), (100.0, 95.0), (101.0, 108.0)] {
+            sizer.update_price("calm", calm);
+            sizer.update_price("wild", wild);
+        }
+        assert!(sizer.allocation("calm", 1_000.0) > sizer.allocation("wild", 1_000.0));
+        
+        sizer.remove("wild");
+        assert!((sizer.allocation("calm", 1_000.0) - 1_000.0).abs() < 1e-6);
+    }
+    
     #[test]
     fn test_fixed_fractional() {
         let sizer = FixedFractionalSizing::moderate();
@@ -292,6 +1043,19 @@ mod tests {
         assert_eq!(sizer.calculate(1000.0), 100.0);
     }
     
+    #[test]
+    fn test_fixed_ratio() {
+        let sizer = FixedRatioSizing::new(10_000.0, 5_000.0).with_unit_size(2.0);
+        assert_eq!(sizer.contracts(8_000.0), 1);
+        assert_eq!(sizer.contracts(14_999.0), 1);
+        assert_eq!(sizer.contracts(15_000.0), 2);
+        // 5k + 10k more for the third contract
+        assert_eq!(sizer.contracts(24_999.0), 2);
+        assert_eq!(sizer.contracts(25_000.0), 3);
+        assert_eq!(sizer.contracts(40_000.0), 4);
+        assert_eq!(sizer.calculate(40_000.0), 8.0);
+    }
+    
     #[test]
     fn test_volatility_sizing() {
         let sizer = VolatilityBasedSizing::new(0.1)
@@ -302,6 +1066,24 @@ mod tests {
         assert!(size < 200.0);
     }
     
+    #[test]
+    fn test_volatility_sizing_from_candles() {
+        // The same 2% daily range at two price levels
+        let sized = |price: f64| {
+            let mut sizer = VolatilityBasedSizing::new(0.1).with_atr_period(3);
+            for _ in 0..3 {
+                sizer.update_candle(&Candle::new(price, price * 1.01, price * 0.99, price));
+            }
+            (sizer.atr(), sizer.calculate(10_000.0))
+        };
+        let (cheap_atr, cheap) = sized(0.5);
+        let (btc_atr, btc) = sized(65_000.0);
+        assert!((btc_atr - 0.02).abs() < 1e-9);
+        assert!((cheap_atr - btc_atr).abs() < 1e-9);
+        assert!((btc - cheap).abs() < 1e-6);
+        assert!((btc - 200.0 / 1.02).abs() < 1e-6);
+    }
+    
     #[test]
     fn test_anti_martingale() {
         let mut sizer = AntiMartingaleSizing::new(100.0);
@@ -0,0 +1,651 @@
+//! Pre-trade checks
+//!
+//! Every order passes through a [`PreTradePipeline`] before it is sent: an
+//! ordered list of [`PreTradeCheck`]s run against the [`OrderIntent`] and the
+//! account's current state. The result is a [`PreTradeDecision`] that approves
+//! the order only if every check passed, with the failed checks as reasons.
+//!
+//! ```rust,ignore
+//! let pipeline = PreTradePipeline::new()
+//!     .with_check(Arc::new(Mutex::new(kill_switch)))
+//!     .with_check(ExposureLimits::new().max_position_pct(20.0))
+//!     .with_check(SizeBounds::new(10.0, 5_000.0))
+//!     .with_check(PriceSanity::new(2.0))
+//!     .with_check(SlippageLimit::new(50.0));
+//!
+//! let intent = OrderIntent::new("BTC-USD", Side::Buy, 0.01, 65_100.0).with_reference_price(65_000.0);
+//! let decision = pipeline.evaluate(&intent, &PreTradeContext::new(25_000.0, &positions));
+//! if !decision.approved {
+//!     tracing::warn!("order denied: {}", decision.reasons().join("; "));
+//! }
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+
+use crate::exposure::{ExposureLimits, Position};
+use crate::kill_switch::KillSwitch;
+use crate::position_sizing::{PositionSizer, SizingContext};
+use crate::types::{Order, RiskCheck, RiskLevel, RiskReport, Side};
+
+/// An order a strategy wants to place, with what the checks need to judge it
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OrderIntent {
+    pub market: String,
+    pub side: Side,
+    pub size: f64,
+    /// Limit price
+    pub price: f64,
+    /// Underlying asset, for token exposure limits
+    pub token: Option<String>,
+    /// Group, for category exposure limits
+    pub category: Option<String>,
+    /// Fair price to compare `price` against, e.g. the mid
+    pub reference_price: Option<f64>,
+    /// Expected slippage from the order book, in basis points
+    pub expected_slippage_bps: Option<f64>,
+    /// Estimated true value per unit, e.g. the model probability of a
+    /// prediction market outcome
+    pub fair_value: Option<f64>,
+}
+
+impl OrderIntent {
+    pub fn new(market: impl Into<String>, side: Side, size: f64, price: f64) -> Self {
+        Self {
+            market: market.into(),
+            side,
+            size,
+            price,
+            token: None,
+            category: None,
+            reference_price: None,
+            expected_slippage_bps: None,
+            fair_value: None,
+        }
+    }
+
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    pub fn with_category(mut self, category: impl Into<String>) -> Self {
+        self.category = Some(category.into());
+        self
+    }
+
+    pub fn with_reference_price(mut self, price: f64) -> Self {
+        self.reference_price = Some(price);
+        self
+    }
+
+    pub fn with_expected_slippage_bps(mut self, bps: f64) -> Self {
+        self.expected_slippage_bps = Some(bps);
+        self
+    }
+
+    pub fn with_fair_value(mut self, value: f64) -> Self {
+        self.fair_value = Some(value);
+        self
+    }
+
+    /// Size times price
+    pub fn notional(&self) -> f64 {
+        self.size * self.price
+    }
+
+    /// The position this order would open, signed by side
+    pub fn as_position(&self) -> Position {
+        let size = match self.side {
+            Side::Buy => self.size,
+            Side::Sell => -self.size,
+        };
+        Position {
+            market: self.market.clone(),
+            token: self.token.clone(),
+            category: self.category.clone(),
+            size,
+            price: self.price,
+        }
+    }
+
+    /// Whether the order only shrinks or closes the net position in its
+    /// market
+    pub fn reduces(&self, positions: &[Position]) -> bool {
+        let net: f64 = positions.iter().filter(|p| p.market == self.market).map(|p| p.size).sum();
+        let size = self.as_position().size;
+        net * size < 0.0 && size.abs() <= net.abs()
+    }
+}
+
+impl From<Order> for OrderIntent {
+    fn from(order: Order) -> Self {
+        Self::new(order.market, order.side, order.size, order.price)
+    }
+}
+
+/// Account state the checks run against
+#[derive(Clone, Copy, Debug)]
+pub struct PreTradeContext<'a> {
+    pub balance: f64,
+    pub positions: &'a [Position],
+}
+
+impl<'a> PreTradeContext<'a> {
+    pub fn new(balance: f64, positions: &'a [Position]) -> Self {
+        Self { balance, positions }
+    }
+}
+
+/// A check every order must pass
+pub trait PreTradeCheck: Send + Sync {
+    /// Judge `intent` given the account state
+    fn check(&self, intent: &OrderIntent, ctx: &PreTradeContext<'_>) -> RiskCheck;
+
+    /// Name shown in the decision's report
+    fn name(&self) -> &'static str;
+}
+
+/// Checks shared with code that keeps updating them, e.g. a kill switch
+impl<T: PreTradeCheck> PreTradeCheck for Arc<Mutex<T>> {
+    fn check(&self, intent: &OrderIntent, ctx: &PreTradeContext<'_>) -> RiskCheck {
+        match self.lock() {
+            Ok(check) => check.check(intent, ctx),
+            Err(_) => RiskCheck::fail(RiskLevel::Critical, "Check state poisoned"),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self.lock() {
+            Ok(check) => check.name(),
+            Err(_) => "Poisoned check",
+        }
+    }
+}
+
+/// A breach triggers the switch, so it latches and runs its hooks as in
+/// [`RiskManager::pre_trade_check`](crate::RiskManager::pre_trade_check).
+/// In close-only mode, orders that reduce a position pass.
+impl PreTradeCheck for Arc<Mutex<KillSwitch>> {
+    fn check(&self, intent: &OrderIntent, ctx: &PreTradeContext<'_>) -> RiskCheck {
+        let Ok(mut kill_switch) = self.lock() else {
+            return RiskCheck::fail(RiskLevel::Critical, "Check state poisoned");
+        };
+        let check = kill_switch.check_and_trigger();
+        kill_switch.check_order(check, &intent.market, intent.reduces(ctx.positions))
+    }
+
+    fn name(&self) -> &'static str {
+        "Kill switch"
+    }
+}
+
+/// Positions after the order fills, against every exposure limit
+impl PreTradeCheck for ExposureLimits {
+    fn check(&self, intent: &OrderIntent, ctx: &PreTradeContext<'_>) -> RiskCheck {
+        let mut positions = ctx.positions.to_vec();
+        positions.push(intent.as_position());
+        ExposureLimits::check(self, &positions, ctx.balance)
+    }
+
+    fn name(&self) -> &'static str {
+        "Exposure"
+    }
+}
+
+/// Order notional against the sizer's size at the current balance
+impl PreTradeCheck for PositionSizer {
+    fn check(&self, intent: &OrderIntent, ctx: &PreTradeContext<'_>) -> RiskCheck {
+        let open_risk = ctx.positions.iter().map(Position::notional).sum();
+        let sizing = SizingContext::new(ctx.balance)
+            .with_price(intent.price)
+            .with_open_risk(open_risk);
+        let limit = self.calculate_with(&sizing);
+        let notional = intent.notional();
+        if notional > limit {
+            RiskCheck::fail(
+                RiskLevel::High,
+                format!("Order notional {:.2} exceeds position size limit {:.2}", notional, limit),
+            )
+        } else {
+            RiskCheck::pass("Position size OK")
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "Position size"
+    }
+}
+
+/// Smallest and largest order notional
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SizeBounds {
+    pub min_notional: f64,
+    pub max_notional: f64,
+}
+
+impl SizeBounds {
+    pub fn new(min_notional: f64, max_notional: f64) -> Self {
+        Self {
+            min_notional,
+            max_notional,
+        }
+    }
+}
+
+impl PreTradeCheck for SizeBounds {
+    fn check(&self, intent: &OrderIntent, _ctx: &PreTradeContext<'_>) -> RiskCheck {
+        let notional = intent.notional();
+        if notional.is_nan() || notional <= 0.0 {
+            return RiskCheck::fail(RiskLevel::High, format!("Order notional {:.2} is not positive", notional));
+        }
+        if notional < self.min_notional {
+            return RiskCheck::fail(
+                RiskLevel::Elevated,
+                format!("Order notional {:.2} below minimum {:.2}", notional, self.min_notional),
+            );
+        }
+        if notional > self.max_notional {
+            return RiskCheck::fail(
+                RiskLevel::High,
+                format!("Order notional {:.2} above maximum {:.2}", notional, self.max_notional),
+            );
+        }
+        RiskCheck::pass("Order size within bounds")
+    }
+
+    fn name(&self) -> &'static str {
+        "Size bounds"
+    }
+}
+
+/// Rejects limit prices far from the reference price, e.g. a fat-fingered
+/// or stale quote
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PriceSanity {
+    /// Largest allowed deviation from the reference price, in percent
+    pub max_deviation_pct: f64,
+}
+
+impl PriceSanity {
+    pub fn new(max_deviation_pct: f64) -> Self {
+        Self { max_deviation_pct }
+    }
+}
+
+impl PreTradeCheck for PriceSanity {
+    fn check(&self, intent: &OrderIntent, _ctx: &PreTradeContext<'_>) -> RiskCheck {
+        if !intent.price.is_finite() || intent.price <= 0.0 {
+            return RiskCheck::fail(RiskLevel::High, format!("Invalid price {}", intent.price));
+        }
+        let Some(reference) = intent.reference_price.filter(|p| *p > 0.0) else {
+            return RiskCheck::pass("No reference price");
+        };
+        let deviation = (intent.price - reference).abs() / reference * 100.0;
+        if deviation > self.max_deviation_pct {
+            RiskCheck::fail(
+                RiskLevel::High,
+                format!(
+                    "Price {} is {:.2}% from reference {} (limit {:.2}%)",
+                    intent.price, deviation, reference, self.max_deviation_pct
+                ),
+            )
+        } else {
+            RiskCheck::pass(format!("Price within {:.2}% of reference", deviation))
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "Price sanity"
+    }
+}
+
+/// Rejects orders whose expected slippage is too high
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SlippageLimit {
+    pub max_slippage_bps: f64,
+}
+
+impl SlippageLimit {
+    pub fn new(max_slippage_bps: f64) -> Self {
+        Self { max_slippage_bps }
+    }
+}
+
+impl PreTradeCheck for SlippageLimit {
+    fn check(&self, intent: &OrderIntent, _ctx: &PreTradeContext<'_>) -> RiskCheck {
+        match intent.expected_slippage_bps {
+            Some(bps) if bps > self.max_slippage_bps => RiskCheck::fail(
+                RiskLevel::High,
+                format!("Expected slippage {:.1}bps exceeds {:.1}bps", bps, self.max_slippage_bps),
+            ),
+            Some(bps) => RiskCheck::pass(format!("Expected slippage {:.1}bps", bps)),
+            None => RiskCheck::pass("No slippage estimate"),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "Slippage"
+    }
+}
+
+/// Slippage in basis points of filling `size` against book `levels`
+/// (price, size), best first: the opposite side, e.g. asks for a buy.
+/// `None` if the book is too thin to fill the order or `size` is not
+/// positive.
+pub fn book_slippage_bps(size: f64, levels: &[(f64, f64)]) -> Option<f64> {
+    if size.is_nan() || size <= 0.0 {
+        return None;
+    }
+    let best = levels.first()?.0;
+    let mut remaining = size;
+    let mut cost = 0.0;
+    for &(price, available) in levels {
+        let take = remaining.min(available);
+        cost += take * price;
+        remaining -= take;
+        if remaining <= 0.0 {
+            let average = cost / size;
+            return Some((average - best).abs() / best * 10_000.0);
+        }
+    }
+    None
+}
+
+/// Rejects orders whose expected profit does not cover fees, slippage and
+/// gas
+///
+/// Expected profit is the order's edge over its fair value, `size * (fair
+/// value - price)` for a buy. Orders without a fair value estimate pass.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExpectedValueCheck {
+    /// Venue fee, in percent of notional
+    pub fee_pct: f64,
+    /// Fixed cost per order, e.g. gas
+    pub gas_cost: f64,
+    /// Net expected profit required after costs
+    pub min_net_ev: f64,
+}
+
+impl ExpectedValueCheck {
+    pub fn new(fee_pct: f64) -> Self {
+        Self {
+            fee_pct,
+            gas_cost: 0.0,
+            min_net_ev: 0.0,
+        }
+    }
+
+    pub fn with_gas_cost(mut self, cost: f64) -> Self {
+        self.gas_cost = cost;
+        self
+    }
+
+    pub fn with_min_net_ev(mut self, ev: f64) -> Self {
+        self.min_net_ev = ev;
+        self
+    }
+
+    /// Fees, expected slippage and gas for the order
+    pub fn costs(&self, intent: &OrderIntent) -> f64 {
+        let notional = intent.notional();
+        let slippage = notional * intent.expected_slippage_bps.unwrap_or(0.0) / 10_000.0;
+        notional * self.fee_pct / 100.0 + slippage + self.gas_cost
+    }
+
+    /// Expected profit after costs, `None` without a fair value
+    pub fn net_ev(&self, intent: &OrderIntent) -> Option<f64> {
+        let fair = intent.fair_value?;
+        let edge = match intent.side {
+            Side::Buy => fair - intent.price,
+            Side::Sell => intent.price - fair,
+        };
+        Some(intent.size * edge - self.costs(intent))
+    }
+}
+
+impl PreTradeCheck for ExpectedValueCheck {
+    fn check(&self, intent: &OrderIntent, _ctx: &PreTradeContext<'_>) -> RiskCheck {
+        let Some(net) = self.net_ev(intent) else {
+            return RiskCheck::pass("No fair value estimate");
+        };
+        if net < self.min_net_ev {
+            RiskCheck::fail(
+                RiskLevel::High,
+                format!(
+                    "Expected value {:.2} after {:.2} costs is below {:.2}",
+                    net,
+                    self.costs(intent),
+                    self.min_net_ev
+                ),
+            )
+        } else {
+            RiskCheck::pass(format!("Expected value {:.2} after costs", net))
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "Expected value"
+    }
+}
+
+/// Outcome of a [`PreTradePipeline`]
+#[derive(Clone, Debug)]
+pub struct PreTradeDecision {
+    pub approved: bool,
+    /// Every check that ran, in order
+    pub report: RiskReport,
+}
+
+impl PreTradeDecision {
+    /// `"<check>: <message>"` for each failed check
+    pub fn reasons(&self) -> Vec<String> {
+        self.report
+            .failed_checks()
+            .iter()
+            .map(|(name, check)| format!("{}: {}", name, check.message))
+            .collect()
+    }
+}
+
+/// Ordered pre-trade checks
+#[derive(Default)]
+pub struct PreTradePipeline {
+    checks: Vec<Box<dyn PreTradeCheck>>,
+    fail_fast: bool,
+}
+
+impl PreTradePipeline {
+    /// Pipeline with no checks, approving everything
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a check; checks run in the order added
+    pub fn with_check(mut self, check: impl PreTradeCheck + 'static) -> Self {
+        self.checks.push(Box::new(check));
+        self
+    }
+
+    /// Stop at the first failed check instead of running them all
+    pub fn fail_fast(mut self, fail_fast: bool) -> Self {
+        self.fail_fast = fail_fast;
+        self
+    }
+
+    /// Names of the checks, in order
+    pub fn check_names(&self) -> Vec<&'static str> {
+        self.checks.iter().map(|c| c.name()).collect()
+    }
+
+    /// Run the checks against `intent`
+    pub fn evaluate(&self, intent: &OrderIntent, ctx: &PreTradeContext<'_>) -> PreTradeDecision {
+        let mut report = RiskReport::new();
+        for check in &self.checks {
+            let result = check.check(intent, ctx);
+            let failed = !result.passed;
+            report = report.add_check(check.name(), result);
+            if failed && self.fail_fast {
+                break;
+            }
+        }
+        PreTradeDecision {
+            approved: report.all_passed(),
+            report,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kill_switch::KillSwitchMode;
+    use crate::position_sizing::FixedFractionalSizing;
+
+    fn pipeline(kill_switch: Arc<Mutex<KillSwitch>>) -> PreTradePipeline {
+        PreTradePipeline::new()
+            .with_check(kill_switch)
+            .with_check(ExposureLimits::new().max_token_notional(3_000.0))
+            .with_check(SizeBounds::new(10.0, 2_000.0))
+            .with_check(PriceSanity::new(1.0))
+            .with_check(SlippageLimit::new(30.0))
+    }
+
+    #[test]
+    fn test_approves_clean_order() {
+        let kill_switch = Arc::new(Mutex::new(KillSwitch::new().balance_floor(0.0)));
+        let pipeline = pipeline(kill_switch);
+        assert_eq!(
+            pipeline.check_names(),
+            vec!["Kill switch", "Exposure", "Size bounds", "Price sanity", "Slippage"]
+        );
+
+        let intent = OrderIntent::new("ETH-USD", Side::Buy, 0.5, 3_010.0)
+            .with_token("ETH")
+            .with_reference_price(3_000.0)
+            .with_expected_slippage_bps(5.0);
+        let decision = pipeline.evaluate(&intent, &PreTradeContext::new(10_000.0, &[]));
+        assert!(decision.approved);
+        assert_eq!(decision.report.checks.len(), 5);
+        assert!(decision.reasons().is_empty());
+    }
+
+    #[test]
+    fn test_denies_with_reasons() {
+        let kill_switch = Arc::new(Mutex::new(KillSwitch::new().balance_floor(0.0)));
+        let pipeline = pipeline(kill_switch.clone());
+        let positions = vec![Position::new("ETH-PERP", 0.6, 3_000.0).with_token("ETH")];
+        let ctx = PreTradeContext::new(10_000.0, &positions);
+        let intent = OrderIntent::new("ETH-USD", Side::Sell, 0.5, 3_100.0)
+            .with_token("ETH")
+            .with_reference_price(3_000.0)
+            .with_expected_slippage_bps(45.0);
+
+        let decision = pipeline.evaluate(&intent, &ctx);
+        assert!(!decision.approved);
+        let reasons = decision.reasons();
+        assert_eq!(reasons.len(), 3);
+        assert!(reasons[0].starts_with("Exposure: "));
+        assert!(reasons[1].starts_with("Price sanity: "));
+        assert!(reasons[2].starts_with("Slippage: "));
+
+        kill_switch.lock().unwrap().manual_trigger("maintenance");
+        let decision = pipeline.fail_fast(true).evaluate(&intent, &ctx);
+        assert_eq!(decision.report.checks.len(), 1);
+        assert_eq!(decision.report.overall_level, RiskLevel::Critical);
+    }
+
+    #[test]
+    fn test_sizer_and_bounds() {
+        let sizer = PositionSizer::new(FixedFractionalSizing::moderate());
+        let ctx = PreTradeContext::new(10_000.0, &[]);
+        let intent: OrderIntent = Order::new("SOL-USD", Side::Buy, 1.0, 150.0).into();
+        assert!(PreTradeCheck::check(&sizer, &intent, &ctx).passed);
+        let large = OrderIntent::new("SOL-USD", Side::Buy, 20.0, 150.0);
+        assert!(!PreTradeCheck::check(&sizer, &large, &ctx).passed);
+
+        let bounds = SizeBounds::new(10.0, 1_000.0);
+        let dust = OrderIntent::new("SOL-USD", Side::Buy, 0.01, 150.0);
+        assert_eq!(bounds.check(&dust, &ctx).level, RiskLevel::Elevated);
+        let zero = OrderIntent::new("SOL-USD", Side::Buy, 0.0, 150.0);
+        assert!(!bounds.check(&zero, &ctx).passed);
+        assert!(!PriceSanity::new(5.0).check(&OrderIntent::new("SOL-USD", Side::Buy, 1.0, f64::NAN), &ctx).passed);
+    }
+
+    #[tokio::test]
+    async fn test_breach_latches() {
+        let fired = Arc::new(Mutex::new(Vec::new()));
+        let hook = fired.clone();
+        let mut switch = KillSwitch::new().balance_floor(100.0).on_trigger(move |reason| {
+            hook.lock().unwrap().push(reason);
+            async {}
+        });
+        switch.update_state(1_000.0, 0);
+        let kill_switch = Arc::new(Mutex::new(switch));
+        let ctx = PreTradeContext::new(1_000.0, &[]);
+        let intent = OrderIntent::new("ETH-USD", Side::Buy, 0.1, 3_000.0);
+        assert!(PreTradeCheck::check(&kill_switch, &intent, &ctx).passed);
+
+        kill_switch.lock().unwrap().update_state(50.0, 0);
+        assert!(!PreTradeCheck::check(&kill_switch, &intent, &ctx).passed);
+        kill_switch.lock().unwrap().update_state(1_000.0, 0);
+        assert!(!PreTradeCheck::check(&kill_switch, &intent, &ctx).passed);
+        assert!(kill_switch.lock().unwrap().is_triggered());
+
+        tokio::task::yield_now().await;
+        assert_eq!(fired.lock().unwrap().len(), 1);
+        assert!(fired.lock().unwrap()[0].starts_with("Balance below floor"));
+    }
+
+    #[test]
+    fn test_close_only_mode() {
+        let mut switch = KillSwitch::new().balance_floor(100.0).trigger_mode(KillSwitchMode::CloseOnly);
+        switch.update_state(50.0, 1);
+        let kill_switch = Arc::new(Mutex::new(switch));
+
+        let positions = vec![Position::new("ETH-USD", 2.0, 3_000.0)];
+        let ctx = PreTradeContext::new(50.0, &positions);
+        let check = |intent: OrderIntent| PreTradeCheck::check(&kill_switch, &intent, &ctx);
+        assert!(check(OrderIntent::new("ETH-USD", Side::Sell, 1.0, 3_000.0)).passed);
+        assert_eq!(kill_switch.lock().unwrap().mode(), KillSwitchMode::CloseOnly);
+        assert!(check(OrderIntent::new("ETH-USD", Side::Sell, 2.0, 3_000.0)).passed);
+        assert!(!check(OrderIntent::new("ETH-USD", Side::Sell, 3.0, 3_000.0)).passed);
+        assert!(!check(OrderIntent::new("ETH-USD", Side::Buy, 1.0, 3_000.0)).passed);
+        assert!(!check(OrderIntent::new("BTC-USD", Side::Sell, 0.1, 60_000.0)).passed);
+
+        let halted = Arc::new(Mutex::new(KillSwitch::new()));
+        halted.lock().unwrap().manual_trigger("stop");
+        assert_eq!(halted.lock().unwrap().mode(), KillSwitchMode::Halted);
+        let sell = OrderIntent::new("ETH-USD", Side::Sell, 1.0, 3_000.0);
+        assert!(!PreTradeCheck::check(&halted, &sell, &ctx).passed);
+        halted.lock().unwrap().close_only("unwind");
+        assert!(PreTradeCheck::check(&halted, &sell, &ctx).passed);
+        halted.lock().unwrap().reset();
+        assert_eq!(halted.lock().unwrap().mode(), KillSwitchMode::Normal);
+    }
+
+    #[test]
+    fn test_expected_value_after_costs() {
+        let ctx = PreTradeContext::new(1_000.0, &[]);
+        let check = ExpectedValueCheck::new(2.0).with_gas_cost(0.05);
+
+        // 100 shares at 0.55 with a 0.56 estimate: 1.00 edge, 1.10 fee
+        let thin = OrderIntent::new("ELECTION-YES", Side::Buy, 100.0, 0.55).with_fair_value(0.56);
+        assert!((check.net_ev(&thin).unwrap() + 0.15).abs() < 1e-9);
+        assert!(!PreTradeCheck::check(&check, &thin, &ctx).passed);
+
+        let wide = thin.clone().with_fair_value(0.575);
+        assert!(PreTradeCheck::check(&check, &wide, &ctx).passed);
+        let slipped = wide.with_expected_slippage_bps(book_slippage_bps(100.0, &[(0.55, 40.0), (0.58, 60.0)]).unwrap());
+        assert!((slipped.expected_slippage_bps.unwrap() - 327.27).abs() < 0.01);
+        assert_eq!(book_slippage_bps(0.0, &[(0.55, 40.0)]), None);
+        assert_eq!(book_slippage_bps(-1.0, &[(0.55, 40.0)]), None);
+        assert_eq!(book_slippage_bps(f64::NAN, &[(0.55, 40.0)]), None);
+        assert!(!PreTradeCheck::check(&check, &slipped, &ctx).passed);
+
+        let sell = OrderIntent::new("ELECTION-YES", Side::Sell, 100.0, 0.55).with_fair_value(0.50);
+        assert!(PreTradeCheck::check(&check, &sell, &ctx).passed);
+        assert!(PreTradeCheck::check(&check, &OrderIntent::new("X", Side::Buy, 1.0, 1.0), &ctx).passed);
+        assert_eq!(book_slippage_bps(200.0, &[(0.55, 40.0)]), None);
+    }
+}
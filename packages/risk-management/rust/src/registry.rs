@@ -0,0 +1,181 @@
+//! Per-strategy circuit breakers
+//!
+//! [`CircuitBreakerRegistry`] keeps one [`CircuitBreaker`] per strategy or
+//! market id, each with its own config, so a misbehaving strategy trips only
+//! its own breaker. Ids without a registered breaker get one with the
+//! registry's default config on first use.
+//!
+//! ```rust,ignore
+//! let mut breakers = CircuitBreakerRegistry::new(CircuitBreakerConfig::default())
+//!     .with_config("scalper", CircuitBreakerConfig { max_consecutive_losses: 6, ..Default::default() });
+//!
+//! breakers.record_trade("scalper", -12.0);
+//! if !breakers.check_and_trigger("mean-reversion").passed {
+//!     return;
+//! }
+//! println!("{}", breakers.report());
+//! ```
+
+use std::collections::BTreeMap;
+
+use crate::circuit_breaker::{CircuitBreaker, CircuitBreakerConfig, CircuitBreakerStatus};
+use crate::types::{RiskCheck, RiskReport};
+
+/// Circuit breakers keyed by strategy or market id
+#[derive(Clone, Debug, Default)]
+pub struct CircuitBreakerRegistry {
+    default_config: CircuitBreakerConfig,
+    breakers: BTreeMap<String, CircuitBreaker>,
+}
+
+impl CircuitBreakerRegistry {
+    /// Registry creating breakers with `default_config`
+    pub fn new(default_config: CircuitBreakerConfig) -> Self {
+        Self {
+            default_config,
+            breakers: BTreeMap::new(),
+        }
+    }
+
+    /// Give `id` its own config
+    pub fn with_config(mut self, id: impl Into<String>, config: CircuitBreakerConfig) -> Self {
+        self.register(id, CircuitBreaker::with_config(config));
+        self
+    }
+
+    /// Use `breaker` for `id`, replacing any existing one
+    pub fn register(&mut self, id: impl Into<String>, breaker: CircuitBreaker) {
+        self.breakers.insert(id.into(), breaker);
+    }
+
+    /// Stop tracking `id`
+    pub fn remove(&mut self, id: &str) -> Option<CircuitBreaker> {
+        self.breakers.remove(id)
+    }
+
+    pub fn get(&self, id: &str) -> Option<&CircuitBreaker> {
+        self.breakers.get(id)
+    }
+
+    /// Breaker for `id`, created with the default config if missing
+    pub fn breaker(&mut self, id: &str) -> &mut CircuitBreaker {
+        self.breakers
+            .entry(id.to_string())
+            .or_insert_with(|| CircuitBreaker::with_config(self.default_config.clone()))
+    }
+
+    /// Registered ids, sorted
+    pub fn ids(&self) -> impl Iterator<Item = &str> {
+        self.breakers.keys().map(String::as_str)
+    }
+
+    /// Record a trade outcome for `id`
+    pub fn record_trade(&mut self, id: &str, pnl: f64) {
+        self.breaker(id).record_trade(pnl);
+    }
+
+    /// Report the balance allocated to `id`
+    pub fn update_balance(&mut self, id: &str, balance: f64) {
+        self.breaker(id).update_balance(balance);
+    }
+
+    /// Check `id`'s breaker; unknown ids pass
+    pub fn check(&self, id: &str) -> RiskCheck {
+        self.breakers.get(id).map_or_else(|| RiskCheck::pass("Circuit breaker OK"), CircuitBreaker::check)
+    }
+
+    /// Check `id`'s breaker and trigger it if needed
+    pub fn check_and_trigger(&mut self, id: &str) -> RiskCheck {
+        self.breaker(id).check_and_trigger()
+    }
+
+    /// Reset `id`'s breaker
+    pub fn reset(&mut self, id: &str) {
+        if let Some(breaker) = self.breakers.get_mut(id) {
+            breaker.reset();
+        }
+    }
+
+    /// Reset every breaker
+    pub fn reset_all(&mut self) {
+        self.breakers.values_mut().for_each(CircuitBreaker::reset);
+    }
+
+    /// Status of every breaker
+    pub fn status(&self) -> RegistryStatus {
+        RegistryStatus {
+            breakers: self.breakers.iter().map(|(id, b)| (id.clone(), b.status())).collect(),
+        }
+    }
+
+    /// One check per breaker, named `Circuit breaker <id>`
+    pub fn report(&self) -> RiskReport {
+        self.breakers.iter().fold(RiskReport::new(), |report, (id, breaker)| {
+            report.add_check(format!("Circuit breaker {}", id), breaker.check())
+        })
+    }
+}
+
+/// Combined view returned by [`CircuitBreakerRegistry::status`]
+#[derive(Clone, Debug)]
+pub struct RegistryStatus {
+    pub breakers: BTreeMap<String, CircuitBreakerStatus>,
+}
+
+impl RegistryStatus {
+    /// Ids whose breaker is open
+    pub fn open(&self) -> Vec<&str> {
+        self.breakers
+            .iter()
+            .filter(|(_, status)| status.is_open)
+            .map(|(id, _)| id.as_str())
+            .collect()
+    }
+
+    /// Whether no breaker is open
+    pub fn all_clear(&self) -> bool {
+        self.breakers.values().all(|status| !status.is_open)
+    }
+
+    /// Sum of every breaker's P&L today
+    pub fn total_daily_pnl(&self) -> f64 {
+        self.breakers.values().map(|status| status.daily_pnl).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_breakers_are_independent() {
+        let strict = CircuitBreakerConfig {
+            max_consecutive_losses: 2,
+            ..CircuitBreakerConfig::default()
+        };
+        let mut registry = CircuitBreakerRegistry::default().with_config("scalper", strict);
+
+        for _ in 0..2 {
+            registry.record_trade("scalper", -10.0);
+            registry.record_trade("trend", -10.0);
+        }
+        registry.record_trade("trend", 25.0);
+
+        assert!(!registry.check_and_trigger("scalper").passed);
+        assert!(registry.check_and_trigger("trend").passed);
+        assert!(registry.check("unknown").passed);
+
+        let status = registry.status();
+        assert_eq!(status.open(), vec!["scalper"]);
+        assert!(!status.all_clear());
+        assert_eq!(status.total_daily_pnl(), -15.0);
+        assert_eq!(registry.ids().collect::<Vec<_>>(), vec!["scalper", "trend"]);
+
+        let report = registry.report();
+        assert_eq!(report.failed_checks().len(), 1);
+        assert_eq!(report.failed_checks()[0].0, "Circuit breaker scalper");
+
+        registry.reset("scalper");
+        assert!(registry.status().all_clear());
+    }
+}
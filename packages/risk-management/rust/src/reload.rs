@@ -0,0 +1,165 @@
+//! Config hot-reload
+//!
+//! [`ConfigWatcher`] polls a TOML config file and reloads it when its
+//! contents change, so limits can be tightened during an incident without
+//! restarting the trading process. A new file is parsed and validated first:
+//! if it is rejected the previous config stays in force. Either way a
+//! [`RiskEvent`] (`ConfigReloaded` with the changed sections, or
+//! `ConfigRejected`) is published. [`ConfigWatcher::watch`] applies each
+//! reload to a [`RiskManager`] and publishes on its event channel.
+//!
+//! ```rust,ignore
+//! let watcher = ConfigWatcher::open("risk.toml")?;
+//! let risk = watcher.config().risk_manager();
+//! let limits = watcher.shared();
+//! tokio::spawn(watcher.watch(risk.clone(), std::time::Duration::from_secs(2)));
+//!
+//! // elsewhere, always the latest valid limits
+//! let max_daily_loss = limits.read().unwrap().limits.max_daily_loss;
+//! ```
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use tokio::sync::broadcast;
+
+use crate::config::RiskConfig;
+use crate::error::{Error, Result};
+use crate::events::{EventEmitter, RiskEvent, RiskSource};
+use crate::manager::RiskManager;
+
+/// Reloads a [`RiskConfig`] file when it changes
+#[derive(Debug)]
+pub struct ConfigWatcher {
+    path: PathBuf,
+    /// Contents last read, valid or not, so a change is handled once
+    last_text: String,
+    config: Arc<RwLock<RiskConfig>>,
+    events: EventEmitter,
+}
+
+impl ConfigWatcher {
+    /// Load and validate `path`, then watch it
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let last_text = std::fs::read_to_string(&path)?;
+        let config = RiskConfig::from_toml_str(&last_text)
+            .map_err(|e| Error::Config(format!("{}: {}", path.display(), e)))?;
+        Ok(Self {
+            path,
+            last_text,
+            config: Arc::new(RwLock::new(config)),
+            events: EventEmitter::new(RiskSource::Config),
+        })
+    }
+
+    /// Publish events on `sender`, e.g. a risk manager's
+    pub fn with_events(mut self, sender: broadcast::Sender<RiskEvent>) -> Self {
+        self.events.attach(sender);
+        self
+    }
+
+    /// Receive this watcher's future events
+    pub fn subscribe(&mut self) -> broadcast::Receiver<RiskEvent> {
+        self.events.subscribe()
+    }
+
+    /// The current config
+    pub fn config(&self) -> RiskConfig {
+        self.read().clone()
+    }
+
+    /// Handle to the current config for code that reads limits directly
+    pub fn shared(&self) -> Arc<RwLock<RiskConfig>> {
+        self.config.clone()
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, RiskConfig> {
+        self.config.read().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Reload the file if it changed since the last poll
+    ///
+    /// Returns the changed top-level sections, or `None` if the contents
+    /// are unchanged or only reformatted. A file that fails to load is
+    /// reported once and leaves the current config in place.
+    pub fn poll(&mut self) -> Result<Option<Vec<String>>> {
+        let text = std::fs::read_to_string(&self.path)?;
+        if text == self.last_text {
+            return Ok(None);
+        }
+        self.last_text = text;
+
+        let config = match RiskConfig::from_toml_str(&self.last_text) {
+            Ok(config) => config,
+            Err(e) => {
+                let error = format!("{}: {}", self.path.display(), e);
+                tracing::warn!("Rejected risk config reload: {}", error);
+                self.events.config_rejected(&error);
+                return Err(Error::Config(error));
+            }
+        };
+        let sections = changed_sections(&self.read(), &config)?;
+        if sections.is_empty() {
+            return Ok(None);
+        }
+
+        *self.config.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = config;
+        tracing::info!("Reloaded risk config: {}", sections.join(", "));
+        self.events.config_reloaded(sections.clone());
+        Ok(Some(sections))
+    }
+
+    /// Poll every `interval` and apply each reload to `manager`, publishing
+    /// events on its channel; runs until the task is dropped
+    pub async fn watch(mut self, manager: RiskManager, interval: std::time::Duration) {
+        self.events.attach(manager.event_sender());
+        loop {
+            tokio::time::sleep(interval).await;
+            match self.poll() {
+                Ok(Some(_)) => manager.apply_config(&self.config()).await,
+                Ok(None) | Err(Error::Config(_)) => {}
+                Err(e) => tracing::warn!("Could not read risk config {}: {}", self.path.display(), e),
+            }
+        }
+    }
+}
+
+/// Top-level sections whose values differ
+fn changed_sections(old: &RiskConfig, new: &RiskConfig) -> Result<Vec<String>> {
+    let to_table = |config: &RiskConfig| {
+        toml::Table::try_from(config).map_err(|e| Error::Config(format!("Cannot compare configs: {}", e)))
+    };
+    let (old, new) = (to_table(old)?, to_table(new)?);
+    Ok(new
+        .iter()
+        .filter(|(section, value)| old.get(*section) != Some(*value))
+        .map(|(section, _)| section.clone())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reload_and_reject() {
+        let path = std::env::temp_dir().join(format!("risk-reload-{}.toml", std::process::id()));
+        std::fs::write(&path, "[circuit_breaker]\nmax_consecutive_losses = 5\n").unwrap();
+
+        let mut watcher = ConfigWatcher::open(&path).unwrap();
+        let mut events = watcher.subscribe();
+        assert_eq!(watcher.poll().unwrap(), None);
+
+        std::fs::write(&path, "# tightened\n[circuit_breaker]\nmax_consecutive_losses = 2\n").unwrap();
+        assert_eq!(watcher.poll().unwrap(), Some(vec!["circuit_breaker".to_string()]));
+        assert_eq!(watcher.config().circuit_breaker.max_consecutive_losses, 2);
+
+        std::fs::write(&path, "[circuit_breaker]\nmax_consecutive_losses = 0\n").unwrap();
+        assert!(watcher.poll().is_err());
+        assert_eq!(watcher.shared().read().unwrap().circuit_breaker.max_consecutive_losses, 2);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(matches!(events.try_recv().unwrap(), RiskEvent::ConfigReloaded { sections, .. } if sections == ["circuit_breaker"]));
+        assert!(matches!(events.try_recv().unwrap(), RiskEvent::ConfigRejected { error, .. } if error.contains("max_consecutive_losses")));
+    }
+}
@@ -0,0 +1,232 @@
+//! Rolling-window trade statistics
+//!
+//! [`RollingStats`] keeps the trades inside a window of the latest N trades
+//! or the latest stretch of time and reports win rate, average win and loss,
+//! and win/loss streaks over them. [`RollingStats::kelly`] turns the window
+//! into a [`KellySizing`], so the Kelly inputs follow recent results instead
+//! of being fixed constants.
+//!
+//! ```rust,ignore
+//! let mut stats = RollingStats::by_duration(Duration::days(30));
+//! stats.record(120.0);
+//! stats.record(-80.0);
+//!
+//! if let Some(kelly) = stats.kelly() {
+//!     sizer = PositionSizer::new(kelly).with_max_size(1_000.0);
+//! }
+//! ```
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+use crate::circuit_breaker::TradeRecord;
+use crate::position_sizing::KellySizing;
+
+/// Which trades count as recent
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RollingWindow {
+    /// The latest N trades
+    Trades(usize),
+    /// Trades closed within this long of the latest one
+    Duration(#[serde(with = "crate::config::duration_secs")] Duration),
+}
+
+/// Win rate, averages and streaks over a rolling window of trades
+#[derive(Clone, Debug)]
+pub struct RollingStats {
+    window: RollingWindow,
+    trades: VecDeque<TradeRecord>,
+}
+
+impl RollingStats {
+    pub fn new(window: RollingWindow) -> Self {
+        Self {
+            window,
+            trades: VecDeque::new(),
+        }
+    }
+
+    /// Window of the latest `trades` trades
+    pub fn by_count(trades: usize) -> Self {
+        Self::new(RollingWindow::Trades(trades))
+    }
+
+    /// Window of trades closed within `duration` of the latest one
+    pub fn by_duration(duration: Duration) -> Self {
+        Self::new(RollingWindow::Duration(duration))
+    }
+
+    pub fn window(&self) -> RollingWindow {
+        self.window
+    }
+
+    /// Record a trade closed now
+    pub fn record(&mut self, pnl: f64) {
+        self.record_at(pnl, Utc::now());
+    }
+
+    /// Record a trade closed at `timestamp`, dropping trades that fall out
+    /// of the window
+    pub fn record_at(&mut self, pnl: f64, timestamp: DateTime<Utc>) {
+        self.trades.push_back(TradeRecord::new(pnl, timestamp));
+        self.expire(timestamp);
+    }
+
+    /// Drop trades outside the window as of `now`; only time windows change
+    pub fn expire(&mut self, now: DateTime<Utc>) {
+        match self.window {
+            RollingWindow::Trades(n) => {
+                while self.trades.len() > n {
+                    self.trades.pop_front();
+                }
+            }
+            RollingWindow::Duration(duration) => {
+                while self.trades.front().is_some_and(|t| now - t.timestamp > duration) {
+                    self.trades.pop_front();
+                }
+            }
+        }
+    }
+
+    /// Trades in the window, oldest first
+    pub fn trades(&self) -> impl Iterator<Item = &TradeRecord> {
+        self.trades.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.trades.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.trades.is_empty()
+    }
+
+    /// Fraction of profitable trades, 0 if empty
+    pub fn win_rate(&self) -> f64 {
+        if self.trades.is_empty() {
+            return 0.0;
+        }
+        self.wins().count() as f64 / self.trades.len() as f64
+    }
+
+    /// Mean P&L of profitable trades, 0 if none
+    pub fn avg_win(&self) -> f64 {
+        mean(self.wins().map(|t| t.pnl))
+    }
+
+    /// Mean loss of losing trades as a positive number, 0 if none
+    pub fn avg_loss(&self) -> f64 {
+        mean(self.trades.iter().filter(|t| !t.was_profitable).map(|t| -t.pnl))
+    }
+
+    /// Length of the run the latest trade belongs to: positive for wins,
+    /// negative for losses
+    pub fn current_streak(&self) -> i64 {
+        let Some(last) = self.trades.back() else { return 0 };
+        let run = self
+            .trades
+            .iter()
+            .rev()
+            .take_while(|t| t.was_profitable == last.was_profitable)
+            .count() as i64;
+        if last.was_profitable {
+            run
+        } else {
+            -run
+        }
+    }
+
+    pub fn longest_win_streak(&self) -> usize {
+        self.longest_streak(true)
+    }
+
+    pub fn longest_loss_streak(&self) -> usize {
+        self.longest_streak(false)
+    }
+
+    /// Kelly sizing from the window's win rate and average win and loss;
+    /// `None` until the window holds at least one win and one loss
+    pub fn kelly(&self) -> Option<KellySizing> {
+        let losses = self.trades.iter().filter(|t| !t.was_profitable).count();
+        if losses == 0 || losses == self.trades.len() {
+            return None;
+        }
+        Some(
+            KellySizing::new()
+                .win_rate(self.win_rate())
+                .avg_win(self.avg_win())
+                .avg_loss(self.avg_loss()),
+        )
+    }
+
+    fn wins(&self) -> impl Iterator<Item = &TradeRecord> {
+        self.trades.iter().filter(|t| t.was_profitable)
+    }
+
+    fn longest_streak(&self, profitable: bool) -> usize {
+        let mut longest = 0;
+        let mut run = 0;
+        for trade in &self.trades {
+            run = if trade.was_profitable == profitable { run + 1 } else { 0 };
+            longest = longest.max(run);
+        }
+        longest
+    }
+}
+
+impl Default for RollingStats {
+    fn default() -> Self {
+        Self::by_count(100)
+    }
+}
+
+fn mean(values: impl Iterator<Item = f64>) -> f64 {
+    let (sum, n) = values.fold((0.0, 0), |(sum, n), v| (sum + v, n + 1));
+    if n == 0 {
+        0.0
+    } else {
+        sum / n as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_count_window() {
+        let mut stats = RollingStats::by_count(5);
+        for pnl in [-500.0, 30.0, -10.0, -10.0, 20.0, 40.0] {
+            stats.record(pnl);
+        }
+        // The -500 loss has left the window
+        assert_eq!(stats.len(), 5);
+        assert_eq!(stats.win_rate(), 0.6);
+        assert_eq!(stats.avg_win(), 30.0);
+        assert_eq!(stats.avg_loss(), 10.0);
+        assert_eq!(stats.current_streak(), 2);
+        assert_eq!(stats.longest_loss_streak(), 2);
+
+        let kelly = stats.kelly().unwrap();
+        assert!((kelly.kelly_fraction() - (0.6 - 0.4 / 3.0)).abs() < 1e-12);
+        assert!(RollingStats::by_count(3).kelly().is_none());
+    }
+
+    #[test]
+    fn test_duration_window() {
+        let start = Utc.with_ymd_and_hms(2025, 3, 3, 0, 0, 0).unwrap();
+        let mut stats = RollingStats::by_duration(Duration::days(7));
+        stats.record_at(100.0, start);
+        stats.record_at(-20.0, start + Duration::days(3));
+        stats.record_at(-30.0, start + Duration::days(8));
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats.current_streak(), -2);
+        assert!(stats.kelly().is_none());
+
+        stats.expire(start + Duration::days(12));
+        assert_eq!(stats.trades().map(|t| t.pnl).collect::<Vec<_>>(), vec![-30.0]);
+    }
+}
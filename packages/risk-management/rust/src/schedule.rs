@@ -0,0 +1,248 @@
+//! Trading hours and blackouts
+//!
+//! [`ScheduleCheck`] fails outside the allowed trading windows, on weekends
+//! and holidays if configured, and during blackouts: fixed periods such as
+//! 30 minutes either side of a market's resolution. All times are UTC.
+//!
+//! ```rust,ignore
+//! let schedule = ScheduleCheck::new()
+//!     .window(TradingWindow::weekdays(NaiveTime::from_hms_opt(13, 30, 0).unwrap(), NaiveTime::from_hms_opt(20, 0, 0).unwrap()))
+//!     .holiday(NaiveDate::from_ymd_opt(2025, 12, 25).unwrap())
+//!     .blackout(Blackout::around(resolution_time, Duration::minutes(30), "Resolution").for_market("FED-RATE-DEC"));
+//!
+//! if !schedule.check().passed {
+//!     return;
+//! }
+//! ```
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+
+use crate::pretrade::{OrderIntent, PreTradeCheck, PreTradeContext};
+use crate::types::{RiskCheck, RiskLevel};
+
+/// Time of day trading is allowed on some days of the week
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TradingWindow {
+    pub days: Vec<Weekday>,
+    pub start: NaiveTime,
+    /// Before `start` for windows crossing midnight, which count toward the
+    /// day they start on
+    pub end: NaiveTime,
+}
+
+impl TradingWindow {
+    pub fn new(days: Vec<Weekday>, start: NaiveTime, end: NaiveTime) -> Self {
+        Self { days, start, end }
+    }
+
+    /// Monday to Friday
+    pub fn weekdays(start: NaiveTime, end: NaiveTime) -> Self {
+        Self::new(
+            vec![Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri],
+            start,
+            end,
+        )
+    }
+
+    /// Whether `at` falls inside the window
+    pub fn contains(&self, at: DateTime<Utc>) -> bool {
+        let time = at.time();
+        if self.start <= self.end {
+            self.days.contains(&at.weekday()) && time >= self.start && time < self.end
+        } else {
+            // Crosses midnight: the late part belongs to today, the early
+            // part to yesterday
+            (self.days.contains(&at.weekday()) && time >= self.start)
+                || (self.days.contains(&at.weekday().pred()) && time < self.end)
+        }
+    }
+}
+
+/// A period with no trading
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Blackout {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub reason: String,
+    /// Applies only to orders in this market; to everything if `None`
+    pub market: Option<String>,
+}
+
+impl Blackout {
+    pub fn new(start: DateTime<Utc>, end: DateTime<Utc>, reason: impl Into<String>) -> Self {
+        Self {
+            start,
+            end,
+            reason: reason.into(),
+            market: None,
+        }
+    }
+
+    /// `margin` either side of `at`, e.g. a market's resolution
+    pub fn around(at: DateTime<Utc>, margin: Duration, reason: impl Into<String>) -> Self {
+        Self::new(at - margin, at + margin, reason)
+    }
+
+    /// Limit the blackout to `market`
+    pub fn for_market(mut self, market: impl Into<String>) -> Self {
+        self.market = Some(market.into());
+        self
+    }
+
+    fn applies(&self, market: Option<&str>, at: DateTime<Utc>) -> bool {
+        let market_matches = match (&self.market, market) {
+            (None, _) => true,
+            (Some(own), Some(market)) => own == market,
+            (Some(_), None) => false,
+        };
+        market_matches && at >= self.start && at < self.end
+    }
+}
+
+/// Trading windows, weekend and holiday closures, and blackouts
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScheduleCheck {
+    /// Allowed windows; always open if empty
+    pub windows: Vec<TradingWindow>,
+    pub block_weekends: bool,
+    pub holidays: Vec<NaiveDate>,
+    pub blackouts: Vec<Blackout>,
+}
+
+impl ScheduleCheck {
+    /// Always open until configured
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allow trading in `window`; with several windows, any one suffices
+    pub fn window(mut self, window: TradingWindow) -> Self {
+        self.windows.push(window);
+        self
+    }
+
+    /// Close on Saturdays and Sundays
+    pub fn no_weekends(mut self) -> Self {
+        self.block_weekends = true;
+        self
+    }
+
+    /// Close all day on `date`
+    pub fn holiday(mut self, date: NaiveDate) -> Self {
+        self.holidays.push(date);
+        self
+    }
+
+    pub fn blackout(mut self, blackout: Blackout) -> Self {
+        self.blackouts.push(blackout);
+        self
+    }
+
+    /// Add a blackout to a running schedule
+    pub fn add_blackout(&mut self, blackout: Blackout) {
+        self.blackouts.push(blackout);
+    }
+
+    /// Drop blackouts that ended before `now`
+    pub fn prune(&mut self, now: DateTime<Utc>) {
+        self.blackouts.retain(|b| b.end > now);
+    }
+
+    /// Whether trading is allowed now
+    pub fn check(&self) -> RiskCheck {
+        self.check_at(None, Utc::now())
+    }
+
+    /// Whether trading `market` (or anything, if `None`) is allowed at `at`
+    pub fn check_at(&self, market: Option<&str>, at: DateTime<Utc>) -> RiskCheck {
+        let closed = |message: String| RiskCheck::fail(RiskLevel::High, message);
+
+        if let Some(blackout) = self.blackouts.iter().find(|b| b.applies(market, at)) {
+            return closed(format!(
+                "Blackout until {}: {}",
+                blackout.end.format("%Y-%m-%d %H:%M UTC"),
+                blackout.reason
+            ));
+        }
+        if self.holidays.contains(&at.date_naive()) {
+            return closed(format!("Holiday: {}", at.date_naive()));
+        }
+        if self.block_weekends && matches!(at.weekday(), Weekday::Sat | Weekday::Sun) {
+            return closed(format!("Weekend: {}", at.weekday()));
+        }
+        if !self.windows.is_empty() && !self.windows.iter().any(|w| w.contains(at)) {
+            return closed(format!("Outside trading hours: {}", at.format("%a %H:%M UTC")));
+        }
+        RiskCheck::pass("Within trading hours")
+    }
+}
+
+/// Blackouts for the order's market apply as well as global ones
+impl PreTradeCheck for ScheduleCheck {
+    fn check(&self, intent: &OrderIntent, _ctx: &PreTradeContext<'_>) -> RiskCheck {
+        self.check_at(Some(&intent.market), Utc::now())
+    }
+
+    fn name(&self) -> &'static str {
+        "Schedule"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        // 2025-06-02 is a Monday
+        Utc.with_ymd_and_hms(2025, 6, day, hour, minute, 0).unwrap()
+    }
+
+    fn time(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn test_windows() {
+        let schedule = ScheduleCheck::new().window(TradingWindow::weekdays(time(13, 30), time(20, 0)));
+        assert!(schedule.check_at(None, at(2, 14, 0)).passed);
+        assert!(!schedule.check_at(None, at(2, 20, 0)).passed);
+        assert!(!schedule.check_at(None, at(7, 14, 0)).passed);
+
+        let overnight = TradingWindow::new(vec![Weekday::Fri], time(22, 0), time(2, 0));
+        assert!(overnight.contains(at(6, 23, 0)));
+        assert!(overnight.contains(at(7, 1, 0)));
+        assert!(!overnight.contains(at(6, 1, 0)));
+    }
+
+    #[test]
+    fn test_weekends_and_holidays() {
+        let schedule = ScheduleCheck::new()
+            .no_weekends()
+            .holiday(NaiveDate::from_ymd_opt(2025, 6, 19).unwrap());
+        assert!(schedule.check_at(None, at(18, 12, 0)).passed);
+        assert!(schedule.check_at(None, at(19, 12, 0)).message.starts_with("Holiday"));
+        let check = schedule.check_at(None, at(8, 12, 0));
+        assert!(!check.passed);
+        assert_eq!(check.message, "Weekend: Sun");
+    }
+
+    #[test]
+    fn test_blackouts() {
+        let mut schedule = ScheduleCheck::new()
+            .blackout(Blackout::around(at(3, 18, 0), Duration::minutes(30), "FOMC"))
+            .blackout(Blackout::around(at(4, 12, 0), Duration::minutes(30), "Resolution").for_market("ELECTION"));
+
+        assert!(!schedule.check_at(None, at(3, 17, 45)).passed);
+        assert!(schedule.check_at(None, at(3, 18, 30)).passed);
+
+        assert!(!schedule.check_at(Some("ELECTION"), at(4, 12, 10)).passed);
+        assert!(schedule.check_at(Some("BTC-USD"), at(4, 12, 10)).passed);
+        assert!(schedule.check_at(None, at(4, 12, 10)).passed);
+
+        schedule.prune(at(4, 0, 0));
+        assert_eq!(schedule.blackouts.len(), 1);
+    }
+}
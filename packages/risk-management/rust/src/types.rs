@@ -1,10 +1,14 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::config::ensure;
+use crate::error::Result;
+
 /// Risk level classification
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum RiskLevel {
     /// Normal operation
+    #[default]
     Normal = 0,
     /// Elevated risk - proceed with caution
     Elevated = 1,
@@ -118,8 +122,21 @@ impl RiskReport {
     }
 }
 
+/// Level header, then one line per check
+impl std::fmt::Display for RiskReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} Risk report: {}", self.overall_level.emoji(), self.overall_level)?;
+        for (name, check) in &self.checks {
+            let mark = if check.passed { "✅" } else { "❌" };
+            write!(f, "\n{} {}: {}", mark, name, check.message)?;
+        }
+        Ok(())
+    }
+}
+
 /// Trading limits configuration
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct TradingLimits {
     pub max_position_size: f64,
     pub max_daily_loss: f64,
@@ -140,6 +157,88 @@ impl Default for TradingLimits {
     }
 }
 
+impl TradingLimits {
+    /// Reject non-positive limits
+    pub fn validate(&self) -> Result<()> {
+        ensure(self.max_position_size > 0.0, "limits.max_position_size must be positive")?;
+        ensure(self.max_daily_loss > 0.0, "limits.max_daily_loss must be positive")?;
+        ensure(
+            self.max_drawdown_pct > 0.0 && self.max_drawdown_pct <= 100.0,
+            "limits.max_drawdown_pct must be in (0, 100]",
+        )?;
+        ensure(self.max_open_positions > 0, "limits.max_open_positions must be at least 1")?;
+        ensure(self.max_consecutive_losses > 0, "limits.max_consecutive_losses must be at least 1")
+    }
+}
+
+/// Order side
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+/// Order a strategy wants to place, as seen by pre-trade checks
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Order {
+    pub market: String,
+    pub side: Side,
+    pub size: f64,
+    pub price: f64,
+    /// Only shrinks or closes an open position, so it may pass while the
+    /// kill switch is close-only
+    #[serde(default)]
+    pub reduces_position: bool,
+}
+
+impl Order {
+    pub fn new(market: impl Into<String>, side: Side, size: f64, price: f64) -> Self {
+        Self {
+            market: market.into(),
+            side,
+            size,
+            price,
+            reduces_position: false,
+        }
+    }
+    
+    /// Mark the order as only reducing or closing a position
+    pub fn reducing(mut self) -> Self {
+        self.reduces_position = true;
+        self
+    }
+    
+    /// Size times price
+    pub fn notional(&self) -> f64 {
+        self.size * self.price
+    }
+}
+
+/// Executed trade reported back to the risk state
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Fill {
+    pub market: String,
+    pub side: Side,
+    pub size: f64,
+    pub price: f64,
+    /// Realized PnL of the trade, 0 for fills that only open a position
+    pub pnl: f64,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl Fill {
+    pub fn new(market: impl Into<String>, side: Side, size: f64, price: f64, pnl: f64) -> Self {
+        Self {
+            market: market.into(),
+            side,
+            size,
+            price,
+            pnl,
+            timestamp: Utc::now(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -147,8 +246,8 @@ mod tests {
     #[test]
     fn test_risk_level_ordering() {
         assert!(RiskLevel::Normal < RiskLevel::Critical);
-        assert!(RiskLevel::High.allows_trading() == false);
-        assert!(RiskLevel::Normal.allows_trading() == true);
+        assert!(!RiskLevel::High.allows_trading());
+        assert!(RiskLevel::Normal.allows_trading());
     }
     
     #[test]
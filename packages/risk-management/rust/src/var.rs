@@ -0,0 +1,352 @@
+//! Value at Risk
+//!
+//! [`VarModel`] estimates how much a portfolio could lose over a horizon at
+//! a given confidence, from each position's value and the history of its
+//! per-period returns (e.g. daily). VaR is the loss exceeded only
+//! `1 - confidence` of the time; CVaR (expected shortfall) is the average loss
+//! in that tail.
+//!
+//! - [`VarMethod::Historical`] replays past returns against today's positions
+//! - [`VarMethod::Parametric`] fits a normal distribution to the same P&L
+//!
+//! Multi-period horizons are scaled from one period by the square root of
+//! time.
+//!
+//! ```rust,ignore
+//! let model = VarModel::new(VarConfig { max_var: Some(500.0), ..VarConfig::default() });
+//! let positions = vec![PositionReturns::new("BTC-USD", 5_000.0, btc_daily_returns)];
+//! let report = model.add_to_report(RiskReport::new(), &positions);
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::ensure;
+use crate::error::Result;
+use crate::types::{RiskCheck, RiskLevel, RiskReport};
+
+/// How VaR is estimated
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VarMethod {
+    Historical,
+    Parametric,
+}
+
+/// VaR settings and limits
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VarConfig {
+    /// e.g. 0.95 or 0.99
+    pub confidence: f64,
+    /// Periods of the return series, e.g. 10 for 10-day VaR on daily returns
+    pub horizon: u32,
+    pub method: VarMethod,
+    /// Largest acceptable VaR, in account currency
+    pub max_var: Option<f64>,
+    /// Largest acceptable CVaR, in account currency
+    pub max_cvar: Option<f64>,
+}
+
+impl Default for VarConfig {
+    fn default() -> Self {
+        Self {
+            confidence: 0.95,
+            horizon: 1,
+            method: VarMethod::Historical,
+            max_var: None,
+            max_cvar: None,
+        }
+    }
+}
+
+impl VarConfig {
+    /// Reject confidences outside (0, 1) and empty horizons
+    pub fn validate(&self) -> Result<()> {
+        ensure(
+            self.confidence > 0.0 && self.confidence < 1.0,
+            "var.confidence must be between 0 and 1",
+        )?;
+        ensure(self.horizon > 0, "var.horizon must be at least 1")?;
+        ensure(self.max_var.map_or(true, |v| v >= 0.0), "var.max_var must not be negative")?;
+        ensure(self.max_cvar.map_or(true, |v| v >= 0.0), "var.max_cvar must not be negative")
+    }
+}
+
+/// A position's current value and past returns
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PositionReturns {
+    pub market: String,
+    /// Signed value; negative for shorts
+    pub value: f64,
+    /// Per-period fractional returns, oldest first, e.g. 0.02 for +2%
+    pub returns: Vec<f64>,
+}
+
+impl PositionReturns {
+    pub fn new(market: impl Into<String>, value: f64, returns: Vec<f64>) -> Self {
+        Self {
+            market: market.into(),
+            value,
+            returns,
+        }
+    }
+}
+
+/// Estimated tail loss of a portfolio
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct VarEstimate {
+    /// Loss exceeded with probability `1 - confidence`, positive for a loss
+    pub var: f64,
+    /// Average loss beyond `var`
+    pub cvar: f64,
+    pub confidence: f64,
+    pub horizon: u32,
+    pub method: VarMethod,
+}
+
+impl VarEstimate {
+    fn label(&self) -> String {
+        format!("{:.0}% {}-period", self.confidence * 100.0, self.horizon)
+    }
+}
+
+/// VaR and CVaR estimator
+#[derive(Clone, Debug, Default)]
+pub struct VarModel {
+    config: VarConfig,
+}
+
+impl VarModel {
+    pub fn new(config: VarConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn config(&self) -> &VarConfig {
+        &self.config
+    }
+
+    /// Estimate with the configured method; `None` with fewer than two
+    /// aligned returns
+    pub fn estimate(&self, positions: &[PositionReturns]) -> Option<VarEstimate> {
+        match self.config.method {
+            VarMethod::Historical => self.historical(positions),
+            VarMethod::Parametric => self.parametric(positions),
+        }
+    }
+
+    /// VaR from the empirical distribution of past portfolio P&L
+    pub fn historical(&self, positions: &[PositionReturns]) -> Option<VarEstimate> {
+        let mut pnl = portfolio_pnl(positions)?;
+        pnl.sort_by(|a, b| a.total_cmp(b));
+        // Round off float noise so 10% of 20 periods is 2, not 3
+        let tail_len = (((1.0 - self.config.confidence) * pnl.len() as f64 - 1e-9).ceil() as usize).clamp(1, pnl.len());
+        let tail = &pnl[..tail_len];
+        let scale = (self.config.horizon as f64).sqrt();
+        Some(self.estimate_of(
+            -tail[tail_len - 1] * scale,
+            -tail.iter().sum::<f64>() / tail_len as f64 * scale,
+            VarMethod::Historical,
+        ))
+    }
+
+    /// VaR from a normal distribution fitted to past portfolio P&L
+    pub fn parametric(&self, positions: &[PositionReturns]) -> Option<VarEstimate> {
+        let pnl = portfolio_pnl(positions)?;
+        let n = pnl.len() as f64;
+        let mean = pnl.iter().sum::<f64>() / n;
+        let std_dev = (pnl.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1.0)).sqrt();
+
+        let horizon = self.config.horizon as f64;
+        let (mean, std_dev) = (mean * horizon, std_dev * horizon.sqrt());
+        let tail = 1.0 - self.config.confidence;
+        let z = normal_quantile(self.config.confidence);
+        Some(self.estimate_of(
+            z * std_dev - mean,
+            std_dev * normal_pdf(z) / tail - mean,
+            VarMethod::Parametric,
+        ))
+    }
+
+    fn estimate_of(&self, var: f64, cvar: f64, method: VarMethod) -> VarEstimate {
+        VarEstimate {
+            var: var.max(0.0),
+            cvar: cvar.max(var).max(0.0),
+            confidence: self.config.confidence,
+            horizon: self.config.horizon,
+            method,
+        }
+    }
+
+    /// VaR against `max_var`; passes when there is no limit or not enough
+    /// data
+    pub fn var_check(&self, estimate: Option<&VarEstimate>) -> RiskCheck {
+        limit_check("VaR", estimate, self.config.max_var, |e| e.var)
+    }
+
+    /// CVaR against `max_cvar`; passes when there is no limit or not enough
+    /// data
+    pub fn cvar_check(&self, estimate: Option<&VarEstimate>) -> RiskCheck {
+        limit_check("CVaR", estimate, self.config.max_cvar, |e| e.cvar)
+    }
+
+    /// Add VaR and CVaR checks for `positions` to `report`
+    pub fn add_to_report(&self, report: RiskReport, positions: &[PositionReturns]) -> RiskReport {
+        let estimate = self.estimate(positions);
+        report
+            .add_check("VaR", self.var_check(estimate.as_ref()))
+            .add_check("CVaR", self.cvar_check(estimate.as_ref()))
+    }
+}
+
+fn limit_check(name: &str, estimate: Option<&VarEstimate>, limit: Option<f64>, value: impl Fn(&VarEstimate) -> f64) -> RiskCheck {
+    let Some(estimate) = estimate else {
+        return RiskCheck::pass(format!("{}: not enough return history", name));
+    };
+    let amount = value(estimate);
+    match limit {
+        Some(limit) if amount > limit => RiskCheck::fail(
+            RiskLevel::High,
+            format!("{} {} {:.2} exceeds limit {:.2}", name, estimate.label(), amount, limit),
+        ),
+        _ => RiskCheck::pass(format!("{} {} {:.2}", name, estimate.label(), amount)),
+    }
+}
+
+/// Portfolio P&L per period over the most recent returns every position has
+fn portfolio_pnl(positions: &[PositionReturns]) -> Option<Vec<f64>> {
+    let len = positions.iter().map(|p| p.returns.len()).min()?;
+    if len < 2 {
+        return None;
+    }
+    Some(
+        (0..len)
+            .map(|i| {
+                positions
+                    .iter()
+                    .map(|p| p.value * p.returns[p.returns.len() - len + i])
+                    .sum()
+            })
+            .collect(),
+    )
+}
+
+fn normal_pdf(x: f64) -> f64 {
+    (-0.5 * x * x).exp() / (2.0 * std::f64::consts::PI).sqrt()
+}
+
+/// Inverse of the standard normal CDF (Acklam's approximation, relative
+/// error below 1.2e-9)
+fn normal_quantile(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969683028665376e1,
+        2.209460984245205e2,
+        -2.759285104469687e2,
+        1.38357751867269e2,
+        -3.066479806614716e1,
+        2.506628277459239,
+    ];
+    const B: [f64; 5] = [
+        -5.447609879822406e1,
+        1.615858368580409e2,
+        -1.556989798598866e2,
+        6.680131188771972e1,
+        -1.328068155288572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784894002430293e-3,
+        -3.223964580411365e-1,
+        -2.400758277161838,
+        -2.549732539343734,
+        4.374664141464968,
+        2.93816398269878,
+    ];
+    const D: [f64; 4] = [
+        7.784695709041462e-3,
+        3.224671290700398e-1,
+        2.445134137142996,
+        3.754408661907416,
+    ];
+    const LOW: f64 = 0.02425;
+
+    let tail = |q: f64| {
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    };
+    if p < LOW {
+        tail((-2.0 * p.ln()).sqrt())
+    } else if p > 1.0 - LOW {
+        -tail((-2.0 * (1.0 - p).ln()).sqrt())
+    } else {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns -10%, -9%, ..., +9%
+    fn returns() -> Vec<f64> {
+        (0..20).map(|i| (i as f64 - 10.0) / 100.0).collect()
+    }
+
+    #[test]
+    fn test_normal_quantile() {
+        assert!((normal_quantile(0.95) - 1.644854).abs() < 1e-6);
+        assert!((normal_quantile(0.99) - 2.326348).abs() < 1e-6);
+        assert!((normal_quantile(0.5)).abs() < 1e-9);
+        assert!((normal_quantile(0.001) + 3.090232).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_historical_var() {
+        let model = VarModel::default();
+        let positions = vec![PositionReturns::new("BTC-USD", 1_000.0, returns())];
+        let estimate = model.historical(&positions).unwrap();
+        // The worst 5% of 20 periods is the single -10% day
+        assert!((estimate.var - 100.0).abs() < 1e-9);
+        assert!((estimate.cvar - 100.0).abs() < 1e-9);
+
+        let model = VarModel::new(VarConfig { confidence: 0.9, horizon: 4, ..VarConfig::default() });
+        let estimate = model.historical(&positions).unwrap();
+        assert!((estimate.var - 180.0).abs() < 1e-9);
+        assert!((estimate.cvar - 190.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_parametric_var() {
+        let model = VarModel::new(VarConfig { method: VarMethod::Parametric, ..VarConfig::default() });
+        let positions = vec![PositionReturns::new("ETH-USD", 1_000.0, returns())];
+        let estimate = model.estimate(&positions).unwrap();
+        let std_dev = (returns().iter().map(|r| (r + 0.005).powi(2)).sum::<f64>() / 19.0).sqrt() * 1_000.0;
+        assert!((estimate.var - (1.644854 * std_dev + 5.0)).abs() < 1e-3);
+        assert!(estimate.cvar > estimate.var);
+    }
+
+    #[test]
+    fn test_hedged_portfolio_has_no_var() {
+        let model = VarModel::default();
+        let positions = vec![
+            PositionReturns::new("BTC-PERP", 1_000.0, returns()),
+            PositionReturns::new("BTC-SPOT", -1_000.0, returns()),
+        ];
+        assert_eq!(model.historical(&positions).unwrap().var, 0.0);
+        assert!(model.historical(&[PositionReturns::new("X", 1.0, vec![0.1])]).is_none());
+    }
+
+    #[test]
+    fn test_checks_feed_report() {
+        let model = VarModel::new(VarConfig { max_var: Some(50.0), ..VarConfig::default() });
+        let positions = vec![PositionReturns::new("SOL-USD", 1_000.0, returns())];
+        let report = model.add_to_report(RiskReport::new(), &positions);
+        assert_eq!(report.failed_checks().len(), 1);
+        assert_eq!(report.failed_checks()[0].0, "VaR");
+        assert_eq!(report.overall_level, RiskLevel::High);
+
+        assert!(model.var_check(None).passed);
+        assert!(VarConfig { confidence: 1.0, ..VarConfig::default() }.validate().is_err());
+    }
+}
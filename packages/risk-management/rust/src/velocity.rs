@@ -0,0 +1,190 @@
+//! Trade velocity limits
+//!
+//! A runaway strategy can send hundreds of orders before any loss- or
+//! balance-based rule notices. [`VelocityLimits`] counts the orders sent in
+//! the last minute and hour and the notional traded in the last hour, and
+//! fails any order that would take one of them past its limit.
+//!
+//! ```rust,ignore
+//! let velocity = Arc::new(Mutex::new(
+//!     VelocityLimits::new().max_orders_per_minute(20).max_notional_per_hour(50_000.0),
+//! ));
+//! let pipeline = PreTradePipeline::new().with_check(velocity.clone());
+//!
+//! if pipeline.evaluate(&intent, &ctx).approved {
+//!     exchange.submit(&intent).await?;
+//!     velocity.lock().unwrap().record_order(intent.notional());
+//! }
+//! ```
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+use crate::config::ensure;
+use crate::error::Result;
+use crate::pretrade::{OrderIntent, PreTradeCheck, PreTradeContext};
+use crate::types::{RiskCheck, RiskLevel};
+
+/// Order rate and traded notional limits
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VelocityLimits {
+    pub max_orders_per_minute: Option<usize>,
+    pub max_orders_per_hour: Option<usize>,
+    pub max_notional_per_hour: Option<f64>,
+    /// Orders sent in the last hour: time and notional
+    #[serde(skip)]
+    orders: VecDeque<(DateTime<Utc>, f64)>,
+}
+
+impl VelocityLimits {
+    /// No limits until some are set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn max_orders_per_minute(mut self, max: usize) -> Self {
+        self.max_orders_per_minute = Some(max);
+        self
+    }
+
+    pub fn max_orders_per_hour(mut self, max: usize) -> Self {
+        self.max_orders_per_hour = Some(max);
+        self
+    }
+
+    pub fn max_notional_per_hour(mut self, max: f64) -> Self {
+        self.max_notional_per_hour = Some(max);
+        self
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        ensure(
+            self.max_orders_per_minute != Some(0),
+            "velocity.max_orders_per_minute must be positive",
+        )?;
+        ensure(
+            self.max_orders_per_hour != Some(0),
+            "velocity.max_orders_per_hour must be positive",
+        )?;
+        ensure(
+            self.max_notional_per_hour.map_or(true, |max| max > 0.0),
+            "velocity.max_notional_per_hour must be positive",
+        )
+    }
+
+    /// Count an order that was sent
+    pub fn record_order(&mut self, notional: f64) {
+        self.record_order_at(notional, Utc::now());
+    }
+
+    pub fn record_order_at(&mut self, notional: f64, at: DateTime<Utc>) {
+        self.orders.push_back((at, notional.abs()));
+        while self
+            .orders
+            .front()
+            .is_some_and(|(sent, _)| *sent <= at - Duration::hours(1))
+        {
+            self.orders.pop_front();
+        }
+    }
+
+    /// Orders sent within `window` before `now`
+    pub fn orders_within(&self, window: Duration, now: DateTime<Utc>) -> usize {
+        self.recent(window, now).count()
+    }
+
+    /// Notional sent within `window` before `now`; at most an hour is kept
+    pub fn notional_within(&self, window: Duration, now: DateTime<Utc>) -> f64 {
+        self.recent(window, now).map(|(_, notional)| notional).sum()
+    }
+
+    fn recent(&self, window: Duration, now: DateTime<Utc>) -> impl Iterator<Item = &(DateTime<Utc>, f64)> {
+        self.orders.iter().filter(move |(sent, _)| *sent > now - window)
+    }
+
+    /// Whether one more order of `notional` stays within every limit
+    pub fn check_order(&self, notional: f64) -> RiskCheck {
+        self.check_order_at(notional, Utc::now())
+    }
+
+    pub fn check_order_at(&self, notional: f64, now: DateTime<Utc>) -> RiskCheck {
+        let windows = [
+            (self.max_orders_per_minute, Duration::minutes(1), "minute"),
+            (self.max_orders_per_hour, Duration::hours(1), "hour"),
+        ];
+        for (max, window, unit) in windows {
+            let Some(max) = max else { continue };
+            let sent = self.orders_within(window, now);
+            if sent >= max {
+                return RiskCheck::fail(
+                    RiskLevel::Critical,
+                    format!("Order rate limit reached: {} orders in the last {} (max {})", sent, unit, max),
+                );
+            }
+        }
+        if let Some(max) = self.max_notional_per_hour {
+            let traded = self.notional_within(Duration::hours(1), now);
+            if traded + notional.abs() > max {
+                return RiskCheck::fail(
+                    RiskLevel::Critical,
+                    format!(
+                        "Hourly notional limit: {:.2} traded, order {:.2} would exceed {:.2}",
+                        traded,
+                        notional.abs(),
+                        max
+                    ),
+                );
+            }
+        }
+        RiskCheck::pass("Order velocity within limits")
+    }
+}
+
+impl PreTradeCheck for VelocityLimits {
+    fn check(&self, intent: &OrderIntent, _ctx: &PreTradeContext<'_>) -> RiskCheck {
+        self.check_order(intent.notional())
+    }
+
+    fn name(&self) -> &'static str {
+        "Velocity"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_order_rate() {
+        let mut velocity = VelocityLimits::new().max_orders_per_minute(3).max_orders_per_hour(5);
+        let start = Utc::now();
+        for i in 0..3 {
+            assert!(velocity.check_order_at(10.0, start + Duration::seconds(i)).passed);
+            velocity.record_order_at(10.0, start + Duration::seconds(i));
+        }
+        let check = velocity.check_order_at(10.0, start + Duration::seconds(3));
+        assert_eq!((check.passed, check.level), (false, RiskLevel::Critical));
+
+        // The minute rolls over, then the hourly count trips
+        let later = start + Duration::minutes(2);
+        velocity.record_order_at(10.0, later);
+        velocity.record_order_at(10.0, later);
+        assert!(velocity.check_order_at(10.0, later).message.contains("last hour"));
+        assert!(velocity.check_order_at(10.0, start + Duration::minutes(61)).passed);
+    }
+
+    #[test]
+    fn test_hourly_notional() {
+        let mut velocity = VelocityLimits::new().max_notional_per_hour(1_000.0);
+        let start = Utc::now();
+        velocity.record_order_at(600.0, start);
+        velocity.record_order_at(-300.0, start + Duration::minutes(30));
+        assert!(velocity.check_order_at(100.0, start + Duration::minutes(31)).passed);
+        assert!(!velocity.check_order_at(101.0, start + Duration::minutes(31)).passed);
+        assert_eq!(velocity.notional_within(Duration::hours(1), start + Duration::minutes(61)), 300.0);
+
+        assert!(VelocityLimits::new().max_orders_per_hour(0).validate().is_err());
+    }
+}
@@ -0,0 +1,252 @@
+//! Volatility estimators
+//!
+//! Streaming estimators fed one candle or price at a time:
+//! - [`Atr`]: Wilder's average true range, in price units
+//! - [`EwmaVolatility`]: exponentially weighted standard deviation of log
+//!   returns (RiskMetrics, λ = 0.94 by default)
+//! - [`RealizedVolatility`]: sample standard deviation of log returns over a
+//!   rolling window
+//!
+//! Volatilities are per period; use [`annualize`] to scale them.
+//! [`VolatilityBasedSizing::update_candle`](crate::position_sizing::VolatilityBasedSizing::update_candle)
+//! keeps its ATR current from the same candles.
+//!
+//! ```rust,ignore
+//! let mut sizer = VolatilityBasedSizing::new(0.02);
+//! let mut vol = RealizedVolatility::new(30);
+//!
+//! while let Some(candle) = candles.next().await {
+//!     sizer.update_candle(&candle);
+//!     if let Some(v) = vol.update(candle.close) {
+//!         println!("annualized vol: {:.1}%", annualize(v, 365.0) * 100.0);
+//!     }
+//! }
+//! ```
+
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+/// OHLC bar
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Candle {
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+}
+
+impl Candle {
+    pub fn new(open: f64, high: f64, low: f64, close: f64) -> Self {
+        Self { open, high, low, close }
+    }
+
+    /// Range of the bar, extended to the previous close across gaps
+    pub fn true_range(&self, prev_close: Option<f64>) -> f64 {
+        let range = self.high - self.low;
+        match prev_close {
+            Some(prev) => range.max((self.high - prev).abs()).max((self.low - prev).abs()),
+            None => range,
+        }
+    }
+}
+
+/// Scale a per-period volatility to a year of `periods_per_year` periods
+pub fn annualize(vol: f64, periods_per_year: f64) -> f64 {
+    vol * periods_per_year.sqrt()
+}
+
+fn log_return(prev: f64, price: f64) -> Option<f64> {
+    (prev > 0.0 && price > 0.0).then(|| (price / prev).ln())
+}
+
+/// Average true range with Wilder smoothing
+#[derive(Clone, Debug)]
+pub struct Atr {
+    period: usize,
+    prev_close: Option<f64>,
+    /// True ranges seen before the first full period
+    seed: Vec<f64>,
+    value: Option<f64>,
+}
+
+impl Atr {
+    pub fn new(period: usize) -> Self {
+        Self {
+            period: period.max(1),
+            prev_close: None,
+            seed: Vec::new(),
+            value: None,
+        }
+    }
+
+    /// Add a candle; returns the ATR once `period` candles have been seen
+    pub fn update(&mut self, candle: &Candle) -> Option<f64> {
+        let tr = candle.true_range(self.prev_close);
+        self.prev_close = Some(candle.close);
+
+        let n = self.period as f64;
+        self.value = match self.value {
+            Some(atr) => Some((atr * (n - 1.0) + tr) / n),
+            None => {
+                self.seed.push(tr);
+                if self.seed.len() < self.period {
+                    return None;
+                }
+                let first = self.seed.iter().sum::<f64>() / n;
+                self.seed.clear();
+                Some(first)
+            }
+        };
+        self.value
+    }
+
+    /// Current ATR, if warmed up
+    pub fn value(&self) -> Option<f64> {
+        self.value
+    }
+
+    pub fn period(&self) -> usize {
+        self.period
+    }
+}
+
+/// Exponentially weighted volatility of log returns
+#[derive(Clone, Debug)]
+pub struct EwmaVolatility {
+    lambda: f64,
+    last_price: Option<f64>,
+    variance: Option<f64>,
+}
+
+impl EwmaVolatility {
+    /// `lambda` is the decay per period, in (0, 1)
+    pub fn new(lambda: f64) -> Self {
+        Self {
+            lambda: lambda.clamp(0.01, 0.9999),
+            last_price: None,
+            variance: None,
+        }
+    }
+
+    /// Add a price; returns the volatility from the second price on
+    pub fn update(&mut self, price: f64) -> Option<f64> {
+        let prev = self.last_price.replace(price);
+        if let Some(r) = prev.and_then(|prev| log_return(prev, price)) {
+            let squared = r * r;
+            self.variance = Some(match self.variance {
+                Some(var) => self.lambda * var + (1.0 - self.lambda) * squared,
+                None => squared,
+            });
+        }
+        self.value()
+    }
+
+    /// Current volatility, if any return has been seen
+    pub fn value(&self) -> Option<f64> {
+        self.variance.map(f64::sqrt)
+    }
+}
+
+impl Default for EwmaVolatility {
+    /// RiskMetrics daily decay
+    fn default() -> Self {
+        Self::new(0.94)
+    }
+}
+
+/// Volatility of log returns over the last `window` periods
+#[derive(Clone, Debug)]
+pub struct RealizedVolatility {
+    window: usize,
+    last_price: Option<f64>,
+    returns: VecDeque<f64>,
+}
+
+impl RealizedVolatility {
+    pub fn new(window: usize) -> Self {
+        let window = window.max(2);
+        Self {
+            window,
+            last_price: None,
+            returns: VecDeque::with_capacity(window),
+        }
+    }
+
+    /// Add a price; returns the volatility once the window is full
+    pub fn update(&mut self, price: f64) -> Option<f64> {
+        let prev = self.last_price.replace(price);
+        if let Some(r) = prev.and_then(|prev| log_return(prev, price)) {
+            if self.returns.len() == self.window {
+                self.returns.pop_front();
+            }
+            self.returns.push_back(r);
+        }
+        self.value()
+    }
+
+    /// Current volatility, if the window is full
+    pub fn value(&self) -> Option<f64> {
+        if self.returns.len() < self.window {
+            return None;
+        }
+        let n = self.returns.len() as f64;
+        let mean = self.returns.iter().sum::<f64>() / n;
+        let variance = self.returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (n - 1.0);
+        Some(variance.sqrt())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::position_sizing::{SizingStrategy, VolatilityBasedSizing};
+
+    #[test]
+    fn test_atr() {
+        let mut atr = Atr::new(3);
+        assert_eq!(atr.update(&Candle::new(10.0, 11.0, 9.0, 10.0)), None);
+        assert_eq!(atr.update(&Candle::new(10.0, 12.0, 10.0, 11.0)), None);
+        // Gap up: true range runs from the previous close of 11 to the high
+        let first = atr.update(&Candle::new(14.0, 15.0, 13.0, 14.0)).unwrap();
+        assert!((first - 8.0 / 3.0).abs() < 1e-9);
+
+        let next = atr.update(&Candle::new(14.0, 14.5, 13.5, 14.0)).unwrap();
+        assert!((next - (first * 2.0 + 1.0) / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_ewma_and_realized() {
+        let prices = [100.0, 102.0, 100.0, 102.0, 100.0];
+
+        let mut ewma = EwmaVolatility::default();
+        assert_eq!(ewma.update(prices[0]), None);
+        let vols: Vec<f64> = prices[1..].iter().filter_map(|&p| ewma.update(p)).collect();
+        assert_eq!(vols.len(), 4);
+        // Equal-sized moves keep the estimate at the move size
+        assert!((vols[3] - (1.02f64).ln()).abs() < 0.001);
+
+        let mut realized = RealizedVolatility::new(4);
+        let last = prices.iter().filter_map(|&p| realized.update(p)).last().unwrap();
+        assert!(last > 0.0);
+        assert!((annualize(0.01, 365.0) - 0.191).abs() < 0.001);
+
+        let mut flat = RealizedVolatility::new(3);
+        let vol = [50.0; 5].iter().filter_map(|&p| flat.update(p)).last();
+        assert_eq!(vol, Some(0.0));
+    }
+
+    #[test]
+    fn test_sizer_follows_candles() {
+        let mut sizer = VolatilityBasedSizing::new(0.01).with_atr_period(2);
+        let calm = sizer.calculate(10_000.0);
+
+        sizer.update_candle(&Candle::new(100.0, 104.0, 96.0, 100.0));
+        assert_eq!(sizer.calculate(10_000.0), calm);
+        sizer.update_candle(&Candle::new(100.0, 106.0, 94.0, 100.0));
+
+        // ATR of 10 on a close of 100
+        assert!((sizer.atr() - 0.1).abs() < 1e-9);
+        assert!(sizer.calculate(10_000.0) < calm);
+    }
+}
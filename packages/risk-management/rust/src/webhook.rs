@@ -0,0 +1,324 @@
+//! HTTP kill switch endpoint
+//!
+//! [`KillWebhook`] serves a minimal HTTP endpoint so operators and alerting
+//! systems can halt trading from outside the process, without Telegram or a
+//! code path. A `POST /kill` with `Authorization: Bearer <token>` runs the
+//! handler with the request body as the reason; anything else is refused.
+//! Put it behind TLS termination if it is reachable beyond localhost.
+//!
+//! ```rust,ignore
+//! let listener = TcpListener::bind("127.0.0.1:9099").await?;
+//! let risk = manager.clone();
+//! tokio::spawn(KillWebhook::new(token).serve(listener, move |reason| {
+//!     let risk = risk.clone();
+//!     async move { risk.trigger_kill_switch(reason).await }
+//! }));
+//! ```
+//!
+//! ```sh
+//! curl -X POST -H "Authorization: Bearer $TOKEN" -d "exchange incident" http://127.0.0.1:9099/kill
+//! ```
+
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::error::Result;
+
+/// Largest request, headers and body, the endpoint reads
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+/// Default time a client has to send its whole request
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+/// Pause after a failed accept before trying again
+const ACCEPT_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Authenticated HTTP trigger for a kill switch
+#[derive(Clone, Debug)]
+pub struct KillWebhook {
+    token: String,
+    path: String,
+    read_timeout: Duration,
+}
+
+impl KillWebhook {
+    /// Endpoint accepting `token` as its bearer token, at `/kill`
+    pub fn new(token: impl Into<String>) -> Self {
+        Self {
+            token: token.into(),
+            path: "/kill".to_string(),
+            read_timeout: READ_TIMEOUT,
+        }
+    }
+
+    pub fn with_path(mut self, path: impl Into<String>) -> Self {
+        self.path = path.into();
+        self
+    }
+
+    /// Drop connections that have not sent a whole request within `timeout`
+    pub fn with_read_timeout(mut self, timeout: Duration) -> Self {
+        self.read_timeout = timeout;
+        self
+    }
+
+    /// Accept connections on `listener`, calling `on_kill` with the reason
+    /// for every authorized request
+    ///
+    /// A failed accept, e.g. when the process is out of file descriptors,
+    /// is logged and retried after a short pause rather than ending the
+    /// endpoint.
+    pub async fn serve<F, Fut>(self, listener: TcpListener, on_kill: F) -> Result<()>
+    where
+        F: Fn(String) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.serve_with(|| listener.accept(), on_kill).await
+    }
+
+    async fn serve_with<A, AFut, F, Fut>(self, mut accept: A, on_kill: F) -> Result<()>
+    where
+        A: FnMut() -> AFut,
+        AFut: Future<Output = std::io::Result<(TcpStream, SocketAddr)>>,
+        F: Fn(String) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let webhook = Arc::new(self);
+        let on_kill = Arc::new(on_kill);
+        loop {
+            let (stream, peer) = match accept().await {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    tracing::warn!("Kill webhook failed to accept a connection: {}", e);
+                    tokio::time::sleep(ACCEPT_BACKOFF).await;
+                    continue;
+                }
+            };
+            let webhook = webhook.clone();
+            let on_kill = on_kill.clone();
+            tokio::spawn(async move {
+                if let Err(e) = webhook.handle(stream, |reason| on_kill(reason)).await {
+                    tracing::warn!("Kill webhook request from {} failed: {}", peer, e);
+                }
+            });
+        }
+    }
+
+    async fn handle<F, Fut>(&self, mut stream: TcpStream, on_kill: F) -> std::io::Result<()>
+    where
+        F: FnOnce(String) -> Fut,
+        Fut: Future<Output = ()>,
+    {
+        let request = match tokio::time::timeout(self.read_timeout, read_request(&mut stream)).await {
+            Ok(request) => request?,
+            Err(_) => return Err(std::io::Error::new(std::io::ErrorKind::TimedOut, "request not received in time")),
+        };
+        let (status, body) = match request {
+            None => ("400 Bad Request", "Malformed request"),
+            Some(request) if request.path != self.path => ("404 Not Found", "Not found"),
+            Some(request) if request.method != "POST" => ("405 Method Not Allowed", "Use POST"),
+            Some(request) if !self.authorized(request.bearer.as_deref()) => ("401 Unauthorized", "Unauthorized"),
+            Some(request) => {
+                let reason = request.body.trim();
+                let reason = if reason.is_empty() { "HTTP kill request" } else { reason };
+                tracing::warn!("Kill switch triggered over HTTP: {}", reason);
+                on_kill(reason.to_string()).await;
+                ("200 OK", "Kill switch triggered")
+            }
+        };
+        let response = format!(
+            "HTTP/1.1 {}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        );
+        stream.write_all(response.as_bytes()).await?;
+        stream.shutdown().await
+    }
+
+    /// Compare tokens in constant time
+    fn authorized(&self, bearer: Option<&str>) -> bool {
+        let Some(bearer) = bearer else { return false };
+        let (expected, given) = (self.token.as_bytes(), bearer.as_bytes());
+        !expected.is_empty()
+            && expected.len() == given.len()
+            && expected.iter().zip(given).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+    }
+}
+
+struct Request {
+    method: String,
+    path: String,
+    bearer: Option<String>,
+    body: String,
+}
+
+/// Read one request; `None` if it is not HTTP or too large
+async fn read_request(stream: &mut TcpStream) -> std::io::Result<Option<Request>> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 1024];
+    let header_end = loop {
+        if let Some(end) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break end + 4;
+        }
+        if buf.len() > MAX_REQUEST_BYTES {
+            return Ok(None);
+        }
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Ok(None);
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    };
+
+    let Ok(head) = std::str::from_utf8(&buf[..header_end]) else { return Ok(None) };
+    let mut lines = head.lines();
+    let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+    let (Some(method), Some(path)) = (request_line.next(), request_line.next()) else {
+        return Ok(None);
+    };
+    let (method, path) = (method.to_string(), path.to_string());
+
+    let mut bearer = None;
+    let mut content_length = 0;
+    for line in lines {
+        let Some((name, value)) = line.split_once(':') else { continue };
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("authorization") {
+            bearer = value.strip_prefix("Bearer ").map(str::to_string);
+        } else if name.eq_ignore_ascii_case("content-length") {
+            content_length = value.parse().unwrap_or(0);
+        }
+    }
+    let Some(request_end) = header_end.checked_add(content_length).filter(|end| *end <= MAX_REQUEST_BYTES) else {
+        return Ok(None);
+    };
+
+    while buf.len() < request_end {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            break;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+    let body_end = buf.len().min(request_end);
+    Ok(Some(Request {
+        method,
+        path,
+        bearer,
+        body: String::from_utf8_lossy(&buf[header_end..body_end]).into_owned(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kill_switch::KillSwitch;
+    use std::sync::Mutex;
+
+    async fn send(addr: std::net::SocketAddr, request: &str) -> String {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[tokio::test]
+    async fn test_authenticated_kill() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let kill_switch = Arc::new(Mutex::new(KillSwitch::new().balance_floor(0.0)));
+        let shared = kill_switch.clone();
+        tokio::spawn(KillWebhook::new("s3cret").serve(listener, move |reason| {
+            shared.lock().unwrap().trigger(reason);
+            async {}
+        }));
+
+        let denied = send(addr, "POST /kill HTTP/1.1\r\nAuthorization: Bearer wrong\r\n\r\n").await;
+        assert!(denied.starts_with("HTTP/1.1 401"));
+        let wrong_method = send(addr, "GET /kill HTTP/1.1\r\nAuthorization: Bearer s3cret\r\n\r\n").await;
+        assert!(wrong_method.starts_with("HTTP/1.1 405"));
+        assert!(!kill_switch.lock().unwrap().is_triggered());
+
+        let body = "exchange incident";
+        let request = format!(
+            "POST /kill HTTP/1.1\r\nauthorization: Bearer s3cret\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        assert!(send(addr, &request).await.starts_with("HTTP/1.1 200"));
+        assert_eq!(kill_switch.lock().unwrap().trigger_reason(), Some("exchange incident"));
+    }
+
+    #[tokio::test]
+    async fn test_accept_error_is_retried() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let kill_switch = Arc::new(Mutex::new(KillSwitch::new().balance_floor(0.0)));
+        let shared = kill_switch.clone();
+        let failures = Arc::new(Mutex::new(2_usize));
+        let remaining = failures.clone();
+        let listener = &listener;
+        let server = KillWebhook::new("s3cret").serve_with(
+            move || {
+                let fail = {
+                    let mut remaining = remaining.lock().unwrap();
+                    let fail = *remaining > 0;
+                    *remaining = remaining.saturating_sub(1);
+                    fail
+                };
+                async move {
+                    if fail {
+                        Err(std::io::Error::other("too many open files"))
+                    } else {
+                        listener.accept().await
+                    }
+                }
+            },
+            move |reason| {
+                shared.lock().unwrap().trigger(reason);
+                async {}
+            },
+        );
+
+        let request = "POST /kill HTTP/1.1\r\nAuthorization: Bearer s3cret\r\n\r\n";
+        tokio::select! {
+            _ = server => panic!("serve returned"),
+            response = send(addr, request) => assert!(response.starts_with("HTTP/1.1 200")),
+        }
+        assert_eq!(*failures.lock().unwrap(), 0);
+        assert!(kill_switch.lock().unwrap().is_triggered());
+    }
+
+    #[tokio::test]
+    async fn test_oversized_content_length() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(KillWebhook::new("s3cret").serve(listener, |_| async {}));
+
+        let request = format!(
+            "POST /kill HTTP/1.1\r\nAuthorization: Bearer s3cret\r\nContent-Length: {}\r\n\r\n",
+            usize::MAX
+        );
+        assert!(send(addr, &request).await.starts_with("HTTP/1.1 400"));
+    }
+
+    #[tokio::test]
+    async fn test_read_timeout() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let handler = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let webhook = KillWebhook::new("s3cret").with_read_timeout(Duration::from_millis(50));
+            webhook.handle(stream, |_| async {}).await
+        });
+        let mut stalled = TcpStream::connect(addr).await.unwrap();
+        stalled.write_all(b"POST /kill HTTP/1.1\r\n").await.unwrap();
+
+        let error = handler.await.unwrap().unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::TimedOut);
+    }
+}
@@ -1,8 +1,15 @@
 use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use std::fmt::Write;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::error::Error;
+use crate::format::{ParseMode, Text};
 
 /// Alert severity levels
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum AlertLevel {
     Info,
     Success,
@@ -23,6 +30,38 @@ impl AlertLevel {
     }
 }
 
+impl FromStr for AlertLevel {
+    type Err = Error;
+
+    /// Level name, e.g. `warning`, in any case
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "info" => Ok(AlertLevel::Info),
+            "success" => Ok(AlertLevel::Success),
+            "warning" => Ok(AlertLevel::Warning),
+            "error" => Ok(AlertLevel::Error),
+            "critical" => Ok(AlertLevel::Critical),
+            _ => Err(Error::InvalidCommand(format!("Unknown alert level: {}", s))),
+        }
+    }
+}
+
+/// File sent along with an alert, e.g. a CSV trade export
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Attachment {
+    pub filename: String,
+    pub bytes: Vec<u8>,
+}
+
+impl Attachment {
+    pub fn new(filename: impl Into<String>, bytes: Vec<u8>) -> Self {
+        Self {
+            filename: filename.into(),
+            bytes,
+        }
+    }
+}
+
 /// Builder for trading alerts
 #[derive(Clone, Debug)]
 pub struct AlertBuilder {
@@ -30,6 +69,11 @@ pub struct AlertBuilder {
     title: String,
     fields: Vec<(String, String)>,
     timestamp: DateTime<Utc>,
+    dedup_key: Option<String>,
+    dedup_window: Option<Duration>,
+    chart: Option<Vec<u8>>,
+    attachment: Option<Attachment>,
+    parse_mode: ParseMode,
 }
 
 impl AlertBuilder {
@@ -39,6 +83,11 @@ impl AlertBuilder {
             title: title.into(),
             fields: Vec::new(),
             timestamp: Utc::now(),
+            dedup_key: None,
+            dedup_window: None,
+            chart: None,
+            attachment: None,
+            parse_mode: ParseMode::Plain,
         }
     }
     
@@ -62,6 +111,71 @@ impl AlertBuilder {
         Self::new(AlertLevel::Critical, title)
     }
     
+    /// Severity of the alert
+    pub fn level(&self) -> AlertLevel {
+        self.level
+    }
+    
+    /// Throttle this alert with others sharing `key` when dispatched
+    pub fn dedup(mut self, key: impl Into<String>) -> Self {
+        self.dedup_key = Some(key.into());
+        self
+    }
+    
+    /// Send at most one alert with `key` per `window` when dispatched
+    pub fn throttle(mut self, key: impl Into<String>, window: Duration) -> Self {
+        self.dedup_key = Some(key.into());
+        self.dedup_window = Some(window);
+        self
+    }
+    
+    pub fn dedup_key(&self) -> Option<&str> {
+        self.dedup_key.as_deref()
+    }
+    
+    pub fn dedup_window(&self) -> Option<Duration> {
+        self.dedup_window
+    }
+    
+    /// Send the alert as a caption on this PNG, e.g. one rendered by the
+    /// `charts` module
+    pub fn chart(mut self, png: Vec<u8>) -> Self {
+        self.chart = Some(png);
+        self
+    }
+    
+    /// When the alert was created
+    pub fn timestamp(&self) -> DateTime<Utc> {
+        self.timestamp
+    }
+    
+    /// Attached chart image, if any
+    pub fn chart_image(&self) -> Option<&[u8]> {
+        self.chart.as_deref()
+    }
+    
+    /// Send `bytes` as a document named `filename` with the alert
+    pub fn attachment(mut self, filename: impl Into<String>, bytes: Vec<u8>) -> Self {
+        self.attachment = Some(Attachment::new(filename, bytes));
+        self
+    }
+    
+    /// Attached document, if any
+    pub fn attached_file(&self) -> Option<&Attachment> {
+        self.attachment.as_ref()
+    }
+    
+    /// Send the alert formatted for `mode` instead of as plain text
+    pub fn parse_mode(mut self, mode: ParseMode) -> Self {
+        self.parse_mode = mode;
+        self
+    }
+    
+    /// Parse mode the alert is sent with
+    pub fn mode(&self) -> ParseMode {
+        self.parse_mode
+    }
+    
     /// Add a field to the alert
     pub fn field(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
         self.fields.push((name.into(), value.into()));
@@ -79,6 +193,22 @@ impl AlertBuilder {
         self.field(name, format!("{} {:.2}%", emoji, value))
     }
     
+    /// Alert as formatted text: bold title, then one line per field
+    pub fn to_text(&self) -> Text {
+        let mut text = Text::new()
+            .plain(format!("{} ", self.level.emoji()))
+            .bold(self.title.as_str());
+        for (name, value) in &self.fields {
+            text = text.line().plain("• ").bold(name.as_str()).plain(format!(": {}", value));
+        }
+        text
+    }
+    
+    /// Render the alert for `mode`, escaping titles and values
+    pub fn render(&self, mode: ParseMode) -> String {
+        self.to_text().render(mode)
+    }
+    
     /// Build the alert message
     pub fn build(self) -> String {
         let mut msg = String::new();
@@ -159,7 +289,7 @@ pub mod templates {
             AlertLevel::Info
         };
         
-        AlertBuilder::new(level, &format!("{} Price Alert", symbol))
+        AlertBuilder::new(level, format!("{} Price Alert", symbol))
             .price("Current Price", price, "USD")
             .percentage("Change", change_pct)
             .build()
@@ -189,6 +319,19 @@ mod tests {
         assert!(alert.contains("Value"));
     }
     
+    #[test]
+    fn test_alert_render_escapes_values() {
+        let alert = AlertBuilder::warning("Fill *partial*").field("Pair", "BTC_USD");
+        assert_eq!(
+            alert.render(ParseMode::MarkdownV2),
+            "⚠️ *Fill \\*partial\\**\n• *Pair*: BTC\\_USD"
+        );
+        assert_eq!(
+            alert.render(ParseMode::Html),
+            "⚠️ <b>Fill *partial*</b>\n• <b>Pair</b>: BTC_USD"
+        );
+    }
+    
     #[test]
     fn test_format_price() {
         assert_eq!(format_price(100.5, "USD"), "$100.50");
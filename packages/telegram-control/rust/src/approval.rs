@@ -0,0 +1,274 @@
+//! Trade approval
+//!
+//! Commands registered with
+//! [`Bot::on_approved_command`](crate::Bot::on_approved_command) run straight
+//! away for admins. For anyone else the bot posts the request to an admin
+//! chat with Approve/Reject buttons and only runs the handler, with the
+//! requester's original context, once an admin approves it. Requests nobody
+//! answers expire, and the requester is told either way.
+//!
+//! ```rust,ignore
+//! let bot = bot.on_approved_command("/trade", Approval::new(DESK_CHAT), |ctx| async move {
+//!     place_order(&ctx).await?;
+//!     ctx.reply("✅ Order placed").await
+//! });
+//! ```
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use teloxide::prelude::*;
+use teloxide::types::MessageId;
+use tracing::{info, warn};
+
+use crate::auth::AccessControl;
+use crate::bot::HandlerFn;
+use crate::error::Result;
+use crate::keyboards::InlineKeyboardBuilder;
+use crate::types::Context;
+
+/// Callback data prefix for approval buttons
+pub const CALLBACK_PREFIX: &str = "approval:";
+
+crate::callback_data! {
+    /// Approve/Reject button for pending request `id`
+    enum Decision("approval") {
+        Approve("yes") { id: u64 },
+        Reject("no") { id: u64 },
+    }
+}
+
+/// Where approval requests go and how long they stay open
+#[derive(Clone, Debug)]
+pub struct Approval {
+    admin_chat: i64,
+    ttl: Duration,
+}
+
+impl Approval {
+    /// Post requests to `admin_chat`; they expire after 15 minutes
+    pub fn new(admin_chat: i64) -> Self {
+        Self {
+            admin_chat,
+            ttl: Duration::from_secs(15 * 60),
+        }
+    }
+
+    /// How long a request waits for an admin
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+}
+
+struct Pending {
+    ctx: Context,
+    handler: HandlerFn,
+    /// Request as posted in the admin chat
+    prompt: String,
+    admin_message: Option<(i64, MessageId)>,
+    expires_at: Instant,
+}
+
+/// Outcome of a button press
+enum Step {
+    Approve(Box<Pending>),
+    Reject(Box<Pending>),
+    Expired,
+    NotAdmin,
+}
+
+/// Requests waiting for an admin, shared between the command and button
+/// handlers
+#[derive(Clone)]
+pub struct Approvals {
+    access_control: AccessControl,
+    pending: Arc<Mutex<HashMap<u64, Pending>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl Approvals {
+    /// Approvals decided by `access_control`'s admins
+    pub fn new(access_control: AccessControl) -> Self {
+        Self {
+            access_control,
+            pending: Arc::default(),
+            next_id: Arc::default(),
+        }
+    }
+
+    /// Number of unexpired requests
+    pub fn pending(&self) -> usize {
+        let now = Instant::now();
+        self.pending
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|p| p.expires_at > now)
+            .count()
+    }
+
+    /// Run the command for admins, or post it to the admin chat
+    pub(crate) async fn request(&self, ctx: Context, handler: HandlerFn, approval: &Approval) -> Result<()> {
+        if self.access_control.is_admin(ctx.user_id()) {
+            return handler(ctx).await;
+        }
+
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let command = ctx.text().unwrap_or_default().to_string();
+        let prompt = format!(
+            "🔔 Approval requested\nUser: {} ({})\nCommand: {}\nExpires in {} min",
+            ctx.username().unwrap_or("-"),
+            ctx.user_id(),
+            command,
+            approval.ttl.as_secs().div_ceil(60)
+        );
+        let message = ctx
+            .bot()
+            .send_message(ChatId(approval.admin_chat), prompt.clone())
+            .reply_markup(
+                InlineKeyboardBuilder::new()
+                    .data_button("✅ Approve", &Decision::Approve { id })
+                    .data_button("❌ Reject", &Decision::Reject { id })
+                    .build(),
+            )
+            .await?;
+        info!("User {} requested approval for {}", ctx.user_id(), command);
+
+        self.insert(
+            id,
+            Pending {
+                ctx: ctx.clone(),
+                handler,
+                prompt,
+                admin_message: Some((approval.admin_chat, message.id)),
+                expires_at: Instant::now() + approval.ttl,
+            },
+        );
+        let approvals = self.clone();
+        let ttl = approval.ttl;
+        tokio::spawn(async move {
+            tokio::time::sleep(ttl).await;
+            approvals.expire(id).await;
+        });
+
+        ctx.reply(format!("⏳ {} was sent to an admin for approval.", command)).await
+    }
+
+    fn insert(&self, id: u64, pending: Pending) {
+        let mut all = self.pending.lock().unwrap();
+        let now = Instant::now();
+        all.retain(|_, p| p.expires_at > now);
+        all.insert(id, pending);
+    }
+
+    /// Decide request `id` for the admin `user_id`
+    fn decide(&self, id: u64, user_id: i64, approve: bool) -> Step {
+        if !self.access_control.is_admin(user_id) {
+            return Step::NotAdmin;
+        }
+        let mut pending = self.pending.lock().unwrap();
+        match pending.remove(&id) {
+            Some(p) if p.expires_at <= Instant::now() => Step::Expired,
+            Some(p) if approve => Step::Approve(Box::new(p)),
+            Some(p) => Step::Reject(Box::new(p)),
+            None => Step::Expired,
+        }
+    }
+
+    /// Handle an Approve/Reject button press
+    pub(crate) async fn handle_callback(&self, ctx: Context) -> Result<()> {
+        let (id, approve) = match ctx.callback_data::<Decision>() {
+            Some(Decision::Approve { id }) => (id, true),
+            Some(Decision::Reject { id }) => (id, false),
+            None => return Ok(()),
+        };
+        let admin = ctx.username().unwrap_or("an admin").to_string();
+
+        match self.decide(id, ctx.user_id(), approve) {
+            Step::Approve(p) => {
+                info!("User {} approved {:?}", ctx.user_id(), p.ctx.text());
+                p.ctx.reply(format!("✅ {} was approved by {}.", command(&p), admin)).await?;
+                let result = (p.handler)(p.ctx.clone()).await;
+                let outcome = match &result {
+                    Ok(()) => format!("✅ Approved by {}", admin),
+                    Err(e) => format!("⚠️ Approved by {}, but failed: {}", admin, e),
+                };
+                resolve(&p, &outcome).await;
+                result
+            }
+            Step::Reject(p) => {
+                info!("User {} rejected {:?}", ctx.user_id(), p.ctx.text());
+                resolve(&p, &format!("❌ Rejected by {}", admin)).await;
+                p.ctx.reply(format!("❌ {} was rejected by {}.", command(&p), admin)).await
+            }
+            Step::Expired => ctx.answer_alert("⌛ This request has expired.").await,
+            Step::NotAdmin => {
+                warn!("Non-admin {} tried to decide an approval", ctx.user_id());
+                ctx.answer_alert("⛔ Only admins can approve requests.").await
+            }
+        }
+    }
+
+    /// Drop request `id` if still open, telling both sides
+    async fn expire(&self, id: u64) {
+        let Some(p) = self.pending.lock().unwrap().remove(&id) else {
+            return;
+        };
+        info!("Approval for {:?} expired", p.ctx.text());
+        resolve(&p, "⌛ Expired without a decision").await;
+        if let Err(e) = p.ctx.reply(format!("⌛ {} expired without approval.", command(&p))).await {
+            warn!("Failed to notify user {} of expired approval: {}", p.ctx.user_id(), e);
+        }
+    }
+}
+
+fn command(p: &Pending) -> &str {
+    p.ctx.text().unwrap_or("Your request")
+}
+
+/// Record the outcome on the admin chat's message, removing its buttons
+async fn resolve(p: &Pending, outcome: &str) {
+    let Some((chat_id, message_id)) = p.admin_message else {
+        return;
+    };
+    let text = format!("{}\n\n{}", p.prompt, outcome);
+    if let Err(e) = p.ctx.bot().edit_message_text(ChatId(chat_id), message_id, text).await {
+        warn!("Failed to update approval message: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::test_context;
+
+    fn pending(ttl: Duration) -> Pending {
+        let handler: HandlerFn = Arc::new(|_| Box::pin(async { Ok(()) }));
+        Pending {
+            ctx: test_context(7, "/trade BTC 1", None),
+            handler,
+            prompt: String::new(),
+            admin_message: None,
+            expires_at: Instant::now() + ttl,
+        }
+    }
+
+    #[test]
+    fn test_decide() {
+        let approvals = Approvals::new(AccessControl::new().with_admins(vec![1]));
+        approvals.insert(0, pending(Duration::from_secs(60)));
+        approvals.insert(1, pending(Duration::from_secs(60)));
+        assert_eq!(approvals.pending(), 2);
+
+        assert!(matches!(approvals.decide(0, 7, true), Step::NotAdmin));
+        assert!(matches!(approvals.decide(0, 1, true), Step::Approve(p) if p.ctx.user_id() == 7));
+        assert!(matches!(approvals.decide(0, 1, true), Step::Expired));
+        assert!(matches!(approvals.decide(1, 1, false), Step::Reject(_)));
+
+        approvals.insert(2, pending(Duration::ZERO));
+        assert!(matches!(approvals.decide(2, 1, true), Step::Expired));
+        assert_eq!(approvals.pending(), 0);
+    }
+}
@@ -0,0 +1,380 @@
+//! Command argument parsing
+//!
+//! A [`CommandSpec`] describes a command's positional arguments, optional and
+//! defaulted arguments, and `--flags`, and parses message text into
+//! [`ParsedArgs`]. Arguments may be quoted (`"BTC-USD limit"` or `'...'`) to
+//! include spaces. Parse errors and [`CommandSpec::usage`] share the same
+//! generated usage text.
+//!
+//! ```rust,ignore
+//! let spec = CommandSpec::new("/trade", "Place an order")
+//!     .arg("symbol", "Trading pair")
+//!     .arg("amount", "Order size")
+//!     .optional("price", "Limit price; market order if omitted")
+//!     .flag("dry-run", "Simulate without placing the order")
+//!     .option("slippage", "1", "Max slippage %");
+//!
+//! let args = spec.parse("/trade BTC-USD 0.5 --slippage 0.3")?;
+//! let amount: f64 = args.get("amount")?;
+//! let price: Option<f64> = args.get_opt("price")?;
+//! ```
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::str::FromStr;
+
+use crate::error::{Error, Result};
+
+/// Split text into arguments, honouring single and double quotes
+///
+/// Inside double quotes, `\"` and `\\` are escapes.
+pub fn tokenize(input: &str) -> Result<Vec<String>> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_token = false;
+    let mut chars = input.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' | '\'' => {
+                in_token = true;
+                let quote = c;
+                loop {
+                    match chars.next() {
+                        Some(c) if c == quote => break,
+                        Some('\\') if quote == '"' => match chars.next() {
+                            Some(escaped @ ('"' | '\\')) => current.push(escaped),
+                            Some(other) => {
+                                current.push('\\');
+                                current.push(other);
+                            }
+                            None => break,
+                        },
+                        Some(c) => current.push(c),
+                        None => {
+                            return Err(Error::InvalidCommand(format!("Unterminated {} quote", quote)));
+                        }
+                    }
+                }
+            }
+            c if c.is_whitespace() => {
+                if in_token {
+                    tokens.push(std::mem::take(&mut current));
+                    in_token = false;
+                }
+            }
+            c => {
+                in_token = true;
+                current.push(c);
+            }
+        }
+    }
+    if in_token {
+        tokens.push(current);
+    }
+    Ok(tokens)
+}
+
+#[derive(Clone, Debug)]
+enum Kind {
+    Required,
+    Optional,
+    Default(String),
+}
+
+#[derive(Clone, Debug)]
+struct Positional {
+    name: String,
+    help: String,
+    kind: Kind,
+}
+
+#[derive(Clone, Debug)]
+struct Named {
+    name: String,
+    help: String,
+    /// `None` for boolean flags
+    default: Option<String>,
+}
+
+/// Arguments a command accepts
+#[derive(Clone, Debug)]
+pub struct CommandSpec {
+    command: String,
+    description: String,
+    positionals: Vec<Positional>,
+    named: Vec<Named>,
+}
+
+impl CommandSpec {
+    pub fn new(command: impl Into<String>, description: impl Into<String>) -> Self {
+        Self {
+            command: command.into(),
+            description: description.into(),
+            positionals: Vec::new(),
+            named: Vec::new(),
+        }
+    }
+
+    /// Required positional argument
+    pub fn arg(self, name: impl Into<String>, help: impl Into<String>) -> Self {
+        self.positional(name, help, Kind::Required)
+    }
+
+    /// Positional argument that may be left out
+    pub fn optional(self, name: impl Into<String>, help: impl Into<String>) -> Self {
+        self.positional(name, help, Kind::Optional)
+    }
+
+    /// Positional argument that is `default` when left out
+    pub fn default(self, name: impl Into<String>, default: impl Into<String>, help: impl Into<String>) -> Self {
+        self.positional(name, help, Kind::Default(default.into()))
+    }
+
+    fn positional(mut self, name: impl Into<String>, help: impl Into<String>, kind: Kind) -> Self {
+        self.positionals.push(Positional {
+            name: name.into(),
+            help: help.into(),
+            kind,
+        });
+        self
+    }
+
+    /// Boolean `--name` flag
+    pub fn flag(mut self, name: impl Into<String>, help: impl Into<String>) -> Self {
+        self.named.push(Named {
+            name: name.into(),
+            help: help.into(),
+            default: None,
+        });
+        self
+    }
+
+    /// `--name value` or `--name=value` option, `default` when left out
+    pub fn option(mut self, name: impl Into<String>, default: impl Into<String>, help: impl Into<String>) -> Self {
+        self.named.push(Named {
+            name: name.into(),
+            help: help.into(),
+            default: Some(default.into()),
+        });
+        self
+    }
+
+    pub fn command(&self) -> &str {
+        &self.command
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// One-line synopsis, e.g. `/trade <symbol> <amount> [price] [--dry-run]`
+    pub fn synopsis(&self) -> String {
+        let mut line = self.command.clone();
+        for p in &self.positionals {
+            match &p.kind {
+                Kind::Required => write!(line, " <{}>", p.name),
+                Kind::Optional => write!(line, " [{}]", p.name),
+                Kind::Default(d) => write!(line, " [{}={}]", p.name, d),
+            }
+            .unwrap();
+        }
+        for n in &self.named {
+            match &n.default {
+                None => write!(line, " [--{}]", n.name),
+                Some(_) => write!(line, " [--{} <value>]", n.name),
+            }
+            .unwrap();
+        }
+        line
+    }
+
+    /// Synopsis, description and one line per argument
+    pub fn usage(&self) -> String {
+        let mut text = format!("Usage: {}\n{}\n", self.synopsis(), self.description);
+        for p in &self.positionals {
+            writeln!(text, "  {} - {}", p.name, p.help).unwrap();
+        }
+        for n in &self.named {
+            match &n.default {
+                Some(d) => writeln!(text, "  --{} - {} (default {})", n.name, n.help, d),
+                None => writeln!(text, "  --{} - {}", n.name, n.help),
+            }
+            .unwrap();
+        }
+        text
+    }
+
+    fn error(&self, message: impl std::fmt::Display) -> Error {
+        Error::InvalidCommand(format!("{}\n{}", message, self.usage()))
+    }
+
+    /// Parse message text; the leading command word is skipped if present
+    pub fn parse(&self, input: &str) -> Result<ParsedArgs> {
+        let mut tokens = tokenize(input)?.into_iter().peekable();
+        if tokens.peek().is_some_and(|t| t.starts_with('/')) {
+            tokens.next();
+        }
+
+        let mut values = HashMap::new();
+        let mut flags = HashMap::new();
+        let mut positionals = Vec::new();
+        while let Some(token) = tokens.next() {
+            let Some(name) = token.strip_prefix("--") else {
+                positionals.push(token);
+                continue;
+            };
+            let (name, inline_value) = match name.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (name, None),
+            };
+            let spec = self
+                .named
+                .iter()
+                .find(|n| n.name == name)
+                .ok_or_else(|| self.error(format!("Unknown option: --{}", name)))?;
+            match (&spec.default, inline_value) {
+                (None, None) => {
+                    flags.insert(spec.name.clone(), true);
+                }
+                (None, Some(_)) => return Err(self.error(format!("--{} does not take a value", name))),
+                (Some(_), Some(value)) => {
+                    values.insert(spec.name.clone(), value);
+                }
+                (Some(_), None) => {
+                    let value = tokens
+                        .next()
+                        .ok_or_else(|| self.error(format!("Missing value for --{}", name)))?;
+                    values.insert(spec.name.clone(), value);
+                }
+            }
+        }
+
+        if positionals.len() > self.positionals.len() {
+            return Err(self.error(format!("Unexpected argument: {}", positionals[self.positionals.len()])));
+        }
+        let mut given = positionals.into_iter();
+        for p in &self.positionals {
+            match (given.next(), &p.kind) {
+                (Some(value), _) => {
+                    values.insert(p.name.clone(), value);
+                }
+                (None, Kind::Required) => return Err(self.error(format!("Missing argument: {}", p.name))),
+                (None, Kind::Optional) => {}
+                (None, Kind::Default(d)) => {
+                    values.insert(p.name.clone(), d.clone());
+                }
+            }
+        }
+        for n in &self.named {
+            if let Some(d) = &n.default {
+                values.entry(n.name.clone()).or_insert_with(|| d.clone());
+            }
+        }
+
+        Ok(ParsedArgs {
+            usage: self.usage(),
+            values,
+            flags,
+        })
+    }
+}
+
+/// Arguments parsed by a [`CommandSpec`]
+#[derive(Clone, Debug)]
+pub struct ParsedArgs {
+    usage: String,
+    values: HashMap<String, String>,
+    flags: HashMap<String, bool>,
+}
+
+impl ParsedArgs {
+    /// Raw value of an argument or option, if given or defaulted
+    pub fn raw(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(String::as_str)
+    }
+
+    /// Argument or option parsed as `T`; errors if it is missing
+    pub fn get<T: FromStr>(&self, name: &str) -> Result<T>
+    where
+        T::Err: std::fmt::Display,
+    {
+        self.get_opt(name)?.ok_or_else(|| {
+            Error::InvalidCommand(format!("Missing argument: {}\n{}", name, self.usage))
+        })
+    }
+
+    /// Argument or option parsed as `T`, or `None` if left out
+    pub fn get_opt<T: FromStr>(&self, name: &str) -> Result<Option<T>>
+    where
+        T::Err: std::fmt::Display,
+    {
+        self.raw(name)
+            .map(|raw| {
+                raw.parse().map_err(|e| {
+                    Error::InvalidCommand(format!("Invalid {} '{}': {}\n{}", name, raw, e, self.usage))
+                })
+            })
+            .transpose()
+    }
+
+    /// Whether a boolean flag was given
+    pub fn flag(&self, name: &str) -> bool {
+        self.flags.get(name).copied().unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec() -> CommandSpec {
+        CommandSpec::new("/trade", "Place an order")
+            .arg("symbol", "Trading pair")
+            .arg("amount", "Order size")
+            .optional("price", "Limit price")
+            .default("tif", "gtc", "Time in force")
+            .flag("dry-run", "Simulate only")
+            .option("slippage", "1", "Max slippage %")
+    }
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            tokenize(r#"/note BTC "buy the \"dip\"" 'a b'"#).unwrap(),
+            vec!["/note", "BTC", "buy the \"dip\"", "a b"]
+        );
+        assert_eq!(tokenize(r#"x """#).unwrap(), vec!["x", ""]);
+        assert!(tokenize(r#"/note "open"#).is_err());
+    }
+
+    #[test]
+    fn test_parse() {
+        let args = spec().parse("/trade BTC-USD 0.5 --slippage=0.3 --dry-run").unwrap();
+        assert_eq!(args.get::<String>("symbol").unwrap(), "BTC-USD");
+        assert_eq!(args.get::<f64>("amount").unwrap(), 0.5);
+        assert_eq!(args.get_opt::<f64>("price").unwrap(), None);
+        assert_eq!(args.get::<String>("tif").unwrap(), "gtc");
+        assert_eq!(args.get::<f64>("slippage").unwrap(), 0.3);
+        assert!(args.flag("dry-run"));
+
+        let args = spec().parse("/trade ETH 1 2500 ioc --slippage 2").unwrap();
+        assert_eq!(args.get_opt::<f64>("price").unwrap(), Some(2500.0));
+        assert_eq!(args.get::<String>("tif").unwrap(), "ioc");
+        assert_eq!(args.get::<f64>("slippage").unwrap(), 2.0);
+        assert!(!args.flag("dry-run"));
+    }
+
+    #[test]
+    fn test_errors_include_usage() {
+        let err = spec().parse("/trade BTC").unwrap_err().to_string();
+        assert!(err.contains("Missing argument: amount"));
+        assert!(err.contains("Usage: /trade <symbol> <amount> [price] [tif=gtc] [--dry-run] [--slippage <value>]"));
+
+        assert!(spec().parse("/trade BTC 1 2 gtc extra").is_err());
+        assert!(spec().parse("/trade BTC 1 --leverage 5").is_err());
+
+        let args = spec().parse("/trade BTC lots").unwrap();
+        assert!(args.get::<f64>("amount").unwrap_err().to_string().contains("Invalid amount 'lots'"));
+    }
+}
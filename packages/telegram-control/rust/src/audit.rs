@@ -0,0 +1,226 @@
+//! Audit log
+//!
+//! Every command and callback the bot receives is recorded with the invoking
+//! user, whether they were authorized, and how the handler finished. Plain
+//! messages are not recorded since they may carry PINs or other replies to
+//! prompts.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::error::Result;
+use crate::types::Context;
+
+/// Kind of update that was audited
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditKind {
+    Command,
+    Callback,
+}
+
+/// How an audited update ended
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", content = "error", rename_all = "snake_case")]
+pub enum AuditOutcome {
+    /// The user was not authorized; no handler ran
+    Denied,
+    Ok,
+    Failed(String),
+}
+
+/// One audited update
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub timestamp: DateTime<Utc>,
+    pub user_id: i64,
+    pub username: Option<String>,
+    pub chat_id: i64,
+    pub kind: AuditKind,
+    /// Command text or callback data
+    pub input: String,
+    pub outcome: AuditOutcome,
+}
+
+impl AuditEntry {
+    /// Entry for an update, or `None` if it is not a command or callback
+    pub fn from_context(ctx: &Context, outcome: AuditOutcome) -> Option<Self> {
+        let (kind, input) = match ctx {
+            Context::Message(msg) => {
+                let text = msg.text.as_deref()?;
+                if !text.starts_with('/') {
+                    return None;
+                }
+                (AuditKind::Command, text.to_string())
+            }
+            Context::Callback(cb) => (AuditKind::Callback, cb.data.clone()),
+        };
+        Some(Self {
+            timestamp: Utc::now(),
+            user_id: ctx.user_id(),
+            username: ctx.username().map(str::to_string),
+            chat_id: ctx.chat_id(),
+            kind,
+            input,
+            outcome,
+        })
+    }
+
+    /// One-line summary for chat output
+    pub fn summary(&self) -> String {
+        let outcome = match &self.outcome {
+            AuditOutcome::Denied => "denied".to_string(),
+            AuditOutcome::Ok => "ok".to_string(),
+            AuditOutcome::Failed(e) => format!("failed: {}", e),
+        };
+        format!(
+            "{} {} ({}) {} → {}",
+            self.timestamp.format("%Y-%m-%d %H:%M:%S"),
+            self.username.as_deref().unwrap_or("-"),
+            self.user_id,
+            self.input,
+            outcome
+        )
+    }
+}
+
+/// Append-only store of audit entries
+#[async_trait]
+pub trait AuditLog: Send + Sync {
+    async fn record(&self, entry: AuditEntry) -> Result<()>;
+
+    /// Most recent entries, oldest first
+    async fn recent(&self, limit: usize) -> Result<Vec<AuditEntry>>;
+}
+
+/// In-memory audit log keeping the last `capacity` entries
+#[derive(Debug)]
+pub struct MemoryAuditLog {
+    entries: Mutex<VecDeque<AuditEntry>>,
+    capacity: usize,
+}
+
+impl MemoryAuditLog {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: Mutex::new(VecDeque::new()),
+            capacity,
+        }
+    }
+}
+
+#[async_trait]
+impl AuditLog for MemoryAuditLog {
+    async fn record(&self, entry: AuditEntry) -> Result<()> {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() == self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+        Ok(())
+    }
+
+    async fn recent(&self, limit: usize) -> Result<Vec<AuditEntry>> {
+        let entries = self.entries.lock().unwrap();
+        Ok(entries.iter().skip(entries.len().saturating_sub(limit)).cloned().collect())
+    }
+}
+
+/// Audit log appended to a JSON-lines file
+#[derive(Debug)]
+pub struct FileAuditLog {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl FileAuditLog {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            lock: Mutex::new(()),
+        }
+    }
+}
+
+#[async_trait]
+impl AuditLog for FileAuditLog {
+    async fn record(&self, entry: AuditEntry) -> Result<()> {
+        let line = serde_json::to_string(&entry)?;
+        let _guard = self.lock.lock().unwrap();
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        writeln!(file, "{}", line)?;
+        Ok(())
+    }
+
+    async fn recent(&self, limit: usize) -> Result<Vec<AuditEntry>> {
+        let _guard = self.lock.lock().unwrap();
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let mut entries = VecDeque::with_capacity(limit);
+        for line in BufReader::new(std::fs::File::open(&self.path)?).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if entries.len() == limit {
+                entries.pop_front();
+            }
+            entries.push_back(serde_json::from_str(&line)?);
+        }
+        Ok(entries.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(input: &str, outcome: AuditOutcome) -> AuditEntry {
+        AuditEntry {
+            timestamp: Utc::now(),
+            user_id: 42,
+            username: Some("alice".to_string()),
+            chat_id: 42,
+            kind: AuditKind::Command,
+            input: input.to_string(),
+            outcome,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_file_audit_log() {
+        let path = std::env::temp_dir().join(format!("audit-test-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let log = FileAuditLog::new(&path);
+
+        log.record(entry("/status", AuditOutcome::Ok)).await.unwrap();
+        log.record(entry("/sellall", AuditOutcome::Denied)).await.unwrap();
+        log.record(entry("/trade x", AuditOutcome::Failed("bad size".to_string())))
+            .await
+            .unwrap();
+
+        let recent = log.recent(2).await.unwrap();
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[0].outcome, AuditOutcome::Denied);
+        assert!(recent[1].summary().contains("failed: bad size"));
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_memory_audit_log_capacity() {
+        let log = MemoryAuditLog::new(2);
+        for input in ["/a", "/b", "/c"] {
+            log.record(entry(input, AuditOutcome::Ok)).await.unwrap();
+        }
+        let inputs: Vec<_> = log.recent(10).await.unwrap().into_iter().map(|e| e.input).collect();
+        assert_eq!(inputs, vec!["/b", "/c"]);
+    }
+}
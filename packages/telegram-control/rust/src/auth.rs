@@ -1,11 +1,81 @@
-use std::collections::HashSet;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
 use crate::error::{Error, Result};
 
-/// Access control for bot users
-#[derive(Clone, Debug, Default)]
-pub struct AccessControl {
+/// User roles, from least to most privileged
+///
+/// Each role includes the permissions of the roles below it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// Read-only access: status, positions, reports
+    Viewer,
+    /// May place and cancel trades
+    Trader,
+    /// May manage the bot and its users
+    Admin,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Viewer => "viewer",
+            Role::Trader => "trader",
+            Role::Admin => "admin",
+        }
+    }
+    
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "viewer" => Some(Role::Viewer),
+            "trader" => Some(Role::Trader),
+            "admin" => Some(Role::Admin),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Users and roles, shared by every clone of an [`AccessControl`]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct AccessState {
     whitelist: Option<HashSet<i64>>,
     admins: HashSet<i64>,
+    roles: HashMap<i64, Role>,
+    /// Group chats the bot works in; `None` allows every group
+    #[serde(default)]
+    chats: Option<HashSet<i64>>,
+}
+
+/// Access control for bot users
+///
+/// Clones share the same user list, so changes made at runtime (e.g. by the
+/// `/adduser` command) are seen by the dispatcher immediately. With
+/// [`with_persistence`](Self::with_persistence) every change is also saved to
+/// a JSON file and restored on the next start.
+#[derive(Clone, Debug)]
+pub struct AccessControl {
+    state: Arc<RwLock<AccessState>>,
+    default_role: Role,
+    path: Option<PathBuf>,
+}
+
+impl Default for AccessControl {
+    fn default() -> Self {
+        Self {
+            state: Arc::default(),
+            default_role: Role::Trader,
+            path: None,
+        }
+    }
 }
 
 impl AccessControl {
@@ -14,20 +84,80 @@ impl AccessControl {
     }
     
     /// Restrict access to specific user IDs
-    pub fn with_whitelist(mut self, ids: Vec<i64>) -> Self {
-        self.whitelist = Some(ids.into_iter().collect());
+    pub fn with_whitelist(self, ids: Vec<i64>) -> Self {
+        self.state.write().unwrap().whitelist = Some(ids.into_iter().collect());
         self
     }
     
     /// Set admin users
-    pub fn with_admins(mut self, ids: Vec<i64>) -> Self {
-        self.admins = ids.into_iter().collect();
+    pub fn with_admins(self, ids: Vec<i64>) -> Self {
+        self.state.write().unwrap().admins = ids.into_iter().collect();
         self
     }
     
+    /// Assign a role to users
+    pub fn with_role(self, ids: Vec<i64>, role: Role) -> Self {
+        {
+            let mut state = self.state.write().unwrap();
+            for id in ids {
+                state.roles.insert(id, role);
+            }
+        }
+        self
+    }
+    
+    /// Restrict group use to specific chat IDs
+    ///
+    /// Private chats are always allowed. Users must still be authorized
+    /// individually inside an allowed group.
+    pub fn with_chats(self, ids: Vec<i64>) -> Self {
+        self.state.write().unwrap().chats = Some(ids.into_iter().collect());
+        self
+    }
+    
+    /// Role for authorized users without an explicit one (default: trader)
+    pub fn with_default_role(mut self, role: Role) -> Self {
+        self.default_role = role;
+        self
+    }
+    
+    /// Persist users and roles to a JSON file
+    ///
+    /// If the file exists its contents replace the configured users;
+    /// otherwise it is created from them.
+    pub fn with_persistence(mut self, path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        if path.exists() {
+            let loaded: AccessState = serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+            *self.state.write().unwrap() = loaded;
+        }
+        self.path = Some(path);
+        self.save()?;
+        Ok(self)
+    }
+    
+    /// Role given to authorized users without an explicit one
+    pub fn default_role(&self) -> Role {
+        self.default_role
+    }
+    
+    /// Admin user IDs, sorted
+    pub fn admins(&self) -> Vec<i64> {
+        let state = self.state.read().unwrap();
+        let mut admins: Vec<i64> = state
+            .admins
+            .iter()
+            .copied()
+            .chain(state.roles.iter().filter(|(_, r)| **r == Role::Admin).map(|(id, _)| *id))
+            .collect();
+        admins.sort();
+        admins.dedup();
+        admins
+    }
+    
     /// Check if user is authorized
     pub fn is_authorized(&self, user_id: i64) -> bool {
-        match &self.whitelist {
+        match &self.state.read().unwrap().whitelist {
             Some(whitelist) => whitelist.contains(&user_id),
             None => true, // No whitelist = open access
         }
@@ -35,7 +165,25 @@ impl AccessControl {
     
     /// Check if user is an admin
     pub fn is_admin(&self, user_id: i64) -> bool {
-        self.admins.contains(&user_id)
+        let state = self.state.read().unwrap();
+        state.admins.contains(&user_id) || state.roles.get(&user_id) == Some(&Role::Admin)
+    }
+    
+    /// Effective role of a user, or `None` if not authorized
+    pub fn role_of(&self, user_id: i64) -> Option<Role> {
+        if self.is_admin(user_id) {
+            return Some(Role::Admin);
+        }
+        if !self.is_authorized(user_id) {
+            return None;
+        }
+        let explicit = self.state.read().unwrap().roles.get(&user_id).copied();
+        Some(explicit.unwrap_or(self.default_role))
+    }
+    
+    /// Check if user has at least `role`
+    pub fn has_role(&self, user_id: i64, role: Role) -> bool {
+        self.role_of(user_id).is_some_and(|r| r >= role)
     }
     
     /// Authorize a user, returning error if not authorized
@@ -46,6 +194,119 @@ impl AccessControl {
             Err(Error::Unauthorized(user_id))
         }
     }
+    
+    /// Check if the bot may be used in a chat
+    ///
+    /// Private chats (positive IDs) always may; groups only if allowed.
+    pub fn is_chat_authorized(&self, chat_id: i64) -> bool {
+        if chat_id > 0 {
+            return true;
+        }
+        match &self.state.read().unwrap().chats {
+            Some(chats) => chats.contains(&chat_id),
+            None => true,
+        }
+    }
+    
+    /// Authorize a chat, returning error if not allowed
+    pub fn authorize_chat(&self, chat_id: i64) -> Result<()> {
+        if self.is_chat_authorized(chat_id) {
+            Ok(())
+        } else {
+            Err(Error::ChatUnauthorized(chat_id))
+        }
+    }
+    
+    /// Require a user to have at least `role`
+    pub fn require_role(&self, user_id: i64, role: Role) -> Result<()> {
+        if self.has_role(user_id, role) {
+            Ok(())
+        } else {
+            Err(Error::Forbidden(user_id, role))
+        }
+    }
+    
+    /// Grant a user access, optionally with an explicit role
+    ///
+    /// Starts a whitelist if there was none, closing the bot to everyone not
+    /// added.
+    pub fn add_user(&self, user_id: i64, role: Option<Role>) -> Result<()> {
+        {
+            let mut state = self.state.write().unwrap();
+            state.whitelist.get_or_insert_with(HashSet::new).insert(user_id);
+            if let Some(role) = role {
+                state.roles.insert(user_id, role);
+            }
+        }
+        self.save()
+    }
+    
+    /// Revoke a user's access and role
+    pub fn remove_user(&self, user_id: i64) -> Result<()> {
+        {
+            let mut state = self.state.write().unwrap();
+            if let Some(whitelist) = state.whitelist.as_mut() {
+                whitelist.remove(&user_id);
+            }
+            state.admins.remove(&user_id);
+            state.roles.remove(&user_id);
+        }
+        self.save()
+    }
+    
+    /// Allow the bot in a group chat
+    ///
+    /// Starts a chat list if there was none, closing every other group.
+    pub fn add_chat(&self, chat_id: i64) -> Result<()> {
+        self.state.write().unwrap().chats.get_or_insert_with(HashSet::new).insert(chat_id);
+        self.save()
+    }
+    
+    /// Stop the bot working in a group chat
+    pub fn remove_chat(&self, chat_id: i64) -> Result<()> {
+        if let Some(chats) = self.state.write().unwrap().chats.as_mut() {
+            chats.remove(&chat_id);
+        }
+        self.save()
+    }
+    
+    /// Allowed group chats, sorted; empty when every group is allowed
+    pub fn chats(&self) -> Vec<i64> {
+        let mut chats: Vec<i64> = match &self.state.read().unwrap().chats {
+            Some(chats) => chats.iter().copied().collect(),
+            None => Vec::new(),
+        };
+        chats.sort();
+        chats
+    }
+    
+    /// Whitelisted users and their effective roles, sorted by ID
+    ///
+    /// Empty when there is no whitelist.
+    pub fn users(&self) -> Vec<(i64, Role)> {
+        let ids: Vec<i64> = match &self.state.read().unwrap().whitelist {
+            Some(whitelist) => whitelist.iter().copied().collect(),
+            None => Vec::new(),
+        };
+        let mut users: Vec<_> = ids
+            .into_iter()
+            .filter_map(|id| self.role_of(id).map(|role| (id, role)))
+            .collect();
+        users.sort();
+        users
+    }
+    
+    fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let json = serde_json::to_string_pretty(&*self.state.read().unwrap())?;
+        // Write-then-rename so a crash never leaves a truncated file
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, json)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -72,5 +333,70 @@ mod tests {
         let auth = AccessControl::new().with_admins(vec![123]);
         assert!(auth.is_admin(123));
         assert!(!auth.is_admin(456));
+        
+        let auth = auth.with_role(vec![7], Role::Admin);
+        assert_eq!(auth.admins(), vec![7, 123]);
+    }
+    
+    #[test]
+    fn test_roles() {
+        let auth = AccessControl::new()
+            .with_whitelist(vec![1, 2, 3])
+            .with_admins(vec![1])
+            .with_role(vec![2], Role::Viewer);
+        
+        assert_eq!(auth.role_of(1), Some(Role::Admin));
+        assert_eq!(auth.role_of(2), Some(Role::Viewer));
+        assert_eq!(auth.role_of(3), Some(Role::Trader));
+        assert_eq!(auth.role_of(4), None);
+        
+        assert!(auth.has_role(1, Role::Trader));
+        assert!(auth.require_role(2, Role::Viewer).is_ok());
+        assert!(auth.require_role(2, Role::Trader).is_err());
+        assert!(auth.require_role(4, Role::Viewer).is_err());
+    }
+    
+    #[test]
+    fn test_chats() {
+        let auth = AccessControl::new();
+        assert!(auth.is_chat_authorized(-100123));
+        
+        let auth = auth.with_chats(vec![-100123]);
+        assert!(auth.is_chat_authorized(-100123));
+        assert!(!auth.is_chat_authorized(-100456));
+        assert!(auth.is_chat_authorized(42));
+        assert!(auth.authorize_chat(-100456).is_err());
+        
+        auth.add_chat(-100456).unwrap();
+        auth.remove_chat(-100123).unwrap();
+        assert_eq!(auth.chats(), vec![-100456]);
+    }
+    
+    #[test]
+    fn test_runtime_users_persist() {
+        let path = std::env::temp_dir().join(format!("access-test-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        
+        let auth = AccessControl::new()
+            .with_admins(vec![1])
+            .with_persistence(&path)
+            .unwrap();
+        let shared = auth.clone();
+        assert!(auth.is_authorized(2));
+        
+        shared.add_user(2, Some(Role::Viewer)).unwrap();
+        shared.add_user(3, None).unwrap();
+        assert!(auth.is_authorized(2));
+        assert!(!auth.is_authorized(4));
+        
+        auth.remove_user(3).unwrap();
+        assert_eq!(auth.users(), vec![(2, Role::Viewer)]);
+        
+        let reloaded = AccessControl::new().with_persistence(&path).unwrap();
+        assert_eq!(reloaded.role_of(2), Some(Role::Viewer));
+        assert!(reloaded.is_admin(1));
+        assert!(!reloaded.is_authorized(3));
+        
+        std::fs::remove_file(path).unwrap();
     }
 }
@@ -2,15 +2,38 @@ use std::collections::HashMap;
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use teloxide::dispatching::{Dispatcher, UpdateFilterExt, HandlerExt};
+use teloxide::dispatching::{DefaultKey, Dispatcher, UpdateFilterExt};
 use teloxide::prelude::*;
-use teloxide::types::Update;
-use teloxide::utils::command::BotCommands;
+use teloxide::types::{BotCommand, BotCommandScope, InlineQueryResult, Recipient, Update};
 use tracing::{info, warn, error};
 
-use crate::auth::AccessControl;
+use crate::approval::{self, Approval, Approvals};
+use crate::audit::{AuditEntry, AuditLog, AuditOutcome};
+use crate::args::{CommandSpec, ParsedArgs};
+use crate::auth::{AccessControl, Role};
+use crate::broadcast::{BroadcastReport, Broadcaster};
+use crate::callback::CallbackData;
+use crate::confirm::{self, Confirmation, Confirmations};
+use crate::dialogue::{Dialogue, DialogueStorage};
+use crate::dispatcher::{format_window, Alert, AlertDispatcher, DispatcherConfig};
+use crate::documents::{DocumentLimits, Upload};
 use crate::error::{Error, Result};
+use crate::history::{self, AlertHistory, AlertQuery, AlertRecord};
+use crate::i18n::I18n;
+use crate::metrics;
+use crate::inline::{self, InlineContext, InlineHandlerFn};
+use crate::middleware::{Middleware, MiddlewareStack};
+use crate::pagination::{self, Pagination, Paginator};
+use crate::quiet::QuietHours;
+use crate::ratelimit::Cooldowns;
+use crate::report::{self, ErrorReport, ErrorReporter};
+use crate::scheduler::{Schedule, Scheduler};
+use crate::session::SessionStore;
+use crate::shutdown::{BotHandle, Cleanup, ShutdownHook};
+use crate::state::State;
+use crate::timeout::{self, HandlerTimeout};
 use crate::types::{CallbackContext, Context, MessageContext};
 
 /// Handler function type
@@ -18,32 +41,766 @@ pub type HandlerFn = Arc<
     dyn Fn(Context) -> Pin<Box<dyn Future<Output = Result<()>> + Send>> + Send + Sync,
 >;
 
+/// Dialogue hook type; resolves to `true` if the chat had an active dialogue
+pub type DialogueFn = Arc<
+    dyn Fn(Context) -> Pin<Box<dyn Future<Output = Result<bool>> + Send>> + Send + Sync,
+>;
+
 /// Telegram Bot wrapper with trading-focused features
 pub struct Bot {
     bot: teloxide::Bot,
     access_control: AccessControl,
+    middleware: MiddlewareStack,
+    router: Router,
+    confirmations: Option<Confirmations>,
+    approvals: Option<Approvals>,
+    paginator: Option<Paginator>,
+    audit_log: Option<Arc<dyn AuditLog>>,
+    scheduler: Option<Scheduler>,
+    shutdown_hooks: Vec<ShutdownHook>,
+    i18n: Arc<I18n>,
+    error_reporter: Option<Arc<ErrorReporter>>,
+    state: State,
+    /// The bot's own username, fetched on launch
+    username: Option<String>,
+}
+
+/// A registered command, as listed in `/help` and the Telegram menu
+#[derive(Clone, Debug)]
+struct CommandInfo {
+    command: String,
+    description: Option<String>,
+    usage: Option<String>,
+    role: Option<Role>,
+    /// Refused in group chats
+    private: bool,
+    timeout: Option<HandlerTimeout>,
+    /// Minimum time between runs by one user
+    cooldown: Option<Duration>,
+}
+
+impl CommandInfo {
+    fn visible_to(&self, role: Role) -> bool {
+        self.role.map_or(true, |required| role >= required)
+    }
+    
+    fn description(&self) -> &str {
+        self.description.as_deref().unwrap_or_default()
+    }
+}
+
+/// Registered handlers
+#[derive(Default)]
+struct Router {
+    commands: Vec<CommandInfo>,
     command_handlers: HashMap<String, HandlerFn>,
     callback_handlers: Vec<(String, HandlerFn)>, // pattern, handler
+    dialogue_handlers: Vec<DialogueFn>,
+    document_handler: Option<HandlerFn>,
     default_handler: Option<HandlerFn>,
+    inline_handler: Option<InlineHandlerFn>,
+    default_timeout: Option<HandlerTimeout>,
+    cooldowns: Cooldowns,
+}
+
+impl Router {
+    fn command_info(&mut self, command: &str) -> &mut CommandInfo {
+        let index = match self.commands.iter().position(|c| c.command == command) {
+            Some(index) => index,
+            None => {
+                self.commands.push(CommandInfo {
+                    command: command.to_string(),
+                    description: None,
+                    usage: None,
+                    role: None,
+                    private: false,
+                    timeout: None,
+                    cooldown: None,
+                });
+                self.commands.len() - 1
+            }
+        };
+        &mut self.commands[index]
+    }
+    
+    /// Timeout for the handler `ctx` will be routed to
+    fn timeout_for(&self, ctx: &Context) -> Option<HandlerTimeout> {
+        let command = match ctx {
+            Context::Message(_) => ctx.text().and_then(|text| text.split_whitespace().next()),
+            Context::Callback(_) => None,
+        };
+        command
+            .and_then(|command| self.commands.iter().find(|c| c.command == command))
+            .and_then(|c| c.timeout)
+            .or(self.default_timeout)
+    }
+    
+    /// Metrics label for what handles `ctx`: its command, `callback:<pattern>`,
+    /// `document` or `message`
+    fn endpoint(&self, ctx: &Context) -> String {
+        match ctx {
+            Context::Message(_) => match ctx.text().and_then(|text| text.split_whitespace().next()) {
+                Some(command) if self.command_handlers.contains_key(command) => command.to_string(),
+                _ if ctx.document().is_some() => "document".to_string(),
+                _ => "message".to_string(),
+            },
+            Context::Callback(cb) => match self.callback_handlers.iter().find(|(p, _)| cb.data.starts_with(p.as_str())) {
+                Some((pattern, _)) => format!("callback:{}", pattern.trim_end_matches(':')),
+                None => "callback".to_string(),
+            },
+        }
+    }
+    
+    /// Route a message to a command handler, an active dialogue, the
+    /// document handler, or the default handler, in that order
+    ///
+    /// Commands with a required role are refused with [`Error::Forbidden`]
+    /// before anything else is checked.
+    async fn route_message(&self, ctx: Context, access_control: &AccessControl, i18n: &I18n) -> Result<()> {
+        let command = match &ctx {
+            Context::Message(msg) => msg
+                .text
+                .as_deref()
+                .and_then(|text| text.split_whitespace().next())
+                .map(str::to_string),
+            Context::Callback(_) => None,
+        };
+        
+        if let Some((command, handler)) = command.and_then(|cmd| self.command_handlers.get_key_value(&cmd)) {
+            let info = self.commands.iter().find(|c| &c.command == command);
+            if let Some(role) = info.and_then(|c| c.role) {
+                access_control.require_role(ctx.user_id(), role)?;
+            }
+            if info.is_some_and(|c| c.private) && !ctx.is_private() {
+                let locale = i18n.locale(&ctx).await;
+                let text = i18n.t(&locale, "private_only", &[("command", command)]);
+                return ctx.reply(text).await;
+            }
+            if let Some(cooldown) = info.and_then(|c| c.cooldown) {
+                if let Err(wait) = self.cooldowns.check(command, ctx.user_id(), cooldown) {
+                    let locale = i18n.locale(&ctx).await;
+                    let wait = format_window(Duration::from_secs(wait.as_secs_f64().ceil() as u64));
+                    let text = i18n.t(&locale, "cooldown", &[("command", command), ("wait", &wait)]);
+                    return ctx.reply(text).await;
+                }
+            }
+            return handler(ctx).await;
+        }
+        
+        for dialogue in &self.dialogue_handlers {
+            if dialogue(ctx.clone()).await? {
+                return Ok(());
+            }
+        }
+        
+        if let Some(handler) = self.document_handler.as_ref().filter(|_| ctx.document().is_some()) {
+            return handler(ctx).await;
+        }
+        
+        match &self.default_handler {
+            Some(handler) => handler(ctx).await,
+            None => Ok(()),
+        }
+    }
+    
+    /// Route a callback query to the first handler whose pattern prefixes its data
+    async fn route_callback(&self, ctx: Context) -> Result<()> {
+        let data = match &ctx {
+            Context::Callback(cb) => cb.data.clone(),
+            Context::Message(_) => return Ok(()),
+        };
+        
+        match self.callback_handlers.iter().find(|(pattern, _)| data.starts_with(pattern.as_str())) {
+            Some((_, handler)) => handler(ctx).await,
+            None => Ok(()),
+        }
+    }
 }
 
 impl Bot {
     /// Create a new bot instance
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(token: impl Into<String>) -> BotBuilder {
         BotBuilder::new(token)
     }
     
+    /// Underlying teloxide bot, for sending messages outside handlers
+    pub fn inner(&self) -> &teloxide::Bot {
+        &self.bot
+    }
+    
+    /// Access control checked for every update
+    pub fn access_control(&self) -> &AccessControl {
+        &self.access_control
+    }
+    
+    /// Start an alert dispatcher that sends through this bot
+    pub fn alert_dispatcher(&self, config: DispatcherConfig) -> AlertDispatcher {
+        AlertDispatcher::spawn(Arc::new(self.bot.clone()), config)
+    }
+    
+    /// Translations for built-in replies, for use in handlers too
+    pub fn i18n(&self) -> Arc<I18n> {
+        self.i18n.clone()
+    }
+    
+    /// Broadcaster that sends through this bot
+    pub fn broadcaster(&self) -> Broadcaster {
+        Broadcaster::new(Arc::new(self.bot.clone()))
+    }
+    
+    /// Send `message` to each of `chats`, paced under Telegram's limits
+    ///
+    /// Use [`Bot::broadcaster`] to broadcast after the bot has started.
+    pub async fn broadcast(&self, chats: &[i64], message: impl Into<Alert>) -> BroadcastReport {
+        self.broadcaster().broadcast(chats, message).await
+    }
+    
+    /// Make `value` available to every handler via [`Context::state`]
+    ///
+    /// Values are looked up by type; adding a second value of the same type
+    /// replaces the first.
+    pub fn with_state<T: Send + Sync + 'static>(mut self, value: T) -> Self {
+        self.state.insert(value);
+        self
+    }
+    
     /// Register a command handler
     pub fn on_command<F, Fut>(mut self, command: impl Into<String>, handler: F) -> Self
     where
         F: Fn(Context) -> Fut + Send + Sync + 'static,
         Fut: Future<Output = Result<()>> + Send + 'static,
     {
+        let command = command.into();
+        let handler: HandlerFn = Arc::new(move |ctx| Box::pin(handler(ctx)));
+        self.router.command_info(&command);
+        self.router.command_handlers.insert(command, handler);
+        self
+    }
+    
+    /// Describe a command for `/help` and the Telegram command menu
+    pub fn describe(mut self, command: &str, description: impl Into<String>) -> Self {
+        self.router.command_info(command).description = Some(description.into());
+        self
+    }
+    
+    /// Register a command whose arguments are parsed with `spec`
+    ///
+    /// The spec's description and usage are shown in `/help`; parse errors
+    /// are replied with the usage text.
+    pub fn on_command_spec<F, Fut>(mut self, spec: CommandSpec, handler: F) -> Self
+    where
+        F: Fn(Context, ParsedArgs) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let info = self.router.command_info(spec.command());
+        info.description = Some(spec.description().to_string());
+        info.usage = Some(spec.synopsis());
+        let command = spec.command().to_string();
+        let handler = Arc::new(handler);
+        self.on_command(command, move |ctx: Context| {
+            let args = ctx.parse_args(&spec);
+            let handler = handler.clone();
+            async move { handler(ctx, args?).await }
+        })
+    }
+    
+    /// Refuse `command` in group chats, e.g. for commands that show balances
+    pub fn private_only(mut self, command: &str) -> Self {
+        self.router.command_info(command).private = true;
+        self
+    }
+    
+    /// Cancel handlers that run longer than `timeout`, unless their command
+    /// has its own
+    pub fn with_handler_timeout(mut self, timeout: HandlerTimeout) -> Self {
+        self.router.default_timeout = Some(timeout);
+        self
+    }
+    
+    /// Give `command` its own timeout, e.g. a longer one for slow reports
+    pub fn command_timeout(mut self, command: &str, timeout: HandlerTimeout) -> Self {
+        self.router.command_info(command).timeout = Some(timeout);
+        self
+    }
+    
+    /// Let each user run `command` at most once per `cooldown`
+    ///
+    /// Early attempts are refused with the time remaining.
+    pub fn command_cooldown(mut self, command: &str, cooldown: Duration) -> Self {
+        self.router.command_info(command).cooldown = Some(cooldown);
+        self
+    }
+    
+    /// Require at least `role` to run `command`
+    ///
+    /// Checked before the handler runs, however it was registered; users
+    /// below the role get a [`Error::Forbidden`] reply.
+    pub fn command_role(mut self, command: &str, role: Role) -> Self {
+        self.router.command_info(command).role = Some(role);
+        self
+    }
+    
+    /// Register a command handler that requires at least `role`
+    ///
+    /// Users below the role get a [`Error::Forbidden`] reply and the handler
+    /// is not run.
+    pub fn on_command_with_role<F, Fut>(self, command: impl Into<String>, role: Role, handler: F) -> Self
+    where
+        F: Fn(Context) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let command = command.into();
+        self.on_command(command.clone(), handler).command_role(&command, role)
+    }
+    
+    /// Register a command handler only admins can run
+    pub fn on_admin_command<F, Fut>(self, command: impl Into<String>, handler: F) -> Self
+    where
+        F: Fn(Context) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.on_command_with_role(command, Role::Admin, handler)
+    }
+    
+    /// Register a command that only runs after the user confirms it
+    ///
+    /// The bot replies with a Yes/No keyboard, asks for the PIN if
+    /// `confirmation` has one, and then runs `handler` with the original
+    /// command context.
+    pub fn on_dangerous_command<F, Fut>(
+        mut self,
+        command: impl Into<String>,
+        confirmation: Confirmation,
+        handler: F,
+    ) -> Self
+    where
+        F: Fn(Context) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let confirmations = self.confirmations();
         let handler: HandlerFn = Arc::new(move |ctx| Box::pin(handler(ctx)));
-        self.command_handlers.insert(command.into(), handler);
+        self.on_command(command, move |ctx: Context| {
+            let confirmations = confirmations.clone();
+            let handler = handler.clone();
+            let confirmation = confirmation.clone();
+            async move { confirmations.request(ctx, handler, &confirmation).await }
+        })
+    }
+    
+    /// Shared confirmation state, wiring up its button and PIN handlers on
+    /// first use
+    fn confirmations(&mut self) -> Confirmations {
+        if let Some(confirmations) = &self.confirmations {
+            return confirmations.clone();
+        }
+        let confirmations = Confirmations::new();
+        
+        let on_button = confirmations.clone();
+        let handler: HandlerFn = Arc::new(move |ctx| {
+            let confirmations = on_button.clone();
+            Box::pin(async move { confirmations.handle_callback(ctx).await })
+        });
+        self.router.callback_handlers.push((confirm::CALLBACK_PREFIX.to_string(), handler));
+        
+        let on_pin = confirmations.clone();
+        let hook: DialogueFn = Arc::new(move |ctx| {
+            let confirmations = on_pin.clone();
+            Box::pin(async move { confirmations.handle_pin(ctx).await })
+        });
+        self.router.dialogue_handlers.push(hook);
+        
+        self.confirmations = Some(confirmations.clone());
+        confirmations
+    }
+    
+    /// Register a command that non-admins need an admin's approval for
+    ///
+    /// Admins run it directly. Other users' requests are posted to the
+    /// approval's admin chat with Approve/Reject buttons; `handler` runs with
+    /// the requester's context once approved.
+    pub fn on_approved_command<F, Fut>(mut self, command: impl Into<String>, approval: Approval, handler: F) -> Self
+    where
+        F: Fn(Context) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let approvals = self.approvals();
+        let handler: HandlerFn = Arc::new(move |ctx| Box::pin(handler(ctx)));
+        self.on_command(command, move |ctx: Context| {
+            let approvals = approvals.clone();
+            let handler = handler.clone();
+            let approval = approval.clone();
+            async move { approvals.request(ctx, handler, &approval).await }
+        })
+    }
+    
+    /// Shared approval state, wiring up its button handler on first use
+    fn approvals(&mut self) -> Approvals {
+        if let Some(approvals) = &self.approvals {
+            return approvals.clone();
+        }
+        let approvals = Approvals::new(self.access_control.clone());
+        let on_button = approvals.clone();
+        let handler: HandlerFn = Arc::new(move |ctx| {
+            let approvals = on_button.clone();
+            Box::pin(async move { approvals.handle_callback(ctx).await })
+        });
+        self.router.callback_handlers.push((approval::CALLBACK_PREFIX.to_string(), handler));
+        self.approvals = Some(approvals.clone());
+        approvals
+    }
+    
+    /// Register a command that replies with a list users can page through
+    ///
+    /// `source` returns the items, one line each; they are kept for the
+    /// pagination's TTL while the Prev/Next buttons edit the message.
+    pub fn on_paginated_command<F, Fut>(mut self, command: impl Into<String>, pagination: Pagination, source: F) -> Self
+    where
+        F: Fn(Context) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Vec<String>>> + Send + 'static,
+    {
+        let paginator = self.paginator();
+        let source = Arc::new(source);
+        self.on_command(command, move |ctx: Context| {
+            let paginator = paginator.clone();
+            let pagination = pagination.clone();
+            let source = source.clone();
+            async move {
+                let items = source(ctx.clone()).await?;
+                paginator.send(&ctx, items, &pagination).await
+            }
+        })
+    }
+    
+    /// Shared pagination state, wiring up its button handler on first use
+    fn paginator(&mut self) -> Paginator {
+        if let Some(paginator) = &self.paginator {
+            return paginator.clone();
+        }
+        let paginator = Paginator::new();
+        let on_button = paginator.clone();
+        let handler: HandlerFn = Arc::new(move |ctx| {
+            let paginator = on_button.clone();
+            Box::pin(async move { paginator.handle_callback(ctx).await })
+        });
+        self.router.callback_handlers.push((pagination::CALLBACK_PREFIX.to_string(), handler));
+        self.paginator = Some(paginator.clone());
+        paginator
+    }
+    
+    /// Register admin commands for managing users at runtime
+    ///
+    /// - `/adduser <id> [viewer|trader|admin]`
+    /// - `/removeuser <id>`
+    /// - `/listusers`
+    /// - `/addchat [chat_id]` (default: the current chat)
+    /// - `/removechat [chat_id]`
+    ///
+    /// Changes are saved if the access control was configured with
+    /// [`AccessControl::with_persistence`].
+    pub fn with_user_management(self) -> Self {
+        let add = self.access_control.clone();
+        let remove = self.access_control.clone();
+        let list = self.access_control.clone();
+        let add_chat = self.access_control.clone();
+        let remove_chat = self.access_control.clone();
+        
+        self.on_command_with_role("/adduser", Role::Admin, move |ctx: Context| {
+            let access_control = add.clone();
+            async move {
+                let (user_id, role) = match ctx.args().as_slice() {
+                    [id] => (parse_user_id(id)?, None),
+                    [id, role] => (
+                        parse_user_id(id)?,
+                        Some(Role::parse(role).ok_or_else(|| {
+                            Error::InvalidCommand(format!("Unknown role: {}", role))
+                        })?),
+                    ),
+                    _ => return Err(Error::InvalidCommand("Usage: /adduser <id> [role]".to_string())),
+                };
+                access_control.add_user(user_id, role)?;
+                info!("User {} granted access by {}", user_id, ctx.user_id());
+                ctx.reply(format!("✅ Added user {}", user_id)).await
+            }
+        })
+        .on_command_with_role("/removeuser", Role::Admin, move |ctx: Context| {
+            let access_control = remove.clone();
+            async move {
+                let user_id = match ctx.args().as_slice() {
+                    [id] => parse_user_id(id)?,
+                    _ => return Err(Error::InvalidCommand("Usage: /removeuser <id>".to_string())),
+                };
+                access_control.remove_user(user_id)?;
+                info!("User {} removed by {}", user_id, ctx.user_id());
+                ctx.reply(format!("✅ Removed user {}", user_id)).await
+            }
+        })
+        .on_command_with_role("/listusers", Role::Admin, move |ctx: Context| {
+            let access_control = list.clone();
+            async move {
+                let users = access_control.users();
+                let mut text = match users.is_empty() {
+                    true => String::from("No whitelist configured; access is open.\n"),
+                    false => String::from("Authorized users:\n"),
+                };
+                for (id, role) in users {
+                    text.push_str(&format!("  {} - {}\n", id, role));
+                }
+                let chats = access_control.chats();
+                if !chats.is_empty() {
+                    text.push_str("Allowed group chats:\n");
+                    for id in chats {
+                        text.push_str(&format!("  {}\n", id));
+                    }
+                }
+                ctx.reply(text).await
+            }
+        })
+        .on_command_with_role("/addchat", Role::Admin, move |ctx: Context| {
+            let access_control = add_chat.clone();
+            async move {
+                let chat_id = match ctx.args().as_slice() {
+                    [] => ctx.chat_id(),
+                    [id] => parse_chat_id(id)?,
+                    _ => return Err(Error::InvalidCommand("Usage: /addchat [chat_id]".to_string())),
+                };
+                access_control.add_chat(chat_id)?;
+                info!("Chat {} allowed by {}", chat_id, ctx.user_id());
+                ctx.reply(format!("✅ Allowed chat {}", chat_id)).await
+            }
+        })
+        .on_command_with_role("/removechat", Role::Admin, move |ctx: Context| {
+            let access_control = remove_chat.clone();
+            async move {
+                let chat_id = match ctx.args().as_slice() {
+                    [] => ctx.chat_id(),
+                    [id] => parse_chat_id(id)?,
+                    _ => return Err(Error::InvalidCommand("Usage: /removechat [chat_id]".to_string())),
+                };
+                access_control.remove_chat(chat_id)?;
+                info!("Chat {} removed by {}", chat_id, ctx.user_id());
+                ctx.reply(format!("✅ Removed chat {}", chat_id)).await
+            }
+        })
+        .describe("/adduser", "Grant a user access: <id> [role]")
+        .describe("/removeuser", "Revoke a user's access: <id>")
+        .describe("/listusers", "List authorized users and chats")
+        .describe("/addchat", "Allow the bot in a group: [chat_id]")
+        .describe("/removechat", "Remove a group: [chat_id]")
+    }
+    
+    /// Record every command and callback to `log`, and register an admin
+    /// `/audit [count]` command that lists the most recent entries
+    pub fn with_audit_log(mut self, log: Arc<dyn AuditLog>) -> Self {
+        self.audit_log = Some(log.clone());
+        self.on_command_with_role("/audit", Role::Admin, move |ctx: Context| {
+            let log = log.clone();
+            async move {
+                let limit = match ctx.args().first() {
+                    Some(n) => n
+                        .parse()
+                        .map_err(|_| Error::InvalidCommand(format!("Invalid count: {}", n)))?,
+                    None => 20,
+                };
+                let entries = log.recent(limit).await?;
+                if entries.is_empty() {
+                    return ctx.reply("Audit log is empty.").await;
+                }
+                let lines: Vec<String> = entries.iter().map(AuditEntry::summary).collect();
+                ctx.reply(lines.join("\n")).await
+            }
+        })
+        .describe("/audit", "Show recent commands: [count]")
+    }
+    
+    /// Register `/alerts [level] [since]` listing alerts recorded in
+    /// `history`, e.g. `/alerts critical 12h`, and handle the Ack buttons on
+    /// critical alerts
+    ///
+    /// Give the same history to
+    /// [`AlertDispatcher::with_history`](crate::AlertDispatcher::with_history).
+    pub fn with_alert_history(mut self, history: Arc<dyn AlertHistory>) -> Self {
+        let on_button = history.clone();
+        let handler: HandlerFn = Arc::new(move |ctx| {
+            let history = on_button.clone();
+            Box::pin(async move { history::handle_callback(history.as_ref(), ctx).await })
+        });
+        self.router.callback_handlers.push((history::CALLBACK_PREFIX.to_string(), handler));
+        self.on_command("/alerts", move |ctx: Context| {
+            let history = history.clone();
+            async move {
+                let query = AlertQuery::parse(&ctx.args())?;
+                let records = history.query(&query).await?;
+                if records.is_empty() {
+                    return ctx.reply("No alerts since then.").await;
+                }
+                let lines: Vec<String> = records.iter().map(AlertRecord::summary).collect();
+                ctx.reply(lines.join("\n")).await
+            }
+        })
+        .describe("/alerts", "Show recent alerts: [level] [since]")
+    }
+    
+    /// Register `/quiet` so traders can set quiet hours for their chat on
+    /// `dispatcher`: `/quiet 23:00-07:00 [utc offset hours]`, `/quiet off`,
+    /// or `/quiet` to show the current setting
+    pub fn with_quiet_hours(self, dispatcher: Arc<AlertDispatcher>) -> Self {
+        self.on_command_with_role("/quiet", Role::Trader, move |ctx: Context| {
+            let dispatcher = dispatcher.clone();
+            async move {
+                let chat_id = ctx.chat_id();
+                let hours = match ctx.args().as_slice() {
+                    [] => {
+                        return match dispatcher.quiet_hours(chat_id) {
+                            Some(hours) => ctx.reply(format!("🌙 Quiet hours: {}", hours)).await,
+                            None => ctx.reply("No quiet hours set.").await,
+                        };
+                    }
+                    ["off"] => None,
+                    [window] => Some(QuietHours::parse(window)?),
+                    [window, offset] => {
+                        let offset: f64 = offset
+                            .parse()
+                            .map_err(|_| Error::InvalidCommand(format!("Invalid UTC offset: {}", offset)))?;
+                        Some(QuietHours::parse(window)?.with_utc_offset((offset * 60.0).round() as i32))
+                    }
+                    _ => {
+                        return Err(Error::InvalidCommand(
+                            "Usage: /quiet [HH:MM-HH:MM [utc offset] | off]".to_string(),
+                        ))
+                    }
+                };
+                dispatcher.set_quiet_hours(chat_id, hours);
+                match hours {
+                    Some(hours) => {
+                        ctx.reply(format!("🌙 Quiet hours set to {}; critical alerts still come through.", hours))
+                            .await
+                    }
+                    None => ctx.reply("🔔 Quiet hours off.").await,
+                }
+            }
+        })
+        .describe("/quiet", "Hold non-critical alerts overnight: [HH:MM-HH:MM [offset] | off]")
+    }
+    
+    /// Send handler errors and panics to `admin_chat`, rate limited
+    pub fn with_error_reports(self, admin_chat: i64) -> Self {
+        let reporter = ErrorReporter::new(Arc::new(self.bot.clone()), admin_chat);
+        self.with_error_reporter(reporter)
+    }
+    
+    /// Send handler errors and panics through `reporter`
+    pub fn with_error_reporter(mut self, reporter: ErrorReporter) -> Self {
+        self.error_reporter = Some(Arc::new(reporter));
         self
     }
     
+    /// Translate built-in replies with `i18n`, and register `/language` so
+    /// users can pick a locale: `/language de`, `/language auto` to follow
+    /// their Telegram app, or `/language` to list the choices
+    ///
+    /// Call this before registering handlers that use [`Bot::i18n`].
+    pub fn with_i18n(mut self, i18n: I18n) -> Self {
+        let i18n = Arc::new(i18n);
+        self.i18n = i18n.clone();
+        self.on_command("/language", move |ctx: Context| {
+            let i18n = i18n.clone();
+            async move {
+                let available = i18n.locales().join(", ");
+                let code = match ctx.args().as_slice() {
+                    [] => {
+                        let locale = i18n.locale(&ctx).await;
+                        let text = i18n.t(&locale, "language.current", &[("locale", &locale), ("available", &available)]);
+                        return ctx.reply(text).await;
+                    }
+                    ["auto"] => None,
+                    [code] => match i18n.supported(code) {
+                        Some(locale) => Some(locale),
+                        None => {
+                            let locale = i18n.locale(&ctx).await;
+                            let text = i18n.t(&locale, "language.unknown", &[("locale", code), ("available", &available)]);
+                            return ctx.reply(text).await;
+                        }
+                    },
+                    _ => return Err(Error::InvalidCommand("Usage: /language [code | auto]".to_string())),
+                };
+                i18n.set_locale(ctx.user_id(), code.as_deref()).await?;
+                let locale = i18n.locale(&ctx).await;
+                let text = i18n.t(&locale, "language.set", &[("locale", &locale)]);
+                ctx.reply(text).await
+            }
+        })
+        .describe("/language", "Choose your language: [code]")
+    }
+    
+    /// Run `scheduler`'s jobs while the bot is running, and register
+    /// `/schedule <job> <when>`, `/unschedule <id>` and `/schedules` for
+    /// traders to manage them from chat
+    ///
+    /// `<when>` is anything [`Schedule::parse`] accepts, e.g.
+    /// `/schedule pnl daily 00:00`.
+    pub fn with_scheduler(mut self, scheduler: Scheduler) -> Self {
+        self.scheduler = Some(scheduler.clone());
+        let list = scheduler.clone();
+        let remove = scheduler.clone();
+        self.on_command_with_role("/schedule", Role::Trader, move |ctx: Context| {
+            let scheduler = scheduler.clone();
+            async move {
+                let args = ctx.args();
+                if args.len() < 3 {
+                    return Err(Error::InvalidCommand(
+                        "Usage: /schedule <job> <every 30m|hourly :MM|daily HH:MM>".to_string(),
+                    ));
+                }
+                let schedule = Schedule::parse(&args[1..].join(" "))?;
+                let id = scheduler.schedule(args[0], ctx.chat_id(), schedule).await?;
+                ctx.reply(format!("✅ Scheduled {} ({}) as #{}", args[0], schedule, id)).await
+            }
+        })
+        .on_command_with_role("/unschedule", Role::Trader, move |ctx: Context| {
+            let scheduler = remove.clone();
+            async move {
+                let id = ctx
+                    .args()
+                    .first()
+                    .and_then(|id| id.trim_start_matches('#').parse().ok())
+                    .ok_or_else(|| Error::InvalidCommand("Usage: /unschedule <id>".to_string()))?;
+                if scheduler.unschedule(id).await? {
+                    ctx.reply(format!("Removed schedule #{}", id)).await
+                } else {
+                    ctx.reply(format!("No schedule #{}", id)).await
+                }
+            }
+        })
+        .on_command_with_role("/schedules", Role::Viewer, move |ctx: Context| {
+            let scheduler = list.clone();
+            async move {
+                let entries: Vec<_> = scheduler
+                    .entries()
+                    .into_iter()
+                    .filter(|e| e.chat_id == ctx.chat_id())
+                    .collect();
+                if entries.is_empty() {
+                    return ctx.reply("No scheduled jobs.").await;
+                }
+                let mut text = String::from("Scheduled jobs (UTC):\n");
+                for e in entries {
+                    text.push_str(&format!(
+                        "  #{} {} - {}, next {}\n",
+                        e.id,
+                        e.job,
+                        e.schedule,
+                        e.next_run.format("%Y-%m-%d %H:%M")
+                    ));
+                }
+                ctx.reply(text).await
+            }
+        })
+        .describe("/schedule", "Schedule a job: <job> <when>")
+        .describe("/unschedule", "Remove a scheduled job: <id>")
+        .describe("/schedules", "List scheduled jobs")
+    }
+    
     /// Register a callback query handler with pattern matching
     pub fn on_callback<F, Fut>(mut self, pattern: impl Into<String>, handler: F) -> Self
     where
@@ -51,7 +808,108 @@ impl Bot {
         Fut: Future<Output = Result<()>> + Send + 'static,
     {
         let handler: HandlerFn = Arc::new(move |ctx| Box::pin(handler(ctx)));
-        self.callback_handlers.push((pattern.into(), handler));
+        self.router.callback_handlers.push((pattern.into(), handler));
+        self
+    }
+    
+    /// Register a handler for buttons carrying `T` callback data
+    ///
+    /// Callbacks with `T`'s prefix that fail to parse are reported as errors
+    /// without reaching `handler`.
+    pub fn on_callback_data<T, F, Fut>(self, handler: F) -> Self
+    where
+        T: CallbackData + Send + 'static,
+        F: Fn(Context, T) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let handler = Arc::new(handler);
+        self.on_callback(format!("{}:", T::PREFIX), move |ctx: Context| {
+            let handler = handler.clone();
+            async move {
+                let data = ctx.callback_data::<T>().ok_or_else(|| {
+                    Error::InvalidCommand(format!("Malformed callback data: {}", ctx.text().unwrap_or_default()))
+                })?;
+                handler(ctx, data).await
+            }
+        })
+    }
+    
+    /// Answer inline queries (`@mybot BTC` typed in any chat) with the
+    /// results `handler` returns
+    ///
+    /// Unauthorized users get no results.
+    pub fn on_inline_query<F, Fut>(mut self, handler: F) -> Self
+    where
+        F: Fn(InlineContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Vec<InlineQueryResult>>> + Send + 'static,
+    {
+        self.router.inline_handler = Some(Arc::new(move |ctx| Box::pin(handler(ctx))));
+        self
+    }
+    
+    /// Register a handler for chats with an active dialogue
+    ///
+    /// Non-command messages from a chat whose state is set in `storage` are
+    /// passed to `handler` along with the current state. Start a dialogue from
+    /// a command handler with [`Dialogue::update`].
+    pub fn on_dialogue<S, F, Fut>(mut self, storage: Arc<dyn DialogueStorage<S>>, handler: F) -> Self
+    where
+        S: Send + 'static,
+        F: Fn(Context, Dialogue<S>, S) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let handler = Arc::new(handler);
+        let hook: DialogueFn = Arc::new(move |ctx: Context| {
+            let storage = storage.clone();
+            let handler = handler.clone();
+            Box::pin(async move {
+                let dialogue = Dialogue::new(storage, ctx.chat_id());
+                match dialogue.get().await? {
+                    Some(state) => {
+                        handler(ctx, dialogue, state).await?;
+                        Ok(true)
+                    }
+                    None => Ok(false),
+                }
+            })
+        });
+        self.router.dialogue_handlers.push(hook);
+        self
+    }
+    
+    /// Register a handler for files users send to the bot
+    ///
+    /// Files within `limits` are downloaded and passed to `handler`; others
+    /// are refused with a reply naming the limit. Chats with an active
+    /// dialogue get their documents in the dialogue handler instead.
+    pub fn on_document<F, Fut>(mut self, limits: DocumentLimits, handler: F) -> Self
+    where
+        F: Fn(Context, Upload) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let handler = Arc::new(handler);
+        self.router.document_handler = Some(Arc::new(move |ctx: Context| {
+            let handler = handler.clone();
+            let limits = limits.clone();
+            Box::pin(async move {
+                let upload = ctx.download_document(&limits).await?;
+                info!(
+                    "User {} uploaded {} ({} bytes)",
+                    ctx.user_id(),
+                    upload.file_name.as_deref().unwrap_or("a file"),
+                    upload.bytes.len()
+                );
+                handler(ctx, upload).await
+            })
+        }));
+        self
+    }
+    
+    /// Add a middleware that wraps every authorized update
+    ///
+    /// Middleware run in the order they are added.
+    pub fn with_middleware(mut self, middleware: impl Middleware + 'static) -> Self {
+        self.middleware.push(middleware);
         self
     }
     
@@ -61,74 +919,253 @@ impl Bot {
         F: Fn(Context) -> Fut + Send + Sync + 'static,
         Fut: Future<Output = Result<()>> + Send + 'static,
     {
-        self.default_handler = Some(Arc::new(move |ctx| Box::pin(handler(ctx))));
+        self.router.default_handler = Some(Arc::new(move |ctx| Box::pin(handler(ctx))));
         self
     }
     
-    /// Run the bot (blocking)
+    /// Run `hook` after the bot stops, e.g. to save state
+    ///
+    /// Hooks run in registration order once polling has stopped.
+    pub fn on_shutdown<F, Fut>(mut self, hook: F) -> Self
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.shutdown_hooks.push(Box::new(move || Box::pin(hook())));
+        self
+    }
+    
+    /// Drain `dispatcher`'s queue when the bot stops
+    pub fn with_alert_dispatcher(self, dispatcher: Arc<AlertDispatcher>) -> Self {
+        self.on_shutdown(move || async move {
+            dispatcher.close().await;
+            Ok(())
+        })
+    }
+    
+    /// Flush `store` when the bot stops
+    pub fn with_session_store(self, store: Arc<dyn SessionStore>) -> Self {
+        self.on_shutdown(move || async move { store.flush().await })
+    }
+    
+    /// Run the bot until Ctrl-C, then run shutdown hooks (blocking)
     pub async fn run(self) -> Result<()> {
         info!("Starting Telegram bot...");
         
-        let bot = self.bot.clone();
+        let (mut dispatcher, cleanup) = self.launch(true).await;
+        dispatcher.dispatch().await;
+        cleanup.run().await
+    }
+    
+    /// Run the bot until `signal` resolves, then shut down gracefully (blocking)
+    ///
+    /// Unlike [`Bot::run`], no Ctrl-C handler is installed; the host
+    /// application decides when to stop.
+    pub async fn run_with_shutdown(self, signal: impl Future<Output = ()>) -> Result<()> {
+        let handle = self.start().await;
+        signal.await;
+        handle.shutdown().await
+    }
+    
+    /// Start the bot in the background
+    ///
+    /// No Ctrl-C handler is installed; stop it with [`BotHandle::shutdown`].
+    pub async fn start(self) -> BotHandle {
+        info!("Starting Telegram bot...");
+        
+        let (mut dispatcher, cleanup) = self.launch(false).await;
+        let token = dispatcher.shutdown_token();
+        let dispatch = tokio::spawn(async move { dispatcher.dispatch().await });
+        BotHandle::new(token, dispatch, cleanup)
+    }
+    
+    /// Add `/help`, publish commands and start the scheduler
+    async fn launch(self, ctrlc: bool) -> (Dispatcher<teloxide::Bot, teloxide::RequestError, DefaultKey>, Cleanup) {
+        report::check_unwind();
+        let mut bot = self.with_help();
+        bot.publish_commands().await;
+        match bot.bot.get_me().await {
+            Ok(me) => bot.username = me.user.username,
+            Err(e) => warn!("Failed to fetch bot username: {}", e),
+        }
+        let cleanup = Cleanup {
+            scheduler: bot.scheduler.as_ref().map(|s| s.start(bot.bot.clone())),
+            hooks: std::mem::take(&mut bot.shutdown_hooks),
+        };
+        (bot.into_dispatcher(ctrlc), cleanup)
+    }
+    
+    /// Publish registered commands to Telegram's command menu
+    ///
+    /// Everyone sees the commands the default role can use, without
+    /// private-only ones in groups; admins see all of them in their private
+    /// chats. Called by [`Bot::run`].
+    pub async fn register_commands(&self) -> Result<()> {
+        let menu = |role: Role, group: bool| -> Vec<BotCommand> {
+            self.router
+                .commands
+                .iter()
+                .filter(|c| c.visible_to(role) && !(group && c.private))
+                .filter_map(|c| {
+                    let name = c.command.trim_start_matches('/');
+                    let valid = (1..=32).contains(&name.len())
+                        && name.chars().all(|ch| ch.is_ascii_lowercase() || ch.is_ascii_digit() || ch == '_');
+                    if !valid {
+                        warn!("Not listing {} in the command menu: invalid name", c.command);
+                        return None;
+                    }
+                    let description = match c.description() {
+                        "" => c.command.clone(),
+                        d => d.chars().take(256).collect(),
+                    };
+                    Some(BotCommand::new(name, description))
+                })
+                .collect()
+        };
+        
+        let default_role = self.access_control.default_role();
+        self.bot.set_my_commands(menu(default_role, false)).await?;
+        self.bot
+            .set_my_commands(menu(default_role, true))
+            .scope(BotCommandScope::AllGroupChats)
+            .await?;
+        let admin_menu = menu(Role::Admin, false);
+        for admin in self.access_control.admins() {
+            self.bot
+                .set_my_commands(admin_menu.clone())
+                .scope(BotCommandScope::Chat {
+                    chat_id: Recipient::Id(ChatId(admin)),
+                })
+                .await?;
+        }
+        Ok(())
+    }
+    
+    async fn publish_commands(&self) {
+        if let Err(e) = self.register_commands().await {
+            warn!("Failed to register commands with Telegram: {}", e);
+        }
+    }
+    
+    /// Register `/help` listing the commands each user can run, unless the
+    /// bot already has one
+    fn with_help(mut self) -> Self {
+        if self.router.command_handlers.contains_key("/help") {
+            return self;
+        }
+        self.router.command_info("/help").description = Some("Show available commands".to_string());
+        let commands = self.router.commands.clone();
         let access_control = self.access_control.clone();
-        let command_handlers = Arc::new(self.command_handlers);
-        let callback_handlers = Arc::new(self.callback_handlers);
-        let default_handler = self.default_handler;
+        let i18n = self.i18n.clone();
+        self.on_command("/help", move |ctx: Context| {
+            let role = access_control.role_of(ctx.user_id()).unwrap_or(access_control.default_role());
+            let commands = commands.clone();
+            let i18n = i18n.clone();
+            async move {
+                let locale = i18n.locale(&ctx).await;
+                let mut text = format!("{}\n", i18n.get(&locale, "help.header"));
+                for c in commands.iter().filter(|c| c.visible_to(role)) {
+                    let usage = c.usage.as_deref().unwrap_or(&c.command);
+                    // Translated as `help.<command>`, e.g. `help.status`
+                    let key = format!("help.{}", c.command.trim_start_matches('/'));
+                    match i18n.lookup(&locale, &key).unwrap_or(c.description()) {
+                        "" => text.push_str(&format!("  {}\n", usage)),
+                        d => text.push_str(&format!("  {} - {}\n", usage, d)),
+                    }
+                }
+                ctx.reply(text).await
+            }
+        })
+    }
+    
+    /// Run the bot with a webhook instead of long polling (blocking)
+    ///
+    /// Listens on `addr` and registers `url` with Telegram; pass `cert` when
+    /// the ingress uses a self-signed certificate. Falls back to long polling
+    /// if the webhook cannot be registered.
+    #[cfg(feature = "webhooks")]
+    pub async fn run_webhook(
+        self,
+        addr: std::net::SocketAddr,
+        url: url::Url,
+        cert: Option<teloxide::types::InputFile>,
+    ) -> Result<()> {
+        use teloxide::error_handlers::LoggingErrorHandler;
+        use teloxide::update_listeners::webhooks;
+        
+        info!("Starting Telegram bot with webhook at {}...", url);
+        
+        let mut options = webhooks::Options::new(addr, url);
+        if let Some(cert) = cert {
+            options = options.certificate(cert);
+        }
+        
+        let bot = self.bot.clone();
+        let (mut dispatcher, cleanup) = self.launch(true).await;
+        match webhooks::axum(bot, options).await {
+            Ok(listener) => {
+                dispatcher
+                    .dispatch_with_listener(
+                        listener,
+                        LoggingErrorHandler::with_custom_text("Webhook listener error"),
+                    )
+                    .await;
+            }
+            Err(e) => {
+                warn!("Webhook setup failed, falling back to long polling: {}", e);
+                dispatcher.dispatch().await;
+            }
+        }
+        
+        cleanup.run().await
+    }
+    
+    /// Point API calls at `url` instead of `https://api.telegram.org`, e.g. a
+    /// self-hosted Bot API server or [`MockTelegram`](crate::testing::MockTelegram)
+    pub fn with_api_url(mut self, url: &str) -> Result<Self> {
+        let url = url
+            .parse()
+            .map_err(|e| Error::Config(format!("Invalid API URL {}: {}", url, e)))?;
+        self.bot = self.bot.set_api_url(url);
+        Ok(self)
+    }
+    
+    /// The bot as [`Bot::run`] would start it, without polling: `/help` is
+    /// registered and `username` stands in for the one fetched from Telegram
+    #[cfg(any(test, feature = "testing"))]
+    pub(crate) fn into_test_pipeline(self, username: &str) -> (teloxide::Bot, Arc<Pipeline>) {
+        let mut bot = self.with_help();
+        bot.username = Some(username.to_string());
+        bot.into_pipeline()
+    }
+    
+    fn into_pipeline(self) -> (teloxide::Bot, Arc<Pipeline>) {
+        let pipeline = Arc::new(Pipeline {
+            access_control: self.access_control,
+            middleware: self.middleware,
+            router: self.router,
+            audit_log: self.audit_log,
+            i18n: self.i18n,
+            error_reporter: self.error_reporter,
+            state: self.state,
+            username: self.username,
+        });
+        (self.bot, pipeline)
+    }
+    
+    fn into_dispatcher(self, ctrlc: bool) -> Dispatcher<teloxide::Bot, teloxide::RequestError, DefaultKey> {
+        let (bot, pipeline) = self.into_pipeline();
+        let callback_pipeline = pipeline.clone();
+        let inline_pipeline = pipeline.clone();
         
         let handler = dptree::entry()
             .branch(
                 Update::filter_message().endpoint(
                     move |bot: teloxide::Bot, msg: Message| {
-                        let access_control = access_control.clone();
-                        let handlers = command_handlers.clone();
-                        let default = default_handler.clone();
+                        let pipeline = pipeline.clone();
                         
                         async move {
-                            if let Some(user) = msg.from() {
-                                let user_id = user.id.0 as i64;
-                                
-                                // Check authorization
-                                if let Err(e) = access_control.authorize(user_id) {
-                                    warn!("Unauthorized access attempt from user {}", user_id);
-                                    let _ = bot.send_message(
-                                        msg.chat.id,
-                                        "⛔ You are not authorized to use this bot."
-                                    ).await;
-                                    return Ok(());
-                                }
-                                
-                                let ctx = MessageContext {
-                                    message: msg.clone(),
-                                    user: user.clone(),
-                                    chat_id: msg.chat.id.0,
-                                    text: msg.text().map(|s| s.to_string()),
-                                    bot: bot.clone(),
-                                };
-                                
-                                // Try to match command
-                                if let Some(text) = msg.text() {
-                                    let parts: Vec<&str> = text.split_whitespace().collect();
-                                    if let Some(cmd) = parts.first() {
-                                        if let Some(handler) = handlers.get(*cmd) {
-                                            if let Err(e) = handler(Context::Message(ctx)).await {
-                                                error!("Handler error: {}", e);
-                                                let _ = bot.send_message(
-                                                    msg.chat.id,
-                                                    format!("❌ Error: {}", e)
-                                                ).await;
-                                            }
-                                            return Ok(());
-                                        }
-                                    }
-                                }
-                                
-                                // Default handler
-                                if let Some(handler) = default {
-                                    if let Err(e) = handler(Context::Message(ctx)).await {
-                                        error!("Default handler error: {}", e);
-                                    }
-                                }
-                            }
+                            pipeline.handle_message(bot, msg).await;
                             Ok(())
                         }
                     }
@@ -137,46 +1174,292 @@ impl Bot {
             .branch(
                 Update::filter_callback_query().endpoint(
                     move |bot: teloxide::Bot, q: CallbackQuery| {
-                        let handlers = callback_handlers.clone();
+                        let pipeline = callback_pipeline.clone();
                         
                         async move {
-                            if let (Some(data), Some(user)) = (q.data.clone(), q.from) {
-                                let ctx = CallbackContext {
-                                    query: q.clone(),
-                                    user: user.clone(),
-                                    chat_id: q.message.as_ref()
-                                        .map(|m| m.chat.id.0)
-                                        .unwrap_or(user.id.0 as i64),
-                                    data: data.clone(),
-                                    bot: bot.clone(),
-                                };
-                                
-                                // Find matching handler
-                                for (pattern, handler) in handlers.iter() {
-                                    if data.starts_with(pattern) {
-                                        if let Err(e) = handler(Context::Callback(ctx)).await {
-                                            error!("Callback handler error: {}", e);
-                                        }
-                                        return Ok(());
-                                    }
-                                }
-                            }
+                            pipeline.handle_callback_query(bot, q).await;
+                            Ok(())
+                        }
+                    }
+                )
+            )
+            .branch(
+                Update::filter_inline_query().endpoint(
+                    move |bot: teloxide::Bot, q: InlineQuery| {
+                        let pipeline = inline_pipeline.clone();
+                        
+                        async move {
+                            pipeline.handle_inline_query(bot, q).await;
                             Ok(())
                         }
                     }
                 )
             );
         
-        Dispatcher::builder(bot, handler)
-            .enable_ctrlc_handler()
-            .build()
-            .dispatch()
-            .await;
+        let mut builder = Dispatcher::builder(bot, handler);
+        if ctrlc {
+            builder = builder.enable_ctrlc_handler();
+        }
+        builder.build()
+    }
+}
+
+/// Everything an update passes through once the bot is running
+pub(crate) struct Pipeline {
+    access_control: AccessControl,
+    middleware: MiddlewareStack,
+    router: Router,
+    audit_log: Option<Arc<dyn AuditLog>>,
+    i18n: Arc<I18n>,
+    error_reporter: Option<Arc<ErrorReporter>>,
+    state: State,
+    username: Option<String>,
+}
+
+impl Pipeline {
+    pub(crate) async fn handle_message(&self, bot: teloxide::Bot, msg: Message) {
+        let text = match msg.text() {
+            Some(text) => match strip_mention(text, self.username.as_deref()) {
+                Some(text) => Some(text),
+                // Addressed to another bot in the group
+                None => return,
+            },
+            None => None,
+        };
+        if let Some(user) = msg.from.as_ref() {
+            let ctx = MessageContext {
+                message: msg.clone(),
+                user: user.clone(),
+                chat_id: msg.chat.id.0,
+                text,
+                bot,
+                state: self.state.clone(),
+            };
+            self.handle(Context::Message(ctx)).await;
+        }
+    }
+    
+    pub(crate) async fn handle_callback_query(&self, bot: teloxide::Bot, q: CallbackQuery) {
+        if let Some(data) = q.data.clone() {
+            let user = q.from.clone();
+            // Messages too old for the bot to read still carry their chat
+            let ctx = CallbackContext {
+                chat_id: q.message.as_ref()
+                    .map(|m| m.chat().id.0)
+                    .unwrap_or(user.id.0 as i64),
+                query: q,
+                user,
+                data,
+                bot,
+                state: self.state.clone(),
+                answered: Arc::default(),
+            };
+            self.handle(Context::Callback(ctx)).await;
+        }
+    }
+    
+    pub(crate) async fn handle_inline_query(&self, bot: teloxide::Bot, q: InlineQuery) {
+        let ctx = InlineContext {
+            user: q.from.clone(),
+            query: q,
+            bot,
+            state: self.state.clone(),
+        };
+        self.handle_inline(ctx).await;
+    }
+    
+    /// Authorize, run middleware and the matched handler, and report errors
+    ///
+    /// Updates from groups that are not allowed are ignored, except from
+    /// admins so they can run `/addchat` there.
+    async fn handle(&self, ctx: Context) {
+        let user_id = ctx.user_id();
+        let chat_id = ctx.chat_id();
+        let endpoint = self.router.endpoint(&ctx);
+        if self.access_control.authorize_chat(chat_id).is_err() && !self.access_control.is_admin(user_id) {
+            warn!("Ignoring update from unauthorized chat {}", chat_id);
+            metrics::authorization_denied(&endpoint, "chat");
+            self.audit(&ctx, AuditOutcome::Denied).await;
+            return;
+        }
+        if self.access_control.authorize(user_id).is_err() {
+            warn!("Unauthorized access attempt from user {}", user_id);
+            metrics::authorization_denied(&endpoint, "user");
+            self.audit(&ctx, AuditOutcome::Denied).await;
+            // In groups only answer commands, not every message members send
+            let is_command = ctx.text().is_some_and(|t| t.starts_with('/'));
+            match &ctx {
+                Context::Message(_) if ctx.is_private() || is_command => {
+                    let locale = self.i18n.locale(&ctx).await;
+                    let _ = ctx.reply(self.i18n.get(&locale, "unauthorized")).await;
+                }
+                Context::Message(_) => {}
+                Context::Callback(cb) => {
+                    let locale = self.i18n.locale(&ctx).await;
+                    let _ = cb.answer_alert(self.i18n.get(&locale, "unauthorized")).await;
+                }
+            }
+            return;
+        }
         
-        Ok(())
+        let started = Instant::now();
+        let router = &self.router;
+        let access_control = &self.access_control;
+        let i18n = self.i18n.as_ref();
+        let handler = self.middleware.run(ctx.clone(), |ctx| async move {
+            match ctx {
+                Context::Message(_) => router.route_message(ctx, access_control, i18n).await,
+                Context::Callback(_) => router.route_callback(ctx).await,
+            }
+        });
+        let handler = async {
+            match router.timeout_for(&ctx) {
+                Some(limit) => timeout::run(handler, limit, || self.still_working(&ctx)).await,
+                None => handler.await,
+            }
+        };
+        // Boxed: handler futures run to hundreds of KB and overflow the stack
+        // in debug builds
+        let (result, trace) = report::catch_panic(Box::pin(handler)).await;
+        metrics::command_handled(&endpoint, metrics::outcome(&result), started.elapsed());
+        if let Err(Error::Forbidden(..)) = &result {
+            metrics::authorization_denied(&endpoint, "role");
+        }
+        
+        let outcome = match &result {
+            Ok(()) => AuditOutcome::Ok,
+            Err(e) => AuditOutcome::Failed(e.to_string()),
+        };
+        self.audit(&ctx, outcome).await;
+        
+        if let Err(e) = result {
+            match ctx {
+                Context::Message(_) => {
+                    error!("Handler error: {}", e);
+                    let locale = self.i18n.locale(&ctx).await;
+                    let text = self.i18n.t(&locale, "error", &[("error", &e)]);
+                    let _ = ctx.reply(text).await;
+                }
+                Context::Callback(ref cb) => {
+                    error!("Callback handler error: {}", e);
+                    let locale = self.i18n.locale(&ctx).await;
+                    let text = self.i18n.t(&locale, "error", &[("error", &e)]);
+                    if let Err(e) = cb.answer_alert(text).await {
+                        warn!("Failed to answer callback query: {}", e);
+                    }
+                }
+            }
+            self.report(ErrorReport::from_context(&ctx, &e).with_trace(trace)).await;
+        }
+        // Unanswered buttons keep spinning in the user's app
+        if let Context::Callback(cb) = &ctx {
+            if let Err(e) = cb.answer_query(None, false).await {
+                warn!("Failed to answer callback query: {}", e);
+            }
+        }
+    }
+    
+    /// Answer an inline query; unauthorized users get an empty answer
+    async fn handle_inline(&self, ctx: InlineContext) {
+        let Some(handler) = &self.router.inline_handler else {
+            return;
+        };
+        let results = if self.access_control.authorize(ctx.user_id()).is_ok() {
+            let started = Instant::now();
+            let result = handler(ctx.clone()).await;
+            let outcome = match &result {
+                Ok(_) => "ok",
+                Err(_) => "error",
+            };
+            metrics::command_handled("inline", outcome, started.elapsed());
+            match result {
+                Ok(results) => results,
+                Err(e) => {
+                    error!("Inline query handler error: {}", e);
+                    self.report(ErrorReport {
+                        user_id: Some(ctx.user_id()),
+                        username: ctx.user.username.clone(),
+                        input: Some(format!("inline: {}", ctx.query.query)),
+                        ..ErrorReport::new(e)
+                    })
+                    .await;
+                    return;
+                }
+            }
+        } else {
+            warn!("Unauthorized inline query from user {}", ctx.user_id());
+            metrics::authorization_denied("inline", "user");
+            Vec::new()
+        };
+        
+        if let Err(e) = ctx
+            .bot
+            .answer_inline_query(ctx.query.id.clone(), results)
+            .cache_time(inline::CACHE_TIME)
+            .is_personal(true)
+            .await
+        {
+            error!("Failed to answer inline query: {}", e);
+        }
+    }
+    
+    async fn still_working(&self, ctx: &Context) {
+        let locale = self.i18n.locale(ctx).await;
+        if let Err(e) = ctx.reply(self.i18n.get(&locale, "still_working")).await {
+            warn!("Failed to send progress notice: {}", e);
+        }
+    }
+    
+    async fn report(&self, report: ErrorReport) {
+        let Some(reporter) = &self.error_reporter else {
+            return;
+        };
+        if let Err(e) = reporter.report(report).await {
+            error!("Failed to send error report to chat {}: {}", reporter.chat_id(), e);
+        }
+    }
+    
+    async fn audit(&self, ctx: &Context, outcome: AuditOutcome) {
+        let Some(log) = &self.audit_log else {
+            return;
+        };
+        if let Some(entry) = AuditEntry::from_context(ctx, outcome) {
+            if let Err(e) = log.record(entry).await {
+                error!("Failed to write audit log: {}", e);
+            }
+        }
     }
 }
 
+/// Strip this bot's `@username` from a leading command
+///
+/// Returns `None` if the command is addressed to another bot. Any suffix is
+/// stripped when the username is unknown.
+fn strip_mention(text: &str, username: Option<&str>) -> Option<String> {
+    let Some(rest) = text.strip_prefix('/') else {
+        return Some(text.to_string());
+    };
+    let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+    let (word, tail) = rest.split_at(end);
+    let Some((command, target)) = word.split_once('@') else {
+        return Some(text.to_string());
+    };
+    match username {
+        Some(name) if !target.eq_ignore_ascii_case(name) => None,
+        _ => Some(format!("/{}{}", command, tail)),
+    }
+}
+
+fn parse_user_id(s: &str) -> Result<i64> {
+    s.parse()
+        .map_err(|_| Error::InvalidCommand(format!("Invalid user ID: {}", s)))
+}
+
+fn parse_chat_id(s: &str) -> Result<i64> {
+    s.parse()
+        .map_err(|_| Error::InvalidCommand(format!("Invalid chat ID: {}", s)))
+}
+
 /// Builder for Bot configuration
 pub struct BotBuilder {
     token: String,
@@ -191,6 +1474,12 @@ impl BotBuilder {
         }
     }
     
+    /// Use a preconfigured access control, e.g. one with persistence
+    pub fn with_access_control(mut self, access_control: AccessControl) -> Self {
+        self.access_control = access_control;
+        self
+    }
+    
     pub fn with_whitelist(mut self, ids: Vec<i64>) -> Self {
         self.access_control = self.access_control.with_whitelist(ids);
         self
@@ -201,13 +1490,38 @@ impl BotBuilder {
         self
     }
     
+    pub fn with_role(mut self, ids: Vec<i64>, role: Role) -> Self {
+        self.access_control = self.access_control.with_role(ids, role);
+        self
+    }
+    
+    pub fn with_default_role(mut self, role: Role) -> Self {
+        self.access_control = self.access_control.with_default_role(role);
+        self
+    }
+    
+    /// Allow the bot only in these group chats
+    pub fn with_chats(mut self, ids: Vec<i64>) -> Self {
+        self.access_control = self.access_control.with_chats(ids);
+        self
+    }
+    
     pub fn build(self) -> Bot {
         Bot {
             bot: teloxide::Bot::new(self.token),
             access_control: self.access_control,
-            command_handlers: HashMap::new(),
-            callback_handlers: Vec::new(),
-            default_handler: None,
+            middleware: MiddlewareStack::new(),
+            router: Router::default(),
+            confirmations: None,
+            approvals: None,
+            paginator: None,
+            audit_log: None,
+            scheduler: None,
+            shutdown_hooks: Vec::new(),
+            i18n: Arc::new(I18n::new()),
+            error_reporter: None,
+            state: State::new(),
+            username: None,
         }
     }
 }
@@ -217,3 +1531,273 @@ impl From<BotBuilder> for Bot {
         builder.build()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_strip_mention() {
+        let name = Some("TradeBot");
+        assert_eq!(strip_mention("/status@tradebot", name).as_deref(), Some("/status"));
+        assert_eq!(strip_mention("/trade@TradeBot BTC 1", name).as_deref(), Some("/trade BTC 1"));
+        assert_eq!(strip_mention("/status@OtherBot", name), None);
+        assert_eq!(strip_mention("/status", name).as_deref(), Some("/status"));
+        assert_eq!(strip_mention("mail me@example.com", name).as_deref(), Some("mail me@example.com"));
+        assert_eq!(strip_mention("/status@anybot", None).as_deref(), Some("/status"));
+    }
+    
+    #[tokio::test]
+    async fn test_command_roles() {
+        use crate::testing::TestBot;
+        
+        let bot = Bot::new("123:test")
+            .with_admins(vec![1])
+            .with_role(vec![7], Role::Viewer)
+            .build()
+            .command_role("/close", Role::Trader)
+            .on_command("/close", |ctx: Context| async move { ctx.reply("Closed").await })
+            .on_admin_command("/halt", |ctx: Context| async move { ctx.reply("Halted").await });
+        let tg = TestBot::start(bot).await.unwrap();
+        
+        tg.send_text(7, "/halt").await;
+        tg.send_text(7, "/close").await;
+        tg.send_text(1, "/halt").await;
+        tg.send_text(1, "/close").await;
+        let replies = tg.replies();
+        assert!(replies[0].contains("requires role"));
+        assert!(replies[1].contains("requires role"));
+        assert_eq!(replies[2..], ["Halted", "Closed"]);
+    }
+    
+    #[tokio::test]
+    async fn test_help_and_command_menu() {
+        use crate::testing::TestBot;
+        
+        let bot = || {
+            Bot::new("123:test")
+                .with_admins(vec![1])
+                .with_role(vec![7], Role::Viewer)
+                .with_default_role(Role::Trader)
+                .build()
+                .on_command("/status", |ctx: Context| async move { ctx.reply("OK").await })
+                .describe("/status", "Show bot status")
+                .on_command_with_role("/close", Role::Trader, |ctx: Context| async move { ctx.reply("Closed").await })
+                .on_admin_command("/halt", |ctx: Context| async move { ctx.reply("Halted").await })
+                .describe("/halt", "Stop trading")
+                .on_command("/balance", |ctx: Context| async move { ctx.reply("0 USDC").await })
+                .private_only("/balance")
+                .on_command("/Bad-Name", |ctx: Context| async move { ctx.reply("?").await })
+        };
+        let tg = TestBot::start(bot()).await.unwrap();
+        
+        tg.send_text(7, "/help").await;
+        let help = tg.replies().pop().unwrap();
+        assert!(help.contains("/status - Show bot status"));
+        assert!(help.contains("/help - Show available commands"));
+        assert!(!help.contains("/halt"));
+        assert!(!help.contains("/close"));
+        // Users without a role of their own get the default role's commands,
+        // as in the published menu
+        tg.send_text(9, "/help").await;
+        let help = tg.replies().pop().unwrap();
+        assert!(help.contains("/close"));
+        assert!(!help.contains("/halt"));
+        tg.send_text(1, "/help").await;
+        assert!(tg.replies().pop().unwrap().contains("/halt - Stop trading"));
+        
+        tg.take_calls();
+        let menu = bot().with_api_url(tg.telegram().url()).unwrap();
+        menu.register_commands().await.unwrap();
+        let calls = tg.take_calls();
+        assert_eq!(calls.len(), 3);
+        assert!(calls.iter().all(|c| c.method == "setMyCommands"));
+        let names = |call: &crate::testing::ApiCall| -> Vec<String> {
+            call.params["commands"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|c| c["command"].as_str().unwrap().to_string())
+                .collect()
+        };
+        // Invalid names and admin commands stay out of the default menu,
+        // private-only ones out of the group menu
+        assert!(calls[0].params["scope"].is_null());
+        assert_eq!(names(&calls[0]), ["status", "close", "balance"]);
+        assert_eq!(calls[1].params["scope"]["type"], "all_group_chats");
+        assert_eq!(names(&calls[1]), ["status", "close"]);
+        assert_eq!(calls[2].params["scope"]["chat_id"], 1);
+        assert_eq!(names(&calls[2]), ["status", "close", "halt", "balance"]);
+    }
+    
+    /// Poll `telegram` until it has seen a call to `method`
+    #[cfg(feature = "webhooks")]
+    async fn wait_for_call(telegram: &crate::testing::MockTelegram, method: &str) -> crate::testing::ApiCall {
+        for _ in 0..200 {
+            if let Some(call) = telegram.calls().into_iter().find(|c| c.method == method) {
+                return call;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("no {} call", method);
+    }
+    
+    #[cfg(feature = "webhooks")]
+    #[tokio::test]
+    async fn test_webhook_delivers_updates() {
+        use crate::testing::MockTelegram;
+        use serde_json::json;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        
+        let telegram = MockTelegram::start().await.unwrap();
+        let bot = Bot::new("123:test")
+            .with_whitelist(vec![7])
+            .build()
+            .on_command("/status", |ctx: Context| async move { ctx.reply("All systems go").await })
+            .with_api_url(telegram.url())
+            .unwrap();
+        let addr = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let url: url::Url = "https://bot.example.com/telegram".parse().unwrap();
+        // The bot future is not `Send`, so drive it alongside the checks
+        let checks = async {
+            let registered = wait_for_call(&telegram, "setWebhook").await;
+            assert_eq!(registered.params["url"], "https://bot.example.com/telegram");
+            let secret = registered.params["secret_token"].as_str().unwrap().to_string();
+            
+            let update = json!({
+                "update_id": 1,
+                "message": {
+                    "message_id": 1,
+                    "date": 0,
+                    "chat": { "id": 7, "type": "private", "first_name": "Test" },
+                    "from": { "id": 7, "is_bot": false, "first_name": "Test" },
+                    "text": "/status",
+                },
+            })
+            .to_string();
+            let request = format!(
+                "POST /telegram HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\n\
+                 X-Telegram-Bot-Api-Secret-Token: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                addr,
+                secret,
+                update.len(),
+                update
+            );
+            // The listener binds after the webhook is registered
+            let mut stream = None;
+            for _ in 0..200 {
+                match tokio::net::TcpStream::connect(addr).await {
+                    Ok(connected) => {
+                        stream = Some(connected);
+                        break;
+                    }
+                    Err(_) => tokio::time::sleep(Duration::from_millis(10)).await,
+                }
+            }
+            let mut stream = stream.expect("webhook listener is up");
+            stream.write_all(request.as_bytes()).await.unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            assert!(response.starts_with("HTTP/1.1 200"));
+            
+            let reply = wait_for_call(&telegram, "sendMessage").await;
+            assert_eq!((reply.chat_id(), reply.text()), (Some(7), Some("All systems go")));
+            assert!(telegram.calls().iter().all(|c| c.method != "getUpdates"));
+        };
+        tokio::select! {
+            _ = bot.run_webhook(addr, url, None) => panic!("bot stopped"),
+            _ = checks => {}
+        }
+    }
+    
+    #[cfg(feature = "webhooks")]
+    #[tokio::test]
+    async fn test_webhook_falls_back_to_polling() {
+        use crate::testing::MockTelegram;
+        
+        let telegram = MockTelegram::start().await.unwrap();
+        telegram.fail("setWebhook", "Bad Request: bad webhook: HTTPS url must be provided for webhook");
+        telegram.respond("getUpdates", serde_json::json!([]));
+        let bot = Bot::new("123:test").build().with_api_url(telegram.url()).unwrap();
+        let addr = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let url: url::Url = "http://bot.example.com/telegram".parse().unwrap();
+        // The bot future is not `Send`, so drive it alongside the checks
+        let checks = async {
+            wait_for_call(&telegram, "getUpdates").await;
+        };
+        tokio::select! {
+            _ = bot.run_webhook(addr, url, None) => panic!("bot stopped"),
+            _ = checks => {}
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_alert_history_and_ack() {
+        use crate::alerts::AlertLevel;
+        use crate::history::{AlertHistory, AlertRecord, MemoryAlertHistory};
+        use crate::testing::TestBot;
+        
+        let history = Arc::new(MemoryAlertHistory::new(10));
+        let bot = Bot::new("123:test").with_admins(vec![1]).build().with_alert_history(history.clone());
+        let tg = TestBot::start(bot).await.unwrap();
+        
+        let sink = Bot::new("123:test").build().with_api_url(tg.telegram().url()).unwrap().bot;
+        let config = DispatcherConfig {
+            per_chat_interval: Duration::ZERO,
+            ..DispatcherConfig::new(vec![1])
+        };
+        history.record(AlertRecord::new(AlertLevel::Info, "fill", vec![1])).await.unwrap();
+        let dispatcher = AlertDispatcher::spawn(Arc::new(sink), config).with_history(history);
+        dispatcher.send(Alert::new(AlertLevel::Critical, "liquidation")).await.unwrap();
+        dispatcher.shutdown().await;
+        assert_eq!(tg.last_reply().unwrap().buttons(), ["✅ Ack"]);
+        
+        tg.send_text(1, "/alerts critical").await;
+        assert!(tg.replies().last().unwrap().ends_with("#2 liquidation — ⏳ not acknowledged"));
+        
+        tg.press_button(1, "ack:a:2").await;
+        assert_eq!(tg.replies().last().unwrap(), "✅ Acknowledged by @user1");
+        tg.send_text(1, "/alerts").await;
+        let listed = tg.replies().last().unwrap().clone();
+        assert!(listed.contains("#1 fill"));
+        assert!(listed.ends_with("#2 liquidation — ✅ @user1"));
+    }
+    
+    #[tokio::test]
+    async fn test_quiet_hours_requires_trader() {
+        use crate::testing::TestBot;
+        
+        let sink = Bot::new("123:test").build().bot;
+        let dispatcher = Arc::new(AlertDispatcher::spawn(Arc::new(sink), DispatcherConfig::new(vec![7, 9])));
+        let bot = Bot::new("123:test")
+            .with_role(vec![7], Role::Viewer)
+            .with_role(vec![9], Role::Trader)
+            .build()
+            .with_quiet_hours(dispatcher.clone());
+        let tg = TestBot::start(bot).await.unwrap();
+        
+        tg.send_text(7, "/quiet 23:00-07:00").await;
+        assert!(tg.replies().last().unwrap().contains("requires role"));
+        assert!(dispatcher.quiet_hours(7).is_none());
+        
+        tg.send_text(9, "/quiet 23:00-07:00").await;
+        assert!(tg.replies().last().unwrap().starts_with("🌙 Quiet hours set to"));
+        assert!(dispatcher.quiet_hours(9).is_some());
+    }
+    
+    #[tokio::test]
+    async fn test_chat_management_rejects_bad_id() {
+        use crate::testing::TestBot;
+        
+        let bot = Bot::new("123:test").with_admins(vec![1]).build().with_user_management();
+        let tg = TestBot::start(bot).await.unwrap();
+        
+        tg.send_text(1, "/addchat general").await;
+        assert!(tg.replies().last().unwrap().contains("Invalid chat ID: general"));
+        tg.send_text(1, "/removechat general").await;
+        assert!(tg.replies().last().unwrap().contains("Invalid chat ID: general"));
+        
+        tg.send_text(1, "/addchat -100123").await;
+        assert_eq!(tg.replies().last().unwrap(), "✅ Allowed chat -100123");
+    }
+}
@@ -0,0 +1,309 @@
+//! Broadcasting to many chats
+//!
+//! A [`Broadcaster`] (from [`Bot::broadcaster`](crate::Bot::broadcaster))
+//! sends one message to a list of chats, paced under Telegram's bulk limit
+//! with a little random jitter, retrying flood-control and network errors.
+//! The [`BroadcastReport`] says which chats got it, which failed and which
+//! have blocked the bot or no longer exist.
+//!
+//! [`Subscribers`] keeps a list of recipient chats, optionally persisted in a
+//! [`SessionStore`]. Broadcasting to it with
+//! [`Broadcaster::broadcast_to`] drops the chats that blocked the bot.
+//!
+//! ```rust,ignore
+//! let broadcaster = bot.broadcaster();
+//! let report = broadcaster.broadcast_to(&subscribers, AlertBuilder::info("Maintenance at 02:00")).await?;
+//! ```
+
+use std::collections::hash_map::RandomState;
+use std::collections::BTreeSet;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use teloxide::{ApiError, RequestError};
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+use crate::dispatcher::{Alert, AlertSink};
+use crate::error::{Error, Result};
+use crate::session::SessionStore;
+
+const STORE_SCOPE: i64 = 0;
+const STORE_KEY: &str = "broadcast.subscribers";
+
+/// Pacing and retry settings for a broadcast
+#[derive(Clone, Debug)]
+pub struct BroadcastConfig {
+    /// Messages per second; Telegram allows about 30 for bulk sends
+    pub max_per_second: u32,
+    /// Up to this much random delay is added between messages
+    pub jitter: Duration,
+    /// Retries after the first attempt for flood-control and network errors
+    pub max_retries: u32,
+    /// Initial backoff for network errors, doubled on each retry
+    pub retry_backoff: Duration,
+}
+
+impl Default for BroadcastConfig {
+    fn default() -> Self {
+        Self {
+            max_per_second: 25,
+            jitter: Duration::from_millis(50),
+            max_retries: 3,
+            retry_backoff: Duration::from_secs(1),
+        }
+    }
+}
+
+/// Outcome of a broadcast, per chat
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BroadcastReport {
+    pub sent: Vec<i64>,
+    /// Chats that could not be reached, with the error
+    pub failed: Vec<(i64, String)>,
+    /// Chats that blocked or removed the bot, or no longer exist
+    pub blocked: Vec<i64>,
+}
+
+impl BroadcastReport {
+    /// Whether every chat got the message
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty() && self.blocked.is_empty()
+    }
+}
+
+/// Sends one message to many chats
+#[derive(Clone)]
+pub struct Broadcaster {
+    sink: Arc<dyn AlertSink>,
+    config: BroadcastConfig,
+}
+
+impl Broadcaster {
+    pub fn new(sink: Arc<dyn AlertSink>) -> Self {
+        Self {
+            sink,
+            config: BroadcastConfig::default(),
+        }
+    }
+
+    pub fn with_config(mut self, config: BroadcastConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Send `message` to each of `chats` in turn
+    pub async fn broadcast(&self, chats: &[i64], message: impl Into<Alert>) -> BroadcastReport {
+        send_all(self.sink.as_ref(), chats, &message.into(), &self.config).await
+    }
+
+    /// Send `message` to every subscriber, removing chats that blocked the bot
+    pub async fn broadcast_to(&self, subscribers: &Subscribers, message: impl Into<Alert>) -> Result<BroadcastReport> {
+        let report = self.broadcast(&subscribers.chats(), message).await;
+        if !report.blocked.is_empty() {
+            let removed = subscribers.remove(&report.blocked).await?;
+            info!("Removed {} unreachable subscribers", removed);
+        }
+        Ok(report)
+    }
+}
+
+async fn send_all(sink: &dyn AlertSink, chats: &[i64], alert: &Alert, config: &BroadcastConfig) -> BroadcastReport {
+    let min_gap = Duration::from_secs(1) / config.max_per_second.max(1);
+    let mut report = BroadcastReport::default();
+    let mut next = Instant::now();
+
+    for &chat_id in chats {
+        tokio::time::sleep_until(next).await;
+        match send_with_retry(sink, chat_id, alert, config).await {
+            Ok(()) => report.sent.push(chat_id),
+            Err(e) if is_blocked(&e) => {
+                info!("Chat {} is unreachable: {}", chat_id, e);
+                report.blocked.push(chat_id);
+            }
+            Err(e) => {
+                warn!("Broadcast to {} failed: {}", chat_id, e);
+                report.failed.push((chat_id, e.to_string()));
+            }
+        }
+        next = Instant::now() + min_gap + jitter(config.jitter);
+    }
+    report
+}
+
+async fn send_with_retry(sink: &dyn AlertSink, chat_id: i64, alert: &Alert, config: &BroadcastConfig) -> Result<()> {
+    let mut backoff = config.retry_backoff;
+    let mut attempt = 0;
+    loop {
+        let wait = match sink.send_alert(chat_id, alert).await {
+            Ok(()) => return Ok(()),
+            Err(Error::Telegram(RequestError::RetryAfter(secs))) if attempt < config.max_retries => secs.duration(),
+            Err(Error::Telegram(RequestError::Network(_))) if attempt < config.max_retries => {
+                let wait = backoff;
+                backoff *= 2;
+                wait
+            }
+            Err(e) => return Err(e),
+        };
+        attempt += 1;
+        tokio::time::sleep(wait).await;
+    }
+}
+
+/// Whether an error means the chat will never accept messages again
+fn is_blocked(error: &Error) -> bool {
+    matches!(
+        error,
+        Error::Telegram(RequestError::Api(
+            ApiError::BotBlocked
+                | ApiError::BotKicked
+                | ApiError::BotKickedFromSupergroup
+                | ApiError::UserDeactivated
+                | ApiError::ChatNotFound
+        ))
+    )
+}
+
+/// Random delay up to `max`
+fn jitter(max: Duration) -> Duration {
+    if max.is_zero() {
+        return Duration::ZERO;
+    }
+    let random = RandomState::new().build_hasher().finish();
+    Duration::from_nanos(random % max.as_nanos() as u64)
+}
+
+/// Chats that receive broadcasts, shared by every clone
+#[derive(Clone, Default)]
+pub struct Subscribers {
+    chats: Arc<RwLock<BTreeSet<i64>>>,
+    store: Option<Arc<dyn SessionStore>>,
+}
+
+impl Subscribers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Persist subscribers in `store`, restoring any saved ones
+    pub async fn with_store(mut self, store: Arc<dyn SessionStore>) -> Result<Self> {
+        if let Some(saved) = store.get::<BTreeSet<i64>>(STORE_SCOPE, STORE_KEY).await? {
+            *self.chats.write().unwrap() = saved;
+        }
+        self.store = Some(store);
+        Ok(self)
+    }
+
+    /// Add a chat; returns whether it was new
+    pub async fn add(&self, chat_id: i64) -> Result<bool> {
+        let added = self.chats.write().unwrap().insert(chat_id);
+        if added {
+            self.save().await?;
+        }
+        Ok(added)
+    }
+
+    /// Remove chats; returns how many were subscribed
+    pub async fn remove(&self, chat_ids: &[i64]) -> Result<usize> {
+        let removed = {
+            let mut chats = self.chats.write().unwrap();
+            chat_ids.iter().filter(|id| chats.remove(id)).count()
+        };
+        if removed > 0 {
+            self.save().await?;
+        }
+        Ok(removed)
+    }
+
+    pub fn contains(&self, chat_id: i64) -> bool {
+        self.chats.read().unwrap().contains(&chat_id)
+    }
+
+    /// Subscribed chats, sorted
+    pub fn chats(&self) -> Vec<i64> {
+        self.chats.read().unwrap().iter().copied().collect()
+    }
+
+    async fn save(&self) -> Result<()> {
+        let Some(store) = &self.store else {
+            return Ok(());
+        };
+        let chats = self.chats.read().unwrap().clone();
+        store.set(STORE_SCOPE, STORE_KEY, &chats).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alerts::AlertLevel;
+    use crate::session::MemorySessionStore;
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+    use teloxide::types::Seconds;
+
+    /// Fails the chats in `errors` once each, with the given error
+    #[derive(Default)]
+    struct ScriptedSink {
+        errors: Mutex<Vec<(i64, RequestError)>>,
+        sent: Mutex<Vec<i64>>,
+    }
+
+    #[async_trait]
+    impl AlertSink for ScriptedSink {
+        async fn send_alert(&self, chat_id: i64, _alert: &Alert) -> Result<()> {
+            let mut errors = self.errors.lock().unwrap();
+            if let Some(i) = errors.iter().position(|(id, _)| *id == chat_id) {
+                return Err(errors.remove(i).1.into());
+            }
+            self.sent.lock().unwrap().push(chat_id);
+            Ok(())
+        }
+    }
+
+    fn config() -> BroadcastConfig {
+        BroadcastConfig {
+            max_per_second: 1000,
+            jitter: Duration::ZERO,
+            retry_backoff: Duration::ZERO,
+            ..BroadcastConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_report_and_removal() {
+        let sink = Arc::new(ScriptedSink::default());
+        *sink.errors.lock().unwrap() = vec![
+            (2, RequestError::Api(ApiError::BotBlocked)),
+            (3, RequestError::RetryAfter(Seconds::from_seconds(0))),
+            (4, RequestError::Api(ApiError::MessageTextIsEmpty)),
+        ];
+
+        let store: Arc<dyn SessionStore> = Arc::new(MemorySessionStore::new());
+        let subscribers = Subscribers::new().with_store(store.clone()).await.unwrap();
+        for id in 1..=4 {
+            subscribers.add(id).await.unwrap();
+        }
+
+        let alert = Alert::new(AlertLevel::Info, "maintenance at 02:00");
+        let broadcaster = Broadcaster::new(sink.clone()).with_config(config());
+        let report = broadcaster.broadcast_to(&subscribers, alert).await.unwrap();
+        assert_eq!(report.sent, vec![1, 3]);
+        assert_eq!(report.blocked, vec![2]);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].0, 4);
+        assert!(!report.is_complete());
+        assert_eq!(*sink.sent.lock().unwrap(), vec![1, 3]);
+
+        let restored = Subscribers::new().with_store(store).await.unwrap();
+        assert_eq!(restored.chats(), vec![1, 3, 4]);
+    }
+
+    #[test]
+    fn test_jitter_bounded() {
+        assert_eq!(jitter(Duration::ZERO), Duration::ZERO);
+        for _ in 0..100 {
+            assert!(jitter(Duration::from_millis(50)) < Duration::from_millis(50));
+        }
+    }
+}
@@ -0,0 +1,148 @@
+//! Typed callback data
+//!
+//! [`CallbackData`] types encode to compact `prefix:tag:field:...` strings for
+//! inline buttons and parse back in handlers registered with
+//! [`Bot::on_callback_data`](crate::Bot::on_callback_data). Define them with
+//! the [`callback_data!`](crate::callback_data) macro:
+//!
+//! ```rust,ignore
+//! callback_data! {
+//!     #[derive(Clone, Debug, PartialEq)]
+//!     pub enum PositionAction("pos") {
+//!         Close("close") { id: u64 },
+//!         Resize("resize") { id: u64, pct: f64 },
+//!         Refresh("refresh"),
+//!     }
+//! }
+//!
+//! let data = PositionAction::Resize { id: 7, pct: 50.0 }.encode(); // "pos:resize:7:50"
+//! ```
+//!
+//! Field values are written with `Display` and read back with `FromStr`;
+//! `:` and `%` in values are escaped.
+
+/// Telegram's limit on callback data, in bytes
+pub const MAX_CALLBACK_DATA_LEN: usize = 64;
+
+/// Value carried in an inline button's callback data
+pub trait CallbackData: Sized {
+    /// Leading segment identifying the type, without the `:`
+    const PREFIX: &'static str;
+
+    /// Encode as callback data
+    fn encode(&self) -> String;
+
+    /// Parse callback data; `None` if it is not a valid value of this type
+    fn decode(data: &str) -> Option<Self>;
+}
+
+/// Escape a field value so it contains no `:`
+pub fn escape(value: &str) -> String {
+    value.replace('%', "%25").replace(':', "%3A")
+}
+
+/// Reverse [`escape`]
+pub fn unescape(value: &str) -> String {
+    value.replace("%3A", ":").replace("%25", "%")
+}
+
+/// Define an enum implementing [`CallbackData`]
+///
+/// Each variant has a short tag and optionally named fields, which must
+/// implement `Display` and `FromStr`.
+#[macro_export]
+macro_rules! callback_data {
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident($prefix:literal) {
+            $(
+                $variant:ident($tag:literal) $({ $($field:ident: $ty:ty),* $(,)? })?
+            ),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis enum $name {
+            $($variant $({ $($field: $ty),* })?),*
+        }
+
+        impl $crate::callback::CallbackData for $name {
+            const PREFIX: &'static str = $prefix;
+
+            fn encode(&self) -> String {
+                match self {
+                    $(
+                        #[allow(unused_mut)]
+                        Self::$variant $({ $($field),* })? => {
+                            let mut data = format!("{}:{}", $prefix, $tag);
+                            $($(
+                                data.push(':');
+                                data.push_str(&$crate::callback::escape(&$field.to_string()));
+                            )*)?
+                            debug_assert!(
+                                data.len() <= $crate::callback::MAX_CALLBACK_DATA_LEN,
+                                "callback data too long: {}",
+                                data
+                            );
+                            data
+                        }
+                    )*
+                }
+            }
+
+            fn decode(data: &str) -> Option<Self> {
+                let mut parts = data.strip_prefix($prefix)?.strip_prefix(':')?.split(':');
+                let value = match parts.next()? {
+                    $(
+                        $tag => Self::$variant $({ $(
+                            $field: $crate::callback::unescape(parts.next()?).parse::<$ty>().ok()?
+                        ),* })?,
+                    )*
+                    _ => return None,
+                };
+                match parts.next() {
+                    Some(_) => None,
+                    None => Some(value),
+                }
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    callback_data! {
+        #[derive(Clone, Debug, PartialEq)]
+        enum Action("act") {
+            Close("close") { id: u64 },
+            Note("note") { id: u64, text: String },
+            Refresh("refresh"),
+        }
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let cases = [
+            (Action::Close { id: 7 }, "act:close:7"),
+            (Action::Refresh, "act:refresh"),
+            (
+                Action::Note { id: 1, text: "tp: 50%".to_string() },
+                "act:note:1:tp%3A 50%25",
+            ),
+        ];
+        for (action, data) in cases {
+            assert_eq!(action.encode(), data);
+            assert_eq!(Action::decode(data), Some(action));
+        }
+    }
+
+    #[test]
+    fn test_rejects_malformed() {
+        assert_eq!(Action::decode("act:close"), None);
+        assert_eq!(Action::decode("act:close:x"), None);
+        assert_eq!(Action::decode("act:close:7:8"), None);
+        assert_eq!(Action::decode("act:open:7"), None);
+        assert_eq!(Action::decode("actx:refresh"), None);
+    }
+}
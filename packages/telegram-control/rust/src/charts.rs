@@ -0,0 +1,211 @@
+//! Chart rendering (requires the `charts` feature)
+//!
+//! Renders candlestick, equity-curve and PnL charts to PNG bytes for
+//! [`Context::reply_photo`](crate::Context::reply_photo) or
+//! [`AlertBuilder::chart`](crate::alerts::AlertBuilder::chart).
+//!
+//! No font is bundled. Call [`register_font`] once at startup with a TTF/OTF
+//! font to get axis labels; without one, charts are drawn without text.
+
+use chrono::{DateTime, Utc};
+use plotters::coord::types::RangedCoordf64;
+use plotters::coord::Shift;
+use plotters::prelude::*;
+use std::io::Cursor;
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::error::{Error, Result};
+
+/// Chart size in pixels
+pub const WIDTH: u32 = 800;
+pub const HEIGHT: u32 = 480;
+
+const FONT_FAMILY: &str = "sans-serif";
+
+static FONT_REGISTERED: AtomicBool = AtomicBool::new(false);
+
+/// Use `font` (TTF or OTF data) for axis labels
+pub fn register_font(font: &'static [u8]) -> Result<()> {
+    plotters::style::register_font(FONT_FAMILY, FontStyle::Normal, font)
+        .map_err(|_| Error::Chart("Invalid font data".to_string()))?;
+    FONT_REGISTERED.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+/// One OHLC bar
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Candle {
+    pub time: DateTime<Utc>,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+}
+
+/// Candlestick chart of `candles`, oldest first
+pub fn candles(candles: &[Candle]) -> Result<Vec<u8>> {
+    let (first, last) = match (candles.first(), candles.last()) {
+        (Some(first), Some(last)) => (first.time, last.time),
+        _ => return Err(Error::Chart("No candles to plot".to_string())),
+    };
+    let y = padded_range(candles.iter().flat_map(|c| [c.low, c.high]));
+    let bar_width = ((WIDTH - 80) / candles.len() as u32).saturating_sub(2).clamp(1, 15);
+
+    render(|root| {
+        let mut chart = cartesian(root, time_range(first, last), y)?;
+        chart
+            .draw_series(candles.iter().map(|c| {
+                CandleStick::new(c.time, c.open, c.high, c.low, c.close, GREEN.filled(), RED.filled(), bar_width)
+            }))
+            .map_err(chart_error)?;
+        Ok(())
+    })
+}
+
+/// Line chart of account equity over time
+pub fn equity_curve(points: &[(DateTime<Utc>, f64)]) -> Result<Vec<u8>> {
+    let (first, last) = time_bounds(points)?;
+    let y = padded_range(points.iter().map(|(_, v)| *v));
+
+    render(|root| {
+        let mut chart = cartesian(root, time_range(first, last), y)?;
+        chart
+            .draw_series(LineSeries::new(points.iter().copied(), BLUE.stroke_width(2)))
+            .map_err(chart_error)?;
+        Ok(())
+    })
+}
+
+/// Bar chart of PnL per period; gains green, losses red
+pub fn pnl_bars(points: &[(DateTime<Utc>, f64)]) -> Result<Vec<u8>> {
+    let (first, last) = time_bounds(points)?;
+    let y = padded_range(points.iter().map(|(_, v)| *v).chain([0.0]));
+    let half_bar = (last - first) / (points.len() as i32 * 3).max(1);
+
+    render(|root| {
+        let x = time_range(first - half_bar, last + half_bar);
+        let mut chart = cartesian(root, x, y)?;
+        chart
+            .draw_series(points.iter().map(|(t, v)| {
+                let color = if *v >= 0.0 { GREEN } else { RED };
+                Rectangle::new([(*t - half_bar, 0.0), (*t + half_bar, *v)], color.filled())
+            }))
+            .map_err(chart_error)?;
+        Ok(())
+    })
+}
+
+type Root<'a> = DrawingArea<BitMapBackend<'a>, Shift>;
+type Chart<'a, 'b> = ChartContext<'a, BitMapBackend<'b>, Cartesian2d<RangedDateTime<DateTime<Utc>>, RangedCoordf64>>;
+
+/// Draw onto a white canvas and encode it as PNG
+fn render<F>(draw: F) -> Result<Vec<u8>>
+where
+    F: FnOnce(&Root<'_>) -> Result<()>,
+{
+    let mut pixels = vec![0u8; (WIDTH * HEIGHT * 3) as usize];
+    {
+        let root = BitMapBackend::with_buffer(&mut pixels, (WIDTH, HEIGHT)).into_drawing_area();
+        root.fill(&WHITE).map_err(chart_error)?;
+        draw(&root)?;
+        root.present().map_err(chart_error)?;
+    }
+
+    let image = image::RgbImage::from_raw(WIDTH, HEIGHT, pixels)
+        .ok_or_else(|| Error::Chart("Pixel buffer has the wrong size".to_string()))?;
+    let mut png = Cursor::new(Vec::new());
+    image
+        .write_to(&mut png, image::ImageOutputFormat::Png)
+        .map_err(chart_error)?;
+    Ok(png.into_inner())
+}
+
+/// Time/value axes with a light grid, labelled if a font is registered
+fn cartesian<'a, 'b>(
+    root: &'a Root<'b>,
+    x: Range<DateTime<Utc>>,
+    y: Range<f64>,
+) -> Result<Chart<'a, 'b>> {
+    let labels = FONT_REGISTERED.load(Ordering::Relaxed);
+    let mut builder = ChartBuilder::on(root);
+    builder.margin(10);
+    if labels {
+        builder.x_label_area_size(30).y_label_area_size(70);
+    }
+    let mut chart = builder.build_cartesian_2d(x, y).map_err(chart_error)?;
+
+    // Without label areas the mesh draws grid lines only
+    let mut mesh = chart.configure_mesh();
+    mesh.x_labels(6)
+        .y_labels(8)
+        .light_line_style(WHITE.mix(0.0))
+        .bold_line_style(BLACK.mix(0.1))
+        .label_style((FONT_FAMILY, 14));
+    mesh.draw().map_err(chart_error)?;
+    Ok(chart)
+}
+
+fn time_bounds(points: &[(DateTime<Utc>, f64)]) -> Result<(DateTime<Utc>, DateTime<Utc>)> {
+    match (points.first(), points.last()) {
+        (Some(first), Some(last)) => Ok((first.0, last.0)),
+        _ => Err(Error::Chart("No points to plot".to_string())),
+    }
+}
+
+/// Time axis range, widened to a minute if every point shares a timestamp
+fn time_range(first: DateTime<Utc>, last: DateTime<Utc>) -> Range<DateTime<Utc>> {
+    if last > first {
+        first..last
+    } else {
+        first..first + chrono::Duration::minutes(1)
+    }
+}
+
+/// Value range with 5% headroom above and below
+fn padded_range(values: impl Iterator<Item = f64>) -> Range<f64> {
+    let (lo, hi) = values.fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| (lo.min(v), hi.max(v)));
+    if !lo.is_finite() || !hi.is_finite() {
+        return 0.0..1.0;
+    }
+    let pad = ((hi - lo) * 0.05).max(hi.abs() * 0.001).max(1e-9);
+    lo - pad..hi + pad
+}
+
+fn chart_error(e: impl std::fmt::Display) -> Error {
+    Error::Chart(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    const PNG_MAGIC: &[u8] = b"\x89PNG";
+
+    #[test]
+    fn test_render_png() {
+        let start = Utc::now();
+        let equity: Vec<_> = (0..24)
+            .map(|h| (start + Duration::hours(h), 10_000.0 + (h as f64 * 0.7).sin() * 250.0))
+            .collect();
+        assert!(equity_curve(&equity).unwrap().starts_with(PNG_MAGIC));
+
+        let pnl: Vec<_> = equity.iter().map(|(t, v)| (*t, v - 10_000.0)).collect();
+        assert!(pnl_bars(&pnl).unwrap().starts_with(PNG_MAGIC));
+
+        let bars: Vec<_> = equity
+            .windows(2)
+            .map(|w| Candle {
+                time: w[1].0,
+                open: w[0].1,
+                close: w[1].1,
+                high: w[0].1.max(w[1].1) + 20.0,
+                low: w[0].1.min(w[1].1) - 20.0,
+            })
+            .collect();
+        assert!(candles(&bars).unwrap().starts_with(PNG_MAGIC));
+
+        assert!(candles(&[]).is_err());
+    }
+}
@@ -22,6 +22,22 @@ pub trait CommandHandler<C: Command> {
 }
 
 /// Macro for defining commands
+///
+/// Arguments are split with [`tokenize`](crate::args::tokenize), so quoted
+/// arguments may contain spaces. Use [`CommandSpec`](crate::args::CommandSpec)
+/// for optional arguments and flags.
+///
+/// ```rust,ignore
+/// use telegram_control::commands;
+///
+/// commands! {
+///     MyCommands {
+///         Start => "/start": "Start the bot",
+///         Status => "/status": "Get system status",
+///         Trade(amount: f64, symbol: String) => "/trade": "Execute a trade",
+///     }
+/// }
+/// ```
 #[macro_export]
 macro_rules! commands {
     (
@@ -38,28 +54,33 @@ macro_rules! commands {
         
         impl $crate::commands::Command for $name {
             fn parse(input: &str) -> $crate::error::Result<Self> {
-                let parts: Vec<&str> = input.split_whitespace().collect();
-                let cmd = parts.first().ok_or_else(|| {
+                let tokens = $crate::args::tokenize(input)?;
+                let cmd = tokens.first().ok_or_else(|| {
                     $crate::error::Error::InvalidCommand("Empty command".to_string())
                 })?;
                 
-                match *cmd {
+                match cmd.as_str() {
                     $(
                         $cmd => {
-                            let mut idx = 1;
-                            Ok(Self::$variant$(($(
-                                parts.get(idx)
+                            let usage = concat!("Usage: ", $cmd $($(, " <", stringify!($arg), ">")*)?);
+                            #[allow(unused_mut, unused_variables)]
+                            let mut args = tokens.iter().skip(1);
+                            let parsed = Self::$variant$(($(
+                                args.next()
                                     .ok_or_else(|| $crate::error::Error::InvalidCommand(
-                                        format!("Missing argument: {}", stringify!($arg))
+                                        format!("Missing argument: {}\n{}", stringify!($arg), usage)
                                     ))?
                                     .parse::<$arg_type>()
                                     .map_err(|e| $crate::error::Error::InvalidCommand(
-                                        format!("Invalid {}: {}", stringify!($arg), e)
+                                        format!("Invalid {}: {}\n{}", stringify!($arg), e, usage)
                                     ))?
-                            )$($({
-                                idx += 1;
-                                idx - 1
-                            }))*))?)
+                            ),*))?;
+                            if let Some(extra) = args.next() {
+                                return Err($crate::error::Error::InvalidCommand(
+                                    format!("Unexpected argument: {}\n{}", extra, usage)
+                                ));
+                            }
+                            Ok(parsed)
                         }
                     ),*
                     _ => Err($crate::error::Error::InvalidCommand(cmd.to_string())),
@@ -68,13 +89,13 @@ macro_rules! commands {
             
             fn name(&self) -> &'static str {
                 match self {
-                    $(Self::$variant$(($(_,)*))?) => $cmd),*
+                    $(Self::$variant { .. } => $cmd),*
                 }
             }
             
             fn description(&self) -> &'static str {
                 match self {
-                    $(Self::$variant$(($(_,)*))?) => $desc),*
+                    $(Self::$variant { .. } => $desc),*
                 }
             }
         }
@@ -91,15 +112,32 @@ macro_rules! commands {
     };
 }
 
-/// Example usage:
-/// ```rust
-/// use telegram_control::commands;
-///
-/// commands! {
-///     MyCommands {
-///         Start => "/start": "Start the bot",
-///         Status => "/status": "Get system status",
-///         Trade(amount: f64, symbol: String) => "/trade": "Execute a trade",
-///     }
-/// }
-/// ```
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    commands! {
+        TestCommands {
+            Status => "/status": "Get system status",
+            Trade(symbol: String, amount: f64) => "/trade": "Execute a trade",
+        }
+    }
+
+    #[test]
+    fn test_multi_argument_command() {
+        match TestCommands::parse("/trade \"BTC USD\" 0.5").unwrap() {
+            TestCommands::Trade(symbol, amount) => {
+                assert_eq!(symbol, "BTC USD");
+                assert_eq!(amount, 0.5);
+            }
+            other => panic!("parsed as {:?}", other),
+        }
+        assert_eq!(TestCommands::parse("/status").unwrap().name(), "/status");
+
+        let err = TestCommands::parse("/trade BTC").unwrap_err().to_string();
+        assert!(err.contains("Missing argument: amount"));
+        assert!(err.contains("Usage: /trade <symbol> <amount>"));
+        assert!(TestCommands::parse("/status now").is_err());
+        assert!(TestCommands::help().contains("/trade - Execute a trade"));
+    }
+}
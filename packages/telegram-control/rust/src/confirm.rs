@@ -0,0 +1,342 @@
+//! Confirmation for dangerous commands
+//!
+//! Commands registered with
+//! [`Bot::on_dangerous_command`](crate::Bot::on_dangerous_command) do not run
+//! straight away. The bot replies with an inline Yes/No keyboard, optionally
+//! asks for a PIN after "Yes", and only then runs the handler with the original
+//! command context. Unanswered requests expire.
+//!
+//! Only a salted hash of the PIN is kept, and the user's PIN reply is
+//! deleted once read so other members of a group can't see it. The bot needs
+//! admin rights in groups to delete it.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use sha2::{Digest, Sha256};
+use teloxide::prelude::*;
+use tracing::{info, warn};
+
+use crate::bot::HandlerFn;
+use crate::error::Result;
+use crate::keyboards::InlineKeyboardBuilder;
+use crate::types::Context;
+
+/// Callback data prefix for confirmation buttons
+pub const CALLBACK_PREFIX: &str = "confirm:";
+
+crate::callback_data! {
+    /// Yes/No button for pending request `id`
+    enum Answer("confirm") {
+        Yes("yes") { id: u64 },
+        No("no") { id: u64 },
+    }
+}
+
+/// Salted SHA-256 of a PIN
+#[derive(Clone, Debug)]
+struct PinHash {
+    salt: [u8; 16],
+    digest: [u8; 32],
+}
+
+impl PinHash {
+    fn new(pin: &str) -> Self {
+        let salt = rand::random();
+        Self {
+            salt,
+            digest: Self::digest(&salt, pin),
+        }
+    }
+
+    fn digest(salt: &[u8], pin: &str) -> [u8; 32] {
+        Sha256::new().chain_update(salt).chain_update(pin.as_bytes()).finalize().into()
+    }
+
+    /// Compare `attempt` in constant time
+    fn matches(&self, attempt: &str) -> bool {
+        let attempt = Self::digest(&self.salt, attempt.trim());
+        self.digest.iter().zip(attempt).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+    }
+}
+
+/// Confirmation settings for a dangerous command
+#[derive(Clone, Debug)]
+pub struct Confirmation {
+    pin: Option<PinHash>,
+    ttl: Duration,
+}
+
+impl Default for Confirmation {
+    fn default() -> Self {
+        Self {
+            pin: None,
+            ttl: Duration::from_secs(60),
+        }
+    }
+}
+
+impl Confirmation {
+    /// Yes/No confirmation that expires after 60 seconds
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also require this PIN after "Yes"
+    pub fn with_pin(mut self, pin: impl Into<String>) -> Self {
+        self.pin = Some(PinHash::new(&pin.into()));
+        self
+    }
+
+    /// How long the request stays answerable
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+}
+
+struct Pending {
+    ctx: Context,
+    handler: HandlerFn,
+    pin: Option<PinHash>,
+    awaiting_pin: bool,
+    expires_at: Instant,
+}
+
+/// Outcome of a button press
+enum Step {
+    Run(Box<Pending>),
+    AskPin,
+    Cancelled,
+    Expired,
+    NotOwner,
+}
+
+/// Outcome of a PIN reply
+enum PinStep {
+    Run(Box<Pending>),
+    Wrong,
+    Expired,
+    /// The chat is not waiting for this user's PIN
+    NotWaiting,
+}
+
+/// Pending confirmations, shared between the command, callback and PIN
+/// handlers
+#[derive(Clone, Default)]
+pub struct Confirmations {
+    pending: Arc<Mutex<HashMap<u64, Pending>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl Confirmations {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of unexpired requests
+    pub fn pending(&self) -> usize {
+        let now = Instant::now();
+        self.pending
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|p| p.expires_at > now)
+            .count()
+    }
+
+    /// Hold a command until the user confirms it
+    pub(crate) async fn request(&self, ctx: Context, handler: HandlerFn, confirmation: &Confirmation) -> Result<()> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let prompt = format!(
+            "⚠️ Confirm {}?\nExpires in {}s.",
+            ctx.text().unwrap_or("this action"),
+            confirmation.ttl.as_secs()
+        );
+        let chat_id = ctx.chat_id();
+        let bot = ctx.bot().clone();
+        self.insert(
+            id,
+            Pending {
+                ctx,
+                handler,
+                pin: confirmation.pin.clone(),
+                awaiting_pin: false,
+                expires_at: Instant::now() + confirmation.ttl,
+            },
+        );
+        bot.send_message(ChatId(chat_id), prompt)
+            .reply_markup(
+                InlineKeyboardBuilder::new()
+                    .data_button("✅ Yes", &Answer::Yes { id })
+                    .data_button("❌ No", &Answer::No { id })
+                    .build(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    fn insert(&self, id: u64, pending: Pending) {
+        let mut all = self.pending.lock().unwrap();
+        let now = Instant::now();
+        all.retain(|_, p| p.expires_at > now);
+        all.insert(id, pending);
+    }
+
+    /// Answer request `id` for `user_id`: Yes if `confirm`, otherwise No
+    fn answer(&self, id: u64, user_id: i64, confirm: bool) -> Step {
+        let mut pending = self.pending.lock().unwrap();
+        match pending.get_mut(&id) {
+            None => Step::Expired,
+            Some(p) if p.ctx.user_id() != user_id => Step::NotOwner,
+            Some(p) if p.expires_at <= Instant::now() => {
+                pending.remove(&id);
+                Step::Expired
+            }
+            Some(p) if confirm && p.pin.is_some() => {
+                p.awaiting_pin = true;
+                Step::AskPin
+            }
+            Some(_) if confirm => pending.remove(&id).map_or(Step::Expired, |p| Step::Run(Box::new(p))),
+            Some(_) => {
+                pending.remove(&id);
+                Step::Cancelled
+            }
+        }
+    }
+
+    /// Check `attempt` against the PIN `user_id` was asked for in `chat_id`
+    ///
+    /// The request is closed either way.
+    fn verify_pin(&self, chat_id: i64, user_id: i64, attempt: Option<&str>) -> PinStep {
+        let mut pending = self.pending.lock().unwrap();
+        let id = pending
+            .iter()
+            .find(|(_, p)| p.awaiting_pin && p.ctx.chat_id() == chat_id && p.ctx.user_id() == user_id)
+            .map(|(id, _)| *id);
+        let Some(p) = id.and_then(|id| pending.remove(&id)) else {
+            return PinStep::NotWaiting;
+        };
+        if p.expires_at <= Instant::now() {
+            PinStep::Expired
+        } else if p.pin.as_ref().zip(attempt).is_some_and(|(pin, attempt)| pin.matches(attempt)) {
+            PinStep::Run(Box::new(p))
+        } else {
+            PinStep::Wrong
+        }
+    }
+
+    /// Handle a Yes/No button press
+    pub(crate) async fn handle_callback(&self, ctx: Context) -> Result<()> {
+        let (id, confirm) = match ctx.callback_data::<Answer>() {
+            Some(Answer::Yes { id }) => (id, true),
+            Some(Answer::No { id }) => (id, false),
+            None => return Ok(()),
+        };
+
+        let step = self.answer(id, ctx.user_id(), confirm);
+        match step {
+            Step::Run(p) => {
+                info!("User {} confirmed {:?}", ctx.user_id(), p.ctx.text());
+                (p.handler)(p.ctx).await
+            }
+            Step::AskPin => ctx.reply("🔐 Enter PIN to confirm.").await,
+            Step::Cancelled => ctx.reply("Cancelled.").await,
+            Step::Expired => ctx.reply("⌛ Confirmation expired.").await,
+            Step::NotOwner => {
+                warn!("User {} tried to answer another user's confirmation", ctx.user_id());
+                Ok(())
+            }
+        }
+    }
+
+    /// Handle a PIN reply; resolves to `false` if the chat is not waiting for one
+    pub(crate) async fn handle_pin(&self, ctx: Context) -> Result<bool> {
+        let step = self.verify_pin(ctx.chat_id(), ctx.user_id(), ctx.text());
+        if matches!(step, PinStep::NotWaiting) {
+            return Ok(false);
+        }
+        if let Err(e) = ctx.delete().await {
+            warn!("Failed to delete PIN message from user {}: {}", ctx.user_id(), e);
+        }
+
+        match step {
+            PinStep::Run(p) => {
+                info!("User {} confirmed {:?} with PIN", ctx.user_id(), p.ctx.text());
+                (p.handler)(p.ctx).await?;
+            }
+            PinStep::Wrong => {
+                warn!("Wrong PIN from user {}", ctx.user_id());
+                ctx.reply("❌ Wrong PIN, cancelled.").await?;
+            }
+            PinStep::Expired => ctx.reply("⌛ Confirmation expired.").await?,
+            PinStep::NotWaiting => {}
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::test_context;
+
+    fn pending(pin: Option<&str>, ttl: Duration) -> Pending {
+        let handler: HandlerFn = Arc::new(|_| Box::pin(async { Ok(()) }));
+        Pending {
+            ctx: test_context(7, "/withdraw all", None),
+            handler,
+            pin: pin.map(PinHash::new),
+            awaiting_pin: false,
+            expires_at: Instant::now() + ttl,
+        }
+    }
+
+    #[test]
+    fn test_answer() {
+        let confirmations = Confirmations::new();
+        confirmations.insert(0, pending(None, Duration::from_secs(60)));
+        confirmations.insert(1, pending(None, Duration::from_secs(60)));
+        assert_eq!(confirmations.pending(), 2);
+
+        assert!(matches!(confirmations.answer(0, 8, true), Step::NotOwner));
+        assert!(matches!(confirmations.answer(0, 7, true), Step::Run(p) if p.ctx.user_id() == 7));
+        assert!(matches!(confirmations.answer(0, 7, true), Step::Expired));
+        assert!(matches!(confirmations.answer(1, 7, false), Step::Cancelled));
+
+        confirmations.insert(2, pending(None, Duration::ZERO));
+        assert!(matches!(confirmations.answer(2, 7, true), Step::Expired));
+        assert_eq!(confirmations.pending(), 0);
+    }
+
+    #[test]
+    fn test_pin() {
+        let confirmations = Confirmations::new();
+        confirmations.insert(0, pending(Some("4821"), Duration::from_secs(60)));
+        assert!(matches!(confirmations.verify_pin(7, 7, Some("4821")), PinStep::NotWaiting));
+
+        assert!(matches!(confirmations.answer(0, 7, true), Step::AskPin));
+        assert!(matches!(confirmations.verify_pin(8, 8, Some("4821")), PinStep::NotWaiting));
+        assert!(matches!(confirmations.verify_pin(7, 7, Some(" 4821\n")), PinStep::Run(_)));
+
+        confirmations.insert(1, pending(Some("4821"), Duration::from_secs(60)));
+        confirmations.answer(1, 7, true);
+        assert!(matches!(confirmations.verify_pin(7, 7, Some("1234")), PinStep::Wrong));
+        assert!(matches!(confirmations.verify_pin(7, 7, Some("4821")), PinStep::NotWaiting));
+
+        confirmations.insert(2, pending(Some("4821"), Duration::from_millis(20)));
+        confirmations.answer(2, 7, true);
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(matches!(confirmations.verify_pin(7, 7, Some("4821")), PinStep::Expired));
+    }
+
+    #[test]
+    fn test_pin_hash() {
+        let a = PinHash::new("4821");
+        let b = PinHash::new("4821");
+        assert_ne!(a.digest, b.digest);
+        assert!(a.matches("4821") && b.matches("4821"));
+        assert!(!a.matches("48210"));
+    }
+}
@@ -0,0 +1,131 @@
+//! Per-chat dialogues
+//!
+//! Multi-step flows ("enter market → enter size → confirm") keep their current
+//! step as a typed state per chat in a [`DialogueStorage`]. Plain messages from
+//! a chat with an active dialogue are routed to the handler registered with
+//! [`Bot::on_dialogue`](crate::Bot::on_dialogue); commands still take
+//! precedence so `/cancel`-style commands keep working mid-flow.
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::error::Result;
+
+/// Storage for per-chat dialogue state
+#[async_trait]
+pub trait DialogueStorage<S>: Send + Sync {
+    /// Current state for a chat, if a dialogue is active
+    async fn get(&self, chat_id: i64) -> Result<Option<S>>;
+
+    /// Set the state for a chat
+    async fn set(&self, chat_id: i64, state: S) -> Result<()>;
+
+    /// End the dialogue for a chat
+    async fn remove(&self, chat_id: i64) -> Result<()>;
+}
+
+/// In-memory dialogue storage; state is lost on restart
+#[derive(Debug)]
+pub struct InMemStorage<S> {
+    states: Mutex<HashMap<i64, S>>,
+}
+
+impl<S> InMemStorage<S> {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            states: Mutex::new(HashMap::new()),
+        })
+    }
+}
+
+#[async_trait]
+impl<S: Clone + Send + Sync> DialogueStorage<S> for InMemStorage<S> {
+    async fn get(&self, chat_id: i64) -> Result<Option<S>> {
+        Ok(self.states.lock().await.get(&chat_id).cloned())
+    }
+
+    async fn set(&self, chat_id: i64, state: S) -> Result<()> {
+        self.states.lock().await.insert(chat_id, state);
+        Ok(())
+    }
+
+    async fn remove(&self, chat_id: i64) -> Result<()> {
+        self.states.lock().await.remove(&chat_id);
+        Ok(())
+    }
+}
+
+/// Handle to one chat's dialogue
+pub struct Dialogue<S> {
+    storage: Arc<dyn DialogueStorage<S>>,
+    chat_id: i64,
+}
+
+impl<S> Clone for Dialogue<S> {
+    fn clone(&self) -> Self {
+        Self {
+            storage: self.storage.clone(),
+            chat_id: self.chat_id,
+        }
+    }
+}
+
+impl<S> Dialogue<S> {
+    pub fn new(storage: Arc<dyn DialogueStorage<S>>, chat_id: i64) -> Self {
+        Self { storage, chat_id }
+    }
+
+    pub fn chat_id(&self) -> i64 {
+        self.chat_id
+    }
+
+    /// Current state, if the dialogue is active
+    pub async fn get(&self) -> Result<Option<S>> {
+        self.storage.get(self.chat_id).await
+    }
+
+    /// Start the dialogue or move it to the next state
+    pub async fn update(&self, state: S) -> Result<()> {
+        self.storage.set(self.chat_id, state).await
+    }
+
+    /// End the dialogue
+    pub async fn exit(&self) -> Result<()> {
+        self.storage.remove(self.chat_id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Debug, PartialEq)]
+    enum TradeFlow {
+        EnterMarket,
+        EnterSize { market: String },
+    }
+
+    #[tokio::test]
+    async fn test_dialogue_transitions() {
+        let storage = InMemStorage::<TradeFlow>::new();
+        let dialogue = Dialogue::new(storage.clone(), 42);
+        let other = Dialogue::new(storage, 7);
+
+        assert_eq!(dialogue.get().await.unwrap(), None);
+        dialogue.update(TradeFlow::EnterMarket).await.unwrap();
+        dialogue
+            .update(TradeFlow::EnterSize { market: "BTC-100K".to_string() })
+            .await
+            .unwrap();
+        assert_eq!(
+            dialogue.get().await.unwrap(),
+            Some(TradeFlow::EnterSize { market: "BTC-100K".to_string() })
+        );
+        assert_eq!(other.get().await.unwrap(), None);
+
+        dialogue.exit().await.unwrap();
+        assert_eq!(dialogue.get().await.unwrap(), None);
+    }
+}
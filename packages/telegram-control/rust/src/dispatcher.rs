@@ -0,0 +1,1131 @@
+//! Alert delivery
+//!
+//! [`AlertDispatcher`] queues alerts and sends them from a background task,
+//! pacing messages to stay under Telegram's limits (about 30 messages per
+//! second overall and one per second per chat) and retrying on flood-control
+//! and network errors. Each [`AlertLevel`] can be routed to its own chats,
+//! e.g. info to a log channel and critical alerts to admin DMs with mentions.
+//! Non-critical alerts with a dedup key are throttled to one per window;
+//! repeats in between are counted and summarized on the next alert that goes
+//! out.
+//!
+//! Each [`Priority`] has its own queue and higher ones are drained first, so
+//! a critical alert goes out ahead of a backlog of fills.
+//!
+//! In forum supergroups alerts can go to a topic, either for a whole route
+//! ([`Route::in_topic`]) or per alert with
+//! [`AlertDispatcher::send_to_topic`], e.g. one topic per strategy.
+//!
+//! Chats with [`QuietHours`] get non-critical alerts as a digest once the
+//! quiet period ends.
+//!
+//! With an [`AlertHistory`] attached, sent alerts are recorded and critical
+//! ones carry a ✅ Ack button.
+//!
+//! Other subsystems can hand alerts over on a channel instead of holding the
+//! dispatcher:
+//!
+//! ```rust,ignore
+//! let (tx, rx) = tokio::sync::mpsc::channel::<Alert>(100);
+//! let dispatcher = AlertDispatcher::from_receiver(sink, config, rx);
+//! risk_engine.on_breach(move |msg| { let _ = tx.try_send(Alert::new(AlertLevel::Critical, msg)); });
+//! ```
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use teloxide::prelude::*;
+use teloxide::types::{InlineKeyboardMarkup, InputFile, MessageId, ReplyMarkup, ThreadId};
+use teloxide::RequestError;
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tracing::{error, warn};
+
+use crate::alerts::{AlertBuilder, AlertLevel, Attachment};
+use crate::error::{Error, Result};
+use crate::format::{self, ParseMode};
+use crate::history::{self, AlertHistory, AlertRecord};
+use crate::metrics;
+use crate::quiet::{self, Held, QuietHours};
+
+/// Longest caption Telegram accepts on a photo or document
+const CAPTION_LIMIT: usize = 1024;
+
+/// A formatted alert ready to send
+#[derive(Clone, Debug, PartialEq)]
+pub struct Alert {
+    pub level: AlertLevel,
+    pub text: String,
+    /// Alerts sharing a key are throttled together, e.g. `price:BTC-USD`;
+    /// critical alerts are never throttled
+    pub dedup_key: Option<String>,
+    /// Throttle window for this key; the dispatcher default if `None`
+    pub dedup_window: Option<Duration>,
+    /// PNG sent with `text` as its caption
+    pub photo: Option<Vec<u8>>,
+    /// Document sent with the alert
+    pub document: Option<Attachment>,
+    /// How `text` is formatted; text added by the dispatcher is escaped to match
+    pub parse_mode: ParseMode,
+    /// Forum topic (`message_thread_id`) to post in
+    pub topic: Option<i32>,
+    /// Buttons on the message carrying `text`
+    pub keyboard: Option<InlineKeyboardMarkup>,
+}
+
+impl Alert {
+    pub fn new(level: AlertLevel, text: impl Into<String>) -> Self {
+        Self {
+            level,
+            text: text.into(),
+            dedup_key: None,
+            dedup_window: None,
+            photo: None,
+            document: None,
+            parse_mode: ParseMode::Plain,
+            topic: None,
+            keyboard: None,
+        }
+    }
+
+    /// Post in forum topic `thread_id`
+    pub fn in_topic(mut self, thread_id: i32) -> Self {
+        self.topic = Some(thread_id);
+        self
+    }
+
+    /// Throttle this alert with others sharing `key`
+    pub fn dedup(mut self, key: impl Into<String>) -> Self {
+        self.dedup_key = Some(key.into());
+        self
+    }
+
+    /// Send at most one alert with this key per `window`
+    pub fn throttle(mut self, key: impl Into<String>, window: Duration) -> Self {
+        self.dedup_key = Some(key.into());
+        self.dedup_window = Some(window);
+        self
+    }
+
+    /// Send the alert as a caption on this PNG
+    pub fn with_photo(mut self, png: Vec<u8>) -> Self {
+        self.photo = Some(png);
+        self
+    }
+
+    /// Alert whose text is already formatted for `mode`
+    pub fn formatted(level: AlertLevel, text: impl Into<String>, mode: ParseMode) -> Self {
+        Self {
+            parse_mode: mode,
+            ..Self::new(level, text)
+        }
+    }
+
+    /// Send `document` along with the alert
+    pub fn with_document(mut self, document: Attachment) -> Self {
+        self.document = Some(document);
+        self
+    }
+
+    /// Attach buttons to the alert
+    pub fn with_keyboard(mut self, keyboard: InlineKeyboardMarkup) -> Self {
+        self.keyboard = Some(keyboard);
+        self
+    }
+}
+
+impl From<AlertBuilder> for Alert {
+    fn from(builder: AlertBuilder) -> Self {
+        let level = builder.level();
+        let dedup_key = builder.dedup_key().map(str::to_string);
+        let dedup_window = builder.dedup_window();
+        let photo = builder.chart_image().map(<[u8]>::to_vec);
+        let document = builder.attached_file().cloned();
+        let parse_mode = builder.mode();
+        let text = match parse_mode {
+            ParseMode::Plain => builder.build(),
+            mode => builder.render(mode),
+        };
+        Self {
+            dedup_key,
+            dedup_window,
+            photo,
+            document,
+            parse_mode,
+            ..Self::new(level, text)
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct KeyState {
+    last_sent: std::time::Instant,
+    suppressed: u32,
+}
+
+/// Per-key throttle windows for alerts
+#[derive(Debug)]
+pub struct AlertThrottle {
+    default_window: Duration,
+    keys: std::sync::Mutex<HashMap<String, KeyState>>,
+}
+
+impl AlertThrottle {
+    pub fn new(default_window: Duration) -> Self {
+        Self {
+            default_window,
+            keys: std::sync::Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Pass an alert through the throttle
+    ///
+    /// Returns `None` if an alert with the same key went out within the
+    /// window, unless it is critical. Otherwise returns the alert, annotated
+    /// with how many repeats were held back since the last one.
+    pub fn admit(&self, alert: Alert) -> Option<Alert> {
+        self.admit_at(alert, std::time::Instant::now())
+    }
+
+    fn admit_at(&self, mut alert: Alert, now: std::time::Instant) -> Option<Alert> {
+        if alert.level == AlertLevel::Critical {
+            return Some(alert);
+        }
+        let Some(key) = alert.dedup_key.clone() else {
+            return Some(alert);
+        };
+        let window = alert.dedup_window.unwrap_or(self.default_window);
+        let mut keys = self.keys.lock().unwrap();
+        match keys.get_mut(&key) {
+            Some(state) if now.duration_since(state.last_sent) < window => {
+                state.suppressed += 1;
+                None
+            }
+            Some(state) => {
+                if state.suppressed > 0 {
+                    let elapsed = now.duration_since(state.last_sent);
+                    let summary = format!("×{} in last {}", state.suppressed + 1, format_window(elapsed));
+                    alert.text = format!("{}\n\n{}", alert.text, format::escape(&summary, alert.parse_mode));
+                }
+                *state = KeyState { last_sent: now, suppressed: 0 };
+                Some(alert)
+            }
+            None => {
+                keys.insert(key, KeyState { last_sent: now, suppressed: 0 });
+                Some(alert)
+            }
+        }
+    }
+}
+
+/// Compact duration like `45s`, `5m` or `1h 30m`
+pub(crate) fn format_window(d: Duration) -> String {
+    let secs = d.as_secs();
+    match (secs / 3600, (secs % 3600) / 60) {
+        (0, 0) => format!("{}s", secs),
+        (0, m) => format!("{}m", m),
+        (h, 0) => format!("{}h", h),
+        (h, m) => format!("{}h {}m", h, m),
+    }
+}
+
+/// Destination that alerts are delivered to
+#[async_trait]
+pub trait AlertSink: Send + Sync {
+    async fn send_alert(&self, chat_id: i64, alert: &Alert) -> Result<()>;
+}
+
+#[async_trait]
+impl AlertSink for teloxide::Bot {
+    async fn send_alert(&self, chat_id: i64, alert: &Alert) -> Result<()> {
+        let chat = ChatId(chat_id);
+        // The text rides as the caption of the first file if it fits;
+        // otherwise it is sent as its own message. Buttons go with the text.
+        let mode = alert.parse_mode.telegram();
+        let markup = alert.keyboard.clone().map(ReplyMarkup::InlineKeyboard);
+        let thread = alert.topic.map(|id| ThreadId(MessageId(id)));
+        let mut caption = (alert.text.chars().count() <= CAPTION_LIMIT).then(|| alert.text.clone());
+        let text_needed = caption.is_none();
+
+        if let Some(photo) = &alert.photo {
+            let mut request = self.send_photo(chat, InputFile::memory(photo.clone()).file_name("chart.png"));
+            request.message_thread_id = thread;
+            if let Some(caption) = caption.take() {
+                request.caption = Some(caption);
+                request.parse_mode = mode;
+                request.reply_markup = markup.clone();
+            }
+            request.await?;
+        }
+        if let Some(document) = &alert.document {
+            let file = InputFile::memory(document.bytes.clone()).file_name(document.filename.clone());
+            let mut request = self.send_document(chat, file);
+            request.message_thread_id = thread;
+            if let Some(caption) = caption.take() {
+                request.caption = Some(caption);
+                request.parse_mode = mode;
+                request.reply_markup = markup.clone();
+            }
+            request.await?;
+        }
+        if text_needed || caption.is_some() {
+            let mut request = self.send_message(chat, alert.text.clone());
+            request.parse_mode = mode;
+            request.message_thread_id = thread;
+            request.reply_markup = markup;
+            request.await?;
+        }
+        Ok(())
+    }
+}
+
+/// Channel that other subsystems send alerts on
+///
+/// Implemented for tokio `mpsc` and `broadcast` receivers of anything that
+/// converts into an [`Alert`].
+#[async_trait]
+pub trait AlertSource: Send + 'static {
+    /// Next alert; `None` once every sender is gone
+    async fn next_alert(&mut self) -> Option<Alert>;
+}
+
+#[async_trait]
+impl<T: Into<Alert> + Send + 'static> AlertSource for mpsc::Receiver<T> {
+    async fn next_alert(&mut self) -> Option<Alert> {
+        self.recv().await.map(Into::into)
+    }
+}
+
+#[async_trait]
+impl<T: Into<Alert> + Send + 'static> AlertSource for mpsc::UnboundedReceiver<T> {
+    async fn next_alert(&mut self) -> Option<Alert> {
+        self.recv().await.map(Into::into)
+    }
+}
+
+#[async_trait]
+impl<T: Into<Alert> + Clone + Send + 'static> AlertSource for broadcast::Receiver<T> {
+    async fn next_alert(&mut self) -> Option<Alert> {
+        loop {
+            match self.recv().await {
+                Ok(alert) => return Some(alert.into()),
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    warn!("Alert channel lagged, {} alerts dropped", missed);
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+/// Where alerts of one level are sent
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Route {
+    pub chats: Vec<i64>,
+    /// Usernames (without `@`) mentioned at the end of the alert
+    pub mentions: Vec<String>,
+    /// Forum topic to post in, unless the alert sets its own
+    pub topic: Option<i32>,
+}
+
+impl Route {
+    pub fn new(chats: Vec<i64>) -> Self {
+        Self {
+            chats,
+            mentions: Vec::new(),
+            topic: None,
+        }
+    }
+
+    pub fn with_mentions(mut self, usernames: Vec<String>) -> Self {
+        self.mentions = usernames;
+        self
+    }
+
+    /// Post in forum topic `thread_id` of the route's chats
+    pub fn in_topic(mut self, thread_id: i32) -> Self {
+        self.topic = Some(thread_id);
+        self
+    }
+
+    /// Alert text with mentions appended, escaped for `mode`
+    fn render(&self, text: &str, mode: ParseMode) -> String {
+        if self.mentions.is_empty() {
+            return text.to_string();
+        }
+        let mentions: Vec<String> = self
+            .mentions
+            .iter()
+            .map(|u| format::escape(&format!("@{}", u), mode))
+            .collect();
+        format!("{}\n\n{}", text, mentions.join(" "))
+    }
+}
+
+/// Alert dispatcher settings
+#[derive(Clone, Debug)]
+pub struct DispatcherConfig {
+    /// Chats for alerts whose level has no route
+    pub chats: Vec<i64>,
+    /// Per-level destinations, overriding `chats`
+    pub routes: HashMap<AlertLevel, Route>,
+    /// Messages per second across all chats
+    pub max_per_second: u32,
+    /// Minimum gap between messages to the same chat
+    pub per_chat_interval: Duration,
+    /// Retries after the first attempt for retryable errors
+    pub max_retries: u32,
+    /// Initial backoff for network errors, doubled on each retry
+    pub retry_backoff: Duration,
+    /// Alerts of each priority that can be queued before `send` waits for room
+    pub queue_size: usize,
+    /// Throttle window for keyed alerts that do not set their own
+    pub dedup_window: Duration,
+    /// Per-chat quiet hours
+    pub quiet_hours: HashMap<i64, QuietHours>,
+}
+
+impl Default for DispatcherConfig {
+    fn default() -> Self {
+        Self {
+            chats: Vec::new(),
+            routes: HashMap::new(),
+            max_per_second: 30,
+            per_chat_interval: Duration::from_secs(1),
+            max_retries: 3,
+            retry_backoff: Duration::from_secs(1),
+            queue_size: 1000,
+            dedup_window: Duration::from_secs(300),
+            quiet_hours: HashMap::new(),
+        }
+    }
+}
+
+impl DispatcherConfig {
+    pub fn new(chats: Vec<i64>) -> Self {
+        Self {
+            chats,
+            ..Default::default()
+        }
+    }
+
+    /// Send alerts of `level` to `route` instead of the default chats
+    pub fn with_route(mut self, level: AlertLevel, route: Route) -> Self {
+        self.routes.insert(level, route);
+        self
+    }
+
+    /// Hold non-critical alerts for `chat_id` during `hours`
+    pub fn with_quiet_hours(mut self, chat_id: i64, hours: QuietHours) -> Self {
+        self.quiet_hours.insert(chat_id, hours);
+        self
+    }
+}
+
+/// Default chats and per-level routes, changeable while running
+#[derive(Clone, Debug, Default)]
+struct Routing {
+    chats: Vec<i64>,
+    routes: HashMap<AlertLevel, Route>,
+}
+
+impl Routing {
+    fn route_for(&self, level: AlertLevel) -> Route {
+        self.routes
+            .get(&level)
+            .cloned()
+            .unwrap_or_else(|| Route::new(self.chats.clone()))
+    }
+}
+
+/// Delivery order of queued alerts
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    Low,
+    Normal,
+    High,
+}
+
+impl Priority {
+    /// Info and Success are low, Warning and Error normal, Critical high
+    pub fn of(level: AlertLevel) -> Self {
+        match level {
+            AlertLevel::Info | AlertLevel::Success => Priority::Low,
+            AlertLevel::Warning | AlertLevel::Error => Priority::Normal,
+            AlertLevel::Critical => Priority::High,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Outgoing {
+    chat_id: i64,
+    alert: Alert,
+    queued_at: Instant,
+}
+
+impl Outgoing {
+    fn new(chat_id: i64, alert: Alert) -> Self {
+        Self {
+            chat_id,
+            alert,
+            queued_at: Instant::now(),
+        }
+    }
+}
+
+/// Sending ends of the per-priority queues
+#[derive(Clone)]
+struct Lanes {
+    high: mpsc::Sender<Outgoing>,
+    normal: mpsc::Sender<Outgoing>,
+    low: mpsc::Sender<Outgoing>,
+}
+
+/// Receiving ends of the per-priority queues
+struct LaneReceivers {
+    high: mpsc::Receiver<Outgoing>,
+    normal: mpsc::Receiver<Outgoing>,
+    low: mpsc::Receiver<Outgoing>,
+}
+
+fn lanes(size: usize) -> (Lanes, LaneReceivers) {
+    let (high, high_rx) = mpsc::channel(size);
+    let (normal, normal_rx) = mpsc::channel(size);
+    let (low, low_rx) = mpsc::channel(size);
+    (
+        Lanes { high, normal, low },
+        LaneReceivers {
+            high: high_rx,
+            normal: normal_rx,
+            low: low_rx,
+        },
+    )
+}
+
+impl Lanes {
+    fn lane(&self, priority: Priority) -> &mpsc::Sender<Outgoing> {
+        match priority {
+            Priority::High => &self.high,
+            Priority::Normal => &self.normal,
+            Priority::Low => &self.low,
+        }
+    }
+
+    fn queued(&self, priority: Priority) -> usize {
+        let tx = self.lane(priority);
+        tx.max_capacity() - tx.capacity()
+    }
+}
+
+impl LaneReceivers {
+    /// Next alert from the highest non-empty lane; `None` once all are closed
+    async fn recv(&mut self) -> Option<Outgoing> {
+        let outgoing = tokio::select! {
+            biased;
+            Some(outgoing) = self.high.recv() => outgoing,
+            Some(outgoing) = self.normal.recv() => outgoing,
+            Some(outgoing) = self.low.recv() => outgoing,
+            else => return None,
+        };
+        metrics::alert_queue_depth(Priority::of(outgoing.alert.level), -1);
+        Some(outgoing)
+    }
+}
+
+/// Queued, rate-limited alert sender
+pub struct AlertDispatcher {
+    /// `None` once closed
+    tx: RwLock<Option<Lanes>>,
+    routing: RwLock<Routing>,
+    throttle: AlertThrottle,
+    quiet_hours: Arc<RwLock<HashMap<i64, QuietHours>>>,
+    history: Option<Arc<dyn AlertHistory>>,
+    worker: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl AlertDispatcher {
+    /// Start the delivery task
+    pub fn spawn(sink: Arc<dyn AlertSink>, config: DispatcherConfig) -> Self {
+        let (tx, rx) = lanes(config.queue_size.max(1));
+        let routing = RwLock::new(Routing {
+            chats: config.chats.clone(),
+            routes: config.routes.clone(),
+        });
+        let throttle = AlertThrottle::new(config.dedup_window);
+        let quiet_hours = Arc::new(RwLock::new(config.quiet_hours.clone()));
+        let worker = tokio::spawn(deliver_all(sink, rx, config, quiet_hours.clone()));
+        Self {
+            tx: RwLock::new(Some(tx)),
+            routing,
+            throttle,
+            quiet_hours,
+            history: None,
+            worker: Mutex::new(Some(worker)),
+        }
+    }
+
+    /// Start the delivery task and send every alert received on `source`
+    ///
+    /// The dispatcher can still be used directly, e.g. to change routes.
+    /// Call [`close`](Self::close) once the senders are gone to flush it.
+    pub fn from_receiver(sink: Arc<dyn AlertSink>, config: DispatcherConfig, source: impl AlertSource) -> Arc<Self> {
+        let dispatcher = Arc::new(Self::spawn(sink, config));
+        dispatcher.forward(source);
+        dispatcher
+    }
+
+    /// Send every alert received on `source` as if passed to
+    /// [`send`](Self::send), until the channel or the dispatcher closes
+    pub fn forward(self: &Arc<Self>, mut source: impl AlertSource) -> JoinHandle<()> {
+        let dispatcher = Arc::downgrade(self);
+        tokio::spawn(async move {
+            while let Some(alert) = source.next_alert().await {
+                let Some(dispatcher) = dispatcher.upgrade() else {
+                    break;
+                };
+                if let Err(e) = dispatcher.send(alert).await {
+                    warn!("Stopped forwarding alerts: {}", e);
+                    break;
+                }
+            }
+        })
+    }
+
+    /// Record alerts queued with [`send`](Self::send) in `history`, and add
+    /// an Ack button to critical ones
+    pub fn with_history(mut self, history: Arc<dyn AlertHistory>) -> Self {
+        self.history = Some(history);
+        self
+    }
+
+    /// Queue an alert for the chats routed for its level
+    ///
+    /// Keyed alerts inside their throttle window are dropped.
+    pub async fn send(&self, alert: impl Into<Alert>) -> Result<()> {
+        let Some(alert) = self.throttle.admit(alert.into()) else {
+            return Ok(());
+        };
+        let route = self.routing.read().unwrap().route_for(alert.level);
+        let mut keyboard = alert.keyboard;
+        if let Some(history) = &self.history {
+            let record = AlertRecord::new(alert.level, alert.text.clone(), route.chats.clone());
+            match history.record(record).await {
+                Ok(id) if alert.level == AlertLevel::Critical => {
+                    let ack = history::ack_keyboard(id);
+                    keyboard = Some(match keyboard {
+                        Some(keyboard) => keyboard.append_row(ack.inline_keyboard.concat()),
+                        None => ack,
+                    });
+                }
+                Ok(_) => {}
+                Err(e) => warn!("Failed to record alert in history: {}", e),
+            }
+        }
+        let routed = Alert {
+            text: route.render(&alert.text, alert.parse_mode),
+            parse_mode: alert.parse_mode,
+            photo: alert.photo,
+            document: alert.document,
+            topic: alert.topic.or(route.topic),
+            keyboard,
+            ..Alert::new(alert.level, "")
+        };
+        for chat_id in route.chats {
+            self.send_to(chat_id, routed.clone()).await?;
+        }
+        Ok(())
+    }
+
+    /// Route alerts of `level` to `route`
+    pub fn set_route(&self, level: AlertLevel, route: Route) {
+        self.routing.write().unwrap().routes.insert(level, route);
+    }
+
+    /// Send alerts of `level` to the default chats again
+    pub fn clear_route(&self, level: AlertLevel) {
+        self.routing.write().unwrap().routes.remove(&level);
+    }
+
+    /// Replace the chats used for levels without a route
+    pub fn set_default_chats(&self, chats: Vec<i64>) {
+        self.routing.write().unwrap().chats = chats;
+    }
+
+    /// Destination for alerts of `level`
+    pub fn route_for(&self, level: AlertLevel) -> Route {
+        self.routing.read().unwrap().route_for(level)
+    }
+
+    /// Set or clear quiet hours for a chat
+    ///
+    /// Alerts already held are still sent when their quiet period ends.
+    pub fn set_quiet_hours(&self, chat_id: i64, hours: Option<QuietHours>) {
+        let mut quiet_hours = self.quiet_hours.write().unwrap();
+        match hours {
+            Some(hours) => quiet_hours.insert(chat_id, hours),
+            None => quiet_hours.remove(&chat_id),
+        };
+    }
+
+    /// Quiet hours configured for a chat
+    pub fn quiet_hours(&self, chat_id: i64) -> Option<QuietHours> {
+        self.quiet_hours.read().unwrap().get(&chat_id).copied()
+    }
+
+    /// Queue an alert for one chat
+    pub async fn send_to(&self, chat_id: i64, alert: impl Into<Alert>) -> Result<()> {
+        let tx = self.tx.read().unwrap().clone();
+        let Some(tx) = tx else {
+            return Err(Error::Dispatch("Alert dispatcher stopped".to_string()));
+        };
+        let alert = alert.into();
+        let priority = Priority::of(alert.level);
+        tx.lane(priority)
+            .send(Outgoing::new(chat_id, alert))
+            .await
+            .map_err(|_| Error::Dispatch("Alert dispatcher stopped".to_string()))?;
+        metrics::alert_queue_depth(priority, 1);
+        Ok(())
+    }
+
+    /// Queue an alert for forum topic `thread_id` of one chat
+    pub async fn send_to_topic(&self, chat_id: i64, thread_id: i32, alert: impl Into<Alert>) -> Result<()> {
+        self.send_to(chat_id, alert.into().in_topic(thread_id)).await
+    }
+
+    /// Alerts waiting to be sent
+    pub fn queued(&self) -> usize {
+        [Priority::High, Priority::Normal, Priority::Low]
+            .into_iter()
+            .map(|p| self.queued_with(p))
+            .sum()
+    }
+
+    /// Alerts of `priority` waiting to be sent
+    pub fn queued_with(&self, priority: Priority) -> usize {
+        match &*self.tx.read().unwrap() {
+            Some(tx) => tx.queued(priority),
+            None => 0,
+        }
+    }
+
+    /// Stop accepting alerts and wait for the queue to drain
+    ///
+    /// Alerts held for quiet hours are sent right away as digests. Unlike
+    /// [`shutdown`](Self::shutdown) this works through a shared reference, so
+    /// a dispatcher held in an `Arc` can be flushed on exit.
+    pub async fn close(&self) {
+        self.tx.write().unwrap().take();
+        let worker = self.worker.lock().unwrap().take();
+        if let Some(worker) = worker {
+            let _ = worker.await;
+        }
+    }
+
+    /// Stop accepting alerts and wait for the queue to drain
+    pub async fn shutdown(self) {
+        self.close().await;
+    }
+}
+
+async fn deliver_all(
+    sink: Arc<dyn AlertSink>,
+    mut rx: LaneReceivers,
+    config: DispatcherConfig,
+    quiet_hours: Arc<RwLock<HashMap<i64, QuietHours>>>,
+) {
+    let mut pacer = Pacer::new(&config);
+    let mut held: HashMap<i64, Held> = HashMap::new();
+
+    loop {
+        let release_at = held.values().map(|h| h.until).min();
+        let wake = release_at.map_or(Instant::now() + Duration::from_secs(3600), |at| {
+            Instant::now() + (at - chrono::Utc::now()).to_std().unwrap_or(Duration::ZERO)
+        });
+        let outgoing = tokio::select! {
+            next = rx.recv() => match next {
+                Some(outgoing) => outgoing,
+                None => break,
+            },
+            _ = tokio::time::sleep_until(wake), if release_at.is_some() => {
+                let now = chrono::Utc::now();
+                let due: Vec<i64> = held.iter().filter(|(_, h)| h.until <= now).map(|(id, _)| *id).collect();
+                for chat_id in due {
+                    if let Some(h) = held.remove(&chat_id) {
+                        pacer.release(sink.as_ref(), chat_id, h, &config).await;
+                    }
+                }
+                continue;
+            }
+        };
+
+        let hours = quiet_hours.read().unwrap().get(&outgoing.chat_id).copied();
+        if let Some(hours) = hours {
+            let now = chrono::Utc::now();
+            if hours.holds(outgoing.alert.level) && hours.contains(now) {
+                held.entry(outgoing.chat_id)
+                    .or_insert_with(|| Held::new(hours.end_after(now)))
+                    .alerts
+                    .push(outgoing.alert);
+                continue;
+            }
+        }
+        pacer.send(sink.as_ref(), &outgoing, &config).await;
+    }
+
+    for (chat_id, h) in held {
+        pacer.release(sink.as_ref(), chat_id, h, &config).await;
+    }
+}
+
+/// Spaces messages out globally and per chat
+struct Pacer {
+    min_gap: Duration,
+    last_sent: Option<Instant>,
+    last_per_chat: HashMap<i64, Instant>,
+}
+
+impl Pacer {
+    fn new(config: &DispatcherConfig) -> Self {
+        Self {
+            min_gap: Duration::from_secs(1) / config.max_per_second.max(1),
+            last_sent: None,
+            last_per_chat: HashMap::new(),
+        }
+    }
+
+    async fn send(&mut self, sink: &dyn AlertSink, outgoing: &Outgoing, config: &DispatcherConfig) {
+        let mut ready = Instant::now();
+        if let Some(at) = self.last_sent {
+            ready = ready.max(at + self.min_gap);
+        }
+        if let Some(at) = self.last_per_chat.get(&outgoing.chat_id) {
+            ready = ready.max(*at + config.per_chat_interval);
+        }
+        tokio::time::sleep_until(ready).await;
+
+        deliver(sink, outgoing, config).await;
+
+        let now = Instant::now();
+        self.last_sent = Some(now);
+        self.last_per_chat.insert(outgoing.chat_id, now);
+    }
+
+    /// Send alerts held for quiet hours as a digest
+    async fn release(&mut self, sink: &dyn AlertSink, chat_id: i64, held: Held, config: &DispatcherConfig) {
+        for alert in quiet::digest(held.alerts) {
+            self.send(sink, &Outgoing::new(chat_id, alert), config).await;
+        }
+    }
+}
+
+async fn deliver(sink: &dyn AlertSink, outgoing: &Outgoing, config: &DispatcherConfig) {
+    let mut backoff = config.retry_backoff;
+    for attempt in 0..=config.max_retries {
+        let wait = match sink.send_alert(outgoing.chat_id, &outgoing.alert).await {
+            Ok(()) => {
+                metrics::alert_delivered(outgoing.alert.level, "sent");
+                metrics::alert_delivery_latency(outgoing.alert.level, outgoing.queued_at.elapsed());
+                return;
+            }
+            Err(Error::Telegram(RequestError::RetryAfter(secs))) => secs.duration(),
+            Err(Error::Telegram(RequestError::Network(e))) => {
+                warn!("Network error sending alert to {}: {}", outgoing.chat_id, e);
+                let wait = backoff;
+                backoff *= 2;
+                wait
+            }
+            Err(e) => {
+                error!("Failed to send alert to {}: {}", outgoing.chat_id, e);
+                metrics::alert_delivered(outgoing.alert.level, "failed");
+                return;
+            }
+        };
+        if attempt < config.max_retries {
+            tokio::time::sleep(wait).await;
+        }
+    }
+    error!(
+        "Giving up on alert to {} after {} retries",
+        outgoing.chat_id, config.max_retries
+    );
+    metrics::alert_delivered(outgoing.alert.level, "failed");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use teloxide::types::Seconds;
+    use teloxide::ApiError;
+
+    /// Records deliveries, failing with queued errors first
+    #[derive(Default)]
+    struct RecordingSink {
+        failures: Mutex<Vec<RequestError>>,
+        sent: Mutex<Vec<(i64, String)>>,
+    }
+
+    #[async_trait]
+    impl AlertSink for RecordingSink {
+        async fn send_alert(&self, chat_id: i64, alert: &Alert) -> Result<()> {
+            if let Some(e) = self.failures.lock().unwrap().pop() {
+                return Err(e.into());
+            }
+            self.sent.lock().unwrap().push((chat_id, alert.text.clone()));
+            Ok(())
+        }
+    }
+
+    fn config(chats: Vec<i64>) -> DispatcherConfig {
+        DispatcherConfig {
+            per_chat_interval: Duration::ZERO,
+            retry_backoff: Duration::ZERO,
+            ..DispatcherConfig::new(chats)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fans_out_and_retries_flood_control() {
+        let sink = Arc::new(RecordingSink::default());
+        sink.failures
+            .lock()
+            .unwrap()
+            .push(RequestError::RetryAfter(Seconds::from_seconds(0)));
+
+        let dispatcher = AlertDispatcher::spawn(sink.clone(), config(vec![1, 2]));
+        dispatcher.send(Alert::new(AlertLevel::Info, "hello")).await.unwrap();
+        dispatcher.shutdown().await;
+
+        let sent = sink.sent.lock().unwrap().clone();
+        assert_eq!(sent, vec![(1, "hello".to_string()), (2, "hello".to_string())]);
+    }
+
+    #[tokio::test]
+    async fn test_telegram_sends_attachments() {
+        let telegram = crate::testing::MockTelegram::start().await.unwrap();
+        let bot = teloxide::Bot::new("123:test").set_api_url(telegram.url().parse().unwrap());
+        let export = Attachment::new("trades.csv", b"id,side\n1,buy\n".to_vec());
+        let built = Alert::from(AlertBuilder::info("Daily export").attachment("trades.csv", export.bytes.clone()));
+        assert_eq!(built.document.as_ref(), Some(&export));
+
+        let alert = Alert::new(AlertLevel::Info, "Daily export").with_document(export.clone());
+        bot.send_alert(7, &alert).await.unwrap();
+        let calls = telegram.take_calls();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].method, "sendDocument");
+        assert_eq!(calls[0].text(), Some("Daily export"));
+        assert_eq!(calls[0].files(), vec![("trades.csv".to_string(), 14)]);
+
+        // The chart takes the caption; the document goes bare
+        let alert = alert.with_photo(vec![0x89, b'P', b'N', b'G']);
+        bot.send_alert(7, &alert).await.unwrap();
+        let calls = telegram.take_calls();
+        let methods: Vec<&str> = calls.iter().map(|c| c.method.as_str()).collect();
+        assert_eq!(methods, ["sendPhoto", "sendDocument"]);
+        assert_eq!(calls[0].text(), Some("Daily export"));
+        assert_eq!(calls[0].files(), vec![("chart.png".to_string(), 4)]);
+        assert_eq!(calls[1].text(), None);
+
+        // Text too long for a caption follows as a message
+        let long = "x".repeat(CAPTION_LIMIT + 1);
+        bot.send_alert(7, &Alert::new(AlertLevel::Info, long.clone()).with_document(export)).await.unwrap();
+        let calls = telegram.take_calls();
+        let methods: Vec<&str> = calls.iter().map(|c| c.method.as_str()).collect();
+        assert_eq!(methods, ["sendDocument", "sendMessage"]);
+        assert_eq!(calls[0].text(), None);
+        assert_eq!(calls[1].text(), Some(long.as_str()));
+    }
+
+    #[tokio::test]
+    async fn test_does_not_retry_api_errors() {
+        let sink = Arc::new(RecordingSink::default());
+        sink.failures
+            .lock()
+            .unwrap()
+            .push(RequestError::Api(ApiError::Unknown("chat not found".to_string())));
+
+        let dispatcher = AlertDispatcher::spawn(sink.clone(), config(vec![]));
+        dispatcher.send_to(7, AlertBuilder::warning("first")).await.unwrap();
+        dispatcher.send_to(7, AlertBuilder::warning("second")).await.unwrap();
+        dispatcher.shutdown().await;
+
+        let sent = sink.sent.lock().unwrap().clone();
+        assert_eq!(sent.len(), 1);
+        assert!(sent[0].1.contains("second"));
+    }
+
+    #[tokio::test]
+    async fn test_topics() {
+        #[derive(Default)]
+        struct TopicSink(Mutex<Vec<(i64, Option<i32>)>>);
+
+        #[async_trait]
+        impl AlertSink for TopicSink {
+            async fn send_alert(&self, chat_id: i64, alert: &Alert) -> Result<()> {
+                self.0.lock().unwrap().push((chat_id, alert.topic));
+                Ok(())
+            }
+        }
+
+        let sink = Arc::new(TopicSink::default());
+        let dispatcher = AlertDispatcher::spawn(
+            sink.clone(),
+            config(vec![-100]).with_route(AlertLevel::Critical, Route::new(vec![-100]).in_topic(3)),
+        );
+        dispatcher.send(Alert::new(AlertLevel::Info, "general")).await.unwrap();
+        dispatcher.send(Alert::new(AlertLevel::Critical, "halt")).await.unwrap();
+        dispatcher.send(Alert::new(AlertLevel::Critical, "binance down").in_topic(7)).await.unwrap();
+        dispatcher.send_to_topic(-100, 9, Alert::new(AlertLevel::Info, "fill")).await.unwrap();
+        dispatcher.shutdown().await;
+
+        assert_eq!(
+            *sink.0.lock().unwrap(),
+            vec![(-100, Some(3)), (-100, Some(7)), (-100, None), (-100, Some(9))]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_quiet_hours_digest() {
+        let now = chrono::Utc::now();
+        let hour = chrono::Duration::hours(1);
+        let quiet = QuietHours::new((now - hour).time(), (now + hour).time());
+
+        let sink = Arc::new(RecordingSink::default());
+        let dispatcher = AlertDispatcher::spawn(sink.clone(), config(vec![1, 2]).with_quiet_hours(1, quiet));
+        assert_eq!(dispatcher.quiet_hours(1), Some(quiet));
+        dispatcher.send(Alert::new(AlertLevel::Info, "fill")).await.unwrap();
+        dispatcher.send(Alert::new(AlertLevel::Warning, "funding")).await.unwrap();
+        dispatcher.send(Alert::new(AlertLevel::Critical, "liquidation")).await.unwrap();
+        dispatcher.shutdown().await;
+
+        let sent = sink.sent.lock().unwrap().clone();
+        let to = |chat: i64| -> Vec<String> {
+            sent.iter().filter(|(c, _)| *c == chat).map(|(_, t)| t.clone()).collect()
+        };
+        assert_eq!(to(2), vec!["liquidation", "funding", "fill"]);
+        assert_eq!(
+            to(1),
+            vec!["liquidation", "🌙 2 alerts during quiet hours\n\nfunding\n\nfill"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_close_drains_queue() {
+        let sink = Arc::new(RecordingSink::default());
+        let dispatcher = Arc::new(AlertDispatcher::spawn(sink.clone(), config(vec![1])));
+        for i in 0..3 {
+            dispatcher.send(Alert::new(AlertLevel::Info, format!("alert {}", i))).await.unwrap();
+        }
+        dispatcher.close().await;
+
+        assert_eq!(sink.sent.lock().unwrap().len(), 3);
+        assert_eq!(dispatcher.queued(), 0);
+        assert!(dispatcher.send(Alert::new(AlertLevel::Info, "late")).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_critical_alerts_go_first() {
+        let sink = Arc::new(RecordingSink::default());
+        let dispatcher = AlertDispatcher::spawn(sink.clone(), config(vec![1]));
+        // Nothing is delivered until the test yields, so all four are queued
+        for i in 0..2 {
+            dispatcher.send(Alert::new(AlertLevel::Info, format!("fill {}", i))).await.unwrap();
+        }
+        dispatcher.send(Alert::new(AlertLevel::Warning, "funding")).await.unwrap();
+        dispatcher.send(Alert::new(AlertLevel::Critical, "liquidation")).await.unwrap();
+        assert_eq!(dispatcher.queued(), 4);
+        assert_eq!(dispatcher.queued_with(Priority::Low), 2);
+        dispatcher.shutdown().await;
+
+        let texts: Vec<String> = sink.sent.lock().unwrap().iter().map(|(_, t)| t.clone()).collect();
+        assert_eq!(texts, vec!["liquidation", "funding", "fill 0", "fill 1"]);
+    }
+
+    #[tokio::test]
+    async fn test_forwards_from_channels() {
+        let sink = Arc::new(RecordingSink::default());
+        let (tx, rx) = mpsc::channel(8);
+        let dispatcher = AlertDispatcher::from_receiver(sink.clone(), config(vec![1]), rx);
+        let (btx, brx) = broadcast::channel(8);
+        let forwarding = dispatcher.forward(brx);
+
+        tx.send(Alert::new(AlertLevel::Warning, "drawdown 5%")).await.unwrap();
+        btx.send(AlertBuilder::info("rpc failover")).unwrap();
+        drop(btx);
+        forwarding.await.unwrap();
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while sink.sent.lock().unwrap().len() < 2 {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+        })
+        .await
+        .unwrap();
+        dispatcher.close().await;
+
+        let mut texts: Vec<String> = sink.sent.lock().unwrap().iter().map(|(_, t)| t.clone()).collect();
+        texts.sort();
+        assert_eq!(texts.len(), 2);
+        assert_eq!(texts[0], "drawdown 5%");
+        assert!(texts[1].contains("rpc failover"));
+    }
+
+    #[test]
+    fn test_throttle_skips_critical() {
+        let throttle = AlertThrottle::new(Duration::from_secs(300));
+        let alert = || Alert::new(AlertLevel::Critical, "kill switch").dedup("risk");
+        assert!(throttle.admit(alert()).is_some());
+        assert!(throttle.admit(alert()).is_some());
+    }
+
+    #[test]
+    fn test_throttle_collapses_repeats() {
+        let throttle = AlertThrottle::new(Duration::from_secs(300));
+        let start = std::time::Instant::now();
+        let alert = || Alert::new(AlertLevel::Warning, "BTC moved").dedup("price:BTC");
+
+        assert!(throttle.admit_at(alert(), start).is_some());
+        for i in 1..=11 {
+            assert!(throttle.admit_at(alert(), start + Duration::from_secs(i * 10)).is_none());
+        }
+        // Unkeyed alerts are never throttled
+        assert!(throttle
+            .admit_at(Alert::new(AlertLevel::Info, "x"), start + Duration::from_secs(1))
+            .is_some());
+
+        let summary = throttle.admit_at(alert(), start + Duration::from_secs(3600)).unwrap();
+        assert_eq!(summary.text, "BTC moved\n\n×12 in last 1h");
+    }
+
+    #[tokio::test]
+    async fn test_routes_by_level() {
+        let sink = Arc::new(RecordingSink::default());
+        let dispatcher = AlertDispatcher::spawn(
+            sink.clone(),
+            config(vec![100]).with_route(
+                AlertLevel::Critical,
+                Route::new(vec![1, 200]).with_mentions(vec!["alice".to_string()]),
+            ),
+        );
+
+        dispatcher.send(Alert::new(AlertLevel::Info, "fyi")).await.unwrap();
+        dispatcher.send(Alert::new(AlertLevel::Critical, "liquidation")).await.unwrap();
+        dispatcher.set_route(AlertLevel::Info, Route::new(vec![300]));
+        dispatcher.send(Alert::new(AlertLevel::Info, "moved")).await.unwrap();
+        dispatcher.shutdown().await;
+
+        let sent = sink.sent.lock().unwrap().clone();
+        assert_eq!(
+            sent,
+            vec![
+                (1, "liquidation\n\n@alice".to_string()),
+                (200, "liquidation\n\n@alice".to_string()),
+                (100, "fyi".to_string()),
+                (300, "moved".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,163 @@
+//! Incoming documents
+//!
+//! Handlers registered with [`Bot::on_document`](crate::Bot::on_document)
+//! receive files users send to the bot, such as watchlists or config CSVs.
+//! The file is checked against [`DocumentLimits`] before it is downloaded;
+//! files that are too large or of the wrong type are refused with a reply.
+//!
+//! ```rust,ignore
+//! let limits = DocumentLimits::new().max_size(256 * 1024).extensions(&["csv", "txt"]);
+//! let bot = bot.on_document(limits, |ctx, upload| async move {
+//!     let symbols: Vec<&str> = upload.text()?.lines().map(str::trim).collect();
+//!     ctx.reply(format!("Watching {} symbols", symbols.len())).await
+//! });
+//! ```
+
+use teloxide::net::Download;
+use teloxide::prelude::*;
+use teloxide::types::Document;
+
+use crate::error::{Error, Result};
+
+/// Which files a document handler accepts
+#[derive(Clone, Debug)]
+pub struct DocumentLimits {
+    max_size: u32,
+    /// Lowercase extensions without the dot; empty accepts any
+    extensions: Vec<String>,
+}
+
+impl Default for DocumentLimits {
+    fn default() -> Self {
+        Self {
+            max_size: 1024 * 1024,
+            extensions: Vec::new(),
+        }
+    }
+}
+
+impl DocumentLimits {
+    /// Files up to 1 MB of any type
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Largest accepted file, in bytes; Telegram lets bots download up to 20 MB
+    pub fn max_size(mut self, bytes: u32) -> Self {
+        self.max_size = bytes;
+        self
+    }
+
+    /// Accept only files with these extensions, e.g. `["csv", "txt"]`
+    pub fn extensions(mut self, extensions: &[&str]) -> Self {
+        self.extensions = extensions
+            .iter()
+            .map(|e| e.trim_start_matches('.').to_lowercase())
+            .collect();
+        self
+    }
+
+    /// Refuse `document` if it breaks a limit, with a reason for the user
+    pub fn check(&self, document: &Document) -> Result<()> {
+        let name = document.file_name.as_deref().unwrap_or_default();
+        if !self.extensions.is_empty() {
+            let extension = name.rsplit_once('.').map(|(_, ext)| ext.to_lowercase());
+            if !extension.is_some_and(|ext| self.extensions.contains(&ext)) {
+                let allowed: Vec<String> = self.extensions.iter().map(|e| format!(".{}", e)).collect();
+                return Err(Error::InvalidCommand(format!(
+                    "Only {} files are accepted",
+                    allowed.join(", ")
+                )));
+            }
+        }
+        if document.file.size > self.max_size {
+            return Err(Error::InvalidCommand(format!(
+                "{} is {}, the limit is {}",
+                if name.is_empty() { "The file" } else { name },
+                format_size(document.file.size),
+                format_size(self.max_size)
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// A downloaded document
+#[derive(Clone, Debug)]
+pub struct Upload {
+    pub file_name: Option<String>,
+    pub mime_type: Option<String>,
+    pub bytes: Vec<u8>,
+}
+
+impl Upload {
+    /// Contents as UTF-8 text
+    pub fn text(&self) -> Result<&str> {
+        std::str::from_utf8(&self.bytes)
+            .map_err(|_| Error::InvalidCommand("The file is not UTF-8 text".to_string()))
+    }
+}
+
+/// Check `document` against `limits` and download it
+pub async fn download(bot: &teloxide::Bot, document: &Document, limits: &DocumentLimits) -> Result<Upload> {
+    limits.check(document)?;
+    let file = bot.get_file(document.file.id.clone()).await?;
+    let mut bytes = Vec::with_capacity(document.file.size as usize);
+    bot.download_file(&file.path, &mut bytes).await?;
+    Ok(Upload {
+        file_name: document.file_name.clone(),
+        mime_type: document.mime_type.as_ref().map(|m| m.to_string()),
+        bytes,
+    })
+}
+
+/// Size like `512 B`, `20 KB` or `1.5 MB`
+fn format_size(bytes: u32) -> String {
+    match bytes {
+        b if b < 1024 => format!("{} B", b),
+        b if b < 1024 * 1024 => format!("{} KB", b / 1024),
+        b => format!("{:.1} MB", b as f64 / (1024.0 * 1024.0)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn document(name: &str, size: u32) -> Document {
+        serde_json::from_value(json!({
+            "file_id": "f1",
+            "file_unique_id": "u1",
+            "file_size": size,
+            "file_name": name,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_limits() {
+        let limits = DocumentLimits::new().max_size(2048).extensions(&[".CSV", "txt"]);
+        assert!(limits.check(&document("watchlist.csv", 100)).is_ok());
+        assert!(limits.check(&document("Notes.TXT", 2048)).is_ok());
+
+        let err = limits.check(&document("setup.exe", 100)).unwrap_err();
+        assert!(err.to_string().contains("Only .csv, .txt files are accepted"));
+        let err = limits.check(&document("big.csv", 3 * 1024 * 1024)).unwrap_err();
+        assert!(err.to_string().contains("big.csv is 3.0 MB, the limit is 2 KB"));
+
+        assert!(DocumentLimits::new().check(&document("anything", 10)).is_ok());
+    }
+
+    #[test]
+    fn test_upload_text() {
+        let upload = Upload {
+            file_name: None,
+            mime_type: None,
+            bytes: b"BTC\nETH\n".to_vec(),
+        };
+        assert_eq!(upload.text().unwrap().lines().count(), 2);
+        let binary = Upload { bytes: vec![0xff, 0xfe], ..upload };
+        assert!(binary.text().is_err());
+    }
+}
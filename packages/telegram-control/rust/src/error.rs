@@ -7,21 +7,50 @@ pub enum Error {
     #[error("Telegram API error: {0}")]
     Telegram(#[from] teloxide::RequestError),
     
+    #[error("Download failed: {0}")]
+    Download(#[from] teloxide::DownloadError),
+    
     #[error("Authentication failed: {0}")]
     Auth(String),
     
     #[error("User not authorized: {0}")]
     Unauthorized(i64),
     
+    #[error("Chat not authorized: {0}")]
+    ChatUnauthorized(i64),
+    
+    #[error("User {0} requires role: {1}")]
+    Forbidden(i64, crate::auth::Role),
+    
     #[error("Invalid command: {0}")]
     InvalidCommand(String),
     
     #[error("Configuration error: {0}")]
     Config(String),
     
+    #[error("Dispatch error: {0}")]
+    Dispatch(String),
+    
+    #[error("Template error: {0}")]
+    Template(String),
+    
+    #[error("Handler panicked: {0}")]
+    Panic(String),
+    
+    #[error("Handler timed out after {0:?}")]
+    Timeout(std::time::Duration),
+    
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
     
     #[error("Serialization error: {0}")]
     Serialization(#[from] serde_json::Error),
+    
+    #[cfg(feature = "sqlite")]
+    #[error("Storage error: {0}")]
+    Storage(#[from] rusqlite::Error),
+    
+    #[cfg(feature = "charts")]
+    #[error("Chart error: {0}")]
+    Chart(String),
 }
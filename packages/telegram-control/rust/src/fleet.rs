@@ -0,0 +1,173 @@
+//! Several bots in one process
+//!
+//! A [`BotFleet`] runs bots with different tokens side by side, e.g. an
+//! alerts bot and a control bot. Bots built with [`BotFleet::bot`] share the
+//! fleet's [`AccessControl`], so a user added through one bot's `/adduser` is
+//! authorized on all of them. The fleet's [`SessionStore`] is flushed once
+//! after every bot has stopped.
+//!
+//! ```rust,ignore
+//! let fleet = BotFleet::new(access_control).with_session_store(store);
+//! let control = fleet.bot(control_token).build().on_command("/status", status);
+//! let alerts = fleet.bot(alerts_token).build();
+//! let dispatcher = Arc::new(alerts.alert_dispatcher(config));
+//!
+//! fleet
+//!     .add("control", control)
+//!     .add("alerts", alerts.with_alert_dispatcher(dispatcher))
+//!     .run()
+//!     .await?;
+//! ```
+
+use std::future::Future;
+use std::sync::Arc;
+use tracing::{error, info};
+
+use crate::auth::AccessControl;
+use crate::bot::{Bot, BotBuilder};
+use crate::error::Result;
+use crate::session::{MemorySessionStore, SessionStore};
+use crate::shutdown::BotHandle;
+
+/// Bots sharing access control and storage
+pub struct BotFleet {
+    access_control: AccessControl,
+    store: Arc<dyn SessionStore>,
+    bots: Vec<(String, Bot)>,
+}
+
+impl BotFleet {
+    pub fn new(access_control: AccessControl) -> Self {
+        Self {
+            access_control,
+            store: Arc::new(MemorySessionStore::new()),
+            bots: Vec::new(),
+        }
+    }
+
+    /// Share `store` between the fleet's bots (default: in memory)
+    pub fn with_session_store(mut self, store: Arc<dyn SessionStore>) -> Self {
+        self.store = store;
+        self
+    }
+
+    /// Shared access control
+    pub fn access_control(&self) -> &AccessControl {
+        &self.access_control
+    }
+
+    /// Shared session store, for schedulers, dialogues and handlers
+    pub fn store(&self) -> Arc<dyn SessionStore> {
+        self.store.clone()
+    }
+
+    /// Builder for a bot using the fleet's access control
+    pub fn bot(&self, token: impl Into<String>) -> BotBuilder {
+        Bot::new(token).with_access_control(self.access_control.clone())
+    }
+
+    /// Add a bot; `name` identifies it in logs
+    pub fn add(mut self, name: impl Into<String>, bot: Bot) -> Self {
+        self.bots.push((name.into(), bot));
+        self
+    }
+
+    /// Start every bot in the background
+    pub async fn start(self) -> FleetHandle {
+        let mut handles = Vec::with_capacity(self.bots.len());
+        for (name, bot) in self.bots {
+            info!("Starting bot '{}'", name);
+            handles.push((name, bot.start().await));
+        }
+        FleetHandle {
+            handles,
+            store: self.store,
+        }
+    }
+
+    /// Run every bot until Ctrl-C, then shut them all down (blocking)
+    pub async fn run(self) -> Result<()> {
+        self.run_with_shutdown(async {
+            if let Err(e) = tokio::signal::ctrl_c().await {
+                error!("Failed to listen for Ctrl-C: {}", e);
+            }
+        })
+        .await
+    }
+
+    /// Run every bot until `signal` resolves, then shut them all down (blocking)
+    pub async fn run_with_shutdown(self, signal: impl Future<Output = ()>) -> Result<()> {
+        let handle = self.start().await;
+        signal.await;
+        handle.shutdown().await
+    }
+}
+
+/// Bots of a [`BotFleet`] running in the background
+pub struct FleetHandle {
+    handles: Vec<(String, BotHandle)>,
+    store: Arc<dyn SessionStore>,
+}
+
+impl FleetHandle {
+    /// Names of the bots, in the order they were added
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.handles.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Whether the named bot is still receiving updates
+    pub fn is_running(&self, name: &str) -> bool {
+        self.handles.iter().any(|(n, handle)| n == name && handle.is_running())
+    }
+
+    /// Shut every bot down concurrently, then flush the shared store
+    ///
+    /// Every bot is stopped even if some fail; the first error is returned.
+    pub async fn shutdown(self) -> Result<()> {
+        let tasks: Vec<_> = self
+            .handles
+            .into_iter()
+            .map(|(name, handle)| (name, tokio::spawn(handle.shutdown())))
+            .collect();
+
+        let mut result = Ok(());
+        for (name, task) in tasks {
+            match task.await {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => {
+                    error!("Bot '{}' did not shut down cleanly: {}", name, e);
+                    if result.is_ok() {
+                        result = Err(e);
+                    }
+                }
+                Err(e) => error!("Shutdown of bot '{}' panicked: {}", name, e),
+            }
+        }
+        if let Err(e) = self.store.flush().await {
+            error!("Failed to flush session store: {}", e);
+            if result.is_ok() {
+                result = Err(e);
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bots_share_access_control() {
+        let fleet = BotFleet::new(AccessControl::new().with_whitelist(vec![1]));
+        let control = fleet.bot("123:control").build();
+        let alerts = fleet.bot("456:alerts").build();
+
+        fleet.access_control().add_user(2, None).unwrap();
+        assert!(control.access_control().is_authorized(2));
+        assert!(alerts.access_control().is_authorized(2));
+
+        let fleet = fleet.add("control", control).add("alerts", alerts);
+        assert_eq!(fleet.bots.len(), 2);
+    }
+}
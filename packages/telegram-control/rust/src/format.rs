@@ -0,0 +1,432 @@
+//! Formatted text
+//!
+//! [`Text`] is built from styled spans and rendered for a [`ParseMode`], with
+//! each span escaped for that mode. Values from outside (symbols, error
+//! messages, usernames) go in as plain spans and can never break the
+//! surrounding markup.
+//!
+//! ```rust,ignore
+//! let text = Text::new()
+//!     .bold("Order filled")
+//!     .line()
+//!     .plain("Size: ")
+//!     .code("0.5 BTC")
+//!     .line()
+//!     .link("View on explorer", url);
+//! ctx.reply_formatted(&text, ParseMode::Html).await?;
+//! ```
+//!
+//! For hand-written markup, [`md!`](crate::md) and [`html!`](crate::html)
+//! work like `format!` but escape every argument:
+//!
+//! ```rust,ignore
+//! ctx.reply_md(md!("*{}* filled at {}", symbol, price)).await?;
+//! ```
+//!
+//! Only positional arguments are escaped; `{name}` captures in the format
+//! string are inserted as-is.
+
+use std::fmt::Write;
+
+/// Longest message Telegram accepts, in characters
+pub const MESSAGE_LIMIT: usize = 4096;
+
+/// `format!` for MarkdownV2 that escapes each argument
+#[macro_export]
+macro_rules! md {
+    ($fmt:literal $(, $arg:expr)* $(,)?) => {
+        format!(
+            $fmt
+            $(, $crate::format::escape(&::std::string::ToString::to_string(&$arg), $crate::format::ParseMode::MarkdownV2))*
+        )
+    };
+}
+
+/// `format!` for HTML that escapes each argument
+#[macro_export]
+macro_rules! html {
+    ($fmt:literal $(, $arg:expr)* $(,)?) => {
+        format!(
+            $fmt
+            $(, $crate::format::escape(&::std::string::ToString::to_string(&$arg), $crate::format::ParseMode::Html))*
+        )
+    };
+}
+
+/// How message text is interpreted by Telegram
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ParseMode {
+    /// No formatting; text is shown as-is
+    #[default]
+    Plain,
+    MarkdownV2,
+    Html,
+}
+
+impl ParseMode {
+    /// Parse mode to set on the request, if any
+    pub fn telegram(self) -> Option<teloxide::types::ParseMode> {
+        match self {
+            ParseMode::Plain => None,
+            ParseMode::MarkdownV2 => Some(teloxide::types::ParseMode::MarkdownV2),
+            ParseMode::Html => Some(teloxide::types::ParseMode::Html),
+        }
+    }
+}
+
+/// Escape plain text for `mode`
+pub fn escape(text: &str, mode: ParseMode) -> String {
+    match mode {
+        ParseMode::Plain => text.to_string(),
+        ParseMode::MarkdownV2 => escape_with(text, "_*[]()~`>#+-=|{}.!\\"),
+        ParseMode::Html => escape_html(text),
+    }
+}
+
+fn escape_with(text: &str, special: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if special.contains(c) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Split text for `mode` into messages within [`MESSAGE_LIMIT`]
+///
+/// Splits at line breaks where possible, then at spaces. Preformatted blocks
+/// cut by a split are closed and reopened so each part parses on its own.
+pub fn split_message(text: &str, mode: ParseMode) -> Vec<String> {
+    split_with_limit(text, mode, MESSAGE_LIMIT)
+}
+
+fn split_with_limit(text: &str, mode: ParseMode, limit: usize) -> Vec<String> {
+    let (open, close) = match mode {
+        ParseMode::Plain => ("", ""),
+        ParseMode::MarkdownV2 => ("```\n", "\n```"),
+        ParseMode::Html => ("<pre>", "</pre>"),
+    };
+    let len = |s: &str| s.chars().count();
+    let piece_limit = limit.saturating_sub(len(open) + len(close)).max(1);
+
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    let mut in_block = false;
+    for line in text.split_inclusive('\n') {
+        for piece in hard_split(line, mode, piece_limit) {
+            let reserve = if in_block { len(close) } else { 0 };
+            if !current.is_empty() && len(&current) + len(piece) + reserve > limit {
+                if in_block {
+                    current.push_str(close);
+                }
+                chunks.push(std::mem::take(&mut current));
+                if in_block {
+                    current.push_str(open);
+                }
+            }
+            current.push_str(piece);
+            in_block = match mode {
+                ParseMode::Plain => false,
+                ParseMode::MarkdownV2 => in_block ^ (piece.matches("```").count() % 2 == 1),
+                ParseMode::Html => {
+                    let opened = piece.matches("<pre").count();
+                    let closed = piece.matches("</pre>").count();
+                    match opened.cmp(&closed) {
+                        std::cmp::Ordering::Greater => true,
+                        std::cmp::Ordering::Less => false,
+                        std::cmp::Ordering::Equal => in_block,
+                    }
+                }
+            };
+        }
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+/// Split one line into pieces of at most `limit` characters, preferring
+/// spaces and never cutting an escape, entity or tag
+fn hard_split(line: &str, mode: ParseMode, limit: usize) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut rest = line;
+    while rest.chars().count() > limit {
+        let max = rest.char_indices().nth(limit).map_or(rest.len(), |(i, _)| i);
+        let head = &rest[..max];
+        let mut cut = match head.rfind(' ') {
+            Some(i) if i > 0 => i + 1,
+            _ => max,
+        };
+        match mode {
+            ParseMode::Plain => {}
+            ParseMode::MarkdownV2 => {
+                // An odd run of trailing backslashes escapes the next char
+                let slashes = rest[..cut].chars().rev().take_while(|c| *c == '\\').count();
+                if slashes % 2 == 1 && cut > 1 {
+                    cut -= 1;
+                }
+            }
+            ParseMode::Html => {
+                let head = &rest[..cut];
+                if let Some(i) = head.rfind(['<', '&']) {
+                    if !head[i..].contains(['>', ';']) && i > 0 {
+                        cut = i;
+                    }
+                }
+            }
+        }
+        pieces.push(&rest[..cut]);
+        rest = &rest[cut..];
+    }
+    if !rest.is_empty() {
+        pieces.push(rest);
+    }
+    pieces
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Span {
+    Plain(String),
+    Bold(String),
+    Italic(String),
+    Underline(String),
+    Strike(String),
+    Spoiler(String),
+    Code(String),
+    Pre(String, Option<String>),
+    Link(String, String),
+}
+
+/// Text built from styled spans
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Text {
+    spans: Vec<Span>,
+}
+
+impl Text {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn span(mut self, span: Span) -> Self {
+        self.spans.push(span);
+        self
+    }
+
+    pub fn plain(self, text: impl Into<String>) -> Self {
+        self.span(Span::Plain(text.into()))
+    }
+
+    pub fn bold(self, text: impl Into<String>) -> Self {
+        self.span(Span::Bold(text.into()))
+    }
+
+    pub fn italic(self, text: impl Into<String>) -> Self {
+        self.span(Span::Italic(text.into()))
+    }
+
+    pub fn underline(self, text: impl Into<String>) -> Self {
+        self.span(Span::Underline(text.into()))
+    }
+
+    pub fn strike(self, text: impl Into<String>) -> Self {
+        self.span(Span::Strike(text.into()))
+    }
+
+    pub fn spoiler(self, text: impl Into<String>) -> Self {
+        self.span(Span::Spoiler(text.into()))
+    }
+
+    /// Inline monospace
+    pub fn code(self, text: impl Into<String>) -> Self {
+        self.span(Span::Code(text.into()))
+    }
+
+    /// Preformatted block, optionally highlighted as `language`
+    pub fn pre(self, text: impl Into<String>, language: Option<&str>) -> Self {
+        self.span(Span::Pre(text.into(), language.map(str::to_string)))
+    }
+
+    pub fn link(self, text: impl Into<String>, url: impl Into<String>) -> Self {
+        self.span(Span::Link(text.into(), url.into()))
+    }
+
+    /// Line break
+    pub fn line(self) -> Self {
+        self.plain("\n")
+    }
+
+    /// Append the spans of `other`
+    pub fn append(mut self, other: Text) -> Self {
+        self.spans.extend(other.spans);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+
+    /// Render with markup and escaping for `mode`
+    pub fn render(&self, mode: ParseMode) -> String {
+        let mut out = String::new();
+        for span in &self.spans {
+            match mode {
+                ParseMode::Plain => render_plain(&mut out, span),
+                ParseMode::MarkdownV2 => render_markdown(&mut out, span),
+                ParseMode::Html => render_html(&mut out, span),
+            }
+        }
+        out
+    }
+}
+
+impl From<&str> for Text {
+    fn from(text: &str) -> Self {
+        Text::new().plain(text)
+    }
+}
+
+impl From<String> for Text {
+    fn from(text: String) -> Self {
+        Text::new().plain(text)
+    }
+}
+
+fn render_plain(out: &mut String, span: &Span) {
+    match span {
+        Span::Plain(t)
+        | Span::Bold(t)
+        | Span::Italic(t)
+        | Span::Underline(t)
+        | Span::Strike(t)
+        | Span::Spoiler(t)
+        | Span::Code(t)
+        | Span::Pre(t, _) => out.push_str(t),
+        Span::Link(text, url) => write!(out, "{} ({})", text, url).unwrap(),
+    }
+}
+
+fn render_markdown(out: &mut String, span: &Span) {
+    let md = |t: &str| escape(t, ParseMode::MarkdownV2);
+    match span {
+        Span::Plain(t) => out.push_str(&md(t)),
+        Span::Bold(t) => write!(out, "*{}*", md(t)).unwrap(),
+        Span::Italic(t) => write!(out, "_{}_", md(t)).unwrap(),
+        // `\r` separates underline from an adjacent italic `_`
+        Span::Underline(t) => write!(out, "__{}__\r", md(t)).unwrap(),
+        Span::Strike(t) => write!(out, "~{}~", md(t)).unwrap(),
+        Span::Spoiler(t) => write!(out, "||{}||", md(t)).unwrap(),
+        Span::Code(t) => write!(out, "`{}`", escape_with(t, "`\\")).unwrap(),
+        Span::Pre(t, lang) => write!(
+            out,
+            "```{}\n{}\n```",
+            lang.as_deref().unwrap_or_default(),
+            escape_with(t, "`\\")
+        )
+        .unwrap(),
+        Span::Link(text, url) => write!(out, "[{}]({})", md(text), escape_with(url, ")\\")).unwrap(),
+    }
+}
+
+fn render_html(out: &mut String, span: &Span) {
+    let html = escape_html;
+    match span {
+        Span::Plain(t) => out.push_str(&html(t)),
+        Span::Bold(t) => write!(out, "<b>{}</b>", html(t)).unwrap(),
+        Span::Italic(t) => write!(out, "<i>{}</i>", html(t)).unwrap(),
+        Span::Underline(t) => write!(out, "<u>{}</u>", html(t)).unwrap(),
+        Span::Strike(t) => write!(out, "<s>{}</s>", html(t)).unwrap(),
+        Span::Spoiler(t) => write!(out, "<tg-spoiler>{}</tg-spoiler>", html(t)).unwrap(),
+        Span::Code(t) => write!(out, "<code>{}</code>", html(t)).unwrap(),
+        Span::Pre(t, Some(lang)) => write!(
+            out,
+            "<pre><code class=\"language-{}\">{}</code></pre>",
+            html(lang),
+            html(t)
+        )
+        .unwrap(),
+        Span::Pre(t, None) => write!(out, "<pre>{}</pre>", html(t)).unwrap(),
+        Span::Link(text, url) => write!(out, "<a href=\"{}\">{}</a>", html(url), html(text)).unwrap(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Text {
+        Text::new()
+            .bold("PnL: +1.5%")
+            .line()
+            .plain("Pair: BTC_USD <spot>")
+            .line()
+            .code("a`b")
+            .plain(" ")
+            .link("tx", "https://example.com/tx?a=1&b=(2)")
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        assert_eq!(
+            sample().render(ParseMode::MarkdownV2),
+            "*PnL: \\+1\\.5%*\nPair: BTC\\_USD <spot\\>\n`a\\`b` [tx](https://example.com/tx?a=1&b=(2\\))"
+        );
+    }
+
+    #[test]
+    fn test_escaping_macros() {
+        let symbol = "BTC_USD";
+        assert_eq!(crate::md!("*{}* at {}", symbol, 1.5), "*BTC\\_USD* at 1\\.5");
+        assert_eq!(crate::html!("<b>{}</b>", "a<b"), "<b>a&lt;b</b>");
+    }
+
+    #[test]
+    fn test_split_message() {
+        let text = "line one\nline two\nline three\n";
+        assert_eq!(
+            split_with_limit(text, ParseMode::Plain, 20),
+            vec!["line one\nline two\n", "line three\n"]
+        );
+
+        let words = "aaaa bbbb cccc dddd";
+        let parts = split_with_limit(words, ParseMode::Plain, 10);
+        assert_eq!(parts, vec!["aaaa bbbb ", "cccc dddd"]);
+
+        // A code block cut in two is closed and reopened
+        let block = "```\n1111\n2222\n3333\n```";
+        let parts = split_with_limit(block, ParseMode::MarkdownV2, 20);
+        assert!(parts.len() > 1);
+        for part in &parts {
+            assert!(part.chars().count() <= 20, "{:?}", part);
+            assert_eq!(part.matches("```").count() % 2, 0, "{:?}", part);
+        }
+
+        // Never cut inside an HTML entity
+        let parts = split_with_limit("xxxxxxx&amp;yyy", ParseMode::Html, 16);
+        assert!(parts.iter().all(|p| !p.ends_with('&')));
+        assert_eq!(parts.concat(), "xxxxxxx&amp;yyy");
+    }
+
+    #[test]
+    fn test_render_html() {
+        assert_eq!(
+            sample().render(ParseMode::Html),
+            "<b>PnL: +1.5%</b>\nPair: BTC_USD &lt;spot&gt;\n<code>a`b</code> \
+             <a href=\"https://example.com/tx?a=1&amp;b=(2)\">tx</a>"
+        );
+        assert_eq!(
+            sample().render(ParseMode::Plain),
+            "PnL: +1.5%\nPair: BTC_USD <spot>\na`b tx (https://example.com/tx?a=1&b=(2))"
+        );
+    }
+}
@@ -0,0 +1,471 @@
+//! Alert history
+//!
+//! An [`AlertDispatcher`](crate::AlertDispatcher) given a history with
+//! [`with_history`](crate::AlertDispatcher::with_history) records every alert
+//! it sends, and critical alerts get a ✅ Ack button so the team can see who
+//! picked them up. [`Bot::with_alert_history`](crate::Bot::with_alert_history)
+//! registers the button handler and `/alerts [level] [since]` for reviewing
+//! what fired while a user was away.
+//!
+//! ```rust,ignore
+//! let history: Arc<dyn AlertHistory> = Arc::new(FileAlertHistory::new("alerts.jsonl"));
+//! let dispatcher = AlertDispatcher::spawn(sink, config).with_history(history.clone());
+//! let bot = bot.with_alert_history(history);
+//! // /alerts critical 12h
+//! ```
+
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use teloxide::types::InlineKeyboardMarkup;
+use tracing::info;
+
+use crate::alerts::AlertLevel;
+use crate::error::{Error, Result};
+use crate::keyboards::InlineKeyboardBuilder;
+use crate::types::Context;
+
+/// Callback data prefix of the Ack button
+pub const CALLBACK_PREFIX: &str = "ack:";
+
+/// How far back `/alerts` looks when no `since` is given
+const DEFAULT_SINCE: chrono::Duration = chrono::Duration::hours(24);
+
+/// Most alerts `/alerts` lists
+pub const QUERY_LIMIT: usize = 20;
+
+crate::callback_data! {
+    /// Ack button for recorded alert `id`
+    enum AckButton("ack") {
+        Ack("a") { id: u64 },
+    }
+}
+
+/// Someone acknowledging an alert
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Acknowledgment {
+    pub user_id: i64,
+    pub username: Option<String>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// An alert the dispatcher sent
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AlertRecord {
+    /// Assigned by the history on [`record`](AlertHistory::record)
+    pub id: u64,
+    pub timestamp: DateTime<Utc>,
+    pub level: AlertLevel,
+    pub text: String,
+    /// Chats the alert was routed to
+    pub chats: Vec<i64>,
+    pub acks: Vec<Acknowledgment>,
+}
+
+impl AlertRecord {
+    pub fn new(level: AlertLevel, text: impl Into<String>, chats: Vec<i64>) -> Self {
+        Self {
+            id: 0,
+            timestamp: Utc::now(),
+            level,
+            text: text.into(),
+            chats,
+            acks: Vec::new(),
+        }
+    }
+
+    /// Whether the alert should be acknowledged
+    pub fn needs_ack(&self) -> bool {
+        self.level == AlertLevel::Critical
+    }
+
+    /// One-line summary for chat output
+    pub fn summary(&self) -> String {
+        let first_line = self.text.lines().next().unwrap_or_default();
+        let mut text: String = first_line.chars().take(80).collect();
+        if text.len() < first_line.len() {
+            text.push('…');
+        }
+        let mut line = format!(
+            "{} {} #{} {}",
+            self.timestamp.format("%Y-%m-%d %H:%M"),
+            self.level.emoji(),
+            self.id,
+            text
+        );
+        if !self.acks.is_empty() {
+            let names: Vec<String> = self.acks.iter().map(ack_name).collect();
+            line.push_str(&format!(" — ✅ {}", names.join(", ")));
+        } else if self.needs_ack() {
+            line.push_str(" — ⏳ not acknowledged");
+        }
+        line
+    }
+}
+
+fn ack_name(ack: &Acknowledgment) -> String {
+    match &ack.username {
+        Some(name) => format!("@{}", name),
+        None => ack.user_id.to_string(),
+    }
+}
+
+/// Filter for [`AlertHistory::query`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AlertQuery {
+    /// Lowest level returned
+    pub min_level: AlertLevel,
+    pub since: DateTime<Utc>,
+    pub limit: usize,
+}
+
+impl AlertQuery {
+    /// Alerts of any level from the last 24 hours
+    pub fn new() -> Self {
+        Self {
+            min_level: AlertLevel::Info,
+            since: Utc::now() - DEFAULT_SINCE,
+            limit: QUERY_LIMIT,
+        }
+    }
+
+    /// Parse `/alerts` arguments: an optional level, then an optional
+    /// `since` such as `30m`, `12h`, `7d` or `2024-05-01`
+    pub fn parse(args: &[&str]) -> Result<Self> {
+        Self::parse_at(args, Utc::now())
+    }
+
+    fn parse_at(args: &[&str], now: DateTime<Utc>) -> Result<Self> {
+        let mut query = Self {
+            since: now - DEFAULT_SINCE,
+            ..Self::new()
+        };
+        let mut args = args.iter().peekable();
+        if let Some(level) = args.peek().and_then(|a| a.parse::<AlertLevel>().ok()) {
+            query.min_level = level;
+            args.next();
+        }
+        if let Some(since) = args.next() {
+            query.since = parse_since(since, now)?;
+        }
+        if let Some(extra) = args.next() {
+            return Err(Error::InvalidCommand(format!(
+                "Unexpected argument: {}. Usage: /alerts [level] [since]",
+                extra
+            )));
+        }
+        Ok(query)
+    }
+
+    fn matches(&self, record: &AlertRecord) -> bool {
+        record.level >= self.min_level && record.timestamp >= self.since
+    }
+}
+
+impl Default for AlertQuery {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// `30m`, `12h` and `7d` back from `now`, or a date's midnight UTC
+fn parse_since(s: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>> {
+    let invalid = || Error::InvalidCommand(format!("Invalid since: {} (e.g. 30m, 12h, 7d or 2024-05-01)", s));
+    if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return Ok(date.and_hms_opt(0, 0, 0).ok_or_else(invalid)?.and_utc());
+    }
+    let (num, unit) = s.split_at(s.len().saturating_sub(1));
+    let n: i64 = num.parse().map_err(|_| invalid())?;
+    let ago = match unit {
+        "m" => chrono::Duration::minutes(n),
+        "h" => chrono::Duration::hours(n),
+        "d" => chrono::Duration::days(n),
+        _ => return Err(invalid()),
+    };
+    Ok(now - ago)
+}
+
+/// Store of sent alerts and their acknowledgments
+#[async_trait]
+pub trait AlertHistory: Send + Sync {
+    /// Store `record`, returning the id assigned to it
+    async fn record(&self, record: AlertRecord) -> Result<u64>;
+
+    /// Most recent matching alerts, oldest first
+    async fn query(&self, query: &AlertQuery) -> Result<Vec<AlertRecord>>;
+
+    /// Add `ack` to alert `id`; `None` if the alert is not in the history
+    async fn acknowledge(&self, id: u64, ack: Acknowledgment) -> Result<Option<AlertRecord>>;
+}
+
+/// Add `ack` unless that user already acknowledged
+fn add_ack(record: &mut AlertRecord, ack: Acknowledgment) {
+    if !record.acks.iter().any(|a| a.user_id == ack.user_id) {
+        record.acks.push(ack);
+    }
+}
+
+/// Last `limit` records matching `query`, oldest first
+fn select<'a>(records: impl DoubleEndedIterator<Item = &'a AlertRecord>, query: &AlertQuery) -> Vec<AlertRecord> {
+    let mut selected: Vec<AlertRecord> = records.rev().filter(|r| query.matches(r)).take(query.limit).cloned().collect();
+    selected.reverse();
+    selected
+}
+
+/// In-memory history keeping the last `capacity` alerts
+#[derive(Debug)]
+pub struct MemoryAlertHistory {
+    records: Mutex<VecDeque<AlertRecord>>,
+    capacity: usize,
+    next_id: Mutex<u64>,
+}
+
+impl MemoryAlertHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            records: Mutex::new(VecDeque::new()),
+            capacity,
+            next_id: Mutex::new(1),
+        }
+    }
+}
+
+#[async_trait]
+impl AlertHistory for MemoryAlertHistory {
+    async fn record(&self, mut record: AlertRecord) -> Result<u64> {
+        let id = {
+            let mut next_id = self.next_id.lock().unwrap();
+            let id = *next_id;
+            *next_id += 1;
+            id
+        };
+        record.id = id;
+        let mut records = self.records.lock().unwrap();
+        if records.len() == self.capacity {
+            records.pop_front();
+        }
+        records.push_back(record);
+        Ok(id)
+    }
+
+    async fn query(&self, query: &AlertQuery) -> Result<Vec<AlertRecord>> {
+        Ok(select(self.records.lock().unwrap().iter(), query))
+    }
+
+    async fn acknowledge(&self, id: u64, ack: Acknowledgment) -> Result<Option<AlertRecord>> {
+        let mut records = self.records.lock().unwrap();
+        let Some(record) = records.iter_mut().find(|r| r.id == id) else {
+            return Ok(None);
+        };
+        add_ack(record, ack);
+        Ok(Some(record.clone()))
+    }
+}
+
+/// Line of a [`FileAlertHistory`]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum HistoryLine {
+    Alert(AlertRecord),
+    Ack { id: u64, ack: Acknowledgment },
+}
+
+/// History appended to a JSON-lines file
+///
+/// Alerts and acknowledgments are separate lines, so the file is only ever
+/// appended to.
+#[derive(Debug)]
+pub struct FileAlertHistory {
+    path: PathBuf,
+    /// Next id, read from the file on first use
+    next_id: Mutex<Option<u64>>,
+}
+
+impl FileAlertHistory {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            next_id: Mutex::new(None),
+        }
+    }
+
+    /// All alerts in the file with their acknowledgments applied
+    fn load(&self) -> Result<Vec<AlertRecord>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let mut records: Vec<AlertRecord> = Vec::new();
+        for line in BufReader::new(std::fs::File::open(&self.path)?).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(&line)? {
+                HistoryLine::Alert(record) => records.push(record),
+                HistoryLine::Ack { id, ack } => {
+                    if let Some(record) = records.iter_mut().rev().find(|r| r.id == id) {
+                        add_ack(record, ack);
+                    }
+                }
+            }
+        }
+        Ok(records)
+    }
+
+    fn append(&self, line: &HistoryLine) -> Result<()> {
+        let line = serde_json::to_string(line)?;
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        writeln!(file, "{}", line)?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AlertHistory for FileAlertHistory {
+    async fn record(&self, mut record: AlertRecord) -> Result<u64> {
+        // The id lock also serializes writers
+        let mut next_id = self.next_id.lock().unwrap();
+        let id = match *next_id {
+            Some(id) => id,
+            None => self.load()?.iter().map(|r| r.id).max().unwrap_or(0) + 1,
+        };
+        record.id = id;
+        self.append(&HistoryLine::Alert(record))?;
+        *next_id = Some(id + 1);
+        Ok(id)
+    }
+
+    async fn query(&self, query: &AlertQuery) -> Result<Vec<AlertRecord>> {
+        let _guard = self.next_id.lock().unwrap();
+        Ok(select(self.load()?.iter(), query))
+    }
+
+    async fn acknowledge(&self, id: u64, ack: Acknowledgment) -> Result<Option<AlertRecord>> {
+        let _guard = self.next_id.lock().unwrap();
+        let Some(mut record) = self.load()?.into_iter().rev().find(|r| r.id == id) else {
+            return Ok(None);
+        };
+        self.append(&HistoryLine::Ack { id, ack: ack.clone() })?;
+        add_ack(&mut record, ack);
+        Ok(Some(record))
+    }
+}
+
+/// Keyboard with the Ack button for alert `id`
+pub fn ack_keyboard(id: u64) -> InlineKeyboardMarkup {
+    InlineKeyboardBuilder::new()
+        .data_button("✅ Ack", &AckButton::Ack { id })
+        .build()
+}
+
+/// Record who pressed an Ack button and take the button off the alert
+pub(crate) async fn handle_callback(history: &dyn AlertHistory, ctx: Context) -> Result<()> {
+    let Some(AckButton::Ack { id }) = ctx.callback_data::<AckButton>() else {
+        return Ok(());
+    };
+    let ack = Acknowledgment {
+        user_id: ctx.user_id(),
+        username: ctx.username().map(str::to_string),
+        timestamp: Utc::now(),
+    };
+    let name = ack_name(&ack);
+    if history.acknowledge(id, ack).await?.is_none() {
+        return ctx.answer_alert("This alert is no longer in the history.").await;
+    }
+    info!("User {} acknowledged alert #{}", ctx.user_id(), id);
+    ctx.clear_keyboard().await?;
+    ctx.answer("Acknowledged").await?;
+    ctx.reply_in_thread(format!("✅ Acknowledged by {}", name)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ack(user_id: i64) -> Acknowledgment {
+        Acknowledgment {
+            user_id,
+            username: Some("alice".to_string()),
+            timestamp: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_parse_query() {
+        let now = Utc::now();
+        let query = AlertQuery::parse_at(&[], now).unwrap();
+        assert_eq!(query.min_level, AlertLevel::Info);
+        assert_eq!(query.since, now - chrono::Duration::hours(24));
+
+        let query = AlertQuery::parse_at(&["critical", "30m"], now).unwrap();
+        assert_eq!(query.min_level, AlertLevel::Critical);
+        assert_eq!(query.since, now - chrono::Duration::minutes(30));
+
+        let query = AlertQuery::parse_at(&["2024-05-01"], now).unwrap();
+        assert_eq!(query.since.to_rfc3339(), "2024-05-01T00:00:00+00:00");
+
+        assert!(AlertQuery::parse_at(&["loud"], now).is_err());
+        assert!(AlertQuery::parse_at(&["error", "1h", "x"], now).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_memory_history() {
+        let history = MemoryAlertHistory::new(2);
+        for (level, text) in [
+            (AlertLevel::Info, "fill"),
+            (AlertLevel::Critical, "liquidation"),
+            (AlertLevel::Warning, "funding"),
+        ] {
+            history.record(AlertRecord::new(level, text, vec![1])).await.unwrap();
+        }
+
+        let all = history.query(&AlertQuery::new()).await.unwrap();
+        let texts: Vec<_> = all.iter().map(|r| r.text.as_str()).collect();
+        assert_eq!(texts, vec!["liquidation", "funding"]);
+        assert!(all[0].summary().contains("⏳ not acknowledged"));
+
+        let query = AlertQuery {
+            min_level: AlertLevel::Error,
+            ..AlertQuery::new()
+        };
+        assert_eq!(history.query(&query).await.unwrap().len(), 1);
+
+        let acked = history.acknowledge(2, ack(7)).await.unwrap().unwrap();
+        history.acknowledge(2, ack(7)).await.unwrap();
+        assert_eq!(acked.acks.len(), 1);
+        assert!(acked.summary().ends_with("— ✅ @alice"));
+        assert!(history.acknowledge(1, ack(7)).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_file_history() {
+        let path = std::env::temp_dir().join(format!("alert-history-test-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let history = FileAlertHistory::new(&path);
+        let id = history
+            .record(AlertRecord::new(AlertLevel::Critical, "kill switch", vec![1, 2]))
+            .await
+            .unwrap();
+        history.acknowledge(id, ack(7)).await.unwrap();
+
+        // A fresh instance picks up ids and acks from the file
+        let reopened = FileAlertHistory::new(&path);
+        let next = reopened
+            .record(AlertRecord::new(AlertLevel::Info, "fill", vec![1]))
+            .await
+            .unwrap();
+        assert_eq!(next, id + 1);
+        let records = reopened.query(&AlertQuery::new()).await.unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].acks[0].user_id, 7);
+        assert_eq!(records[0].chats, vec![1, 2]);
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
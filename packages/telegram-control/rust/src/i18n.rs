@@ -0,0 +1,233 @@
+//! Translations
+//!
+//! [`I18n`] holds a key → text map per locale. The bot's built-in replies
+//! (the unauthorized warning, `/help`, errors) are looked up here, and
+//! handlers can use it for their own messages. Texts may contain `{name}`
+//! placeholders.
+//!
+//! A user's locale is the one they picked with `/language`, stored in the
+//! [`SessionStore`], else their Telegram app language if there are
+//! translations for it, else the default locale (`en`).
+//!
+//! ```rust,ignore
+//! let i18n = I18n::load_dir("config/locales")?.with_store(store); // de.json, es.json, ...
+//! let bot = Bot::new(token).build().with_i18n(i18n);
+//!
+//! let i18n = bot.i18n();
+//! let bot = bot.on_command("/balance", move |ctx: Context| {
+//!     let i18n = i18n.clone();
+//!     async move {
+//!         let locale = i18n.locale(&ctx).await;
+//!         ctx.reply(i18n.t(&locale, "balance", &[("usd", &1250.0)])).await
+//!     }
+//! });
+//! ```
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::path::Path;
+use std::sync::Arc;
+use tracing::warn;
+
+use crate::error::{Error, Result};
+use crate::session::{MemorySessionStore, SessionStore};
+use crate::types::Context;
+
+const STORE_KEY: &str = "i18n.locale";
+
+/// English texts for the bot's built-in messages
+const BUILTIN: &[(&str, &str)] = &[
+    ("unauthorized", "⛔ You are not authorized to use this bot."),
+    ("private_only", "🔒 {command} only works in a private chat with the bot."),
+    ("error", "❌ Error: {error}"),
+    ("still_working", "⏳ Still working…"),
+    ("cooldown", "⏳ {command} was run recently; try again in {wait}."),
+    ("help.header", "Available commands:"),
+    ("help.help", "Show available commands"),
+    ("help.language", "Choose your language: [code]"),
+    ("language.current", "🌐 Language: {locale}\nAvailable: {available}"),
+    ("language.set", "🌐 Language set to {locale}."),
+    ("language.unknown", "Unknown language: {locale}. Available: {available}"),
+];
+
+/// Texts per locale, and each user's chosen locale
+#[derive(Clone)]
+pub struct I18n {
+    default_locale: String,
+    texts: HashMap<String, HashMap<String, String>>,
+    store: Arc<dyn SessionStore>,
+}
+
+impl Default for I18n {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl I18n {
+    /// Built-in English texts only; preferences are kept in memory
+    pub fn new() -> Self {
+        let mut i18n = Self {
+            default_locale: "en".to_string(),
+            texts: HashMap::new(),
+            store: Arc::new(MemorySessionStore::new()),
+        };
+        i18n.add_all("en", BUILTIN.iter().copied());
+        i18n
+    }
+
+    /// Load `<locale>.json` files of `{"key": "text"}` from `dir`
+    pub fn load_dir(dir: impl AsRef<Path>) -> Result<Self> {
+        let mut i18n = Self::new();
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            let Some(locale) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            let texts: HashMap<String, String> = serde_json::from_str(&std::fs::read_to_string(&path)?)
+                .map_err(|e| Error::Config(format!("{}: {}", path.display(), e)))?;
+            i18n.add_all(locale, texts);
+        }
+        Ok(i18n)
+    }
+
+    /// Persist users' locale choices in `store`
+    pub fn with_store(mut self, store: Arc<dyn SessionStore>) -> Self {
+        self.store = store;
+        self
+    }
+
+    /// Locale used when a user has none or it lacks a text (default: `en`)
+    pub fn with_default_locale(mut self, locale: &str) -> Self {
+        self.default_locale = normalize(locale);
+        self
+    }
+
+    /// Add or replace one text
+    pub fn add(&mut self, locale: &str, key: impl Into<String>, text: impl Into<String>) {
+        self.texts.entry(normalize(locale)).or_default().insert(key.into(), text.into());
+    }
+
+    /// Add or replace several texts
+    pub fn add_all<K, V>(&mut self, locale: &str, texts: impl IntoIterator<Item = (K, V)>)
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        let map = self.texts.entry(normalize(locale)).or_default();
+        map.extend(texts.into_iter().map(|(k, v)| (k.into(), v.into())));
+    }
+
+    /// Locales with translations, sorted
+    pub fn locales(&self) -> Vec<&str> {
+        let mut locales: Vec<&str> = self.texts.keys().map(String::as_str).collect();
+        locales.sort_unstable();
+        locales
+    }
+
+    /// The supported locale matching `code`, e.g. `pt` for `pt-BR`
+    pub fn supported(&self, code: &str) -> Option<String> {
+        let code = normalize(code);
+        if self.texts.contains_key(&code) {
+            return Some(code);
+        }
+        let primary = code.split('-').next().unwrap_or_default();
+        self.texts.contains_key(primary).then(|| primary.to_string())
+    }
+
+    /// Text for `key` in `locale` or else the default locale
+    pub fn lookup(&self, locale: &str, key: &str) -> Option<&str> {
+        [locale, self.default_locale.as_str()]
+            .iter()
+            .find_map(|l| self.texts.get(*l)?.get(key))
+            .map(String::as_str)
+    }
+
+    /// Like [`I18n::lookup`], falling back to the key itself
+    pub fn get<'a>(&'a self, locale: &str, key: &'a str) -> &'a str {
+        self.lookup(locale, key).unwrap_or(key)
+    }
+
+    /// Text for `key` with `{name}` placeholders filled from `args`
+    pub fn t(&self, locale: &str, key: &str, args: &[(&str, &dyn Display)]) -> String {
+        let mut text = self.get(locale, key).to_string();
+        for (name, value) in args {
+            text = text.replace(&format!("{{{}}}", name), &value.to_string());
+        }
+        text
+    }
+
+    /// Locale for the user behind `ctx`
+    pub async fn locale(&self, ctx: &Context) -> String {
+        let stored = match self.store.get::<String>(ctx.user_id(), STORE_KEY).await {
+            Ok(stored) => stored,
+            Err(e) => {
+                warn!("Failed to load locale of user {}: {}", ctx.user_id(), e);
+                None
+            }
+        };
+        stored
+            .or_else(|| ctx.language_code().and_then(|code| self.supported(code)))
+            .unwrap_or_else(|| self.default_locale.clone())
+    }
+
+    /// Store `user_id`'s choice of locale, or clear it with `None`
+    pub async fn set_locale(&self, user_id: i64, locale: Option<&str>) -> Result<()> {
+        match locale {
+            Some(locale) => {
+                let locale = self
+                    .supported(locale)
+                    .ok_or_else(|| Error::InvalidCommand(format!("Unsupported locale: {}", locale)))?;
+                self.store.set(user_id, STORE_KEY, &locale).await
+            }
+            None => self.store.remove(user_id, STORE_KEY).await,
+        }
+    }
+}
+
+/// `pt_BR` → `pt-br`
+fn normalize(locale: &str) -> String {
+    locale.trim().replace('_', "-").to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup() {
+        let mut i18n = I18n::new();
+        i18n.add("de", "unauthorized", "⛔ Du darfst diesen Bot nicht benutzen.");
+        i18n.add("de", "error", "❌ Fehler: {error}");
+
+        assert_eq!(i18n.get("de", "unauthorized"), "⛔ Du darfst diesen Bot nicht benutzen.");
+        assert_eq!(i18n.get("de", "help.header"), "Available commands:");
+        assert_eq!(i18n.get("de", "no.such.key"), "no.such.key");
+        assert_eq!(i18n.lookup("de", "no.such.key"), None);
+        assert_eq!(i18n.t("de", "error", &[("error", &"Timeout")]), "❌ Fehler: Timeout");
+        assert_eq!(
+            i18n.t("en", "private_only", &[("command", &"/balance")]),
+            "🔒 /balance only works in a private chat with the bot."
+        );
+
+        assert_eq!(i18n.supported("de_DE").as_deref(), Some("de"));
+        assert_eq!(i18n.supported("fr"), None);
+        assert_eq!(i18n.locales(), vec!["de", "en"]);
+    }
+
+    #[tokio::test]
+    async fn test_set_locale() {
+        let store: Arc<dyn SessionStore> = Arc::new(MemorySessionStore::new());
+        let mut i18n = I18n::new().with_store(store.clone());
+        i18n.add("es", "error", "❌ Error: {error}");
+
+        i18n.set_locale(7, Some("es-MX")).await.unwrap();
+        assert_eq!(store.get::<String>(7, STORE_KEY).await.unwrap().as_deref(), Some("es"));
+        assert!(i18n.set_locale(7, Some("fr")).await.is_err());
+        i18n.set_locale(7, None).await.unwrap();
+        assert!(store.get::<String>(7, STORE_KEY).await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,126 @@
+//! Inline queries
+//!
+//! Handlers registered with [`Bot::on_inline_query`](crate::Bot::on_inline_query)
+//! answer `@mybot BTC`-style queries typed in any chat. They return a list of
+//! results and the bot answers the query with them. Inline queries are
+//! authorized like other updates but skip middleware and the audit log, since
+//! Telegram sends one per keystroke.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use teloxide::types::{
+    InlineQuery, InlineQueryResult, InlineQueryResultArticle, InputMessageContent,
+    InputMessageContentText, User,
+};
+
+use crate::error::Result;
+use crate::state::State;
+
+/// How long Telegram may cache answers, in seconds
+pub const CACHE_TIME: u32 = 10;
+
+/// Inline query handler type
+pub type InlineHandlerFn = Arc<
+    dyn Fn(InlineContext) -> Pin<Box<dyn Future<Output = Result<Vec<InlineQueryResult>>> + Send>>
+        + Send
+        + Sync,
+>;
+
+/// Context for inline queries
+#[derive(Clone, Debug)]
+pub struct InlineContext {
+    pub query: InlineQuery,
+    pub user: User,
+    pub bot: teloxide::Bot,
+    pub state: State,
+}
+
+impl InlineContext {
+    pub fn user_id(&self) -> i64 {
+        self.user.id.0 as i64
+    }
+
+    pub fn username(&self) -> Option<&str> {
+        self.user.username.as_deref()
+    }
+
+    /// Text typed after the bot's username
+    pub fn text(&self) -> &str {
+        self.query.query.trim()
+    }
+
+    /// Underlying teloxide bot
+    pub fn bot(&self) -> &teloxide::Bot {
+        &self.bot
+    }
+
+    /// Shared value of type `T`, as for [`Context::state`](crate::Context::state)
+    pub fn state<T: Send + Sync + 'static>(&self) -> Result<Arc<T>> {
+        self.state.require()
+    }
+}
+
+/// Result that posts `text` to the chat when picked
+pub fn article(id: impl Into<String>, title: impl Into<String>, text: impl Into<String>) -> InlineQueryResult {
+    InlineQueryResult::Article(InlineQueryResultArticle::new(
+        id,
+        title,
+        InputMessageContent::Text(InputMessageContentText::new(text)),
+    ))
+}
+
+/// Like [`article`], with a description line under the title
+pub fn article_with_description(
+    id: impl Into<String>,
+    title: impl Into<String>,
+    description: impl Into<String>,
+    text: impl Into<String>,
+) -> InlineQueryResult {
+    InlineQueryResult::Article(
+        InlineQueryResultArticle::new(id, title, InputMessageContent::Text(InputMessageContentText::new(text)))
+            .description(description),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+    use crate::bot::Bot;
+    use crate::testing::TestBot;
+
+    #[tokio::test]
+    async fn test_inline_answers() {
+        let bot = Bot::new("123:test").with_whitelist(vec![7]).build().on_inline_query(|ctx: InlineContext| async move {
+            match ctx.text() {
+                "fail" => Err(Error::InvalidCommand("no prices".to_string())),
+                symbol => Ok(vec![
+                    article("price", format!("{} price", symbol), format!("{}: 64000", symbol)),
+                    article_with_description("chart", "Chart", "Last 24h", format!("{} chart", symbol)),
+                ]),
+            }
+        });
+        let tg = TestBot::start(bot).await.unwrap();
+
+        tg.inline_query(7, " BTC ").await;
+        let answer = tg.take_calls().pop().unwrap();
+        assert_eq!(answer.method, "answerInlineQuery");
+        assert_eq!(answer.params["cache_time"], CACHE_TIME);
+        assert_eq!(answer.params["is_personal"], true);
+        let results = answer.params["results"].as_array().unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0]["title"], "BTC price");
+        assert_eq!(results[0]["input_message_content"]["message_text"], "BTC: 64000");
+        assert_eq!(results[1]["description"], "Last 24h");
+
+        // Strangers get an empty answer without the handler running
+        tg.inline_query(8, "BTC").await;
+        let answer = tg.take_calls().pop().unwrap();
+        assert_eq!(answer.params["results"], serde_json::json!([]));
+
+        // A failing handler leaves the query unanswered
+        tg.inline_query(7, "fail").await;
+        assert!(tg.take_calls().iter().all(|c| c.method != "answerInlineQuery"));
+    }
+}
@@ -1,4 +1,6 @@
-use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup, KeyboardButton, ReplyKeyboardMarkup};
+use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup, KeyboardButton, KeyboardMarkup};
+
+use crate::callback::CallbackData;
 
 /// Builder for inline keyboards
 #[derive(Debug, Default)]
@@ -21,6 +23,11 @@ impl InlineKeyboardBuilder {
         self
     }
     
+    /// Add a button carrying typed callback data
+    pub fn data_button(self, text: impl Into<String>, data: &impl CallbackData) -> Self {
+        self.button(text, data.encode())
+    }
+    
     /// Add a URL button
     pub fn url_button(mut self, text: impl Into<String>, url: impl Into<String>) -> Self {
         self.current_row.push(InlineKeyboardButton::url(
@@ -30,6 +37,40 @@ impl InlineKeyboardBuilder {
         self
     }
     
+    /// Add a button showing whether a setting is on, e.g. `✅ Alerts`
+    pub fn toggle(self, text: impl Into<String>, on: bool, callback_data: impl Into<String>) -> Self {
+        self.button(toggle_label(&text.into(), on), callback_data)
+    }
+    
+    /// Lay out `(text, callback_data)` buttons `columns` to a row, on rows
+    /// of their own
+    pub fn grid<T, D>(mut self, items: impl IntoIterator<Item = (T, D)>, columns: usize) -> Self
+    where
+        T: Into<String>,
+        D: Into<String>,
+    {
+        self = self.row();
+        for (i, (text, data)) in items.into_iter().enumerate() {
+            if i > 0 && i % columns.max(1) == 0 {
+                self = self.row();
+            }
+            self = self.button(text, data);
+        }
+        self.row()
+    }
+    
+    /// Add the toggles and Done button of a selection list, on rows of
+    /// their own
+    pub fn selection(self, list: &SelectionList) -> Self {
+        let toggles = list.options.iter().enumerate().map(|(i, option)| {
+            let selected = list.selected ^ (1 << i);
+            (toggle_label(option, list.is_selected(i)), format!("{}:t:{:x}", list.prefix, selected))
+        });
+        self.grid(toggles, list.columns)
+            .button(list.done_label.clone(), format!("{}:done:{:x}", list.prefix, list.selected))
+            .row()
+    }
+    
     /// Finish current row and start new one
     pub fn row(mut self) -> Self {
         if !self.current_row.is_empty() {
@@ -47,6 +88,117 @@ impl InlineKeyboardBuilder {
     }
 }
 
+fn toggle_label(text: &str, on: bool) -> String {
+    format!("{} {}", if on { "✅" } else { "⬜" }, text)
+}
+
+/// A multiple-choice list of toggle buttons, e.g. which symbols to watch
+///
+/// The selection travels in the buttons' callback data, so handlers rebuild
+/// the list with the same options and apply the pressed button:
+///
+/// ```rust,ignore
+/// let symbols = SelectionList::new("watch", ["BTC", "ETH", "SOL"]);
+/// ctx.bot().send_message(chat, "Watch which?").reply_markup(symbols.keyboard()).await?;
+///
+/// // in the `watch:` callback handler
+/// let mut symbols = SelectionList::new("watch", ["BTC", "ETH", "SOL"]);
+/// match symbols.apply(ctx.text().unwrap_or_default()) {
+///     Some(SelectionEvent::Changed) => ctx.edit_keyboard(symbols.keyboard()).await?,
+///     Some(SelectionEvent::Done) => save(symbols.selected_options()).await?,
+///     None => {}
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct SelectionList {
+    prefix: String,
+    options: Vec<String>,
+    /// Bit `i` set if option `i` is chosen
+    selected: u64,
+    columns: usize,
+    done_label: String,
+}
+
+/// What a press on a [`SelectionList`] button did
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SelectionEvent {
+    /// An option was toggled; redraw the keyboard
+    Changed,
+    /// The user pressed Done
+    Done,
+}
+
+impl SelectionList {
+    /// Up to 64 options, none selected, in two columns
+    ///
+    /// `prefix` starts each button's callback data and must not contain `:`.
+    pub fn new<T: Into<String>>(prefix: impl Into<String>, options: impl IntoIterator<Item = T>) -> Self {
+        Self {
+            prefix: prefix.into(),
+            options: options.into_iter().take(64).map(Into::into).collect(),
+            selected: 0,
+            columns: 2,
+            done_label: "Done".to_string(),
+        }
+    }
+    
+    /// Start with the options at `indices` selected
+    pub fn with_selected(mut self, indices: &[usize]) -> Self {
+        self.selected = indices
+            .iter()
+            .filter(|&&i| i < self.options.len())
+            .fold(0, |mask, &i| mask | (1 << i));
+        self
+    }
+    
+    /// Toggles per row
+    pub fn columns(mut self, columns: usize) -> Self {
+        self.columns = columns.max(1);
+        self
+    }
+    
+    /// Label of the button that finishes the selection
+    pub fn done_label(mut self, label: impl Into<String>) -> Self {
+        self.done_label = label.into();
+        self
+    }
+    
+    pub fn is_selected(&self, index: usize) -> bool {
+        index < self.options.len() && self.selected & (1 << index) != 0
+    }
+    
+    /// Indices of the chosen options
+    pub fn selected(&self) -> Vec<usize> {
+        (0..self.options.len()).filter(|&i| self.is_selected(i)).collect()
+    }
+    
+    /// The chosen options
+    pub fn selected_options(&self) -> Vec<&str> {
+        self.selected().into_iter().map(|i| self.options[i].as_str()).collect()
+    }
+    
+    /// Update the selection from a pressed button's callback data; `None`
+    /// if the data is not from this list
+    pub fn apply(&mut self, data: &str) -> Option<SelectionEvent> {
+        let rest = data.strip_prefix(self.prefix.as_str())?.strip_prefix(':')?;
+        let (action, mask) = rest.split_once(':')?;
+        let mask = u64::from_str_radix(mask, 16).ok()?;
+        let event = match action {
+            "t" => SelectionEvent::Changed,
+            "done" => SelectionEvent::Done,
+            _ => return None,
+        };
+        let valid = if self.options.len() == 64 { u64::MAX } else { (1 << self.options.len()) - 1 };
+        self.selected = mask & valid;
+        Some(event)
+    }
+    
+    /// The toggles and a Done button
+    pub fn keyboard(&self) -> InlineKeyboardMarkup {
+        InlineKeyboardBuilder::new().selection(self).build()
+    }
+}
+
 /// Builder for reply keyboards
 #[derive(Debug, Default)]
 pub struct ReplyKeyboardBuilder {
@@ -92,13 +244,15 @@ impl ReplyKeyboardBuilder {
     }
     
     /// Build the keyboard
-    pub fn build(mut self) -> ReplyKeyboardMarkup {
+    pub fn build(mut self) -> KeyboardMarkup {
         if !self.current_row.is_empty() {
             self.rows.push(self.current_row);
         }
-        ReplyKeyboardMarkup::new(self.rows)
-            .resize_keyboard(self.resize)
-            .one_time_keyboard(self.one_time)
+        KeyboardMarkup {
+            resize_keyboard: self.resize,
+            one_time_keyboard: self.one_time,
+            ..KeyboardMarkup::new(self.rows)
+        }
     }
 }
 
@@ -127,7 +281,7 @@ pub mod layouts {
             format!("{}:noop", prefix),
         );
         
-        if current < total - 1 {
+        if current + 1 < total {
             builder = builder.button("Next ➡️", format!("{}:page:{}", prefix, current + 1));
         }
         
@@ -146,7 +300,7 @@ pub mod layouts {
     }
     
     /// Main menu keyboard
-    pub fn main_menu() -> ReplyKeyboardMarkup {
+    pub fn main_menu() -> KeyboardMarkup {
         ReplyKeyboardBuilder::new()
             .button("📊 Dashboard")
             .button("💼 Portfolio")
@@ -174,4 +328,63 @@ mod tests {
         assert_eq!(kb.inline_keyboard[0].len(), 2);
         assert_eq!(kb.inline_keyboard[1].len(), 1);
     }
+    
+    fn labels(kb: &InlineKeyboardMarkup) -> Vec<Vec<String>> {
+        kb.inline_keyboard
+            .iter()
+            .map(|row| row.iter().map(|b| b.text.clone()).collect())
+            .collect()
+    }
+    
+    fn data(kb: &InlineKeyboardMarkup, row: usize, col: usize) -> String {
+        match &kb.inline_keyboard[row][col].kind {
+            teloxide::types::InlineKeyboardButtonKind::CallbackData(data) => data.clone(),
+            _ => String::new(),
+        }
+    }
+    
+    #[test]
+    fn test_grid_and_toggle() {
+        let symbols = ["BTC", "ETH", "SOL", "ADA", "XRP"];
+        let kb = InlineKeyboardBuilder::new()
+            .toggle("Alerts", true, "alerts")
+            .grid(symbols.iter().map(|s| (*s, format!("sym:{}", s))), 2)
+            .toggle("Digest", false, "digest")
+            .build();
+        assert_eq!(
+            labels(&kb),
+            vec![
+                vec!["✅ Alerts"],
+                vec!["BTC", "ETH"],
+                vec!["SOL", "ADA"],
+                vec!["XRP"],
+                vec!["⬜ Digest"],
+            ]
+        );
+        assert_eq!(data(&kb, 3, 0), "sym:XRP");
+    }
+    
+    #[test]
+    fn test_selection_list() {
+        let list = || SelectionList::new("watch", ["BTC", "ETH", "SOL"]);
+        let kb = list().with_selected(&[1]).keyboard();
+        assert_eq!(labels(&kb), vec![vec!["⬜ BTC", "✅ ETH"], vec!["⬜ SOL"], vec!["Done"]]);
+        
+        // Pressing BTC selects it alongside ETH
+        let mut pressed = list();
+        assert_eq!(pressed.apply(&data(&kb, 0, 0)), Some(SelectionEvent::Changed));
+        assert_eq!(pressed.selected_options(), vec!["BTC", "ETH"]);
+        
+        // Pressing ETH deselects it
+        let mut pressed = list();
+        pressed.apply(&data(&kb, 0, 1));
+        assert!(pressed.selected().is_empty());
+        
+        let mut done = list();
+        assert_eq!(done.apply(&data(&kb, 2, 0)), Some(SelectionEvent::Done));
+        assert_eq!(done.selected(), vec![1]);
+        
+        assert_eq!(list().apply("other:t:1"), None);
+        assert_eq!(list().apply("watch:t:zz"), None);
+    }
 }
@@ -5,42 +5,127 @@
 //! ## Example
 //!
 //! ```rust,no_run
-//! use telegram_control::{Bot, CommandHandler, Context};
+//! use telegram_control::{Bot, Context};
 //!
 //! #[tokio::main]
-//! async fn main() {
+//! async fn main() -> telegram_control::Result<()> {
 //!     let bot = Bot::new(std::env::var("TELEGRAM_TOKEN").unwrap())
-//!         .with_whitelist(vec![12345678]);
+//!         .with_whitelist(vec![12345678])
+//!         .build()
+//!         .on_command("/status", |ctx: Context| async move {
+//!             ctx.reply("System operational").await
+//!         });
 //!     
-//!     bot.on_command("/status", |ctx: Context| async move {
-//!         ctx.reply("System operational").await
-//!     });
-//!     
-//!     bot.run().await;
+//!     bot.run().await
 //! }
 //! ```
 
 pub mod alerts;
+pub mod approval;
+pub mod args;
+pub mod audit;
 pub mod auth;
 pub mod bot;
+pub mod broadcast;
+pub mod callback;
+#[cfg(feature = "charts")]
+pub mod charts;
 pub mod commands;
+pub mod confirm;
+pub mod dialogue;
+pub mod dispatcher;
+pub mod documents;
 pub mod error;
+pub mod fleet;
+pub mod format;
+pub mod history;
+pub mod i18n;
+pub mod inline;
 pub mod keyboards;
+pub mod live;
+pub mod metrics;
+pub mod middleware;
+pub mod pagination;
+pub mod quiet;
+pub mod ratelimit;
+pub mod report;
+pub mod scheduler;
+pub mod session;
+pub mod shutdown;
+pub mod state;
+pub mod template;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+pub mod timeout;
 pub mod types;
 
+pub use approval::Approval;
+pub use args::{CommandSpec, ParsedArgs};
+pub use audit::{AuditEntry, AuditLog, FileAuditLog, MemoryAuditLog};
 pub use bot::{Bot, BotBuilder};
+pub use broadcast::{BroadcastReport, Broadcaster, Subscribers};
+pub use callback::CallbackData;
 pub use commands::{Command, CommandHandler};
+pub use confirm::Confirmation;
+pub use dialogue::{Dialogue, DialogueStorage, InMemStorage};
+pub use dispatcher::{Alert, AlertDispatcher, AlertSink, AlertSource, AlertThrottle, DispatcherConfig, Priority, Route};
+pub use documents::{DocumentLimits, Upload};
 pub use error::{Error, Result};
+pub use fleet::{BotFleet, FleetHandle};
+pub use format::{ParseMode, Text};
+pub use history::{AlertHistory, AlertQuery, AlertRecord, FileAlertHistory, MemoryAlertHistory};
+pub use i18n::I18n;
+pub use inline::InlineContext;
+pub use live::{LiveMessage, MessageEditor};
+pub use middleware::{Flow, Middleware, MiddlewareStack};
+pub use pagination::Pagination;
+pub use quiet::QuietHours;
+pub use ratelimit::{RateLimit, RateLimiter};
+pub use report::{ErrorReport, ErrorReporter};
+pub use scheduler::{JobContext, Schedule, Scheduler};
+pub use session::{MemorySessionStore, SessionStore};
+pub use shutdown::BotHandle;
+pub use state::State;
+pub use template::{Template, TemplateSet};
+pub use timeout::HandlerTimeout;
 pub use types::{CallbackContext, Context, MessageContext};
 
 /// Re-export commonly used types
 pub mod prelude {
     pub use crate::{
         alerts::*,
+        approval::Approval,
+        args::*,
+        audit::*,
         auth::*,
         bot::{Bot, BotBuilder},
+        broadcast::*,
+        callback::CallbackData,
+        callback_data,
         commands::{Command, CommandHandler},
+        confirm::Confirmation,
+        dialogue::*,
+        dispatcher::*,
+        documents::{DocumentLimits, Upload},
+        fleet::*,
+        format::{ParseMode, Text},
+        history::{Acknowledgment, AlertHistory, AlertQuery, AlertRecord, FileAlertHistory, MemoryAlertHistory},
+        html, md,
+        i18n::I18n,
+        inline::*,
         keyboards::*,
+        live::*,
+        middleware::*,
+        pagination::Pagination,
+        quiet::QuietHours,
+        ratelimit::*,
+        report::{ErrorReport, ErrorReporter},
+        scheduler::*,
+        session::*,
+        shutdown::BotHandle,
+        state::State,
+        template::{Template, TemplateSet},
+        timeout::HandlerTimeout,
         types::*,
     };
 }
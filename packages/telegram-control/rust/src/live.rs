@@ -0,0 +1,252 @@
+//! Live-updating messages
+//!
+//! A [`LiveMessage`] posts one message and keeps editing it in place, either
+//! when new content is pushed (position or PnL events) or on a fixed refresh
+//! interval. Updates arriving faster than the edit interval are coalesced so
+//! only the latest text is sent, and unchanged text is never re-sent.
+
+use async_trait::async_trait;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use teloxide::prelude::*;
+use teloxide::types::MessageId;
+use teloxide::{ApiError, RequestError};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use crate::error::{Error, Result};
+
+/// Default minimum gap between edits of one message
+pub const DEFAULT_EDIT_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Posts and edits text messages
+///
+/// Implemented for `teloxide::Bot`; implement it yourself to capture edits in
+/// tests.
+#[async_trait]
+pub trait MessageEditor: Send + Sync {
+    /// Send a message, returning its ID
+    async fn send_text(&self, chat_id: i64, text: &str) -> Result<i32>;
+
+    async fn edit_text(&self, chat_id: i64, message_id: i32, text: &str) -> Result<()>;
+}
+
+#[async_trait]
+impl MessageEditor for teloxide::Bot {
+    async fn send_text(&self, chat_id: i64, text: &str) -> Result<i32> {
+        let msg = self.send_message(ChatId(chat_id), text).await?;
+        Ok(msg.id.0)
+    }
+
+    async fn edit_text(&self, chat_id: i64, message_id: i32, text: &str) -> Result<()> {
+        self.edit_message_text(ChatId(chat_id), MessageId(message_id), text)
+            .await?;
+        Ok(())
+    }
+}
+
+/// A message that is edited in place as its content changes
+pub struct LiveMessage {
+    chat_id: i64,
+    message_id: i32,
+    tx: Arc<watch::Sender<String>>,
+    worker: JoinHandle<()>,
+    refreshers: Vec<JoinHandle<()>>,
+}
+
+impl LiveMessage {
+    /// Post `text` to `chat_id`, editing at most every
+    /// [`DEFAULT_EDIT_INTERVAL`]
+    pub async fn post(
+        editor: Arc<dyn MessageEditor>,
+        chat_id: i64,
+        text: impl Into<String>,
+    ) -> Result<Self> {
+        Self::post_with_interval(editor, chat_id, text, DEFAULT_EDIT_INTERVAL).await
+    }
+
+    /// Post `text` to `chat_id`, editing at most every `min_interval`
+    pub async fn post_with_interval(
+        editor: Arc<dyn MessageEditor>,
+        chat_id: i64,
+        text: impl Into<String>,
+        min_interval: Duration,
+    ) -> Result<Self> {
+        let text = text.into();
+        let message_id = editor.send_text(chat_id, &text).await?;
+        let (tx, rx) = watch::channel(text.clone());
+        let worker = tokio::spawn(apply_edits(editor, chat_id, message_id, rx, text, min_interval));
+        Ok(Self {
+            chat_id,
+            message_id,
+            tx: Arc::new(tx),
+            worker,
+            refreshers: Vec::new(),
+        })
+    }
+
+    pub fn chat_id(&self) -> i64 {
+        self.chat_id
+    }
+
+    pub fn message_id(&self) -> i32 {
+        self.message_id
+    }
+
+    /// Replace the message content; only the latest update per edit interval
+    /// is sent
+    pub fn update(&self, text: impl Into<String>) {
+        self.tx.send_replace(text.into());
+    }
+
+    /// Re-render the message every `interval` with `render`
+    pub fn refresh_every<F, Fut>(mut self, interval: Duration, render: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String>> + Send + 'static,
+    {
+        let tx = self.tx.clone();
+        self.refreshers.push(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+            loop {
+                ticker.tick().await;
+                match render().await {
+                    Ok(text) => {
+                        tx.send_replace(text);
+                    }
+                    Err(e) => warn!("Failed to render live message: {}", e),
+                }
+            }
+        }));
+        self
+    }
+
+    /// Stop refreshing, wait for the last update to be applied, and leave the
+    /// message as it is
+    pub async fn stop(self) {
+        for refresher in &self.refreshers {
+            refresher.abort();
+        }
+        for refresher in self.refreshers {
+            let _ = refresher.await;
+        }
+        drop(self.tx);
+        let _ = self.worker.await;
+    }
+}
+
+async fn apply_edits(
+    editor: Arc<dyn MessageEditor>,
+    chat_id: i64,
+    message_id: i32,
+    mut rx: watch::Receiver<String>,
+    mut last: String,
+    min_interval: Duration,
+) {
+    while rx.changed().await.is_ok() {
+        let mut text = rx.borrow_and_update().clone();
+        if text == last {
+            continue;
+        }
+        loop {
+            match editor.edit_text(chat_id, message_id, &text).await {
+                Ok(()) | Err(Error::Telegram(RequestError::Api(ApiError::MessageNotModified))) => {
+                    last = text;
+                    break;
+                }
+                Err(Error::Telegram(RequestError::RetryAfter(secs))) => {
+                    tokio::time::sleep(secs.duration()).await;
+                    // Newer content may have arrived while waiting
+                    text = rx.borrow_and_update().clone();
+                }
+                Err(Error::Telegram(RequestError::Api(ApiError::MessageToEditNotFound))) => {
+                    info!("Live message {} in chat {} was deleted", message_id, chat_id);
+                    return;
+                }
+                Err(e) => {
+                    warn!("Failed to edit live message {} in chat {}: {}", message_id, chat_id, e);
+                    break;
+                }
+            }
+        }
+        // Updates arriving meanwhile are coalesced into the next edit
+        tokio::time::sleep(min_interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use teloxide::types::Seconds;
+
+    /// Records edits, failing with queued errors first
+    #[derive(Default)]
+    struct RecordingEditor {
+        failures: Mutex<Vec<RequestError>>,
+        edits: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl MessageEditor for RecordingEditor {
+        async fn send_text(&self, _chat_id: i64, _text: &str) -> Result<i32> {
+            Ok(7)
+        }
+
+        async fn edit_text(&self, _chat_id: i64, _message_id: i32, text: &str) -> Result<()> {
+            if let Some(e) = self.failures.lock().unwrap().pop() {
+                return Err(e.into());
+            }
+            self.edits.lock().unwrap().push(text.to_string());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_updates_coalesce() {
+        let editor = Arc::new(RecordingEditor::default());
+        let live = LiveMessage::post_with_interval(editor.clone(), 1, "PnL: 0", Duration::from_millis(50))
+            .await
+            .unwrap();
+        assert_eq!(live.message_id(), 7);
+
+        live.update("PnL: 0");
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        live.update("PnL: 1");
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        live.update("PnL: 2");
+        live.update("PnL: 3");
+        live.stop().await;
+
+        assert_eq!(*editor.edits.lock().unwrap(), vec!["PnL: 1", "PnL: 3"]);
+    }
+
+    #[tokio::test]
+    async fn test_retries_rate_limited_edit() {
+        let editor = Arc::new(RecordingEditor::default());
+        editor
+            .failures
+            .lock()
+            .unwrap()
+            .push(RequestError::RetryAfter(Seconds::from_seconds(0)));
+        editor
+            .failures
+            .lock()
+            .unwrap()
+            .push(RequestError::Api(ApiError::MessageNotModified));
+        let live = LiveMessage::post_with_interval(editor.clone(), 1, "a", Duration::ZERO)
+            .await
+            .unwrap();
+
+        live.update("b");
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        live.update("c");
+        live.stop().await;
+
+        // "b" hit "not modified", then "c" was retried after the rate limit
+        assert_eq!(*editor.edits.lock().unwrap(), vec!["c"]);
+    }
+}
@@ -0,0 +1,121 @@
+//! Usage metrics
+//!
+//! With the `metrics` feature the bot reports what it handles as `tracing`
+//! events on the `metrics` target, using the `monotonic_counter.` and
+//! `histogram.` field prefixes that `tracing-opentelemetry`'s `MetricsLayer`
+//! exports as OpenTelemetry instruments. Like the blockchain-clients spans
+//! they carry `venue` and `endpoint` fields, so one dashboard can show bot
+//! commands next to exchange calls. Queue depth uses the `counter.` prefix,
+//! going up when an alert is queued and down when it is taken for delivery.
+//! Without the feature nothing is emitted.
+//!
+//! | Metric                        | Kind            | Fields                       |
+//! |-------------------------------|-----------------|------------------------------|
+//! | `telegram_commands_handled`   | counter         | `endpoint`, `outcome`        |
+//! | `telegram_command_latency_ms` | histogram       | `endpoint`, `outcome`        |
+//! | `telegram_auth_denials`       | counter         | `endpoint`, `reason`         |
+//! | `telegram_alert_deliveries`   | counter         | `level`, `outcome`           |
+//! | `telegram_alert_queue_depth`  | up-down counter | `priority`                   |
+//! | `telegram_alert_latency_ms`   | histogram       | `level`                      |
+//!
+//! ```rust,ignore
+//! tracing_subscriber::registry()
+//!     .with(tracing_subscriber::fmt::layer())
+//!     .with(tracing_opentelemetry::MetricsLayer::new(meter_provider))
+//!     .init();
+//! ```
+
+#![cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+
+use std::time::Duration;
+
+use crate::alerts::AlertLevel;
+use crate::dispatcher::Priority;
+use crate::error::Error;
+
+/// `venue` field of every metric, matching the blockchain-clients spans
+pub const VENUE: &str = "telegram";
+
+/// `outcome` field for a handler result
+pub(crate) fn outcome(result: &Result<(), Error>) -> &'static str {
+    match result {
+        Ok(()) => "ok",
+        Err(Error::Forbidden(..) | Error::Unauthorized(_) | Error::ChatUnauthorized(_)) => "denied",
+        Err(Error::Timeout(_)) => "timeout",
+        Err(Error::Panic(_)) => "panic",
+        Err(_) => "error",
+    }
+}
+
+/// An update handled by `endpoint`, e.g. `/status` or `callback:approval`
+pub(crate) fn command_handled(endpoint: &str, outcome: &str, latency: Duration) {
+    #[cfg(feature = "metrics")]
+    tracing::info!(
+        target: "metrics",
+        venue = VENUE,
+        monotonic_counter.telegram_commands_handled = 1u64,
+        histogram.telegram_command_latency_ms = latency.as_secs_f64() * 1000.0,
+        endpoint,
+        outcome,
+    );
+}
+
+/// An update refused because of the user (`user`), the chat (`chat`), or a
+/// missing role (`role`)
+pub(crate) fn authorization_denied(endpoint: &str, reason: &str) {
+    #[cfg(feature = "metrics")]
+    tracing::info!(
+        target: "metrics",
+        venue = VENUE,
+        monotonic_counter.telegram_auth_denials = 1u64,
+        endpoint,
+        reason,
+    );
+}
+
+/// An alert sent (`sent`) or given up on (`failed`) by the dispatcher
+pub(crate) fn alert_delivered(level: AlertLevel, outcome: &str) {
+    #[cfg(feature = "metrics")]
+    tracing::info!(
+        target: "metrics",
+        venue = VENUE,
+        monotonic_counter.telegram_alert_deliveries = 1u64,
+        level = ?level,
+        outcome,
+    );
+}
+
+/// Alerts entering (`change` 1) or leaving (-1) a dispatcher queue
+pub(crate) fn alert_queue_depth(priority: Priority, change: i64) {
+    #[cfg(feature = "metrics")]
+    tracing::info!(
+        target: "metrics",
+        venue = VENUE,
+        counter.telegram_alert_queue_depth = change,
+        priority = ?priority,
+    );
+}
+
+/// Time from queueing an alert to Telegram accepting it
+pub(crate) fn alert_delivery_latency(level: AlertLevel, latency: Duration) {
+    #[cfg(feature = "metrics")]
+    tracing::info!(
+        target: "metrics",
+        venue = VENUE,
+        histogram.telegram_alert_latency_ms = latency.as_secs_f64() * 1000.0,
+        level = ?level,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outcome() {
+        assert_eq!(outcome(&Ok(())), "ok");
+        assert_eq!(outcome(&Err(Error::Unauthorized(7))), "denied");
+        assert_eq!(outcome(&Err(Error::Timeout(Duration::from_secs(1)))), "timeout");
+        assert_eq!(outcome(&Err(Error::InvalidCommand("x".into()))), "error");
+    }
+}
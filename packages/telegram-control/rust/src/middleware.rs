@@ -0,0 +1,212 @@
+//! Middleware pipeline
+//!
+//! Middleware wraps every authorized update with hooks that run before and
+//! after the matched handler, for cross-cutting concerns like logging,
+//! metrics and rate limiting. `before` hooks run in registration order and may
+//! rewrite the context or halt the update; `after` hooks run in reverse order
+//! with the handler's result.
+
+use async_trait::async_trait;
+use std::future::Future;
+use std::sync::Arc;
+use tracing::{debug, warn};
+
+use crate::error::Result;
+use crate::types::Context;
+
+/// Whether an update continues down the pipeline
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Flow {
+    Continue,
+    /// Drop the update without running the handler
+    Halt,
+}
+
+/// Hooks around update handling
+#[async_trait]
+pub trait Middleware: Send + Sync {
+    /// Called before the handler; may mutate the context
+    async fn before(&self, _ctx: &mut Context) -> Result<Flow> {
+        Ok(Flow::Continue)
+    }
+
+    /// Called after the handler, or after a later middleware halted the update
+    async fn after(&self, _ctx: &Context, _result: &Result<()>) {}
+}
+
+/// Ordered list of middleware
+#[derive(Clone, Default)]
+pub struct MiddlewareStack {
+    layers: Vec<Arc<dyn Middleware>>,
+}
+
+impl MiddlewareStack {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a middleware; it runs after the ones already added
+    pub fn push(&mut self, middleware: impl Middleware + 'static) {
+        self.layers.push(Arc::new(middleware));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+
+    /// Run an update through the stack and into `handler`
+    ///
+    /// An error from a `before` hook halts the update and is returned as the
+    /// result. Only middleware whose `before` hook ran get the `after` call.
+    pub async fn run<F, Fut>(&self, mut ctx: Context, handler: F) -> Result<()>
+    where
+        F: FnOnce(Context) -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        let mut entered = 0;
+        let mut outcome = None;
+        for layer in &self.layers {
+            entered += 1;
+            match layer.before(&mut ctx).await {
+                Ok(Flow::Continue) => {}
+                Ok(Flow::Halt) => {
+                    outcome = Some(Ok(()));
+                    break;
+                }
+                Err(e) => {
+                    outcome = Some(Err(e));
+                    break;
+                }
+            }
+        }
+
+        let result = match outcome {
+            Some(result) => result,
+            None => handler(ctx.clone()).await,
+        };
+
+        for layer in self.layers[..entered].iter().rev() {
+            layer.after(&ctx, &result).await;
+        }
+        result
+    }
+}
+
+/// Logs every update and its outcome
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LoggingMiddleware;
+
+#[async_trait]
+impl Middleware for LoggingMiddleware {
+    async fn before(&self, ctx: &mut Context) -> Result<Flow> {
+        debug!(user_id = ctx.user_id(), chat_id = ctx.chat_id(), "Received update");
+        Ok(Flow::Continue)
+    }
+
+    async fn after(&self, ctx: &Context, result: &Result<()>) {
+        match result {
+            Ok(()) => debug!(user_id = ctx.user_id(), chat_id = ctx.chat_id(), "Update handled"),
+            Err(e) => warn!(user_id = ctx.user_id(), chat_id = ctx.chat_id(), "Update failed: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+    use crate::types::test_context;
+    use std::sync::Mutex;
+
+    /// Records its hooks and stops updates whose text is `halt_on`
+    struct Recorder {
+        name: &'static str,
+        halt_on: Option<&'static str>,
+        log: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl Middleware for Recorder {
+        async fn before(&self, ctx: &mut Context) -> Result<Flow> {
+            self.log.lock().unwrap().push(format!("{} before", self.name));
+            match ctx.text() {
+                Some("/fail") => Err(Error::InvalidCommand("rejected".to_string())),
+                text if text.is_some() && text == self.halt_on => Ok(Flow::Halt),
+                _ => Ok(Flow::Continue),
+            }
+        }
+
+        async fn after(&self, _ctx: &Context, result: &Result<()>) {
+            self.log.lock().unwrap().push(format!("{} after {}", self.name, result.is_ok()));
+        }
+    }
+
+    fn stack(log: &Arc<Mutex<Vec<String>>>) -> MiddlewareStack {
+        let mut stack = MiddlewareStack::new();
+        for (name, halt_on) in [("a", None), ("b", Some("/halt")), ("c", None)] {
+            stack.push(Recorder { name, halt_on, log: log.clone() });
+        }
+        stack
+    }
+
+    async fn run(stack: &MiddlewareStack, log: &Arc<Mutex<Vec<String>>>, text: &str) -> Result<()> {
+        let handled = log.clone();
+        stack
+            .run(test_context(7, text, None), |_| async move {
+                handled.lock().unwrap().push("handler".to_string());
+                Ok(())
+            })
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_order_and_halt() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let stack = stack(&log);
+
+        assert!(run(&stack, &log, "/status").await.is_ok());
+        assert_eq!(
+            std::mem::take(&mut *log.lock().unwrap()),
+            ["a before", "b before", "c before", "handler", "c after true", "b after true", "a after true"]
+        );
+
+        // Halting skips the handler and the middleware after it
+        assert!(run(&stack, &log, "/halt").await.is_ok());
+        assert_eq!(
+            std::mem::take(&mut *log.lock().unwrap()),
+            ["a before", "b before", "b after true", "a after true"]
+        );
+
+        // An error from the first hook is the result
+        assert!(matches!(run(&stack, &log, "/fail").await, Err(Error::InvalidCommand(_))));
+        assert_eq!(*log.lock().unwrap(), ["a before", "a after false"]);
+    }
+
+    #[tokio::test]
+    async fn test_before_rewrites_context() {
+        struct Lowercase;
+
+        #[async_trait]
+        impl Middleware for Lowercase {
+            async fn before(&self, ctx: &mut Context) -> Result<Flow> {
+                if let Context::Message(message) = ctx {
+                    message.text = message.text.as_ref().map(|t| t.to_lowercase());
+                }
+                Ok(Flow::Continue)
+            }
+        }
+
+        let mut stack = MiddlewareStack::new();
+        stack.push(Lowercase);
+        let seen = Arc::new(Mutex::new(None));
+        let handled = seen.clone();
+        stack
+            .run(test_context(7, "/STATUS", None), |ctx| async move {
+                *handled.lock().unwrap() = ctx.text().map(str::to_string);
+                Ok(())
+            })
+            .await
+            .unwrap();
+        assert_eq!(seen.lock().unwrap().as_deref(), Some("/status"));
+    }
+}
@@ -0,0 +1,261 @@
+//! Paginated lists
+//!
+//! Commands registered with
+//! [`Bot::on_paginated_command`](crate::Bot::on_paginated_command) return a
+//! list of items; the bot shows the first page with the
+//! [`pagination`](crate::keyboards::layouts::pagination) keyboard and edits
+//! the message in place as users page through it. The items are kept until
+//! the list expires, after which the buttons ask the user to run the command
+//! again.
+//!
+//! ```rust,ignore
+//! let bot = bot.on_paginated_command("/trades", Pagination::new(10).with_title("Recent trades"), |ctx| async move {
+//!     Ok(store.recent_trades().await?.iter().map(|t| t.summary()).collect())
+//! });
+//! ```
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use teloxide::payloads::EditMessageTextSetters;
+use teloxide::prelude::*;
+use teloxide::types::{InlineKeyboardMarkup, MessageId, ThreadId};
+use teloxide::{ApiError, RequestError};
+
+use crate::error::{Error, Result};
+use crate::keyboards::layouts;
+use crate::types::Context;
+
+/// Callback data prefix for page buttons
+pub const CALLBACK_PREFIX: &str = "pages:";
+
+/// Page size, title and lifetime of a paginated list
+#[derive(Clone, Debug)]
+pub struct Pagination {
+    page_size: usize,
+    title: Option<String>,
+    ttl: Duration,
+}
+
+impl Default for Pagination {
+    fn default() -> Self {
+        Self {
+            page_size: 10,
+            title: None,
+            ttl: Duration::from_secs(600),
+        }
+    }
+}
+
+impl Pagination {
+    /// `page_size` items per page, kept for 10 minutes
+    pub fn new(page_size: usize) -> Self {
+        Self {
+            page_size: page_size.max(1),
+            ..Self::default()
+        }
+    }
+
+    /// Line shown above the items on every page
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// How long the page buttons keep working
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+}
+
+struct Listing {
+    items: Vec<String>,
+    pagination: Pagination,
+    expires_at: Instant,
+}
+
+impl Listing {
+    fn pages(&self) -> usize {
+        self.items.len().div_ceil(self.pagination.page_size).max(1)
+    }
+
+    /// Text of page `page`, counted from zero
+    fn page_text(&self, page: usize) -> String {
+        let size = self.pagination.page_size;
+        let items = self.items.iter().skip(page * size).take(size);
+        let body = match self.items.is_empty() {
+            true => "No items.".to_string(),
+            false => items.cloned().collect::<Vec<_>>().join("\n"),
+        };
+        match &self.pagination.title {
+            Some(title) => format!("{}\n\n{}", title, body),
+            None => body,
+        }
+    }
+
+    /// Page buttons, or none for a single page
+    fn keyboard(&self, id: u64, page: usize) -> Option<InlineKeyboardMarkup> {
+        let pages = self.pages();
+        (pages > 1).then(|| layouts::pagination(page, pages, &format!("{}{}", CALLBACK_PREFIX, id)))
+    }
+}
+
+/// A page button press: `pages:<id>:page:<n>` or `pages:<id>:noop`
+fn parse_button(data: &str) -> Option<(u64, Option<usize>)> {
+    let mut parts = data.strip_prefix(CALLBACK_PREFIX)?.split(':');
+    let id = parts.next()?.parse().ok()?;
+    let page = match (parts.next()?, parts.next()) {
+        ("page", Some(n)) => Some(n.parse().ok()?),
+        ("noop", None) => None,
+        _ => return None,
+    };
+    Some((id, page))
+}
+
+/// Lists being paged through, shared between the commands and the page
+/// button handler
+#[derive(Clone, Default)]
+pub struct Paginator {
+    listings: Arc<Mutex<HashMap<u64, Listing>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl Paginator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of unexpired lists
+    pub fn active(&self) -> usize {
+        let now = Instant::now();
+        self.listings
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|l| l.expires_at > now)
+            .count()
+    }
+
+    /// Keep `items` and reply with their first page
+    pub async fn send(&self, ctx: &Context, items: Vec<String>, pagination: &Pagination) -> Result<()> {
+        let (text, keyboard) = self.insert(items, pagination);
+        let mut request = ctx.bot().send_message(ChatId(ctx.chat_id()), text);
+        request.message_thread_id = ctx.thread_id().map(|id| ThreadId(MessageId(id)));
+        request.reply_markup = keyboard.map(Into::into);
+        request.await?;
+        Ok(())
+    }
+
+    /// Store a listing, returning its first page
+    fn insert(&self, items: Vec<String>, pagination: &Pagination) -> (String, Option<InlineKeyboardMarkup>) {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let now = Instant::now();
+        let listing = Listing {
+            items,
+            pagination: pagination.clone(),
+            expires_at: now + pagination.ttl,
+        };
+        let page = (listing.page_text(0), listing.keyboard(id, 0));
+        let mut listings = self.listings.lock().unwrap();
+        listings.retain(|_, l| l.expires_at > now);
+        if listing.pages() > 1 {
+            listings.insert(id, listing);
+        }
+        page
+    }
+
+    /// Text and keyboard for a button press, or `None` if the list expired
+    fn turn(&self, id: u64, page: usize) -> Option<(String, Option<InlineKeyboardMarkup>)> {
+        let mut listings = self.listings.lock().unwrap();
+        let listing = listings.get(&id)?;
+        if listing.expires_at <= Instant::now() {
+            listings.remove(&id);
+            return None;
+        }
+        let page = page.min(listing.pages() - 1);
+        Some((listing.page_text(page), listing.keyboard(id, page)))
+    }
+
+    /// Handle a page button press by editing the list message
+    pub(crate) async fn handle_callback(&self, ctx: Context) -> Result<()> {
+        let Context::Callback(cb) = &ctx else {
+            return Ok(());
+        };
+        let (id, page) = parse_button(&cb.data)
+            .ok_or_else(|| Error::InvalidCommand(format!("Malformed callback data: {}", cb.data)))?;
+        let Some(page) = page else {
+            return Ok(());
+        };
+        let Some(message) = &cb.query.message else {
+            return Ok(());
+        };
+        let Some((text, keyboard)) = self.turn(id, page) else {
+            return ctx.answer_alert("⌛ This list has expired, run the command again.").await;
+        };
+
+        let mut request = ctx.bot().edit_message_text(ChatId(ctx.chat_id()), message.id(), text);
+        if let Some(keyboard) = keyboard {
+            request = request.reply_markup(keyboard);
+        }
+        match request.await {
+            Ok(_) | Err(RequestError::Api(ApiError::MessageNotModified)) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use teloxide::types::InlineKeyboardButtonKind;
+
+    fn callbacks(keyboard: &InlineKeyboardMarkup) -> Vec<String> {
+        keyboard.inline_keyboard[0]
+            .iter()
+            .map(|b| match &b.kind {
+                InlineKeyboardButtonKind::CallbackData(data) => data.clone(),
+                _ => String::new(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_pages() {
+        let paginator = Paginator::new();
+        let items: Vec<String> = (1..=5).map(|i| format!("trade {}", i)).collect();
+        let pagination = Pagination::new(2).with_title("Trades");
+
+        let (text, keyboard) = paginator.insert(items, &pagination);
+        assert_eq!(text, "Trades\n\ntrade 1\ntrade 2");
+        assert_eq!(callbacks(&keyboard.unwrap()), vec!["pages:0:noop", "pages:0:page:1"]);
+        assert_eq!(paginator.active(), 1);
+
+        let (text, keyboard) = paginator.turn(0, 2).unwrap();
+        assert_eq!(text, "Trades\n\ntrade 5");
+        assert_eq!(callbacks(&keyboard.unwrap()), vec!["pages:0:page:1", "pages:0:noop"]);
+        assert_eq!(paginator.turn(0, 9).unwrap().0, "Trades\n\ntrade 5");
+        assert!(paginator.turn(1, 0).is_none());
+
+        // Single pages get no buttons and are not kept
+        let (text, keyboard) = paginator.insert(Vec::new(), &Pagination::new(2));
+        assert_eq!(text, "No items.");
+        assert!(keyboard.is_none());
+        assert_eq!(paginator.active(), 1);
+    }
+
+    #[test]
+    fn test_expiry_and_buttons() {
+        let paginator = Paginator::new();
+        let items = vec!["a".to_string(), "b".to_string()];
+        paginator.insert(items, &Pagination::new(1).with_ttl(Duration::ZERO));
+        assert!(paginator.turn(0, 1).is_none());
+        assert_eq!(paginator.active(), 0);
+
+        assert_eq!(parse_button("pages:3:page:2"), Some((3, Some(2))));
+        assert_eq!(parse_button("pages:3:noop"), Some((3, None)));
+        assert_eq!(parse_button("pages:3:page"), None);
+        assert_eq!(parse_button("pages:x:noop"), None);
+    }
+}
@@ -0,0 +1,210 @@
+//! Quiet hours
+//!
+//! During a chat's [`QuietHours`] the [`AlertDispatcher`](crate::AlertDispatcher)
+//! holds back alerts below [`AlertLevel::Critical`] and sends them as one
+//! digest when the quiet period ends. Critical alerts always go straight
+//! through. Times are in UTC unless an offset is set.
+//!
+//! ```rust,ignore
+//! let quiet = QuietHours::parse("23:00-07:00")?.with_utc_offset(120);
+//! dispatcher.set_quiet_hours(chat_id, Some(quiet));
+//! ```
+
+use chrono::{DateTime, Duration, NaiveTime, Utc};
+use std::fmt;
+
+use crate::alerts::AlertLevel;
+use crate::dispatcher::Alert;
+use crate::error::{Error, Result};
+use crate::format;
+
+/// Daily period during which non-critical alerts are held for a digest
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct QuietHours {
+    start: NaiveTime,
+    end: NaiveTime,
+    /// Minutes east of UTC that `start` and `end` are given in
+    utc_offset: i32,
+    /// Alerts at or above this level are never held
+    pass_level: AlertLevel,
+}
+
+impl QuietHours {
+    /// Quiet from `start` until `end`, wrapping past midnight if `end` is earlier
+    pub fn new(start: NaiveTime, end: NaiveTime) -> Self {
+        Self {
+            start,
+            end,
+            utc_offset: 0,
+            pass_level: AlertLevel::Critical,
+        }
+    }
+
+    /// Parse `HH:MM-HH:MM`, e.g. `23:00-07:00`
+    pub fn parse(s: &str) -> Result<Self> {
+        let invalid = || Error::Config(format!("Invalid quiet hours '{}', expected HH:MM-HH:MM", s));
+        let (start, end) = s.trim().split_once('-').ok_or_else(invalid)?;
+        let time = |t: &str| NaiveTime::parse_from_str(t.trim(), "%H:%M").map_err(|_| invalid());
+        Ok(Self::new(time(start)?, time(end)?))
+    }
+
+    /// Interpret the times as local to a zone `minutes` east of UTC
+    pub fn with_utc_offset(mut self, minutes: i32) -> Self {
+        self.utc_offset = minutes;
+        self
+    }
+
+    /// Let alerts at or above `level` through (default: critical only)
+    pub fn pass_through(mut self, level: AlertLevel) -> Self {
+        self.pass_level = level;
+        self
+    }
+
+    /// Whether alerts of `level` are held during quiet hours
+    pub fn holds(&self, level: AlertLevel) -> bool {
+        level < self.pass_level
+    }
+
+    /// Whether `at` falls inside the quiet period
+    pub fn contains(&self, at: DateTime<Utc>) -> bool {
+        let time = (at + self.offset()).time();
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+
+    /// First end of the quiet period after `at`
+    pub fn end_after(&self, at: DateTime<Utc>) -> DateTime<Utc> {
+        let local = at.naive_utc() + self.offset();
+        let mut end = local.date().and_time(self.end);
+        if end <= local {
+            end += Duration::days(1);
+        }
+        (end - self.offset()).and_utc()
+    }
+
+    fn offset(&self) -> Duration {
+        Duration::minutes(self.utc_offset as i64)
+    }
+}
+
+impl fmt::Display for QuietHours {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.start.format("%H:%M"), self.end.format("%H:%M"))?;
+        match self.utc_offset {
+            0 => write!(f, " UTC"),
+            m if m % 60 == 0 => write!(f, " UTC{:+}", m / 60),
+            m => write!(f, " UTC{:+}:{:02}", m / 60, (m % 60).abs()),
+        }
+    }
+}
+
+/// Combine held alerts into digest messages
+///
+/// Alerts sharing a parse mode and topic are joined under one header, split
+/// to fit Telegram's length limit. Alerts with a photo or document are kept
+/// as they are.
+pub(crate) fn digest(alerts: Vec<Alert>) -> Vec<Alert> {
+    let mut groups: Vec<Vec<Alert>> = Vec::new();
+    let mut with_files = Vec::new();
+    for alert in alerts {
+        if alert.photo.is_some() || alert.document.is_some() {
+            with_files.push(alert);
+            continue;
+        }
+        match groups
+            .iter_mut()
+            .find(|g| g[0].parse_mode == alert.parse_mode && g[0].topic == alert.topic)
+        {
+            Some(group) => group.push(alert),
+            None => groups.push(vec![alert]),
+        }
+    }
+
+    let mut out = Vec::new();
+    for mut group in groups {
+        if group.len() == 1 {
+            out.append(&mut group);
+            continue;
+        }
+        let mode = group[0].parse_mode;
+        let level = group.iter().map(|a| a.level).max().unwrap_or(AlertLevel::Info);
+        let header = format::escape(&format!("🌙 {} alerts during quiet hours", group.len()), mode);
+        let texts: Vec<&str> = group.iter().map(|a| a.text.as_str()).collect();
+        let text = format!("{}\n\n{}", header, texts.join("\n\n"));
+        for chunk in format::split_message(&text, mode) {
+            let mut alert = Alert::formatted(level, chunk, mode);
+            alert.topic = group[0].topic;
+            out.push(alert);
+        }
+    }
+    out.extend(with_files);
+    out
+}
+
+/// Held alerts for one chat
+#[derive(Debug)]
+pub(crate) struct Held {
+    pub(crate) until: DateTime<Utc>,
+    pub(crate) alerts: Vec<Alert>,
+}
+
+impl Held {
+    pub(crate) fn new(until: DateTime<Utc>) -> Self {
+        Self {
+            until,
+            alerts: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(h: u32, m: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, 1, h, m, 0).unwrap()
+    }
+
+    #[test]
+    fn test_window() {
+        let quiet = QuietHours::parse("23:00-07:00").unwrap();
+        assert!(quiet.contains(at(23, 30)));
+        assert!(quiet.contains(at(3, 0)));
+        assert!(!quiet.contains(at(7, 0)));
+        assert!(!quiet.contains(at(12, 0)));
+        assert_eq!(quiet.end_after(at(23, 30)), Utc.with_ymd_and_hms(2024, 3, 2, 7, 0, 0).unwrap());
+        assert_eq!(quiet.end_after(at(3, 0)), at(7, 0));
+
+        // 22:00-06:00 at UTC+2 is 20:00-04:00 UTC
+        let quiet = QuietHours::parse("22:00-06:00").unwrap().with_utc_offset(120);
+        assert!(quiet.contains(at(21, 0)));
+        assert!(!quiet.contains(at(5, 0)));
+        assert_eq!(quiet.end_after(at(21, 0)), Utc.with_ymd_and_hms(2024, 3, 2, 4, 0, 0).unwrap());
+        assert_eq!(quiet.to_string(), "22:00-06:00 UTC+2");
+
+        assert!(quiet.holds(AlertLevel::Error));
+        assert!(!quiet.holds(AlertLevel::Critical));
+        assert!(QuietHours::parse("late").is_err());
+    }
+
+    #[test]
+    fn test_digest() {
+        let held = vec![
+            Alert::new(AlertLevel::Info, "BTC +2%"),
+            Alert::new(AlertLevel::Warning, "ETH funding high"),
+            Alert::new(AlertLevel::Info, "chart").with_photo(vec![1]),
+        ];
+        let digest = digest(held);
+        assert_eq!(digest.len(), 2);
+        assert_eq!(digest[0].level, AlertLevel::Warning);
+        assert_eq!(
+            digest[0].text,
+            "🌙 2 alerts during quiet hours\n\nBTC +2%\n\nETH funding high"
+        );
+        assert!(digest[1].photo.is_some());
+    }
+}
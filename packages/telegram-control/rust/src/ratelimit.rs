@@ -0,0 +1,323 @@
+//! Flood control
+//!
+//! [`RateLimiter`] is a middleware that caps how many updates each user and
+//! each chat may send in a sliding window. Updates over the limit are dropped
+//! before any handler runs, and the user is told once per window when they
+//! can try again.
+//!
+//! Commands can also have a cooldown per user, set with
+//! [`Bot::command_cooldown`](crate::Bot::command_cooldown).
+
+use async_trait::async_trait;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+use crate::error::Result;
+use crate::middleware::{Flow, Middleware};
+use crate::types::Context;
+
+/// How often idle users, chats and cooldowns are swept from memory
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// At most `max` updates per `per`; a `max` of 0 allows none
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RateLimit {
+    pub max: usize,
+    pub per: Duration,
+}
+
+impl RateLimit {
+    pub fn new(max: usize, per: Duration) -> Self {
+        Self { max, per }
+    }
+
+    pub fn per_minute(max: usize) -> Self {
+        Self::new(max, Duration::from_secs(60))
+    }
+}
+
+#[derive(Debug, Default)]
+struct Windows {
+    users: HashMap<i64, VecDeque<Instant>>,
+    chats: HashMap<i64, VecDeque<Instant>>,
+    notified: HashMap<i64, Instant>,
+    last_sweep: Option<Instant>,
+}
+
+impl Windows {
+    /// Forget users and chats whose windows have emptied and notices that
+    /// have lapsed, at most once per [`SWEEP_INTERVAL`]
+    fn sweep(&mut self, per_user: Option<RateLimit>, per_chat: Option<RateLimit>, now: Instant) {
+        if self
+            .last_sweep
+            .is_some_and(|last| now.duration_since(last) < SWEEP_INTERVAL)
+        {
+            return;
+        }
+        self.last_sweep = Some(now);
+        for (windows, limit) in [(&mut self.users, per_user), (&mut self.chats, per_chat)] {
+            match limit {
+                Some(limit) => windows.retain(|_, hits| {
+                    retry_after(hits, limit, now);
+                    !hits.is_empty()
+                }),
+                None => windows.clear(),
+            }
+        }
+        self.notified.retain(|_, until| *until > now);
+    }
+}
+
+/// Per-user and per-chat sliding-window rate limiter
+#[derive(Debug)]
+pub struct RateLimiter {
+    per_user: Option<RateLimit>,
+    per_chat: Option<RateLimit>,
+    cooldown_message: Option<String>,
+    windows: Mutex<Windows>,
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self {
+            per_user: None,
+            per_chat: None,
+            cooldown_message: Some("⏳ Too many requests, try again in {secs}s.".to_string()),
+            windows: Mutex::new(Windows::default()),
+        }
+    }
+}
+
+impl RateLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit updates from each user across all chats
+    pub fn per_user(mut self, limit: RateLimit) -> Self {
+        self.per_user = Some(limit);
+        self
+    }
+
+    /// Limit updates within each chat across all users
+    pub fn per_chat(mut self, limit: RateLimit) -> Self {
+        self.per_chat = Some(limit);
+        self
+    }
+
+    /// Message sent when a user is throttled; `{secs}` is replaced with the
+    /// seconds until the next allowed update. `None` drops updates silently.
+    pub fn cooldown_message(mut self, message: Option<String>) -> Self {
+        self.cooldown_message = message;
+        self
+    }
+
+    /// Record an update, or return how long until it would be allowed
+    ///
+    /// Rejected updates do not count against the window.
+    pub fn check(&self, user_id: i64, chat_id: i64) -> std::result::Result<(), Duration> {
+        self.check_at(user_id, chat_id, Instant::now())
+    }
+
+    fn check_at(&self, user_id: i64, chat_id: i64, now: Instant) -> std::result::Result<(), Duration> {
+        let mut windows = self.windows.lock().unwrap();
+        windows.sweep(self.per_user, self.per_chat, now);
+        let Windows { users, chats, .. } = &mut *windows;
+
+        let mut wait = Duration::ZERO;
+        if let Some(limit) = self.per_user {
+            wait = wait.max(retry_after(users.entry(user_id).or_default(), limit, now));
+        }
+        if let Some(limit) = self.per_chat {
+            wait = wait.max(retry_after(chats.entry(chat_id).or_default(), limit, now));
+        }
+        if !wait.is_zero() {
+            return Err(wait);
+        }
+
+        if self.per_user.is_some() {
+            users.entry(user_id).or_default().push_back(now);
+        }
+        if self.per_chat.is_some() {
+            chats.entry(chat_id).or_default().push_back(now);
+        }
+        Ok(())
+    }
+
+    /// Whether to tell the user they are throttled; true once per window
+    fn should_notify(&self, user_id: i64, wait: Duration) -> bool {
+        let now = Instant::now();
+        let mut windows = self.windows.lock().unwrap();
+        match windows.notified.get(&user_id) {
+            Some(until) if *until > now => false,
+            _ => {
+                windows.notified.insert(user_id, now + wait);
+                true
+            }
+        }
+    }
+}
+
+/// Drop expired hits and return the wait until the window has room
+pub(crate) fn retry_after(hits: &mut VecDeque<Instant>, limit: RateLimit, now: Instant) -> Duration {
+    while hits
+        .front()
+        .is_some_and(|hit| now.duration_since(*hit) >= limit.per)
+    {
+        hits.pop_front();
+    }
+    match hits.front() {
+        _ if limit.max == 0 => limit.per,
+        Some(oldest) if hits.len() >= limit.max => limit.per - now.duration_since(*oldest),
+        _ => Duration::ZERO,
+    }
+}
+
+/// When each command is next allowed per user, for per-command cooldowns
+#[derive(Debug, Default)]
+pub(crate) struct Cooldowns {
+    state: Mutex<CooldownState>,
+}
+
+#[derive(Debug, Default)]
+struct CooldownState {
+    ready_at: HashMap<(String, i64), Instant>,
+    last_sweep: Option<Instant>,
+}
+
+impl Cooldowns {
+    /// Record a run of `command` by `user_id`, or return how long until the
+    /// cooldown allows one
+    pub(crate) fn check(&self, command: &str, user_id: i64, cooldown: Duration) -> std::result::Result<(), Duration> {
+        self.check_at(command, user_id, cooldown, Instant::now())
+    }
+
+    fn check_at(&self, command: &str, user_id: i64, cooldown: Duration, now: Instant) -> std::result::Result<(), Duration> {
+        let mut state = self.state.lock().unwrap();
+        let CooldownState { ready_at, last_sweep } = &mut *state;
+        // Forget cooldowns that have run out
+        if !last_sweep.is_some_and(|last| now.duration_since(last) < SWEEP_INTERVAL) {
+            *last_sweep = Some(now);
+            ready_at.retain(|_, ready| *ready > now);
+        }
+
+        let key = (command.to_string(), user_id);
+        if let Some(ready) = ready_at.get(&key) {
+            if *ready > now {
+                return Err(*ready - now);
+            }
+        }
+        ready_at.insert(key, now + cooldown);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Middleware for RateLimiter {
+    async fn before(&self, ctx: &mut Context) -> Result<Flow> {
+        let user_id = ctx.user_id();
+        match self.check(user_id, ctx.chat_id()) {
+            Ok(()) => Ok(Flow::Continue),
+            Err(wait) => {
+                warn!("Rate limited user {} for {:?}", user_id, wait);
+                if let Some(message) = &self.cooldown_message {
+                    if self.should_notify(user_id, wait) {
+                        let secs = wait.as_secs_f64().ceil() as u64;
+                        ctx.reply(message.replace("{secs}", &secs.to_string())).await?;
+                    }
+                }
+                Ok(Flow::Halt)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_per_user_window() {
+        let limiter = RateLimiter::new().per_user(RateLimit::new(2, Duration::from_secs(10)));
+        let start = Instant::now();
+
+        assert!(limiter.check_at(1, 100, start).is_ok());
+        assert!(limiter.check_at(1, 100, start + Duration::from_secs(1)).is_ok());
+        let wait = limiter.check_at(1, 100, start + Duration::from_secs(2)).unwrap_err();
+        assert_eq!(wait, Duration::from_secs(8));
+
+        // Other users are unaffected, and the window slides
+        assert!(limiter.check_at(2, 100, start + Duration::from_secs(2)).is_ok());
+        assert!(limiter.check_at(1, 100, start + Duration::from_secs(10)).is_ok());
+    }
+
+    #[test]
+    fn test_per_chat_window() {
+        let limiter = RateLimiter::new()
+            .per_user(RateLimit::per_minute(10))
+            .per_chat(RateLimit::new(1, Duration::from_secs(5)));
+        let start = Instant::now();
+
+        assert!(limiter.check_at(1, 100, start).is_ok());
+        assert!(limiter.check_at(2, 100, start).is_err());
+        assert!(limiter.check_at(2, 200, start).is_ok());
+    }
+
+    #[test]
+    fn test_cooldowns() {
+        let cooldowns = Cooldowns::default();
+        let cooldown = Duration::from_secs(600);
+        let start = Instant::now();
+
+        assert!(cooldowns.check_at("/rebalance", 1, cooldown, start).is_ok());
+        let wait = cooldowns
+            .check_at("/rebalance", 1, cooldown, start + Duration::from_secs(60))
+            .unwrap_err();
+        assert_eq!(wait, Duration::from_secs(540));
+
+        // Refused attempts do not restart the cooldown; other users and commands are separate
+        assert!(cooldowns.check_at("/rebalance", 2, cooldown, start).is_ok());
+        assert!(cooldowns.check_at("/status", 1, cooldown, start).is_ok());
+        assert!(cooldowns.check_at("/rebalance", 1, cooldown, start + cooldown).is_ok());
+    }
+
+    #[test]
+    fn test_zero_limit_denies_all() {
+        let limiter = RateLimiter::new().per_user(RateLimit::new(0, Duration::from_secs(10)));
+        let start = Instant::now();
+
+        assert_eq!(limiter.check_at(1, 100, start), Err(Duration::from_secs(10)));
+        assert!(limiter.check_at(1, 100, start + Duration::from_secs(60)).is_err());
+    }
+
+    #[test]
+    fn test_idle_entries_are_swept() {
+        let limiter = RateLimiter::new()
+            .per_user(RateLimit::new(1, Duration::from_secs(10)))
+            .per_chat(RateLimit::new(5, Duration::from_secs(10)));
+        let start = Instant::now();
+
+        for user_id in 0..100 {
+            assert!(limiter.check_at(user_id, user_id, start).is_ok());
+        }
+        assert!(limiter.should_notify(0, Duration::from_secs(10)));
+        assert_eq!(limiter.windows.lock().unwrap().users.len(), 100);
+
+        assert!(limiter.check_at(1000, 1000, start + SWEEP_INTERVAL).is_ok());
+        let windows = limiter.windows.lock().unwrap();
+        assert_eq!(windows.users.keys().collect::<Vec<_>>(), [&1000]);
+        assert_eq!(windows.chats.len(), 1);
+        assert!(windows.notified.is_empty());
+        drop(windows);
+
+        let cooldowns = Cooldowns::default();
+        let cooldown = Duration::from_secs(10);
+        for user_id in 0..100 {
+            assert!(cooldowns.check_at("/rebalance", user_id, cooldown, start).is_ok());
+        }
+        assert!(cooldowns.check_at("/rebalance", 1000, cooldown, start + SWEEP_INTERVAL).is_ok());
+        assert_eq!(cooldowns.state.lock().unwrap().ready_at.len(), 1);
+    }
+}
@@ -0,0 +1,384 @@
+//! Error reports
+//!
+//! Handler errors normally only reach the logs. An [`ErrorReporter`] also
+//! sends each one to an admin chat with the command, the user and, for
+//! panics, where it happened. Reports are rate limited so a failing exchange
+//! at night produces a few messages rather than hundreds; the next report
+//! that gets through says how many were skipped.
+//!
+//! Panics in handlers are caught whether or not reports are enabled, and the
+//! user gets the usual error reply. This needs a build with
+//! `panic = "unwind"` (the default): the workspace release profile sets
+//! `panic = "abort"`, and under it a panicking handler stops the whole
+//! process before it can be caught. The bot logs a warning at startup when
+//! built that way.
+//!
+//! ```rust,ignore
+//! let bot = Bot::new(token).build().with_error_reports(ADMIN_CHAT);
+//! ```
+
+use chrono::{DateTime, Utc};
+use futures::FutureExt;
+use std::backtrace::Backtrace;
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::pin;
+use std::sync::{Arc, Mutex, Once};
+use std::time::{Duration, Instant};
+
+use crate::alerts::AlertLevel;
+use crate::dispatcher::{Alert, AlertSink};
+use crate::error::{Error, Result};
+use crate::ratelimit::{retry_after, RateLimit};
+use crate::types::Context;
+
+/// Frames kept in a backtrace summary
+const MAX_FRAMES: usize = 8;
+/// Longest input or error text included in a report
+const MAX_TEXT: usize = 500;
+/// Crates whose frames are runtime plumbing rather than handler code
+const SKIPPED_CRATES: &[&str] = &[
+    "std", "core", "alloc", "tokio", "futures", "futures_util", "futures_core", "teloxide",
+    "teloxide_core", "dptree",
+];
+
+thread_local! {
+    /// Location and backtrace summary of the last panic on this thread
+    static LAST_PANIC: RefCell<Option<String>> = const { RefCell::new(None) };
+    /// Whether this thread is polling a handler inside [`catch_panic`]
+    static IN_HANDLER: Cell<bool> = const { Cell::new(false) };
+}
+
+static PANIC_HOOK: Once = Once::new();
+
+/// Record a backtrace summary for handler panics, then run the previous
+/// hook
+///
+/// Panics elsewhere in the process go straight to the previous hook, so
+/// they don't pay for a backtrace `RUST_BACKTRACE` didn't ask for.
+fn install_panic_hook() {
+    PANIC_HOOK.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if !IN_HANDLER.get() {
+                return previous(info);
+            }
+            let mut lines: Vec<String> = info
+                .location()
+                .map(|l| format!("at {}:{}", l.file(), l.line()))
+                .into_iter()
+                .collect();
+            lines.extend(summarize(&Backtrace::force_capture().to_string()));
+            LAST_PANIC.with(|last| *last.borrow_mut() = Some(lines.join("\n")));
+            previous(info);
+        }));
+    });
+}
+
+/// Warn when panics abort the process, since handler panics can't be caught
+pub(crate) fn check_unwind() {
+    if cfg!(panic = "abort") {
+        tracing::warn!(
+            "Built with panic = \"abort\": a panicking handler stops the bot instead of being reported; \
+             build with panic = \"unwind\" to catch handler panics"
+        );
+    }
+}
+
+/// Restores [`IN_HANDLER`] after a poll, including one that panics
+struct HandlerScope(bool);
+
+impl HandlerScope {
+    fn enter() -> Self {
+        Self(IN_HANDLER.replace(true))
+    }
+}
+
+impl Drop for HandlerScope {
+    fn drop(&mut self) {
+        IN_HANDLER.set(self.0);
+    }
+}
+
+/// Run `future`, turning a panic into [`Error::Panic`]
+///
+/// Also returns the panic's location and backtrace summary, if the panic
+/// hook is installed. Only works when panics unwind; see [`check_unwind`].
+pub(crate) async fn catch_panic(future: impl Future<Output = Result<()>>) -> (Result<()>, Option<String>) {
+    let mut future = pin!(future);
+    let handler = std::future::poll_fn(|cx| {
+        let _scope = HandlerScope::enter();
+        future.as_mut().poll(cx)
+    });
+    match AssertUnwindSafe(handler).catch_unwind().await {
+        Ok(result) => (result, None),
+        Err(payload) => {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string());
+            // The hook ran on this thread while unwinding, just before this poll returned
+            let trace = LAST_PANIC.with(|last| last.borrow_mut().take());
+            (Err(Error::Panic(message)), trace)
+        }
+    }
+}
+
+/// Application frames of a formatted [`Backtrace`], innermost first
+fn summarize(backtrace: &str) -> Vec<String> {
+    let mut frames = Vec::new();
+    let mut lines = backtrace.lines().map(str::trim).peekable();
+    while let Some(line) = lines.next() {
+        let Some((index, function)) = line.split_once(": ") else {
+            continue;
+        };
+        if index.parse::<usize>().is_err() {
+            continue;
+        }
+        let location = lines.next_if(|l| l.starts_with("at ")).map(|l| &l[3..]);
+        let krate = function.trim_start_matches('<').split("::").next().unwrap_or_default();
+        if SKIPPED_CRATES.contains(&krate)
+            || krate.starts_with("__rust")
+            || krate.starts_with("rust_")
+            || function.contains("telegram_control::report::")
+        {
+            continue;
+        }
+        frames.push(match location {
+            Some(location) => format!("{} ({})", function, location),
+            None => function.to_string(),
+        });
+        if frames.len() == MAX_FRAMES {
+            break;
+        }
+    }
+    frames
+}
+
+fn truncate(text: &str) -> String {
+    match text.char_indices().nth(MAX_TEXT) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+/// A failed handler, as sent to the admin chat
+#[derive(Clone, Debug)]
+pub struct ErrorReport {
+    pub timestamp: DateTime<Utc>,
+    pub user_id: Option<i64>,
+    pub username: Option<String>,
+    pub chat_id: Option<i64>,
+    /// Command text or callback data
+    pub input: Option<String>,
+    pub error: String,
+    /// Panic location and the innermost application frames
+    pub trace: Option<String>,
+}
+
+impl ErrorReport {
+    pub fn new(error: impl ToString) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            user_id: None,
+            username: None,
+            chat_id: None,
+            input: None,
+            error: error.to_string(),
+            trace: None,
+        }
+    }
+
+    /// Report for an update; plain message text is left out since it may
+    /// carry PINs or other replies to prompts
+    pub fn from_context(ctx: &Context, error: &Error) -> Self {
+        let input = match ctx {
+            Context::Message(msg) => msg.text.as_deref().filter(|t| t.starts_with('/')).map(str::to_string),
+            Context::Callback(cb) => Some(format!("callback: {}", cb.data)),
+        };
+        Self {
+            user_id: Some(ctx.user_id()),
+            username: ctx.username().map(str::to_string),
+            chat_id: Some(ctx.chat_id()),
+            input,
+            ..Self::new(error)
+        }
+    }
+
+    pub fn with_trace(mut self, trace: Option<String>) -> Self {
+        self.trace = trace;
+        self
+    }
+
+    /// Plain-text message for the admin chat
+    pub fn text(&self) -> String {
+        let mut text = format!("🚨 Handler failed at {}\n", self.timestamp.format("%Y-%m-%d %H:%M:%S UTC"));
+        if let Some(user_id) = self.user_id {
+            text.push_str(&format!("User: {} ({})\n", self.username.as_deref().unwrap_or("-"), user_id));
+        }
+        if let Some(chat_id) = self.chat_id.filter(|chat| Some(*chat) != self.user_id) {
+            text.push_str(&format!("Chat: {}\n", chat_id));
+        }
+        if let Some(input) = &self.input {
+            text.push_str(&format!("Input: {}\n", truncate(input)));
+        }
+        text.push_str(&format!("Error: {}", truncate(&self.error)));
+        if let Some(trace) = &self.trace {
+            text.push_str(&format!("\n\nTrace:\n{}", trace));
+        }
+        text
+    }
+}
+
+#[derive(Debug, Default)]
+struct Window {
+    sent: VecDeque<Instant>,
+    /// Reports dropped since the last one sent
+    suppressed: usize,
+}
+
+/// Sends [`ErrorReport`]s to an admin chat, rate limited
+pub struct ErrorReporter {
+    sink: Arc<dyn AlertSink>,
+    chat_id: i64,
+    limit: RateLimit,
+    window: Mutex<Window>,
+}
+
+impl ErrorReporter {
+    /// Report to `chat_id`, at most 5 reports per 10 minutes
+    ///
+    /// Installs a panic hook that records backtraces for reports; it calls
+    /// the hook that was set before.
+    pub fn new(sink: Arc<dyn AlertSink>, chat_id: i64) -> Self {
+        install_panic_hook();
+        Self {
+            sink,
+            chat_id,
+            limit: RateLimit::new(5, Duration::from_secs(600)),
+            window: Mutex::new(Window::default()),
+        }
+    }
+
+    pub fn with_limit(mut self, limit: RateLimit) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    pub fn chat_id(&self) -> i64 {
+        self.chat_id
+    }
+
+    /// Send `report`; returns `false` if it was dropped by the rate limit
+    pub async fn report(&self, report: ErrorReport) -> Result<bool> {
+        let Some(suppressed) = self.admit(Instant::now()) else {
+            return Ok(false);
+        };
+        let mut text = report.text();
+        if suppressed > 0 {
+            text.push_str(&format!("\n\n({} earlier reports were rate limited)", suppressed));
+        }
+        self.sink
+            .send_alert(self.chat_id, &Alert::new(AlertLevel::Error, text))
+            .await?;
+        Ok(true)
+    }
+
+    /// Record a report if the window has room, returning how many were
+    /// dropped before it
+    fn admit(&self, now: Instant) -> Option<usize> {
+        let mut window = self.window.lock().unwrap();
+        if !retry_after(&mut window.sent, self.limit, now).is_zero() {
+            window.suppressed += 1;
+            return None;
+        }
+        window.sent.push_back(now);
+        Some(std::mem::take(&mut window.suppressed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+
+    #[derive(Default)]
+    struct RecordingSink {
+        sent: Mutex<Vec<(i64, String)>>,
+    }
+
+    #[async_trait]
+    impl AlertSink for RecordingSink {
+        async fn send_alert(&self, chat_id: i64, alert: &Alert) -> Result<()> {
+            self.sent.lock().unwrap().push((chat_id, alert.text.clone()));
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_reports() {
+        let sink = Arc::new(RecordingSink::default());
+        let reporter = ErrorReporter::new(sink.clone(), -100).with_limit(RateLimit::new(2, Duration::from_secs(60)));
+        let report = ErrorReport {
+            user_id: Some(7),
+            username: Some("alice".to_string()),
+            chat_id: Some(7),
+            input: Some("/trade BTC 1".to_string()),
+            ..ErrorReport::new("exchange timeout")
+        };
+
+        for _ in 0..4 {
+            reporter.report(report.clone()).await.unwrap();
+        }
+        let sent = sink.sent.lock().unwrap();
+        assert_eq!(sent.len(), 2);
+        assert_eq!(sent[0].0, -100);
+        assert!(sent[0].1.contains("User: alice (7)\nInput: /trade BTC 1\nError: exchange timeout"));
+        assert!(!sent[0].1.contains("Chat:"));
+        drop(sent);
+
+        let later = Instant::now() + Duration::from_secs(61);
+        assert_eq!(reporter.admit(later), Some(2));
+        assert_eq!(reporter.admit(later), Some(0));
+    }
+
+    #[tokio::test]
+    async fn test_catch_panic() {
+        install_panic_hook();
+        let (result, trace) = catch_panic(async { panic!("index {} out of range", 3) }).await;
+        assert!(matches!(result, Err(Error::Panic(m)) if m == "index 3 out of range"));
+        assert!(trace.unwrap().starts_with(&format!("at {}:", file!())));
+        assert!(!IN_HANDLER.get());
+
+        // Panics outside handlers are left to the previous hook
+        let _ = panic::catch_unwind(|| panic!("elsewhere"));
+        assert!(LAST_PANIC.with(|last| last.borrow().is_none()));
+
+        let (result, trace) = catch_panic(async { Err(Error::Dispatch("closed".into())) }).await;
+        assert!(matches!(result, Err(Error::Dispatch(_))));
+        assert!(trace.is_none());
+    }
+
+    #[test]
+    fn test_summarize() {
+        let backtrace = "   0: std::backtrace::Backtrace::force_capture
+             at /rustc/abc/library/std/src/backtrace.rs:312:9
+   1: telegram_control::report::install_panic_hook::{{closure}}
+   2: my_bot::handlers::trade::{{closure}}
+             at ./src/handlers.rs:42:17
+   3: <core::pin::Pin<P> as core::future::future::Future>::poll
+   4: tokio::runtime::task::harness::poll_future
+   5: <my_bot::Risk as my_bot::Check>::run
+";
+        assert_eq!(
+            summarize(backtrace),
+            vec![
+                "my_bot::handlers::trade::{{closure}} (./src/handlers.rs:42:17)".to_string(),
+                "<my_bot::Risk as my_bot::Check>::run".to_string(),
+            ]
+        );
+    }
+}
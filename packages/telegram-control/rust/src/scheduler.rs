@@ -0,0 +1,351 @@
+//! Scheduled jobs
+//!
+//! Job kinds (a daily PnL summary, an hourly heartbeat) are registered in code
+//! by name; schedules that run them for a chat are data and can be added or
+//! removed at runtime. With a [`SessionStore`] attached, schedules are saved
+//! and restored across restarts. All times are UTC.
+
+use chrono::{DateTime, Duration as ChronoDuration, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+
+use crate::error::{Error, Result};
+use crate::session::SessionStore;
+
+/// Session scope and key that schedules are persisted under
+const STORE_SCOPE: i64 = 0;
+const STORE_KEY: &str = "scheduler.entries";
+
+/// When a job runs
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Schedule {
+    /// Fixed interval from when the schedule was added
+    Every { secs: u64 },
+    /// Every hour at `minute`
+    Hourly { minute: u32 },
+    /// Every day at `hour:minute`
+    Daily { hour: u32, minute: u32 },
+}
+
+impl Schedule {
+    /// Parse `every 30m`, `every 2h`, `hourly :15` or `daily 00:00`
+    pub fn parse(s: &str) -> Result<Self> {
+        let invalid = || Error::Config(format!("Invalid schedule: {}", s));
+        let (kind, arg) = s.trim().split_once(' ').ok_or_else(invalid)?;
+        let arg = arg.trim();
+        let schedule = match kind {
+            "every" => {
+                let (num, unit) = arg.split_at(arg.len().saturating_sub(1));
+                let n: u64 = num.parse().map_err(|_| invalid())?;
+                let secs = match unit {
+                    "s" => n,
+                    "m" => n * 60,
+                    "h" => n * 3600,
+                    "d" => n * 86_400,
+                    _ => return Err(invalid()),
+                };
+                Schedule::Every { secs }
+            }
+            "hourly" => Schedule::Hourly {
+                minute: arg.trim_start_matches(':').parse().map_err(|_| invalid())?,
+            },
+            "daily" => {
+                let (hour, minute) = arg.split_once(':').ok_or_else(invalid)?;
+                Schedule::Daily {
+                    hour: hour.parse().map_err(|_| invalid())?,
+                    minute: minute.parse().map_err(|_| invalid())?,
+                }
+            }
+            _ => return Err(invalid()),
+        };
+        schedule.validate().map_err(|_| invalid())?;
+        Ok(schedule)
+    }
+
+    fn validate(&self) -> Result<()> {
+        let ok = match *self {
+            Schedule::Every { secs } => secs > 0,
+            Schedule::Hourly { minute } => minute < 60,
+            Schedule::Daily { hour, minute } => hour < 24 && minute < 60,
+        };
+        if ok {
+            Ok(())
+        } else {
+            Err(Error::Config(format!("Invalid schedule: {}", self)))
+        }
+    }
+
+    /// First run strictly after `after`
+    pub fn next_after(&self, after: DateTime<Utc>) -> DateTime<Utc> {
+        let floor_minute = after.with_second(0).and_then(|t| t.with_nanosecond(0)).unwrap_or(after);
+        match *self {
+            Schedule::Every { secs } => after + ChronoDuration::seconds(secs as i64),
+            Schedule::Hourly { minute } => {
+                let candidate = floor_minute.with_minute(minute).unwrap_or(floor_minute);
+                if candidate > after {
+                    candidate
+                } else {
+                    candidate + ChronoDuration::hours(1)
+                }
+            }
+            Schedule::Daily { hour, minute } => {
+                let candidate = floor_minute
+                    .with_hour(hour)
+                    .and_then(|t| t.with_minute(minute))
+                    .unwrap_or(floor_minute);
+                if candidate > after {
+                    candidate
+                } else {
+                    candidate + ChronoDuration::days(1)
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Schedule::Every { secs } => write!(f, "every {}s", secs),
+            Schedule::Hourly { minute } => write!(f, "hourly :{:02}", minute),
+            Schedule::Daily { hour, minute } => write!(f, "daily {:02}:{:02}", hour, minute),
+        }
+    }
+}
+
+/// A job kind scheduled for a chat
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ScheduleEntry {
+    pub id: u64,
+    /// Name the job kind was registered under
+    pub job: String,
+    pub chat_id: i64,
+    pub schedule: Schedule,
+    pub next_run: DateTime<Utc>,
+}
+
+/// What a job gets when it runs
+#[derive(Clone, Debug)]
+pub struct JobContext {
+    pub bot: teloxide::Bot,
+    pub entry: ScheduleEntry,
+}
+
+impl JobContext {
+    pub fn chat_id(&self) -> i64 {
+        self.entry.chat_id
+    }
+}
+
+/// Job function type
+pub type JobFn = Arc<
+    dyn Fn(JobContext) -> Pin<Box<dyn Future<Output = Result<()>> + Send>> + Send + Sync,
+>;
+
+/// Runs registered jobs on their schedules
+#[derive(Clone, Default)]
+pub struct Scheduler {
+    jobs: Arc<RwLock<HashMap<String, JobFn>>>,
+    entries: Arc<RwLock<Vec<ScheduleEntry>>>,
+    store: Option<Arc<dyn SessionStore>>,
+    changed: Arc<Notify>,
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Persist schedules in `store`, restoring any saved ones
+    pub async fn with_store(mut self, store: Arc<dyn SessionStore>) -> Result<Self> {
+        if let Some(saved) = store.get::<Vec<ScheduleEntry>>(STORE_SCOPE, STORE_KEY).await? {
+            info!("Restored {} scheduled jobs", saved.len());
+            *self.entries.write().unwrap() = saved;
+        }
+        self.store = Some(store);
+        Ok(self)
+    }
+
+    /// Register a job kind under `name`
+    pub fn register<F, Fut>(&self, name: impl Into<String>, job: F)
+    where
+        F: Fn(JobContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let job: JobFn = Arc::new(move |ctx| Box::pin(job(ctx)));
+        self.jobs.write().unwrap().insert(name.into(), job);
+    }
+
+    /// Schedule a registered job for a chat, returning the schedule ID
+    pub async fn schedule(&self, job: &str, chat_id: i64, schedule: Schedule) -> Result<u64> {
+        schedule.validate()?;
+        if !self.jobs.read().unwrap().contains_key(job) {
+            return Err(Error::Config(format!("Unknown job: {}", job)));
+        }
+        let id = {
+            let mut entries = self.entries.write().unwrap();
+            let id = entries.iter().map(|e| e.id).max().map_or(1, |max| max + 1);
+            entries.push(ScheduleEntry {
+                id,
+                job: job.to_string(),
+                chat_id,
+                schedule,
+                next_run: schedule.next_after(Utc::now()),
+            });
+            id
+        };
+        self.save().await?;
+        self.changed.notify_one();
+        Ok(id)
+    }
+
+    /// Remove a schedule; returns whether it existed
+    pub async fn unschedule(&self, id: u64) -> Result<bool> {
+        let removed = {
+            let mut entries = self.entries.write().unwrap();
+            let before = entries.len();
+            entries.retain(|e| e.id != id);
+            entries.len() != before
+        };
+        if removed {
+            self.save().await?;
+            self.changed.notify_one();
+        }
+        Ok(removed)
+    }
+
+    /// Current schedules, soonest first
+    pub fn entries(&self) -> Vec<ScheduleEntry> {
+        let mut entries = self.entries.read().unwrap().clone();
+        entries.sort_by_key(|e| e.next_run);
+        entries
+    }
+
+    /// Run due jobs in the background until the handle is aborted
+    pub fn start(&self, bot: teloxide::Bot) -> JoinHandle<()> {
+        let scheduler = self.clone();
+        tokio::spawn(async move {
+            loop {
+                let next = scheduler.entries.read().unwrap().iter().map(|e| e.next_run).min();
+                let wait = next
+                    .map(|at| (at - Utc::now()).to_std().unwrap_or(Duration::ZERO))
+                    .unwrap_or(Duration::from_secs(3600));
+                tokio::select! {
+                    _ = tokio::time::sleep(wait) => scheduler.run_due(&bot).await,
+                    _ = scheduler.changed.notified() => {}
+                }
+            }
+        })
+    }
+
+    async fn run_due(&self, bot: &teloxide::Bot) {
+        let now = Utc::now();
+        let due: Vec<ScheduleEntry> = {
+            let mut entries = self.entries.write().unwrap();
+            let mut due = Vec::new();
+            for entry in entries.iter_mut().filter(|e| e.next_run <= now) {
+                due.push(entry.clone());
+                entry.next_run = entry.schedule.next_after(now);
+            }
+            due
+        };
+        if due.is_empty() {
+            return;
+        }
+        if let Err(e) = self.save().await {
+            error!("Failed to save schedules: {}", e);
+        }
+
+        for entry in due {
+            let job = self.jobs.read().unwrap().get(&entry.job).cloned();
+            let Some(job) = job else {
+                error!("Scheduled job {} is not registered", entry.job);
+                continue;
+            };
+            let name = entry.job.clone();
+            let ctx = JobContext {
+                bot: bot.clone(),
+                entry,
+            };
+            // Run each job on its own task so a slow report doesn't delay others
+            tokio::spawn(async move {
+                if let Err(e) = job(ctx).await {
+                    error!("Scheduled job {} failed: {}", name, e);
+                }
+            });
+        }
+    }
+
+    async fn save(&self) -> Result<()> {
+        let Some(store) = &self.store else {
+            return Ok(());
+        };
+        let entries = self.entries.read().unwrap().clone();
+        store.set(STORE_SCOPE, STORE_KEY, &entries).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::MemorySessionStore;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_next_after() {
+        let at = Utc.with_ymd_and_hms(2024, 3, 1, 10, 30, 15).unwrap();
+        assert_eq!(
+            Schedule::Daily { hour: 0, minute: 0 }.next_after(at),
+            Utc.with_ymd_and_hms(2024, 3, 2, 0, 0, 0).unwrap()
+        );
+        assert_eq!(
+            Schedule::Daily { hour: 12, minute: 0 }.next_after(at),
+            Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap()
+        );
+        assert_eq!(
+            Schedule::Hourly { minute: 30 }.next_after(at),
+            Utc.with_ymd_and_hms(2024, 3, 1, 11, 30, 0).unwrap()
+        );
+        assert_eq!(
+            Schedule::Every { secs: 90 }.next_after(at),
+            Utc.with_ymd_and_hms(2024, 3, 1, 10, 31, 45).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Schedule::parse("daily 00:00").unwrap(), Schedule::Daily { hour: 0, minute: 0 });
+        assert_eq!(Schedule::parse("hourly :15").unwrap(), Schedule::Hourly { minute: 15 });
+        assert_eq!(Schedule::parse("every 30m").unwrap(), Schedule::Every { secs: 1800 });
+        assert!(Schedule::parse("daily 25:00").is_err());
+        assert!(Schedule::parse("weekly mon").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_schedules_persist() {
+        let store: Arc<dyn SessionStore> = Arc::new(MemorySessionStore::new());
+        let scheduler = Scheduler::new().with_store(store.clone()).await.unwrap();
+        scheduler.register("heartbeat", |_ctx| async { Ok(()) });
+
+        let id = scheduler
+            .schedule("heartbeat", 42, Schedule::Hourly { minute: 0 })
+            .await
+            .unwrap();
+        assert!(scheduler.schedule("missing", 42, Schedule::Hourly { minute: 0 }).await.is_err());
+
+        let restored = Scheduler::new().with_store(store).await.unwrap();
+        assert_eq!(restored.entries().len(), 1);
+        assert_eq!(restored.entries()[0].chat_id, 42);
+        assert!(restored.unschedule(id).await.unwrap());
+        assert!(restored.entries().is_empty());
+    }
+}
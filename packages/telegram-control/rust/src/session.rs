@@ -0,0 +1,252 @@
+//! Session storage
+//!
+//! A [`SessionStore`] keeps small JSON values per user or chat (settings,
+//! preferences, dialogue state) so they survive bot restarts. Use
+//! [`MemorySessionStore`] for tests and throwaway bots, or `SqliteSessionStore`
+//! behind the `sqlite` feature for persistence.
+
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::{Arc, RwLock};
+
+use crate::dialogue::DialogueStorage;
+use crate::error::Result;
+
+/// Key-value store scoped by user or chat ID
+#[async_trait]
+pub trait SessionStore: Send + Sync {
+    /// Raw JSON value stored under `key` for `scope`
+    async fn get_raw(&self, scope: i64, key: &str) -> Result<Option<String>>;
+
+    /// Store a raw JSON value under `key` for `scope`
+    async fn set_raw(&self, scope: i64, key: &str, value: String) -> Result<()>;
+
+    /// Remove `key` for `scope`
+    async fn remove(&self, scope: i64, key: &str) -> Result<()>;
+
+    /// Remove every key for `scope`
+    async fn clear(&self, scope: i64) -> Result<()>;
+
+    /// Write out buffered changes; called on bot shutdown
+    ///
+    /// Stores that write through on every call need not override this.
+    async fn flush(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl dyn SessionStore {
+    /// Typed value stored under `key` for `scope`
+    pub async fn get<T: DeserializeOwned>(&self, scope: i64, key: &str) -> Result<Option<T>> {
+        match self.get_raw(scope, key).await? {
+            Some(raw) => Ok(Some(serde_json::from_str(&raw)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Store a typed value under `key` for `scope`
+    pub async fn set<T: Serialize + Sync>(&self, scope: i64, key: &str, value: &T) -> Result<()> {
+        self.set_raw(scope, key, serde_json::to_string(value)?).await
+    }
+}
+
+/// In-memory session store; contents are lost on restart
+#[derive(Debug, Default)]
+pub struct MemorySessionStore {
+    values: RwLock<HashMap<(i64, String), String>>,
+}
+
+impl MemorySessionStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl SessionStore for MemorySessionStore {
+    async fn get_raw(&self, scope: i64, key: &str) -> Result<Option<String>> {
+        Ok(self.values.read().unwrap().get(&(scope, key.to_string())).cloned())
+    }
+
+    async fn set_raw(&self, scope: i64, key: &str, value: String) -> Result<()> {
+        self.values.write().unwrap().insert((scope, key.to_string()), value);
+        Ok(())
+    }
+
+    async fn remove(&self, scope: i64, key: &str) -> Result<()> {
+        self.values.write().unwrap().remove(&(scope, key.to_string()));
+        Ok(())
+    }
+
+    async fn clear(&self, scope: i64) -> Result<()> {
+        self.values.write().unwrap().retain(|(s, _), _| *s != scope);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteSessionStore;
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use super::*;
+    use rusqlite::{params, Connection, OptionalExtension};
+    use std::path::Path;
+    use std::sync::Mutex;
+
+    const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS sessions (
+        scope       INTEGER NOT NULL,
+        key         TEXT NOT NULL,
+        value       TEXT NOT NULL,
+        updated_at  TEXT NOT NULL,
+        PRIMARY KEY (scope, key)
+    );
+    ";
+
+    /// Sqlite-backed session store
+    pub struct SqliteSessionStore {
+        conn: Mutex<Connection>,
+    }
+
+    impl SqliteSessionStore {
+        /// Open or create a session database at `path`
+        pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+            Self::init(Connection::open(path)?)
+        }
+
+        /// In-memory database, for tests
+        pub fn open_in_memory() -> Result<Self> {
+            Self::init(Connection::open_in_memory()?)
+        }
+
+        fn init(conn: Connection) -> Result<Self> {
+            conn.execute_batch(SCHEMA)?;
+            Ok(Self { conn: Mutex::new(conn) })
+        }
+    }
+
+    #[async_trait]
+    impl SessionStore for SqliteSessionStore {
+        async fn get_raw(&self, scope: i64, key: &str) -> Result<Option<String>> {
+            let conn = self.conn.lock().unwrap();
+            Ok(conn
+                .query_row(
+                    "SELECT value FROM sessions WHERE scope = ?1 AND key = ?2",
+                    params![scope, key],
+                    |row| row.get(0),
+                )
+                .optional()?)
+        }
+
+        async fn set_raw(&self, scope: i64, key: &str, value: String) -> Result<()> {
+            let conn = self.conn.lock().unwrap();
+            conn.execute(
+                "INSERT INTO sessions (scope, key, value, updated_at) VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT (scope, key) DO UPDATE SET value = ?3, updated_at = ?4",
+                params![scope, key, value, chrono::Utc::now().to_rfc3339()],
+            )?;
+            Ok(())
+        }
+
+        async fn remove(&self, scope: i64, key: &str) -> Result<()> {
+            let conn = self.conn.lock().unwrap();
+            conn.execute(
+                "DELETE FROM sessions WHERE scope = ?1 AND key = ?2",
+                params![scope, key],
+            )?;
+            Ok(())
+        }
+
+        async fn clear(&self, scope: i64) -> Result<()> {
+            let conn = self.conn.lock().unwrap();
+            conn.execute("DELETE FROM sessions WHERE scope = ?1", params![scope])?;
+            Ok(())
+        }
+    }
+}
+
+/// Dialogue storage backed by a session store, keyed by chat ID
+///
+/// Lets dialogue state survive restarts when the session store is persistent.
+pub struct SessionDialogueStorage<S> {
+    store: Arc<dyn SessionStore>,
+    key: String,
+    _state: PhantomData<fn() -> S>,
+}
+
+impl<S> SessionDialogueStorage<S> {
+    /// Store dialogue state under `key` in each chat's session
+    pub fn new(store: Arc<dyn SessionStore>, key: impl Into<String>) -> Arc<Self> {
+        Arc::new(Self {
+            store,
+            key: key.into(),
+            _state: PhantomData,
+        })
+    }
+}
+
+#[async_trait]
+impl<S> DialogueStorage<S> for SessionDialogueStorage<S>
+where
+    S: Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    async fn get(&self, chat_id: i64) -> Result<Option<S>> {
+        self.store.get(chat_id, &self.key).await
+    }
+
+    async fn set(&self, chat_id: i64, state: S) -> Result<()> {
+        self.store.set(chat_id, &self.key, &state).await
+    }
+
+    async fn remove(&self, chat_id: i64) -> Result<()> {
+        self.store.remove(chat_id, &self.key).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialogue::Dialogue;
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Settings {
+        max_position: f64,
+        notify: bool,
+    }
+
+    async fn roundtrip(store: Arc<dyn SessionStore>) {
+        let settings = Settings { max_position: 250.0, notify: true };
+        store.set(1, "settings", &settings).await.unwrap();
+        store.set(1, "lang", &"en").await.unwrap();
+        store.set(2, "lang", &"es").await.unwrap();
+
+        assert_eq!(store.get::<Settings>(1, "settings").await.unwrap(), Some(settings));
+        assert_eq!(store.get::<String>(2, "lang").await.unwrap(), Some("es".to_string()));
+
+        store.clear(1).await.unwrap();
+        assert_eq!(store.get::<String>(1, "lang").await.unwrap(), None);
+        assert_eq!(store.get::<String>(2, "lang").await.unwrap(), Some("es".to_string()));
+
+        let dialogue = Dialogue::new(SessionDialogueStorage::<u8>::new(store.clone(), "flow"), 3);
+        dialogue.update(2).await.unwrap();
+        assert_eq!(store.get::<u8>(3, "flow").await.unwrap(), Some(2));
+        dialogue.exit().await.unwrap();
+        assert_eq!(dialogue.get().await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_memory_store() {
+        roundtrip(Arc::new(MemorySessionStore::new())).await;
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_sqlite_store() {
+        roundtrip(Arc::new(SqliteSessionStore::open_in_memory().unwrap())).await;
+    }
+}
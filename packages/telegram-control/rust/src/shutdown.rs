@@ -0,0 +1,132 @@
+//! Graceful shutdown
+//!
+//! [`Bot::start`](crate::Bot::start) runs the bot in the background and
+//! returns a [`BotHandle`]. Shutting the handle down stops polling, lets
+//! in-flight handlers finish, stops the scheduler, then runs the bot's
+//! shutdown hooks in registration order: draining alert dispatchers, flushing
+//! session stores and anything added with
+//! [`Bot::on_shutdown`](crate::Bot::on_shutdown).
+//!
+//! ```rust,ignore
+//! let handle = bot.with_alert_dispatcher(alerts.clone()).start().await;
+//! tokio::signal::ctrl_c().await?;
+//! handle.shutdown().await?;
+//! ```
+
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+use teloxide::dispatching::ShutdownToken;
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+
+use crate::error::Result;
+
+/// Cleanup run once the bot has stopped
+pub type ShutdownHook = Box<dyn FnOnce() -> Pin<Box<dyn Future<Output = Result<()>> + Send>> + Send>;
+
+/// Background work to stop and hooks to run after the dispatcher exits
+pub(crate) struct Cleanup {
+    pub(crate) scheduler: Option<JoinHandle<()>>,
+    pub(crate) hooks: Vec<ShutdownHook>,
+}
+
+impl Cleanup {
+    /// Stop the scheduler and run every hook, returning the first error
+    pub(crate) async fn run(self) -> Result<()> {
+        if let Some(scheduler) = self.scheduler {
+            scheduler.abort();
+        }
+        let mut result = Ok(());
+        for hook in self.hooks {
+            if let Err(e) = hook().await {
+                error!("Shutdown hook failed: {}", e);
+                if result.is_ok() {
+                    result = Err(e);
+                }
+            }
+        }
+        info!("Telegram bot stopped");
+        result
+    }
+}
+
+/// A bot running in the background
+pub struct BotHandle {
+    token: ShutdownToken,
+    dispatch: JoinHandle<()>,
+    cleanup: Cleanup,
+}
+
+impl BotHandle {
+    pub(crate) fn new(token: ShutdownToken, dispatch: JoinHandle<()>, cleanup: Cleanup) -> Self {
+        Self {
+            token,
+            dispatch,
+            cleanup,
+        }
+    }
+
+    /// Whether the bot is still receiving updates
+    pub fn is_running(&self) -> bool {
+        !self.dispatch.is_finished()
+    }
+
+    /// Stop polling, wait for in-flight handlers, then run cleanup
+    pub async fn shutdown(self) -> Result<()> {
+        info!("Shutting down Telegram bot...");
+        // The token refuses to shut down a dispatcher that has not started
+        // yet, so retry until it has or the task is gone
+        loop {
+            match self.token.shutdown() {
+                Ok(stopped) => {
+                    stopped.await;
+                    break;
+                }
+                Err(_) if !self.dispatch.is_finished() => tokio::time::sleep(Duration::from_millis(10)).await,
+                Err(_) => break,
+            }
+        }
+        self.join().await
+    }
+
+    /// Wait for the bot to stop on its own, then run cleanup
+    pub async fn join(self) -> Result<()> {
+        if let Err(e) = self.dispatch.await {
+            error!("Dispatcher task failed: {}", e);
+        }
+        self.cleanup.run().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+    use std::sync::{Arc, Mutex};
+
+    #[tokio::test]
+    async fn test_cleanup_runs_every_hook() {
+        let ran = Arc::new(Mutex::new(Vec::new()));
+        let hook = |name: &'static str, fail: bool| -> ShutdownHook {
+            let ran = ran.clone();
+            Box::new(move || {
+                Box::pin(async move {
+                    ran.lock().unwrap().push(name);
+                    match fail {
+                        true => Err(Error::Config(format!("{} failed", name))),
+                        false => Ok(()),
+                    }
+                })
+            })
+        };
+        let cleanup = Cleanup {
+            scheduler: Some(tokio::spawn(std::future::pending())),
+            hooks: vec![hook("alerts", false), hook("store", true), hook("custom", false)],
+        };
+
+        let err = cleanup.run().await.unwrap_err();
+        assert!(err.to_string().contains("store failed"));
+        assert_eq!(*ran.lock().unwrap(), vec!["alerts", "store", "custom"]);
+    }
+}
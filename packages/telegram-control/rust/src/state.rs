@@ -0,0 +1,91 @@
+//! Shared handler state
+//!
+//! Values registered with [`Bot::with_state`](crate::Bot::with_state) are
+//! handed to every handler through its context and looked up by type, so
+//! exchange clients and risk managers need no globals or `Arc`s captured in
+//! each closure.
+//!
+//! ```rust,ignore
+//! let bot = Bot::new(token).build()
+//!     .with_state(ExchangeClient::new(keys))
+//!     .on_command("/balance", |ctx: Context| async move {
+//!         let exchange = ctx.state::<ExchangeClient>()?;
+//!         ctx.reply(exchange.balance().await?.to_string()).await
+//!     });
+//! ```
+
+use std::any::{type_name, Any, TypeId};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::error::{Error, Result};
+
+/// Values keyed by their type; cloning shares them
+#[derive(Clone, Default)]
+pub struct State {
+    values: Arc<HashMap<TypeId, Arc<dyn Any + Send + Sync>>>,
+}
+
+impl State {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `value`, replacing any earlier value of the same type
+    pub fn insert<T: Send + Sync + 'static>(&mut self, value: T) {
+        Arc::make_mut(&mut self.values).insert(TypeId::of::<T>(), Arc::new(value));
+    }
+
+    /// The value of type `T`, if one was added
+    pub fn get<T: Send + Sync + 'static>(&self) -> Option<Arc<T>> {
+        let value = self.values.get(&TypeId::of::<T>())?.clone();
+        value.downcast().ok()
+    }
+
+    /// Like [`State::get`], with an error naming the missing type
+    pub fn require<T: Send + Sync + 'static>(&self) -> Result<Arc<T>> {
+        self.get()
+            .ok_or_else(|| Error::Config(format!("No state of type {} registered", type_name::<T>())))
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+impl fmt::Debug for State {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("State").field("len", &self.len()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Exchange {
+        name: &'static str,
+    }
+
+    #[test]
+    fn test_lookup_by_type() {
+        let mut state = State::new();
+        state.insert(Exchange { name: "binance" });
+        state.insert(5u32);
+        let shared = state.clone();
+        state.insert(6u32);
+
+        assert_eq!(state.get::<Exchange>().unwrap().name, "binance");
+        assert_eq!(*state.get::<u32>().unwrap(), 6);
+        assert_eq!(*shared.get::<u32>().unwrap(), 5);
+        assert!(state.get::<String>().is_none());
+
+        let err = state.require::<String>().unwrap_err();
+        assert!(err.to_string().contains("alloc::string::String"));
+    }
+}
@@ -0,0 +1,342 @@
+//! Alert templates
+//!
+//! Layouts for alerts can live in files so they can change without a
+//! rebuild. Templates use a small Handlebars-style syntax:
+//!
+//! ```text
+//! *{{market}} closed* {{#if win}}🟢{{else}}🔴{{/if}}
+//! PnL: {{pnl | price:USD}} \({{change | pct}}\)
+//! Venue: {{trade.venue | upper}}
+//! ```
+//!
+//! - `{{name}}` inserts a value; dotted names reach into nested objects
+//! - `{{name | filter}}` formats it: `price:CUR`, `pct`, `fixed:N`, `upper`, `lower`
+//! - `{{#if name}}...{{else}}...{{/if}}` tests whether a value is set and
+//!   not `false`, `0`, `""` or empty
+//!
+//! Inserted values are escaped for the template's [`ParseMode`]; the
+//! template's own markup is kept as written. A missing value is an error
+//! rather than a blank, so typos in config files show up.
+//!
+//! ```rust,ignore
+//! let templates = TemplateSet::load_dir("config/alerts")?; // fill.md, daily.html, ...
+//! let alert = templates.alert("fill", AlertLevel::Success, &json!({ "market": "BTC-USD", "pnl": 12.5 }))?;
+//! dispatcher.send(alert).await?;
+//! ```
+
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::alerts::{format_percentage, format_price, AlertLevel};
+use crate::dispatcher::Alert;
+use crate::error::{Error, Result};
+use crate::format::{self, ParseMode};
+
+#[derive(Clone, Debug, PartialEq)]
+enum Node {
+    Text(String),
+    Value { path: String, filters: Vec<String> },
+    If { path: String, then: Vec<Node>, otherwise: Vec<Node> },
+}
+
+/// A parsed template
+#[derive(Clone, Debug, PartialEq)]
+pub struct Template {
+    mode: ParseMode,
+    nodes: Vec<Node>,
+}
+
+impl Template {
+    /// Parse `source`, whose markup is for `mode`
+    pub fn parse(source: &str, mode: ParseMode) -> Result<Self> {
+        let mut tags = Tags { rest: source };
+        let (nodes, end) = parse_nodes(&mut tags)?;
+        match end {
+            None => Ok(Self { mode, nodes }),
+            Some(tag) => Err(template_error(format!("Unexpected {{{{{}}}}}", tag))),
+        }
+    }
+
+    pub fn mode(&self) -> ParseMode {
+        self.mode
+    }
+
+    /// Render with the fields of `vars`, which must serialize to an object
+    pub fn render(&self, vars: &impl Serialize) -> Result<String> {
+        let vars = serde_json::to_value(vars)?;
+        let mut out = String::new();
+        render_nodes(&self.nodes, &vars, self.mode, &mut out)?;
+        Ok(out)
+    }
+}
+
+/// Named templates
+#[derive(Clone, Debug, Default)]
+pub struct TemplateSet {
+    templates: HashMap<String, Template>,
+}
+
+impl TemplateSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load every `.md`, `.html` and `.txt` file in `dir`
+    ///
+    /// Each template is named after its file stem; the extension sets its
+    /// parse mode (MarkdownV2, HTML or plain).
+    pub fn load_dir(dir: impl AsRef<Path>) -> Result<Self> {
+        let mut set = Self::new();
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            let mode = match path.extension().and_then(|e| e.to_str()) {
+                Some("md") => ParseMode::MarkdownV2,
+                Some("html") => ParseMode::Html,
+                Some("txt") => ParseMode::Plain,
+                _ => continue,
+            };
+            let Some(name) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            let source = std::fs::read_to_string(&path)?;
+            let template = Template::parse(&source, mode)
+                .map_err(|e| template_error(format!("{}: {}", path.display(), e)))?;
+            set.templates.insert(name.to_string(), template);
+        }
+        Ok(set)
+    }
+
+    /// Add or replace a template
+    pub fn insert(&mut self, name: impl Into<String>, source: &str, mode: ParseMode) -> Result<()> {
+        self.templates.insert(name.into(), Template::parse(source, mode)?);
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Template> {
+        self.templates.get(name)
+    }
+
+    /// Render the named template
+    pub fn render(&self, name: &str, vars: &impl Serialize) -> Result<String> {
+        self.template(name)?.render(vars)
+    }
+
+    /// Render the named template as an alert in its parse mode
+    pub fn alert(&self, name: &str, level: AlertLevel, vars: &impl Serialize) -> Result<Alert> {
+        let template = self.template(name)?;
+        Ok(Alert::formatted(level, template.render(vars)?, template.mode))
+    }
+
+    fn template(&self, name: &str) -> Result<&Template> {
+        self.get(name)
+            .ok_or_else(|| template_error(format!("Unknown template: {}", name)))
+    }
+}
+
+/// Splits source into text and `{{tag}}` pieces
+struct Tags<'a> {
+    rest: &'a str,
+}
+
+enum Piece<'a> {
+    Text(&'a str),
+    Tag(&'a str),
+}
+
+impl<'a> Tags<'a> {
+    fn next(&mut self) -> Result<Option<Piece<'a>>> {
+        if self.rest.is_empty() {
+            return Ok(None);
+        }
+        match self.rest.find("{{") {
+            Some(0) => {
+                let end = self.rest.find("}}").ok_or_else(|| template_error("Unclosed {{"))?;
+                let tag = self.rest[2..end].trim();
+                self.rest = &self.rest[end + 2..];
+                Ok(Some(Piece::Tag(tag)))
+            }
+            Some(start) => {
+                let text = &self.rest[..start];
+                self.rest = &self.rest[start..];
+                Ok(Some(Piece::Text(text)))
+            }
+            None => {
+                let text = self.rest;
+                self.rest = "";
+                Ok(Some(Piece::Text(text)))
+            }
+        }
+    }
+}
+
+/// Parse until the end of input or an `{{else}}` / `{{/if}}`, which is returned
+fn parse_nodes<'a>(tags: &mut Tags<'a>) -> Result<(Vec<Node>, Option<&'a str>)> {
+    let mut nodes = Vec::new();
+    while let Some(piece) = tags.next()? {
+        let tag = match piece {
+            Piece::Text(text) => {
+                nodes.push(Node::Text(text.to_string()));
+                continue;
+            }
+            Piece::Tag(tag) => tag,
+        };
+        if tag == "else" || tag == "/if" {
+            return Ok((nodes, Some(tag)));
+        }
+        if let Some(path) = tag.strip_prefix("#if ") {
+            let (then, end) = parse_nodes(tags)?;
+            let otherwise = match end {
+                Some("else") => match parse_nodes(tags)? {
+                    (nodes, Some("/if")) => nodes,
+                    _ => return Err(template_error(format!("Missing {{{{/if}}}} for {{{{#if {}}}}}", path))),
+                },
+                Some("/if") => Vec::new(),
+                _ => return Err(template_error(format!("Missing {{{{/if}}}} for {{{{#if {}}}}}", path))),
+            };
+            nodes.push(Node::If {
+                path: path.trim().to_string(),
+                then,
+                otherwise,
+            });
+            continue;
+        }
+        let mut parts = tag.split('|').map(str::trim);
+        let path = parts.next().unwrap_or_default();
+        if path.is_empty() || path.starts_with(['#', '/']) {
+            return Err(template_error(format!("Invalid tag {{{{{}}}}}", tag)));
+        }
+        nodes.push(Node::Value {
+            path: path.to_string(),
+            filters: parts.map(str::to_string).collect(),
+        });
+    }
+    Ok((nodes, None))
+}
+
+fn render_nodes(nodes: &[Node], vars: &Value, mode: ParseMode, out: &mut String) -> Result<()> {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Value { path, filters } => {
+                let value = lookup(vars, path).ok_or_else(|| template_error(format!("Missing value: {}", path)))?;
+                let mut text = match value {
+                    Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                };
+                for filter in filters {
+                    text = apply_filter(filter, value, text)?;
+                }
+                out.push_str(&format::escape(&text, mode));
+            }
+            Node::If { path, then, otherwise } => {
+                let branch = if lookup(vars, path).is_some_and(truthy) { then } else { otherwise };
+                render_nodes(branch, vars, mode, out)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn lookup<'a>(vars: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(vars, |value, key| value.get(key))
+}
+
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64() != Some(0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(a) => !a.is_empty(),
+        Value::Object(o) => !o.is_empty(),
+    }
+}
+
+fn apply_filter(filter: &str, value: &Value, text: String) -> Result<String> {
+    let (name, arg) = match filter.split_once(':') {
+        Some((name, arg)) => (name.trim(), Some(arg.trim())),
+        None => (filter, None),
+    };
+    let number = || {
+        value
+            .as_f64()
+            .or_else(|| text.parse().ok())
+            .ok_or_else(|| template_error(format!("Filter {} needs a number, got {}", name, text)))
+    };
+    Ok(match (name, arg) {
+        ("price", currency) => format_price(number()?, currency.unwrap_or("USD")),
+        ("pct", None) => format_percentage(number()?),
+        ("fixed", Some(digits)) => {
+            let digits: usize = digits
+                .parse()
+                .map_err(|_| template_error(format!("Invalid digits for fixed: {}", digits)))?;
+            format!("{:.*}", digits, number()?)
+        }
+        ("upper", None) => text.to_uppercase(),
+        ("lower", None) => text.to_lowercase(),
+        _ => return Err(template_error(format!("Unknown filter: {}", filter))),
+    })
+}
+
+fn template_error(message: impl Into<String>) -> Error {
+    Error::Template(message.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_render() {
+        let source = "*{{market}} closed* {{#if win}}🟢{{else}}🔴{{/if}}\nPnL: {{pnl | price:USD}} \\({{change | pct}}\\)\nVenue: {{trade.venue | upper}}";
+        let template = Template::parse(source, ParseMode::MarkdownV2).unwrap();
+        let vars = json!({
+            "market": "BTC_USD",
+            "win": true,
+            "pnl": 12.5,
+            "change": 1.234,
+            "trade": { "venue": "binance" },
+        });
+        assert_eq!(
+            template.render(&vars).unwrap(),
+            "*BTC\\_USD closed* 🟢\nPnL: $12\\.50 \\(\\+1\\.23%\\)\nVenue: BINANCE"
+        );
+
+        let plain = Template::parse("{{#if win}}won{{/if}}{{n | fixed:1}}", ParseMode::Plain).unwrap();
+        assert_eq!(plain.render(&json!({ "win": 0, "n": 2 })).unwrap(), "2.0");
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(Template::parse("{{#if x}}open", ParseMode::Plain).is_err());
+        assert!(Template::parse("{{/if}}", ParseMode::Plain).is_err());
+        assert!(Template::parse("{{name", ParseMode::Plain).is_err());
+
+        let template = Template::parse("{{pnl}} {{name | shout}}", ParseMode::Plain).unwrap();
+        let err = template.render(&json!({ "name": "x" })).unwrap_err();
+        assert!(err.to_string().contains("Missing value: pnl"));
+        assert!(template.render(&json!({ "pnl": 1, "name": "x" })).is_err());
+    }
+
+    #[test]
+    fn test_template_set() {
+        let dir = std::env::temp_dir().join(format!("templates-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("fill.html"), "<b>{{market}}</b> filled").unwrap();
+        std::fs::write(dir.join("notes.json"), "{}").unwrap();
+
+        let set = TemplateSet::load_dir(&dir).unwrap();
+        let alert = set
+            .alert("fill", AlertLevel::Success, &json!({ "market": "<BTC>" }))
+            .unwrap();
+        assert_eq!(alert.text, "<b>&lt;BTC&gt;</b> filled");
+        assert_eq!(alert.parse_mode, ParseMode::Html);
+        assert!(set.get("notes").is_none());
+        assert!(set.render("missing", &json!({})).is_err());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
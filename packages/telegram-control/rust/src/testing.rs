@@ -0,0 +1,740 @@
+//! Testing bots without Telegram
+//!
+//! [`TestBot`] feeds synthetic updates through a bot's access control,
+//! middleware and handlers exactly as [`Bot::run`](crate::Bot::run) would,
+//! but against [`MockTelegram`]: a local server that answers Bot API calls
+//! and records them so tests can assert on what the bot sent. Enable the
+//! `testing` feature in `dev-dependencies` to use it.
+//!
+//! ```rust,ignore
+//! #[tokio::test]
+//! async fn status_replies() {
+//!     let bot = Bot::new("123:test").with_whitelist(vec![7]).build()
+//!         .on_command("/status", |ctx: Context| async move { ctx.reply("All systems go").await });
+//!     let tg = TestBot::start(bot).await.unwrap();
+//!
+//!     tg.send_text(7, "/status").await;
+//!     assert_eq!(tg.replies(), vec!["All systems go"]);
+//! }
+//! ```
+
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::future::Future;
+use std::io;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::{Arc, Mutex};
+use teloxide::types::{CallbackQuery, InlineKeyboardMarkup, InlineQuery, Message};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+use crate::bot::{Bot, Pipeline};
+use crate::error::Result;
+
+/// Username the bot under test answers to
+pub const BOT_USERNAME: &str = "test_bot";
+
+/// A Bot API request the bot made
+#[derive(Clone, Debug)]
+pub struct ApiCall {
+    /// Method name as in the Bot API docs, e.g. `sendMessage`
+    pub method: String,
+    /// Request parameters; multipart values are kept as strings
+    pub params: Value,
+    /// Result the mock answered with
+    pub result: Value,
+}
+
+impl ApiCall {
+    /// Chat the request targets
+    pub fn chat_id(&self) -> Option<i64> {
+        match &self.params["chat_id"] {
+            Value::Number(n) => n.as_i64(),
+            Value::String(s) => s.parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// Message text, or the caption of photos and documents
+    pub fn text(&self) -> Option<&str> {
+        self.params["text"].as_str().or(self.params["caption"].as_str())
+    }
+
+    /// Name and size of each file uploaded with the call
+    pub fn files(&self) -> Vec<(String, usize)> {
+        let Some(params) = self.params.as_object() else {
+            return Vec::new();
+        };
+        params
+            .values()
+            .filter_map(|v| Some((v["file_name"].as_str()?.to_string(), v["size"].as_u64()? as usize)))
+            .collect()
+    }
+
+    /// Inline keyboard attached to the message
+    pub fn keyboard(&self) -> Option<InlineKeyboardMarkup> {
+        let markup = match &self.params["reply_markup"] {
+            Value::String(s) => serde_json::from_str(s).ok()?,
+            value => value.clone(),
+        };
+        serde_json::from_value(markup).ok()
+    }
+
+    /// Labels of the inline keyboard's buttons, row by row
+    pub fn buttons(&self) -> Vec<String> {
+        self.keyboard()
+            .map(|k| k.inline_keyboard.into_iter().flatten().map(|b| b.text).collect())
+            .unwrap_or_default()
+    }
+
+    /// Whether this call sent or edited message text
+    fn is_reply(&self) -> bool {
+        matches!(self.method.as_str(), "sendMessage" | "editMessageText")
+    }
+}
+
+/// Canned answer for a method
+#[derive(Clone, Debug)]
+enum Answer {
+    Ok(Value),
+    Err(String),
+}
+
+#[derive(Default)]
+struct Backend {
+    calls: Vec<ApiCall>,
+    answers: HashMap<String, Answer>,
+    /// Downloadable files by file ID
+    files: HashMap<String, Vec<u8>>,
+    /// Latest message sent or edited in each chat
+    last_messages: HashMap<i64, Value>,
+    next_message_id: i32,
+}
+
+impl Backend {
+    /// Record a Bot API call and build the response body
+    fn call(&mut self, method: String, params: Value) -> Value {
+        let result = match self.answers.get(&method).cloned() {
+            Some(Answer::Ok(result)) => result,
+            Some(Answer::Err(description)) => {
+                self.calls.push(ApiCall { method, params, result: Value::Null });
+                return json!({ "ok": false, "error_code": 400, "description": description });
+            }
+            None => match self.result(&method, &params) {
+                Ok(result) => result,
+                Err(description) => return json!({ "ok": false, "error_code": 400, "description": description }),
+            },
+        };
+        self.calls.push(ApiCall {
+            method,
+            params,
+            result: result.clone(),
+        });
+        json!({ "ok": true, "result": result })
+    }
+
+    /// What Telegram would answer `method` with
+    fn result(&mut self, method: &str, params: &Value) -> std::result::Result<Value, String> {
+        let result = match method {
+            "getMe" => json!({
+                "id": 1,
+                "is_bot": true,
+                "first_name": "Test",
+                "username": BOT_USERNAME,
+                "can_join_groups": true,
+                "can_read_all_group_messages": false,
+                "supports_inline_queries": true,
+            }),
+            "getFile" => {
+                let id = params["file_id"].as_str().unwrap_or_default();
+                let bytes = self.files.get(id).ok_or("Bad Request: invalid file_id")?;
+                json!({
+                    "file_id": id,
+                    "file_unique_id": id,
+                    "file_size": bytes.len(),
+                    "file_path": format!("documents/{}", id),
+                })
+            }
+            "sendMessage" | "sendPhoto" | "sendDocument" | "editMessageText" | "editMessageCaption"
+            | "editMessageReplyMarkup"
+                if params["inline_message_id"].is_null() =>
+            {
+                self.message(method, params)
+            }
+            _ => Value::Bool(true),
+        };
+        Ok(result)
+    }
+
+    /// The message a send or edit call results in
+    fn message(&mut self, method: &str, params: &Value) -> Value {
+        let chat_id = match &params["chat_id"] {
+            Value::Number(n) => n.as_i64().unwrap_or_default(),
+            Value::String(s) => s.parse().unwrap_or_default(),
+            _ => 0,
+        };
+        let message_id = match &params["message_id"] {
+            Value::Number(n) => n.as_i64().unwrap_or_default() as i32,
+            Value::String(s) => s.parse().unwrap_or_default(),
+            _ => {
+                self.next_message_id += 1;
+                self.next_message_id
+            }
+        };
+        let mut message = json!({
+            "message_id": message_id,
+            "date": chrono::Utc::now().timestamp(),
+            "chat": chat(chat_id),
+            "from": { "id": 1, "is_bot": true, "first_name": "Test", "username": BOT_USERNAME },
+        });
+        match method {
+            "sendPhoto" => {
+                message["photo"] = json!([{ "file_id": "photo", "file_unique_id": "photo", "width": 1, "height": 1 }]);
+                message["caption"] = params["caption"].clone();
+            }
+            "sendDocument" => {
+                message["document"] = json!({ "file_id": "document", "file_unique_id": "document" });
+                message["caption"] = params["caption"].clone();
+            }
+            _ => message["text"] = json!(params["text"].as_str().unwrap_or_default()),
+        }
+        let markup = match &params["reply_markup"] {
+            Value::String(s) => serde_json::from_str(s).unwrap_or_default(),
+            value => value.clone(),
+        };
+        if markup.get("inline_keyboard").is_some() {
+            message["reply_markup"] = markup;
+        }
+        self.last_messages.insert(chat_id, message.clone());
+        message
+    }
+}
+
+/// A local stand-in for the Telegram Bot API
+///
+/// Point a bot at it with [`Bot::with_api_url`](crate::Bot::with_api_url).
+/// Sends and edits answer with a plausible message, `getFile` serves files
+/// added with [`MockTelegram::add_file`], and everything else answers `true`
+/// unless overridden with [`MockTelegram::respond`] or [`MockTelegram::fail`].
+pub struct MockTelegram {
+    url: String,
+    backend: Arc<Mutex<Backend>>,
+    server: JoinHandle<()>,
+}
+
+impl MockTelegram {
+    /// Listen on a free local port
+    pub async fn start() -> Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let url = format!("http://{}/", listener.local_addr()?);
+        let backend: Arc<Mutex<Backend>> = Arc::default();
+        let shared = backend.clone();
+        let server = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let backend = shared.clone();
+                tokio::spawn(async move {
+                    // Clients hang up between tests; nothing to report
+                    let _ = serve(stream, backend).await;
+                });
+            }
+        });
+        Ok(Self { url, backend, server })
+    }
+
+    /// Base URL to pass to [`Bot::with_api_url`](crate::Bot::with_api_url)
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Every call so far, oldest first
+    pub fn calls(&self) -> Vec<ApiCall> {
+        self.backend.lock().unwrap().calls.clone()
+    }
+
+    /// Every call so far, clearing the record
+    pub fn take_calls(&self) -> Vec<ApiCall> {
+        std::mem::take(&mut self.backend.lock().unwrap().calls)
+    }
+
+    /// Answer `method` with `result` from now on
+    pub fn respond(&self, method: &str, result: Value) {
+        self.backend.lock().unwrap().answers.insert(method.to_string(), Answer::Ok(result));
+    }
+
+    /// Answer `method` with an API error from now on, e.g.
+    /// `"Forbidden: bot was blocked by the user"`
+    pub fn fail(&self, method: &str, description: &str) {
+        self.backend
+            .lock()
+            .unwrap()
+            .answers
+            .insert(method.to_string(), Answer::Err(description.to_string()));
+    }
+
+    /// The latest message the bot sent or edited in `chat_id`
+    pub fn last_message(&self, chat_id: i64) -> Option<Message> {
+        let message = self.backend.lock().unwrap().last_messages.get(&chat_id).cloned()?;
+        serde_json::from_value(message).ok()
+    }
+
+    /// Make `bytes` downloadable as `file_id`
+    pub fn add_file(&self, file_id: &str, bytes: Vec<u8>) {
+        self.backend.lock().unwrap().files.insert(file_id.to_string(), bytes);
+    }
+}
+
+impl Drop for MockTelegram {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+/// Answer HTTP/1.1 requests on one connection until the client closes it
+async fn serve(stream: TcpStream, backend: Arc<Mutex<Backend>>) -> io::Result<()> {
+    let mut stream = BufReader::new(stream);
+    loop {
+        let mut request_line = String::new();
+        if stream.read_line(&mut request_line).await? == 0 {
+            return Ok(());
+        }
+        let path = request_line.split_whitespace().nth(1).unwrap_or_default().to_string();
+
+        let (mut length, mut chunked, mut content_type) = (0, false, String::new());
+        loop {
+            let mut line = String::new();
+            stream.read_line(&mut line).await?;
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                let value = value.trim();
+                match name.to_ascii_lowercase().as_str() {
+                    "content-length" => length = value.parse().unwrap_or(0),
+                    "transfer-encoding" => chunked = value.eq_ignore_ascii_case("chunked"),
+                    "content-type" => content_type = value.to_string(),
+                    _ => {}
+                }
+            }
+        }
+        let body = match chunked {
+            true => read_chunked(&mut stream).await?,
+            false => {
+                let mut body = vec![0; length];
+                stream.read_exact(&mut body).await?;
+                body
+            }
+        };
+
+        let (status, content_type, body) = respond(&backend, &path, &content_type, &body);
+        let head = format!(
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\n\r\n",
+            status,
+            content_type,
+            body.len()
+        );
+        let stream = stream.get_mut();
+        stream.write_all(head.as_bytes()).await?;
+        stream.write_all(&body).await?;
+    }
+}
+
+async fn read_chunked(stream: &mut BufReader<TcpStream>) -> io::Result<Vec<u8>> {
+    let mut body = Vec::new();
+    loop {
+        let mut line = String::new();
+        stream.read_line(&mut line).await?;
+        let size = line.trim().split(';').next().unwrap_or_default();
+        let size = usize::from_str_radix(size, 16).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let mut chunk = vec![0; size + 2];
+        stream.read_exact(&mut chunk).await?;
+        if size == 0 {
+            return Ok(body);
+        }
+        body.extend_from_slice(&chunk[..size]);
+    }
+}
+
+/// Status, content type and body for a request to `path`
+fn respond(backend: &Mutex<Backend>, path: &str, content_type: &str, body: &[u8]) -> (&'static str, &'static str, Vec<u8>) {
+    // File downloads: /file/bot<token>/documents/<file_id>
+    if let Some(rest) = path.strip_prefix("/file/") {
+        let file_id = rest.rsplit('/').next().unwrap_or_default();
+        return match backend.lock().unwrap().files.get(file_id) {
+            Some(bytes) => ("200 OK", "application/octet-stream", bytes.clone()),
+            None => ("404 Not Found", "text/plain", b"Not Found".to_vec()),
+        };
+    }
+
+    // Method calls: /bot<token>/<Method>
+    let method = method_name(path.rsplit('/').next().unwrap_or_default());
+    let params = match content_type.split_once("boundary=") {
+        Some((_, boundary)) => parse_multipart(body, boundary.trim_matches('"')),
+        None if body.is_empty() => json!({}),
+        None => serde_json::from_slice(body).unwrap_or_else(|_| json!({})),
+    };
+    let response = backend.lock().unwrap().call(method, params);
+    ("200 OK", "application/json", response.to_string().into_bytes())
+}
+
+/// `SendMessage` to `sendMessage`, as the method is named in the Bot API docs
+fn method_name(name: &str) -> String {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_lowercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Form fields of a `multipart/form-data` body; files are recorded as
+/// `{"file_name": .., "size": ..}`
+fn parse_multipart(body: &[u8], boundary: &str) -> Value {
+    let delimiter = format!("--{}", boundary);
+    let mut params = Map::new();
+    for part in split(body, delimiter.as_bytes()) {
+        let Some(end) = find(part, b"\r\n\r\n") else {
+            continue;
+        };
+        let headers = String::from_utf8_lossy(&part[..end]);
+        let value = &part[end + 4..];
+        let value = value.strip_suffix(b"\r\n").unwrap_or(value);
+        let quoted = |key: &str| {
+            let start = headers.find(&format!(" {}=\"", key))? + key.len() + 3;
+            headers[start..].split('"').next().map(str::to_string)
+        };
+        let Some(name) = quoted("name") else {
+            continue;
+        };
+        let value = match quoted("filename") {
+            Some(file_name) => json!({ "file_name": file_name, "size": value.len() }),
+            None => Value::String(String::from_utf8_lossy(value).into_owned()),
+        };
+        params.insert(name, value);
+    }
+    Value::Object(params)
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+fn split<'a>(mut haystack: &'a [u8], delimiter: &[u8]) -> Vec<&'a [u8]> {
+    let mut parts = Vec::new();
+    while let Some(i) = find(haystack, delimiter) {
+        parts.push(&haystack[..i]);
+        haystack = &haystack[i + delimiter.len()..];
+    }
+    parts.push(haystack);
+    parts
+}
+
+/// Private chat for positive IDs, a supergroup otherwise
+fn chat(id: i64) -> Value {
+    match id > 0 {
+        true => json!({ "id": id, "type": "private", "first_name": "Test" }),
+        false => json!({ "id": id, "type": "supergroup", "title": "Test group" }),
+    }
+}
+
+fn user(id: i64) -> Value {
+    json!({ "id": id, "is_bot": false, "first_name": "Test", "username": format!("user{}", id) })
+}
+
+/// A bot wired to a [`MockTelegram`], with helpers to send it updates
+///
+/// Each `send_*` call returns once the update has been handled, so the
+/// calls it caused can be asserted on straight away.
+pub struct TestBot {
+    telegram: MockTelegram,
+    bot: teloxide::Bot,
+    pipeline: Arc<Pipeline>,
+    next_id: AtomicI32,
+}
+
+impl TestBot {
+    /// Start a mock backend and prepare `bot` as [`Bot::run`] would
+    pub async fn start(bot: Bot) -> Result<Self> {
+        let telegram = MockTelegram::start().await?;
+        let (bot, pipeline) = bot.with_api_url(telegram.url())?.into_test_pipeline(BOT_USERNAME);
+        Ok(Self {
+            telegram,
+            bot,
+            pipeline,
+            next_id: AtomicI32::new(1000),
+        })
+    }
+
+    /// The mock backend, to inspect calls or script responses
+    pub fn telegram(&self) -> &MockTelegram {
+        &self.telegram
+    }
+
+    /// Every Bot API call so far
+    pub fn calls(&self) -> Vec<ApiCall> {
+        self.telegram.calls()
+    }
+
+    /// Every Bot API call so far, clearing the record
+    pub fn take_calls(&self) -> Vec<ApiCall> {
+        self.telegram.take_calls()
+    }
+
+    /// Text of every message the bot sent or edited
+    pub fn replies(&self) -> Vec<String> {
+        self.calls()
+            .iter()
+            .filter(|c| c.is_reply())
+            .filter_map(|c| c.text().map(str::to_string))
+            .collect()
+    }
+
+    /// The last message the bot sent or edited
+    pub fn last_reply(&self) -> Option<ApiCall> {
+        self.calls().into_iter().rev().find(ApiCall::is_reply)
+    }
+
+    fn next_id(&self) -> i32 {
+        self.next_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// A private message from `user_id`
+    pub async fn send_text(&self, user_id: i64, text: &str) {
+        self.send_group_text(user_id, user_id, text).await
+    }
+
+    /// A message from `user_id` in `chat_id`
+    pub async fn send_group_text(&self, chat_id: i64, user_id: i64, text: &str) {
+        let message = json!({
+            "message_id": self.next_id(),
+            "date": chrono::Utc::now().timestamp(),
+            "chat": chat(chat_id),
+            "from": user(user_id),
+            "text": text,
+        });
+        self.send_json(message).await
+    }
+
+    /// A private message from `user_id` with `bytes` attached as `file_name`
+    ///
+    /// The file is added to the mock backend so handlers can download it.
+    pub async fn send_document(&self, user_id: i64, file_name: &str, bytes: Vec<u8>) {
+        let id = self.next_id();
+        let file_id = format!("file{}", id);
+        let message = json!({
+            "message_id": id,
+            "date": chrono::Utc::now().timestamp(),
+            "chat": chat(user_id),
+            "from": user(user_id),
+            "document": {
+                "file_id": file_id,
+                "file_unique_id": file_id,
+                "file_size": bytes.len(),
+                "file_name": file_name,
+            },
+        });
+        self.telegram.add_file(&file_id, bytes);
+        self.send_json(message).await
+    }
+
+    /// Any message, e.g. one with a photo or a reply
+    pub async fn send_message(&self, message: Message) {
+        let (pipeline, bot) = (self.pipeline.clone(), self.bot.clone());
+        run(async move { pipeline.handle_message(bot, message).await }).await
+    }
+
+    async fn send_json(&self, message: Value) {
+        let message = serde_json::from_value(message).expect("valid test message");
+        self.send_message(message).await
+    }
+
+    /// `user_id` pressing a button with `data` on the last message the bot
+    /// sent to their private chat
+    pub async fn press_button(&self, user_id: i64, data: &str) {
+        let message = self.telegram.backend.lock().unwrap().last_messages.get(&user_id).cloned();
+        let mut query = json!({
+            "id": self.next_id().to_string(),
+            "from": user(user_id),
+            "chat_instance": "test",
+            "data": data,
+        });
+        if let Some(message) = message {
+            query["message"] = message;
+        }
+        let query: CallbackQuery = serde_json::from_value(query).expect("valid test callback query");
+        let (pipeline, bot) = (self.pipeline.clone(), self.bot.clone());
+        run(async move { pipeline.handle_callback_query(bot, query).await }).await
+    }
+
+    /// `user_id` typing `@test_bot query` in any chat
+    pub async fn inline_query(&self, user_id: i64, query: &str) {
+        let query: InlineQuery = serde_json::from_value(json!({
+            "id": self.next_id().to_string(),
+            "from": user(user_id),
+            "query": query,
+            "offset": "",
+        }))
+        .expect("valid test inline query");
+        let (pipeline, bot) = (self.pipeline.clone(), self.bot.clone());
+        run(async move { pipeline.handle_inline_query(bot, query).await }).await
+    }
+}
+
+/// Handle an update on its own task, as the dispatcher does
+async fn run(update: impl Future<Output = ()> + Send + 'static) {
+    tokio::spawn(update).await.expect("update handler panicked")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::Bot;
+    use crate::documents::DocumentLimits;
+    use crate::keyboards::InlineKeyboardBuilder;
+    use crate::types::Context;
+    use teloxide::prelude::*;
+
+    fn bot() -> Bot {
+        Bot::new("123:test")
+            .with_whitelist(vec![7])
+            .build()
+            .on_command("/status", |ctx: Context| async move { ctx.reply("All systems go").await })
+            .on_command("/menu", |ctx: Context| async move {
+                let keyboard = InlineKeyboardBuilder::new().button("Close", "menu:close").build();
+                ctx.bot()
+                    .send_message(ChatId(ctx.chat_id()), "Menu")
+                    .reply_markup(keyboard)
+                    .await?;
+                Ok(())
+            })
+            .on_callback("menu:", |ctx: Context| async move { ctx.reply(format!("pressed {}", ctx.text().unwrap())).await })
+            .on_callback("ack:", |ctx: Context| async move { ctx.answer("Done").await })
+            .on_callback("edit:", |ctx: Context| async move {
+                match ctx.text() {
+                    Some("edit:rename") => ctx.edit_text("Renamed").await,
+                    Some("edit:clear") => ctx.clear_keyboard().await,
+                    _ => ctx.delete().await,
+                }
+            })
+    }
+
+    #[tokio::test]
+    async fn test_commands_and_buttons() {
+        let tg = TestBot::start(bot()).await.unwrap();
+
+        tg.send_text(7, "/status").await;
+        assert_eq!(tg.replies(), vec!["All systems go"]);
+        assert_eq!(tg.last_reply().unwrap().chat_id(), Some(7));
+
+        tg.send_text(8, "/status").await;
+        assert_eq!(tg.last_reply().unwrap().chat_id(), Some(8));
+        assert!(tg.last_reply().unwrap().text().unwrap().contains("not authorized"));
+
+        tg.take_calls();
+        tg.send_text(7, "/menu").await;
+        assert_eq!(tg.last_reply().unwrap().buttons(), vec!["Close"]);
+        tg.press_button(7, "menu:close").await;
+        assert_eq!(tg.last_reply().unwrap().text(), Some("pressed menu:close"));
+        let answers: Vec<ApiCall> = tg.calls().into_iter().filter(|c| c.method == "answerCallbackQuery").collect();
+        assert_eq!(answers.len(), 1);
+        assert!(answers[0].params["text"].is_null());
+
+        tg.take_calls();
+        tg.press_button(7, "ack:1").await;
+        let calls = tg.calls();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].method, "answerCallbackQuery");
+        assert_eq!(calls[0].params["text"], "Done");
+    }
+
+    #[tokio::test]
+    async fn test_every_button_press_is_answered() {
+        let bot = bot().on_callback("fail:", |_ctx: Context| async move {
+            Err(crate::error::Error::InvalidCommand("market closed".to_string()))
+        });
+        let tg = TestBot::start(bot).await.unwrap();
+        let answers = |tg: &TestBot| -> Vec<ApiCall> {
+            tg.take_calls().into_iter().filter(|c| c.method == "answerCallbackQuery").collect()
+        };
+
+        tg.press_button(7, "unknown:1").await;
+        let calls = answers(&tg);
+        assert_eq!(calls.len(), 1);
+        assert!(calls[0].params["text"].is_null());
+
+        tg.press_button(7, "fail:1").await;
+        let calls = answers(&tg);
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].params["show_alert"], true);
+        assert!(calls[0].text().unwrap().contains("market closed"));
+
+        tg.press_button(8, "menu:close").await;
+        let calls = answers(&tg);
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].params["show_alert"], true);
+        assert!(calls[0].text().unwrap().contains("not authorized"));
+    }
+
+    #[tokio::test]
+    async fn test_edit_and_delete() {
+        let tg = TestBot::start(bot()).await.unwrap();
+        tg.send_text(7, "/menu").await;
+        let menu = tg.last_reply().unwrap().result["message_id"].clone();
+
+        tg.take_calls();
+        tg.press_button(7, "edit:rename").await;
+        let edit = &tg.calls()[0];
+        assert_eq!(edit.method, "editMessageText");
+        assert_eq!(edit.params["message_id"], menu);
+        assert_eq!(edit.text(), Some("Renamed"));
+        assert_eq!(edit.buttons(), vec!["Close"]);
+
+        tg.take_calls();
+        tg.press_button(7, "edit:clear").await;
+        assert_eq!(tg.calls()[0].method, "editMessageReplyMarkup");
+        assert!(tg.calls()[0].keyboard().is_none());
+
+        tg.take_calls();
+        tg.press_button(7, "edit:delete").await;
+        assert_eq!(tg.calls()[0].method, "deleteMessage");
+        assert_eq!(tg.calls()[0].params["message_id"], menu);
+    }
+
+    #[tokio::test]
+    async fn test_documents_and_failures() {
+        let bot = bot()
+            .on_document(DocumentLimits::new().extensions(&["csv"]), |ctx, upload| async move {
+                ctx.reply(format!("{} lines", upload.text()?.lines().count())).await
+            })
+            .on_command("/export", |ctx: Context| async move { ctx.reply_document(b"BTC\n".to_vec(), "watchlist.csv").await });
+        let tg = TestBot::start(bot).await.unwrap();
+
+        tg.send_text(7, "/export").await;
+        let export = tg.take_calls().pop().unwrap();
+        assert_eq!((export.method.as_str(), export.chat_id()), ("sendDocument", Some(7)));
+        assert_eq!(export.files(), vec![("watchlist.csv".to_string(), 4)]);
+
+        tg.send_document(7, "watchlist.csv", b"BTC\nETH\nSOL\n".to_vec()).await;
+        assert_eq!(tg.last_reply().unwrap().text(), Some("3 lines"));
+        tg.send_document(7, "watchlist.xls", vec![0; 10]).await;
+        assert!(tg.last_reply().unwrap().text().unwrap().contains("Only .csv files"));
+
+        tg.telegram().fail("sendMessage", "Forbidden: bot was blocked by the user");
+        tg.take_calls();
+        tg.send_text(7, "/status").await;
+        let calls = tg.calls();
+        assert_eq!(calls[0].method, "sendMessage");
+        assert!(calls[0].result.is_null());
+    }
+
+    #[test]
+    fn test_parse_multipart() {
+        let body = b"--b\r\nContent-Disposition: form-data; name=\"chat_id\"\r\n\r\n7\r\n\
+            --b\r\nContent-Disposition: form-data; name=\"photo\"; filename=\"chart.png\"\r\n\
+            Content-Type: image/png\r\n\r\n\x89PNG\r\n--b--\r\n";
+        let params = parse_multipart(body, "b");
+        assert_eq!(params["chat_id"], "7");
+        assert_eq!(params["photo"], json!({ "file_name": "chart.png", "size": 4 }));
+        assert_eq!(method_name("SendMessage"), "sendMessage");
+    }
+}
@@ -0,0 +1,97 @@
+//! Handler timeouts
+//!
+//! A handler stuck on a hung exchange call would leave the chat silent and
+//! hold up the update worker for that chat. With a [`HandlerTimeout`] the
+//! handler is cancelled once the limit passes and the user gets the usual
+//! error reply; optionally they are told the bot is still working first.
+//!
+//! ```rust,ignore
+//! let bot = bot
+//!     .with_handler_timeout(HandlerTimeout::new(Duration::from_secs(30)))
+//!     .command_timeout("/backtest", HandlerTimeout::new(Duration::from_secs(300)).notice_after(Duration::from_secs(10)));
+//! ```
+
+use std::future::Future;
+use std::time::Duration;
+use tokio::time::Instant;
+
+use crate::error::{Error, Result};
+
+/// Time limits for a handler
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HandlerTimeout {
+    /// Cancel the handler after this long
+    pub limit: Duration,
+    /// Tell the user the bot is still working after this long
+    pub notice_after: Option<Duration>,
+}
+
+impl HandlerTimeout {
+    pub fn new(limit: Duration) -> Self {
+        Self {
+            limit,
+            notice_after: None,
+        }
+    }
+
+    /// Send a "still working" message if the handler runs longer than `after`
+    pub fn notice_after(mut self, after: Duration) -> Self {
+        self.notice_after = Some(after);
+        self
+    }
+}
+
+/// Run `handler` within `timeout`, calling `notice` once if it runs long
+///
+/// Fails with [`Error::Timeout`] when the limit passes; the handler is dropped.
+pub(crate) async fn run<F, N>(handler: F, timeout: HandlerTimeout, notice: impl FnOnce() -> N) -> Result<()>
+where
+    F: Future<Output = Result<()>>,
+    N: Future<Output = ()>,
+{
+    let deadline = Instant::now() + timeout.limit;
+    tokio::pin!(handler);
+    if let Some(after) = timeout.notice_after.filter(|after| *after < timeout.limit) {
+        tokio::select! {
+            result = &mut handler => return result,
+            _ = tokio::time::sleep(after) => notice().await,
+        }
+    }
+    match tokio::time::timeout_at(deadline, handler).await {
+        Ok(result) => result,
+        Err(_) => Err(Error::Timeout(timeout.limit)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test(start_paused = true)]
+    async fn test_timeout_and_notice() {
+        let notices = AtomicUsize::new(0);
+        let notice = || async {
+            notices.fetch_add(1, Ordering::SeqCst);
+        };
+        let timeout = HandlerTimeout::new(Duration::from_secs(30)).notice_after(Duration::from_secs(5));
+
+        let quick = run(async { Ok(()) }, timeout, notice).await;
+        assert!(quick.is_ok());
+        assert_eq!(notices.load(Ordering::SeqCst), 0);
+
+        let slow = async {
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            Ok(())
+        };
+        assert!(run(slow, timeout, notice).await.is_ok());
+        assert_eq!(notices.load(Ordering::SeqCst), 1);
+
+        let hung = std::future::pending::<Result<()>>();
+        let started = Instant::now();
+        let err = run(hung, timeout, notice).await.unwrap_err();
+        assert!(matches!(err, Error::Timeout(limit) if limit == Duration::from_secs(30)));
+        assert_eq!(started.elapsed(), Duration::from_secs(30));
+        assert_eq!(notices.load(Ordering::SeqCst), 2);
+    }
+}
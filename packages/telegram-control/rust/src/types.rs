@@ -1,4 +1,17 @@
-use teloxide::types::{CallbackQuery, Message, User};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use teloxide::prelude::*;
+use teloxide::types::{
+    CallbackQuery, Document, InlineKeyboardMarkup, InputFile, Message, MessageId, ReplyParameters, ThreadId, User,
+};
+use teloxide::{ApiError, RequestError};
+
+use crate::args::{CommandSpec, ParsedArgs};
+use crate::callback::CallbackData;
+use crate::documents::{self, DocumentLimits, Upload};
+use crate::error::{Error, Result};
+use crate::format::{self, ParseMode, Text};
+use crate::state::State;
 
 /// Context for message-based commands
 #[derive(Clone, Debug)]
@@ -8,6 +21,15 @@ pub struct MessageContext {
     pub chat_id: i64,
     pub text: Option<String>,
     pub bot: teloxide::Bot,
+    /// Values registered with [`Bot::with_state`](crate::Bot::with_state)
+    pub state: State,
+}
+
+impl MessageContext {
+    /// Message this one replies to, e.g. the alert a user answered "cancel" to
+    pub fn reply_to(&self) -> Option<&Message> {
+        self.message.reply_to_message()
+    }
 }
 
 /// Context for callback queries (inline keyboard)
@@ -18,10 +40,49 @@ pub struct CallbackContext {
     pub chat_id: i64,
     pub data: String,
     pub bot: teloxide::Bot,
+    pub state: State,
+    /// Set once the query is answered; shared by clones
+    pub(crate) answered: Arc<AtomicBool>,
+}
+
+impl CallbackContext {
+    /// Show `text` briefly at the top of the chat and stop the button's
+    /// loading spinner
+    pub async fn answer(&self, text: impl Into<String>) -> Result<()> {
+        self.answer_query(Some(text.into()), false).await
+    }
+    
+    /// Show `text` in a popup the user has to dismiss, e.g. for errors
+    pub async fn answer_alert(&self, text: impl Into<String>) -> Result<()> {
+        self.answer_query(Some(text.into()), true).await
+    }
+    
+    /// Whether the query has been answered
+    ///
+    /// Queries the handler leaves unanswered are answered without text once
+    /// it returns.
+    pub fn is_answered(&self) -> bool {
+        self.answered.load(Ordering::SeqCst)
+    }
+    
+    /// Answer the query; Telegram accepts one answer, so later ones are
+    /// skipped
+    pub(crate) async fn answer_query(&self, text: Option<String>, show_alert: bool) -> Result<()> {
+        if self.answered.swap(true, Ordering::SeqCst) {
+            return Ok(());
+        }
+        let mut request = self.bot.answer_callback_query(self.query.id.clone());
+        // Telegram allows up to 200 characters
+        request.text = text.map(|text| text.chars().take(200).collect());
+        request.show_alert = show_alert.then_some(true);
+        request.await?;
+        Ok(())
+    }
 }
 
 /// Unified context type
 #[derive(Clone, Debug)]
+#[allow(clippy::large_enum_variant)]
 pub enum Context {
     Message(MessageContext),
     Callback(CallbackContext),
@@ -35,6 +96,11 @@ impl Context {
         }
     }
     
+    /// Whether the update came from a private chat with the bot
+    pub fn is_private(&self) -> bool {
+        self.chat_id() == self.user_id()
+    }
+    
     pub fn user_id(&self) -> i64 {
         match self {
             Context::Message(ctx) => ctx.user.id.0 as i64,
@@ -48,6 +114,321 @@ impl Context {
             Context::Callback(ctx) => ctx.user.username.as_deref(),
         }
     }
+    
+    /// Language of the user's Telegram app, e.g. `en` or `pt-br`
+    pub fn language_code(&self) -> Option<&str> {
+        match self {
+            Context::Message(ctx) => ctx.user.language_code.as_deref(),
+            Context::Callback(ctx) => ctx.user.language_code.as_deref(),
+        }
+    }
+    
+    /// Forum topic the update came from; `None` outside topics
+    ///
+    /// Replies are posted back into this topic.
+    pub fn thread_id(&self) -> Option<i32> {
+        let message = match self {
+            Context::Message(ctx) => Some(&ctx.message),
+            Context::Callback(ctx) => ctx.query.message.as_ref().and_then(|m| m.regular_message()),
+        }?;
+        message
+            .thread_id
+            .filter(|_| message.is_topic_message)
+            .map(|thread| thread.0 .0)
+    }
+    
+    /// Message the user replied to; `None` for callbacks and other messages
+    pub fn replied_to(&self) -> Option<&Message> {
+        match self {
+            Context::Message(ctx) => ctx.reply_to(),
+            Context::Callback(_) => None,
+        }
+    }
+    
+    /// Text or caption of the message the user replied to
+    pub fn replied_text(&self) -> Option<&str> {
+        self.replied_to().and_then(|m| m.text().or(m.caption()))
+    }
+    
+    /// The message that triggered the update: the user's message, or the
+    /// one carrying the pressed button
+    fn message_id(&self) -> Option<MessageId> {
+        match self {
+            Context::Message(ctx) => Some(ctx.message.id),
+            Context::Callback(ctx) => ctx.query.message.as_ref().map(|m| m.id()),
+        }
+    }
+    
+    /// File attached to the message; `None` for callbacks and other messages
+    pub fn document(&self) -> Option<&Document> {
+        match self {
+            Context::Message(ctx) => ctx.message.document(),
+            Context::Callback(_) => None,
+        }
+    }
+    
+    /// Download the attached file if it is within `limits`
+    pub async fn download_document(&self, limits: &DocumentLimits) -> Result<Upload> {
+        let document = self
+            .document()
+            .ok_or_else(|| Error::InvalidCommand("No file attached".to_string()))?;
+        documents::download(self.bot(), document, limits).await
+    }
+    
+    /// Message text, or callback data for callback queries
+    pub fn text(&self) -> Option<&str> {
+        match self {
+            Context::Message(ctx) => ctx.text.as_deref(),
+            Context::Callback(ctx) => Some(&ctx.data),
+        }
+    }
+    
+    /// Callback data parsed as `T`; `None` for messages or other data
+    pub fn callback_data<T: CallbackData>(&self) -> Option<T> {
+        match self {
+            Context::Callback(ctx) => T::decode(&ctx.data),
+            Context::Message(_) => None,
+        }
+    }
+    
+    /// Whitespace-separated words after the command
+    pub fn args(&self) -> Vec<&str> {
+        match self {
+            Context::Message(ctx) => ctx
+                .text
+                .as_deref()
+                .map(|text| text.split_whitespace().skip(1).collect())
+                .unwrap_or_default(),
+            Context::Callback(_) => Vec::new(),
+        }
+    }
+    
+    /// Message text parsed against `spec`
+    pub fn parse_args(&self, spec: &CommandSpec) -> Result<ParsedArgs> {
+        spec.parse(self.text().unwrap_or_default())
+    }
+    
+    /// Underlying teloxide bot
+    pub fn bot(&self) -> &teloxide::Bot {
+        match self {
+            Context::Message(ctx) => &ctx.bot,
+            Context::Callback(ctx) => &ctx.bot,
+        }
+    }
+    
+    /// Shared value of type `T` registered with
+    /// [`Bot::with_state`](crate::Bot::with_state)
+    pub fn state<T: Send + Sync + 'static>(&self) -> Result<Arc<T>> {
+        match self {
+            Context::Message(ctx) => ctx.state.require(),
+            Context::Callback(ctx) => ctx.state.require(),
+        }
+    }
+    
+    /// Answer a button press with a brief notice; messages get `text` as a
+    /// reply
+    pub async fn answer(&self, text: impl Into<String>) -> Result<()> {
+        match self {
+            Context::Callback(ctx) => ctx.answer(text).await,
+            Context::Message(_) => self.reply(text).await,
+        }
+    }
+    
+    /// Answer a button press with a popup; messages get `text` as a reply
+    pub async fn answer_alert(&self, text: impl Into<String>) -> Result<()> {
+        match self {
+            Context::Callback(ctx) => ctx.answer_alert(text).await,
+            Context::Message(_) => self.reply(text).await,
+        }
+    }
+    
+    /// Send a plain text message to the chat (and topic) the update came from
+    ///
+    /// Text over Telegram's length limit is sent as several messages.
+    pub async fn reply(&self, text: impl Into<String>) -> Result<()> {
+        self.send_split(&text.into(), ParseMode::Plain).await
+    }
+    
+    /// Send MarkdownV2 text; build it with [`md!`](crate::md) to escape values
+    pub async fn reply_md(&self, text: impl Into<String>) -> Result<()> {
+        self.send_split(&text.into(), ParseMode::MarkdownV2).await
+    }
+    
+    /// Send HTML text; build it with [`html!`](crate::html) to escape values
+    pub async fn reply_html(&self, text: impl Into<String>) -> Result<()> {
+        self.send_split(&text.into(), ParseMode::Html).await
+    }
+    
+    /// Send formatted text, rendered and escaped for `mode`
+    pub async fn reply_formatted(&self, text: &Text, mode: ParseMode) -> Result<()> {
+        self.send_split(&text.render(mode), mode).await
+    }
+    
+    /// Send a plain text message to another forum topic of this chat
+    pub async fn reply_in_topic(&self, thread_id: i32, text: impl Into<String>) -> Result<()> {
+        self.send_split_to(Some(thread_id), &text.into(), ParseMode::Plain, None).await
+    }
+    
+    /// Send plain text as a reply to the message that triggered the update
+    ///
+    /// For callbacks this replies to the message carrying the button.
+    pub async fn reply_in_thread(&self, text: impl Into<String>) -> Result<()> {
+        let reply = self.message_id().map(|id| ReplyParameters::new(id).allow_sending_without_reply());
+        self.send_split_to(self.thread_id(), &text.into(), ParseMode::Plain, reply).await
+    }
+    
+    /// Reply to the triggering message, quoting `quote` from it
+    ///
+    /// Telegram rejects quotes that are not part of the message, so the
+    /// quote is left out if the message text does not contain it.
+    pub async fn reply_quoting(&self, text: impl Into<String>, quote: &str) -> Result<()> {
+        let Some(id) = self.message_id() else {
+            return self.reply(text).await;
+        };
+        let mut reply = ReplyParameters::new(id).allow_sending_without_reply();
+        let quoted = match self {
+            Context::Message(ctx) => ctx.message.text().or(ctx.message.caption()),
+            Context::Callback(ctx) => ctx.query.regular_message().and_then(|m| m.text()),
+        };
+        if !quote.is_empty() && quoted.is_some_and(|t| t.contains(quote)) {
+            reply = reply.quote(quote.to_string());
+        }
+        self.send_split_to(self.thread_id(), &text.into(), ParseMode::Plain, Some(reply)).await
+    }
+    
+    async fn send_split(&self, text: &str, mode: ParseMode) -> Result<()> {
+        self.send_split_to(self.thread_id(), text, mode, None).await
+    }
+    
+    async fn send_split_to(
+        &self,
+        thread_id: Option<i32>,
+        text: &str,
+        mode: ParseMode,
+        reply: Option<ReplyParameters>,
+    ) -> Result<()> {
+        for chunk in format::split_message(text, mode) {
+            let mut request = self.bot().send_message(ChatId(self.chat_id()), chunk);
+            request.parse_mode = mode.telegram();
+            request.message_thread_id = thread_id.map(|id| ThreadId(MessageId(id)));
+            request.reply_parameters = reply.clone();
+            request.await?;
+        }
+        Ok(())
+    }
+    
+    fn thread(&self) -> Option<ThreadId> {
+        self.thread_id().map(|id| ThreadId(MessageId(id)))
+    }
+    
+    /// Send a PNG or JPEG image to the chat, with an optional caption
+    pub async fn reply_photo(&self, image: Vec<u8>, caption: Option<&str>) -> Result<()> {
+        let photo = InputFile::memory(image).file_name("chart.png");
+        let mut request = self.bot().send_photo(ChatId(self.chat_id()), photo);
+        request.message_thread_id = self.thread();
+        if let Some(caption) = caption {
+            request = request.caption(caption);
+        }
+        request.await?;
+        Ok(())
+    }
+    
+    /// Replace the text of the message carrying the pressed button, keeping
+    /// its keyboard
+    ///
+    /// For messages this targets the user's own message, which bots cannot
+    /// edit; use it from button handlers.
+    pub async fn edit_text(&self, text: impl Into<String>) -> Result<()> {
+        let keyboard = match self {
+            Context::Callback(ctx) => ctx.query.regular_message().and_then(|m| m.reply_markup()).cloned(),
+            Context::Message(_) => None,
+        };
+        let text = text.into();
+        let result = match self.inline_message_id() {
+            Some(id) => {
+                let mut request = self.bot().edit_message_text_inline(id, text);
+                request.reply_markup = keyboard;
+                request.await.map(drop)
+            }
+            None => {
+                let (chat_id, message_id) = self.target()?;
+                let mut request = self.bot().edit_message_text(chat_id, message_id, text);
+                request.reply_markup = keyboard;
+                request.await.map(drop)
+            }
+        };
+        unless_unchanged(result)
+    }
+    
+    /// Replace the keyboard of the message carrying the pressed button
+    pub async fn edit_keyboard(&self, keyboard: InlineKeyboardMarkup) -> Result<()> {
+        self.set_keyboard(Some(keyboard)).await
+    }
+    
+    /// Remove the keyboard from the message carrying the pressed button
+    pub async fn clear_keyboard(&self) -> Result<()> {
+        self.set_keyboard(None).await
+    }
+    
+    async fn set_keyboard(&self, keyboard: Option<InlineKeyboardMarkup>) -> Result<()> {
+        let result = match self.inline_message_id() {
+            Some(id) => {
+                let mut request = self.bot().edit_message_reply_markup_inline(id);
+                request.reply_markup = keyboard;
+                request.await.map(drop)
+            }
+            None => {
+                let (chat_id, message_id) = self.target()?;
+                let mut request = self.bot().edit_message_reply_markup(chat_id, message_id);
+                request.reply_markup = keyboard;
+                request.await.map(drop)
+            }
+        };
+        unless_unchanged(result)
+    }
+    
+    /// Delete the message carrying the pressed button, or the user's message,
+    /// e.g. one containing an API key
+    ///
+    /// In groups the bot needs admin rights to delete other users' messages.
+    pub async fn delete(&self) -> Result<()> {
+        let (chat_id, message_id) = self.target()?;
+        self.bot().delete_message(chat_id, message_id).await?;
+        Ok(())
+    }
+    
+    /// Message sent in inline mode whose button was pressed
+    fn inline_message_id(&self) -> Option<&str> {
+        match self {
+            Context::Callback(ctx) => ctx.query.inline_message_id.as_deref(),
+            Context::Message(_) => None,
+        }
+    }
+    
+    /// Chat and ID of the message edits and deletes act on
+    fn target(&self) -> Result<(ChatId, MessageId)> {
+        let id = self
+            .message_id()
+            .ok_or_else(|| Error::InvalidCommand("The message is no longer available".to_string()))?;
+        Ok((ChatId(self.chat_id()), id))
+    }
+    
+    /// Send `bytes` to the chat as a file named `filename`
+    pub async fn reply_document(&self, bytes: Vec<u8>, filename: impl Into<String>) -> Result<()> {
+        let document = InputFile::memory(bytes).file_name(filename.into());
+        let mut request = self.bot().send_document(ChatId(self.chat_id()), document);
+        request.message_thread_id = self.thread();
+        request.await?;
+        Ok(())
+    }
+}
+
+/// Editing a message to what it already says is not an error
+fn unless_unchanged(result: std::result::Result<(), RequestError>) -> Result<()> {
+    match result {
+        Ok(()) | Err(RequestError::Api(ApiError::MessageNotModified)) => Ok(()),
+        Err(e) => Err(e.into()),
+    }
 }
 
 impl From<MessageContext> for Context {
@@ -61,3 +442,41 @@ impl From<CallbackContext> for Context {
         Context::Callback(ctx)
     }
 }
+
+/// Context for a private-chat message from `user_id`, for unit tests
+#[cfg(test)]
+pub(crate) fn test_context(user_id: i64, text: &str, reply_to: Option<&str>) -> Context {
+    use serde_json::json;
+    
+    let user = json!({ "id": user_id, "is_bot": false, "first_name": "Alice" });
+    let chat = json!({ "id": user_id, "type": "private", "first_name": "Alice" });
+    let mut message = json!({ "message_id": 2, "date": 0, "chat": chat, "from": user, "text": text });
+    if let Some(reply) = reply_to {
+        message["reply_to_message"] = json!({ "message_id": 1, "date": 0, "chat": chat, "text": reply });
+    }
+    let message: Message = serde_json::from_value(message).unwrap();
+    Context::Message(MessageContext {
+        user: message.from.clone().unwrap(),
+        chat_id: message.chat.id.0,
+        text: message.text().map(str::to_string),
+        message,
+        bot: teloxide::Bot::new("123:test"),
+        state: State::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_replied_to() {
+        let ctx = test_context(7, "cancel", Some("🟢 Order #42 filled: BTC 0.1"));
+        assert_eq!(ctx.replied_to().map(|m| m.id), Some(MessageId(1)));
+        assert_eq!(ctx.replied_text(), Some("🟢 Order #42 filled: BTC 0.1"));
+        assert_eq!(ctx.message_id(), Some(MessageId(2)));
+        
+        let ctx = test_context(7, "/status", None);
+        assert!(ctx.replied_to().is_none());
+    }
+}